@@ -0,0 +1,28 @@
+-- Per-bucket sandwich counters maintained by the detector, bucket_slot = slot - slot % 750 (~5 minutes)
+-- Address ids refer to address_lookup_table
+
+CREATE TABLE IF NOT EXISTS `sandwich_rollup` (
+  `bucket_slot` bigint(20) UNSIGNED NOT NULL,
+  `sandwiches` int(10) UNSIGNED NOT NULL DEFAULT 0,
+  `victims` int(10) UNSIGNED NOT NULL DEFAULT 0,
+  `victim_loss_lamports` bigint(20) UNSIGNED NOT NULL DEFAULT 0,
+  PRIMARY KEY (`bucket_slot`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS `sandwich_attacker_rollup` (
+  `bucket_slot` bigint(20) UNSIGNED NOT NULL,
+  `attacker_id` int(10) UNSIGNED NOT NULL,
+  `sandwiches` int(10) UNSIGNED NOT NULL DEFAULT 0,
+  `victims` int(10) UNSIGNED NOT NULL DEFAULT 0,
+  `victim_loss_lamports` bigint(20) UNSIGNED NOT NULL DEFAULT 0,
+  PRIMARY KEY (`bucket_slot`, `attacker_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS `sandwich_pool_rollup` (
+  `bucket_slot` bigint(20) UNSIGNED NOT NULL,
+  `amm_id` int(10) UNSIGNED NOT NULL,
+  `sandwiches` int(10) UNSIGNED NOT NULL DEFAULT 0,
+  `victims` int(10) UNSIGNED NOT NULL DEFAULT 0,
+  `victim_loss_lamports` bigint(20) UNSIGNED NOT NULL DEFAULT 0,
+  PRIMARY KEY (`bucket_slot`, `amm_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
@@ -0,0 +1,16 @@
+-- Victims that carried the jitodontfront account and got sandwiched anyway
+
+ALTER TABLE `sandwich_rollup` ADD COLUMN `dont_front_victims` int(10) UNSIGNED NOT NULL DEFAULT 0;
+ALTER TABLE `sandwich_attacker_rollup` ADD COLUMN `dont_front_victims` int(10) UNSIGNED NOT NULL DEFAULT 0;
+ALTER TABLE `sandwich_pool_rollup` ADD COLUMN `dont_front_victims` int(10) UNSIGNED NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS `dont_front_violations` (
+  `sandwich_id` char(36) NOT NULL,
+  `victim_event_id` bigint(20) UNSIGNED NOT NULL,
+  `slot` bigint(20) UNSIGNED NOT NULL,
+  `inclusion_order` int(10) UNSIGNED NOT NULL,
+  `attacker_id` int(10) UNSIGNED NOT NULL,
+  PRIMARY KEY (`sandwich_id`, `victim_event_id`),
+  KEY `slot` (`slot`),
+  KEY `attacker_id` (`attacker_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
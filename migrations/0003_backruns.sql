@@ -0,0 +1,11 @@
+-- Backrun-only trades found by the backrun detector, kept apart from sandwiches
+
+CREATE TABLE IF NOT EXISTS `backruns` (
+  `id` char(36) NOT NULL,
+  `victim_event_id` bigint(20) UNSIGNED NOT NULL,
+  `backrun_event_id` bigint(20) UNSIGNED NOT NULL,
+  `slot` bigint(20) UNSIGNED NOT NULL,
+  `profit_lamports` bigint(20) UNSIGNED NOT NULL,
+  PRIMARY KEY (`id`),
+  KEY `slot` (`slot`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
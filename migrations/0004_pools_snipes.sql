@@ -0,0 +1,22 @@
+-- Pool registry (first swap seen on each AMM) and launch snipes found by the realtime detector
+
+CREATE TABLE IF NOT EXISTS `pools` (
+  `amm_id` int(10) UNSIGNED NOT NULL,
+  `first_slot` bigint(20) UNSIGNED NOT NULL,
+  `first_inclusion_order` int(10) UNSIGNED NOT NULL,
+  PRIMARY KEY (`amm_id`),
+  KEY `first_slot` (`first_slot`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS `snipes` (
+  `event_id` bigint(20) UNSIGNED NOT NULL,
+  `amm_id` int(10) UNSIGNED NOT NULL,
+  `authority_id` int(10) UNSIGNED NOT NULL,
+  `slot` bigint(20) UNSIGNED NOT NULL,
+  `inclusion_order` int(10) UNSIGNED NOT NULL,
+  `lamports` bigint(20) UNSIGNED NOT NULL,
+  `slots_after_creation` int(10) UNSIGNED NOT NULL,
+  PRIMARY KEY (`event_id`),
+  KEY `amm_id` (`amm_id`),
+  KEY `slot` (`slot`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
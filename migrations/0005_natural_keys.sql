@@ -0,0 +1,6 @@
+-- Natural keys so the indexer and detector can reprocess slots without duplicating rows.
+-- Duplicates left behind by earlier reprocessing have to be removed before these can be added.
+
+ALTER TABLE `events_with_id` ADD UNIQUE KEY `natural_key` (`slot`, `inclusion_order`, `ix_index`, `inner_ix_index`, `event_type`, `input_inner_ix_index`, `output_inner_ix_index`);
+ALTER TABLE `transactions` ADD UNIQUE KEY `natural_key` (`slot`, `inclusion_order`);
+ALTER TABLE `sandwiches` ADD UNIQUE KEY `natural_key` (`id`, `event_id`);
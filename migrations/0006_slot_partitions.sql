@@ -0,0 +1,21 @@
+-- Range partitions on slot for the two largest tables, maintained by the indexer (see partition.rs).
+-- Partitions are named after their first slot, p0 holds everything up to the first boundary and pmax
+-- catches anything past the newest partition until the maintenance task splits it off.
+-- Both tables use an auto increment `id` primary key, which has to include the partition column.
+-- This rewrites both tables, expect it to take a while on a populated database.
+
+ALTER TABLE `events_with_id` DROP PRIMARY KEY, ADD PRIMARY KEY (`id`, `slot`);
+ALTER TABLE `transactions` DROP PRIMARY KEY, ADD PRIMARY KEY (`id`, `slot`);
+
+-- first boundary is the start of the next epoch-sized (432000 slots) range after the latest slot
+SET @boundary = (SELECT (IFNULL(MAX(`slot`), 0) DIV 432000 + 1) * 432000 FROM `transactions`);
+
+SET @stmt = CONCAT('ALTER TABLE `events_with_id` PARTITION BY RANGE (`slot`) (PARTITION p0 VALUES LESS THAN (', @boundary, '), PARTITION pmax VALUES LESS THAN MAXVALUE)');
+PREPARE stmt FROM @stmt;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+SET @stmt = CONCAT('ALTER TABLE `transactions` PARTITION BY RANGE (`slot`) (PARTITION p0 VALUES LESS THAN (', @boundary, '), PARTITION pmax VALUES LESS THAN MAXVALUE)');
+PREPARE stmt FROM @stmt;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
@@ -0,0 +1,10 @@
+-- Span of each sandwich from its first frontrun to its last backrun, for auditing candidate selection
+
+CREATE TABLE IF NOT EXISTS `sandwich_spans` (
+  `sandwich_id` char(36) NOT NULL,
+  `slot` bigint(20) UNSIGNED NOT NULL,
+  `slot_span` int(10) UNSIGNED NOT NULL,
+  `inclusion_order_span` int(10) UNSIGNED NOT NULL,
+  PRIMARY KEY (`sandwich_id`),
+  KEY `slot` (`slot`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
@@ -0,0 +1,3 @@
+-- Unix block time of each tx, events get theirs by joining on (slot, inclusion_order)
+
+ALTER TABLE `transactions` ADD COLUMN `block_time` bigint(20) NULL DEFAULT NULL;
@@ -0,0 +1,9 @@
+-- Wallets opted in to victim notifications, cursor_slot is the last slot notified about
+
+CREATE TABLE IF NOT EXISTS `notification_subscriptions` (
+  `wallet` varchar(44) NOT NULL,
+  `webhook` varchar(512) NULL DEFAULT NULL,
+  `cursor_slot` bigint(20) UNSIGNED NOT NULL,
+  `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (`wallet`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
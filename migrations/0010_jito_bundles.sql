@@ -0,0 +1,12 @@
+-- Bundle receipts imported from Jito block-engine data, one row per landed tx
+-- sandwiches.bundle_id is filled in from here once both the sandwich and the receipt are known
+
+CREATE TABLE IF NOT EXISTS `jito_bundles` (
+  `slot` bigint(20) UNSIGNED NOT NULL,
+  `sig` varchar(88) NOT NULL,
+  `bundle_id` char(64) NOT NULL,
+  PRIMARY KEY (`slot`, `sig`),
+  KEY `bundle_id` (`bundle_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+ALTER TABLE `sandwiches` ADD COLUMN `bundle_id` char(64) NULL;
@@ -0,0 +1,4 @@
+-- Slippage bound decoded from the swap ix data: min_out for exact in swaps, max_in for exact out swaps
+-- Null for transfers and for programs whose ix layout isn't decoded
+
+ALTER TABLE `events_with_id` ADD COLUMN `min_out` bigint(20) UNSIGNED NULL, ADD COLUMN `max_in` bigint(20) UNSIGNED NULL;
@@ -0,0 +1,20 @@
+-- Attacker wallets grouped into clusters by shared sandwiches, cluster_id is the lowest attacker_id of the cluster
+-- Address ids refer to address_lookup_table
+
+CREATE TABLE IF NOT EXISTS `attacker_clusters` (
+  `attacker_id` int(10) UNSIGNED NOT NULL,
+  `cluster_id` int(10) UNSIGNED NOT NULL,
+  `updated_slot` bigint(20) UNSIGNED NOT NULL,
+  PRIMARY KEY (`attacker_id`),
+  KEY `cluster_id` (`cluster_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+-- Periodic fingerprint of each cluster over the slots [from_slot, to_slot]
+
+CREATE TABLE IF NOT EXISTS `attacker_fingerprints` (
+  `cluster_id` int(10) UNSIGNED NOT NULL,
+  `from_slot` bigint(20) UNSIGNED NOT NULL,
+  `to_slot` bigint(20) UNSIGNED NOT NULL,
+  `fingerprint` json NOT NULL,
+  PRIMARY KEY (`cluster_id`, `to_slot`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
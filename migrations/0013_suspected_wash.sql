@@ -0,0 +1,3 @@
+-- Victims signed by one of the attacker's own wallets, only stored when FLAG_SUSPECTED_WASH is set
+
+ALTER TABLE `sandwiches` MODIFY `role` enum('FRONTRUN','BACKRUN','VICTIM','TRANSFER','SUSPECTED_WASH') NOT NULL;
@@ -0,0 +1,23 @@
+-- Runs of a wallet swapping back and forth on one pool with little net change, found by the wash trading detector
+-- Mint A is the input mint of the run's first swap, nets are received minus spent
+
+CREATE TABLE IF NOT EXISTS `wash_events` (
+  `id` char(36) NOT NULL,
+  `authority_id` int(10) UNSIGNED NOT NULL,
+  `amm_id` int(10) UNSIGNED NOT NULL,
+  `slot` bigint(20) UNSIGNED NOT NULL,
+  `last_slot` bigint(20) UNSIGNED NOT NULL,
+  `swaps` int(10) UNSIGNED NOT NULL,
+  `round_trips` int(10) UNSIGNED NOT NULL,
+  `mint_a_id` int(10) UNSIGNED NOT NULL,
+  `mint_b_id` int(10) UNSIGNED NOT NULL,
+  `volume_a` bigint(20) UNSIGNED NOT NULL,
+  `volume_b` bigint(20) UNSIGNED NOT NULL,
+  `net_a` bigint(20) NOT NULL,
+  `net_b` bigint(20) NOT NULL,
+  `volume_lamports` bigint(20) UNSIGNED NULL,
+  PRIMARY KEY (`id`),
+  KEY `slot` (`slot`),
+  KEY `authority_slot` (`authority_id`, `slot`),
+  KEY `amm_slot` (`amm_id`, `slot`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
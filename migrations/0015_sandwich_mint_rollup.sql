@@ -0,0 +1,15 @@
+-- Per-bucket sandwich counters by the non-SOL mint(s) of the pair and pool, maintained alongside sandwich_pool_rollup
+-- victim_volume is in the mint's raw units, victim_volume_lamports is 0 unless the pair is priced in SOL
+
+CREATE TABLE IF NOT EXISTS `sandwich_mint_rollup` (
+  `bucket_slot` bigint(20) UNSIGNED NOT NULL,
+  `mint_id` int(10) UNSIGNED NOT NULL,
+  `amm_id` int(10) UNSIGNED NOT NULL,
+  `sandwiches` int(10) UNSIGNED NOT NULL DEFAULT 0,
+  `victims` int(10) UNSIGNED NOT NULL DEFAULT 0,
+  `victim_volume` bigint(20) UNSIGNED NOT NULL DEFAULT 0,
+  `victim_volume_lamports` bigint(20) UNSIGNED NOT NULL DEFAULT 0,
+  `victim_loss_lamports` bigint(20) UNSIGNED NOT NULL DEFAULT 0,
+  PRIMARY KEY (`bucket_slot`, `mint_id`, `amm_id`),
+  KEY `mint_bucket` (`mint_id`, `bucket_slot`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
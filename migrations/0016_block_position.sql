@@ -0,0 +1,7 @@
+-- Position of frontruns and backruns within their blocks
+-- block_tx_count includes vote txs, same as inclusion_order; NULL for txs indexed before this column existed
+-- position_bps is inclusion_order * 10000 / (block_tx_count - 1), only set on FRONTRUN and BACKRUN rows
+
+ALTER TABLE `transactions` ADD COLUMN `block_tx_count` int(10) UNSIGNED NULL;
+
+ALTER TABLE `sandwiches` ADD COLUMN `position_bps` smallint(5) UNSIGNED NULL;
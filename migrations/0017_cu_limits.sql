@@ -0,0 +1,6 @@
+-- CU limit each tx ran with (set through ComputeBudget or the runtime default) and the CU consumed by its whole block
+-- NULL for txs indexed before these columns existed
+
+ALTER TABLE `transactions` ADD COLUMN `cu_limit` int(10) UNSIGNED NULL;
+
+ALTER TABLE `transactions` ADD COLUMN `block_cu` bigint(20) UNSIGNED NULL;
@@ -0,0 +1,10 @@
+-- Per-block swap volume on the SOL side of each swap and the part of it that was sandwiched victims, written by the detectors
+-- sandwiched_bps is sandwiched_volume_lamports / swap_volume_lamports, sum the volumes when aggregating over many blocks
+
+CREATE TABLE IF NOT EXISTS `block_volume` (
+  `slot` bigint(20) UNSIGNED NOT NULL,
+  `swap_volume_lamports` bigint(20) UNSIGNED NOT NULL,
+  `sandwiched_volume_lamports` bigint(20) UNSIGNED NOT NULL,
+  `sandwiched_bps` smallint(5) UNSIGNED NOT NULL,
+  PRIMARY KEY (`slot`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
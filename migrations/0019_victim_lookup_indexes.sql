@@ -0,0 +1,5 @@
+-- Composite indexes for looking up a wallet's sandwiches by role, as done by /wallet/{pubkey}/summary and the notifier
+
+ALTER TABLE `events_with_id` ADD KEY `authority_slot` (`authority_id`, `slot`);
+ALTER TABLE `sandwiches` ADD KEY `event_role` (`event_id`, `role`);
+ALTER TABLE `sandwiches` ADD KEY `id_role` (`id`, `role`);
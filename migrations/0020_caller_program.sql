@@ -0,0 +1,4 @@
+-- Program that directly invoked the AMM, as opposed to outer_program which is always the top level program
+-- NULL for top level swaps, transfers and events indexed before this column existed
+
+ALTER TABLE `events_with_id` ADD COLUMN `caller_program_id` int(10) UNSIGNED NULL;
@@ -0,0 +1,4 @@
+-- Victim's price vs the price it would have got without the frontrun, in bps, see VictimLoss::price_impact_bps
+-- Only set for VICTIM rows
+
+ALTER TABLE `sandwiches` ADD COLUMN `price_impact_bps` int(10) UNSIGNED NULL;
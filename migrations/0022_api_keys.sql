@@ -0,0 +1,20 @@
+-- API keys, each belonging to a tenant with its own rate limit, watchlist and webhooks
+-- Only the sha256 of a key is stored, the key itself is shown once when it's created
+-- Requests without a key belong to the default tenant, ''
+
+CREATE TABLE IF NOT EXISTS `api_keys` (
+  `id` int(10) UNSIGNED NOT NULL AUTO_INCREMENT,
+  `tenant` varchar(64) NOT NULL,
+  `key_hash` char(64) NOT NULL,
+  `rate_limit_per_min` int(10) UNSIGNED NOT NULL DEFAULT 0,
+  `created_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  `revoked_at` timestamp NULL DEFAULT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `key_hash` (`key_hash`),
+  KEY `tenant` (`tenant`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+ALTER TABLE `notification_subscriptions`
+  ADD COLUMN `tenant` varchar(64) NOT NULL DEFAULT '' FIRST,
+  DROP PRIMARY KEY,
+  ADD PRIMARY KEY (`tenant`, `wallet`);
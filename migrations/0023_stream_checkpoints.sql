@@ -0,0 +1,8 @@
+-- The last slot each stream consumer fully processed, written on a clean shutdown and resumed from on restart
+
+CREATE TABLE IF NOT EXISTS `stream_checkpoints` (
+  `stream` varchar(32) NOT NULL,
+  `slot` bigint(20) UNSIGNED NOT NULL,
+  `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+  PRIMARY KEY (`stream`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
@@ -0,0 +1,12 @@
+-- Sandwiches found by a shadow detector configuration, laid out like `sandwiches` and tagged with the shadow's label
+-- Kept out of every API and rollup, only meant to be compared against the primary output
+
+CREATE TABLE IF NOT EXISTS `shadow_sandwiches` (
+  `label` varchar(32) NOT NULL,
+  `id` char(36) NOT NULL,
+  `event_id` bigint(20) UNSIGNED NOT NULL,
+  `role` enum('FRONTRUN','BACKRUN','VICTIM','TRANSFER','SUSPECTED_WASH') NOT NULL,
+  `slot` bigint(20) UNSIGNED NOT NULL,
+  PRIMARY KEY (`label`, `id`, `event_id`),
+  KEY `label_slot` (`label`, `slot`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
@@ -0,0 +1,4 @@
+-- Attacker swaps linking a sandwich's legs through another mint, only found when LINK_CONVERSIONS is set
+
+ALTER TABLE `sandwiches` MODIFY `role` enum('FRONTRUN','BACKRUN','VICTIM','TRANSFER','SUSPECTED_WASH','CONVERSION') NOT NULL;
+ALTER TABLE `shadow_sandwiches` MODIFY `role` enum('FRONTRUN','BACKRUN','VICTIM','TRANSFER','SUSPECTED_WASH','CONVERSION') NOT NULL;
@@ -0,0 +1,4 @@
+-- Victim's estimated loss in lamports at detection time, see SandwichCandidate::estimate_victim_losses_lamports
+-- Only set for VICTIM rows of sandwiches on pairs priced in SOL
+
+ALTER TABLE `sandwiches` ADD COLUMN `est_victim_loss_lamports` bigint(20) UNSIGNED NULL;
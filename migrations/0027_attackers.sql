@@ -0,0 +1,13 @@
+-- Every frontrun authority seen so far, maintained by the detector, attacker_id refers to address_lookup_table
+-- notified is cleared for attackers that weren't part of any cluster when first seen, until the notifier announces them
+
+CREATE TABLE IF NOT EXISTS `attackers` (
+  `attacker_id` int(10) UNSIGNED NOT NULL,
+  `first_slot` bigint(20) UNSIGNED NOT NULL,
+  `first_sandwich_id` char(36) NOT NULL,
+  `last_slot` bigint(20) UNSIGNED NOT NULL,
+  `sandwiches` int(10) UNSIGNED NOT NULL DEFAULT 0,
+  `notified` tinyint(1) NOT NULL DEFAULT 1,
+  PRIMARY KEY (`attacker_id`),
+  KEY `notified` (`notified`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
@@ -0,0 +1,3 @@
+-- Looking transactions up by signature, for the /action/check blink
+
+ALTER TABLE `transactions` ADD KEY `sig` (`sig`);
@@ -0,0 +1,12 @@
+-- Per-bucket sandwich counters by the AMM program of the sandwiched pool, maintained alongside sandwich_pool_rollup
+-- victim_volume_lamports is 0 unless the pair is priced in SOL
+
+CREATE TABLE IF NOT EXISTS `sandwich_program_rollup` (
+  `bucket_slot` bigint(20) UNSIGNED NOT NULL,
+  `program_id` int(10) UNSIGNED NOT NULL,
+  `sandwiches` int(10) UNSIGNED NOT NULL DEFAULT 0,
+  `victims` int(10) UNSIGNED NOT NULL DEFAULT 0,
+  `victim_volume_lamports` bigint(20) UNSIGNED NOT NULL DEFAULT 0,
+  `victim_loss_lamports` bigint(20) UNSIGNED NOT NULL DEFAULT 0,
+  PRIMARY KEY (`bucket_slot`, `program_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
@@ -0,0 +1,16 @@
+-- Raw ixs of the swaps a finder matched but couldn't pair with transfers, for debugging layout changes
+-- The indexer keeps the most recent DECODE_FAILURES_MAX_ROWS per program
+
+CREATE TABLE IF NOT EXISTS `decode_failures` (
+  `id` bigint(20) UNSIGNED NOT NULL AUTO_INCREMENT,
+  `slot` bigint(20) UNSIGNED NOT NULL,
+  `sig` varchar(88) NOT NULL,
+  `program` varchar(44) NOT NULL,
+  `ix_index` int(10) UNSIGNED NOT NULL,
+  `inner_ix_index` int(10) UNSIGNED DEFAULT NULL,
+  `data` blob NOT NULL,
+  `accounts` text NOT NULL,
+  `created_at` timestamp NOT NULL DEFAULT current_timestamp(),
+  PRIMARY KEY (`id`),
+  KEY `program_id` (`program`, `id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
@@ -0,0 +1,8 @@
+-- The last slot each detector following the indexer (`detector --follow`) is done with, keyed by DETECTOR_CURSOR
+
+CREATE TABLE IF NOT EXISTS `detector_cursor` (
+  `name` varchar(32) NOT NULL,
+  `slot` bigint(20) UNSIGNED NOT NULL,
+  `updated_at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+  PRIMARY KEY (`name`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
@@ -0,0 +1,4 @@
+-- Whether the victim swap was routed by a known aggregator (Jupiter, DFlow, OKX) rather than sent to the DEX directly
+-- Only set for VICTIM rows
+
+ALTER TABLE `sandwiches` ADD COLUMN `aggregator_routed` tinyint(1) NULL;
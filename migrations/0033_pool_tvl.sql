@@ -0,0 +1,23 @@
+-- Balances of the pool's vaults after each swap's tx, see PoolReserves
+-- NULL for transfers, swaps whose legs weren't found and events indexed before these columns existed
+
+ALTER TABLE `events_with_id` ADD COLUMN `input_reserve` bigint(20) UNSIGNED NULL;
+ALTER TABLE `events_with_id` ADD COLUMN `output_reserve` bigint(20) UNSIGNED NULL;
+
+-- Liquidity of the attacked pool just before the first frontrun, for weighing sandwiches against the pool's depth
+-- reserve_a is in the mint the frontrun paid in, reserve_b in the one it received
+-- tvl_lamports values both at the frontrun's price, NULL unless the pair is priced in SOL
+
+CREATE TABLE IF NOT EXISTS `sandwich_pool_tvl` (
+  `sandwich_id` char(36) NOT NULL,
+  `slot` bigint(20) UNSIGNED NOT NULL,
+  `amm_id` int(10) UNSIGNED NOT NULL,
+  `mint_a_id` int(10) UNSIGNED NOT NULL,
+  `reserve_a` bigint(20) UNSIGNED NOT NULL,
+  `mint_b_id` int(10) UNSIGNED NOT NULL,
+  `reserve_b` bigint(20) UNSIGNED NOT NULL,
+  `tvl_lamports` bigint(20) UNSIGNED NULL,
+  PRIMARY KEY (`sandwich_id`),
+  KEY `slot` (`slot`),
+  KEY `amm_id` (`amm_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
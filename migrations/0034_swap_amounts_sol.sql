@@ -0,0 +1,5 @@
+-- Swap amounts in lamports, at the price of the last swap of the mint against SOL when it was indexed, see PriceOracle
+-- NULL for transfers, mints without a recent price and events indexed before these columns existed
+
+ALTER TABLE `events_with_id` ADD COLUMN `input_amount_sol` bigint(20) UNSIGNED NULL;
+ALTER TABLE `events_with_id` ADD COLUMN `output_amount_sol` bigint(20) UNSIGNED NULL;
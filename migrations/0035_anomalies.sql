@@ -0,0 +1,25 @@
+-- Pools whose price moved at least ANOMALY_MIN_MOVE_BPS away from the first swap of a run and came back within a block
+-- sandwich_id is the detected sandwich sharing a swap with the run, NULL for the ones it doesn't explain
+
+CREATE TABLE IF NOT EXISTS `anomalies` (
+  `id` char(36) NOT NULL,
+  `amm_id` int(10) UNSIGNED NOT NULL,
+  `slot` bigint(20) UNSIGNED NOT NULL,
+  `swaps` int(10) UNSIGNED NOT NULL,
+  `move_bps` int(10) UNSIGNED NOT NULL,
+  `sandwich_id` char(36) NULL,
+  PRIMARY KEY (`id`),
+  KEY `slot` (`slot`),
+  KEY `amm_slot` (`amm_id`, `slot`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+-- The swaps of each run in order, peak is set for the one furthest from the first
+
+CREATE TABLE IF NOT EXISTS `anomaly_swaps` (
+  `anomaly_id` char(36) NOT NULL,
+  `event_id` bigint(20) UNSIGNED NOT NULL,
+  `position` int(10) UNSIGNED NOT NULL,
+  `peak` tinyint(1) NOT NULL,
+  PRIMARY KEY (`anomaly_id`, `event_id`),
+  KEY `event_id` (`event_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
@@ -0,0 +1,4 @@
+-- What kind of wallet the victim is from its swaps indexed before the sandwich, see VictimCohort
+-- Only set for VICTIM rows
+
+ALTER TABLE `sandwiches` ADD COLUMN `victim_cohort` enum('NEW','RETAIL','BOT') NULL;
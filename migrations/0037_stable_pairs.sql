@@ -0,0 +1,12 @@
+-- Sandwiches on pools between two stablecoins (STABLE_MINTS), mostly depeg arb rather than retail harm
+-- They're flagged on every role row and counted in sandwich_stable_rollup instead of the other rollups
+
+ALTER TABLE `sandwiches` ADD COLUMN `stable_pair` tinyint(1) NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS `sandwich_stable_rollup` (
+  `bucket_slot` bigint(20) UNSIGNED NOT NULL,
+  `amm_id` int(10) UNSIGNED NOT NULL,
+  `sandwiches` int(10) UNSIGNED NOT NULL DEFAULT 0,
+  `victims` int(10) UNSIGNED NOT NULL DEFAULT 0,
+  PRIMARY KEY (`bucket_slot`, `amm_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
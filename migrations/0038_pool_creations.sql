@@ -0,0 +1,13 @@
+-- Pools seen being created along with their first buy (currently pump.fun bonding curves), written at ingest
+-- The realtime detector takes their creation slots over the first swaps and doesn't hold them to the registry warmup
+
+CREATE TABLE IF NOT EXISTS `pool_creations` (
+  `amm_id` int(10) UNSIGNED NOT NULL,
+  `program_id` int(10) UNSIGNED NOT NULL,
+  `mint_id` int(10) UNSIGNED NOT NULL,
+  `slot` bigint(20) UNSIGNED NOT NULL,
+  `inclusion_order` int(10) UNSIGNED NOT NULL,
+  `ix_index` int(10) UNSIGNED NOT NULL,
+  PRIMARY KEY (`amm_id`),
+  KEY `slot` (`slot`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
@@ -0,0 +1,9 @@
+-- Which of the leader's 4 slots each sandwich's first frontrun, first victim and last backrun landed in,
+-- and the slots between the legs. NULL for sandwiches found before this was recorded
+
+ALTER TABLE `sandwich_spans`
+  ADD COLUMN `frontrun_slot_offset` tinyint(3) UNSIGNED NULL,
+  ADD COLUMN `victim_slot_offset` tinyint(3) UNSIGNED NULL,
+  ADD COLUMN `backrun_slot_offset` tinyint(3) UNSIGNED NULL,
+  ADD COLUMN `frontrun_victim_slots` int(10) UNSIGNED NULL,
+  ADD COLUMN `victim_backrun_slots` int(10) UNSIGNED NULL;
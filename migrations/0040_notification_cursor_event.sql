@@ -0,0 +1,5 @@
+-- Last event notified about within cursor_slot, so a batch cut off in the middle of a slot resumes where it stopped.
+-- NULL when all of cursor_slot was notified about
+
+ALTER TABLE `notification_subscriptions`
+  ADD COLUMN `cursor_event_id` bigint(20) UNSIGNED NULL DEFAULT NULL AFTER `cursor_slot`;
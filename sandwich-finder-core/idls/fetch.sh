@@ -0,0 +1,13 @@
+#!/bin/bash
+# Refetches the on-chain IDLs the finder conformance tests (src/swaps/idl.rs) check against,
+# keeping only the instructions the finders decode. Needs the anchor cli and jq.
+# A failing conformance test after a refetch means the program changed under a finder.
+cd "$(dirname "$0")"
+
+fetch() {
+    anchor idl fetch "$2" --provider.cluster mainnet | jq --argjson keep "$3" '.instructions |= map(select(.name as $n | $keep | index($n)))' > "$1.json"
+}
+
+fetch whirlpool whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc '["swap", "swapV2", "twoHopSwap", "twoHopSwapV2"]'
+fetch meteora_dlmm LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo '["swap", "swap2", "swapExactOut", "swapExactOut2", "swapWithPriceImpact", "swapWithPriceImpact2"]'
+fetch pumpfun 6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P '["buy", "sell"]'
@@ -40,14 +40,25 @@ pub const TOKEN_PROGRAM_ID: Pubkey = Pubkey::from_str_const("TokenkegQfeZyiNwAJb
 pub const TOKEN_2022_PROGRAM_ID: Pubkey = Pubkey::from_str_const("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
 pub const SYSTEM_PROGRAM_ID: Pubkey = Pubkey::from_str_const("11111111111111111111111111111111");
 pub const STAKE_PROGRAM_ID: Pubkey = Pubkey::from_str_const("Stake11111111111111111111111111111111111111");
+pub const LUT_PROGRAM_ID: Pubkey = Pubkey::from_str_const("AddressLookupTab1e1111111111111111111111111");
+pub const COMPUTE_BUDGET_PROGRAM_ID: Pubkey = Pubkey::from_str_const("ComputeBudget111111111111111111111111111111");
 pub const WSOL_MINT: Pubkey = Pubkey::from_str_const("So11111111111111111111111111111111111111112");
 
 pub const JUP_V6_PROGRAM_ID: Pubkey = Pubkey::from_str_const("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
 pub const JUP_V4_PROGRAM_ID: Pubkey = Pubkey::from_str_const("JUP4Fb2cqiRUcaTHdrPC8h2gNsA2ETXiPDD33WcGuJB");
 pub const DFLOW_PROGRAM_ID: Pubkey = Pubkey::from_str_const("DF1ow4tspfHX9JwWJsAb9epbkA8hmpSEAtxXy1V27QBH");
+pub const OKX_DEX_ROUTER_PROGRAM_ID: Pubkey = Pubkey::from_str_const("6m2CDdhRgxpH4WjvdzxAYbGxwdGUz5MziiL5jek2kBma");
 
-pub const DONT_FRONT_START: [u8; 32] = [10,241,195,67,33,136,202,58,99,81,53,161,58,24,149,26,206,189,41,230,172,45,174,103,255,219,6,215,64,0,0,0];
-pub const DONT_FRONT_END: [u8; 32]   = [10,241,195,67,33,136,202,58,99,82,11,83,236,186,243,27,60,23,98,46,152,130,58,175,28,197,174,53,128,0,0,0];
+pub const JITO_TIP_ACCOUNTS: [Pubkey; 8] = [
+    Pubkey::from_str_const("96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5"),
+    Pubkey::from_str_const("HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe"),
+    Pubkey::from_str_const("Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY"),
+    Pubkey::from_str_const("ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49"),
+    Pubkey::from_str_const("DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh"),
+    Pubkey::from_str_const("ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt"),
+    Pubkey::from_str_const("DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL"),
+    Pubkey::from_str_const("3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT"),
+];
 
 pub fn is_known_aggregator(program_id: &Pubkey) -> bool {
     matches!(
@@ -55,5 +66,48 @@ pub fn is_known_aggregator(program_id: &Pubkey) -> bool {
         JUP_V6_PROGRAM_ID
             | JUP_V4_PROGRAM_ID
             | DFLOW_PROGRAM_ID
+            | OKX_DEX_ROUTER_PROGRAM_ID
     )
-}
\ No newline at end of file
+}
+/// Display name of the AMM programs there are finders for
+pub fn program_name(program_id: &Pubkey) -> Option<&'static str> {
+    let name = match *program_id {
+        RAYDIUM_V4_PUBKEY => "Raydium v4",
+        RAYDIUM_V5_PUBKEY => "Raydium CPMM",
+        RAYDIUM_LP_PUBKEY => "Raydium LaunchLab",
+        RAYDIUM_CL_PUBKEY => "Raydium CLMM",
+        PDF_PUBKEY => "Pump.fun",
+        PDF2_PUBKEY => "PumpSwap",
+        WHIRLPOOL_PUBKEY => "Whirlpool",
+        METEORA_DLMM_PUBKEY => "Meteora DLMM",
+        METEORA_PUBKEY => "Meteora DAMM",
+        METEORA_DBC_PUBKEY => "Meteora DBC",
+        METEORA_DAMMV2_PUBKEY => "Meteora DAMM v2",
+        OPENBOOK_V2_PUBKEY => "OpenBook v2",
+        ZEROFI_PUBKEY => "ZeroFi",
+        JUP_ORDER_ENGINE_PUBKEY => "Jupiter Order Engine",
+        PANCAKE_SWAP_PUBKEY => "PancakeSwap",
+        FLUXBEAM_PUBKEY => "FluxBeam",
+        HUMIDIFI_PUBKEY => "HumidiFi",
+        SAROS_DLMM_PUBKEY => "Saros DLMM",
+        SOLFI_PUBKEY => "SolFi",
+        GOONFI_PUBKEY => "GoonFi",
+        SUGAR_PUBKEY => "Sugar",
+        TESS_V_PUBKEY => "TesseraV",
+        SV2E_PUBKEY => "SV2E",
+        LIFINITY_V2_PUBKEY => "Lifinity v2",
+        APESU_PUBKEY => "ApeSu",
+        ONEDEX_PUBKEY => "1DEX",
+        AQUA_PUBKEY => "Aqua",
+        STABBLE_WEIGHTED_PUBKEY => "Stabble Weighted",
+        JUP_PERPS_PUBKEY => "Jupiter Perps",
+        DOOAR_PUBKEY => "Dooar",
+        PUMPUP_PUBKEY => "PumpUp",
+        CLEARPOOL_PUBKEY => "Clearpool",
+        FUSIONAMM_PUBKEY => "FusionAMM",
+        ALPHA_PUBKEY => "AlphaQ",
+        LIMO_PUBKEY => "Limo",
+        _ => return None,
+    };
+    Some(name)
+}
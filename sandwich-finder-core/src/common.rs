@@ -0,0 +1,43 @@
+use chrono::{DateTime, SecondsFormat};
+use derive_getters::Getters;
+use serde::{ser::SerializeMap as _, Serialize, Serializer};
+
+/// Where an event happened, ordered chronologically. A missing inner ix index sorts before the ix's inner ixs, which
+/// covers both top-level ixs and legacy rows stored without one, so those only order by their ix.
+#[derive(Debug, Clone, Copy, Getters, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct Timestamp {
+    slot: u64,
+    inclusion_order: u32,
+    ix_index: u32,
+    inner_ix_index: Option<u32>,
+}
+
+impl Timestamp {
+    pub fn new(slot: u64, inclusion_order: u32, ix_index: u32, inner_ix_index: Option<u32>) -> Self {
+        Self {
+            slot,
+            inclusion_order,
+            ix_index,
+            inner_ix_index,
+        }
+    }
+}
+
+/// Unix time of the block an event is in, if known. Serialized as `blockTime` along with an ISO 8601 `blockTimeIso`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlockTime(pub Option<i64>);
+
+impl BlockTime {
+    pub fn iso(&self) -> Option<String> {
+        self.0.and_then(|t| DateTime::from_timestamp(t, 0)).map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, true))
+    }
+}
+
+impl Serialize for BlockTime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("blockTime", &self.0)?;
+        map.serialize_entry("blockTimeIso", &self.iso())?;
+        map.end()
+    }
+}
@@ -0,0 +1,204 @@
+use std::{collections::HashMap, env};
+
+use solana_sdk::{bs58, pubkey::Pubkey};
+use thiserror::Error;
+
+/// What's matched when `DONT_FRONT_MARKERS` isn't set, Jito's convention
+pub const DEFAULT_MARKERS: &str = "jitodontfront";
+
+/// Inclusive bounds of a set of keys
+type KeyRange = ([u8; 32], [u8; 32]);
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum DontFrontError {
+    #[error("invalid vanity prefix: {0}")]
+    Prefix(String),
+    #[error("invalid key range: {0}")]
+    Range(String),
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    children: HashMap<u8, Node>,
+    /// Every key under this node is a marker
+    terminal: bool,
+    /// Inclusive bounds on the rest of the key, for markers that don't end on a byte boundary
+    ranges: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+/// Tells whether a tx opted out of being frontrun by including a marker account, keys in one of a set of ranges.
+/// The ranges are kept in a trie on the bytes they share so a key is checked against all of them in one walk.
+#[derive(Debug, Default)]
+pub struct DontFrontMatcher {
+    root: Node,
+}
+
+impl DontFrontMatcher {
+    /// `markers` are either base58 vanity prefixes such as `jitodontfront`, or `START..END` with END excluded
+    pub fn new<S: AsRef<str>>(markers: &[S]) -> Result<Self, DontFrontError> {
+        let mut matcher = Self::default();
+        for marker in markers {
+            for (lo, hi) in parse_marker(marker.as_ref())? {
+                matcher.insert(lo, hi);
+            }
+        }
+        Ok(matcher)
+    }
+
+    /// Comma separated markers from `DONT_FRONT_MARKERS`, see [`DontFrontMatcher::new`].
+    /// Invalid ones are logged and left out.
+    pub fn from_env() -> Self {
+        let markers = env::var("DONT_FRONT_MARKERS").unwrap_or(DEFAULT_MARKERS.to_string());
+        let mut matcher = Self::default();
+        for marker in markers.split(',').map(|m| m.trim()).filter(|m| !m.is_empty()) {
+            match parse_marker(marker) {
+                Ok(ranges) => ranges.into_iter().for_each(|(lo, hi)| matcher.insert(lo, hi)),
+                Err(e) => eprintln!("Failed to parse dont front marker {}: {}", marker, e),
+            }
+        }
+        matcher
+    }
+
+    fn insert(&mut self, lo: [u8; 32], hi: [u8; 32]) {
+        let shared = lo.iter().zip(hi.iter()).take_while(|(a, b)| a == b).count();
+        let mut node = &mut self.root;
+        for byte in &lo[..shared] {
+            node = node.children.entry(*byte).or_default();
+        }
+        if lo[shared..].iter().all(|&b| b == 0) && hi[shared..].iter().all(|&b| b == u8::MAX) {
+            node.terminal = true;
+        } else {
+            node.ranges.push((lo[shared..].to_vec(), hi[shared..].to_vec()));
+        }
+    }
+
+    pub fn matches(&self, key: &Pubkey) -> bool {
+        let key = key.as_ref();
+        let mut node = &self.root;
+        for depth in 0..=key.len() {
+            let rest = &key[depth..];
+            if node.terminal || node.ranges.iter().any(|(lo, hi)| rest >= lo.as_slice() && rest <= hi.as_slice()) {
+                return true;
+            }
+            match rest.first().and_then(|byte| node.children.get(byte)) {
+                Some(child) => node = child,
+                None => return false,
+            }
+        }
+        false
+    }
+
+    pub fn any(&self, keys: &[Pubkey]) -> bool {
+        keys.iter().any(|key| self.matches(key))
+    }
+}
+
+/// Key ranges covered by a marker
+fn parse_marker(marker: &str) -> Result<Vec<KeyRange>, DontFrontError> {
+    if let Some((start, end)) = marker.split_once("..") {
+        let parse = |key: &str| key.trim().parse::<Pubkey>().map_err(|_| DontFrontError::Range(marker.to_string()));
+        let (lo, end) = (parse(start)?.to_bytes(), parse(end)?.to_bytes());
+        return match decrement(end) {
+            Some(hi) if lo <= hi => Ok(vec![(lo, hi)]),
+            _ => Err(DontFrontError::Range(marker.to_string())),
+        };
+    }
+    vanity_ranges(marker)
+}
+
+/// The keys whose base58 form starts with `prefix`. Keys without a leading zero byte are 43 or 44 digits long,
+/// each length gets its own range.
+fn vanity_ranges(prefix: &str) -> Result<Vec<KeyRange>, DontFrontError> {
+    // leading 1s stand for zero bytes rather than digits
+    if prefix.is_empty() || prefix.starts_with('1') || prefix.len() > 44 {
+        return Err(DontFrontError::Prefix(prefix.to_string()));
+    }
+    let mut smallest = [0; 32];
+    smallest[0] = 1;
+    let mut ranges = vec![];
+    for len in prefix.len().max(43)..=44 {
+        let pad = len - prefix.len();
+        let (Some(lo), hi) = (decode_padded(prefix, '1', pad)?, decode_padded(prefix, 'z', pad)?) else {
+            continue;
+        };
+        let hi = hi.unwrap_or([u8::MAX; 32]);
+        if hi >= smallest {
+            ranges.push((lo.max(smallest), hi));
+        }
+    }
+    Ok(ranges)
+}
+
+/// `prefix` padded with `pad` copies of `digit` as a 32 byte big endian number, `None` if it doesn't fit
+fn decode_padded(prefix: &str, digit: char, pad: usize) -> Result<Option<[u8; 32]>, DontFrontError> {
+    let padded = format!("{}{}", prefix, digit.to_string().repeat(pad));
+    let bytes = bs58::decode(&padded).into_vec().map_err(|_| DontFrontError::Prefix(prefix.to_string()))?;
+    let significant = &bytes[bytes.iter().take_while(|&&b| b == 0).count()..];
+    if significant.len() > 32 {
+        return Ok(None);
+    }
+    let mut key = [0; 32];
+    key[32 - significant.len()..].copy_from_slice(significant);
+    Ok(Some(key))
+}
+
+fn decrement(mut key: [u8; 32]) -> Option<[u8; 32]> {
+    for byte in key.iter_mut().rev() {
+        if *byte > 0 {
+            *byte -= 1;
+            return Some(key);
+        }
+        *byte = u8::MAX;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The range the jitodontfront prefix was hard-coded as
+    const JITO_START: [u8; 32] = [10,241,195,67,33,136,202,58,99,81,53,161,58,24,149,26,206,189,41,230,172,45,174,103,255,219,6,215,64,0,0,0];
+    const JITO_END: [u8; 32] = [10,241,195,67,33,136,202,58,99,82,11,83,236,186,243,27,60,23,98,46,152,130,58,175,28,197,174,53,128,0,0,0];
+
+    #[test]
+    fn test_vanity_ranges() {
+        let ranges = vanity_ranges("jitodontfront").unwrap();
+        assert!(ranges.contains(&(JITO_START, decrement(JITO_END).unwrap())));
+        for (lo, hi) in &ranges {
+            assert!(bs58::encode(lo).into_string().starts_with("jitodontfront"));
+            assert!(bs58::encode(hi).into_string().starts_with("jitodontfront"));
+        }
+        assert!(vanity_ranges("jito0").is_err());
+        assert!(vanity_ranges("1abc").is_err());
+    }
+
+    #[test]
+    fn test_matches() {
+        let matcher = DontFrontMatcher::new(&[DEFAULT_MARKERS]).unwrap();
+        let marker: Pubkey = "jitodontfront111111111111111111111111111111".parse().unwrap();
+        assert!(matcher.matches(&marker));
+        assert!(matcher.matches(&"jitodontfrontzzzzzzzzzzzzzzzzzzzzzzzzzzzzzz".parse().unwrap()));
+        assert!(matcher.matches(&Pubkey::new_from_array(JITO_START)));
+        assert!(!matcher.matches(&Pubkey::new_from_array(JITO_END)));
+        assert!(!matcher.matches(&"jitodontfronu111111111111111111111111111111".parse().unwrap()));
+        assert!(!matcher.any(&[Pubkey::default(), Pubkey::new_unique()]));
+        assert!(matcher.any(&[Pubkey::default(), marker]));
+        assert!(!DontFrontMatcher::default().matches(&marker));
+
+        // explicit ranges, byte aligned or not
+        let mut start = [0; 32];
+        start[0] = 7;
+        let mut end = [0; 32];
+        end[0] = 8;
+        let range = format!("{}..{}", Pubkey::new_from_array(start), Pubkey::new_from_array(end));
+        let matcher = DontFrontMatcher::new(&[range.as_str(), "11111111111111111111111111111112..11111111111111111111111111111114"]).unwrap();
+        assert!(matcher.root.children[&7].terminal);
+        assert!(matcher.matches(&Pubkey::new_from_array([7; 32])));
+        assert!(!matcher.matches(&Pubkey::new_from_array(end)));
+        assert!(matcher.matches(&"11111111111111111111111111111113".parse().unwrap()));
+        assert!(!matcher.matches(&"11111111111111111111111111111114".parse().unwrap()));
+        assert!(!matcher.matches(&Pubkey::default()));
+        assert_eq!(DontFrontMatcher::new(&["11111111111111111111111111111114..11111111111111111111111111111112"]).unwrap_err(), DontFrontError::Range("11111111111111111111111111111114..11111111111111111111111111111112".to_string()));
+    }
+}
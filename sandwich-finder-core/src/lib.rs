@@ -0,0 +1,15 @@
+//! The decoding layer of sandwich-finder: [`swap::SwapFinder`]s and [`transfer::TransferFinder`]s for the supported
+//! programs, turning a decompiled transaction into [`swap::SwapV2`]s, [`transfer::TransferV2`]s and [`pool::PoolCreated`]s.
+//! Free of any database or web dependencies so other indexers can reuse it.
+
+pub mod addresses;
+pub mod common;
+pub mod dont_front;
+pub mod metrics;
+pub mod pool;
+pub mod swap;
+pub mod swaps;
+pub mod token_accounts;
+pub mod transfer;
+pub mod transfers;
+pub mod utils;
@@ -0,0 +1,34 @@
+use std::sync::{atomic::{AtomicU64, Ordering}, LazyLock};
+
+use dashmap::DashMap;
+
+static METRICS: LazyLock<DashMap<&'static str, AtomicU64>> = LazyLock::new(DashMap::new);
+
+/// Adds `value` to the counter `name`, creating it if needed.
+pub fn add(name: &'static str, value: u64) {
+    if let Some(metric) = METRICS.get(name) {
+        metric.fetch_add(value, Ordering::Relaxed);
+        return;
+    }
+    METRICS.entry(name).or_default().fetch_add(value, Ordering::Relaxed);
+}
+
+pub fn incr(name: &'static str) {
+    add(name, 1);
+}
+
+/// Overwrites the gauge `name`.
+pub fn set(name: &'static str, value: u64) {
+    METRICS.entry(name).or_default().store(value, Ordering::Relaxed);
+}
+
+pub fn get(name: &str) -> u64 {
+    METRICS.get(name).map(|m| m.load(Ordering::Relaxed)).unwrap_or(0)
+}
+
+/// All metrics sorted by name.
+pub fn snapshot() -> Vec<(&'static str, u64)> {
+    let mut metrics: Vec<_> = METRICS.iter().map(|m| (*m.key(), m.value().load(Ordering::Relaxed))).collect();
+    metrics.sort();
+    metrics
+}
@@ -0,0 +1,61 @@
+use std::{fmt::Debug, sync::Arc};
+
+use derive_getters::Getters;
+use serde::Serialize;
+
+use crate::common::{BlockTime, Timestamp};
+
+/// A pool seen being created, as opposed to inferred from the first swap on it
+#[derive(Clone, Serialize, Getters)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolCreated {
+    // The program the pool belongs to
+    program: Arc<str>,
+    amm: Arc<str>,
+    // Mint of the token the pool was created for
+    mint: Arc<str>,
+    timestamp: Timestamp,
+    #[serde(flatten)]
+    block_time: BlockTime,
+}
+
+impl Debug for PoolCreated {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("PoolCreated")?;
+        f.write_str(&format!(" in slot {} (order {}, ix {}, inner_ix {:?})\n", self.slot(), self.inclusion_order(), self.ix_index(), self.inner_ix_index()))?;
+        f.write_str(&format!(" on {} market {} mint {}", self.program, self.amm, self.mint))?;
+        Ok(())
+    }
+}
+
+impl PoolCreated {
+    pub fn new(program: Arc<str>, amm: Arc<str>, mint: Arc<str>, slot: u64, inclusion_order: u32, ix_index: u32, inner_ix_index: Option<u32>) -> Self {
+        Self {
+            program,
+            amm,
+            mint,
+            timestamp: Timestamp::new(slot, inclusion_order, ix_index, inner_ix_index),
+            block_time: BlockTime::default(),
+        }
+    }
+
+    pub fn set_block_time(&mut self, block_time: Option<i64>) {
+        self.block_time = BlockTime(block_time);
+    }
+
+    pub fn slot(&self) -> &u64 {
+        self.timestamp.slot()
+    }
+
+    pub fn inclusion_order(&self) -> &u32 {
+        self.timestamp.inclusion_order()
+    }
+
+    pub fn ix_index(&self) -> &u32 {
+        self.timestamp.ix_index()
+    }
+
+    pub fn inner_ix_index(&self) -> &Option<u32> {
+        self.timestamp.inner_ix_index()
+    }
+}
@@ -0,0 +1,313 @@
+use std::{fmt::Debug, str::FromStr as _, sync::Arc};
+
+use derive_getters::Getters;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use yellowstone_grpc_proto::{prelude::{InnerInstruction, InnerInstructions, TransactionStatusMeta}};
+
+use crate::{addresses::is_known_aggregator, common::{BlockTime, Timestamp}, token_accounts::TokenAccounts};
+
+#[derive(Clone, Serialize, Getters)]
+#[serde(rename_all = "camelCase")]
+pub struct SwapV2 {
+    // The wrapper program for this swap, if any
+    outer_program: Option<Arc<str>>,
+    // The program that CPI'd into the AMM, which differs from the outer program when the swap is nested deeper than one level
+    caller_program: Option<Arc<str>>,
+    // The actual AMM program
+    program: Arc<str>,
+    // Wallet that authorised the swap
+    authority: Arc<str>,
+    // The AMM used for this trade
+    amm: Arc<str>,
+    // In/out mints of the swap
+    input_mint: Arc<str>,
+    output_mint: Arc<str>,
+    // In/out amounts of the swap
+    input_amount: u64,
+    output_amount: u64,
+    // In/out token accounts
+    input_ata: Arc<str>,
+    output_ata: Arc<str>,
+    // In/out inner ix indexes
+    input_inner_ix_index: Option<u32>,
+    output_inner_ix_index: Option<u32>,
+    // Worst acceptable amounts, decoded from the ix data where the layout is known
+    #[serde(flatten)]
+    quote_limits: QuoteLimits,
+    // The pool's vault balances after the tx
+    #[serde(flatten)]
+    pool_reserves: PoolReserves,
+    // Which legs were found, derived from the mints
+    completeness: SwapCompleteness,
+    // These fields are meant to be replaced when inserting to the db
+    timestamp: Timestamp,
+    id: u64,
+    #[serde(flatten)]
+    block_time: BlockTime,
+}
+
+impl SwapV2 {
+    pub fn new(
+        outer_program: Option<Arc<str>>,
+        program: Arc<str>,
+        authority: Arc<str>,
+        amm: Arc<str>,
+        input_mint: Arc<str>,
+        output_mint: Arc<str>,
+        input_amount: u64,
+        output_amount: u64,
+        input_ata: Arc<str>,
+        output_ata: Arc<str>,
+        input_inner_ix_index: Option<u32>,
+        output_inner_ix_index: Option<u32>,
+        slot: u64,
+        inclusion_order: u32,
+        ix_index: u32,
+        inner_ix_index: Option<u32>,
+        id: u64,
+    ) -> Self {
+        let completeness = SwapCompleteness::from_mints(&input_mint, &output_mint);
+        Self {
+            outer_program,
+            caller_program: None,
+            program,
+            authority,
+            amm,
+            input_mint,
+            output_mint,
+            input_amount,
+            output_amount,
+            input_ata,
+            output_ata,
+            input_inner_ix_index,
+            output_inner_ix_index,
+            quote_limits: QuoteLimits::default(),
+            pool_reserves: PoolReserves::default(),
+            completeness,
+            timestamp: Timestamp::new(
+                slot,
+                inclusion_order,
+                ix_index,
+                inner_ix_index,
+            ),
+            id,
+            block_time: BlockTime::default(),
+        }
+    }
+
+    pub fn set_block_time(&mut self, block_time: Option<i64>) {
+        self.block_time = BlockTime(block_time);
+    }
+
+    /// For finders that derive amounts from logs first and correct them afterwards
+    pub fn set_amounts(&mut self, input_amount: u64, output_amount: u64) {
+        self.input_amount = input_amount;
+        self.output_amount = output_amount;
+    }
+
+    pub fn set_quote_limits(&mut self, quote_limits: QuoteLimits) {
+        self.quote_limits = quote_limits;
+    }
+
+    pub fn set_pool_reserves(&mut self, pool_reserves: PoolReserves) {
+        self.pool_reserves = pool_reserves;
+    }
+
+    pub fn set_caller_program(&mut self, caller_program: Option<Arc<str>>) {
+        self.caller_program = caller_program;
+    }
+
+    /// For rewriting the addresses a swap exposes, such as when redacting them
+    pub fn set_authority(&mut self, authority: Arc<str>) {
+        self.authority = authority;
+    }
+
+    pub fn set_atas(&mut self, input_ata: Arc<str>, output_ata: Arc<str>) {
+        self.input_ata = input_ata;
+        self.output_ata = output_ata;
+    }
+
+    /// For mapping equivalent mints onto one, see `SolEquivalents` in the detector
+    pub fn set_mints(&mut self, input_mint: Arc<str>, output_mint: Arc<str>) {
+        self.completeness = SwapCompleteness::from_mints(&input_mint, &output_mint);
+        self.input_mint = input_mint;
+        self.output_mint = output_mint;
+    }
+
+    /// For restoring a swap stored along with its completeness
+    pub fn set_completeness(&mut self, completeness: SwapCompleteness) {
+        self.completeness = completeness;
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.completeness == SwapCompleteness::Complete
+    }
+
+    /// The program wrapping this swap as far as sandwich detection is concerned, the direct caller where it's known
+    pub fn wrapper_program(&self) -> Option<&Arc<str>> {
+        self.caller_program.as_ref().or(self.outer_program.as_ref())
+    }
+
+    /// Whether a known aggregator routed this swap rather than the user calling the DEX (or their own wrapper) directly
+    pub fn is_aggregator_routed(&self) -> bool {
+        [&self.outer_program, &self.caller_program].into_iter().flatten().any(|p| Pubkey::from_str(p).is_ok_and(|p| is_known_aggregator(&p)))
+    }
+
+    pub fn slot(&self) -> &u64 {
+        self.timestamp.slot()
+    }
+    pub fn inclusion_order(&self) -> &u32 {
+        self.timestamp.inclusion_order()
+    }
+    pub fn ix_index(&self) -> &u32 {
+        self.timestamp.ix_index()
+    }
+    pub fn inner_ix_index(&self) -> &Option<u32> {
+        self.timestamp.inner_ix_index()
+    }
+}
+
+/// Which legs of a swap its finder found. A missing leg, e.g. an output that rounded to zero and was never transferred,
+/// is left with an empty mint and a zero amount, which aren't real values and shouldn't be treated as such.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SwapCompleteness {
+    #[default]
+    Complete,
+    MissingInput,
+    MissingOutput,
+    /// Only the swap ix itself was found
+    MissingBoth,
+}
+
+impl SwapCompleteness {
+    pub fn from_mints(input_mint: &str, output_mint: &str) -> Self {
+        match (input_mint.is_empty(), output_mint.is_empty()) {
+            (false, false) => Self::Complete,
+            (true, false) => Self::MissingInput,
+            (false, true) => Self::MissingOutput,
+            (true, true) => Self::MissingBoth,
+        }
+    }
+}
+
+/// The slippage bound a swap ix was submitted with. Exact in swaps carry a minimum output and exact out swaps a maximum input.
+/// How tight the bound is compared to the executed amounts tells how much slippage the trader tolerated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Getters)]
+#[serde(rename_all = "camelCase")]
+pub struct QuoteLimits {
+    min_out: Option<u64>,
+    max_in: Option<u64>,
+}
+
+impl QuoteLimits {
+    pub fn new(min_out: Option<u64>, max_in: Option<u64>) -> Self {
+        Self { min_out, max_in }
+    }
+
+    pub fn exact_in(min_out: Option<u64>) -> Self {
+        Self { min_out, max_in: None }
+    }
+
+    pub fn exact_out(max_in: Option<u64>) -> Self {
+        Self { min_out: None, max_in }
+    }
+
+    /// Slippage tolerated relative to the executed amounts, in bps
+    pub fn slippage_bps(&self, input_amount: u64, output_amount: u64) -> Option<u64> {
+        match (self.min_out, self.max_in) {
+            (Some(min_out), _) if output_amount > 0 => Some(output_amount.saturating_sub(min_out) as u128 * 10000 / output_amount as u128),
+            (_, Some(max_in)) if input_amount > 0 => Some(max_in.saturating_sub(input_amount) as u128 * 10000 / input_amount as u128),
+            _ => None,
+        }.map(|bps| bps as u64)
+    }
+}
+
+/// Balances of the pool's vaults once the swap's tx is done: the one the input was paid into and the one the output was paid
+/// out of, in their own mints. Either is `None` if its leg wasn't found or its balance isn't in the tx meta.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Getters)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolReserves {
+    input_reserve: Option<u64>,
+    output_reserve: Option<u64>,
+}
+
+impl PoolReserves {
+    pub fn new(input_reserve: Option<u64>, output_reserve: Option<u64>) -> Self {
+        Self { input_reserve, output_reserve }
+    }
+
+    /// (input, output) reserves just before a swap of these amounts, assuming nothing else in the tx touched the vaults
+    pub fn before_swap(&self, input_amount: u64, output_amount: u64) -> Option<(u64, u64)> {
+        Some((self.input_reserve?.checked_sub(input_amount)?, self.output_reserve?.checked_add(output_amount)?))
+    }
+}
+
+impl Debug for SwapV2 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // f.debug_struct("SwapV2").field("outer_program", &self.outer_program).field("program", &self.program).field("amm", &self.amm).field("input_mint", &self.input_mint).field("output_mint", &self.output_mint).field("input_amount", &self.input_amount).field("output_amount", &self.output_amount).field("input_ata", &self.input_ata).field("output_ata", &self.output_ata).field("sig_id", &self.sig_id).field("slot", &self.slot).field("inclusion_order", &self.inclusion_order).field("ix_index", &self.ix_index).field("inner_ix_index", &self.inner_ix_index).finish()
+        f.write_str("Swap")?;
+        if self.id != 0 {
+            f.write_str(&format!(" #{}", self.id))?;
+        }
+        f.write_str(&format!(" in slot {} (order {}, ix {}, inner_ix {:?})\n", self.slot(), self.inclusion_order(), self.ix_index(), self.inner_ix_index()))?;
+        if let Some(outer_program) = &self.outer_program {
+            f.write_str(&format!(" via {}\n", outer_program))?;
+        }
+        f.write_str(&format!(" on {} market {}\n", self.program, self.amm))?;
+        f.write_str(&format!(" Route {} -> {}", self.input_mint, self.output_mint))?;
+        f.write_str(&format!(" Amounts {} -> {}\n", self.input_amount, self.output_amount))?;
+        f.write_str(&format!(" ATAs {} -> {}", self.input_ata, self.output_ata))?;
+        Ok(())
+    }
+}
+
+pub trait SwapFinder {
+    /// Returns the swaps utilising a program found in the given instruction and inner instructions.
+    /// A swap involves an inner instruction that the user's out ATA sends tokens to the pool's in ATA,
+    /// and one that the pool's out ATA sends tokens to the user's in ATA.
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, meta: &TransactionStatusMeta, token_accounts: &TokenAccounts) -> Vec<SwapV2>;
+
+    /// Returns the AMM address for the swap instruction. The instruction will have matching program ID, discriminant and enough instruction data.
+    fn amm_ix(ix: &Instruction) -> Pubkey;
+    /// Like [`SwapFinder::amm_ix`], but takes an inner instruction and the account keys vector for key resolution.
+    fn amm_inner_ix(inner_ix: &InnerInstruction, account_keys: &Vec<Pubkey>) -> Pubkey;
+
+    /// Returns the user's in/out ATAs involved in the swap, in that order. The instruction follows the same constraints as above.
+    fn user_ata_ix(ix: &Instruction) -> (Pubkey, Pubkey);
+    /// Like [`SwapFinder::user_ata_ix`], but takes an inner instruction and the account keys vector for key resolution.
+    fn user_ata_inner_ix(inner_ix: &InnerInstruction, account_keys: &Vec<Pubkey>) -> (Pubkey, Pubkey);
+
+    /// Returns the pool's in/out ATAs involved in the swap, in that order. The instruction follows the same constraints as above.
+    /// Can return [`Pubkey::default()`] to bypass this check.
+    fn pool_ata_ix(_ix: &Instruction) -> (Pubkey, Pubkey) {
+        return (
+            Pubkey::default(),
+            Pubkey::default(),
+        );
+    }
+    /// Like [`SwapFinder::pool_ata_ix`], but takes an inner instruction and the account keys vector for key resolution.
+    fn pool_ata_inner_ix(_inner_ix: &InnerInstruction, _account_keys: &Vec<Pubkey>) -> (Pubkey, Pubkey) {
+        return (
+            Pubkey::default(),
+            Pubkey::default(),
+        );
+    }
+
+    /// Number of inner instructions to skip before the actual relevant transfers.
+    fn ixs_to_skip() -> usize {
+        0
+    }
+
+    /// The indexes of the accounts that definitely won't be involved in the swap, such as referral/fee accounts.
+    fn blacklist_ata_indexs() -> Vec<usize> {
+        vec![]
+    }
+
+    /// Decodes the min out/max in the swap was submitted with from the ix data. The data has matching program ID and discriminant.
+    /// Defaults to neither for programs whose layout isn't decoded.
+    fn quote_limits(_ix_data: &[u8]) -> QuoteLimits {
+        QuoteLimits::default()
+    }
+}
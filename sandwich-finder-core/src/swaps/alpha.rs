@@ -1,7 +1,7 @@
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 use yellowstone_grpc_proto::prelude::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
 
-use crate::events::{addresses::ALPHA_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}};
+use crate::{addresses::ALPHA_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}, token_accounts::TokenAccounts};
 
 impl Sealed for AlphaSwapFinder {}
 
@@ -81,10 +81,10 @@ impl SwapFinder for AlphaSwapFinder {
         }
     }
 
-    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, meta: &TransactionStatusMeta) -> Vec<SwapV2> {
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
         [
             // swap
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &ALPHA_PUBKEY, &[0x0c], 0, 18),
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &ALPHA_PUBKEY, &[0x0c], 0, 18),
         ].concat()
     }
 }
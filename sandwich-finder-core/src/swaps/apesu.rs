@@ -1,7 +1,7 @@
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 use yellowstone_grpc_proto::prelude::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
 
-use crate::events::{addresses::APESU_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}};
+use crate::{addresses::APESU_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}, token_accounts::TokenAccounts};
 
 impl Sealed for ApesuSwapFinder {}
 
@@ -82,10 +82,10 @@ impl SwapFinder for ApesuSwapFinder {
         }
     }
 
-    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, meta: &TransactionStatusMeta) -> Vec<SwapV2> {
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
         [
             // swap
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &APESU_PUBKEY, &[0xf8, 0xc6, 0x9e, 0x91, 0xe1, 0x75, 0x87, 0xc8], 0, 25),
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &APESU_PUBKEY, &[0xf8, 0xc6, 0x9e, 0x91, 0xe1, 0x75, 0x87, 0xc8], 0, 25),
         ].concat()
     }
 }
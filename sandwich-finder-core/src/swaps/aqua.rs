@@ -1,7 +1,7 @@
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 use yellowstone_grpc_proto::prelude::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
 
-use crate::events::{addresses::AQUA_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}};
+use crate::{addresses::AQUA_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}, token_accounts::TokenAccounts};
 
 impl Sealed for AquaSwapFinder {}
 
@@ -47,10 +47,10 @@ impl SwapFinder for AquaSwapFinder {
         )
     }
 
-    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, meta: &TransactionStatusMeta) -> Vec<SwapV2> {
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
         [
             // swap
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &AQUA_PUBKEY, &[0x01], 0, 9),
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &AQUA_PUBKEY, &[0x01], 0, 9),
         ].concat()
     }
 }
@@ -3,7 +3,7 @@ use std::{collections::HashSet, sync::Arc};
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 use yellowstone_grpc_proto::prelude::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
 
-use crate::events::{swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, utils::token_transferred_inner}};
+use crate::{addresses::is_known_aggregator, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, utils::token_transferred_inner}, token_accounts::TokenAccounts};
 
 const BLACKLISTED_COMBINATIONS: &[(Pubkey, &[u8], usize)] = &[ // program, discriminant, offset
     (Pubkey::from_str_const("DDZDcYdQFEMwcu2Mwo75yGFjJ1mUQyyXLWzhZLEVFcei"), &[], 0), // appears to be something that does smth with the audio token
@@ -45,9 +45,11 @@ impl SwapFinder for Discoverer {
         )
     }
 
-    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, meta: &TransactionStatusMeta) -> Vec<SwapV2> {
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
         // ignore known programs
         match ix.program_id {
+            // aggregators route through AMMs we may not support yet, the AMM is what should get discovered rather than the aggregator
+            program_id if is_known_aggregator(&program_id) => vec![],
             // RAYDIUM_V4_PUBKEY | RAYDIUM_V5_PUBKEY | RAYDIUM_LP_PUBKEY | RAYDIUM_CL_PUBKEY | PDF_PUBKEY | PDF2_PUBKEY | WHIRLPOOL_PUBKEY | DLMM_PUBKEY | METEORA_PUBKEY => vec![],
             _ => {
                 let mut transfer_count = 0;
@@ -63,7 +65,7 @@ impl SwapFinder for Discoverer {
                     }
                 }
                 for inner_ix in &inner_ixs.instructions {
-                    if let Some((_from, _to, _auth, mint, _amount)) = token_transferred_inner(&inner_ix, &account_keys, &meta) {
+                    if let Some((_from, _to, _auth, mint, _amount)) = token_transferred_inner(&inner_ix, &account_keys, token_accounts) {
                         transfer_count += 1;
                         match inner_ix.data[0] {
                             2 => { // System transfer
@@ -99,4 +101,28 @@ impl SwapFinder for Discoverer {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::addresses::{OKX_DEX_ROUTER_PROGRAM_ID, TOKEN_PROGRAM_ID};
+
+    use super::*;
+
+    #[test]
+    fn test_aggregators_not_discovered() {
+        // user -> vault of one mint and vault -> user of another, with the accounts of a TransferChecked
+        let account_keys: Vec<Pubkey> = std::iter::once(TOKEN_PROGRAM_ID).chain((0..8).map(|_| Pubkey::new_unique())).collect();
+        let transfer = |accounts: Vec<u8>| InnerInstruction {
+            program_id_index: 0,
+            accounts,
+            data: [vec![12], 100u64.to_le_bytes().to_vec(), vec![6]].concat(),
+            stack_height: Some(2),
+        };
+        let inner_ixs = InnerInstructions { index: 0, instructions: vec![transfer(vec![3, 1, 4, 5]), transfer(vec![6, 2, 7, 8])] };
+        let find = |program_id: Pubkey| Discoverer::find_swaps(&Instruction { program_id, accounts: vec![], data: vec![] }, &inner_ixs, &account_keys, &TransactionStatusMeta::default(), &TokenAccounts::default());
+        let unknown = Pubkey::new_unique();
+        assert_eq!(find(unknown).iter().map(|s| s.program().to_string()).collect::<Vec<_>>(), vec![unknown.to_string()]);
+        assert!(find(OKX_DEX_ROUTER_PROGRAM_ID).is_empty());
+    }
+}
@@ -1,7 +1,7 @@
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 use yellowstone_grpc_proto::prelude::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
 
-use crate::events::{addresses::FLUXBEAM_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}};
+use crate::{addresses::FLUXBEAM_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}, token_accounts::TokenAccounts};
 
 impl Sealed for FluxbeamSwapFinder {}
 
@@ -45,9 +45,9 @@ impl SwapFinder for FluxbeamSwapFinder {
         )
     }
 
-    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, meta: &TransactionStatusMeta) -> Vec<SwapV2> {
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
         [
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &FLUXBEAM_PUBKEY, &[0x01], 0, 17),
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &FLUXBEAM_PUBKEY, &[0x01], 0, 17),
         ].concat()
     }
 }
\ No newline at end of file
@@ -1,15 +1,19 @@
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 use yellowstone_grpc_proto::prelude::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
 
-use crate::events::{addresses::GOONFI_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}};
+use crate::{addresses::GOONFI_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt, utils::reconcile_swap_amounts}, token_accounts::TokenAccounts};
 
 impl Sealed for GoonFiSwapFinder {}
 
 pub struct GoonFiSwapFinder {}
 
+/// Transfer fees of token-2022 mints make the user receive less than the pool sent
+const BALANCE_TOLERANCE_BPS: u64 = 200;
+
 /// SolFi a single swap instruction
 /// [1] is a_to_b
 /// user a/b: 3/2, pool a/b: 5/4
+/// Amounts are checked against the user's balance deltas since the transfers alone don't account for transfer fees
 impl GoonFiSwapFinder {
     fn is_a_to_b(data: &[u8]) -> bool {
         data[1] == 1
@@ -81,10 +85,12 @@ impl SwapFinder for GoonFiSwapFinder {
         }
     }
 
-    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, meta: &TransactionStatusMeta) -> Vec<SwapV2> {
-        [
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, meta: &TransactionStatusMeta, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
+        let mut swaps = [
             // swap
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &GOONFI_PUBKEY, &[0x02], 0, 19),
-        ].concat()
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &GOONFI_PUBKEY, &[0x02], 0, 19),
+        ].concat();
+        swaps.iter_mut().for_each(|swap| reconcile_swap_amounts(swap, account_keys, meta, BALANCE_TOLERANCE_BPS));
+        swaps
     }
 }
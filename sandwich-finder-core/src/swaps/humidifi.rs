@@ -1,7 +1,7 @@
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 use yellowstone_grpc_proto::prelude::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
 
-use crate::events::{addresses::HUMIDIFI_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}};
+use crate::{addresses::HUMIDIFI_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}, token_accounts::TokenAccounts};
 
 impl Sealed for HumidiFiSwapFinder {}
 
@@ -103,10 +103,10 @@ impl SwapFinder for HumidiFiSwapFinder {
         }
     }
 
-    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, meta: &TransactionStatusMeta) -> Vec<SwapV2> {
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
         [
             // swap
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &HUMIDIFI_PUBKEY, &[0xff, 0x2d, 0xff, 0xe0, 0xba, 0xe9, 0xc3, 0x3d], 17, 25),
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &HUMIDIFI_PUBKEY, &[0xff, 0x2d, 0xff, 0xe0, 0xba, 0xe9, 0xc3, 0x3d], 17, 25),
         ].concat()
     }
 }
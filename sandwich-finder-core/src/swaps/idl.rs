@@ -0,0 +1,247 @@
+//! Conformance of the finders' hard-coded discriminants and account indexes with the programs' IDLs in `idls/`.
+//! A program upgrade that renumbers its accounts fails here instead of silently mislabelling swaps,
+//! refetch the IDLs with `idls/fetch.sh` to check for drift.
+//!
+//! Each instruction is fed to its finder with every account set to a key standing for its IDL name,
+//! so the names of the keys the finder picks out are what's compared.
+
+use std::{collections::HashMap, fs};
+
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use solana_sdk::{instruction::{AccountMeta, Instruction}, pubkey::Pubkey};
+use yellowstone_grpc_proto::prelude::InnerInstruction;
+
+use crate::{swap::SwapFinder, swaps::{meteora_dlmm::{self, MeteoraDLMMSwapFinder}, pumpfun::{self, PumpFunSwapFinder}, whirlpool::{self, WhirlpoolSwapFinder, WhirlpoolTwoHopSwapFinder1, WhirlpoolTwoHopSwapFinder2, WhirlpoolTwoHopSwapV2Finder1, WhirlpoolTwoHopSwapV2Finder2}}};
+
+/// (amm, user in/out, pool in/out) as picked out of an instruction
+type Roles = (Pubkey, (Pubkey, Pubkey), (Pubkey, Pubkey));
+
+struct IdlInstruction {
+    discriminator: [u8; 8],
+    /// Snake cased, composite accounts flattened
+    accounts: Vec<String>,
+    args: Vec<(String, Value)>,
+}
+
+/// Legacy IDLs are camel cased, Anchor hashes the snake cased name for the discriminator
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            snake.push('_');
+        }
+        snake.push(c.to_ascii_lowercase());
+    }
+    snake
+}
+
+fn flatten_accounts(accounts: &[Value], out: &mut Vec<String>) {
+    for account in accounts {
+        match account["accounts"].as_array() {
+            Some(nested) => flatten_accounts(nested, out),
+            None => out.push(snake_case(account["name"].as_str().unwrap())),
+        }
+    }
+}
+
+fn load(idl: &str, instruction: &str) -> IdlInstruction {
+    let path = format!("{}/idls/{}.json", env!("CARGO_MANIFEST_DIR"), idl);
+    let idl: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    let ix = idl["instructions"].as_array().unwrap().iter()
+        .find(|ix| snake_case(ix["name"].as_str().unwrap()) == instruction)
+        .unwrap_or_else(|| panic!("{} not in {}", instruction, path));
+    // only IDLs from anchor 0.30 onwards spell out the discriminator
+    let discriminator = match ix["discriminator"].as_array() {
+        Some(bytes) => bytes.iter().map(|b| b.as_u64().unwrap() as u8).collect::<Vec<_>>().try_into().unwrap(),
+        None => Sha256::digest(format!("global:{}", instruction))[..8].try_into().unwrap(),
+    };
+    let mut accounts = vec![];
+    flatten_accounts(ix["accounts"].as_array().unwrap(), &mut accounts);
+    let args = ix["args"].as_array().unwrap().iter()
+        .map(|arg| (snake_case(arg["name"].as_str().unwrap()), arg["type"].clone()))
+        .collect();
+    IdlInstruction { discriminator, accounts, args }
+}
+
+fn type_size(ty: &Value) -> usize {
+    match ty.as_str() {
+        Some("bool" | "u8" | "i8") => 1,
+        Some("u16" | "i16") => 2,
+        Some("u32" | "i32") => 4,
+        Some("u64" | "i64") => 8,
+        Some("u128" | "i128") => 16,
+        Some("pubkey" | "publicKey") => 32,
+        _ => panic!("variable sized arg {}", ty),
+    }
+}
+
+/// Where `arg` sits in the instruction data, as long as every arg before it has a fixed size
+fn arg_offset(ix: &IdlInstruction, arg: &str) -> usize {
+    let mut offset = 8;
+    for (name, ty) in &ix.args {
+        if name == arg {
+            return offset;
+        }
+        offset += type_size(ty);
+    }
+    panic!("no arg {}", arg);
+}
+
+/// The roles `F` picks out of `ix`, which must be the same whether it's a top level or an inner instruction
+fn roles<F: SwapFinder>(ix: &Instruction) -> Roles {
+    let outer = (F::amm_ix(ix), F::user_ata_ix(ix), F::pool_ata_ix(ix));
+    let account_keys = ix.accounts.iter().map(|account| account.pubkey).collect();
+    let inner_ix = InnerInstruction {
+        accounts: (0..ix.accounts.len() as u8).collect(),
+        data: ix.data.clone(),
+        ..Default::default()
+    };
+    let inner = (F::amm_inner_ix(&inner_ix, &account_keys), F::user_ata_inner_ix(&inner_ix, &account_keys), F::pool_ata_inner_ix(&inner_ix, &account_keys));
+    assert_eq!(outer, inner, "ix and inner ix disagree");
+    outer
+}
+
+struct Case {
+    /// File stem in `idls/`
+    idl: &'static str,
+    instruction: &'static str,
+    /// What the finder matches on
+    discriminant: [u8; 8],
+    /// The bool arg setting the direction, `user` and `pool` are for when it's set and swap places when it isn't
+    direction: Option<&'static str>,
+    amm: &'static str,
+    user: (&'static str, &'static str),
+    /// Blank for finders that don't check the pool's side
+    pool: (&'static str, &'static str),
+    roles: fn(&Instruction) -> Roles,
+}
+
+fn check(case: &Case) {
+    let idl = load(case.idl, case.instruction);
+    assert_eq!(idl.discriminator, case.discriminant, "{}::{} discriminant drifted", case.idl, case.instruction);
+    let keys: Vec<Pubkey> = idl.accounts.iter().map(|_| Pubkey::new_unique()).collect();
+    let names: HashMap<Pubkey, &str> = keys.iter().zip(&idl.accounts).map(|(key, name)| (*key, name.as_str())).collect();
+    let name = |key: Pubkey| names.get(&key).copied().unwrap_or("");
+    let mut data = case.discriminant.to_vec();
+    data.resize(128, 0);
+    let offset = case.direction.map(|arg| arg_offset(&idl, arg));
+    let directions: &[bool] = if offset.is_some() { &[true, false] } else { &[true] };
+    for &set in directions {
+        if let Some(offset) = offset {
+            data[offset] = set as u8;
+        }
+        let ix = Instruction {
+            program_id: Pubkey::default(),
+            accounts: keys.iter().map(|key| AccountMeta::new(*key, false)).collect(),
+            data: data.clone(),
+        };
+        let (amm, user, pool) = (case.roles)(&ix);
+        let expected = if set { (case.user, case.pool) } else { ((case.user.1, case.user.0), (case.pool.1, case.pool.0)) };
+        assert_eq!(
+            (name(amm), (name(user.0), name(user.1)), (name(pool.0), name(pool.1))),
+            (case.amm, expected.0, expected.1),
+            "{}::{} accounts drifted ({:?} = {})", case.idl, case.instruction, case.direction, set,
+        );
+    }
+}
+
+#[test]
+fn test_whirlpool_conformance() {
+    let swap = |instruction, discriminant, roles| Case {
+        idl: "whirlpool",
+        instruction,
+        discriminant,
+        direction: Some("a_to_b"),
+        amm: "whirlpool",
+        user: ("token_owner_account_a", "token_owner_account_b"),
+        pool: ("token_vault_b", "token_vault_a"),
+        roles,
+    };
+    check(&swap("swap", whirlpool::SWAP_DISCRIMINANT, roles::<WhirlpoolSwapFinder>));
+    check(&swap("swap_v2", whirlpool::SWAP_V2_DISCRIMINANT, roles::<WhirlpoolSwapFinder>));
+    check(&Case {
+        idl: "whirlpool",
+        instruction: "two_hop_swap",
+        discriminant: WhirlpoolTwoHopSwapFinder1::DISCRIMINANT,
+        direction: Some("a_to_b_one"),
+        amm: "whirlpool_one",
+        user: ("token_owner_account_one_a", "token_owner_account_one_b"),
+        pool: ("token_vault_one_b", "token_vault_one_a"),
+        roles: roles::<WhirlpoolTwoHopSwapFinder1>,
+    });
+    check(&Case {
+        idl: "whirlpool",
+        instruction: "two_hop_swap",
+        discriminant: WhirlpoolTwoHopSwapFinder2::DISCRIMINANT,
+        direction: Some("a_to_b_two"),
+        amm: "whirlpool_two",
+        user: ("token_owner_account_two_a", "token_owner_account_two_b"),
+        pool: ("token_vault_two_b", "token_vault_two_a"),
+        roles: roles::<WhirlpoolTwoHopSwapFinder2>,
+    });
+    // the intermediate vaults stand in for the user's side of the hops, see the finders
+    check(&Case {
+        idl: "whirlpool",
+        instruction: "two_hop_swap_v2",
+        discriminant: WhirlpoolTwoHopSwapV2Finder1::DISCRIMINANT,
+        direction: None,
+        amm: "whirlpool_one",
+        user: ("token_owner_account_input", "token_vault_two_intermediate"),
+        pool: ("token_vault_one_intermediate", "token_vault_one_input"),
+        roles: roles::<WhirlpoolTwoHopSwapV2Finder1>,
+    });
+    check(&Case {
+        idl: "whirlpool",
+        instruction: "two_hop_swap_v2",
+        discriminant: WhirlpoolTwoHopSwapV2Finder2::DISCRIMINANT,
+        direction: None,
+        amm: "whirlpool_two",
+        user: ("token_vault_one_intermediate", "token_owner_account_output"),
+        pool: ("token_vault_two_output", "token_vault_two_intermediate"),
+        roles: roles::<WhirlpoolTwoHopSwapV2Finder2>,
+    });
+}
+
+#[test]
+fn test_meteora_dlmm_conformance() {
+    for (instruction, discriminant) in [
+        ("swap", meteora_dlmm::SWAP_DISCRIMINANT),
+        ("swap2", meteora_dlmm::SWAP2_DISCRIMINANT),
+        ("swap_exact_out", meteora_dlmm::SWAP_EXACT_OUT_DISCRIMINANT),
+        ("swap_exact_out2", meteora_dlmm::SWAP_EXACT_OUT2_DISCRIMINANT),
+        ("swap_with_price_impact", meteora_dlmm::SWAP_WITH_PRICE_IMPACT_DISCRIMINANT),
+        ("swap_with_price_impact2", meteora_dlmm::SWAP_WITH_PRICE_IMPACT2_DISCRIMINANT),
+    ] {
+        check(&Case {
+            idl: "meteora_dlmm",
+            instruction,
+            discriminant,
+            direction: None,
+            amm: "lb_pair",
+            user: ("user_token_in", "user_token_out"),
+            pool: ("", ""),
+            roles: roles::<MeteoraDLMMSwapFinder>,
+        });
+    }
+}
+
+#[test]
+fn test_pumpfun_conformance() {
+    // sol moves straight out of/into the user's wallet
+    for (instruction, discriminant, user) in [
+        ("buy", pumpfun::BUY_DISCRIMINANT, ("user", "associated_user")),
+        ("sell", pumpfun::SELL_DISCRIMINANT, ("associated_user", "user")),
+    ] {
+        check(&Case {
+            idl: "pumpfun",
+            instruction,
+            discriminant,
+            direction: None,
+            amm: "bonding_curve",
+            user,
+            pool: ("", ""),
+            roles: roles::<PumpFunSwapFinder>,
+        });
+    }
+}
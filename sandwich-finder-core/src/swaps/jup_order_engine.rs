@@ -1,7 +1,7 @@
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 use yellowstone_grpc_proto::prelude::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
 
-use crate::events::{addresses::JUP_ORDER_ENGINE_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}};
+use crate::{addresses::JUP_ORDER_ENGINE_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}, token_accounts::TokenAccounts};
 
 impl Sealed for JupOrderEngineSwapFinder {}
 
@@ -91,10 +91,10 @@ impl SwapFinder for JupOrderEngineSwapFinder {
         (keys[2], keys[3])
     }
 
-    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, meta: &TransactionStatusMeta) -> Vec<SwapV2> {
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
         [
             // fill
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &JUP_ORDER_ENGINE_PUBKEY, &[0xa8, 0x60, 0xb7, 0xa3, 0x5c, 0x0a, 0x28, 0xa0], 0, 32),
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &JUP_ORDER_ENGINE_PUBKEY, &[0xa8, 0x60, 0xb7, 0xa3, 0x5c, 0x0a, 0x28, 0xa0], 0, 32),
         ].concat()
     }
 }
@@ -1,7 +1,7 @@
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 use yellowstone_grpc_proto::prelude::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
 
-use crate::events::{addresses::JUP_PERPS_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}};
+use crate::{addresses::JUP_PERPS_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}, token_accounts::TokenAccounts};
 
 enum JupPerpsSwapVariant {
     Swap2,
@@ -83,14 +83,14 @@ impl SwapFinder for JupPerpsSwapFinder {
         }
     }
 
-    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, meta: &TransactionStatusMeta) -> Vec<SwapV2> {
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
         [
             // swap_base_input
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &JUP_PERPS_PUBKEY, &[0x41, 0x4b, 0x3f, 0x4c, 0xeb, 0x5b, 0x5b, 0x88], 0, 24),
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &JUP_PERPS_PUBKEY, &[0x41, 0x4b, 0x3f, 0x4c, 0xeb, 0x5b, 0x5b, 0x88], 0, 24),
             // swap_base_output
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &JUP_PERPS_PUBKEY, &[0x8b, 0x8d, 0xee, 0xc5, 0x29, 0xd3, 0xac, 0x13], 0, 24),
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &JUP_PERPS_PUBKEY, &[0x8b, 0x8d, 0xee, 0xc5, 0x29, 0xd3, 0xac, 0x13], 0, 24),
             // instant_increase_position_pre_swap
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &JUP_PERPS_PUBKEY, &[0xc5, 0x26, 0x56, 0xa5, 0xc7, 0x17, 0x26, 0xea], 0, 24),
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &JUP_PERPS_PUBKEY, &[0xc5, 0x26, 0x56, 0xa5, 0xc7, 0x17, 0x26, 0xea], 0, 24),
         ].concat()
     }
 }
\ No newline at end of file
@@ -0,0 +1,135 @@
+use solana_sdk::pubkey::Pubkey;
+use yellowstone_grpc_proto::prelude::{InnerInstructions, SubscribeUpdateTransactionInfo};
+
+use crate::{addresses::JUP_V6_PROGRAM_ID, metrics, swap::SwapV2, swaps::utils::read_u64};
+
+/// Anchor's tag for events emitted through a self-CPI, stored little endian in front of the event
+const EVENT_IX_TAG: u64 = 0x1d9acb512ea545e4;
+const SWAP_EVENT_DISCRIMINANT: [u8; 8] = [0x40, 0xc6, 0xcd, 0xe8, 0x26, 0x08, 0x71, 0xe2];
+// tag, discriminant, amm, input mint, input amount, output mint, output amount
+const SWAP_EVENT_LENGTH: usize = 8 + 8 + 32 + 32 + 8 + 32 + 8;
+
+/// One leg of a Jupiter v6 route, as logged by Jupiter itself after the leg's CPI into the AMM program
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JupiterSwapEvent {
+    /// The AMM's program, not the pool
+    pub amm: Pubkey,
+    pub input_mint: Pubkey,
+    pub input_amount: u64,
+    pub output_mint: Pubkey,
+    pub output_amount: u64,
+    pub inner_ix_index: u32,
+}
+
+impl JupiterSwapEvent {
+    fn parse(data: &[u8], inner_ix_index: u32) -> Option<Self> {
+        if data.len() < SWAP_EVENT_LENGTH || data[..8] != EVENT_IX_TAG.to_le_bytes() || data[8..16] != SWAP_EVENT_DISCRIMINANT {
+            return None;
+        }
+        let pubkey = |offset: usize| Pubkey::new_from_array(data[offset..offset + 32].try_into().unwrap());
+        Some(Self {
+            amm: pubkey(16),
+            input_mint: pubkey(48),
+            input_amount: read_u64(data, 80)?,
+            output_mint: pubkey(88),
+            output_amount: read_u64(data, 120)?,
+            inner_ix_index,
+        })
+    }
+}
+
+/// The swap events Jupiter logged under one top level ix, in order
+pub fn swap_events(inner_ixs: &InnerInstructions, account_keys: &[Pubkey]) -> Vec<JupiterSwapEvent> {
+    inner_ixs.instructions.iter().enumerate().filter(|(_, inner_ix)| {
+        account_keys.get(inner_ix.program_id_index as usize) == Some(&JUP_V6_PROGRAM_ID)
+    }).filter_map(|(i, inner_ix)| JupiterSwapEvent::parse(&inner_ix.data, i as u32)).collect()
+}
+
+/// Replaces the amounts of the legs of Jupiter routes found by the AMM finders with the ones Jupiter logged.
+/// Transfer pairing struggles with shared-accounts routes, where the legs move tokens between Jupiter's own accounts,
+/// while the events carry the exact amounts of each leg. Each leg takes the first unused event after its CPI from the
+/// same AMM program and with the same mints, legs without one keep their amounts.
+pub fn apply_swap_events<'a>(swaps: impl IntoIterator<Item = &'a mut SwapV2>, events: &[JupiterSwapEvent]) {
+    let mut used = vec![false; events.len()];
+    for swap in swaps {
+        let Some(inner_ix_index) = *swap.inner_ix_index() else {
+            continue;
+        };
+        if swap.outer_program().as_deref() != Some(JUP_V6_PROGRAM_ID.to_string().as_str()) {
+            continue;
+        }
+        let found = events.iter().enumerate().find(|(i, e)| {
+            !used[*i]
+                && e.inner_ix_index > inner_ix_index
+                && e.amm.to_string() == swap.program().as_ref()
+                && e.input_mint.to_string() == swap.input_mint().as_ref()
+                && e.output_mint.to_string() == swap.output_mint().as_ref()
+        });
+        let Some((i, event)) = found else {
+            continue;
+        };
+        used[i] = true;
+        if (event.input_amount, event.output_amount) != (*swap.input_amount(), *swap.output_amount()) {
+            metrics::incr("jupiter_event_amount_mismatch");
+            swap.set_amounts(event.input_amount, event.output_amount);
+        }
+    }
+}
+
+/// Runs [`apply_swap_events`] over each top level ix of a tx, `swaps` being the swaps found in the tx
+pub fn apply_swap_events_in_tx(swaps: &mut [SwapV2], raw_tx: &SubscribeUpdateTransactionInfo, account_keys: &[Pubkey]) {
+    let Some(meta) = &raw_tx.meta else {
+        return;
+    };
+    for inner_ixs in meta.inner_instructions.iter() {
+        let events = swap_events(inner_ixs, account_keys);
+        if events.is_empty() {
+            continue;
+        }
+        apply_swap_events(swaps.iter_mut().filter(|s| *s.ix_index() == inner_ixs.index), &events);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sha2::{Digest as _, Sha256};
+    use yellowstone_grpc_proto::prelude::InnerInstruction;
+
+    use super::*;
+
+    #[test]
+    fn test_discriminants() {
+        let mut tag: [u8; 8] = Sha256::digest("anchor:event")[..8].try_into().unwrap();
+        tag.reverse();
+        assert_eq!(tag, EVENT_IX_TAG.to_le_bytes());
+        assert_eq!(Sha256::digest("event:SwapEvent")[..8], SWAP_EVENT_DISCRIMINANT);
+    }
+
+    #[test]
+    fn test_apply_swap_events() {
+        // jupiter, whirlpool, mint A, mint B
+        let keys = [JUP_V6_PROGRAM_ID, Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let event_ix = |input_amount: u64, output_amount: u64| InnerInstruction {
+            program_id_index: 0,
+            accounts: vec![],
+            data: [EVENT_IX_TAG.to_le_bytes().to_vec(), SWAP_EVENT_DISCRIMINANT.to_vec(), keys[1].to_bytes().to_vec(), keys[2].to_bytes().to_vec(), input_amount.to_le_bytes().to_vec(), keys[3].to_bytes().to_vec(), output_amount.to_le_bytes().to_vec()].concat(),
+            stack_height: Some(2),
+        };
+        let other_ix = InnerInstruction { program_id_index: 1, accounts: vec![], data: vec![], stack_height: Some(2) };
+        // two legs on the same program, each followed by its event
+        let inner_ixs = InnerInstructions { index: 0, instructions: vec![other_ix.clone(), event_ix(1000, 500), other_ix, event_ix(2000, 990)] };
+        let events = swap_events(&inner_ixs, &keys);
+        assert_eq!(events.len(), 2);
+        assert_eq!((events[1].amm, events[1].input_amount, events[1].output_amount, events[1].inner_ix_index), (keys[1], 2000, 990, 3));
+        let swap = |inner_ix_index: u32, output_amount: u64| SwapV2::new(Some(JUP_V6_PROGRAM_ID.to_string().into()), keys[1].to_string().into(), "user".into(), "pool".into(), keys[2].to_string().into(), keys[3].to_string().into(), 1000, output_amount, "in".into(), "out".into(), None, None, 1, 0, 0, Some(inner_ix_index), 0);
+        // the second leg's output was mispaired
+        let mut swaps = vec![swap(0, 500), swap(2, 7)];
+        apply_swap_events(&mut swaps, &events);
+        assert_eq!((*swaps[0].input_amount(), *swaps[0].output_amount()), (1000, 500));
+        assert_eq!((*swaps[1].input_amount(), *swaps[1].output_amount()), (2000, 990));
+        // legs after the last event have nothing to go by
+        let mut swaps = vec![swap(4, 7)];
+        apply_swap_events(&mut swaps, &events);
+        assert_eq!(*swaps[0].output_amount(), 7);
+    }
+}
@@ -1,7 +1,7 @@
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 use yellowstone_grpc_proto::prelude::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
 
-use crate::events::{addresses::LIMO_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}};
+use crate::{addresses::LIMO_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}, token_accounts::TokenAccounts};
 
 impl Sealed for LimoSwapFinder {}
 
@@ -75,10 +75,10 @@ impl SwapFinder for LimoSwapFinder {
         (keys[2], keys[3])
     }
 
-    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, meta: &TransactionStatusMeta) -> Vec<SwapV2> {
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
         [
             // fill
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &LIMO_PUBKEY, &[0xa3, 0xd0, 0x14, 0xac, 0xdf, 0x41, 0xff, 0xe4], 0, 32),
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &LIMO_PUBKEY, &[0xa3, 0xd0, 0x14, 0xac, 0xdf, 0x41, 0xff, 0xe4], 0, 32),
         ].concat()
     }
 }
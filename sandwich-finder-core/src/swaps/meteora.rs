@@ -1,7 +1,7 @@
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 use yellowstone_grpc_proto::prelude::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
 
-use crate::events::{addresses::METEORA_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}};
+use crate::{addresses::METEORA_PUBKEY, swap::{QuoteLimits, SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt, utils::read_u64}, token_accounts::TokenAccounts};
 
 impl Sealed for MeteoraSwapFinder {}
 
@@ -45,7 +45,11 @@ impl SwapFinder for MeteoraSwapFinder {
         1
     }
 
-    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, meta: &TransactionStatusMeta) -> Vec<SwapV2> {
-        Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &METEORA_PUBKEY, &[0xf8, 0xc6, 0x9e, 0x91, 0xe1, 0x75, 0x87, 0xc8], 0, 17)
+    fn quote_limits(ix_data: &[u8]) -> QuoteLimits {
+        QuoteLimits::exact_in(read_u64(ix_data, 16)) // swap(in_amount, minimum_out_amount)
+    }
+
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
+        Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &METEORA_PUBKEY, &[0xf8, 0xc6, 0x9e, 0x91, 0xe1, 0x75, 0x87, 0xc8], 0, 17)
     }
 }
\ No newline at end of file
@@ -1,7 +1,7 @@
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 use yellowstone_grpc_proto::prelude::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
 
-use crate::events::{addresses::METEORA_DAMMV2_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}};
+use crate::{addresses::METEORA_DAMMV2_PUBKEY, swap::{QuoteLimits, SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt, utils::read_u64}, token_accounts::TokenAccounts};
 
 impl Sealed for MeteoraDammV2Finder {}
 
@@ -35,11 +35,21 @@ impl SwapFinder for MeteoraDammV2Finder {
         vec![11] // referral
     }
 
-    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, meta: &TransactionStatusMeta) -> Vec<SwapV2> {
+    /// swap is (amount_in, minimum_amount_out), swap2 is (amount_0, amount_1, swap_mode) where
+    /// mode 0/1 (exact in/partial fill) has amount_1 as the min out and mode 2 (exact out) as the max in
+    fn quote_limits(ix_data: &[u8]) -> QuoteLimits {
+        match (ix_data.starts_with(&[0x41, 0x4b, 0x3f, 0x4c, 0xeb, 0x5b, 0x5b, 0x88]), ix_data.get(24)) {
+            (true, Some(2)) => QuoteLimits::exact_out(read_u64(ix_data, 16)),
+            (true, None) => QuoteLimits::default(),
+            _ => QuoteLimits::exact_in(read_u64(ix_data, 16)),
+        }
+    }
+
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
         [
             // swap
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &METEORA_DAMMV2_PUBKEY, &[0xf8, 0xc6, 0x9e, 0x91, 0xe1, 0x75, 0x87, 0xc8], 0, 24),
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &METEORA_DAMMV2_PUBKEY, &[0x41, 0x4b, 0x3f, 0x4c, 0xeb, 0x5b, 0x5b, 0x88], 0, 25),
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &METEORA_DAMMV2_PUBKEY, &[0xf8, 0xc6, 0x9e, 0x91, 0xe1, 0x75, 0x87, 0xc8], 0, 24),
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &METEORA_DAMMV2_PUBKEY, &[0x41, 0x4b, 0x3f, 0x4c, 0xeb, 0x5b, 0x5b, 0x88], 0, 25),
         ].concat()
     }
 }
\ No newline at end of file
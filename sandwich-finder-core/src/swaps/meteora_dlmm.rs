@@ -0,0 +1,68 @@
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use yellowstone_grpc_proto::prelude::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
+
+use crate::{addresses::METEORA_DLMM_PUBKEY, swap::{QuoteLimits, SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt, utils::read_u64}, token_accounts::TokenAccounts};
+
+impl Sealed for MeteoraDLMMSwapFinder {}
+
+pub struct MeteoraDLMMSwapFinder {}
+
+pub(super) const SWAP_DISCRIMINANT: [u8; 8] = [0xf8, 0xc6, 0x9e, 0x91, 0xe1, 0x75, 0x87, 0xc8];
+pub(super) const SWAP2_DISCRIMINANT: [u8; 8] = [0x41, 0x4b, 0x3f, 0x4c, 0xeb, 0x5b, 0x5b, 0x88];
+pub(super) const SWAP_EXACT_OUT_DISCRIMINANT: [u8; 8] = [0xfa, 0x49, 0x65, 0x21, 0x26, 0xcf, 0x4b, 0xb8];
+pub(super) const SWAP_EXACT_OUT2_DISCRIMINANT: [u8; 8] = [0x2b, 0xd7, 0xf7, 0x84, 0x89, 0x3c, 0xf3, 0x51];
+pub(super) const SWAP_WITH_PRICE_IMPACT_DISCRIMINANT: [u8; 8] = [0x38, 0xad, 0xe6, 0xd0, 0xad, 0xe4, 0x9c, 0xcd];
+pub(super) const SWAP_WITH_PRICE_IMPACT2_DISCRIMINANT: [u8; 8] = [0x4a, 0x62, 0xc0, 0xd6, 0xb1, 0x33, 0x4b, 0x33];
+
+/// There's a grand total of 6 swap variants for DLMM
+/// But all 6 of them have user_token_{in,out} at the [4] and [5] respectively
+impl SwapFinder for MeteoraDLMMSwapFinder {
+    fn amm_ix(ix: &Instruction) -> Pubkey {
+        return ix.accounts[0].pubkey;
+    }
+
+    fn amm_inner_ix(inner_ix: &InnerInstruction, account_keys: &Vec<Pubkey>) -> Pubkey {
+        return account_keys[inner_ix.accounts[0] as usize];
+    }
+
+    fn user_ata_ix(ix: &Instruction) -> (Pubkey, Pubkey) {
+        return (
+            ix.accounts[4].pubkey,
+            ix.accounts[5].pubkey,
+        );
+    }
+
+    fn user_ata_inner_ix(inner_ix: &InnerInstruction, account_keys: &Vec<Pubkey>) -> (Pubkey, Pubkey) {
+        return (
+            account_keys[inner_ix.accounts[4] as usize],
+            account_keys[inner_ix.accounts[5] as usize],
+        );
+    }
+
+    /// The price impact variants bound the price instead of the amounts
+    fn quote_limits(ix_data: &[u8]) -> QuoteLimits {
+        let discriminant: [u8; 8] = ix_data[0..8].try_into().unwrap();
+        match discriminant {
+            SWAP_DISCRIMINANT | SWAP2_DISCRIMINANT => QuoteLimits::exact_in(read_u64(ix_data, 16)), // (amount_in, min_amount_out)
+            SWAP_EXACT_OUT_DISCRIMINANT | SWAP_EXACT_OUT2_DISCRIMINANT => QuoteLimits::exact_out(read_u64(ix_data, 8)), // (max_in_amount, out_amount)
+            _ => QuoteLimits::default(),
+        }
+    }
+
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
+        [
+            // swap
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &METEORA_DLMM_PUBKEY, &SWAP_DISCRIMINANT, 0, 24),
+            // swap2
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &METEORA_DLMM_PUBKEY, &SWAP2_DISCRIMINANT, 0, 24),
+            // swap_exact_out
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &METEORA_DLMM_PUBKEY, &SWAP_EXACT_OUT_DISCRIMINANT, 0, 24),
+            // swap_exact_out2
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &METEORA_DLMM_PUBKEY, &SWAP_EXACT_OUT2_DISCRIMINANT, 0, 24),
+            // swap_with_price_impact
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &METEORA_DLMM_PUBKEY, &SWAP_WITH_PRICE_IMPACT_DISCRIMINANT, 0, 24),
+            // swap_with_price_impact2
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &METEORA_DLMM_PUBKEY, &SWAP_WITH_PRICE_IMPACT2_DISCRIMINANT, 0, 24),
+        ].concat()
+    }
+}
\ No newline at end of file
@@ -1,9 +1,12 @@
 mod private;
+#[cfg(test)]
+mod idl;
 
 pub mod swap_finder_ext;
 pub mod utils;
 
 pub mod discoverer;
+pub mod registry;
 
 pub mod alpha;
 pub mod apesu;
@@ -17,6 +20,7 @@ pub mod heaven;
 pub mod humidifi;
 pub mod jup_order_engine;
 pub mod jup_perps;
+pub mod jupiter_v6;
 pub mod meteora;
 pub mod meteora_dlmm;
 pub mod meteora_damm_v2;
@@ -1,7 +1,7 @@
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 use yellowstone_grpc_proto::prelude::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
 
-use crate::events::{addresses::ONEDEX_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}};
+use crate::{addresses::ONEDEX_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}, token_accounts::TokenAccounts};
 
 impl Sealed for OneDexSwapFinder {}
 
@@ -47,10 +47,10 @@ impl SwapFinder for OneDexSwapFinder {
         )
     }
 
-    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, meta: &TransactionStatusMeta) -> Vec<SwapV2> {
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
         [
             // swap
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &ONEDEX_PUBKEY, &[0x08, 0x97, 0xf5, 0x4c, 0xac, 0xcb, 0x90, 0x27], 0, 24),
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &ONEDEX_PUBKEY, &[0x08, 0x97, 0xf5, 0x4c, 0xac, 0xcb, 0x90, 0x27], 0, 24),
         ].concat()
     }
 }
@@ -1,7 +1,7 @@
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 use yellowstone_grpc_proto::prelude::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
 
-use crate::events::{addresses::OPENBOOK_V2_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}};
+use crate::{addresses::OPENBOOK_V2_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}, token_accounts::TokenAccounts};
 
 impl Sealed for OpenbookV2SwapFinder {}
 
@@ -81,8 +81,8 @@ impl SwapFinder for OpenbookV2SwapFinder {
         }
     }
 
-    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, meta: &TransactionStatusMeta) -> Vec<SwapV2> {
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
         // placeTakeOrder
-        Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &OPENBOOK_V2_PUBKEY, &[0x03, 0x2c, 0x47, 0x03, 0x1a, 0xc7, 0xcb, 0x55], 0, 35)
+        Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &OPENBOOK_V2_PUBKEY, &[0x03, 0x2c, 0x47, 0x03, 0x1a, 0xc7, 0xcb, 0x55], 0, 35)
     }
 }
\ No newline at end of file
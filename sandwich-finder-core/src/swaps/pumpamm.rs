@@ -1,7 +1,7 @@
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 use yellowstone_grpc_proto::prelude::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
 
-use crate::events::{addresses::PDF2_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}};
+use crate::{addresses::PDF2_PUBKEY, swap::{QuoteLimits, SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt, utils::read_u64}, token_accounts::TokenAccounts};
 
 impl Sealed for PumpAmmSwapFinder {}
 
@@ -77,14 +77,22 @@ impl SwapFinder for PumpAmmSwapFinder {
         )
     }
 
-    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, meta: &TransactionStatusMeta) -> Vec<SwapV2> {
+    fn quote_limits(ix_data: &[u8]) -> QuoteLimits {
+        if ix_data.starts_with(&[0x66, 0x06, 0x3d, 0x12, 0x01, 0xda, 0xeb, 0xea]) {
+            QuoteLimits::exact_out(read_u64(ix_data, 16)) // buy(base_amount_out, max_quote_amount_in)
+        } else {
+            QuoteLimits::exact_in(read_u64(ix_data, 16)) // sell(base_amount_in, min_quote_amount_out), buyExactQuoteIn(spendable_quote_in, min_base_amount_out)
+        }
+    }
+
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
         [
             // buy
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &PDF2_PUBKEY, &[0x66, 0x06, 0x3d, 0x12, 0x01, 0xda, 0xeb, 0xea], 0, 24),
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &PDF2_PUBKEY, &[0x66, 0x06, 0x3d, 0x12, 0x01, 0xda, 0xeb, 0xea], 0, 24),
             // sell
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &PDF2_PUBKEY, &[0x33, 0xe6, 0x85, 0xa4, 0x01, 0x7f, 0x83, 0xad], 0, 24),
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &PDF2_PUBKEY, &[0x33, 0xe6, 0x85, 0xa4, 0x01, 0x7f, 0x83, 0xad], 0, 24),
             // buyExactQuoteIn
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &PDF2_PUBKEY, &[0xc6, 0x2e, 0x15, 0x52, 0xb4, 0xd9, 0xe8, 0x70], 0, 24),
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &PDF2_PUBKEY, &[0xc6, 0x2e, 0x15, 0x52, 0xb4, 0xd9, 0xe8, 0x70], 0, 24),
         ].concat()
     }
 }
\ No newline at end of file
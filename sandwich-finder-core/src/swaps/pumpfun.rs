@@ -0,0 +1,304 @@
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use yellowstone_grpc_proto::{geyser::SubscribeUpdateTransactionInfo, prelude::{InnerInstruction, InnerInstructions, TransactionStatusMeta}};
+
+use crate::{addresses::{PDF_PUBKEY, WSOL_MINT}, metrics, pool::PoolCreated, swap::{QuoteLimits, SwapFinder, SwapV2}, swaps::{private::Sealed, utils::read_u64}, token_accounts::TokenAccounts, utils::pubkey_from_slice};
+
+impl Sealed for PumpFunSwapFinder {}
+
+pub struct PumpFunSwapFinder {}
+
+pub(super) const BUY_DISCRIMINANT: [u8; 8] = [0x66, 0x06, 0x3d, 0x12, 0x01, 0xda, 0xeb, 0xea];
+pub(super) const SELL_DISCRIMINANT: [u8; 8] = [0x33, 0xe6, 0x85, 0xa4, 0x01, 0x7f, 0x83, 0xad];
+/// create and create_v2, both taking (0=mint, 2=bonding curve) first
+const CREATE_DISCRIMINANTS: [[u8; 8]; 2] = [
+    [0x18, 0x1e, 0xc8, 0x28, 0x05, 0x1c, 0x07, 0x77],
+    [0xd6, 0x90, 0x4c, 0xec, 0x5f, 0x8b, 0x31, 0xb4],
+];
+
+// Includes both the ix and event discrimant
+const LOG_DISCRIMINANT: &[u8] = &[
+    0xe4, 0x45, 0xa5, 0x2e, 0x51, 0xcb, 0x9a, 0x1d,
+    0xbd, 0xdb, 0x7f, 0xd3, 0x4e, 0xe6, 0x61, 0xee,
+];
+
+/// Shortest trade event, the original layout without fee fields
+const MIN_TRADE_EVENT_LEN: usize = 137;
+
+/// Trade event layouts pump.fun has emitted so far, told apart by their length.
+/// Fields are only ever appended so the offsets of older fields stay put.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TradeEventLayout {
+    /// Up to the reserves, sol amount excludes fees
+    V1,
+    /// Adds the fee recipient, fee [177..185], creator and creator fee [225..233]
+    V2,
+    /// Adds volume tracking, up to 266
+    V3,
+    /// Adds the ix name as a length prefixed string at 266
+    V4,
+}
+
+impl TradeEventLayout {
+    fn of(data: &[u8]) -> Option<Self> {
+        match data.len() {
+            137 => Some(Self::V1),
+            233 => Some(Self::V2),
+            266 => Some(Self::V3),
+            len if len >= 270 && read_u32(data, 266).is_some_and(|name_len| 270 + name_len as usize == len) => Some(Self::V4),
+            _ => None,
+        }
+    }
+
+    fn has_fees(&self) -> bool {
+        *self != Self::V1
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4).map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+}
+
+/// Pump.fun have two variants:
+/// 1. buy [0x66, 0x06, 0x3d, 0x12, 0x01, 0xda, 0xeb, 0xea] (3, 6=in sol, 5=out token)
+/// 2. sell [0x33, 0xe6, 0x85, 0xa4, 0x01, 0x7f, 0x83, 0xad] (3, 6=out sol, 5=in token)
+/// In/out amounts follows the discriminant, with the first one being exact and the other being the worst acceptable value.
+/// SOL transfers use the system program instead of token program.
+/// Swap direction is determined instruction's name.
+/// This one requires custom logic for event parsing since it issues so many transfer for all sorts of fees (all in SOL).
+/// mint[16..48], sol amount [48..56], token amount [56..64], is buy [64], user [65..97], fee [177..185], creator fee [225..233]
+/// See [`TradeEventLayout`] for which fields each version of the event has.
+impl PumpFunSwapFinder {
+    fn user_in_out_index(ix_data: &[u8]) -> (usize, usize) {
+        if ix_data[0] == 0x66 {
+            // buy
+            (6, 5)
+        } else {
+            // sell
+            (5, 6)
+        }
+    }
+
+    fn swap_from_pdf_trade_event(outer_program: Option<String>, amm: Pubkey, input_ata: Pubkey, output_ata: Pubkey, data: &[u8], inner_ix_index: Option<u32>) -> SwapV2 {
+        let mint = pubkey_from_slice(&data[16..48]);
+        let sol_amount = u64::from_le_bytes(data[48..56].try_into().unwrap());
+        let token_amount = u64::from_le_bytes(data[56..64].try_into().unwrap());
+        let is_buy = data[64] != 0;
+        let layout = TradeEventLayout::of(data);
+        if layout.is_none() {
+            // newer fields are appended, so the known offsets are still the best guess
+            metrics::incr("pumpfun_unknown_trade_event_layout");
+        }
+        let (fee, creator_fee) = if layout.is_none_or(|l| l.has_fees()) {
+            (read_u64(data, 177).unwrap_or_default(), read_u64(data, 225).unwrap_or_default())
+        } else {
+            (0, 0)
+        };
+        let (input_mint, output_mint) = if is_buy {
+            (WSOL_MINT, mint)
+        } else {
+            (mint, WSOL_MINT)
+        };
+        let (input_amount, output_amount) = if is_buy {
+            (sol_amount + fee + creator_fee, token_amount)
+        } else {
+            (token_amount, sol_amount.saturating_sub(fee + creator_fee))
+        };
+        SwapV2::new(
+            outer_program.map(|s| s.into()),
+            PDF_PUBKEY.to_string().into(),
+            pubkey_from_slice(&data[65..97]).to_string().into(),
+            amm.to_string().into(),
+            input_mint.to_string().into(),
+            output_mint.to_string().into(),
+            input_amount,
+            output_amount,
+            input_ata.to_string().into(),
+            output_ata.to_string().into(),
+            // todo: should try to locate the actual ix
+            None,
+            None,
+            0,
+            0,
+            0,
+            inner_ix_index,
+            0,
+        )
+    }
+
+    fn is_create(data: &[u8]) -> bool {
+        CREATE_DISCRIMINANTS.iter().any(|d| data.starts_with(d))
+    }
+
+    /// Bonding curves created in this tx along with a buy on them, which makes the create tx the first buy's.
+    /// `swaps` are the ones found in the same tx. Creates invoked through another program are included.
+    pub fn find_pool_creations_in_tx(slot: u64, raw_tx: &SubscribeUpdateTransactionInfo, ixs: &[Instruction], account_keys: &[Pubkey], swaps: &[SwapV2]) -> Vec<PoolCreated> {
+        let (pdf, wsol) = (PDF_PUBKEY.to_string(), WSOL_MINT.to_string());
+        let bought = |amm: &Pubkey| swaps.iter().any(|s| s.program().as_ref() == pdf && s.input_mint().as_ref() == wsol && s.amm().as_ref() == amm.to_string());
+        let creation = |mint: Pubkey, amm: Pubkey, ix_index: usize, inner_ix_index: Option<u32>| {
+            bought(&amm).then(|| PoolCreated::new(pdf.clone().into(), amm.to_string().into(), mint.to_string().into(), slot, raw_tx.index as u32, ix_index as u32, inner_ix_index))
+        };
+        let mut creations = vec![];
+        for (i, ix) in ixs.iter().enumerate() {
+            if ix.program_id == PDF_PUBKEY && Self::is_create(&ix.data) && ix.accounts.len() > 2 {
+                creations.extend(creation(ix.accounts[0].pubkey, ix.accounts[2].pubkey, i, None));
+            }
+        }
+        let Some(meta) = &raw_tx.meta else {
+            return creations;
+        };
+        for inner_ixs in meta.inner_instructions.iter() {
+            for (j, inner_ix) in inner_ixs.instructions.iter().enumerate() {
+                if account_keys.get(inner_ix.program_id_index as usize) != Some(&PDF_PUBKEY) || !Self::is_create(&inner_ix.data) || inner_ix.accounts.len() <= 2 {
+                    continue;
+                }
+                let (Some(mint), Some(amm)) = (account_keys.get(inner_ix.accounts[0] as usize), account_keys.get(inner_ix.accounts[2] as usize)) else {
+                    continue;
+                };
+                creations.extend(creation(*mint, *amm, inner_ixs.index as usize, Some(j as u32)));
+            }
+        }
+        creations
+    }
+}
+
+impl SwapFinder for PumpFunSwapFinder {
+    fn amm_ix(ix: &Instruction) -> Pubkey {
+        ix.accounts[3].pubkey
+    }
+
+    fn amm_inner_ix(inner_ix: &InnerInstruction, account_keys: &Vec<Pubkey>) -> Pubkey {
+        account_keys[inner_ix.accounts[3] as usize]
+    }
+
+    fn user_ata_ix(ix: &Instruction) -> (Pubkey, Pubkey) {
+        let (in_index, out_index) = Self::user_in_out_index(&ix.data);
+        (
+            ix.accounts[in_index].pubkey,
+            ix.accounts[out_index].pubkey,
+        )
+    }
+
+    fn user_ata_inner_ix(inner_ix: &InnerInstruction, account_keys: &Vec<Pubkey>) -> (Pubkey, Pubkey) {
+        let (in_index, out_index) = Self::user_in_out_index(&inner_ix.data);
+        (
+            account_keys[inner_ix.accounts[in_index] as usize],
+            account_keys[inner_ix.accounts[out_index] as usize],
+        )
+    }
+
+    fn quote_limits(ix_data: &[u8]) -> QuoteLimits {
+        if ix_data.starts_with(&BUY_DISCRIMINANT) {
+            QuoteLimits::exact_out(read_u64(ix_data, 16)) // buy(amount, max_sol_cost)
+        } else if ix_data.starts_with(&SELL_DISCRIMINANT) {
+            QuoteLimits::exact_in(read_u64(ix_data, 16)) // sell(amount, min_sol_output)
+        } else {
+            QuoteLimits::default()
+        }
+    }
+
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta, _token_accounts: &TokenAccounts) -> Vec<SwapV2> {
+        if ix.program_id == PDF_PUBKEY {
+            for inner_ix in inner_ixs.instructions.iter() {
+                if inner_ix.data.len() >= MIN_TRADE_EVENT_LEN && inner_ix.data[0..16] == LOG_DISCRIMINANT[..] {
+                    let is_buy = inner_ix.data[64] != 0;
+                    let (in_index, out_index) = if is_buy {
+                        (6, 5) // in sol, out token
+                    } else {
+                        (5, 6) // in token, out sol
+                    };
+                    let mut swap = Self::swap_from_pdf_trade_event(
+                        None,
+                        ix.accounts[3].pubkey,
+                        ix.accounts[in_index].pubkey,
+                        ix.accounts[out_index].pubkey,
+                        &inner_ix.data,
+                        None,
+                    );
+                    swap.set_quote_limits(Self::quote_limits(&ix.data));
+                    return vec![swap];
+                }
+            } 
+        }
+        let mut swaps = vec![];
+        let mut next_logical_ix = 0;
+        for (i, inner_ix) in inner_ixs.instructions.iter().enumerate() {
+            if inner_ix.program_id_index >= account_keys.len() as u32 || i < next_logical_ix {
+                continue; // Skip already processed instructions or invalid program ID
+            }
+            if account_keys[inner_ix.program_id_index as usize] != PDF_PUBKEY {
+                continue; // Not a Pump.fun instruction
+            }
+            if inner_ix.data.len() < 24 {
+                continue; // Not a swap
+            }
+            if inner_ix.data.starts_with(&BUY_DISCRIMINANT) ||
+               inner_ix.data.starts_with(&SELL_DISCRIMINANT) {
+                // Valid swap instruction
+                let (input_ata, output_ata) = Self::user_ata_inner_ix(inner_ix, account_keys);
+                for j in i + 1..inner_ixs.instructions.len() {
+                    let next_inner_ix = &inner_ixs.instructions[j];
+                    if next_inner_ix.program_id_index >= account_keys.len() as u32 {
+                        continue; // Skip invalid program ID
+                    }
+                    if account_keys[next_inner_ix.program_id_index as usize] != PDF_PUBKEY {
+                        continue; // Not a Pump.fun instruction
+                    }
+                    if next_inner_ix.data.len() < MIN_TRADE_EVENT_LEN || next_inner_ix.data[0..16] != LOG_DISCRIMINANT[..] {
+                        continue; // Not an event
+                    }
+                    let mut swap = Self::swap_from_pdf_trade_event(
+                        Some(ix.program_id.to_string()),
+                        Self::amm_inner_ix(inner_ix, account_keys),
+                        input_ata,
+                        output_ata,
+                        &next_inner_ix.data,
+                        Some(i as u32),
+                    );
+                    swap.set_quote_limits(Self::quote_limits(&inner_ix.data));
+                    swaps.push(swap);
+                    next_logical_ix = j + 1;
+                }
+            }
+        }
+        swaps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::instruction::AccountMeta;
+
+    use super::*;
+
+    #[test]
+    fn test_trade_event_layout() {
+        let mut data = vec![0; 266];
+        assert_eq!(TradeEventLayout::of(&data[..137]), Some(TradeEventLayout::V1));
+        assert_eq!(TradeEventLayout::of(&data[..233]), Some(TradeEventLayout::V2));
+        assert_eq!(TradeEventLayout::of(&data), Some(TradeEventLayout::V3));
+        data.extend(3u32.to_le_bytes());
+        data.extend(b"buy");
+        assert_eq!(TradeEventLayout::of(&data), Some(TradeEventLayout::V4));
+        data.push(0);
+        assert_eq!(TradeEventLayout::of(&data), None);
+        assert_eq!(TradeEventLayout::of(&data[..200]), None);
+    }
+
+    #[test]
+    fn test_find_pool_creations() {
+        let (mint, curve, other_curve, user) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let ixs = [Instruction::new_with_bytes(PDF_PUBKEY, &CREATE_DISCRIMINANTS[0], vec![
+            AccountMeta::new(mint, true),
+            AccountMeta::new_readonly(Pubkey::new_unique(), false),
+            AccountMeta::new(curve, false),
+        ])];
+        let raw_tx = SubscribeUpdateTransactionInfo { index: 7, ..Default::default() };
+        let buy = |amm: Pubkey| SwapV2::new(None, PDF_PUBKEY.to_string().into(), user.to_string().into(), amm.to_string().into(), WSOL_MINT.to_string().into(), mint.to_string().into(), 100, 1000, "in".into(), "out".into(), None, None, 1, 7, 1, None, 0);
+        let creations = PumpFunSwapFinder::find_pool_creations_in_tx(1, &raw_tx, &ixs, &[], &[buy(curve)]);
+        assert_eq!(creations.len(), 1);
+        assert_eq!((creations[0].amm().to_string(), creations[0].mint().to_string()), (curve.to_string(), mint.to_string()));
+        assert_eq!((*creations[0].inclusion_order(), *creations[0].ix_index()), (7, 0));
+        // created without buying
+        assert!(PumpFunSwapFinder::find_pool_creations_in_tx(1, &raw_tx, &ixs, &[], &[buy(other_curve)]).is_empty());
+    }
+}
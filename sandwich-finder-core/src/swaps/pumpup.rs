@@ -3,7 +3,7 @@ use std::sync::Arc;
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 use yellowstone_grpc_proto::prelude::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
 
-use crate::{events::{addresses::PUMPUP_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::private::Sealed}, utils::pubkey_from_slice};
+use crate::{addresses::PUMPUP_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::private::Sealed, token_accounts::TokenAccounts, utils::pubkey_from_slice};
 
 impl Sealed for PumpupSwapFinder {}
 
@@ -83,7 +83,7 @@ impl SwapFinder for PumpupSwapFinder {
         )
     }
 
-    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta) -> Vec<SwapV2> {
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta, _token_accounts: &TokenAccounts) -> Vec<SwapV2> {
         if ix.program_id == PUMPUP_PUBKEY {
             for inner_ix in inner_ixs.instructions.iter() {
                 if inner_ix.data.len() >= 193 && inner_ix.data[0..16] == LOG_DISCRIMINANT[..] {
@@ -0,0 +1,242 @@
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use yellowstone_grpc_proto::prelude::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
+
+use crate::{addresses::{RAYDIUM_CL_PUBKEY, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID}, swap::{QuoteLimits, SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt, utils::{read_u64, token_transferred_inner}}, token_accounts::TokenAccounts};
+
+const SWAP_ROUTER_BASE_IN: [u8; 8] = [0x45, 0x7d, 0x73, 0xda, 0xf5, 0xba, 0xf2, 0xc4];
+// discriminant, amount in, amount out minimum
+const SWAP_ROUTER_BASE_IN_DATA_LENGTH: usize = 24;
+// payer, input token account, input mint, token program, token 2022 program, memo program
+const ROUTER_FIXED_ACCOUNTS: usize = 6;
+
+impl Sealed for RaydiumCLSwapFinder {}
+
+pub struct RaydiumCLSwapFinder {}
+
+/// A swapRouterBaseIn ix, top level or inner, with its accounts resolved
+struct RouterIx<'a> {
+    accounts: Vec<Pubkey>,
+    data: &'a [u8],
+    outer_program: Option<Pubkey>,
+    inner_ix_index: Option<u32>,
+    stack_height: Option<u32>,
+    // where the inner ixs it invoked start
+    start: usize,
+}
+
+/// swapRouterBaseIn goes through several pools in one ix. After the fixed accounts, each hop has
+/// [amm config, pool, output token account, input vault, output vault, output mint, observation, tick arrays...],
+/// and since the number of tick arrays varies, hops are told apart by their transfers instead of by position:
+/// each transfer into an input vault is paired with the next one out of the vault after it, the pool being 2 accounts before.
+impl RaydiumCLSwapFinder {
+    fn find_router_swaps(router: RouterIx, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
+        let accounts = &router.accounts;
+        // (inner ix index, from, to, authority, mint, amount), only counting transfers the router made itself
+        let mut transfers = vec![];
+        for (j, inner_ix) in inner_ixs.instructions.iter().enumerate().skip(router.start) {
+            if let (Some(h), Some(inner_h)) = (router.stack_height, inner_ix.stack_height) {
+                if inner_h <= h {
+                    break;
+                }
+                if inner_h != h + 1 {
+                    continue;
+                }
+            }
+            if !matches!(account_keys.get(inner_ix.program_id_index as usize), Some(&TOKEN_PROGRAM_ID | &TOKEN_2022_PROGRAM_ID)) {
+                continue;
+            }
+            if let Some((from, to, auth, mint, amount)) = token_transferred_inner(inner_ix, account_keys, token_accounts) {
+                transfers.push((j as u32, from, to, auth, mint, amount));
+            }
+        }
+        let mut swaps = vec![];
+        let mut k = 0;
+        while k < transfers.len() {
+            let (input_index, input_ata, input_vault, authority, input_mint, input_amount) = &transfers[k];
+            k += 1;
+            let Some(p) = accounts.iter().skip(ROUTER_FIXED_ACCOUNTS + 3).position(|a| a == input_vault).map(|p| p + ROUTER_FIXED_ACCOUNTS + 3) else {
+                continue;
+            };
+            let (pool, output_ata, output_vault) = (accounts[p - 2], accounts[p - 1], accounts.get(p + 1).copied().unwrap_or_default());
+            let output = transfers[k..].iter().position(|t| t.1 == output_vault && t.2 == output_ata).map(|o| k + o);
+            let (output_mint, output_amount, output_index) = match output {
+                Some(o) => {
+                    k = o + 1;
+                    (transfers[o].4.clone(), transfers[o].5, Some(transfers[o].0))
+                },
+                // Sometimes the output tx may not exist due to tiny input that rounds the output to 0.
+                None => (String::new(), 0, None),
+            };
+            swaps.push(SwapV2::new(
+                router.outer_program.map(|p| p.to_string().into()),
+                RAYDIUM_CL_PUBKEY.to_string().into(),
+                authority.to_string().into(),
+                pool.to_string().into(),
+                input_mint.clone().into(),
+                output_mint.into(),
+                *input_amount,
+                output_amount,
+                input_ata.to_string().into(),
+                output_ata.to_string().into(),
+                Some(*input_index),
+                output_index,
+                0,
+                0,
+                0,
+                router.inner_ix_index,
+                0,
+            ));
+        }
+        // the route's minimum out only applies to the last hop
+        if let Some(last) = swaps.last_mut() {
+            last.set_quote_limits(QuoteLimits::exact_in(read_u64(router.data, 16)));
+        }
+        swaps
+    }
+
+    fn find_router_swaps_in_ix(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
+        let is_router = |data: &[u8]| data.len() >= SWAP_ROUTER_BASE_IN_DATA_LENGTH && data.starts_with(&SWAP_ROUTER_BASE_IN);
+        if ix.program_id == RAYDIUM_CL_PUBKEY {
+            if !is_router(&ix.data) {
+                return vec![];
+            }
+            let router = RouterIx {
+                accounts: ix.accounts.iter().map(|a| a.pubkey).collect(),
+                data: &ix.data,
+                outer_program: None,
+                inner_ix_index: None,
+                stack_height: Some(1),
+                start: 0,
+            };
+            return Self::find_router_swaps(router, inner_ixs, account_keys, token_accounts);
+        }
+        let mut swaps = vec![];
+        for (i, inner_ix) in inner_ixs.instructions.iter().enumerate() {
+            if account_keys.get(inner_ix.program_id_index as usize) != Some(&RAYDIUM_CL_PUBKEY) || !is_router(&inner_ix.data) {
+                continue;
+            }
+            let router = RouterIx {
+                accounts: inner_ix.accounts.iter().filter_map(|&a| account_keys.get(a as usize).copied()).collect(),
+                data: &inner_ix.data,
+                outer_program: Some(ix.program_id),
+                inner_ix_index: Some(i as u32),
+                stack_height: inner_ix.stack_height,
+                start: i + 1,
+            };
+            swaps.extend(Self::find_router_swaps(router, inner_ixs, account_keys, token_accounts));
+        }
+        swaps
+    }
+}
+
+/// Ray concentrated liquidity has 2 variants:
+/// 1. swap [0xf8, 0xc6, 0x9e, 0x91, 0xe1, 0x75, 0x87, 0xc8]
+/// 2. swapV2 [0x2b, 0x04, 0xed, 0x0b, 0x1a, 0xc9, 0x1e, 0x62] 
+///
+/// Multi-hop swapRouterBaseIn ixs are handled separately, see [`RaydiumCLSwapFinder::find_router_swaps`]
+impl SwapFinder for RaydiumCLSwapFinder {
+    fn amm_ix(ix: &Instruction) -> Pubkey {
+        ix.accounts[2].pubkey
+    }
+
+    fn amm_inner_ix(inner_ix: &InnerInstruction, account_keys: &Vec<Pubkey>) -> Pubkey {
+        account_keys[inner_ix.accounts[2] as usize]
+    }
+
+    fn user_ata_ix(ix: &Instruction) -> (Pubkey, Pubkey) {
+        (
+            ix.accounts[3].pubkey,
+            ix.accounts[4].pubkey,
+        )
+    }
+
+    fn user_ata_inner_ix(inner_ix: &InnerInstruction, account_keys: &Vec<Pubkey>) -> (Pubkey, Pubkey) {
+        (
+            account_keys[inner_ix.accounts[3] as usize],
+            account_keys[inner_ix.accounts[4] as usize],
+        )
+    }
+    
+    fn pool_ata_ix(ix: &Instruction) -> (Pubkey, Pubkey) {
+        (
+            ix.accounts[6].pubkey,
+            ix.accounts[5].pubkey,
+        )
+    }
+    
+    fn pool_ata_inner_ix(inner_ix: &InnerInstruction, account_keys: &Vec<Pubkey>) -> (Pubkey, Pubkey) {
+        (
+            account_keys[inner_ix.accounts[6] as usize],
+            account_keys[inner_ix.accounts[5] as usize],
+        )
+    }
+
+    /// Both variants are (amount, other_amount_threshold, sqrt_price_limit_x64, is_base_input)
+    fn quote_limits(ix_data: &[u8]) -> QuoteLimits {
+        if ix_data[40] != 0 {
+            QuoteLimits::exact_in(read_u64(ix_data, 16))
+        } else {
+            QuoteLimits::exact_out(read_u64(ix_data, 16))
+        }
+    }
+
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
+        [
+            // swap
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &RAYDIUM_CL_PUBKEY, &[0xf8, 0xc6, 0x9e, 0x91, 0xe1, 0x75, 0x87, 0xc8], 0, 41),
+            // swap_v2
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &RAYDIUM_CL_PUBKEY, &[0x2b, 0x04, 0xed, 0x0b, 0x1a, 0xc9, 0x1e, 0x62], 0, 41),
+            // swap_router_base_in
+            Self::find_router_swaps_in_ix(ix, inner_ixs, account_keys, token_accounts),
+        ].concat()
+    }
+}
+#[cfg(test)]
+mod tests {
+    use sha2::{Digest as _, Sha256};
+    use solana_sdk::instruction::AccountMeta;
+
+    use super::*;
+
+    #[test]
+    fn test_router_discriminant() {
+        assert_eq!(Sha256::digest("global:swap_router_base_in")[..8], SWAP_ROUTER_BASE_IN);
+    }
+
+    #[test]
+    fn test_router_swaps() {
+        // payer, user A, mint A, token, token-2022, memo, then the hops:
+        // config, pool 1, user B, vault 1A, vault 1B, mint B, observation 1, 2 tick arrays
+        // config, pool 2, user C, vault 2B, vault 2C, mint C, observation 2, 1 tick array
+        let mut keys: Vec<Pubkey> = (0..23).map(|_| Pubkey::new_unique()).collect();
+        keys[3] = TOKEN_PROGRAM_ID;
+        keys[4] = TOKEN_2022_PROGRAM_ID;
+        keys[22] = RAYDIUM_CL_PUBKEY;
+        let transfer_checked = |from: u8, mint: u8, to: u8, amount: u64| InnerInstruction {
+            program_id_index: 3,
+            accounts: vec![from, mint, to, 0],
+            data: [vec![12], amount.to_le_bytes().to_vec(), vec![6]].concat(),
+            stack_height: Some(2),
+        };
+        let inner_ixs = InnerInstructions {
+            index: 0,
+            instructions: vec![
+                transfer_checked(1, 2, 9, 1000),
+                transfer_checked(10, 11, 8, 500),
+                transfer_checked(8, 11, 18, 500),
+                transfer_checked(19, 20, 17, 250),
+            ],
+        };
+        let data = [SWAP_ROUTER_BASE_IN.to_vec(), 1000u64.to_le_bytes().to_vec(), 240u64.to_le_bytes().to_vec()].concat();
+        let ix = Instruction { program_id: RAYDIUM_CL_PUBKEY, accounts: keys[..22].iter().map(|&k| AccountMeta::new(k, false)).collect(), data };
+        let swaps = RaydiumCLSwapFinder::find_swaps(&ix, &inner_ixs, &keys, &TransactionStatusMeta::default(), &TokenAccounts::default());
+        assert_eq!(swaps.len(), 2);
+        assert_eq!((swaps[0].amm().as_ref(), swaps[1].amm().as_ref()), (keys[7].to_string().as_str(), keys[16].to_string().as_str()));
+        assert_eq!((*swaps[0].input_amount(), *swaps[0].output_amount(), *swaps[1].input_amount(), *swaps[1].output_amount()), (1000, 500, 500, 250));
+        assert_eq!((swaps[0].output_mint(), swaps[1].input_mint()), (swaps[1].input_mint(), swaps[0].output_mint()));
+        assert_eq!((*swaps[1].input_inner_ix_index(), *swaps[1].output_inner_ix_index()), (Some(2), Some(3)));
+        assert_eq!(swaps[1].input_ata().as_ref(), keys[8].to_string());
+        assert_eq!(swaps[0].authority().as_ref(), keys[0].to_string());
+        assert_eq!((*swaps[0].quote_limits(), *swaps[1].quote_limits()), (QuoteLimits::default(), QuoteLimits::exact_in(Some(240))));
+    }
+}
@@ -1,18 +1,23 @@
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 use yellowstone_grpc_proto::prelude::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
 
-use crate::events::{addresses::RAYDIUM_LP_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}};
+use crate::{addresses::RAYDIUM_LP_PUBKEY, swap::{QuoteLimits, SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt, utils::read_u64}, token_accounts::TokenAccounts};
 
 impl Sealed for RaydiumLPSwapFinder {}
 
 pub struct RaydiumLPSwapFinder {}
 
-/// Ray Launchpad swaps have two variants:
+const BUY_EXACT_IN: [u8; 8] = [0xfa, 0xea, 0x0d, 0x7b, 0xd5, 0x9c, 0x13, 0xec];
+const SELL_EXACT_IN: [u8; 8] = [0x95, 0x27, 0xde, 0x9b, 0xd3, 0x7c, 0x98, 0x1a];
+const BUY_EXACT_OUT: [u8; 8] = [0x18, 0xd3, 0x74, 0x28, 0x69, 0x03, 0x99, 0x38];
+const SELL_EXACT_OUT: [u8; 8] = [0x5f, 0xc8, 0x47, 0x22, 0x08, 0x09, 0x0b, 0xa6];
+
+/// Ray Launchpad swaps have four variants:
 /// 1. buy_exact_in [0xfa, 0xea, 0x0d, 0x7b, 0xd5, 0x9c, 0x13, 0xec] (4, 5=base, 6=quote)
 /// 2. sell_exact_in [0x95, 0x27, 0xde, 0x9b, 0xd3, 0x7c, 0x98, 0x1a] (4, 5=base, 6=quote)
 /// 3. buy_exact_out [0x18, 0xd3, 0x74, 0x28, 0x69, 0x03, 0x99, 0x38] (4, 5=base, 6=quote)
 /// 4. sell_exact_out [0x5f, 0xc8, 0x47, 0x22, 0x8, 0x9, 0xb, 0xa6] (4, 5=base, 6=quote)
-/// In/out amounts follows the discriminant, with one being exact and the other being the worst acceptable value.
+/// The exact amount follows the discriminant, then the worst acceptable value of the other side.
 /// share_fee_rate follows the above but we don't care.
 /// Swap direction is determined by the instruction's name.
 /// Buy = quote->base, sell = base->quote.
@@ -81,16 +86,38 @@ impl SwapFinder for RaydiumLPSwapFinder {
         )
     }
 
-    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, meta: &TransactionStatusMeta) -> Vec<SwapV2> {
+    fn quote_limits(ix_data: &[u8]) -> QuoteLimits {
+        if ix_data.starts_with(&BUY_EXACT_OUT) || ix_data.starts_with(&SELL_EXACT_OUT) {
+            QuoteLimits::exact_out(read_u64(ix_data, 16)) // (amount_out, maximum_amount_in, share_fee_rate)
+        } else {
+            QuoteLimits::exact_in(read_u64(ix_data, 16)) // (amount_in, minimum_amount_out, share_fee_rate)
+        }
+    }
+
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
         [
             // buy_exact_in
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &RAYDIUM_LP_PUBKEY, &[0xfa, 0xea, 0x0d, 0x7b, 0xd5, 0x9c, 0x13, 0xec], 0, 32),
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &RAYDIUM_LP_PUBKEY, &BUY_EXACT_IN, 0, 32),
             // sell_exact_in
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &RAYDIUM_LP_PUBKEY, &[0x95, 0x27, 0xde, 0x9b, 0xd3, 0x7c, 0x98, 0x1a], 0, 32),
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &RAYDIUM_LP_PUBKEY, &SELL_EXACT_IN, 0, 32),
             // buy_exact_out
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &RAYDIUM_LP_PUBKEY, &[0x18, 0xd3, 0x74, 0x28, 0x69, 0x03, 0x99, 0x38], 0, 32),
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &RAYDIUM_LP_PUBKEY, &BUY_EXACT_OUT, 0, 32),
             // sell_exact_out
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &RAYDIUM_LP_PUBKEY, &[0x5f, 0xc8, 0x47, 0x22, 0x08, 0x09, 0x0b, 0xa6], 0, 32),
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &RAYDIUM_LP_PUBKEY, &SELL_EXACT_OUT, 0, 32),
         ].concat()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use sha2::{Digest as _, Sha256};
+
+    use super::*;
+
+    #[test]
+    fn test_discriminants() {
+        for (name, discriminant) in [("buy_exact_in", BUY_EXACT_IN), ("sell_exact_in", SELL_EXACT_IN), ("buy_exact_out", BUY_EXACT_OUT), ("sell_exact_out", SELL_EXACT_OUT)] {
+            assert_eq!(Sha256::digest(format!("global:{name}"))[..8], discriminant, "{name}");
+        }
+    }
+}
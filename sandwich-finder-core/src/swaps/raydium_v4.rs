@@ -1,7 +1,7 @@
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 use yellowstone_grpc_proto::prelude::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
 
-use crate::events::{addresses::RAYDIUM_V4_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}};
+use crate::{addresses::RAYDIUM_V4_PUBKEY, swap::{QuoteLimits, SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt, utils::read_u64}, token_accounts::TokenAccounts};
 
 impl Sealed for RaydiumV4SwapFinder {}
 
@@ -33,11 +33,18 @@ impl SwapFinder for RaydiumV4SwapFinder {
         )
     }
 
-    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, meta: &TransactionStatusMeta) -> Vec<SwapV2> {
+    fn quote_limits(ix_data: &[u8]) -> QuoteLimits {
+        match ix_data[0] {
+            0x0b => QuoteLimits::exact_out(read_u64(ix_data, 1)), // swap_base_out
+            _ => QuoteLimits::exact_in(read_u64(ix_data, 9)), // swap_base_in(_v2)
+        }
+    }
+
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
         [
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &RAYDIUM_V4_PUBKEY, &[0x09], 0, 17),
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &RAYDIUM_V4_PUBKEY, &[0x0b], 0, 17),
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &RAYDIUM_V4_PUBKEY, &[0x10], 0, 17),
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &RAYDIUM_V4_PUBKEY, &[0x09], 0, 17),
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &RAYDIUM_V4_PUBKEY, &[0x0b], 0, 17),
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &RAYDIUM_V4_PUBKEY, &[0x10], 0, 17),
         ].concat()
     }
 }
\ No newline at end of file
@@ -1,7 +1,7 @@
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 use yellowstone_grpc_proto::prelude::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
 
-use crate::events::{addresses::RAYDIUM_V5_PUBKEY, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt}};
+use crate::{addresses::RAYDIUM_V5_PUBKEY, swap::{QuoteLimits, SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt, utils::read_u64}, token_accounts::TokenAccounts};
 
 impl Sealed for RaydiumV5SwapFinder {}
 
@@ -50,12 +50,20 @@ impl SwapFinder for RaydiumV5SwapFinder {
         )
     }
 
-    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, meta: &TransactionStatusMeta) -> Vec<SwapV2> {
+    fn quote_limits(ix_data: &[u8]) -> QuoteLimits {
+        if ix_data.starts_with(&[0x37, 0xd9, 0x62, 0x56, 0xa3, 0x4a, 0xb4, 0xad]) {
+            QuoteLimits::exact_out(read_u64(ix_data, 8)) // swap_base_output(max_amount_in, amount_out)
+        } else {
+            QuoteLimits::exact_in(read_u64(ix_data, 16)) // swap_base_input(amount_in, minimum_amount_out)
+        }
+    }
+
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
         [
             // swap_base_input
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &RAYDIUM_V5_PUBKEY, &[0x8f, 0xbe, 0x5a, 0xda, 0xc4, 0x1e, 0x33, 0xde], 0, 24),
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &RAYDIUM_V5_PUBKEY, &[0x8f, 0xbe, 0x5a, 0xda, 0xc4, 0x1e, 0x33, 0xde], 0, 24),
             // swap_base_output
-            Self::find_swaps_generic(ix, inner_ixs, account_keys, meta, &RAYDIUM_V5_PUBKEY, &[0x37, 0xd9, 0x62, 0x56, 0xa3, 0x4a, 0xb4, 0xad], 0, 24),
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &RAYDIUM_V5_PUBKEY, &[0x37, 0xd9, 0x62, 0x56, 0xa3, 0x4a, 0xb4, 0xad], 0, 24),
         ].concat()
     }
 }
\ No newline at end of file
@@ -0,0 +1,142 @@
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use yellowstone_grpc_proto::geyser::SubscribeUpdateTransactionInfo;
+
+use crate::{addresses::{ALPHA_PUBKEY, APESU_PUBKEY, AQUA_PUBKEY, CLEARPOOL_PUBKEY, DOOAR_PUBKEY, FLUXBEAM_PUBKEY, FUSIONAMM_PUBKEY, GOONFI_PUBKEY, HUMIDIFI_PUBKEY, JUP_ORDER_ENGINE_PUBKEY, JUP_PERPS_PUBKEY, LIFINITY_V2_PUBKEY, LIMO_PUBKEY, METEORA_DAMMV2_PUBKEY, METEORA_DBC_PUBKEY, METEORA_DLMM_PUBKEY, METEORA_PUBKEY, ONEDEX_PUBKEY, OPENBOOK_V2_PUBKEY, PANCAKE_SWAP_PUBKEY, PDF2_PUBKEY, PDF_PUBKEY, PUMPUP_PUBKEY, RAYDIUM_CL_PUBKEY, RAYDIUM_LP_PUBKEY, RAYDIUM_V4_PUBKEY, RAYDIUM_V5_PUBKEY, SAROS_DLMM_PUBKEY, SOLFI_PUBKEY, STABBLE_WEIGHTED_PUBKEY, SUGAR_PUBKEY, SV2E_PUBKEY, TESS_V_PUBKEY, WHIRLPOOL_PUBKEY, ZEROFI_PUBKEY}, swap::SwapV2, swaps::{alpha::AlphaSwapFinder, apesu::ApesuSwapFinder, aqua::AquaSwapFinder, clearpool::ClearpoolSwapFinder, dooar::DooarSwapFinder, fluxbeam::FluxbeamSwapFinder, fusionamm::FusionAmmSwapFinder, goonfi::GoonFiSwapFinder, humidifi::HumidiFiSwapFinder, jup_order_engine::JupOrderEngineSwapFinder, jup_perps::JupPerpsSwapFinder, lifinity_v2::LifinityV2SwapFinder, limo::LimoSwapFinder, meteora::MeteoraSwapFinder, meteora_damm_v2::MeteoraDammV2Finder, meteora_dbc::MeteoraDBCSwapFinder, meteora_dlmm::MeteoraDLMMSwapFinder, onedex::OneDexSwapFinder, openbook_v2::OpenbookV2SwapFinder, pancake_swap::PancakeSwapSwapFinder, pumpamm::PumpAmmSwapFinder, pumpfun::PumpFunSwapFinder, pumpup::PumpupSwapFinder, raydium_cl::RaydiumCLSwapFinder, raydium_lp::RaydiumLPSwapFinder, raydium_v4::RaydiumV4SwapFinder, raydium_v5::RaydiumV5SwapFinder, saros_dlmm::SarosDLMMSwapFinder, solfi::SolFiSwapFinder, stabble_weighted::StabbleWeightedSwapFinder, sugar::SugarSwapFinder, sv2e::Sv2eSwapFinder, swap_finder_ext::SwapFinderExt, tessv::TessVSwapFinder, whirlpool::{WhirlpoolSwapFinder, WhirlpoolTwoHopSwapFinder1, WhirlpoolTwoHopSwapFinder2, WhirlpoolTwoHopSwapV2Finder1, WhirlpoolTwoHopSwapV2Finder2}, zerofi::ZeroFiSwapFinder}, token_accounts::TokenAccounts};
+
+/// Same signature as [`SwapFinderExt::find_swaps_in_tx`], so finders outside this crate can be registered as plain functions.
+pub type FindSwapsInTx = fn(u64, &SubscribeUpdateTransactionInfo, &Vec<Instruction>, &Vec<Pubkey>, &TokenAccounts) -> Vec<SwapV2>;
+
+/// The swap finders to run over each transaction, each registered under the program it decodes.
+/// [`FinderRegistry::default`] has every finder in this crate, [`FinderRegistry::new`] none of them.
+#[derive(Clone)]
+pub struct FinderRegistry {
+    finders: Vec<(Pubkey, FindSwapsInTx)>,
+}
+
+impl Default for FinderRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl FinderRegistry {
+    pub fn new() -> Self {
+        Self { finders: vec![] }
+    }
+
+    /// Every finder in this crate, in the order their swaps are returned in
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        registry
+            .register_finder::<RaydiumV4SwapFinder>(RAYDIUM_V4_PUBKEY)
+            .register_finder::<RaydiumV5SwapFinder>(RAYDIUM_V5_PUBKEY)
+            .register_finder::<RaydiumLPSwapFinder>(RAYDIUM_LP_PUBKEY)
+            .register_finder::<RaydiumCLSwapFinder>(RAYDIUM_CL_PUBKEY)
+            .register_finder::<PumpFunSwapFinder>(PDF_PUBKEY)
+            .register_finder::<PumpAmmSwapFinder>(PDF2_PUBKEY)
+            .register_finder::<WhirlpoolSwapFinder>(WHIRLPOOL_PUBKEY)
+            .register_finder::<WhirlpoolTwoHopSwapFinder1>(WHIRLPOOL_PUBKEY)
+            .register_finder::<WhirlpoolTwoHopSwapFinder2>(WHIRLPOOL_PUBKEY)
+            .register_finder::<WhirlpoolTwoHopSwapV2Finder1>(WHIRLPOOL_PUBKEY)
+            .register_finder::<WhirlpoolTwoHopSwapV2Finder2>(WHIRLPOOL_PUBKEY)
+            .register_finder::<MeteoraDLMMSwapFinder>(METEORA_DLMM_PUBKEY)
+            .register_finder::<MeteoraSwapFinder>(METEORA_PUBKEY)
+            .register_finder::<MeteoraDBCSwapFinder>(METEORA_DBC_PUBKEY)
+            .register_finder::<MeteoraDammV2Finder>(METEORA_DAMMV2_PUBKEY)
+            .register_finder::<OpenbookV2SwapFinder>(OPENBOOK_V2_PUBKEY)
+            .register_finder::<ZeroFiSwapFinder>(ZEROFI_PUBKEY)
+            .register_finder::<JupOrderEngineSwapFinder>(JUP_ORDER_ENGINE_PUBKEY)
+            .register_finder::<PancakeSwapSwapFinder>(PANCAKE_SWAP_PUBKEY)
+            .register_finder::<FluxbeamSwapFinder>(FLUXBEAM_PUBKEY)
+            .register_finder::<HumidiFiSwapFinder>(HUMIDIFI_PUBKEY)
+            .register_finder::<SarosDLMMSwapFinder>(SAROS_DLMM_PUBKEY)
+            .register_finder::<SolFiSwapFinder>(SOLFI_PUBKEY)
+            .register_finder::<GoonFiSwapFinder>(GOONFI_PUBKEY)
+            .register_finder::<SugarSwapFinder>(SUGAR_PUBKEY)
+            .register_finder::<TessVSwapFinder>(TESS_V_PUBKEY)
+            .register_finder::<Sv2eSwapFinder>(SV2E_PUBKEY)
+            .register_finder::<LifinityV2SwapFinder>(LIFINITY_V2_PUBKEY)
+            .register_finder::<ApesuSwapFinder>(APESU_PUBKEY)
+            .register_finder::<OneDexSwapFinder>(ONEDEX_PUBKEY)
+            .register_finder::<AquaSwapFinder>(AQUA_PUBKEY)
+            .register_finder::<StabbleWeightedSwapFinder>(STABBLE_WEIGHTED_PUBKEY)
+            .register_finder::<JupPerpsSwapFinder>(JUP_PERPS_PUBKEY)
+            .register_finder::<DooarSwapFinder>(DOOAR_PUBKEY)
+            .register_finder::<PumpupSwapFinder>(PUMPUP_PUBKEY)
+            .register_finder::<ClearpoolSwapFinder>(CLEARPOOL_PUBKEY)
+            .register_finder::<FusionAmmSwapFinder>(FUSIONAMM_PUBKEY)
+            .register_finder::<AlphaSwapFinder>(ALPHA_PUBKEY)
+            .register_finder::<LimoSwapFinder>(LIMO_PUBKEY);
+        registry
+    }
+
+    /// Adds a finder for `program`, after the ones already registered. A program can have several finders.
+    pub fn register(&mut self, program: Pubkey, find: FindSwapsInTx) -> &mut Self {
+        self.finders.push((program, find));
+        self
+    }
+
+    /// Like [`FinderRegistry::register`], for the finders in this crate
+    pub fn register_finder<T: SwapFinderExt>(&mut self, program: Pubkey) -> &mut Self {
+        self.register(program, T::find_swaps_in_tx)
+    }
+
+    /// Drops every finder registered for `program`
+    pub fn disable(&mut self, program: &Pubkey) -> &mut Self {
+        self.retain(|p| p != program)
+    }
+
+    /// Keeps the finders whose program `enabled` returns true for
+    pub fn retain(&mut self, enabled: impl Fn(&Pubkey) -> bool) -> &mut Self {
+        self.finders.retain(|(program, _)| enabled(program));
+        self
+    }
+
+    /// Programs with at least one finder, in registration order. A transaction not touching any of them has no swaps to find.
+    pub fn programs(&self) -> Vec<Pubkey> {
+        let mut programs: Vec<Pubkey> = vec![];
+        for (program, _) in self.finders.iter() {
+            if !programs.contains(program) {
+                programs.push(*program);
+            }
+        }
+        programs
+    }
+
+    pub fn len(&self) -> usize {
+        self.finders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.finders.is_empty()
+    }
+
+    /// Runs every registered finder over the transaction, concatenating their swaps in registration order
+    pub fn find_swaps_in_tx(&self, slot: u64, raw_tx: &SubscribeUpdateTransactionInfo, ixs: &Vec<Instruction>, account_keys: &Vec<Pubkey>, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
+        self.finders.iter().flat_map(|(_, find)| find(slot, raw_tx, ixs, account_keys, token_accounts)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find_nothing(_slot: u64, _raw_tx: &SubscribeUpdateTransactionInfo, _ixs: &Vec<Instruction>, _account_keys: &Vec<Pubkey>, _token_accounts: &TokenAccounts) -> Vec<SwapV2> {
+        vec![]
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut registry = FinderRegistry::builtin();
+        assert_eq!(registry.len(), 39);
+        assert_eq!(registry.programs().len(), 35);
+        registry.disable(&WHIRLPOOL_PUBKEY);
+        assert_eq!(registry.len(), 34);
+        assert!(!registry.programs().contains(&WHIRLPOOL_PUBKEY));
+        let custom = Pubkey::new_unique();
+        registry.register(custom, find_nothing);
+        assert_eq!(registry.programs().last(), Some(&custom));
+        registry.retain(|p| *p == custom);
+        assert_eq!(registry.programs(), vec![custom]);
+    }
+}
@@ -3,7 +3,7 @@ use std::sync::Arc;
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 use yellowstone_grpc_proto::prelude::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
 
-use crate::{events::{addresses::{SUGAR_PUBKEY, WSOL_MINT}, swap::{SwapFinder, SwapV2}, swaps::private::Sealed}, utils::pubkey_from_slice};
+use crate::{addresses::{SUGAR_PUBKEY, WSOL_MINT}, swap::{SwapFinder, SwapV2}, swaps::{private::Sealed, utils::reconcile_swap_amounts}, token_accounts::TokenAccounts, utils::pubkey_from_slice};
 
 impl Sealed for SugarSwapFinder {}
 
@@ -19,12 +19,16 @@ const BUY_EXACT_OUT: &[u8] = &[0x18, 0xd3, 0x74, 0x28, 0x69, 0x03, 0x99, 0x38];
 const BUY_MAX_OUT: &[u8] = &[0x60, 0xb1, 0xcb, 0x75, 0xb7, 0x41, 0xc4, 0xb1];
 const SELL_EXACT_IN: &[u8] = &[0x95, 0x27, 0xde, 0x9b, 0xd3, 0x7c, 0x98, 0x1a];
 const SELL_EXACT_OUT: &[u8] = &[0x5f, 0xc8, 0x47, 0x22, 0x08, 0x09, 0x0b, 0xa6];
+/// How far the balance deltas may be from the amounts in the event, which leave out the 0.9% fee
+const BALANCE_TOLERANCE_BPS: u64 = 200;
 
 /// ~~Pump.fun~~ Sugar have a few variants but it doesn't matter since we rely on the logging instruction here
 /// buyExactIn, buyExactOut, buyMaxOut, sellExactIn, sellExactOut
 /// This one requires custom logic for event parsing since it issues so many transfer for all sorts of fees (all in SOL).
 /// mint[16..48], sol amount [48..56], token amount [56..64], is buy [64], user [65..97]
 /// suspiciously sumilar to pump.fun
+/// The event doesn't carry the fee, so the SOL amount is taken from the user's balance delta where it can be,
+/// falling back to the fee from their docs.
 impl SugarSwapFinder {
     fn user_in_out_index(ix_data: &[u8]) -> (usize, usize) {
         match &ix_data[..8] {
@@ -49,6 +53,7 @@ impl SugarSwapFinder {
         let is_buy = data[64] != 0;
         // let fee = u64::from_le_bytes(data[177..185].try_into().unwrap());
         // let creator_fee = u64::from_le_bytes(data[225..233].try_into().unwrap());
+        // fallback only, the fee rounds differently on chain
         let fee = if is_buy {
             sol_amount * 9 / 991 // 0.9% fee according to their docs
         } else {
@@ -62,7 +67,7 @@ impl SugarSwapFinder {
         let (input_amount, output_amount) = if is_buy {
             (sol_amount + fee, token_amount)
         } else {
-            (token_amount, sol_amount - fee)
+            (token_amount, sol_amount.saturating_sub(fee))
         };
         SwapV2::new(
             outer_program,
@@ -128,21 +133,21 @@ impl SwapFinder for SugarSwapFinder {
         )
     }
 
-    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta) -> Vec<SwapV2> {
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, meta: &TransactionStatusMeta, _token_accounts: &TokenAccounts) -> Vec<SwapV2> {
         if ix.program_id == SUGAR_PUBKEY {
             for inner_ix in inner_ixs.instructions.iter() {
                 if inner_ix.data.len() == 137 && inner_ix.data[0..16] == LOG_DISCRIMINANT[..] {
                     let (in_index, out_index) = Self::user_in_out_index(&ix.data);
-                    return vec![
-                        Self::swap_from_pdf_trade_event(
-                            None,
-                            ix.accounts[2].pubkey,
-                            ix.accounts[in_index].pubkey,
-                            ix.accounts[out_index].pubkey,
-                            &inner_ix.data,
-                            None,
-                        )
-                    ];
+                    let mut swap = Self::swap_from_pdf_trade_event(
+                        None,
+                        ix.accounts[2].pubkey,
+                        ix.accounts[in_index].pubkey,
+                        ix.accounts[out_index].pubkey,
+                        &inner_ix.data,
+                        None,
+                    );
+                    reconcile_swap_amounts(&mut swap, account_keys, meta, BALANCE_TOLERANCE_BPS);
+                    return vec![swap];
                 }
             } 
         }
@@ -174,14 +179,16 @@ impl SwapFinder for SugarSwapFinder {
                         if next_inner_ix.data.len() != 137 || next_inner_ix.data[0..16] != LOG_DISCRIMINANT[..] {
                             continue; // Not an event
                         }
-                        swaps.push(Self::swap_from_pdf_trade_event(
+                        let mut swap = Self::swap_from_pdf_trade_event(
                             Some(ix.program_id.to_string().into()),
                             Self::amm_inner_ix(inner_ix, account_keys),
                             input_ata,
                             output_ata,
                             &next_inner_ix.data,
                             Some(i as u32),
-                        ));
+                        );
+                        reconcile_swap_amounts(&mut swap, account_keys, meta, BALANCE_TOLERANCE_BPS);
+                        swaps.push(swap);
                         next_logical_ix = j + 1;
                     }
                 },
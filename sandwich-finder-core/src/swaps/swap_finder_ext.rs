@@ -2,9 +2,9 @@ use std::sync::Arc;
 
 use debug_print::debug_println;
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
-use yellowstone_grpc_proto::{geyser::SubscribeUpdateTransactionInfo, prelude::{InnerInstructions, TransactionStatusMeta}};
+use yellowstone_grpc_proto::{geyser::SubscribeUpdateTransactionInfo, prelude::InnerInstructions};
 
-use crate::events::{swap::{SwapFinder, SwapV2}, swaps::{private, utils::token_transferred_inner}};
+use crate::{metrics, swap::{SwapFinder, SwapV2}, swaps::{private, utils::{caller_program, pool_reserves, token_transferred_inner}}, token_accounts::TokenAccounts};
 
 
 /// This trait contains helper methods not meant to be overridden by the implementors of [`SwapFinder`].
@@ -15,7 +15,7 @@ pub trait SwapFinderExt: private::Sealed {
         ix: &Instruction,
         inner_ixs: &InnerInstructions,
         account_keys: &Vec<Pubkey>,
-        meta: &TransactionStatusMeta,
+        token_accounts: &TokenAccounts,
         program_id: &Pubkey,
         discriminant: &[u8],
         discriminant_offset: usize,
@@ -23,7 +23,7 @@ pub trait SwapFinderExt: private::Sealed {
     ) -> Vec<SwapV2>;
 
     /// Finds swaps in this tx utilising the provided program id by iterating through the ixs.
-    fn find_swaps_in_tx(slot: u64, raw_tx: &SubscribeUpdateTransactionInfo, ixs: &Vec<Instruction>, account_keys: &Vec<Pubkey>) -> Vec<SwapV2>;
+    fn find_swaps_in_tx(slot: u64, raw_tx: &SubscribeUpdateTransactionInfo, ixs: &Vec<Instruction>, account_keys: &Vec<Pubkey>, token_accounts: &TokenAccounts) -> Vec<SwapV2>;
 }
 
 impl<T: SwapFinder + private::Sealed> SwapFinderExt for T {
@@ -31,7 +31,7 @@ impl<T: SwapFinder + private::Sealed> SwapFinderExt for T {
         ix: &Instruction,
         inner_ixs: &InnerInstructions,
         account_keys: &Vec<Pubkey>,
-        meta: &TransactionStatusMeta,
+        token_accounts: &TokenAccounts,
         program_id: &Pubkey,
         discriminant: &[u8],
         discriminant_offset: usize,
@@ -67,7 +67,7 @@ impl<T: SwapFinder + private::Sealed> SwapFinderExt for T {
             let blacklist_atas: Vec<Pubkey> = blacklist_ata_indexes.iter().filter_map(|&i| ix.accounts.get(i).map(|acc| acc.pubkey)).collect();
             debug_println!("{} -> {} {} -> {}", input_ata, pool_output_ata, pool_input_ata, output_ata);
             inner_ixs.instructions.iter().skip(ixs_to_skip).enumerate().for_each(|(i, inner_ix)| {
-                if let Some((from, to, auth, mint, amount)) = token_transferred_inner(&inner_ix, &account_keys, &meta) {
+                if let Some((from, to, auth, mint, amount)) = token_transferred_inner(&inner_ix, &account_keys, token_accounts) {
                     debug_println!("token transferred: {} -> {} (mint: {}, amount: {})", from, to, mint, amount);
                     if blacklist_atas.contains(&from) || blacklist_atas.contains(&to) {
                         return; // Skip blacklisted ATAs
@@ -85,27 +85,30 @@ impl<T: SwapFinder + private::Sealed> SwapFinderExt for T {
                 }
             });
             // Sometimes the output tx may not exist due to tiny input that rounds the output to 0.
-            return vec![
-                SwapV2::new(
-                    None,
-                    ix.program_id.to_string().into(),
-                    authority.into(),
-                    Self::amm_ix(ix).to_string().into(),
-                    input_mint.unwrap_or_default().into(),
-                    output_mint.unwrap_or_default().into(),
-                    input_amount,
-                    output_amount,
-                    input_ata.to_string().into(),
-                    output_ata.to_string().into(),
-                    input_index,
-                    output_index,
-                    0,
-                    0,
-                    0,
-                    None,
-                    0,
-                )
-            ];
+            let mut swap = SwapV2::new(
+                None,
+                ix.program_id.to_string().into(),
+                authority.into(),
+                Self::amm_ix(ix).to_string().into(),
+                input_mint.unwrap_or_default().into(),
+                output_mint.unwrap_or_default().into(),
+                input_amount,
+                output_amount,
+                input_ata.to_string().into(),
+                output_ata.to_string().into(),
+                input_index,
+                output_index,
+                0,
+                0,
+                0,
+                None,
+                0,
+            );
+            swap.set_quote_limits(Self::quote_limits(&ix.data));
+            if !swap.is_complete() {
+                metrics::incr("swaps_incomplete");
+            }
+            return vec![swap];
         }
         let mut swaps = vec![];
         let mut next_logical_ix = 0;
@@ -144,7 +147,7 @@ impl<T: SwapFinder + private::Sealed> SwapFinderExt for T {
                 if next_inner_ix.program_id_index >= account_keys.len() as u32 {
                     continue;
                 }
-                if let Some((from, to, auth, mint, amount)) = token_transferred_inner(&next_inner_ix, &account_keys, &meta) {
+                if let Some((from, to, auth, mint, amount)) = token_transferred_inner(&next_inner_ix, &account_keys, token_accounts) {
                     let blacklist_atas: Vec<Pubkey> = blacklist_ata_indexes.iter().filter_map(|&i| next_inner_ix.accounts.get(i).map(|acc| account_keys[*acc as usize])).collect();
                     if blacklist_atas.contains(&from) || blacklist_atas.contains(&to) {
                         continue; // Skip blacklisted ATAs
@@ -162,7 +165,7 @@ impl<T: SwapFinder + private::Sealed> SwapFinderExt for T {
                 }
                 if input_mint.is_some() && output_mint.is_some() {
                     // Found both input and output mints
-                    swaps.push(SwapV2::new(
+                    let mut swap = SwapV2::new(
                         Some(ix.program_id.to_string().into()),
                         program_id.to_string().into(),
                         authority,
@@ -180,13 +183,16 @@ impl<T: SwapFinder + private::Sealed> SwapFinderExt for T {
                         0,
                         Some(i as u32),
                         0,
-                    ));
+                    );
+                    swap.set_quote_limits(Self::quote_limits(&inner_ix.data));
+                    swaps.push(swap);
                     next_logical_ix = j + 1;
                     return;
                 }
             }
             // Still push in case we can't find one of the legs - rounded to zero or bug somewhere?
-            swaps.push(SwapV2::new(
+            // Marked by its completeness so detection leaves it out
+            let mut swap = SwapV2::new(
                 Some(ix.program_id.to_string().into()),
                 program_id.to_string().into(),
                 authority,
@@ -204,19 +210,22 @@ impl<T: SwapFinder + private::Sealed> SwapFinderExt for T {
                 0,
                 Some(i as u32),
                 0,
-            ));
+            );
+            swap.set_quote_limits(Self::quote_limits(&inner_ix.data));
+            metrics::incr("swaps_incomplete");
+            swaps.push(swap);
         });
         swaps
     }
 
-    fn find_swaps_in_tx(slot: u64, raw_tx: &SubscribeUpdateTransactionInfo, ixs: &Vec<Instruction>, account_keys: &Vec<Pubkey>) -> Vec<SwapV2> {
+    fn find_swaps_in_tx(slot: u64, raw_tx: &SubscribeUpdateTransactionInfo, ixs: &Vec<Instruction>, account_keys: &Vec<Pubkey>, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
         if let Some(meta) = &raw_tx.meta {
             let mut swaps = vec![];
             ixs.iter().enumerate().for_each(|(i, ix)| {
                 let inner_ixs = meta.inner_instructions.iter().find(|x| x.index == i as u32);
                 if let Some(inner_ixs) = inner_ixs {
-                    Self::find_swaps(ix, inner_ixs, account_keys, meta).iter().for_each(|swap| {
-                        let swap = SwapV2::new(
+                    Self::find_swaps(ix, inner_ixs, account_keys, meta, token_accounts).iter().for_each(|swap| {
+                        let mut swap_in_tx = SwapV2::new(
                             swap.outer_program().clone(),
                             swap.program().clone(),
                             swap.authority().clone(),
@@ -235,7 +244,10 @@ impl<T: SwapFinder + private::Sealed> SwapFinderExt for T {
                             *swap.inner_ix_index(),
                             0,
                         );
-                        swaps.push(swap);
+                        swap_in_tx.set_quote_limits(*swap.quote_limits());
+                        swap_in_tx.set_pool_reserves(pool_reserves(inner_ixs, *swap.input_inner_ix_index(), *swap.output_inner_ix_index(), account_keys, token_accounts, meta));
+                        swap_in_tx.set_caller_program(caller_program(&ix.program_id, inner_ixs, *swap.inner_ix_index(), account_keys).map(|p| p.to_string().into()));
+                        swaps.push(swap_in_tx);
                     });
                 }
             });
@@ -0,0 +1,167 @@
+use std::str::FromStr as _;
+
+use solana_sdk::pubkey::Pubkey;
+use yellowstone_grpc_proto::prelude::{InnerInstruction, InnerInstructions, TokenBalance, TransactionStatusMeta};
+
+use crate::{addresses::{SYSTEM_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID, WSOL_MINT}, metrics, swap::{PoolReserves, SwapV2}, token_accounts::TokenAccounts};
+
+fn token_amount(balances: &[TokenBalance], account_index: usize) -> Option<u64> {
+    balances.iter().find(|b| b.account_index as usize == account_index)?.ui_token_amount.as_ref()?.amount.parse().ok()
+}
+
+/// Net change of an account's balance over the tx, in tokens for token accounts and lamports otherwise.
+/// `None` when it can't be told, such as for accounts closed by the tx and the fee payer's lamports which also cover fees and tips.
+pub fn balance_delta(account: &Pubkey, account_keys: &[Pubkey], meta: &TransactionStatusMeta) -> Option<i128> {
+    let index = account_keys.iter().position(|key| key == account)?;
+    let pre_token = token_amount(&meta.pre_token_balances, index);
+    match token_amount(&meta.post_token_balances, index) {
+        Some(post) => Some(post as i128 - pre_token.unwrap_or_default() as i128),
+        None if pre_token.is_some() => None, // closed
+        None if index == 0 => None,
+        None => Some(*meta.post_balances.get(index)? as i128 - *meta.pre_balances.get(index)? as i128),
+    }
+}
+
+/// Replaces an estimated amount with the one moved according to a balance delta, as long as they're within `tolerance_bps`.
+/// A larger gap means something else in the tx touched the account too, so the estimate is kept.
+pub fn reconcile_amount(estimate: u64, delta: Option<i128>, tolerance_bps: u64) -> u64 {
+    let Some(actual) = delta.filter(|&d| d > 0 && d <= u64::MAX as i128).map(|d| d as u64) else {
+        return estimate;
+    };
+    if actual.abs_diff(estimate) as u128 * 10000 <= estimate as u128 * tolerance_bps as u128 {
+        actual
+    } else {
+        metrics::incr("swap_balance_mismatch");
+        estimate
+    }
+}
+
+/// Reconciles both amounts of a swap against the balance deltas of the user's in/out accounts, see [`reconcile_amount`]
+pub fn reconcile_swap_amounts(swap: &mut SwapV2, account_keys: &[Pubkey], meta: &TransactionStatusMeta, tolerance_bps: u64) {
+    let delta = |ata: &str| balance_delta(&Pubkey::from_str(ata).ok()?, account_keys, meta);
+    let input_amount = reconcile_amount(*swap.input_amount(), delta(swap.input_ata()).map(|d| -d), tolerance_bps);
+    let output_amount = reconcile_amount(*swap.output_amount(), delta(swap.output_ata()), tolerance_bps);
+    swap.set_amounts(input_amount, output_amount);
+}
+
+/// Reserves of the pool a swap traded against, read off the post balances of the accounts on the pool's end of the swap's legs
+/// at `input_inner_ix_index` and `output_inner_ix_index`. SOL paid through the system program counts the vault's lamports.
+pub fn pool_reserves(inner_ixs: &InnerInstructions, input_inner_ix_index: Option<u32>, output_inner_ix_index: Option<u32>, account_keys: &Vec<Pubkey>, token_accounts: &TokenAccounts, meta: &TransactionStatusMeta) -> PoolReserves {
+    let vault_balance = |inner_ix_index: Option<u32>, pool_receives: bool| {
+        let inner_ix = inner_ixs.instructions.get(inner_ix_index? as usize)?;
+        let (from, to, _, _, _) = token_transferred_inner(inner_ix, account_keys, token_accounts)?;
+        let vault = if pool_receives { to } else { from };
+        let index = account_keys.iter().position(|key| *key == vault)?;
+        match account_keys[inner_ix.program_id_index as usize] {
+            SYSTEM_PROGRAM_ID => meta.post_balances.get(index).copied(),
+            _ => token_amount(&meta.post_token_balances, index),
+        }
+    };
+    PoolReserves::new(vault_balance(input_inner_ix_index, true), vault_balance(output_inner_ix_index, false))
+}
+
+/// The program that invoked the inner ix at `inner_ix_index`: the closest ix before it one level up the stack.
+/// Falls back to the top level program for direct CPIs and for txs without stack heights, None for top level ixs.
+pub fn caller_program(top_level_program: &Pubkey, inner_ixs: &InnerInstructions, inner_ix_index: Option<u32>, account_keys: &[Pubkey]) -> Option<Pubkey> {
+    let index = inner_ix_index? as usize;
+    let nested_caller = match inner_ixs.instructions.get(index)?.stack_height {
+        Some(height) if height > 2 => inner_ixs.instructions[..index].iter().rev().find(|ix| ix.stack_height == Some(height - 1)),
+        _ => None,
+    };
+    match nested_caller {
+        Some(caller) => account_keys.get(caller.program_id_index as usize).copied(),
+        None => Some(*top_level_program),
+    }
+}
+
+/// Reads the little endian u64 at `offset` of some ix data, if it's long enough
+pub fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    data.get(offset..offset + 8).map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+}
+
+pub fn token_transferred_inner(inner_ix: &InnerInstruction, account_keys: &Vec<Pubkey>, token_accounts: &TokenAccounts) -> Option<(Pubkey, Pubkey, Pubkey, String, u64)> {
+    // (from, to, mint, amount)
+    if inner_ix.program_id_index >= account_keys.len() as u32 {
+        return None;
+    }
+    let program_id = account_keys[inner_ix.program_id_index as usize];
+    match program_id {
+        TOKEN_PROGRAM_ID | TOKEN_2022_PROGRAM_ID => {
+            if inner_ix.data.len() < 9 {
+                return None;
+            }
+            let (from_index, to_index, auth_index) = match inner_ix.data[0] {
+                3 => (inner_ix.accounts[0], inner_ix.accounts[1], inner_ix.accounts[2]), // Transfer
+                12 => (inner_ix.accounts[0], inner_ix.accounts[2], inner_ix.accounts[3]), // TransferChecked
+                _ => (255, 255, 255), // Not a transfer, will be caught by bounds check
+            };
+            if from_index as usize >= account_keys.len() || to_index as usize >= account_keys.len() {
+                return None;
+            }
+            let checked_mint = if inner_ix.data[0] == 12 {
+                Some(account_keys[inner_ix.accounts[1] as usize].to_string())
+            } else {
+                None
+            };
+            let from_mint = token_accounts.mint_at(from_index as usize).map(|mint| mint.to_string());
+            let to_mint = token_accounts.mint_at(to_index as usize).map(|mint| mint.to_string());
+            if checked_mint.is_none() && from_mint.is_none() && to_mint.is_none() {
+                return None;
+            }
+            return Some((
+                account_keys[from_index as usize],
+                account_keys[to_index as usize],
+                account_keys[auth_index as usize],
+                checked_mint.or(from_mint).or(to_mint).unwrap(),
+                u64::from_le_bytes(inner_ix.data[1..9].try_into().unwrap()),
+            ));
+        },
+        SYSTEM_PROGRAM_ID => {
+            if inner_ix.data.len() < 12 {
+                return None;
+            }
+            if inner_ix.data[0] != 2 {
+                return None; // Not a transfer
+            }
+            return Some((
+                account_keys[inner_ix.accounts[0] as usize],
+                account_keys[inner_ix.accounts[1] as usize],
+                account_keys[inner_ix.accounts[0] as usize],
+                WSOL_MINT.to_string(),
+                u64::from_le_bytes(inner_ix.data[4..12].try_into().unwrap()),
+            ));
+        },
+        _ => None,
+    }
+    // ix, amount[, decimals]
+    
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reconcile_amount() {
+        // within 2%, the delta wins
+        assert_eq!(reconcile_amount(1009, Some(1000), 200), 1000);
+        assert_eq!(reconcile_amount(1000, Some(1100), 200), 1000);
+        assert_eq!(reconcile_amount(1000, Some(-1000), 200), 1000);
+        assert_eq!(reconcile_amount(1000, None, 200), 1000);
+    }
+
+    #[test]
+    fn test_caller_program() {
+        // top level -> wrapper -> aggregator -> amm
+        let keys: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let inner_ix = |program_id_index: u32, stack_height: Option<u32>| InnerInstruction { program_id_index, accounts: vec![], data: vec![], stack_height };
+        let inner_ixs = InnerInstructions { index: 0, instructions: vec![inner_ix(1, Some(2)), inner_ix(2, Some(3)), inner_ix(3, Some(4)), inner_ix(3, Some(2))] };
+        assert_eq!(caller_program(&keys[0], &inner_ixs, Some(2), &keys), Some(keys[2]));
+        assert_eq!(caller_program(&keys[0], &inner_ixs, Some(1), &keys), Some(keys[1]));
+        assert_eq!(caller_program(&keys[0], &inner_ixs, Some(3), &keys), Some(keys[0]));
+        assert_eq!(caller_program(&keys[0], &inner_ixs, None, &keys), None);
+        let unknown_heights = InnerInstructions { index: 0, instructions: vec![inner_ix(1, None), inner_ix(3, None)] };
+        assert_eq!(caller_program(&keys[0], &unknown_heights, Some(1), &keys), Some(keys[0]));
+    }
+}
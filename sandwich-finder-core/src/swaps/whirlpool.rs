@@ -0,0 +1,466 @@
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use yellowstone_grpc_proto::prelude::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
+
+use crate::{addresses::{TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID, WHIRLPOOL_PUBKEY}, swap::{QuoteLimits, SwapFinder, SwapV2}, swaps::{private::Sealed, swap_finder_ext::SwapFinderExt, utils::{read_u64, token_transferred_inner}}, token_accounts::TokenAccounts};
+
+pub(super) const SWAP_DISCRIMINANT: [u8; 8] = [0xf8, 0xc6, 0x9e, 0x91, 0xe1, 0x75, 0x87, 0xc8];
+pub(super) const SWAP_V2_DISCRIMINANT: [u8; 8] = [0x2b, 0x04, 0xed, 0x0b, 0x1a, 0xc9, 0x1e, 0x62];
+// up to and including aToB
+const SWAP_V2_DATA_LENGTH: usize = 42;
+
+// (mint, amount, inner ix index) of one side of a swap
+type Leg = (String, u64, u32);
+
+impl Sealed for WhirlpoolSwapFinder {}
+
+pub struct WhirlpoolSwapFinder {}
+
+/// Whirlpool 1-hop swaps have two variants:
+/// 1. swap [0xf8, 0xc6, 0x9e, 0x91, 0xe1, 0x75, 0x87, 0xc8]
+/// 2. swapV2 [0x2b, 0x04, 0xed, 0x0b, 0x1a, 0xc9, 0x1e, 0x62]
+/// For swap, [amm, userA, poolA, userB, poolB] = [2, 3, 4, 5, 6]
+/// For swapV2, [amm, userA, poolA, userB, poolB] = [4, 7, 8, 9, 10]
+/// As far as swap amounts are concerned, both instructions has the same data layout
+/// in amount, min out, sqrt price limit, amount is in, aToB
+/// aToB determines trade direction.
+impl WhirlpoolSwapFinder {
+    fn is_swap_v2(ix_data: &[u8]) -> bool {
+        ix_data.starts_with(&SWAP_V2_DISCRIMINANT)
+    }
+
+    /// Finds the two legs of a swapV2 among the inner ixs it invoked, which start at `start` and run until the next ix at
+    /// `stack_height` or above. Token-2022 transfer hooks run as CPIs of the transfers themselves and may move tokens of their own, so only
+    /// transfers made directly by the swapV2 (one level below `stack_height`) through one of its two token programs count.
+    /// The first matching transfer wins on each side.
+    fn find_swap_v2_legs(
+        inner_ixs: &InnerInstructions,
+        start: usize,
+        stack_height: Option<u32>,
+        token_programs: [Pubkey; 2],
+        ((input_ata, output_ata), (pool_input_ata, pool_output_ata)): ((Pubkey, Pubkey), (Pubkey, Pubkey)),
+        account_keys: &Vec<Pubkey>,
+        token_accounts: &TokenAccounts,
+    ) -> (Pubkey, Option<Leg>, Option<Leg>) {
+        let mut authority = Pubkey::default();
+        let (mut input, mut output) = (None, None);
+        for (j, inner_ix) in inner_ixs.instructions.iter().enumerate().skip(start) {
+            if stack_height.zip(inner_ix.stack_height).is_some_and(|(h, inner_h)| inner_h <= h) {
+                break;
+            }
+            // blocks from before stack heights were recorded can't be scoped, take them as they are
+            if stack_height.zip(inner_ix.stack_height).is_some_and(|(h, inner_h)| inner_h != h + 1) {
+                continue;
+            }
+            let Some(&program) = account_keys.get(inner_ix.program_id_index as usize) else {
+                continue;
+            };
+            if !(program == TOKEN_PROGRAM_ID || program == TOKEN_2022_PROGRAM_ID) || !token_programs.contains(&program) {
+                continue;
+            }
+            let Some((from, to, auth, mint, amount)) = token_transferred_inner(inner_ix, account_keys, token_accounts) else {
+                continue;
+            };
+            if input.is_none() && from == input_ata && to == pool_output_ata {
+                input = Some((mint, amount, j as u32));
+                authority = auth;
+            } else if output.is_none() && to == output_ata && from == pool_input_ata {
+                output = Some((mint, amount, j as u32));
+            }
+            if input.is_some() && output.is_some() {
+                break;
+            }
+        }
+        (authority, input, output)
+    }
+
+    fn swap_v2_from_legs(outer_program: Option<Pubkey>, amm: Pubkey, (input_ata, output_ata): (Pubkey, Pubkey), (authority, input, output): (Pubkey, Option<Leg>, Option<Leg>), inner_ix_index: Option<u32>, data: &[u8]) -> SwapV2 {
+        let (input_mint, input_amount, input_index) = input.map_or((String::new(), 0, None), |(m, a, i)| (m, a, Some(i)));
+        let (output_mint, output_amount, output_index) = output.map_or((String::new(), 0, None), |(m, a, i)| (m, a, Some(i)));
+        let authority = if input_index.is_some() { authority.to_string() } else { String::new() };
+        let mut swap = SwapV2::new(
+            outer_program.map(|p| p.to_string().into()),
+            WHIRLPOOL_PUBKEY.to_string().into(),
+            authority.into(),
+            amm.to_string().into(),
+            input_mint.into(),
+            output_mint.into(),
+            input_amount,
+            output_amount,
+            input_ata.to_string().into(),
+            output_ata.to_string().into(),
+            input_index,
+            output_index,
+            0,
+            0,
+            0,
+            inner_ix_index,
+            0,
+        );
+        swap.set_quote_limits(Self::quote_limits(data));
+        swap
+    }
+
+    /// swapV2 gets its own matching instead of [`SwapFinderExt::find_swaps_generic`], which scans every inner ix after
+    /// the swap and can pair the transfers made by a transfer hook with the swap's own, see [`Self::find_swap_v2_legs`].
+    fn find_swaps_v2(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
+        if ix.program_id == WHIRLPOOL_PUBKEY {
+            if ix.data.len() < SWAP_V2_DATA_LENGTH || !Self::is_swap_v2(&ix.data) || ix.accounts.len() < 11 {
+                return vec![];
+            }
+            let user_atas = Self::user_ata_ix(ix);
+            let token_programs = [ix.accounts[0].pubkey, ix.accounts[1].pubkey];
+            let legs = Self::find_swap_v2_legs(inner_ixs, 0, Some(1), token_programs, (user_atas, Self::pool_ata_ix(ix)), account_keys, token_accounts);
+            return vec![Self::swap_v2_from_legs(None, Self::amm_ix(ix), user_atas, legs, None, &ix.data)];
+        }
+        let mut swaps = vec![];
+        for (i, inner_ix) in inner_ixs.instructions.iter().enumerate() {
+            if account_keys.get(inner_ix.program_id_index as usize) != Some(&WHIRLPOOL_PUBKEY) {
+                continue;
+            }
+            if inner_ix.data.len() < SWAP_V2_DATA_LENGTH || !Self::is_swap_v2(&inner_ix.data) || inner_ix.accounts.len() < 11 || inner_ix.accounts.iter().any(|&a| a as usize >= account_keys.len()) {
+                continue;
+            }
+            let user_atas = Self::user_ata_inner_ix(inner_ix, account_keys);
+            let token_programs = [account_keys[inner_ix.accounts[0] as usize], account_keys[inner_ix.accounts[1] as usize]];
+            let legs = Self::find_swap_v2_legs(inner_ixs, i + 1, inner_ix.stack_height, token_programs, (user_atas, Self::pool_ata_inner_ix(inner_ix, account_keys)), account_keys, token_accounts);
+            swaps.push(Self::swap_v2_from_legs(Some(ix.program_id), Self::amm_inner_ix(inner_ix, account_keys), user_atas, legs, Some(i as u32), &inner_ix.data));
+        }
+        swaps
+    }
+
+    fn is_from_a_to_b(ix_data: &[u8]) -> bool {
+        ix_data[41] != 0
+    }
+}
+
+impl SwapFinder for WhirlpoolSwapFinder {
+    fn amm_ix(ix: &Instruction) -> Pubkey {
+        if Self::is_swap_v2(&ix.data) {
+            ix.accounts[4].pubkey // swapV2
+        } else {
+            ix.accounts[2].pubkey // swap
+        }
+    }
+
+    fn amm_inner_ix(inner_ix: &InnerInstruction, account_keys: &Vec<Pubkey>) -> Pubkey {
+        if Self::is_swap_v2(&inner_ix.data) {
+            account_keys[inner_ix.accounts[4] as usize] // swapV2
+        } else {
+            account_keys[inner_ix.accounts[2] as usize] // swap
+        }
+    }
+
+    fn user_ata_ix(ix: &Instruction) -> (Pubkey, Pubkey) {
+        match (Self::is_swap_v2(&ix.data), Self::is_from_a_to_b(&ix.data)) {
+            (true, true) => (ix.accounts[7].pubkey, ix.accounts[9].pubkey), // swapV2, aToB
+            (true, false) => (ix.accounts[9].pubkey, ix.accounts[7].pubkey), // swapV2, bToA
+            (false, true) => (ix.accounts[3].pubkey, ix.accounts[5].pubkey), // swap, aToB
+            (false, false) => (ix.accounts[5].pubkey, ix.accounts[3].pubkey), // swap, bToA
+        }
+    }
+
+    fn user_ata_inner_ix(inner_ix: &InnerInstruction, account_keys: &Vec<Pubkey>) -> (Pubkey, Pubkey) {
+        match (Self::is_swap_v2(&inner_ix.data), Self::is_from_a_to_b(&inner_ix.data)) {
+            (true, true) => (
+                account_keys[inner_ix.accounts[7] as usize],
+                account_keys[inner_ix.accounts[9] as usize],
+            ), // swapV2, aToB
+            (true, false) => (
+                account_keys[inner_ix.accounts[9] as usize],
+                account_keys[inner_ix.accounts[7] as usize],
+            ), // swapV2, bToA
+            (false, true) => (
+                account_keys[inner_ix.accounts[3] as usize],
+                account_keys[inner_ix.accounts[5] as usize],
+            ), // swap, aToB
+            (false, false) => (
+                account_keys[inner_ix.accounts[5] as usize],
+                account_keys[inner_ix.accounts[3] as usize],
+            ), // swap, bToA
+        }
+    }
+    
+    fn pool_ata_ix(ix: &Instruction) -> (Pubkey, Pubkey) {
+        match (Self::is_swap_v2(&ix.data), Self::is_from_a_to_b(&ix.data)) {
+            (true, true) => (ix.accounts[10].pubkey, ix.accounts[8].pubkey), // swapV2, aToB
+            (true, false) => (ix.accounts[8].pubkey, ix.accounts[10].pubkey), // swapV2, bToA
+            (false, true) => (ix.accounts[6].pubkey, ix.accounts[4].pubkey), // swap, aToB
+            (false, false) => (ix.accounts[4].pubkey, ix.accounts[6].pubkey), // swap, bToA
+        }
+    }
+    
+    fn pool_ata_inner_ix(inner_ix: &InnerInstruction, account_keys: &Vec<Pubkey>) -> (Pubkey, Pubkey) {
+        match (Self::is_swap_v2(&inner_ix.data), Self::is_from_a_to_b(&inner_ix.data)) {
+            (true, true) => (
+                account_keys[inner_ix.accounts[10] as usize],
+                account_keys[inner_ix.accounts[8] as usize],
+            ), // swapV2, aToB
+            (true, false) => (
+                account_keys[inner_ix.accounts[8] as usize],
+                account_keys[inner_ix.accounts[10] as usize],
+            ), // swapV2, bToA
+            (false, true) => (
+                account_keys[inner_ix.accounts[6] as usize],
+                account_keys[inner_ix.accounts[4] as usize],
+            ), // swap, aToB
+            (false, false) => (
+                account_keys[inner_ix.accounts[4] as usize],
+                account_keys[inner_ix.accounts[6] as usize],
+            ), // swap, bToA
+        }
+    }
+
+    fn quote_limits(ix_data: &[u8]) -> QuoteLimits {
+        // amount specified is input
+        match ix_data.get(40) {
+            Some(0) => QuoteLimits::exact_out(read_u64(ix_data, 16)),
+            Some(_) => QuoteLimits::exact_in(read_u64(ix_data, 16)),
+            None => QuoteLimits::default(),
+        }
+    }
+
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
+        [
+            // swap
+            Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &WHIRLPOOL_PUBKEY, &SWAP_DISCRIMINANT, 0, 24),
+            // swap_v2
+            Self::find_swaps_v2(ix, inner_ixs, account_keys, token_accounts),
+        ].concat()
+    }
+}
+
+pub struct WhirlpoolTwoHopSwapFinder<
+    const A2B: usize,
+    const AMM_INDEX: usize,
+    const USER_A_INDEX: usize,
+    const USER_B_INDEX: usize,
+    const POOL_A_INDEX: usize,
+    const POOL_B_INDEX: usize,
+    const DATA_SIZE: usize,
+    const D0: u8,
+    const D1: u8,
+    const D2: u8,
+    const D3: u8,
+    const D4: u8,
+    const D5: u8,
+    const D6: u8,
+    const D7: u8,
+>;
+
+impl<
+    const A2B: usize,
+    const AMM: usize,
+    const UA: usize,
+    const UB: usize,
+    const PA: usize,
+    const PB: usize,
+    const DS: usize,
+    const D0: u8,
+    const D1: u8,
+    const D2: u8,
+    const D3: u8,
+    const D4: u8,
+    const D5: u8,
+    const D6: u8,
+    const D7: u8,
+> Sealed for WhirlpoolTwoHopSwapFinder<A2B, AMM, UA, UB, PA, PB, DS, D0, D1, D2, D3, D4, D5, D6, D7> {}
+
+impl<
+    const A2B: usize,
+    const AMM: usize,
+    const UA: usize,
+    const UB: usize,
+    const PA: usize,
+    const PB: usize,
+    const DS: usize,
+    const D0: u8,
+    const D1: u8,
+    const D2: u8,
+    const D3: u8,
+    const D4: u8,
+    const D5: u8,
+    const D6: u8,
+    const D7: u8,
+> WhirlpoolTwoHopSwapFinder<A2B, AMM, UA, UB, PA, PB, DS, D0, D1, D2, D3, D4, D5, D6, D7> {
+    pub const DISCRIMINANT: [u8; 8] = [D0, D1, D2, D3, D4, D5, D6, D7];
+
+    pub fn is_from_a_to_b(ix_data: &[u8]) -> bool {
+        ix_data[A2B] != 0
+    }
+}
+
+impl<
+    const A2B: usize,
+    const AMM: usize,
+    const UA: usize,
+    const UB: usize,
+    const PA: usize,
+    const PB: usize,
+    const DS: usize,
+    const D0: u8,
+    const D1: u8,
+    const D2: u8,
+    const D3: u8,
+    const D4: u8,
+    const D5: u8,
+    const D6: u8,
+    const D7: u8,
+> SwapFinder for WhirlpoolTwoHopSwapFinder<A2B, AMM, UA, UB, PA, PB, DS, D0, D1, D2, D3, D4, D5, D6, D7> {
+    fn amm_ix(ix: &Instruction) -> Pubkey {
+        ix.accounts[AMM].pubkey
+    }
+
+    fn amm_inner_ix(inner_ix: &InnerInstruction, account_keys: &Vec<Pubkey>) -> Pubkey {
+        account_keys[inner_ix.accounts[AMM] as usize]
+    }
+    
+    fn user_ata_ix(ix: &Instruction) -> (Pubkey, Pubkey) {
+        if Self::is_from_a_to_b(&ix.data) {
+            (ix.accounts[UA].pubkey, ix.accounts[UB].pubkey) // aToB
+        } else {
+            (ix.accounts[UB].pubkey, ix.accounts[UA].pubkey) // bToA
+        }
+    }
+    
+    fn user_ata_inner_ix(inner_ix: &InnerInstruction, account_keys: &Vec<Pubkey>) -> (Pubkey, Pubkey) {
+        if Self::is_from_a_to_b(&inner_ix.data) {
+            (
+                account_keys[inner_ix.accounts[UA] as usize],
+                account_keys[inner_ix.accounts[UB] as usize],
+            ) // aToB
+        } else {
+            (
+                account_keys[inner_ix.accounts[UB] as usize],
+                account_keys[inner_ix.accounts[UA] as usize],
+            ) // bToA
+        }
+    }
+
+    fn pool_ata_ix(_ix: &Instruction) -> (Pubkey, Pubkey) {
+        if Self::is_from_a_to_b(&_ix.data) {
+            (_ix.accounts[PB].pubkey, _ix.accounts[PA].pubkey) // aToB
+        } else {
+            (_ix.accounts[PA].pubkey, _ix.accounts[PB].pubkey) // bToA
+        }
+    }
+
+    fn pool_ata_inner_ix(_inner_ix: &InnerInstruction, _account_keys: &Vec<Pubkey>) -> (Pubkey, Pubkey) {
+        if Self::is_from_a_to_b(&_inner_ix.data) {
+            (
+                _account_keys[_inner_ix.accounts[PB] as usize],
+                _account_keys[_inner_ix.accounts[PA] as usize],
+            ) // aToB
+        } else {
+            (
+                _account_keys[_inner_ix.accounts[PA] as usize],
+                _account_keys[_inner_ix.accounts[PB] as usize],
+            ) // bToA
+        }
+    }
+
+    fn find_swaps(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta, token_accounts: &TokenAccounts) -> Vec<SwapV2> {
+        Self::find_swaps_generic(ix, inner_ixs, account_keys, token_accounts, &WHIRLPOOL_PUBKEY, &Self::DISCRIMINANT, 0, DS)
+    }
+}
+
+/// Whirlpool also has 2-hop swaps with two variants
+/// It's much easier to run 2 passes for the 2 hops
+/// Hop 1: [amm, userA, poolA, userB, poolB] = [2, 4, 5, 6, 7]
+/// Hop 2: [amm, userA, poolA, userB, poolB] = [3, 8, 9, 10, 11]
+/// 
+pub type WhirlpoolTwoHopSwapFinder1 = WhirlpoolTwoHopSwapFinder<25, 2, 4, 6, 5, 7, 59, 0xc3, 0x60, 0xed, 0x6c, 0x44, 0xa2, 0xdb, 0xe6>;
+pub type WhirlpoolTwoHopSwapFinder2 = WhirlpoolTwoHopSwapFinder<26, 3, 8, 10, 9, 11, 59, 0xc3, 0x60, 0xed, 0x6c, 0x44, 0xa2, 0xdb, 0xe6>;
+
+/// For TwoHopSwapV2 there's only 3 transfers, but the second one is reused (both the output of the 1st hop and the input of the 2nd hop)
+/// The structure looks something like this
+/// A->B B->C
+/// pump->sol->usdt
+/// [8]  UA /UA1 [pump]
+/// [9]  P1A/PA1 [pump]
+/// [10] P1B/PB1/UA2 [sol]
+/// [11] P2B/UB1/PA2 [sol]
+/// [12] P2C    /PB2 [usdt]
+/// [13] UC     /UB2 [usdt]
+/// swap 1: UA->P1A, P1B->P2B
+/// swap 2: P1B->P2B, P2C->UC
+/// We set A2B to 0 since it's one of the discriminant bytes and is guaranteed to be non zero
+pub type WhirlpoolTwoHopSwapV2Finder1 = WhirlpoolTwoHopSwapFinder<0, 0, 8, 11, 9, 10, 59, 0xba, 0x8f, 0xd1, 0x1d, 0xfe, 0x02, 0xc2, 0x75>;
+pub type WhirlpoolTwoHopSwapV2Finder2 = WhirlpoolTwoHopSwapFinder<0, 1, 10, 13, 11, 12, 59, 0xba, 0x8f, 0xd1, 0x1d, 0xfe, 0x02, 0xc2, 0x75>;
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::instruction::AccountMeta;
+
+    use super::*;
+
+    #[test]
+    fn test_quote_limits() {
+        let mut data = vec![0xf8, 0xc6, 0x9e, 0x91, 0xe1, 0x75, 0x87, 0xc8];
+        data.extend(1000u64.to_le_bytes());
+        data.extend(990u64.to_le_bytes());
+        data.extend(0u128.to_le_bytes());
+        data.extend([1, 1]);
+        assert_eq!(WhirlpoolSwapFinder::quote_limits(&data), QuoteLimits::exact_in(Some(990)));
+        data[40] = 0;
+        assert_eq!(WhirlpoolSwapFinder::quote_limits(&data), QuoteLimits::exact_out(Some(990)));
+        assert_eq!(WhirlpoolSwapFinder::quote_limits(&data[..24]), QuoteLimits::default());
+    }
+
+    // A router calling swapV2 on a pool whose mint A has a transfer hook, laid out like the inner ixs of such a tx.
+    // The hook pays back a token of mint B to the user, which used to be taken as the swap's output.
+    fn transfer_hook_fixture(router: bool) -> (Instruction, InnerInstructions, Vec<Pubkey>) {
+        // user, router, whirlpool, token, token-2022, memo, pool, mint A, mint B, user A, vault A, user B, vault B, hook
+        let mut account_keys: Vec<Pubkey> = (0..14).map(|_| Pubkey::new_unique()).collect();
+        account_keys[2] = WHIRLPOOL_PUBKEY;
+        account_keys[3] = TOKEN_PROGRAM_ID;
+        account_keys[4] = TOKEN_2022_PROGRAM_ID;
+        let mut swap_data = SWAP_V2_DISCRIMINANT.to_vec();
+        swap_data.extend(1000u64.to_le_bytes());
+        swap_data.extend(900u64.to_le_bytes());
+        swap_data.extend(0u128.to_le_bytes());
+        swap_data.extend([1, 1]);
+        let swap_accounts = vec![4, 3, 5, 0, 6, 7, 8, 9, 10, 11, 12];
+        let transfer_checked = |amount: u64| [vec![12], amount.to_le_bytes().to_vec(), vec![6]].concat();
+        let inner_ix = |program_id_index: u32, accounts: Vec<u8>, data: Vec<u8>, stack_height: u32| InnerInstruction { program_id_index, accounts, data, stack_height: Some(stack_height) };
+        let base = if router { 2 } else { 1 };
+        let mut instructions = vec![
+            inner_ix(5, vec![], b"memo".to_vec(), base + 1),
+            // user A -> vault A through token-2022, which invokes the hook
+            inner_ix(4, vec![9, 7, 10, 0], transfer_checked(1000), base + 1),
+            inner_ix(13, vec![9, 7, 10, 0], vec![], base + 2),
+            // the hook's own transfer, vault B -> user B
+            inner_ix(3, vec![12, 8, 11, 6], transfer_checked(1), base + 3),
+            // the actual output, vault B -> user B
+            inner_ix(3, vec![12, 8, 11, 6], transfer_checked(950), base + 1),
+        ];
+        let ix = if router {
+            instructions.insert(0, inner_ix(2, swap_accounts.clone(), swap_data, 2));
+            Instruction { program_id: account_keys[1], accounts: vec![], data: vec![] }
+        } else {
+            Instruction { program_id: WHIRLPOOL_PUBKEY, accounts: swap_accounts.iter().map(|&i| AccountMeta::new(account_keys[i as usize], false)).collect(), data: swap_data }
+        };
+        (ix, InnerInstructions { index: 0, instructions }, account_keys)
+    }
+
+    #[test]
+    fn test_swap_v2_transfer_hook() {
+        let meta = TransactionStatusMeta::default();
+        for router in [false, true] {
+            let (ix, inner_ixs, account_keys) = transfer_hook_fixture(router);
+            let swaps = WhirlpoolSwapFinder::find_swaps(&ix, &inner_ixs, &account_keys, &meta, &TokenAccounts::default());
+            assert_eq!(swaps.len(), 1);
+            let swap = &swaps[0];
+            let offset = router as u32;
+            assert_eq!((*swap.input_amount(), *swap.output_amount()), (1000, 950));
+            assert_eq!((*swap.input_inner_ix_index(), *swap.output_inner_ix_index()), (Some(offset + 1), Some(offset + 4)));
+            assert_eq!((swap.input_mint().as_ref(), swap.output_mint().as_ref()), (account_keys[7].to_string().as_str(), account_keys[8].to_string().as_str()));
+            assert_eq!(swap.authority().as_ref(), account_keys[0].to_string());
+            assert_eq!(swap.amm().as_ref(), account_keys[6].to_string());
+            assert_eq!(*swap.inner_ix_index(), router.then_some(0));
+        }
+        // what the generic matcher makes of it
+        let (ix, inner_ixs, account_keys) = transfer_hook_fixture(true);
+        let swaps = WhirlpoolSwapFinder::find_swaps_generic(&ix, &inner_ixs, &account_keys, &TokenAccounts::default(), &WHIRLPOOL_PUBKEY, &SWAP_V2_DISCRIMINANT, 0, 24);
+        assert_eq!(*swaps[0].output_amount(), 1);
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+
+use derive_getters::Getters;
+use solana_sdk::pubkey::Pubkey;
+use yellowstone_grpc_proto::prelude::{TokenBalance, TransactionStatusMeta};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Getters)]
+pub struct TokenAccount {
+    mint: Pubkey,
+    /// `None` for balances recorded without one
+    owner: Option<Pubkey>,
+    decimals: u8,
+}
+
+/// The token accounts a tx touches by their index in its account keys, built once from its token balances
+/// so finders don't rescan and reparse them for every inner ix
+#[derive(Clone, Debug, Default)]
+pub struct TokenAccounts {
+    accounts: HashMap<u32, TokenAccount>,
+}
+
+impl TokenAccounts {
+    /// Pre balances win over post balances, post balances cover accounts opened by the tx
+    pub fn from_meta(meta: &TransactionStatusMeta) -> Self {
+        let mut accounts = HashMap::new();
+        for balance in meta.post_token_balances.iter().chain(meta.pre_token_balances.iter()) {
+            if let Some(account) = Self::parse(balance) {
+                accounts.insert(balance.account_index, account);
+            }
+        }
+        Self { accounts }
+    }
+
+    fn parse(balance: &TokenBalance) -> Option<TokenAccount> {
+        Some(TokenAccount {
+            mint: balance.mint.parse().ok()?,
+            owner: balance.owner.parse().ok(),
+            decimals: balance.ui_token_amount.as_ref().map_or(0, |amount| amount.decimals as u8),
+        })
+    }
+
+    pub fn get(&self, account_index: usize) -> Option<&TokenAccount> {
+        self.accounts.get(&(account_index as u32))
+    }
+
+    pub fn mint_at(&self, account_index: usize) -> Option<Pubkey> {
+        self.get(account_index).map(|account| account.mint)
+    }
+
+    /// For top level ixs, which carry keys rather than indexes
+    pub fn mint_of(&self, pubkey: &Pubkey, account_keys: &[Pubkey]) -> Option<Pubkey> {
+        self.mint_at(account_keys.iter().position(|key| key == pubkey)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use yellowstone_grpc_proto::prelude::UiTokenAmount;
+
+    use super::*;
+
+    fn balance(account_index: u32, mint: &Pubkey, decimals: u32) -> TokenBalance {
+        TokenBalance {
+            account_index,
+            mint: mint.to_string(),
+            owner: Pubkey::default().to_string(),
+            ui_token_amount: Some(UiTokenAmount { decimals, ..Default::default() }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_from_meta() {
+        let (mint, other_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let meta = TransactionStatusMeta {
+            // 1 is closed by the tx, 2 opened by it
+            pre_token_balances: vec![balance(1, &mint, 6), balance(3, &mint, 6)],
+            post_token_balances: vec![balance(2, &other_mint, 9), balance(3, &other_mint, 9), TokenBalance { account_index: 4, mint: "not a mint".to_string(), ..Default::default() }],
+            ..Default::default()
+        };
+        let accounts = TokenAccounts::from_meta(&meta);
+        assert_eq!(accounts.mint_at(1), Some(mint));
+        assert_eq!(accounts.get(2).map(|account| (account.mint, account.decimals)), Some((other_mint, 9)));
+        assert_eq!(accounts.mint_at(3), Some(mint));
+        assert_eq!(accounts.get(3).unwrap().owner, Some(Pubkey::default()));
+        assert_eq!(accounts.mint_at(4), None);
+        assert_eq!(accounts.mint_at(0), None);
+        let keys = [Pubkey::new_unique(), Pubkey::new_unique()];
+        assert_eq!(accounts.mint_of(&keys[1], &keys), Some(mint));
+        assert_eq!(accounts.mint_of(&Pubkey::new_unique(), &keys), None);
+    }
+}
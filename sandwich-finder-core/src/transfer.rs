@@ -5,7 +5,7 @@ use serde::Serialize;
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 use yellowstone_grpc_proto::{prelude::{InnerInstructions, TransactionStatusMeta}};
 
-use crate::events::common::Timestamp;
+use crate::{common::{BlockTime, Timestamp}, token_accounts::TokenAccounts};
 
 #[derive(Clone, Serialize, Getters)]
 #[serde(rename_all = "camelCase")]
@@ -26,6 +26,8 @@ pub struct TransferV2 {
     // These fields are meant to be replaced when inserting to the db
     timestamp: Timestamp,
     id: u64,
+    #[serde(flatten)]
+    block_time: BlockTime,
 }
 
 impl Debug for TransferV2 {
@@ -73,9 +75,28 @@ impl TransferV2 {
                 inner_ix_index,
             ),
             id,
+            block_time: BlockTime::default(),
         }
     }
 
+    pub fn set_block_time(&mut self, block_time: Option<i64>) {
+        self.block_time = BlockTime(block_time);
+    }
+
+    /// For rewriting the addresses a transfer exposes, such as when redacting them
+    pub fn set_authority(&mut self, authority: Arc<str>) {
+        self.authority = authority;
+    }
+
+    pub fn set_atas(&mut self, input_ata: Arc<str>, output_ata: Arc<str>) {
+        self.input_ata = input_ata;
+        self.output_ata = output_ata;
+    }
+
+    pub fn set_mint(&mut self, mint: Arc<str>) {
+        self.mint = mint;
+    }
+
     pub fn slot(&self) -> &u64 {
         self.timestamp.slot()
     }
@@ -92,5 +113,5 @@ impl TransferV2 {
 
 pub trait TransferFinder {
     /// Returns the transfers utilising a program found in the given instruction and inner instructions.
-    fn find_transfers(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, meta: &TransactionStatusMeta) -> Vec<TransferV2>;
+    fn find_transfers(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, meta: &TransactionStatusMeta, token_accounts: &TokenAccounts) -> Vec<TransferV2>;
 }
\ No newline at end of file
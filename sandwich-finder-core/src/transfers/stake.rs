@@ -1,32 +1,49 @@
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 use yellowstone_grpc_proto::prelude::{InnerInstructions, TransactionStatusMeta};
 
-use crate::events::{addresses::{STAKE_PROGRAM_ID, WSOL_MINT}, transfer::{TransferFinder, TransferV2}, transfers::private::Sealed};
+use crate::{addresses::{STAKE_PROGRAM_ID, WSOL_MINT}, token_accounts::TokenAccounts, transfer::{TransferFinder, TransferV2}, transfers::private::Sealed};
 
 impl Sealed for StakeProgramTransferfinder {}
-/// [0x02, 0x00, 0x00, 0x00, u64]
+/// [0x04, 0x00, 0x00, 0x00, u64]
+/// Splits, merges and withdrawals all count as transfers so funds parked in stake accounts can still be followed
 pub struct StakeProgramTransferfinder{}
 
 impl StakeProgramTransferfinder {
-    /// Returns (from, to, auth, amount)
-    fn amount_and_endpoint_from_data(data: &[u8]) -> Option<(usize, usize, usize, u64)> {
-        if data.len() < 12 {
+    /// Returns (from, to, auth, amount), amount is None if the whole source account is moved
+    fn amount_and_endpoint_from_data(data: &[u8]) -> Option<(usize, usize, usize, Option<u64>)> {
+        if data.len() < 4 {
             return None;
         }
+        let amount = || data.get(4..12).map(|a| u64::from_le_bytes(a.try_into().unwrap()));
         match data[0] {
-            4 => Some((0, 1, 4, u64::from_le_bytes(data[4..12].try_into().unwrap()))), // Withdraw
+            // accounts: stake, split stake, stake authority
+            3 => Some((0, 1, 2, Some(amount()?))), // Split
+            4 => Some((0, 1, 4, Some(amount()?))), // Withdraw
+            // accounts: destination, source, clock, stake history, stake authority
+            7 => Some((1, 0, 4, None)), // Merge
             _ => None,
         }
     }
+
+    /// Merges drain the source account, so the amount is its balance before the tx
+    fn resolve_amount(amount: Option<u64>, from: usize, meta: &TransactionStatusMeta) -> Option<u64> {
+        amount.or_else(|| meta.pre_balances.get(from).copied())
+    }
 }
 
 impl TransferFinder for StakeProgramTransferfinder {
-    fn find_transfers(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta) -> Vec<TransferV2> {
+    fn find_transfers(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, meta: &TransactionStatusMeta, _token_accounts: &TokenAccounts) -> Vec<TransferV2> {
         if ix.program_id == STAKE_PROGRAM_ID {
             if let Some((from, to, auth, amount)) = Self::amount_and_endpoint_from_data(&ix.data) {
-                if ix.accounts.len() < 2 {
+                if ix.accounts.len() <= from.max(to).max(auth) {
                     return vec![];
                 }
+                let Some(from_index) = account_keys.iter().position(|k| *k == ix.accounts[from].pubkey) else {
+                    return vec![];
+                };
+                let Some(amount) = Self::resolve_amount(amount, from_index, meta) else {
+                    return vec![];
+                };
                 return vec![TransferV2::new(
                     None,
                     STAKE_PROGRAM_ID.to_string().into(),
@@ -52,10 +69,10 @@ impl TransferFinder for StakeProgramTransferfinder {
             if account_keys[inner_ix.program_id_index as usize] != STAKE_PROGRAM_ID {
                 return;
             }
-            if inner_ix.accounts.len() < 2 {
-                return;
-            }
             if let Some((from, to, auth, amount)) = Self::amount_and_endpoint_from_data(&inner_ix.data) {
+                if inner_ix.accounts.len() <= from.max(to).max(auth) {
+                    return;
+                }
                 let from = inner_ix.accounts[from] as usize;
                 let to = inner_ix.accounts[to] as usize;
                 let auth = inner_ix.accounts[auth] as usize;
@@ -66,6 +83,9 @@ impl TransferFinder for StakeProgramTransferfinder {
                     // Don't log self transfers
                     return;
                 }
+                let Some(amount) = Self::resolve_amount(amount, from, meta) else {
+                    return;
+                };
                 transfers.push(TransferV2::new(
                     Some(ix.program_id.to_string().into()),
                     STAKE_PROGRAM_ID.to_string().into(),
@@ -86,4 +106,22 @@ impl TransferFinder for StakeProgramTransferfinder {
         });
         transfers
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_amount_and_endpoint() {
+        let split = [3u32.to_le_bytes().as_slice(), 42u64.to_le_bytes().as_slice()].concat();
+        assert_eq!(StakeProgramTransferfinder::amount_and_endpoint_from_data(&split), Some((0, 1, 2, Some(42))));
+        assert_eq!(StakeProgramTransferfinder::amount_and_endpoint_from_data(&split[..4]), None);
+        let merge = 7u32.to_le_bytes();
+        assert_eq!(StakeProgramTransferfinder::amount_and_endpoint_from_data(&merge), Some((1, 0, 4, None)));
+        let meta = TransactionStatusMeta { pre_balances: vec![5, 7], ..Default::default() };
+        assert_eq!(StakeProgramTransferfinder::resolve_amount(None, 1, &meta), Some(7));
+        assert_eq!(StakeProgramTransferfinder::resolve_amount(Some(3), 1, &meta), Some(3));
+        assert_eq!(StakeProgramTransferfinder::resolve_amount(None, 2, &meta), None);
+    }
+}
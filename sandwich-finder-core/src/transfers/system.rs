@@ -0,0 +1,132 @@
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use yellowstone_grpc_proto::prelude::{InnerInstructions, TransactionStatusMeta};
+
+use crate::{addresses::{SYSTEM_PROGRAM_ID, WSOL_MINT}, token_accounts::TokenAccounts, transfer::{TransferFinder, TransferV2}, transfers::private::Sealed};
+
+impl Sealed for SystemProgramTransferfinder {}
+/// [0x02, 0x00, 0x00, 0x00, u64]
+/// Also covers the variants bots use to move SOL out of seeded accounts and nonce accounts
+pub struct SystemProgramTransferfinder{}
+
+impl SystemProgramTransferfinder {
+    /// Returns (from, to, auth, amount)
+    fn amount_and_endpoint_from_data(data: &[u8]) -> Option<(usize, usize, usize, u64)> {
+        if data.len() < 12 {
+            return None;
+        }
+        let amount = u64::from_le_bytes(data[4..12].try_into().unwrap());
+        match data[0] {
+            0 => Some((0, 1, 0, amount)), // CreateAccount
+            2 => Some((0, 1, 0, amount)), // Transfer
+            3 => {
+                // 0..4: discriminator, 4..36: base, 36..44: seed len, 44..(44+seed len): seed, (44+seed len)..(52+seed len): lamports
+                if data.len() < 44 {
+                    return None;
+                }
+                let start = 44usize.checked_add(u64::from_le_bytes(data[36..44].try_into().unwrap()) as usize)?;
+                let end = start.checked_add(8)?;
+                if data.len() < end {
+                    return None;
+                }
+                Some((0, 1, 0, u64::from_le_bytes(data[start..end].try_into().unwrap())))
+            }, // CreateAccountWithSeed
+            // accounts: nonce, recipient, recent blockhashes, rent, nonce authority
+            5 => Some((0, 1, 4, amount)), // WithdrawNonceAccount
+            // accounts: from, base, to
+            11 => Some((0, 2, 1, amount)), // TransferWithSeed
+            _ => None,
+        }
+    }
+}
+
+impl TransferFinder for SystemProgramTransferfinder {
+    fn find_transfers(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta, _token_accounts: &TokenAccounts) -> Vec<TransferV2> {
+        if ix.program_id == SYSTEM_PROGRAM_ID {
+            if let Some((from, to, auth, amount)) = Self::amount_and_endpoint_from_data(&ix.data) {
+                if ix.accounts.len() <= from.max(to).max(auth) {
+                    return vec![];
+                }
+                return vec![TransferV2::new(
+                    None,
+                    SYSTEM_PROGRAM_ID.to_string().into(),
+                    ix.accounts[auth].pubkey.to_string().into(),
+                    WSOL_MINT.to_string().into(),
+                    amount,
+                    ix.accounts[from].pubkey.to_string().into(),
+                    ix.accounts[to].pubkey.to_string().into(),
+                    0,
+                    0,
+                    0,
+                    None,
+                    0,
+                )];
+            }
+            return vec![];
+        }
+        let mut transfers = vec![];
+        inner_ixs.instructions.iter().enumerate().for_each(|(i, inner_ix)| {
+            if inner_ix.program_id_index as usize >= account_keys.len() {
+                return;
+            }
+            if account_keys[inner_ix.program_id_index as usize] != SYSTEM_PROGRAM_ID {
+                return;
+            }
+            if let Some((from, to, auth, amount)) = Self::amount_and_endpoint_from_data(&inner_ix.data) {
+                if inner_ix.accounts.len() <= from.max(to).max(auth) {
+                    return;
+                }
+                let from = inner_ix.accounts[from] as usize;
+                let to = inner_ix.accounts[to] as usize;
+                let auth = inner_ix.accounts[auth] as usize;
+                if from >= account_keys.len() || to >= account_keys.len() || auth >= account_keys.len() {
+                    return;
+                }
+                if from == to {
+                    // Don't log self transfers
+                    return;
+                }
+                transfers.push(TransferV2::new(
+                    Some(ix.program_id.to_string().into()),
+                    SYSTEM_PROGRAM_ID.to_string().into(),
+                    account_keys[auth].to_string().into(),
+                    WSOL_MINT.to_string().into(),
+                    amount,
+                    account_keys[from].to_string().into(),
+                    account_keys[to].to_string().into(),
+                    0,
+                    0,
+                    0,
+                    Some(i as u32),
+                    0,
+                ));
+            } else {
+                return;
+            }
+        });
+        transfers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ix_data(discriminant: u32, amount: u64) -> Vec<u8> {
+        [discriminant.to_le_bytes().as_slice(), amount.to_le_bytes().as_slice()].concat()
+    }
+
+    #[test]
+    fn test_amount_and_endpoint() {
+        assert_eq!(SystemProgramTransferfinder::amount_and_endpoint_from_data(&ix_data(2, 42)), Some((0, 1, 0, 42)));
+        assert_eq!(SystemProgramTransferfinder::amount_and_endpoint_from_data(&ix_data(5, 42)), Some((0, 1, 4, 42)));
+        let mut with_seed = ix_data(11, 42);
+        with_seed.extend_from_slice(&4u64.to_le_bytes());
+        with_seed.extend_from_slice(b"seed");
+        with_seed.extend_from_slice(&[0; 32]);
+        assert_eq!(SystemProgramTransferfinder::amount_and_endpoint_from_data(&with_seed), Some((0, 2, 1, 42)));
+        // AdvanceNonceAccount moves nothing
+        assert_eq!(SystemProgramTransferfinder::amount_and_endpoint_from_data(&ix_data(4, 42)), None);
+        // truncated CreateAccountWithSeed
+        assert_eq!(SystemProgramTransferfinder::amount_and_endpoint_from_data(&ix_data(3, 42)), None);
+    }
+}
@@ -3,7 +3,7 @@ use std::u64;
 use solana_sdk::{instruction::Instruction, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
 use yellowstone_grpc_proto::prelude::{InnerInstructions, TransactionStatusMeta};
 
-use crate::events::{addresses::{TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID}, swaps::utils::mint_of, transfer::{TransferFinder, TransferV2}, transfers::private::Sealed};
+use crate::{addresses::{TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID}, token_accounts::TokenAccounts, transfer::{TransferFinder, TransferV2}, transfers::private::Sealed};
 
 impl Sealed for TokenProgramTransferFinder {}
 pub struct TokenProgramTransferFinder {}
@@ -38,7 +38,7 @@ impl TokenProgramTransferFinder {
 }
 
 impl TransferFinder for TokenProgramTransferFinder {
-    fn find_transfers(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, meta: &TransactionStatusMeta) -> Vec<TransferV2> {
+    fn find_transfers(ix: &Instruction, inner_ixs: &InnerInstructions, account_keys: &Vec<Pubkey>, _meta: &TransactionStatusMeta, token_accounts: &TokenAccounts) -> Vec<TransferV2> {
         if Self::is_token_program(ix.program_id) {
             if let Some(amount) = Self::amount_from_data(&ix.data) {
                 if let Some((from_index, to_index, auth_index)) = Self::from_to_indexs(&ix.data) {
@@ -50,14 +50,14 @@ impl TransferFinder for TokenProgramTransferFinder {
                             return vec![];
                         }
                         let auth = ix.accounts[auth_index].pubkey;
-                        let mint = mint_of(&from_ata, account_keys, meta)
-                            .or_else(|| mint_of(&to_ata, account_keys, meta));
+                        let mint = token_accounts.mint_of(&from_ata, account_keys)
+                            .or_else(|| token_accounts.mint_of(&to_ata, account_keys));
                         if let Some(mint) = mint {
                             return vec![TransferV2::new(
                                 None,
                                 ix.program_id.to_string().into(),
                                 auth.to_string().into(),
-                                mint.into(),
+                                mint.to_string().into(),
                                 amount,
                                 from_ata.to_string().into(),
                                 to_ata.to_string().into(),
@@ -99,14 +99,14 @@ impl TransferFinder for TokenProgramTransferFinder {
                         let from_ata_pubkey = account_keys[from_ata];
                         let to_ata_pubkey = account_keys[to_ata];
                         let auth_pubkey = account_keys[auth];
-                        let mint = mint_of(&from_ata_pubkey, account_keys, meta)
-                            .or_else(|| mint_of(&to_ata_pubkey, account_keys, meta));
+                        let mint = token_accounts.mint_at(from_ata)
+                            .or_else(|| token_accounts.mint_at(to_ata));
                         if let Some(mint) = mint {
                             transfers.push(TransferV2::new(
                                 Some(ix.program_id.to_string().into()),
                                 account_keys[inner_ix.program_id_index as usize].to_string().into(),
                                 auth_pubkey.to_string().into(),
-                                mint.into(),
+                                mint.to_string().into(),
                                 amount,
                                 from_ata_pubkey.to_string().into(),
                                 to_ata_pubkey.to_string().into(),
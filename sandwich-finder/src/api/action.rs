@@ -0,0 +1,199 @@
+use std::{env, str::FromStr as _};
+
+use axum::{extract::{Query, Request, State}, http::{HeaderName, HeaderValue, StatusCode}, middleware::Next, response::{IntoResponse, Response}, Json};
+use mysql::{prelude::Queryable as _, PooledConn};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use solana_sdk::signature::Signature;
+
+use crate::api::ApiState;
+
+/// Version of the Actions spec the responses follow
+const ACTION_VERSION: &str = "2.4";
+/// CAIP-2 id of mainnet
+const BLOCKCHAIN_ID: &str = "solana:5eykt4UsFv8P8NJdTREpY1vzqKqZKvdp";
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000.0;
+
+/// What's needed to serve the blink, enabled by setting `ACTIONS_ICON_URL` as the spec wants an absolute icon url
+pub struct ActionsConfig {
+    icon: String,
+}
+
+impl ActionsConfig {
+    pub fn from_env() -> Option<Self> {
+        let icon = env::var("ACTIONS_ICON_URL").ok().filter(|u| !u.is_empty())?;
+        Some(Self { icon })
+    }
+}
+
+#[derive(Serialize)]
+pub struct ActionParameter {
+    name: &'static str,
+    label: &'static str,
+    required: bool,
+}
+
+#[derive(Serialize)]
+pub struct LinkedAction {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    href: &'static str,
+    label: &'static str,
+    parameters: Vec<ActionParameter>,
+}
+
+#[derive(Serialize)]
+pub struct ActionLinks {
+    actions: Vec<LinkedAction>,
+}
+
+/// `ActionGetResponse` of the spec, `type` is `completed` for the verdict
+#[derive(Serialize)]
+pub struct Action {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    icon: String,
+    title: String,
+    description: String,
+    label: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    links: Option<ActionLinks>,
+}
+
+#[derive(Deserialize)]
+pub struct CheckQuery {
+    signature: String,
+}
+
+/// Adds the CORS and Actions headers every Actions route must answer with, preflights included
+pub async fn action_headers(request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    for (name, value) in [
+        ("access-control-allow-origin", "*"),
+        ("access-control-allow-methods", "GET,POST,PUT,OPTIONS"),
+        ("access-control-allow-headers", "Content-Type, Authorization, Content-Encoding, Accept-Encoding"),
+        ("access-control-expose-headers", "X-Action-Version, X-Blockchain-Ids"),
+        ("x-action-version", ACTION_VERSION),
+        ("x-blockchain-ids", BLOCKCHAIN_ID),
+    ] {
+        headers.insert(HeaderName::from_static(name), HeaderValue::from_static(value));
+    }
+    response
+}
+
+pub async fn handle_preflight() -> StatusCode {
+    StatusCode::NO_CONTENT
+}
+
+/// Maps the blink's urls to the action routes, served at the root of the domain
+pub async fn handle_actions_json() -> Json<serde_json::Value> {
+    Json(json!({
+        "rules": [
+            { "pathPattern": "/action/**", "apiPath": "/action/**" },
+        ],
+    }))
+}
+
+pub async fn handle_check_metadata(State(state): State<ApiState>) -> Response {
+    let Some(config) = &state.actions else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    Json(Action {
+        kind: "action",
+        icon: config.icon.clone(),
+        title: "Was I sandwiched?".to_string(),
+        description: "Checks whether a swap of yours was sandwiched, and what it cost you.".to_string(),
+        label: "Check".to_string(),
+        links: Some(ActionLinks {
+            actions: vec![LinkedAction {
+                kind: "post",
+                href: "/action/check?signature={signature}",
+                label: "Check",
+                parameters: vec![ActionParameter { name: "signature", label: "Transaction signature", required: true }],
+            }],
+        }),
+    }).into_response()
+}
+
+/// (title, description) for a tx given whether it was indexed and the (role, est loss lamports, price impact bps)
+/// of its events within sandwiches
+fn verdict(indexed: bool, roles: &[(String, Option<u64>, Option<u64>)]) -> (String, String) {
+    if !indexed {
+        return ("Not indexed".to_string(), "This transaction hasn't been indexed, it may be too recent or not be a swap on a supported DEX.".to_string());
+    }
+    let victim = roles.iter().filter(|r| r.0 == "VICTIM").fold(None, |acc: Option<(Option<u64>, Option<u64>)>, r| {
+        let (loss, impact) = acc.unwrap_or_default();
+        Some((loss.max(r.1), impact.max(r.2)))
+    });
+    if let Some((loss, impact)) = victim {
+        let mut description = "This transaction was sandwiched.".to_string();
+        if let Some(loss) = loss {
+            description += &format!(" Estimated loss: {:.6} SOL.", loss as f64 / LAMPORTS_PER_SOL);
+        }
+        if let Some(impact) = impact {
+            description += &format!(" Price impact of the frontrun: {:.2}%.", impact as f64 / 100.0);
+        }
+        return ("Sandwiched".to_string(), description);
+    }
+    if roles.iter().any(|r| r.0 == "FRONTRUN" || r.0 == "BACKRUN") {
+        return ("Sandwich attacker".to_string(), "This transaction is a leg of a sandwich attack.".to_string());
+    }
+    ("Not sandwiched".to_string(), "No sandwich was found around this transaction.".to_string())
+}
+
+/// (title, description) of whether the tx with `signature` was sandwiched and how
+pub fn check_transaction(conn: &mut PooledConn, signature: &str) -> (String, String) {
+    let position: Option<(u64, u32)> = conn.exec_first("select slot, inclusion_order from transactions where sig=?", (signature,)).unwrap();
+    let roles: Vec<(String, Option<u64>, Option<u64>)> = match position {
+        Some((slot, inclusion_order)) => conn.exec(
+            "select s.role, s.est_victim_loss_lamports, s.price_impact_bps from sandwiches s join events_with_id e on e.id=s.event_id where e.slot=? and e.inclusion_order=?",
+            (slot, inclusion_order),
+        ).unwrap(),
+        None => vec![],
+    };
+    verdict(position.is_some(), &roles)
+}
+
+/// The verdict for `signature`, as an inline completed action the blink shows in place
+pub async fn handle_check(State(state): State<ApiState>, Query(query): Query<CheckQuery>) -> Response {
+    let Some(config) = &state.actions else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    if Signature::from_str(&query.signature).is_err() {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "message": "invalid transaction signature" }))).into_response();
+    }
+    let (title, description) = check_transaction(&mut state.pool.get_conn().unwrap(), &query.signature);
+    let completed = Action {
+        kind: "completed",
+        icon: config.icon.clone(),
+        title,
+        description,
+        label: "Checked".to_string(),
+        links: None,
+    };
+    Json(json!({
+        "type": "post",
+        "links": { "next": { "type": "inline", "action": completed } },
+    })).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verdict() {
+        assert_eq!(verdict(false, &[]).0, "Not indexed");
+        assert_eq!(verdict(true, &[]).0, "Not sandwiched");
+        assert_eq!(verdict(true, &[("TRANSFER".to_string(), None, None)]).0, "Not sandwiched");
+        assert_eq!(verdict(true, &[("FRONTRUN".to_string(), None, None)]).0, "Sandwich attacker");
+        let (title, description) = verdict(true, &[("VICTIM".to_string(), Some(1_500_000), Some(125))]);
+        assert_eq!(title, "Sandwiched");
+        assert!(description.contains("0.001500 SOL") && description.contains("1.25%"));
+        // not priced in SOL
+        let (title, description) = verdict(true, &[("VICTIM".to_string(), None, Some(125))]);
+        assert_eq!(title, "Sandwiched");
+        assert!(!description.contains("SOL"));
+    }
+}
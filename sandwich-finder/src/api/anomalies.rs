@@ -0,0 +1,80 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{extract::{Query, State}, Json};
+use mysql::prelude::Queryable as _;
+use serde::{Deserialize, Serialize};
+
+use crate::api::ApiState;
+
+const MAX_LIMIT: u64 = 1000;
+
+#[derive(Deserialize)]
+pub struct AnomalyQuery {
+    amm: Option<String>,
+    /// `true` for only the anomalies no sandwich was found in
+    #[serde(default)]
+    unexplained: bool,
+    limit: Option<u64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnomalyRow {
+    id: Arc<str>,
+    amm: Arc<str>,
+    slot: u64,
+    move_bps: u64,
+    sandwich_id: Option<Arc<str>>,
+    /// Event ids of the swaps in execution order
+    swaps: Vec<u64>,
+    peak_swap: Option<u64>,
+}
+
+/// Most recent intra-block price anomalies, newest first. The unexplained ones are where the sandwich detector may have
+/// missed something.
+pub async fn handle_anomalies(State(state): State<ApiState>, Query(query): Query<AnomalyQuery>) -> Json<Vec<AnomalyRow>> {
+    let mut conn = state.pool.get_conn().unwrap();
+    let limit = query.limit.unwrap_or(100).min(MAX_LIMIT);
+    let mut filters = vec![];
+    if query.amm.is_some() {
+        filters.push("amm.address=?");
+    }
+    if query.unexplained {
+        filters.push("an.sandwich_id is null");
+    }
+    let filters = if filters.is_empty() { String::new() } else { format!("where {}", filters.join(" and ")) };
+    let stmt = format!("select an.id, amm.address, an.slot, an.move_bps, an.sandwich_id from anomalies an join address_lookup_table amm on amm.id=an.amm_id {filters} order by an.slot desc limit ?");
+    let to_row = |(id, amm, slot, move_bps, sandwich_id): (String, String, u64, u64, Option<String>)| AnomalyRow {
+        id: id.into(),
+        amm: amm.into(),
+        slot,
+        move_bps,
+        sandwich_id: sandwich_id.map(Arc::from),
+        swaps: vec![],
+        peak_swap: None,
+    };
+    let mut rows = match query.amm {
+        Some(amm) => conn.exec_map(stmt, (amm, limit), to_row),
+        None => conn.exec_map(stmt, (limit,), to_row),
+    }.unwrap();
+    if rows.is_empty() {
+        return Json(rows);
+    }
+    let ids: Vec<_> = rows.iter().map(|r| r.id.to_string()).collect();
+    let swaps: Vec<(String, u64, bool)> = conn.exec(format!("select anomaly_id, event_id, peak from anomaly_swaps where anomaly_id in ({}) order by anomaly_id, position", "?,".repeat(ids.len()).trim_end_matches(",")), ids).unwrap();
+    let mut by_anomaly: HashMap<String, (Vec<u64>, Option<u64>)> = HashMap::new();
+    for (anomaly_id, event_id, peak) in swaps {
+        let (swaps, peak_swap) = by_anomaly.entry(anomaly_id).or_default();
+        swaps.push(event_id);
+        if peak {
+            *peak_swap = Some(event_id);
+        }
+    }
+    for row in rows.iter_mut() {
+        if let Some((swaps, peak_swap)) = by_anomaly.remove(row.id.as_ref()) {
+            row.swaps = swaps;
+            row.peak_swap = peak_swap;
+        }
+    }
+    Json(rows)
+}
@@ -0,0 +1,26 @@
+use axum::{extract::{Path, State}, Json};
+use mysql::prelude::Queryable as _;
+use serde::Serialize;
+
+use crate::{api::ApiState, fingerprint::Fingerprint};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterFingerprint {
+    cluster_id: u32,
+    from_slot: u64,
+    to_slot: u64,
+    fingerprint: Fingerprint,
+}
+
+/// The latest fingerprint of an attacker cluster
+pub async fn handle_fingerprint(State(state): State<ApiState>, Path(cluster_id): Path<u32>) -> Json<Option<ClusterFingerprint>> {
+    let mut conn = state.pool.get_conn().unwrap();
+    let row: Option<(u64, u64, String)> = conn.exec_first("select from_slot, to_slot, fingerprint from attacker_fingerprints where cluster_id=? order by to_slot desc limit 1", (cluster_id,)).unwrap();
+    Json(row.map(|(from_slot, to_slot, fingerprint)| ClusterFingerprint {
+        cluster_id,
+        from_slot,
+        to_slot,
+        fingerprint: serde_json::from_str(&fingerprint).unwrap(),
+    }))
+}
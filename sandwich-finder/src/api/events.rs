@@ -0,0 +1,87 @@
+use std::convert::Infallible;
+
+use axum::{body::Body, extract::{Query, State}, http::{header, StatusCode}, response::{IntoResponse, Response}};
+use mysql::{prelude::Queryable as _, Row, Value};
+use serde::Deserialize;
+use tokio::sync::mpsc;
+
+use crate::{api::ApiState, detector::{event_from_row, SLOTS_PER_HOUR}, redact::Redact as _};
+
+/// Widest range a single request may cover
+const MAX_SLOT_RANGE: u64 = SLOTS_PER_HOUR;
+/// Slots fetched per query while streaming
+const CHUNK_SLOTS: u64 = 100;
+
+#[derive(Deserialize)]
+pub struct EventQuery {
+    from_slot: u64,
+    to_slot: u64,
+    /// `swap` or `transfer`, both if omitted
+    #[serde(rename = "type")]
+    event_type: Option<String>,
+    amm: Option<String>,
+}
+
+/// Streams the events in `[from_slot, to_slot]` as newline delimited json, one event per line in chronological order.
+/// A stream cut short by a db error ends with a `{"error", "slot"}` line, `slot` being the first one missing.
+pub async fn handle_events(State(state): State<ApiState>, Query(query): Query<EventQuery>) -> Response {
+    if query.to_slot < query.from_slot || query.to_slot - query.from_slot >= MAX_SLOT_RANGE {
+        return (StatusCode::BAD_REQUEST, format!("slot range must be non-empty and span at most {MAX_SLOT_RANGE} slots")).into_response();
+    }
+    let event_type = match query.event_type.as_deref() {
+        None => None,
+        Some("swap") => Some("SWAP"),
+        Some("transfer") => Some("TRANSFER"),
+        Some(other) => return (StatusCode::BAD_REQUEST, format!("unknown event type {other}")).into_response(),
+    };
+    let mut filters = String::new();
+    let mut filter_params: Vec<Value> = vec![];
+    if let Some(event_type) = event_type {
+        filters.push_str(" and v.event_type=?");
+        filter_params.push(event_type.into());
+    }
+    if let Some(amm) = query.amm {
+        filters.push_str(" and v.amm=?");
+        filter_params.push(amm.into());
+    }
+    let stmt = format!("select v.*, t.block_time from event_view v left join transactions t on t.slot=v.slot and t.inclusion_order=v.inclusion_order where v.slot between ? and ?{filters} order by v.slot, v.inclusion_order, v.ix_index, v.inner_ix_index");
+    let pool = state.pool.clone();
+    let mut conn = match tokio::task::spawn_blocking(move || pool.get_conn().map_err(|e| e.to_string())).await.map_err(|e| e.to_string()).flatten() {
+        Ok(conn) => conn,
+        Err(e) => {
+            eprintln!("Failed to connect to the db for events: {}", e);
+            return (StatusCode::SERVICE_UNAVAILABLE, "unable to load events").into_response();
+        }
+    };
+    let (sender, receiver) = mpsc::channel::<String>(4);
+    tokio::task::spawn_blocking(move || {
+        let mut start = query.from_slot;
+        while start <= query.to_slot {
+            let end = (start + CHUNK_SLOTS - 1).min(query.to_slot);
+            let mut params: Vec<Value> = vec![start.into(), end.into()];
+            params.extend(filter_params.iter().cloned());
+            let rows: Vec<Row> = match conn.exec(&stmt, params) {
+                Ok(rows) => rows,
+                Err(e) => {
+                    eprintln!("Failed to load events: {}", e);
+                    // the status is long sent, a line the client can tell from an event is all that's left to say it's cut short
+                    let _ = sender.blocking_send(serde_json::json!({ "error": "unable to load events", "slot": start }).to_string() + "\n");
+                    return;
+                }
+            };
+            let lines = rows.iter().filter_map(event_from_row).map(|mut event| {
+                // external consumers get the same view of victims as the swap feed
+                event.redact(&state.redaction, true);
+                serde_json::to_string(&event).unwrap() + "\n"
+            }).collect::<String>();
+            if !lines.is_empty() && sender.blocking_send(lines).is_err() {
+                return; // Client disconnected
+            }
+            start = end + 1;
+        }
+    });
+    let stream = futures::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|lines| (Ok::<_, Infallible>(lines), receiver))
+    });
+    ([(header::CONTENT_TYPE, "application/x-ndjson")], Body::from_stream(stream)).into_response()
+}
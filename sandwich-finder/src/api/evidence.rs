@@ -0,0 +1,278 @@
+use std::{collections::{BTreeMap, BTreeSet, HashSet}, env, sync::Arc, time::{SystemTime, UNIX_EPOCH}};
+
+use axum::{extract::{Path, State}, http::StatusCode, response::{IntoResponse, Response}, Json};
+use serde::Serialize;
+use serde_json::{json, Value};
+use solana_rpc_client_api::request::RpcRequest;
+use solana_sdk::{commitment_config::CommitmentConfig, signature::{read_keypair_file, Keypair, Signer as _}};
+
+use crate::{api::{sandwich::{load_timeline, TimelineEntry}, ApiState}, config, events::{event::Event, sandwich::{ProfitTolerance, SandwichCandidate, SelectionPolicy}, swap::SwapV2}, rpc::BoundedRpc, utils::VictimLoss};
+
+/// Bumped whenever the layout of [`EvidenceReport`] changes
+const EVIDENCE_VERSION: u32 = 1;
+
+/// What's needed to produce evidence, the txs are fetched again from `RPC_URL` (or the `RPC_URLS` pool) at export
+/// time and the reports are signed with the keypair file at `EVIDENCE_KEYPAIR`
+pub struct EvidenceConfig {
+    rpc: BoundedRpc,
+    keypair: Keypair,
+}
+
+impl EvidenceConfig {
+    pub fn from_env() -> Option<Self> {
+        let rpc_url = config::get().rpc_url().clone()?;
+        let path = env::var("EVIDENCE_KEYPAIR").ok().filter(|p| !p.is_empty())?;
+        let keypair = match read_keypair_file(&path) {
+            Ok(keypair) => keypair,
+            Err(e) => {
+                eprintln!("Failed to read the evidence keypair at {}: {}", path, e);
+                return None;
+            }
+        };
+        Some(Self {
+            rpc: BoundedRpc::from_env(&rpc_url, CommitmentConfig::confirmed()),
+            keypair,
+        })
+    }
+}
+
+/// The detection settings of this deployment, which decide what counts as a sandwich
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectionInfo {
+    evidence_version: u32,
+    finder_version: &'static str,
+    selection: String,
+    profit_tolerance_bps: u64,
+    loss_model: &'static str,
+}
+
+impl DetectionInfo {
+    fn from_env() -> Self {
+        Self {
+            evidence_version: EVIDENCE_VERSION,
+            finder_version: env!("CARGO_PKG_VERSION"),
+            selection: format!("{:?}", SelectionPolicy::from_env()),
+            profit_tolerance_bps: ProfitTolerance::from_env().default_bps,
+            loss_model: "constant product, reserves solved from the frontrun and the first victim",
+        }
+    }
+}
+
+/// The attacker's legs summed up, profits are in raw units of each side's mint
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfitMath {
+    input_mint: Arc<str>,
+    output_mint: Arc<str>,
+    frontrun_spent: u64,
+    frontrun_received: u64,
+    backrun_spent: u64,
+    backrun_received: u64,
+    /// Backrun received less frontrun spent
+    profit_input_mint: i128,
+    /// Frontrun received less backrun spent
+    profit_output_mint: i128,
+    victim_losses: Vec<VictimLoss>,
+    est_victim_loss_lamports: u64,
+}
+
+impl ProfitMath {
+    fn new(candidate: &SandwichCandidate) -> Option<Self> {
+        let first = candidate.frontrun().first()?;
+        candidate.backrun().first()?;
+        let sum = |swaps: &[SwapV2], f: fn(&SwapV2) -> u64| swaps.iter().map(f).sum::<u64>();
+        let frontrun_spent = sum(candidate.frontrun(), |s| *s.input_amount());
+        let frontrun_received = sum(candidate.frontrun(), |s| *s.output_amount());
+        let backrun_spent = sum(candidate.backrun(), |s| *s.input_amount());
+        let backrun_received = sum(candidate.backrun(), |s| *s.output_amount());
+        Some(Self {
+            input_mint: first.input_mint().clone(),
+            output_mint: first.output_mint().clone(),
+            frontrun_spent,
+            frontrun_received,
+            backrun_spent,
+            backrun_received,
+            profit_input_mint: backrun_received as i128 - frontrun_spent as i128,
+            profit_output_mint: frontrun_received as i128 - backrun_spent as i128,
+            victim_losses: candidate.estimate_victim_losses(),
+            est_victim_loss_lamports: candidate.estimate_victim_loss_lamports(),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceDelta {
+    account: String,
+    lamports: i64,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenDelta {
+    account: String,
+    mint: String,
+    owner: Option<String>,
+    amount: i128,
+}
+
+/// A tx as returned by `getTransaction` with `jsonParsed` encoding, which decodes the instructions of well known
+/// programs, along with the balance changes it made
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvidenceTx {
+    sig: Arc<str>,
+    balance_deltas: Vec<BalanceDelta>,
+    token_deltas: Vec<TokenDelta>,
+    transaction: Value,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvidenceReport {
+    sandwich_id: Arc<str>,
+    generated_at: u64,
+    detection: DetectionInfo,
+    events: Vec<TimelineEntry>,
+    profit: Option<ProfitMath>,
+    txs: Vec<EvidenceTx>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedEvidence {
+    report: Value,
+    signer: String,
+    /// Base58 ed25519 signature over [`canonical_json`] of `report`
+    signature: String,
+}
+
+/// Compact JSON with object keys sorted, so anyone can reproduce the signed bytes from the report
+fn canonical_json(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let sorted: BTreeMap<_, _> = map.iter().collect();
+            let fields: Vec<_> = sorted.into_iter().map(|(k, v)| format!("{}:{}", Value::String(k.clone()), canonical_json(v))).collect();
+            format!("{{{}}}", fields.join(","))
+        },
+        Value::Array(values) => format!("[{}]", values.iter().map(canonical_json).collect::<Vec<_>>().join(",")),
+        _ => value.to_string(),
+    }
+}
+
+/// Lamport and token balance changes from a tx's meta, accounts that didn't change are left out
+fn balance_deltas(tx: &Value) -> (Vec<BalanceDelta>, Vec<TokenDelta>) {
+    // plain strings with `json` encoding, objects with `jsonParsed` which also includes the addresses loaded from lookup tables
+    let keys: Vec<String> = tx["transaction"]["message"]["accountKeys"].as_array().map(|keys| keys.iter().filter_map(|k| k.as_str().or(k["pubkey"].as_str()).map(str::to_string)).collect()).unwrap_or_default();
+    let meta = &tx["meta"];
+    let lamports = |field: &str| meta[field].as_array().map(|b| b.iter().map(|v| v.as_u64().unwrap_or(0)).collect::<Vec<_>>()).unwrap_or_default();
+    let (pre, post) = (lamports("preBalances"), lamports("postBalances"));
+    let balance_deltas = pre.iter().zip(post.iter()).enumerate().filter(|(_, (pre, post))| pre != post).filter_map(|(i, (pre, post))| Some(BalanceDelta {
+        account: keys.get(i)?.clone(),
+        lamports: *post as i64 - *pre as i64,
+    })).collect();
+    // account index to (mint, owner, amount)
+    let token_balances = |field: &str| meta[field].as_array().map(|balances| balances.iter().filter_map(|b| Some((
+        b["accountIndex"].as_u64()? as usize,
+        (b["mint"].as_str()?.to_string(), b["owner"].as_str().map(str::to_string), b["uiTokenAmount"]["amount"].as_str()?.parse::<i128>().ok()?),
+    ))).collect::<BTreeMap<_, _>>()).unwrap_or_default();
+    let (pre, post) = (token_balances("preTokenBalances"), token_balances("postTokenBalances"));
+    let indexes: BTreeSet<_> = pre.keys().chain(post.keys()).copied().collect();
+    let token_deltas = indexes.into_iter().filter_map(|i| {
+        let (mint, owner, _) = post.get(&i).or(pre.get(&i))?.clone();
+        let amount = post.get(&i).map_or(0, |b| b.2) - pre.get(&i).map_or(0, |b| b.2);
+        (amount != 0).then_some(TokenDelta { account: keys.get(i)?.clone(), mint, owner, amount })
+    }).collect();
+    (balance_deltas, token_deltas)
+}
+
+async fn fetch_tx(rpc: &BoundedRpc, sig: &str) -> Result<Value, String> {
+    let params = json!([sig, {"encoding": "jsonParsed", "maxSupportedTransactionVersion": 0, "commitment": "confirmed"}]);
+    let tx: Value = rpc.call(|client| client.send(RpcRequest::GetTransaction, params.clone())).await.map_err(|e| e.to_string())?;
+    if tx.is_null() {
+        return Err("not found".to_string());
+    }
+    Ok(tx)
+}
+
+/// A signed, self-contained report of a sandwich for disputes and public callouts: the detected events, the txs
+/// fetched again from RPC with their decoded instructions and balance changes, the profit and loss math, and the
+/// detection settings. Unavailable while victims are redacted, as the raw txs would reveal them.
+pub async fn handle_evidence(State(state): State<ApiState>, Path(id): Path<String>) -> Response {
+    let Some(config) = state.evidence.clone() else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    if state.redaction.is_enabled() {
+        return (StatusCode::FORBIDDEN, "victims are redacted").into_response();
+    }
+    let events = load_timeline(&mut state.pool.get_conn().unwrap(), &id, &state.redaction);
+    if events.is_empty() {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    let (mut frontrun, mut victim, mut backrun, mut transfers, mut conversions) = (vec![], vec![], vec![], vec![], vec![]);
+    for entry in events.iter() {
+        match (entry.role().as_ref(), entry.event()) {
+            ("FRONTRUN", Event::Swap(swap)) => frontrun.push(swap.clone()),
+            ("VICTIM", Event::Swap(swap)) => victim.push(swap.clone()),
+            ("BACKRUN", Event::Swap(swap)) => backrun.push(swap.clone()),
+            ("TRANSFER", Event::Transfer(transfer)) => transfers.push(transfer.clone()),
+            ("CONVERSION", Event::Swap(swap)) => conversions.push(swap.clone()),
+            _ => {},
+        }
+    }
+    let candidate = SandwichCandidate::from_parts(frontrun, victim, backrun, transfers, vec![]).with_conversions(conversions);
+    let mut seen = HashSet::new();
+    let sigs: Vec<Arc<str>> = events.iter().filter_map(|e| e.sig().clone()).filter(|sig| seen.insert(sig.clone())).collect();
+    let mut txs = Vec::with_capacity(sigs.len());
+    for sig in sigs {
+        let transaction = match fetch_tx(&config.rpc, &sig).await {
+            Ok(tx) => tx,
+            Err(e) => return (StatusCode::BAD_GATEWAY, format!("Failed to fetch {}: {}", sig, e)).into_response(),
+        };
+        let (balance_deltas, token_deltas) = balance_deltas(&transaction);
+        txs.push(EvidenceTx { sig, balance_deltas, token_deltas, transaction });
+    }
+    let report = EvidenceReport {
+        sandwich_id: id.into(),
+        generated_at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+        detection: DetectionInfo::from_env(),
+        profit: ProfitMath::new(&candidate),
+        events,
+        txs,
+    };
+    let report = serde_json::to_value(&report).unwrap();
+    let signature = config.keypair.sign_message(canonical_json(&report).as_bytes());
+    Json(SignedEvidence {
+        report,
+        signer: config.keypair.pubkey().to_string(),
+        signature: signature.to_string(),
+    }).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_json() {
+        let value = json!({"b": [1, {"d": "x", "c": null}], "a": true});
+        assert_eq!(canonical_json(&value), r#"{"a":true,"b":[1,{"c":null,"d":"x"}]}"#);
+    }
+
+    #[test]
+    fn test_balance_deltas() {
+        let tx = json!({
+            "transaction": {"message": {"accountKeys": [{"pubkey": "payer"}, {"pubkey": "ata"}, {"pubkey": "program"}]}},
+            "meta": {
+                "preBalances": [1000, 2039280, 1],
+                "postBalances": [900, 2039280, 1],
+                "preTokenBalances": [{"accountIndex": 1, "mint": "mint", "owner": "payer", "uiTokenAmount": {"amount": "500"}}],
+                "postTokenBalances": [{"accountIndex": 1, "mint": "mint", "owner": "payer", "uiTokenAmount": {"amount": "800"}}],
+            },
+        });
+        let (lamports, tokens) = balance_deltas(&tx);
+        assert_eq!(lamports, vec![BalanceDelta { account: "payer".to_string(), lamports: -100 }]);
+        assert_eq!(tokens, vec![TokenDelta { account: "ata".to_string(), mint: "mint".to_string(), owner: Some("payer".to_string()), amount: 300 }]);
+    }
+}
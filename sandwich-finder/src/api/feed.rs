@@ -0,0 +1,94 @@
+use std::{collections::HashSet, sync::Arc};
+
+use axum::{extract::{ws::{Message, WebSocket}, Query, State, WebSocketUpgrade}, response::IntoResponse, routing::get, Router};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::{events::{event::Event, swap::SwapV2}, redact::{Redact as _, Redaction}};
+
+/// Events of a slot as emitted by the event processor
+pub type SlotEvents = (u64, Arc<[Event]>);
+
+/// Comma separated lists, a swap passes if it matches any of the AMMs and any of the mints
+#[derive(Debug, Default, Deserialize)]
+pub struct FeedFilter {
+    amm: Option<String>,
+    mint: Option<String>,
+}
+
+struct ParsedFilter {
+    amms: HashSet<String>,
+    mints: HashSet<String>,
+}
+
+impl ParsedFilter {
+    fn new(filter: FeedFilter) -> Self {
+        let split = |s: Option<String>| s.map(|s| s.split(',').filter(|x| !x.is_empty()).map(|x| x.to_string()).collect()).unwrap_or_default();
+        Self {
+            amms: split(filter.amm),
+            mints: split(filter.mint),
+        }
+    }
+
+    fn matches(&self, swap: &SwapV2) -> bool {
+        (self.amms.is_empty() || self.amms.contains(swap.amm().as_ref()))
+            && (self.mints.is_empty() || self.mints.contains(swap.input_mint().as_ref()) || self.mints.contains(swap.output_mint().as_ref()))
+    }
+}
+
+#[derive(Serialize)]
+struct SwapBatch {
+    slot: u64,
+    swaps: Vec<SwapV2>,
+}
+
+#[derive(Clone)]
+struct FeedState {
+    sender: broadcast::Sender<SlotEvents>,
+    redaction: Redaction,
+}
+
+/// Serves `/swaps`, a per-slot batched feed of swaps filtered by `?amm=` and/or `?mint=`
+pub fn router(sender: broadcast::Sender<SlotEvents>) -> Router {
+    Router::new()
+        .route("/swaps", get(handle_swap_feed))
+        .with_state(FeedState {
+            sender,
+            redaction: Redaction::from_env(),
+        })
+}
+
+async fn handle_swap_feed(
+    ws: WebSocketUpgrade,
+    Query(filter): Query<FeedFilter>,
+    State(state): State<FeedState>,
+) -> impl IntoResponse {
+    let filter = ParsedFilter::new(filter);
+    ws.on_upgrade(move |socket| handle_swap_socket(socket, state.sender.subscribe(), filter, state.redaction))
+}
+
+async fn handle_swap_socket(mut socket: WebSocket, mut receiver: broadcast::Receiver<SlotEvents>, filter: ParsedFilter, redaction: Redaction) {
+    loop {
+        let (slot, events) = match receiver.recv().await {
+            Ok(msg) => msg,
+            // slow clients skip slots instead of holding the whole feed back
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        };
+        let swaps: Vec<_> = events.iter().filter_map(|e| match e {
+            // any swap in the feed could be a victim's
+            Event::Swap(swap) if filter.matches(swap) => Some(swap.clone()).map(|mut swap| {
+                swap.redact(&redaction, true);
+                swap
+            }),
+            _ => None,
+        }).collect();
+        if swaps.is_empty() {
+            continue;
+        }
+        let msg = serde_json::to_string(&SwapBatch { slot, swaps }).unwrap();
+        if socket.send(Message::Text(msg.into())).await.is_err() {
+            break; // Client disconnected
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use axum::{middleware, routing::{delete, get, post}, Router};
+use mysql::{prelude::Queryable as _, PooledConn};
+use tokio::sync::broadcast;
+
+use crate::{api::{action::ActionsConfig, evidence::EvidenceConfig, notify::{NewAttacker, Notification}, tenant::Tenants}, metrics, redact::Redaction, replica::ReadPool};
+
+pub mod action;
+pub mod anomalies;
+pub mod cluster;
+pub mod events;
+pub mod evidence;
+pub mod feed;
+pub mod notify;
+pub mod pool;
+pub mod sandwich;
+pub mod snipes;
+pub mod stats;
+pub mod summary;
+pub mod tenant;
+pub mod wallet;
+
+#[derive(Clone)]
+pub struct ApiState {
+    pool: ReadPool,
+    redaction: Redaction,
+    notifications: broadcast::Sender<Arc<[Notification]>>,
+    new_attackers: broadcast::Sender<Arc<[NewAttacker]>>,
+    tenants: Tenants,
+    evidence: Option<Arc<EvidenceConfig>>,
+    actions: Option<Arc<ActionsConfig>>,
+}
+
+/// Routes backed by the V2 tables, to be merged into the web server's router.
+/// Also starts the notifier for registered victim wallets.
+/// Requests are made on behalf of the tenant of their API key, see [`tenant::authenticate`], and the key management
+/// routes are only there when an admin token is configured. The Solana Actions routes are public so wallets can
+/// unfurl them without a key.
+pub fn router(pool: ReadPool) -> Router {
+    let (notifications, _) = broadcast::channel(100);
+    let (new_attackers, _) = broadcast::channel(100);
+    // it moves the wallets' cursors, so it stays on the primary
+    notify::start_notifier(pool.primary().clone(), notifications.clone(), new_attackers.clone());
+    let state = ApiState {
+        pool,
+        redaction: Redaction::from_env(),
+        notifications,
+        new_attackers,
+        tenants: Tenants::from_env(),
+        evidence: EvidenceConfig::from_env().map(Arc::new),
+        actions: ActionsConfig::from_env().map(Arc::new),
+    };
+    let mut router = Router::new()
+        .route("/sandwich/{id}/timeline", get(sandwich::handle_timeline))
+        .route("/summary", get(summary::handle_summary))
+        .route("/stats/dont-front", get(stats::handle_dont_front))
+        .route("/stats/mints", get(stats::handle_mint_stats))
+        .route("/stats/positions", get(stats::handle_positions))
+        .route("/stats/leader-timing", get(stats::handle_leader_timing))
+        .route("/stats/cu", get(stats::handle_cu_stats))
+        .route("/stats/programs/timeseries", get(stats::handle_program_timeseries))
+        .route("/stats/routing", get(stats::handle_routing))
+        .route("/stats/cohorts", get(stats::handle_cohorts))
+        .route("/wallet/{pubkey}/summary", get(wallet::handle_wallet_summary))
+        .route("/snipes", get(snipes::handle_snipes))
+        .route("/anomalies", get(anomalies::handle_anomalies))
+        .route("/pool/{amm}/price", get(pool::handle_pool_price))
+        .route("/cluster/{id}/fingerprint", get(cluster::handle_fingerprint))
+        .route("/events", get(events::handle_events))
+        .route("/notify/register", post(notify::handle_register))
+        .route("/notify/unregister", post(notify::handle_unregister))
+        .route("/notify/ws", get(notify::handle_notify_socket))
+        .route("/notify/attackers/ws", get(notify::handle_attackers_socket));
+    if state.evidence.is_some() {
+        router = router.route("/sandwich/{id}/evidence", get(evidence::handle_evidence));
+    }
+    router = router
+        .layer(middleware::from_fn_with_state(state.clone(), tenant::authenticate));
+    if state.tenants.has_admin() {
+        router = router
+            .route("/admin/keys", get(tenant::handle_list_keys).post(tenant::handle_create_key))
+            .route("/admin/keys/{id}", delete(tenant::handle_revoke_key));
+    }
+    if state.actions.is_some() {
+        router = router.merge(Router::new()
+            .route("/actions.json", get(action::handle_actions_json).options(action::handle_preflight))
+            .route("/action/check", get(action::handle_check_metadata).post(action::handle_check).options(action::handle_preflight))
+            .layer(middleware::from_fn(action::action_headers)));
+    }
+    router.with_state(state)
+}
+
+/// (chain tip seen by this process, latest indexed slot)
+fn latest_slots(conn: &mut PooledConn) -> (Option<u64>, Option<u64>) {
+    let chain_tip = Some(metrics::get("chain_tip_slot")).filter(|&s| s > 0);
+    let processed_slot: Option<u64> = conn.query_first("select max(slot) from transactions").unwrap().flatten();
+    (chain_tip, processed_slot)
+}
+
+/// The slot relative time windows end at
+fn anchor_slot(conn: &mut PooledConn) -> u64 {
+    let (chain_tip, processed_slot) = latest_slots(conn);
+    chain_tip.or(processed_slot).unwrap_or(0)
+}
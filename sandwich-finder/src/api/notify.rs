@@ -0,0 +1,382 @@
+use std::{env, net::IpAddr, str::FromStr as _, sync::Arc, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+use axum::{extract::{ws::{Message, WebSocket}, Query, State, WebSocketUpgrade}, Extension, http::StatusCode, response::{IntoResponse, Response}, Json};
+use mysql::{prelude::Queryable as _, Pool, PooledConn, Value};
+use serde::{Deserialize, Serialize};
+use reqwest::{redirect, Url};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::{api::{anchor_slot, tenant::Tenant, ApiState}, detector::SLOTS_PER_HOUR};
+
+/// Signed messages older than this are rejected so they can't be replayed later
+const MAX_MESSAGE_AGE_SECS: u64 = 300;
+/// Notifications fetched per wallet per poll, a historical scan catches up over several polls
+const MAX_BATCH: u64 = 1000;
+/// Attackers first seen longer ago than this, e.g. by a backfill, are registered without being announced
+const NEW_ATTACKER_MAX_AGE_SLOTS: u64 = SLOTS_PER_HOUR;
+
+/// A sandwich one of the registered wallets was a victim of
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Notification {
+    /// Subscriptions are per tenant, this keeps one tenant's listeners from getting another's notifications
+    #[serde(skip)]
+    tenant: Arc<str>,
+    wallet: Arc<str>,
+    sandwich_id: Arc<str>,
+    #[serde(skip)]
+    event_id: u64,
+    slot: u64,
+    inclusion_order: u32,
+    sig: Option<Arc<str>>,
+    amm: Arc<str>,
+    input_mint: Arc<str>,
+    output_mint: Arc<str>,
+    input_amount: u64,
+    output_amount: u64,
+}
+
+/// A frontrun authority that wasn't part of any attacker cluster when first seen, with the frontrun of its first sandwich
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewAttacker {
+    attacker: Arc<str>,
+    sandwich_id: Arc<str>,
+    slot: u64,
+    sig: Option<Arc<str>>,
+    amm: Arc<str>,
+    input_mint: Arc<str>,
+    output_mint: Arc<str>,
+    input_amount: u64,
+    output_amount: u64,
+    victims: u64,
+}
+
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    wallet: &'a str,
+    notifications: &'a [Notification],
+}
+
+/// The wallet proves ownership by signing `sandwich-finder <action> <wallet> [<webhook>] <timestamp>`
+#[derive(Deserialize)]
+pub struct SignedRequest {
+    wallet: String,
+    /// Unix seconds, also part of the signed message
+    timestamp: u64,
+    /// Base58 signature of the message by the wallet
+    signature: String,
+    webhook: Option<String>,
+    /// Scan for sandwiches since this slot on registration, only new ones are pushed otherwise
+    since_slot: Option<u64>,
+}
+
+impl SignedRequest {
+    fn verify(&self, action: &str) -> Result<(), (StatusCode, &'static str)> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        if self.timestamp.abs_diff(now) > MAX_MESSAGE_AGE_SECS {
+            return Err((StatusCode::BAD_REQUEST, "timestamp too far from now"));
+        }
+        let pubkey = Pubkey::from_str(&self.wallet).map_err(|_| (StatusCode::BAD_REQUEST, "invalid wallet"))?;
+        let signature = Signature::from_str(&self.signature).map_err(|_| (StatusCode::BAD_REQUEST, "invalid signature"))?;
+        let message = match &self.webhook {
+            Some(webhook) => format!("sandwich-finder {action} {} {webhook} {}", self.wallet, self.timestamp),
+            None => format!("sandwich-finder {action} {} {}", self.wallet, self.timestamp),
+        };
+        if !signature.verify(pubkey.as_ref(), message.as_bytes()) {
+            return Err((StatusCode::UNAUTHORIZED, "signature mismatch"));
+        }
+        Ok(())
+    }
+}
+
+/// Opts a wallet in for the request's tenant, replacing its previous registration with the tenant
+pub async fn handle_register(State(state): State<ApiState>, Extension(tenant): Extension<Tenant>, Json(request): Json<SignedRequest>) -> Response {
+    if let Err(e) = request.verify("register") {
+        return e.into_response();
+    }
+    if let Some(webhook) = &request.webhook {
+        if let Err(e) = webhook_client(webhook).await {
+            return (StatusCode::BAD_REQUEST, e).into_response();
+        }
+    }
+    let mut conn = state.pool.primary().get_conn().unwrap();
+    let cursor_slot = match request.since_slot {
+        Some(slot) => slot.saturating_sub(1),
+        None => anchor_slot(&mut conn),
+    };
+    conn.exec_drop(
+        "insert into notification_subscriptions (tenant, wallet, webhook, cursor_slot, cursor_event_id) values (?, ?, ?, ?, null) on duplicate key update webhook=values(webhook), cursor_slot=values(cursor_slot), cursor_event_id=null",
+        (&*tenant.name, &request.wallet, &request.webhook, cursor_slot),
+    ).unwrap();
+    StatusCode::NO_CONTENT.into_response()
+}
+
+pub async fn handle_unregister(State(state): State<ApiState>, Extension(tenant): Extension<Tenant>, Json(request): Json<SignedRequest>) -> Response {
+    if let Err(e) = request.verify("unregister") {
+        return e.into_response();
+    }
+    let mut conn = state.pool.primary().get_conn().unwrap();
+    conn.exec_drop("delete from notification_subscriptions where tenant=? and wallet=?", (&*tenant.name, &request.wallet)).unwrap();
+    StatusCode::NO_CONTENT.into_response()
+}
+
+/// Whether `ip` is reachable from the internet, webhooks can't point at the host itself or its private network
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            // 100.64.0.0/10 is carrier-grade nat, 0.0.0.0/8 is "this network"
+            !(ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified() || ip.is_broadcast() || ip.is_multicast() || ip.is_documentation() || a == 0 || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(ip));
+            }
+            let first = ip.segments()[0];
+            // fc00::/7 is unique local, fe80::/10 link-local
+            !(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast() || (first & 0xfe00) == 0xfc00 || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// A client for posting to `webhook`, which has to be https and resolve to public addresses only.
+/// The client is pinned to the addresses checked so the host can't resolve elsewhere by the time it's posted to, and
+/// doesn't follow redirects, which could lead anywhere.
+async fn webhook_client(webhook: &str) -> Result<reqwest::Client, &'static str> {
+    let url = Url::parse(webhook).map_err(|_| "invalid webhook url")?;
+    if url.scheme() != "https" {
+        return Err("webhook must be an https url");
+    }
+    let host = url.host_str().ok_or("webhook has no host")?;
+    let port = url.port_or_known_default().unwrap_or(443);
+    let addrs: Vec<_> = tokio::net::lookup_host((host.trim_start_matches('[').trim_end_matches(']'), port)).await.map_err(|_| "webhook host doesn't resolve")?.collect();
+    if addrs.is_empty() || addrs.iter().any(|addr| !is_public_ip(addr.ip())) {
+        return Err("webhook must resolve to public addresses");
+    }
+    reqwest::Client::builder().redirect(redirect::Policy::none()).resolve_to_addrs(host, &addrs).build().map_err(|_| "unable to build the webhook client")
+}
+
+/// Pushes the notifications of a registered wallet over websocket, signed with the `listen` action
+pub async fn handle_notify_socket(ws: WebSocketUpgrade, State(state): State<ApiState>, Extension(tenant): Extension<Tenant>, Query(request): Query<SignedRequest>) -> Response {
+    if let Err(e) = request.verify("listen") {
+        return e.into_response();
+    }
+    let receiver = state.notifications.subscribe();
+    ws.on_upgrade(move |socket| notify_socket(socket, receiver, tenant.name, request.wallet)).into_response()
+}
+
+async fn notify_socket(mut socket: WebSocket, mut receiver: broadcast::Receiver<Arc<[Notification]>>, tenant: Arc<str>, wallet: String) {
+    loop {
+        let notifications = match receiver.recv().await {
+            Ok(msg) => msg,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        };
+        if notifications.is_empty() || notifications[0].tenant != tenant || notifications[0].wallet.as_ref() != wallet {
+            continue;
+        }
+        let msg = serde_json::to_string(&*notifications).unwrap();
+        if socket.send(Message::Text(msg.into())).await.is_err() {
+            break; // Client disconnected
+        }
+    }
+}
+
+/// Pushes every new attacker as it's announced, to any client of the tenant
+pub async fn handle_attackers_socket(ws: WebSocketUpgrade, State(state): State<ApiState>) -> Response {
+    let receiver = state.new_attackers.subscribe();
+    ws.on_upgrade(move |socket| attackers_socket(socket, receiver)).into_response()
+}
+
+async fn attackers_socket(mut socket: WebSocket, mut receiver: broadcast::Receiver<Arc<[NewAttacker]>>) {
+    loop {
+        let attackers = match receiver.recv().await {
+            Ok(msg) => msg,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        };
+        let msg = serde_json::to_string(&*attackers).unwrap();
+        if socket.send(Message::Text(msg.into())).await.is_err() {
+            break; // Client disconnected
+        }
+    }
+}
+
+/// Announces the attackers the detector registered as new, see [`crate::events::common::Inserter`],
+/// marking them as notified whether they were recent enough to be announced or not
+fn announce_attackers(conn: &mut PooledConn, sender: &broadcast::Sender<Arc<[NewAttacker]>>) -> Result<(), mysql::Error> {
+    let pending: Vec<(u32, String, u64, String)> = conn.exec(
+        "select a.attacker_id, addr.address, a.first_slot, a.first_sandwich_id from attackers a join address_lookup_table addr on addr.id=a.attacker_id where a.notified=0 order by a.first_slot limit ?",
+        (MAX_BATCH,),
+    )?;
+    if pending.is_empty() {
+        return Ok(());
+    }
+    let since_slot = anchor_slot(conn).saturating_sub(NEW_ATTACKER_MAX_AGE_SLOTS);
+    let mut attackers = vec![];
+    for (_, attacker, slot, sandwich_id) in pending.iter().filter(|a| a.2 >= since_slot) {
+        let frontrun: Option<(Option<String>, String, String, String, u64, u64)> = conn.exec_first(
+            "select t.sig, v.amm, v.input_mint, v.output_mint, v.input_amount, v.output_amount from sandwiches s join event_view v on v.id=s.event_id left join transactions t on t.slot=v.slot and t.inclusion_order=v.inclusion_order where s.id=? and s.role='FRONTRUN' order by v.slot, v.inclusion_order, v.ix_index, v.inner_ix_index limit 1",
+            (sandwich_id,),
+        )?;
+        let Some((sig, amm, input_mint, output_mint, input_amount, output_amount)) = frontrun else {
+            continue;
+        };
+        let victims: Option<u64> = conn.exec_first("select count(*) from sandwiches where id=? and role='VICTIM'", (sandwich_id,))?;
+        attackers.push(NewAttacker {
+            attacker: attacker.as_str().into(),
+            sandwich_id: sandwich_id.as_str().into(),
+            slot: *slot,
+            sig: sig.map(|s| s.into()),
+            amm: amm.into(),
+            input_mint: input_mint.into(),
+            output_mint: output_mint.into(),
+            input_amount,
+            output_amount,
+            victims: victims.unwrap_or(0),
+        });
+    }
+    let ids: Vec<Value> = pending.iter().map(|a| Value::from(a.0)).collect();
+    conn.exec_drop(format!("update attackers set notified=1 where attacker_id in ({})", "?,".repeat(ids.len()).trim_end_matches(",")), ids)?;
+    if !attackers.is_empty() {
+        println!("announcing {} new attackers", attackers.len());
+        let _ = sender.send(attackers.into());
+    }
+    Ok(())
+}
+
+// tenant, wallet, webhook, cursor slot, cursor event id
+type SubscriptionRow = (String, String, Option<String>, u64, Option<u64>);
+// sandwich id, event id, slot, inclusion order, sig, amm, input mint, output mint, input amount, output amount
+type NotificationRow = (String, u64, u64, u32, Option<String>, String, String, String, u64, u64);
+
+/// Polls for new victim sandwiches of every registered wallet, pushing them to its webhook and websocket listeners.
+/// A wallet's cursor only moves once its webhook accepted the batch, so failed deliveries are retried.
+/// The cursor is the last slot and event delivered, a batch cut off in the middle of a slot resumes within it.
+/// New attackers are announced to `new_attackers` along the way.
+/// Polls every `NOTIFY_POLL_SECS` seconds, 10 by default.
+pub fn start_notifier(pool: Pool, sender: broadcast::Sender<Arc<[Notification]>>, new_attackers: broadcast::Sender<Arc<[NewAttacker]>>) {
+    let period = env::var("NOTIFY_POLL_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(10);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(period));
+        loop {
+            interval.tick().await;
+            let mut conn = match pool.get_conn() {
+                Ok(conn) => conn,
+                Err(e) => {
+                    eprintln!("Notifier unable to connect: {}", e);
+                    continue;
+                }
+            };
+            if let Err(e) = announce_attackers(&mut conn, &new_attackers) {
+                eprintln!("Failed to announce new attackers: {}", e);
+            }
+            let subscriptions: Vec<SubscriptionRow> = match conn.query("select tenant, wallet, webhook, cursor_slot, cursor_event_id from notification_subscriptions") {
+                Ok(subscriptions) => subscriptions,
+                Err(e) => {
+                    eprintln!("Failed to load notification subscriptions: {}", e);
+                    continue;
+                }
+            };
+            for (tenant, wallet, webhook, cursor_slot, cursor_event_id) in subscriptions {
+                let tenant: Arc<str> = tenant.into();
+                // no event id means the whole slot was delivered
+                let cursor_event_id = cursor_event_id.unwrap_or(u64::MAX);
+                let notifications = conn.exec_map(
+                    "select s.id, v.id, v.slot, v.inclusion_order, t.sig, v.amm, v.input_mint, v.output_mint, v.input_amount, v.output_amount from sandwiches s join event_view v on v.id=s.event_id left join transactions t on t.slot=v.slot and t.inclusion_order=v.inclusion_order where s.role='VICTIM' and v.authority=? and (v.slot>? or (v.slot=? and v.id>?)) order by v.slot, v.id limit ?",
+                    (&wallet, cursor_slot, cursor_slot, cursor_event_id, MAX_BATCH),
+                    |(sandwich_id, event_id, slot, inclusion_order, sig, amm, input_mint, output_mint, input_amount, output_amount): NotificationRow| Notification {
+                        tenant: tenant.clone(),
+                        wallet: wallet.as_str().into(),
+                        sandwich_id: sandwich_id.into(),
+                        event_id,
+                        slot,
+                        inclusion_order,
+                        sig: sig.map(|s| s.into()),
+                        amm: amm.into(),
+                        input_mint: input_mint.into(),
+                        output_mint: output_mint.into(),
+                        input_amount,
+                        output_amount,
+                    },
+                );
+                let notifications: Vec<Notification> = match notifications {
+                    Ok(notifications) => notifications,
+                    Err(e) => {
+                        eprintln!("Failed to load notifications for {}: {}", wallet, e);
+                        continue;
+                    }
+                };
+                let Some((last_slot, last_event_id)) = notifications.last().map(|n| (n.slot, n.event_id)) else {
+                    continue;
+                };
+                if let Some(webhook) = &webhook {
+                    // checked again on every delivery, the host may resolve elsewhere since it was registered
+                    let client = match webhook_client(webhook).await {
+                        Ok(client) => client,
+                        Err(e) => {
+                            eprintln!("Webhook for {} rejected: {}", wallet, e);
+                            continue;
+                        }
+                    };
+                    let res = client.post(webhook).json(&WebhookPayload { wallet: &wallet, notifications: &notifications }).timeout(Duration::from_secs(10)).send().await;
+                    if let Err(e) = res.and_then(|r| r.error_for_status()) {
+                        eprintln!("Webhook for {} failed: {}", wallet, e);
+                        continue;
+                    }
+                }
+                let _ = sender.send(notifications.into());
+                if let Err(e) = conn.exec_drop("update notification_subscriptions set cursor_slot=?, cursor_event_id=? where tenant=? and wallet=?", (last_slot, last_event_id, &*tenant, &wallet)) {
+                    eprintln!("Failed to move the notification cursor of {}: {}", wallet, e);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::signature::{Keypair, Signer as _};
+
+    use super::*;
+
+    #[test]
+    fn test_signed_request() {
+        let keypair = Keypair::new();
+        let wallet = keypair.pubkey().to_string();
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let webhook = "https://example.com/hook".to_string();
+        let message = format!("sandwich-finder register {wallet} {webhook} {timestamp}");
+        let request = SignedRequest {
+            wallet,
+            timestamp,
+            signature: keypair.sign_message(message.as_bytes()).to_string(),
+            webhook: Some(webhook),
+            since_slot: None,
+        };
+        assert!(request.verify("register").is_ok());
+        assert_eq!(request.verify("unregister").unwrap_err().0, StatusCode::UNAUTHORIZED);
+        let stale = SignedRequest { timestamp: timestamp - MAX_MESSAGE_AGE_SECS - 1, ..request };
+        assert_eq!(stale.verify("register").unwrap_err().0, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_is_public_ip() {
+        for ip in ["127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "100.64.0.1", "0.0.0.0", "::1", "fd00::1", "fe80::1", "::ffff:127.0.0.1"] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["1.1.1.1", "100.128.0.1", "2606:4700::1111"] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_webhook_client() {
+        assert!(webhook_client("http://example.com/hook").await.is_err());
+        assert!(webhook_client("https://127.0.0.1/hook").await.is_err());
+        assert!(webhook_client("https://[::1]:8443/hook").await.is_err());
+        assert!(webhook_client("https://169.254.169.254/latest/meta-data").await.is_err());
+    }
+}
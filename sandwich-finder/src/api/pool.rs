@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use axum::{extract::{Path, Query, State}, http::StatusCode, response::{IntoResponse, Response}, Json};
+use mysql::prelude::Queryable as _;
+use serde::{Deserialize, Serialize};
+
+use crate::{api::ApiState, events::addresses::WSOL_MINT};
+
+const DEFAULT_WINDOW_SLOTS: u64 = 2;
+const MAX_WINDOW_SLOTS: u64 = 50;
+
+// (event id, slot, inclusion order, ix index, input mint, output mint, input amount, output amount)
+type SwapRow = (u64, u64, u32, u32, String, String, u64, u64);
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceQuery {
+    slot: u64,
+    /// The price is taken right before this tx of the slot, the start of the slot by default
+    #[serde(default)]
+    inclusion_order: u32,
+    /// Slots of swaps to return either side of `slot`
+    window: Option<u64>,
+}
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PricePoint {
+    event_id: u64,
+    slot: u64,
+    inclusion_order: u32,
+    ix_index: u32,
+    /// Whether the swap bought the base mint
+    buy: bool,
+    /// Quote per base in raw amounts, not adjusted for the mints' decimals
+    price: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolPrice {
+    amm: Arc<str>,
+    base_mint: Arc<str>,
+    quote_mint: Arc<str>,
+    /// Price set by the last swap before the requested point
+    before: Option<PricePoint>,
+    /// Price set by the first swap at or after it
+    after: Option<PricePoint>,
+    /// Every swap of the pair in the window, in execution order
+    points: Vec<PricePoint>,
+}
+
+/// The pair a pool's swaps are priced in as (base, quote), quoted in SOL if it's one of the mints
+fn price_pair(input_mint: &str, output_mint: &str) -> (Arc<str>, Arc<str>) {
+    if input_mint == WSOL_MINT.to_string() {
+        (output_mint.into(), input_mint.into())
+    } else {
+        (input_mint.into(), output_mint.into())
+    }
+}
+
+/// Quote per base implied by a swap, `None` if it isn't on the pair or moved nothing
+fn implied_price(row: &SwapRow, base: &str, quote: &str) -> Option<PricePoint> {
+    let (event_id, slot, inclusion_order, ix_index, input_mint, output_mint, input_amount, output_amount) = row;
+    let (buy, base_amount, quote_amount) = if input_mint == quote && output_mint == base {
+        (true, output_amount, input_amount)
+    } else if input_mint == base && output_mint == quote {
+        (false, input_amount, output_amount)
+    } else {
+        return None;
+    };
+    if *base_amount == 0 {
+        return None;
+    }
+    Some(PricePoint {
+        event_id: *event_id,
+        slot: *slot,
+        inclusion_order: *inclusion_order,
+        ix_index: *ix_index,
+        buy,
+        price: *quote_amount as f64 / *base_amount as f64,
+    })
+}
+
+/// Implied price of a pool around a point in the slot ordering, rebuilt from its stored swaps
+pub async fn handle_pool_price(State(state): State<ApiState>, Path(amm): Path<String>, Query(query): Query<PriceQuery>) -> Response {
+    let window = query.window.unwrap_or(DEFAULT_WINDOW_SLOTS).min(MAX_WINDOW_SLOTS);
+    let mut conn = state.pool.get_conn().unwrap();
+    let rows: Vec<SwapRow> = conn.exec(
+        "select id, slot, inclusion_order, ix_index, input_mint, output_mint, input_amount, output_amount from event_view where amm=? and event_type='SWAP' and slot between ? and ? order by slot, inclusion_order, ix_index, inner_ix_index",
+        (&amm, query.slot.saturating_sub(window), query.slot + window),
+    ).unwrap();
+    let Some(first) = rows.first() else {
+        return (StatusCode::NOT_FOUND, "no swaps in the window").into_response();
+    };
+    let (base_mint, quote_mint) = price_pair(&first.4, &first.5);
+    let points: Vec<PricePoint> = rows.iter().filter_map(|row| implied_price(row, &base_mint, &quote_mint)).collect();
+    let split = points.partition_point(|p| (p.slot, p.inclusion_order) < (query.slot, query.inclusion_order));
+    Json(PoolPrice {
+        amm: amm.into(),
+        before: split.checked_sub(1).map(|i| points[i]),
+        after: points.get(split).copied(),
+        base_mint,
+        quote_mint,
+        points,
+    }).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_implied_price() {
+        let wsol = WSOL_MINT.to_string();
+        let (base, quote) = price_pair("token", &wsol);
+        assert_eq!((base.as_ref(), quote.as_ref()), ("token", wsol.as_str()));
+        assert_eq!(price_pair(&wsol, "token"), (base.clone(), quote.clone()));
+        let buy = implied_price(&(1, 10, 0, 0, wsol.clone(), "token".into(), 200, 100), &base, &quote).unwrap();
+        assert!(buy.buy && buy.price == 2.0);
+        let sell = implied_price(&(2, 10, 1, 0, "token".into(), wsol.clone(), 100, 150), &base, &quote).unwrap();
+        assert!(!sell.buy && sell.price == 1.5);
+        assert!(implied_price(&(3, 10, 2, 0, "other".into(), wsol.clone(), 100, 150), &base, &quote).is_none());
+        assert!(implied_price(&(4, 10, 3, 0, wsol, "token".into(), 100, 0), &base, &quote).is_none());
+    }
+}
@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use axum::{extract::{Path, State}, Json};
+use derive_getters::Getters;
+use mysql::{prelude::Queryable as _, PooledConn, Row};
+use serde::Serialize;
+
+use crate::{api::ApiState, detector::event_from_row, events::{common::Timestamp, event::Event}, redact::{Redact as _, Redaction}};
+
+#[derive(Serialize, Getters)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineEntry {
+    role: Arc<str>,
+    timestamp: Timestamp,
+    sig: Option<Arc<str>>,
+    fee: Option<u64>,
+    cu_actual: Option<u64>,
+    /// Jito bundle the tx landed in, known once its receipt is imported
+    bundle_id: Option<Arc<str>>,
+    event: Event,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SandwichTimeline {
+    id: Arc<str>,
+    events: Vec<TimelineEntry>,
+}
+
+/// Every event of a sandwich in execution order, along with the fee and CU of its tx
+pub(crate) fn load_timeline(conn: &mut PooledConn, id: &str, redaction: &Redaction) -> Vec<TimelineEntry> {
+    let res: Vec<Row> = conn.exec("select s.role, s.bundle_id, v.*, e.min_out, e.max_in, e.input_reserve, e.output_reserve, t.sig, t.fee, t.cu_actual, t.block_time from sandwiches s join event_view v on v.id=s.event_id join events_with_id e on e.id=s.event_id left join transactions t on t.slot=v.slot and t.inclusion_order=v.inclusion_order where s.id=? order by v.slot, v.inclusion_order, v.ix_index, v.inner_ix_index", (id,)).unwrap();
+    res.iter().filter_map(|row| {
+        let mut event = event_from_row(row)?;
+        let role: Arc<str> = row.get("role").unwrap();
+        event.redact(redaction, role.as_ref() == "VICTIM");
+        let timestamp = match &event {
+            Event::Swap(swap) => *swap.timestamp(),
+            Event::Transfer(transfer) => *transfer.timestamp(),
+            Event::Transaction(_) | Event::PoolCreated(_) => return None,
+        };
+        Some(TimelineEntry {
+            role,
+            timestamp,
+            sig: row.get("sig").unwrap(),
+            fee: row.get("fee").unwrap(),
+            cu_actual: row.get("cu_actual").unwrap(),
+            bundle_id: row.get("bundle_id").unwrap(),
+            event,
+        })
+    }).collect()
+}
+
+pub async fn handle_timeline(State(state): State<ApiState>, Path(id): Path<String>) -> Json<Option<SandwichTimeline>> {
+    let mut conn = state.pool.get_conn().unwrap();
+    let events = load_timeline(&mut conn, &id, &state.redaction);
+    if events.is_empty() {
+        return Json(None);
+    }
+    Json(Some(SandwichTimeline {
+        id: id.into(),
+        events,
+    }))
+}
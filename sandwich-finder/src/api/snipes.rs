@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use axum::{extract::{Query, State}, Json};
+use mysql::prelude::Queryable as _;
+use serde::{Deserialize, Serialize};
+
+use crate::api::ApiState;
+
+const MAX_LIMIT: u64 = 1000;
+
+#[derive(Deserialize)]
+pub struct SnipeQuery {
+    amm: Option<String>,
+    limit: Option<u64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnipeRow {
+    amm: Arc<str>,
+    authority: Arc<str>,
+    slot: u64,
+    inclusion_order: u32,
+    lamports: u64,
+    slots_after_creation: u32,
+}
+
+/// Most recent launch snipes, optionally for a single pool
+pub async fn handle_snipes(State(state): State<ApiState>, Query(query): Query<SnipeQuery>) -> Json<Vec<SnipeRow>> {
+    let mut conn = state.pool.get_conn().unwrap();
+    let limit = query.limit.unwrap_or(100).min(MAX_LIMIT);
+    let to_row = |(amm, authority, slot, inclusion_order, lamports, slots_after_creation): (String, String, u64, u32, u64, u32)| SnipeRow {
+        amm: amm.into(),
+        authority: authority.into(),
+        slot,
+        inclusion_order,
+        lamports,
+        slots_after_creation,
+    };
+    let base = "select amm.address, auth.address, s.slot, s.inclusion_order, s.lamports, s.slots_after_creation from snipes s join address_lookup_table amm on amm.id=s.amm_id join address_lookup_table auth on auth.id=s.authority_id";
+    let rows = match query.amm {
+        Some(amm) => conn.exec_map(format!("{base} where amm.address=? order by s.slot desc, s.inclusion_order desc limit ?"), (amm, limit), to_row),
+        None => conn.exec_map(format!("{base} order by s.slot desc, s.inclusion_order desc limit ?"), (limit,), to_row),
+    }.unwrap();
+    Json(rows)
+}
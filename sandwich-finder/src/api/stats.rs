@@ -0,0 +1,717 @@
+use std::{collections::{BTreeMap, HashMap, HashSet}, str::FromStr as _, sync::Arc};
+
+use axum::{extract::{Query, State}, Json};
+use mysql::prelude::Queryable as _;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{api::{anchor_slot, ApiState}, detector::SLOTS_PER_HOUR, events::addresses::program_name, fingerprint::{percentiles, Percentiles}};
+
+const MAX_HOURS: u64 = 24 * 30;
+
+#[derive(Deserialize)]
+pub struct WindowQuery {
+    hours: Option<u64>,
+    /// `true` for only the sandwiches on stablecoin pairs, `false` for none of them, both if omitted.
+    /// Only applies to the stats read from the sandwiches themselves, the rollups leave stablecoin pairs out.
+    stable: Option<bool>,
+}
+
+impl WindowQuery {
+    fn slots(&self) -> u64 {
+        self.hours.unwrap_or(24).clamp(1, MAX_HOURS) * SLOTS_PER_HOUR
+    }
+
+    /// Condition on the sandwiches aliased `s` to append to a where clause
+    fn stable_filter(&self) -> &'static str {
+        match self.stable {
+            Some(true) => " and s.stable_pair=1",
+            Some(false) => " and s.stable_pair=0",
+            None => "",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct MintStatsQuery {
+    /// e.g. `30m`, `24h` or `7d`
+    window: Option<String>,
+    /// `volume` (default) or `loss`
+    sort: Option<String>,
+}
+
+/// Slots covered by a window like `24h`, None if it doesn't parse
+fn window_slots(window: &str) -> Option<u64> {
+    let (n, unit) = window.split_at(window.len().checked_sub(1)?);
+    let n: u64 = n.parse().ok()?;
+    let slots = match unit {
+        "m" => n * SLOTS_PER_HOUR / 60,
+        "h" => n * SLOTS_PER_HOUR,
+        "d" => n * 24 * SLOTS_PER_HOUR,
+        _ => return None,
+    };
+    Some(slots.clamp(1, MAX_HOURS * SLOTS_PER_HOUR))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolMintStats {
+    amm: Arc<str>,
+    sandwiches: u64,
+    victims: u64,
+    victim_volume: u64,
+    victim_volume_lamports: u64,
+    victim_loss_lamports: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MintStats {
+    mint: Arc<str>,
+    sandwiches: u64,
+    victims: u64,
+    victim_volume: u64,
+    victim_volume_lamports: u64,
+    victim_loss_lamports: u64,
+    pools: Vec<PoolMintStats>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MintStatsResponse {
+    since_slot: u64,
+    mints: Vec<MintStats>,
+}
+
+/// Mints ranked by how much victim volume got sandwiched on their pairs, with a per-pool breakdown.
+/// Read from sandwich_mint_rollup, so only as fine-grained as its buckets.
+pub async fn handle_mint_stats(State(state): State<ApiState>, Query(query): Query<MintStatsQuery>) -> Json<MintStatsResponse> {
+    let mut conn = state.pool.get_conn().unwrap();
+    let slots = query.window.as_deref().and_then(window_slots).unwrap_or(24 * SLOTS_PER_HOUR);
+    let since_slot = anchor_slot(&mut conn).saturating_sub(slots);
+    let order = match query.sort.as_deref() {
+        Some("loss") => "l desc",
+        _ => "vl desc, l desc",
+    };
+    let rows: Vec<(u32, String, u64, u64, u64, u64, u64)> = conn.exec(
+        format!("select r.mint_id, a.address, sum(r.sandwiches), sum(r.victims), sum(r.victim_volume), sum(r.victim_volume_lamports) as vl, sum(r.victim_loss_lamports) as l from sandwich_mint_rollup r join address_lookup_table a on a.id=r.mint_id where r.bucket_slot >= ? group by r.mint_id order by {order} limit 100"),
+        (since_slot,),
+    ).unwrap();
+    let mut pools: HashMap<u32, Vec<PoolMintStats>> = HashMap::new();
+    if !rows.is_empty() {
+        let mint_ids: Vec<_> = rows.iter().map(|r| r.0).collect();
+        let stmt = format!("select r.mint_id, a.address, sum(r.sandwiches), sum(r.victims), sum(r.victim_volume), sum(r.victim_volume_lamports) as vl, sum(r.victim_loss_lamports) as l from sandwich_mint_rollup r join address_lookup_table a on a.id=r.amm_id where r.bucket_slot >= ? and r.mint_id in ({}) group by r.mint_id, r.amm_id order by {order}", "?,".repeat(mint_ids.len()).trim_end_matches(","));
+        let params: Vec<_> = [since_slot].into_iter().chain(mint_ids.into_iter().map(u64::from)).collect();
+        let pool_rows: Vec<(u32, String, u64, u64, u64, u64, u64)> = conn.exec(stmt, params).unwrap();
+        for (mint_id, amm, sandwiches, victims, victim_volume, victim_volume_lamports, victim_loss_lamports) in pool_rows {
+            pools.entry(mint_id).or_default().push(PoolMintStats {
+                amm: amm.into(),
+                sandwiches,
+                victims,
+                victim_volume,
+                victim_volume_lamports,
+                victim_loss_lamports,
+            });
+        }
+    }
+    let mints = rows.into_iter().map(|(mint_id, mint, sandwiches, victims, victim_volume, victim_volume_lamports, victim_loss_lamports)| MintStats {
+        mint: mint.into(),
+        sandwiches,
+        victims,
+        victim_volume,
+        victim_volume_lamports,
+        victim_loss_lamports,
+        pools: pools.remove(&mint_id).unwrap_or_default(),
+    }).collect();
+    Json(MintStatsResponse {
+        since_slot,
+        mints,
+    })
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DontFrontCount {
+    address: Arc<str>,
+    violations: u64,
+    sandwiches: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DontFrontStats {
+    since_slot: u64,
+    violations: u64,
+    per_attacker: Vec<DontFrontCount>,
+    per_validator: Vec<DontFrontCount>,
+}
+
+/// Victims that carried the jitodontfront account and got sandwiched anyway, by attacker and by the leader of the victim's slot
+pub async fn handle_dont_front(State(state): State<ApiState>, Query(window): Query<WindowQuery>) -> Json<DontFrontStats> {
+    let mut conn = state.pool.get_conn().unwrap();
+    let since_slot = anchor_slot(&mut conn).saturating_sub(window.slots());
+    let violations: u64 = conn.exec_first("select count(*) from dont_front_violations where slot >= ?", (since_slot,)).unwrap().unwrap_or(0);
+    let to_count = |(address, violations, sandwiches): (String, u64, u64)| DontFrontCount {
+        address: address.into(),
+        violations,
+        sandwiches,
+    };
+    let per_attacker = conn.exec_map("select a.address, count(*) as c, count(distinct d.sandwich_id) from dont_front_violations d join address_lookup_table a on a.id=d.attacker_id where d.slot >= ? group by d.attacker_id order by c desc limit 100", (since_slot,), to_count).unwrap();
+    let per_validator = conn.exec_map("select a.address, count(*) as c, count(distinct d.sandwich_id) from dont_front_violations d join leader_schedule l on l.slot=d.slot join address_lookup_table a on a.id=l.leader_id where d.slot >= ? group by l.leader_id order by c desc limit 100", (since_slot,), to_count).unwrap();
+    Json(DontFrontStats {
+        since_slot,
+        violations,
+        per_attacker,
+        per_validator,
+    })
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionStats {
+    legs: u64,
+    percentiles: Option<Percentiles>,
+    /// Legs per tenth of the block, top of block first
+    deciles: [u64; 10],
+}
+
+impl PositionStats {
+    fn new(positions: Vec<u64>) -> Self {
+        let mut deciles = [0; 10];
+        for &p in positions.iter() {
+            deciles[(p / 1000).min(9) as usize] += 1;
+        }
+        Self {
+            legs: positions.len() as u64,
+            percentiles: percentiles(positions),
+            deciles,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaderPositionStats {
+    leader: Arc<str>,
+    frontrun: PositionStats,
+    backrun: PositionStats,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionStatsResponse {
+    since_slot: u64,
+    frontrun: PositionStats,
+    backrun: PositionStats,
+    per_leader: Vec<LeaderPositionStats>,
+}
+
+/// Where in their blocks frontruns and backruns land, in bps of the block (0 = first tx, 10000 = last).
+/// Attacks bought through bundle auctions sit near the top, latency-driven ones spread through the block.
+pub async fn handle_positions(State(state): State<ApiState>, Query(window): Query<WindowQuery>) -> Json<PositionStatsResponse> {
+    let mut conn = state.pool.get_conn().unwrap();
+    let since_slot = anchor_slot(&mut conn).saturating_sub(window.slots());
+    let rows: Vec<(String, u64, Option<String>)> = conn.exec(format!("select s.role, s.position_bps, a.address from sandwiches s join event_view v on v.id=s.event_id left join leader_schedule l on l.slot=v.slot left join address_lookup_table a on a.id=l.leader_id where s.role in ('FRONTRUN', 'BACKRUN') and s.position_bps is not null and v.slot >= ?{}", window.stable_filter()), (since_slot,)).unwrap();
+    let (mut frontrun, mut backrun) = (vec![], vec![]);
+    let mut per_leader: HashMap<String, (Vec<u64>, Vec<u64>)> = HashMap::new();
+    for (role, position_bps, leader) in rows {
+        let is_frontrun = role == "FRONTRUN";
+        if let Some(leader) = leader {
+            let (leader_frontrun, leader_backrun) = per_leader.entry(leader).or_default();
+            if is_frontrun {
+                leader_frontrun.push(position_bps);
+            } else {
+                leader_backrun.push(position_bps);
+            }
+        }
+        if is_frontrun {
+            frontrun.push(position_bps);
+        } else {
+            backrun.push(position_bps);
+        }
+    }
+    let mut per_leader: Vec<_> = per_leader.into_iter().map(|(leader, (frontrun, backrun))| LeaderPositionStats {
+        leader: leader.into(),
+        frontrun: PositionStats::new(frontrun),
+        backrun: PositionStats::new(backrun),
+    }).collect();
+    per_leader.sort_by_key(|l| std::cmp::Reverse(l.frontrun.legs + l.backrun.legs));
+    per_leader.truncate(100);
+    Json(PositionStatsResponse {
+        since_slot,
+        frontrun: PositionStats::new(frontrun),
+        backrun: PositionStats::new(backrun),
+        per_leader,
+    })
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttackerCuStats {
+    attacker: Arc<str>,
+    txs: u64,
+    cu_actual: u64,
+    cu_limit: u64,
+    /// Share of the requested limit left unused per tx, in bps
+    unused_bps: Option<Percentiles>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaderCuStats {
+    leader: Arc<str>,
+    blocks: u64,
+    sandwich_cu: u64,
+    block_cu: u64,
+    /// Share of each block's CU consumed by frontruns and backruns, in bps
+    block_share_bps: Option<Percentiles>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CuStatsResponse {
+    since_slot: u64,
+    txs: u64,
+    unused_bps: Option<Percentiles>,
+    blocks: u64,
+    block_share_bps: Option<Percentiles>,
+    per_attacker: Vec<AttackerCuStats>,
+    per_leader: Vec<LeaderCuStats>,
+}
+
+// slot, inclusion_order, cu_actual, cu_limit, block_cu, attacker, leader
+type CuRow = (u64, u32, u64, u32, u64, String, Option<String>);
+
+/// Compute usage of frontrun and backrun txs: how far attackers over-provision their CU limits,
+/// and how much of each block's CU goes to sandwiches, by the leader that produced the block.
+/// Only txs indexed with their CU limit and block totals are counted.
+pub async fn handle_cu_stats(State(state): State<ApiState>, Query(window): Query<WindowQuery>) -> Json<CuStatsResponse> {
+    let mut conn = state.pool.get_conn().unwrap();
+    let since_slot = anchor_slot(&mut conn).saturating_sub(window.slots());
+    let rows: Vec<CuRow> = conn.exec(format!("select distinct t.slot, t.inclusion_order, t.cu_actual, t.cu_limit, t.block_cu, v.authority, a.address from sandwiches s join event_view v on v.id=s.event_id join transactions t on t.slot=v.slot and t.inclusion_order=v.inclusion_order left join leader_schedule l on l.slot=t.slot left join address_lookup_table a on a.id=l.leader_id where s.role in ('FRONTRUN', 'BACKRUN') and t.cu_limit is not null and t.block_cu is not null and v.slot >= ?{}", window.stable_filter()), (since_slot,)).unwrap();
+    // a tx can show up once per swap and sandwich it's part of
+    let mut seen = HashSet::new();
+    let mut unused = vec![];
+    let mut per_attacker: HashMap<String, (u64, u64, u64, Vec<u64>)> = HashMap::new();
+    // slot -> (leader, sandwich cu, block cu)
+    let mut blocks: HashMap<u64, (Option<String>, u64, u64)> = HashMap::new();
+    for (slot, inclusion_order, cu_actual, cu_limit, block_cu, attacker, leader) in rows {
+        if !seen.insert((slot, inclusion_order)) {
+            continue;
+        }
+        let unused_bps = (cu_limit as u64).saturating_sub(cu_actual) * 10000 / (cu_limit as u64).max(1);
+        unused.push(unused_bps);
+        let (txs, attacker_cu_actual, attacker_cu_limit, attacker_unused) = per_attacker.entry(attacker).or_default();
+        *txs += 1;
+        *attacker_cu_actual += cu_actual;
+        *attacker_cu_limit += cu_limit as u64;
+        attacker_unused.push(unused_bps);
+        let block = blocks.entry(slot).or_insert((leader, 0, block_cu));
+        block.1 += cu_actual;
+    }
+    let mut per_attacker: Vec<_> = per_attacker.into_iter().map(|(attacker, (txs, cu_actual, cu_limit, unused))| AttackerCuStats {
+        attacker: attacker.into(),
+        txs,
+        cu_actual,
+        cu_limit,
+        unused_bps: percentiles(unused),
+    }).collect();
+    per_attacker.sort_by_key(|a| std::cmp::Reverse(a.cu_limit.saturating_sub(a.cu_actual)));
+    per_attacker.truncate(100);
+    let share_bps = |sandwich_cu: u64, block_cu: u64| sandwich_cu * 10000 / block_cu.max(1);
+    let mut per_leader: HashMap<String, (u64, u64, u64, Vec<u64>)> = HashMap::new();
+    for (leader, sandwich_cu, block_cu) in blocks.values() {
+        if let Some(leader) = leader {
+            let (blocks, leader_sandwich_cu, leader_block_cu, shares) = per_leader.entry(leader.clone()).or_default();
+            *blocks += 1;
+            *leader_sandwich_cu += sandwich_cu;
+            *leader_block_cu += block_cu;
+            shares.push(share_bps(*sandwich_cu, *block_cu));
+        }
+    }
+    let mut per_leader: Vec<_> = per_leader.into_iter().map(|(leader, (blocks, sandwich_cu, block_cu, shares))| LeaderCuStats {
+        leader: leader.into(),
+        blocks,
+        sandwich_cu,
+        block_cu,
+        block_share_bps: percentiles(shares),
+    }).collect();
+    per_leader.sort_by_key(|l| std::cmp::Reverse(l.sandwich_cu));
+    per_leader.truncate(100);
+    Json(CuStatsResponse {
+        since_slot,
+        txs: seen.len() as u64,
+        unused_bps: percentiles(unused),
+        blocks: blocks.len() as u64,
+        block_share_bps: percentiles(blocks.values().map(|(_, sandwich_cu, block_cu)| share_bps(*sandwich_cu, *block_cu)).collect()),
+        per_attacker,
+        per_leader,
+    })
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramCounts {
+    sandwiches: u64,
+    victims: u64,
+    victim_volume_lamports: u64,
+    victim_loss_lamports: u64,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramPoint {
+    /// First slot of the hour
+    bucket_slot: u64,
+    #[serde(flatten)]
+    counts: ProgramCounts,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramSeries {
+    program: Arc<str>,
+    name: Option<&'static str>,
+    /// Over the whole window
+    total: ProgramCounts,
+    /// Hours without sandwiches are left out
+    points: Vec<ProgramPoint>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramTimeseries {
+    since_slot: u64,
+    bucket_slots: u64,
+    programs: Vec<ProgramSeries>,
+}
+
+// program, hour, sandwiches, victims, victim volume in lamports, loss
+type ProgramRow = (String, u64, u64, u64, u64, u64);
+
+/// Rows ordered by hour into a series per program, the most sandwiched first
+fn program_series(rows: Vec<ProgramRow>) -> Vec<ProgramSeries> {
+    let mut series: HashMap<String, Vec<ProgramPoint>> = HashMap::new();
+    for (program, hour, sandwiches, victims, victim_volume_lamports, victim_loss_lamports) in rows {
+        series.entry(program).or_default().push(ProgramPoint {
+            bucket_slot: hour * SLOTS_PER_HOUR,
+            counts: ProgramCounts { sandwiches, victims, victim_volume_lamports, victim_loss_lamports },
+        });
+    }
+    let mut series: Vec<_> = series.into_iter().map(|(program, points)| {
+        let total = points.iter().fold(ProgramCounts::default(), |acc, p| ProgramCounts {
+            sandwiches: acc.sandwiches + p.counts.sandwiches,
+            victims: acc.victims + p.counts.victims,
+            victim_volume_lamports: acc.victim_volume_lamports + p.counts.victim_volume_lamports,
+            victim_loss_lamports: acc.victim_loss_lamports + p.counts.victim_loss_lamports,
+        });
+        ProgramSeries {
+            name: Pubkey::from_str(&program).ok().and_then(|p| program_name(&p)),
+            program: program.into(),
+            total,
+            points,
+        }
+    }).collect();
+    series.sort_by(|a, b| b.total.sandwiches.cmp(&a.total.sandwiches).then_with(|| a.program.cmp(&b.program)));
+    series
+}
+
+/// Sandwiches per AMM program and hour, for comparing the MEV exposure of protocols over time.
+/// Read from sandwich_program_rollup, the window is rounded down to a whole hour.
+pub async fn handle_program_timeseries(State(state): State<ApiState>, Query(window): Query<WindowQuery>) -> Json<ProgramTimeseries> {
+    let mut conn = state.pool.get_conn().unwrap();
+    let since_slot = anchor_slot(&mut conn).saturating_sub(window.slots()) / SLOTS_PER_HOUR * SLOTS_PER_HOUR;
+    let rows: Vec<ProgramRow> = conn.exec(
+        "select a.address, r.bucket_slot div ? as h, sum(r.sandwiches), sum(r.victims), sum(r.victim_volume_lamports), sum(r.victim_loss_lamports) from sandwich_program_rollup r join address_lookup_table a on a.id=r.program_id where r.bucket_slot >= ? group by r.program_id, h order by h",
+        (SLOTS_PER_HOUR, since_slot),
+    ).unwrap();
+    Json(ProgramTimeseries {
+        since_slot,
+        bucket_slots: SLOTS_PER_HOUR,
+        programs: program_series(rows),
+    })
+}
+
+// est_victim_loss_lamports, price_impact_bps
+type LossRow = (Option<u64>, Option<u64>);
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VictimCounts {
+    victims: u64,
+    victim_loss_lamports: u64,
+    price_impact_bps: Option<Percentiles>,
+}
+
+impl VictimCounts {
+    fn new(losses: &[LossRow]) -> Self {
+        Self {
+            victims: losses.len() as u64,
+            victim_loss_lamports: losses.iter().filter_map(|(loss, _)| *loss).sum(),
+            price_impact_bps: percentiles(losses.iter().filter_map(|(_, impact)| *impact).collect()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AggregatorRoutingCounts {
+    /// Top level program of the victims' txs
+    outer_program: Arc<str>,
+    #[serde(flatten)]
+    counts: VictimCounts,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoutingStatsResponse {
+    since_slot: u64,
+    routed: VictimCounts,
+    direct: VictimCounts,
+    per_aggregator: Vec<AggregatorRoutingCounts>,
+}
+
+// aggregator_routed, outer_program, est_victim_loss_lamports, price_impact_bps
+type RoutingRow = (bool, Option<String>, Option<u64>, Option<u64>);
+
+fn routing_stats(since_slot: u64, rows: Vec<RoutingRow>) -> RoutingStatsResponse {
+    let (mut routed, mut direct) = (vec![], vec![]);
+    let mut per_aggregator: HashMap<String, Vec<LossRow>> = HashMap::new();
+    for (aggregator_routed, outer_program, loss, impact) in rows {
+        if !aggregator_routed {
+            direct.push((loss, impact));
+            continue;
+        }
+        routed.push((loss, impact));
+        if let Some(outer_program) = outer_program {
+            per_aggregator.entry(outer_program).or_default().push((loss, impact));
+        }
+    }
+    let mut per_aggregator: Vec<_> = per_aggregator.into_iter().map(|(outer_program, losses)| AggregatorRoutingCounts {
+        outer_program: outer_program.into(),
+        counts: VictimCounts::new(&losses),
+    }).collect();
+    per_aggregator.sort_by(|a, b| b.counts.victims.cmp(&a.counts.victims).then_with(|| a.outer_program.cmp(&b.outer_program)));
+    RoutingStatsResponse {
+        since_slot,
+        routed: VictimCounts::new(&routed),
+        direct: VictimCounts::new(&direct),
+        per_aggregator,
+    }
+}
+
+/// Victims that went through a known aggregator (Jupiter, DFlow, OKX) against those that called the DEX directly,
+/// for telling whether routers shield their users. Victims stored before routing was recorded are left out.
+pub async fn handle_routing(State(state): State<ApiState>, Query(window): Query<WindowQuery>) -> Json<RoutingStatsResponse> {
+    let mut conn = state.pool.get_conn().unwrap();
+    let since_slot = anchor_slot(&mut conn).saturating_sub(window.slots());
+    let rows: Vec<RoutingRow> = conn.exec(format!("select s.aggregator_routed, v.outer_program, s.est_victim_loss_lamports, s.price_impact_bps from sandwiches s join event_view v on v.id=s.event_id where s.role='VICTIM' and s.aggregator_routed is not null and v.slot >= ?{}", window.stable_filter()), (since_slot,)).unwrap();
+    Json(routing_stats(since_slot, rows))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CohortCounts {
+    cohort: Arc<str>,
+    #[serde(flatten)]
+    counts: VictimCounts,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CohortStatsResponse {
+    since_slot: u64,
+    cohorts: Vec<CohortCounts>,
+}
+
+/// Victims by the kind of wallet they were when sandwiched, new, retail or bot, see
+/// [`VictimCohort`](crate::events::cohort::VictimCohort). Victims stored before cohorts were recorded are left out.
+pub async fn handle_cohorts(State(state): State<ApiState>, Query(window): Query<WindowQuery>) -> Json<CohortStatsResponse> {
+    let mut conn = state.pool.get_conn().unwrap();
+    let since_slot = anchor_slot(&mut conn).saturating_sub(window.slots());
+    let rows: Vec<CohortRow> = conn.exec(format!("select s.victim_cohort, s.est_victim_loss_lamports, s.price_impact_bps from sandwiches s join event_view v on v.id=s.event_id where s.role='VICTIM' and s.victim_cohort is not null and v.slot >= ?{}", window.stable_filter()), (since_slot,)).unwrap();
+    Json(cohort_stats(since_slot, rows))
+}
+
+type CohortRow = (String, Option<u64>, Option<u64>);
+
+fn cohort_stats(since_slot: u64, rows: Vec<CohortRow>) -> CohortStatsResponse {
+    let mut per_cohort: BTreeMap<String, Vec<LossRow>> = BTreeMap::new();
+    for (cohort, loss, impact) in rows {
+        per_cohort.entry(cohort).or_default().push((loss, impact));
+    }
+    CohortStatsResponse {
+        since_slot,
+        cohorts: per_cohort.into_iter().map(|(cohort, losses)| CohortCounts {
+            cohort: cohort.into(),
+            counts: VictimCounts::new(&losses),
+        }).collect(),
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimingStats {
+    sandwiches: u64,
+    /// Sandwiches with legs in different slots, the attacker saw the victim before it landed
+    multi_slot: u64,
+    multi_slot_bps: u64,
+    /// Sandwiches by which of the leader's 4 slots the first frontrun landed in
+    frontrun_offsets: [u64; 4],
+    frontrun_victim_slots: Option<Percentiles>,
+    victim_backrun_slots: Option<Percentiles>,
+}
+
+impl TimingStats {
+    fn new(rows: &[TimingRow]) -> Self {
+        let mut frontrun_offsets = [0; 4];
+        for (_, offset, _, _) in rows {
+            frontrun_offsets[(*offset as usize).min(3)] += 1;
+        }
+        let sandwiches = rows.len() as u64;
+        let multi_slot = rows.iter().filter(|(_, _, frontrun_victim, victim_backrun)| frontrun_victim + victim_backrun > 0).count() as u64;
+        Self {
+            sandwiches,
+            multi_slot,
+            multi_slot_bps: multi_slot * 10000 / sandwiches.max(1),
+            frontrun_offsets,
+            frontrun_victim_slots: percentiles(rows.iter().map(|(_, _, gap, _)| *gap).collect()),
+            victim_backrun_slots: percentiles(rows.iter().map(|(_, _, _, gap)| *gap).collect()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaderTimingStats {
+    leader: Arc<str>,
+    #[serde(flatten)]
+    timing: TimingStats,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimingStatsResponse {
+    since_slot: u64,
+    #[serde(flatten)]
+    timing: TimingStats,
+    per_leader: Vec<LeaderTimingStats>,
+}
+
+/// Where in the leader's window of slots sandwiches land and how many slots apart their legs are, by the leader of the
+/// frontrun's slot. A leader whose blocks keep hosting multi-slot sandwiches is likely leaking txs to the attackers.
+pub async fn handle_leader_timing(State(state): State<ApiState>, Query(window): Query<WindowQuery>) -> Json<TimingStatsResponse> {
+    let mut conn = state.pool.get_conn().unwrap();
+    let since_slot = anchor_slot(&mut conn).saturating_sub(window.slots());
+    let stable_filter = match window.stable {
+        Some(_) => format!(" and sp.sandwich_id in (select s.id from sandwiches s where s.role='FRONTRUN'{})", window.stable_filter()),
+        None => String::new(),
+    };
+    let rows: Vec<TimingRow> = conn.exec(format!("select a.address, sp.frontrun_slot_offset, sp.frontrun_victim_slots, sp.victim_backrun_slots from sandwich_spans sp left join leader_schedule l on l.slot=sp.slot left join address_lookup_table a on a.id=l.leader_id where sp.slot >= ? and sp.frontrun_slot_offset is not null{stable_filter}"), (since_slot,)).unwrap();
+    Json(timing_stats(since_slot, rows))
+}
+
+// leader, frontrun offset, frontrun -> victim slots, victim -> backrun slots
+type TimingRow = (Option<String>, u8, u64, u64);
+
+fn timing_stats(since_slot: u64, rows: Vec<TimingRow>) -> TimingStatsResponse {
+    let mut per_leader: HashMap<&str, Vec<TimingRow>> = HashMap::new();
+    for row in rows.iter() {
+        if let Some(leader) = &row.0 {
+            per_leader.entry(leader).or_default().push(row.clone());
+        }
+    }
+    let mut per_leader: Vec<_> = per_leader.into_iter().map(|(leader, rows)| LeaderTimingStats {
+        leader: leader.into(),
+        timing: TimingStats::new(&rows),
+    }).collect();
+    per_leader.sort_by_key(|l| std::cmp::Reverse((l.timing.multi_slot, l.timing.sandwiches)));
+    per_leader.truncate(100);
+    TimingStatsResponse {
+        since_slot,
+        timing: TimingStats::new(&rows),
+        per_leader,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_slots() {
+        assert_eq!(window_slots("24h"), Some(24 * SLOTS_PER_HOUR));
+        assert_eq!(window_slots("30m"), Some(SLOTS_PER_HOUR / 2));
+        assert_eq!(window_slots("7d"), Some(7 * 24 * SLOTS_PER_HOUR));
+        assert_eq!(window_slots("365d"), Some(MAX_HOURS * SLOTS_PER_HOUR));
+        assert_eq!(window_slots("h"), None);
+        assert_eq!(window_slots("24"), None);
+        assert_eq!(window_slots(""), None);
+    }
+
+    #[test]
+    fn test_timing_stats() {
+        let leader = |l: &str| Some(l.to_string());
+        let stats = timing_stats(0, vec![
+            (leader("a"), 0, 0, 0),
+            (leader("a"), 1, 1, 0),
+            (leader("b"), 0, 0, 0),
+            (None, 3, 0, 2),
+        ]);
+        assert_eq!((stats.timing.sandwiches, stats.timing.multi_slot, stats.timing.multi_slot_bps), (4, 2, 5000));
+        assert_eq!(stats.timing.frontrun_offsets, [2, 1, 0, 1]);
+        assert_eq!(stats.per_leader.iter().map(|l| (l.leader.as_ref(), l.timing.multi_slot)).collect::<Vec<_>>(), vec![("a", 1), ("b", 0)]);
+        assert_eq!(stats.per_leader[0].timing.frontrun_victim_slots, Some(Percentiles { p10: 0, p50: 0, p90: 0 }));
+    }
+
+    #[test]
+    fn test_position_stats() {
+        let stats = PositionStats::new(vec![0, 500, 1000, 9999, 10000]);
+        assert_eq!(stats.legs, 5);
+        assert_eq!(stats.deciles, [2, 1, 0, 0, 0, 0, 0, 0, 0, 2]);
+        assert_eq!(stats.percentiles, Some(Percentiles { p10: 0, p50: 1000, p90: 9999 }));
+        assert_eq!(PositionStats::new(vec![]), PositionStats::default());
+    }
+
+    #[test]
+    fn test_program_series() {
+        let pumpswap = "pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA";
+        let series = program_series(vec![
+            (pumpswap.to_string(), 10, 3, 4, 100, 10),
+            ("unknown".to_string(), 10, 1, 1, 50, 5),
+            (pumpswap.to_string(), 12, 2, 2, 200, 20),
+        ]);
+        assert_eq!(series.len(), 2);
+        assert_eq!(series[0].name, Some("PumpSwap"));
+        assert_eq!(series[0].points.iter().map(|p| p.bucket_slot).collect::<Vec<_>>(), vec![10 * SLOTS_PER_HOUR, 12 * SLOTS_PER_HOUR]);
+        assert_eq!(series[0].total, ProgramCounts { sandwiches: 5, victims: 6, victim_volume_lamports: 300, victim_loss_lamports: 30 });
+        assert_eq!(series[1].name, None);
+    }
+
+    #[test]
+    fn test_routing_stats() {
+        let jup = "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4";
+        let RoutingStatsResponse { routed, direct, per_aggregator, .. } = routing_stats(0, vec![
+            (true, Some(jup.to_string()), Some(100), Some(50)),
+            (true, Some(jup.to_string()), None, Some(10)),
+            (false, None, Some(30), Some(20)),
+        ]);
+        assert_eq!(routed.victims, 2);
+        assert_eq!(routed.victim_loss_lamports, 100);
+        assert_eq!(routed.price_impact_bps, Some(Percentiles { p10: 10, p50: 10, p90: 10 }));
+        assert_eq!(direct, VictimCounts { victims: 1, victim_loss_lamports: 30, price_impact_bps: Some(Percentiles { p10: 20, p50: 20, p90: 20 }) });
+        assert_eq!(per_aggregator.len(), 1);
+        assert_eq!(&*per_aggregator[0].outer_program, jup);
+    }
+
+    #[test]
+    fn test_cohort_stats() {
+        let CohortStatsResponse { cohorts, .. } = cohort_stats(0, vec![
+            ("RETAIL".to_string(), Some(100), Some(50)),
+            ("NEW".to_string(), Some(40), None),
+            ("RETAIL".to_string(), Some(20), Some(30)),
+        ]);
+        let cohorts: Vec<_> = cohorts.iter().map(|c| (&*c.cohort, c.counts.victims, c.counts.victim_loss_lamports)).collect();
+        assert_eq!(cohorts, vec![("NEW", 1, 40), ("RETAIL", 2, 120)]);
+    }
+}
@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use axum::{extract::State, Json};
+use derive_getters::Getters;
+use mysql::{prelude::Queryable as _, PooledConn};
+use serde::Serialize;
+
+use crate::{api::{latest_slots, ApiState}, detector::SLOTS_PER_HOUR};
+
+#[derive(Serialize, Getters)]
+#[serde(rename_all = "camelCase")]
+pub struct RankedAddress {
+    address: Arc<str>,
+    sandwiches: u64,
+    victim_loss_lamports: u64,
+}
+
+#[derive(Serialize, Getters)]
+#[serde(rename_all = "camelCase")]
+pub struct Summary {
+    chain_tip: Option<u64>,
+    processed_slot: Option<u64>,
+    slot_lag: Option<u64>,
+    sandwiches_1h: u64,
+    sandwiches_24h: u64,
+    victim_loss_lamports_24h: u64,
+    /// Sandwiches on stablecoin pairs, left out of the other counts
+    stable_sandwiches_24h: u64,
+    stable_victims_24h: u64,
+    swap_volume_lamports_24h: u64,
+    sandwiched_volume_lamports_24h: u64,
+    /// Share of the day's SOL-side DEX volume that was sandwiched victims
+    sandwiched_volume_bps_24h: Option<u64>,
+    top_attackers: Vec<RankedAddress>,
+    top_pools: Vec<RankedAddress>,
+}
+
+pub async fn handle_summary(State(state): State<ApiState>) -> Json<Summary> {
+    Json(load_summary(&mut state.pool.get_conn().unwrap()))
+}
+
+/// Headline numbers for the last 24h, read from the rollups
+pub fn load_summary(conn: &mut PooledConn) -> Summary {
+    let (chain_tip, processed_slot) = latest_slots(conn);
+    let anchor = chain_tip.or(processed_slot).unwrap_or(0);
+    let since_1h = anchor.saturating_sub(SLOTS_PER_HOUR);
+    let since_24h = anchor.saturating_sub(24 * SLOTS_PER_HOUR);
+    let (sandwiches_1h, _): (u64, u64) = conn.exec_first("select ifnull(sum(sandwiches), 0), ifnull(sum(victim_loss_lamports), 0) from sandwich_rollup where bucket_slot >= ?", (since_1h,)).unwrap().unwrap_or((0, 0));
+    let (sandwiches_24h, victim_loss_lamports_24h): (u64, u64) = conn.exec_first("select ifnull(sum(sandwiches), 0), ifnull(sum(victim_loss_lamports), 0) from sandwich_rollup where bucket_slot >= ?", (since_24h,)).unwrap().unwrap_or((0, 0));
+    let (stable_sandwiches_24h, stable_victims_24h): (u64, u64) = conn.exec_first("select ifnull(sum(sandwiches), 0), ifnull(sum(victims), 0) from sandwich_stable_rollup where bucket_slot >= ?", (since_24h,)).unwrap().unwrap_or((0, 0));
+    let (swap_volume_lamports_24h, sandwiched_volume_lamports_24h): (u64, u64) = conn.exec_first("select ifnull(sum(swap_volume_lamports), 0), ifnull(sum(sandwiched_volume_lamports), 0) from block_volume where slot >= ?", (since_24h,)).unwrap().unwrap_or((0, 0));
+    let mut top = |table: &str, column: &str| conn.exec_map(
+        format!("select a.address, sum(r.sandwiches) as c, sum(r.victim_loss_lamports) from {table} r join address_lookup_table a on a.id=r.{column} where r.bucket_slot >= ? group by r.{column} order by c desc limit 3"),
+        (since_24h,),
+        |(address, sandwiches, victim_loss_lamports): (String, u64, u64)| RankedAddress {
+            address: address.into(),
+            sandwiches,
+            victim_loss_lamports,
+        },
+    ).unwrap();
+    let top_attackers = top("sandwich_attacker_rollup", "attacker_id");
+    let top_pools = top("sandwich_pool_rollup", "amm_id");
+    Summary {
+        chain_tip,
+        processed_slot,
+        slot_lag: chain_tip.zip(processed_slot).map(|(tip, processed)| tip.saturating_sub(processed)),
+        sandwiches_1h,
+        sandwiches_24h,
+        victim_loss_lamports_24h,
+        stable_sandwiches_24h,
+        stable_victims_24h,
+        swap_volume_lamports_24h,
+        sandwiched_volume_lamports_24h,
+        sandwiched_volume_bps_24h: Some(swap_volume_lamports_24h).filter(|&v| v > 0).map(|v| sandwiched_volume_lamports_24h * 10000 / v),
+        top_attackers,
+        top_pools,
+    }
+}
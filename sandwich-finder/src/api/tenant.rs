@@ -0,0 +1,296 @@
+use std::{env, hash::Hash, net::{IpAddr, Ipv4Addr, SocketAddr}, sync::Arc, time::{Duration, Instant, SystemTime, UNIX_EPOCH}};
+
+use axum::{extract::{ConnectInfo, Path, Query, Request, State}, http::{header, HeaderMap, StatusCode}, middleware::Next, response::{IntoResponse, Response}, Json};
+use dashmap::DashMap;
+use mysql::{prelude::Queryable as _, Pool};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+
+use crate::api::ApiState;
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+const MAX_TENANT_LENGTH: usize = 64;
+/// How long a key looked up in the db is trusted for, which bounds how long replicas other than the one revoking a key
+/// keep accepting it
+const KEY_CACHE_TTL: Duration = Duration::from_secs(60);
+/// Expired keys and past minutes' usage are pruned once a map grows this large
+const MAX_CACHED_ENTRIES: usize = 100_000;
+
+/// Who a request is made on behalf of, requests without a key belong to the default tenant named `""`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tenant {
+    pub name: Arc<str>,
+    /// Requests per minute, 0 for unlimited
+    pub rate_limit_per_min: u32,
+}
+
+/// API keys and their usage. Configured with `API_KEY_REQUIRED` (1 to reject keyless requests),
+/// `API_RATE_LIMIT_PER_MIN` for each client of the default tenant, `API_KEY_LOOKUPS_PER_MIN` for the keys a client can
+/// have looked up in the db, `API_TRUST_FORWARDED_FOR` (1 to tell clients apart by `x-forwarded-for` behind a proxy)
+/// and `ADMIN_TOKEN` to enable the key management routes.
+#[derive(Clone, Default)]
+pub struct Tenants {
+    require_key: bool,
+    default_rate_limit_per_min: u32,
+    lookups_per_min: u32,
+    trust_forwarded_for: bool,
+    admin_token: Option<Arc<str>>,
+    /// Key hash to (when it was looked up, its tenant), unknown keys are cached too so they aren't looked up again
+    keys: Arc<DashMap<String, (Instant, Option<Tenant>)>>,
+    /// Tenant to (minute, requests made in it)
+    usage: Arc<DashMap<Arc<str>, (u64, u32)>>,
+    /// Client to (minute, requests made in it) for keyless requests, which would all share the default tenant's otherwise
+    anonymous_usage: Arc<DashMap<IpAddr, (u64, u32)>>,
+    /// Client to (minute, uncached keys looked up in it)
+    lookups: Arc<DashMap<IpAddr, (u64, u32)>>,
+}
+
+impl Tenants {
+    pub fn from_env() -> Self {
+        Self {
+            require_key: env::var("API_KEY_REQUIRED").is_ok_and(|v| v == "1"),
+            default_rate_limit_per_min: env::var("API_RATE_LIMIT_PER_MIN").ok().and_then(|v| v.parse().ok()).unwrap_or(0),
+            lookups_per_min: env::var("API_KEY_LOOKUPS_PER_MIN").ok().and_then(|v| v.parse().ok()).unwrap_or(60),
+            trust_forwarded_for: env::var("API_TRUST_FORWARDED_FOR").is_ok_and(|v| v == "1"),
+            admin_token: env::var("ADMIN_TOKEN").ok().filter(|t| !t.is_empty()).map(Into::into),
+            ..Default::default()
+        }
+    }
+
+    pub fn has_admin(&self) -> bool {
+        self.admin_token.is_some()
+    }
+
+    fn default_tenant(&self) -> Tenant {
+        Tenant {
+            name: "".into(),
+            rate_limit_per_min: self.default_rate_limit_per_min,
+        }
+    }
+
+    /// The key's tenant, `None` if it's unknown or revoked. Looked up off the async runtime, at most `lookups_per_min`
+    /// times a minute for each client.
+    async fn lookup(&self, pool: &Pool, key: &str, client: IpAddr, minute: u64) -> Result<Option<Tenant>, (StatusCode, &'static str)> {
+        let hash = hash_key(key);
+        let now = Instant::now();
+        if let Some(cached) = self.keys.get(&hash).filter(|cached| now.duration_since(cached.0) < KEY_CACHE_TTL) {
+            return Ok(cached.1.clone());
+        }
+        if !count(&self.lookups, client, self.lookups_per_min, minute) {
+            return Err((StatusCode::TOO_MANY_REQUESTS, "too many api keys tried"));
+        }
+        let pool = pool.clone();
+        let key_hash = hash.clone();
+        let found = tokio::task::spawn_blocking(move || -> Result<Option<(String, u32)>, mysql::Error> {
+            pool.get_conn()?.exec_first("select tenant, rate_limit_per_min from api_keys where key_hash=? and revoked_at is null", (&key_hash,))
+        }).await;
+        let found = match found {
+            Ok(Ok(found)) => found,
+            Ok(Err(e)) => {
+                eprintln!("Failed to look up an api key: {}", e);
+                return Err((StatusCode::SERVICE_UNAVAILABLE, "unable to check the api key"));
+            }
+            Err(e) => {
+                eprintln!("Failed to look up an api key: {}", e);
+                return Err((StatusCode::SERVICE_UNAVAILABLE, "unable to check the api key"));
+            }
+        };
+        let tenant = found.map(|(name, rate_limit_per_min)| Tenant { name: name.into(), rate_limit_per_min });
+        if self.keys.len() >= MAX_CACHED_ENTRIES {
+            self.keys.retain(|_, cached| now.duration_since(cached.0) < KEY_CACHE_TTL);
+        }
+        self.keys.insert(hash, (now, tenant.clone()));
+        Ok(tenant)
+    }
+
+    /// Counts a request against the tenant's limit for `minute`, false if it's over
+    fn check_rate(&self, tenant: &Tenant, minute: u64) -> bool {
+        count(&self.usage, tenant.name.clone(), tenant.rate_limit_per_min, minute)
+    }
+
+    /// Same for a keyless request, limited per client
+    fn check_anonymous_rate(&self, client: IpAddr, minute: u64) -> bool {
+        count(&self.anonymous_usage, client, self.default_rate_limit_per_min, minute)
+    }
+
+    /// The proxy's `x-forwarded-for` if it's trusted, the peer's address otherwise
+    fn client(&self, request: &Request) -> IpAddr {
+        let forwarded = self.trust_forwarded_for.then(|| request.headers().get("x-forwarded-for")).flatten()
+            .and_then(|v| v.to_str().ok()).and_then(|v| v.split(',').next()).and_then(|ip| ip.trim().parse().ok());
+        forwarded.or_else(|| request.extensions().get::<ConnectInfo<SocketAddr>>().map(|info| info.0.ip())).unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
+    }
+
+    fn is_admin(&self, headers: &HeaderMap) -> bool {
+        let token = headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok()).and_then(|v| v.strip_prefix("Bearer "));
+        match (self.admin_token.as_deref(), token) {
+            (Some(admin), Some(token)) => constant_time_eq(admin.as_bytes(), token.as_bytes()),
+            _ => false,
+        }
+    }
+}
+
+/// Counts a request against `limit` for `minute`, false if it's over. A limit of 0 is unlimited.
+fn count<K: Eq + Hash>(usage: &DashMap<K, (u64, u32)>, key: K, limit: u32, minute: u64) -> bool {
+    if limit == 0 {
+        return true;
+    }
+    if usage.len() >= MAX_CACHED_ENTRIES {
+        usage.retain(|_, u| u.0 == minute);
+    }
+    let mut usage = usage.entry(key).or_insert((minute, 0));
+    if usage.0 != minute {
+        *usage = (minute, 0);
+    }
+    usage.1 += 1;
+    usage.1 <= limit
+}
+
+/// Compares the digests of both, so the time taken tells neither where they differ nor how long they are
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    Sha256::digest(a).iter().zip(Sha256::digest(b).iter()).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+#[derive(Deserialize)]
+struct KeyQuery {
+    api_key: Option<String>,
+}
+
+/// Resolves the request's tenant from its key and applies the tenant's rate limit.
+/// The key goes in the `x-api-key` header, or the `api_key` query parameter for websockets opened from a browser.
+pub async fn authenticate(State(state): State<ApiState>, mut request: Request, next: Next) -> Response {
+    let key = request.headers().get(API_KEY_HEADER).and_then(|v| v.to_str().ok()).map(str::to_string)
+        .or_else(|| Query::<KeyQuery>::try_from_uri(request.uri()).ok().and_then(|q| q.0.api_key));
+    let minute = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() / 60;
+    let client = state.tenants.client(&request);
+    let (tenant, within_rate) = match key {
+        Some(key) => match state.tenants.lookup(state.pool.primary(), &key, client, minute).await {
+            Ok(Some(tenant)) => {
+                let within_rate = state.tenants.check_rate(&tenant, minute);
+                (tenant, within_rate)
+            }
+            Ok(None) => return (StatusCode::UNAUTHORIZED, "invalid api key").into_response(),
+            Err(e) => return e.into_response(),
+        },
+        None if state.tenants.require_key => return (StatusCode::UNAUTHORIZED, "api key required").into_response(),
+        None => (state.tenants.default_tenant(), state.tenants.check_anonymous_rate(client, minute)),
+    };
+    if !within_rate {
+        return (StatusCode::TOO_MANY_REQUESTS, "rate limit exceeded").into_response();
+    }
+    request.extensions_mut().insert(tenant);
+    next.run(request).await
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewKey {
+    tenant: String,
+    #[serde(default)]
+    rate_limit_per_min: u32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedKey {
+    id: u64,
+    tenant: String,
+    /// Only ever returned here
+    key: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyInfo {
+    id: u64,
+    tenant: String,
+    rate_limit_per_min: u32,
+    created_at: i64,
+    revoked_at: Option<i64>,
+}
+
+/// Issues a key for a tenant, creating the tenant if it's new. Tenants share nothing but the indexed data.
+pub async fn handle_create_key(State(state): State<ApiState>, headers: HeaderMap, Json(new_key): Json<NewKey>) -> Response {
+    if !state.tenants.is_admin(&headers) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    if new_key.tenant.is_empty() || new_key.tenant.len() > MAX_TENANT_LENGTH {
+        return (StatusCode::BAD_REQUEST, "tenant must be 1 to 64 characters").into_response();
+    }
+    let key = hex::encode(rand::random::<[u8; 32]>());
+    let mut conn = state.pool.primary().get_conn().unwrap();
+    conn.exec_drop("insert into api_keys (tenant, key_hash, rate_limit_per_min) values (?, ?, ?)", (&new_key.tenant, hash_key(&key), new_key.rate_limit_per_min)).unwrap();
+    Json(CreatedKey { id: conn.last_insert_id(), tenant: new_key.tenant, key }).into_response()
+}
+
+pub async fn handle_list_keys(State(state): State<ApiState>, headers: HeaderMap) -> Response {
+    if !state.tenants.is_admin(&headers) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    let mut conn = state.pool.primary().get_conn().unwrap();
+    let keys = conn.query_map(
+        "select id, tenant, rate_limit_per_min, unix_timestamp(created_at), unix_timestamp(revoked_at) from api_keys order by id",
+        |(id, tenant, rate_limit_per_min, created_at, revoked_at)| KeyInfo { id, tenant, rate_limit_per_min, created_at, revoked_at },
+    ).unwrap();
+    Json(keys).into_response()
+}
+
+/// Revokes a key, requests with it are rejected from then on by this replica and within `KEY_CACHE_TTL` by the others
+pub async fn handle_revoke_key(State(state): State<ApiState>, headers: HeaderMap, Path(id): Path<u64>) -> Response {
+    if !state.tenants.is_admin(&headers) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    let mut conn = state.pool.primary().get_conn().unwrap();
+    let Some(hash): Option<String> = conn.exec_first("select key_hash from api_keys where id=? and revoked_at is null", (id,)).unwrap() else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    conn.exec_drop("update api_keys set revoked_at=current_timestamp where id=?", (id,)).unwrap();
+    state.tenants.keys.remove(&hash);
+    StatusCode::NO_CONTENT.into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_rate() {
+        let tenants = Tenants::default();
+        let tenant = Tenant { name: "a".into(), rate_limit_per_min: 2 };
+        assert!(tenants.check_rate(&tenant, 1) && tenants.check_rate(&tenant, 1));
+        assert!(!tenants.check_rate(&tenant, 1));
+        // tenants are limited separately
+        assert!(tenants.check_rate(&Tenant { name: "b".into(), rate_limit_per_min: 2 }, 1));
+        // the count starts over every minute
+        assert!(tenants.check_rate(&tenant, 2));
+        let unlimited = Tenant { name: "c".into(), rate_limit_per_min: 0 };
+        assert!((0..100).all(|_| tenants.check_rate(&unlimited, 1)));
+    }
+
+    #[test]
+    fn test_check_anonymous_rate() {
+        let tenants = Tenants { default_rate_limit_per_min: 1, ..Default::default() };
+        let (a, b) = (IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1)), IpAddr::V4(Ipv4Addr::new(2, 2, 2, 2)));
+        assert!(tenants.check_anonymous_rate(a, 1));
+        assert!(!tenants.check_anonymous_rate(a, 1));
+        // keyless clients no longer share one bucket
+        assert!(tenants.check_anonymous_rate(b, 1));
+    }
+
+    #[test]
+    fn test_is_admin() {
+        let tenants = Tenants { admin_token: Some("secret".into()), ..Default::default() };
+        let mut headers = HeaderMap::new();
+        assert!(!tenants.is_admin(&headers));
+        headers.insert(header::AUTHORIZATION, "Bearer wrong".parse().unwrap());
+        assert!(!tenants.is_admin(&headers));
+        headers.insert(header::AUTHORIZATION, "Bearer secret".parse().unwrap());
+        assert!(tenants.is_admin(&headers));
+        headers.insert(header::AUTHORIZATION, "Bearer secret2".parse().unwrap());
+        assert!(!tenants.is_admin(&headers));
+        assert!(!Tenants::default().is_admin(&headers));
+    }
+}
@@ -0,0 +1,166 @@
+use std::{collections::{HashMap, HashSet}, str::FromStr as _, sync::Arc};
+
+use axum::{extract::{Path, State}, http::StatusCode, response::{IntoResponse, Response}, Json};
+use derive_getters::Getters;
+use mysql::{prelude::Queryable as _, PooledConn, Value};
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{api::ApiState, events::addresses::WSOL_MINT, utils::estimate_victim_losses};
+
+// (sandwich id, role, event id, input mint, output mint, input amount, output amount)
+type LegRow = (String, String, u64, String, String, u64, u64);
+// (sandwich id, event id, slot, amm, block time, price impact bps, est loss lamports)
+type VictimRow = (String, u64, u64, String, Option<i64>, Option<u64>, Option<u64>);
+
+#[derive(Serialize, Getters)]
+#[serde(rename_all = "camelCase")]
+pub struct Incident {
+    slot: u64,
+    block_time: Option<i64>,
+}
+
+#[derive(Serialize, Getters)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetedPool {
+    amm: Arc<str>,
+    times_sandwiched: u64,
+}
+
+#[derive(Serialize, Getters)]
+#[serde(rename_all = "camelCase")]
+pub struct InvolvedCluster {
+    cluster_id: u32,
+    sandwiches: u64,
+}
+
+#[derive(Serialize, Getters)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletSummary {
+    wallet: Arc<str>,
+    times_sandwiched: u64,
+    /// Only counts sandwiches on pairs priced in SOL
+    est_loss_lamports: u64,
+    /// Mean of the stored per-incident price impacts, incidents indexed before they were stored are left out
+    avg_price_impact_bps: Option<u64>,
+    worst_price_impact_bps: Option<u64>,
+    most_targeted_pool: Option<TargetedPool>,
+    first_incident: Option<Incident>,
+    last_incident: Option<Incident>,
+    attacker_clusters: Vec<InvolvedCluster>,
+}
+
+/// How often and how badly a wallet got sandwiched, and by whom.
+/// Unavailable while victims are redacted, as it would tie a wallet to its incidents.
+pub async fn handle_wallet_summary(State(state): State<ApiState>, Path(wallet): Path<String>) -> Response {
+    if Pubkey::from_str(&wallet).is_err() {
+        return (StatusCode::BAD_REQUEST, "invalid wallet").into_response();
+    }
+    if state.redaction.victim(&wallet).is_some() {
+        return (StatusCode::FORBIDDEN, "victims are redacted").into_response();
+    }
+    Json(load_wallet_summary(&mut state.pool.get_conn().unwrap(), &wallet)).into_response()
+}
+
+/// Summary of every sandwich `wallet` was a victim of, it's up to the caller to check it isn't redacted
+pub fn load_wallet_summary(conn: &mut PooledConn, wallet: &str) -> WalletSummary {
+    // each time the wallet was a victim
+    let victim_rows: Vec<VictimRow> = conn.exec(
+        "select s.id, v.id, v.slot, v.amm, t.block_time, s.price_impact_bps, s.est_victim_loss_lamports from sandwiches s join event_view v on v.id=s.event_id left join transactions t on t.slot=v.slot and t.inclusion_order=v.inclusion_order where s.role='VICTIM' and v.authority=? order by v.slot, v.inclusion_order",
+        (wallet,),
+    ).unwrap();
+    let mut summary = WalletSummary {
+        wallet: wallet.into(),
+        times_sandwiched: victim_rows.len() as u64,
+        est_loss_lamports: 0,
+        avg_price_impact_bps: None,
+        worst_price_impact_bps: None,
+        most_targeted_pool: None,
+        first_incident: victim_rows.first().map(|r| Incident { slot: r.2, block_time: r.4 }),
+        last_incident: victim_rows.last().map(|r| Incident { slot: r.2, block_time: r.4 }),
+        attacker_clusters: vec![],
+    };
+    if victim_rows.is_empty() {
+        return summary;
+    }
+    let impacts: Vec<u64> = victim_rows.iter().filter_map(|r| r.5).collect();
+    if !impacts.is_empty() {
+        summary.avg_price_impact_bps = Some(impacts.iter().sum::<u64>() / impacts.len() as u64);
+        summary.worst_price_impact_bps = impacts.iter().max().copied();
+    }
+    let mut pools: HashMap<&str, u64> = HashMap::new();
+    victim_rows.iter().for_each(|r| *pools.entry(r.3.as_str()).or_default() += 1);
+    summary.most_targeted_pool = pools.into_iter().max_by_key(|(_, count)| *count).map(|(amm, times_sandwiched)| TargetedPool { amm: amm.into(), times_sandwiched });
+
+    let sandwich_ids: HashSet<&str> = victim_rows.iter().map(|r| r.0.as_str()).collect();
+    let placeholders = "?,".repeat(sandwich_ids.len());
+    let placeholders = placeholders.trim_end_matches(",");
+    let params: Vec<Value> = sandwich_ids.iter().map(|&id| Value::from(id)).collect();
+    // losses are stored at detection time, only incidents indexed before that are estimated here
+    let (stored, unstored): (Vec<&VictimRow>, Vec<&VictimRow>) = victim_rows.iter().partition(|r| r.6.is_some());
+    summary.est_loss_lamports = stored.iter().filter_map(|r| r.6).sum();
+    if !unstored.is_empty() {
+        let unstored_ids: HashSet<&str> = unstored.iter().map(|r| r.0.as_str()).collect();
+        // the loss model needs every victim of the sandwich in order, not just this wallet's
+        let legs: Vec<LegRow> = conn.exec(
+            format!("select s.id, s.role, v.id, v.input_mint, v.output_mint, v.input_amount, v.output_amount from sandwiches s join event_view v on v.id=s.event_id where s.id in ({}) and s.role in ('FRONTRUN', 'VICTIM') order by v.slot, v.inclusion_order, v.ix_index, v.inner_ix_index", "?,".repeat(unstored_ids.len()).trim_end_matches(",")),
+            unstored_ids.iter().map(|&id| Value::from(id)).collect::<Vec<_>>(),
+        ).unwrap();
+        let own_events: Vec<u64> = unstored.iter().map(|r| r.1).collect();
+        summary.est_loss_lamports += wallet_loss_lamports(&legs, &own_events);
+    }
+    summary.attacker_clusters = conn.exec_map(
+        format!("select c.cluster_id, count(distinct s.id) as n from sandwiches s join events_with_id e on e.id=s.event_id join attacker_clusters c on c.attacker_id=e.authority_id where s.id in ({placeholders}) and s.role in ('FRONTRUN', 'BACKRUN') group by c.cluster_id order by n desc"),
+        params,
+        |(cluster_id, sandwiches)| InvolvedCluster { cluster_id, sandwiches },
+    ).unwrap();
+    summary
+}
+
+/// Sums the estimated loss of the victim events in `own_events`, given the frontruns and victims of their sandwiches in chronological order
+fn wallet_loss_lamports(legs: &[LegRow], own_events: &[u64]) -> u64 {
+    let wsol = WSOL_MINT.to_string();
+    let mut sandwiches: HashMap<&str, Vec<&LegRow>> = HashMap::new();
+    legs.iter().for_each(|l| sandwiches.entry(l.0.as_str()).or_default().push(l));
+    sandwiches.values().map(|legs| {
+        let (frontruns, victims): (Vec<&LegRow>, Vec<&LegRow>) = legs.iter().partition(|l| l.1 == "FRONTRUN");
+        let Some(first) = frontruns.first() else {
+            return 0;
+        };
+        let frontrun = frontruns.iter().fold((0, 0), |(i, o), l| (i + l.5, o + l.6));
+        let losses = estimate_victim_losses(frontrun, &victims.iter().map(|l| (l.5, l.6)).collect::<Vec<_>>());
+        victims.iter().zip(losses).filter(|(l, _)| own_events.contains(&l.2)).map(|(_, loss)| {
+            if first.3 == wsol {
+                *loss.input_amount()
+            } else if first.4 == wsol {
+                *loss.output_amount()
+            } else {
+                0
+            }
+        }).sum::<u64>()
+    }).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wallet_loss_lamports() {
+        let wsol = WSOL_MINT.to_string();
+        let leg = |id: &str, role: &str, event_id: u64, input_amount: u64, output_amount: u64| (id.to_string(), role.to_string(), event_id, wsol.clone(), "token".to_string(), input_amount, output_amount);
+        let legs = vec![
+            leg("a", "FRONTRUN", 1, 1_000_000, 900_000),
+            leg("a", "VICTIM", 2, 1_000_000, 700_000),
+            leg("a", "VICTIM", 3, 1_000_000, 600_000),
+        ];
+        let all = wallet_loss_lamports(&legs, &[2, 3]);
+        let first = wallet_loss_lamports(&legs, &[2]);
+        let second = wallet_loss_lamports(&legs, &[3]);
+        assert!(first > 0 && second > 0);
+        assert_eq!(all, first + second);
+        assert_eq!(wallet_loss_lamports(&legs, &[4]), 0);
+        // no frontrun to solve the pool from
+        assert_eq!(wallet_loss_lamports(&legs[1..], &[2, 3]), 0);
+    }
+}
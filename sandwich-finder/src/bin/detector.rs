@@ -1,111 +1,20 @@
-use std::sync::{atomic::{AtomicU64, Ordering}, Arc};
+//! Same as `sandwich-finder detect`, kept for existing deployments
 
-use sandwich_finder::{detector::{get_events, LEADER_GROUP_SIZE}, events::{common::Inserter, sandwich::detect}, utils::create_db_pool};
-use serde::{Deserialize, Serialize};
-use tokio::task::JoinSet;
-
-const MAX_CHUNK_SIZE: u64 = 1000; // max slots to fetch at a time
-
-#[derive(Debug, Serialize, Deserialize)]
-struct GraphNode {
-    id: String,
-    label: String,
-    #[serde(rename = "type")]
-    node_type: String, // "token_account" or "market"
-    value: Option<u64>,
-    mint: Option<String>, // For token accounts
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct GraphEdge {
-    source: String,
-    target: String,
-    label: String,
-    amount: u64,
-    timestamp: String, // Serialized timestamp for ordering
-    order: usize,
-    edge_type: String, // "swap" or "transfer"
-    trading_pair: Option<String>, // For swaps
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct TransferGraph {
-    nodes: Vec<GraphNode>,
-    edges: Vec<GraphEdge>,
-    slot: u64,
-}
-
-// Swap in slot 371237175 (order 1242, ix 1, inner_ix Some(1))
-// Swap in slot 371237175 (order 1247, ix 5, inner_ix None)
-// Swap in slot 371237175 (order 1248, ix 2, inner_ix Some(0))
+use sandwich_finder::commands::{detect::{self, DetectMode}, Context};
 
 #[tokio::main]
 async fn main() {
-    dotenv::dotenv().ok();
-    // let slot = 371237175;
-    // parse the 1st arg for slot
     let args: Vec<String> = std::env::args().collect();
     if args.len() < 2 {
-        println!("Usage: {} <slot>", args[0]);
+        println!("Usage: {} <slot> [end slot] | --follow [start slot]", args[0]);
         return;
     }
-    let start_slot: u64 = args[1].parse().expect("Invalid slot");
-    let end_slot: u64 = if args.len() >= 3 {
-        args[2].parse().expect("Invalid slot")
+    let slot = |arg: &String| arg.parse().expect("Invalid slot");
+    let mode = if args[1] == "--follow" {
+        DetectMode::Follow(args.get(2).map(slot))
     } else {
-        start_slot
+        let start_slot = slot(&args[1]);
+        DetectMode::Range(start_slot, args.get(2).map(slot).unwrap_or(start_slot))
     };
-    // alignment
-    let start_slot = start_slot / LEADER_GROUP_SIZE * LEADER_GROUP_SIZE;
-    let end_slot = end_slot / LEADER_GROUP_SIZE * LEADER_GROUP_SIZE + LEADER_GROUP_SIZE - 1;
-    // fetch events for up to 1k slots at a time and process in groups of 4 slots
-    let pool = create_db_pool();
-    let inserter = Inserter::new(pool.clone());
-    let chunk_size = ((end_slot - start_slot + 1) / 16).min(MAX_CHUNK_SIZE - LEADER_GROUP_SIZE) / LEADER_GROUP_SIZE * LEADER_GROUP_SIZE + LEADER_GROUP_SIZE;
-    println!("Processing slots {} to {} ({} leader groups)", start_slot, end_slot, (end_slot - start_slot + 1) / LEADER_GROUP_SIZE);
-    let progress = Arc::from(AtomicU64::new(0));
-    let mut set = JoinSet::new();
-    for chunk_start in (start_slot..=end_slot).step_by(chunk_size as usize) {
-        let chunk_end = (chunk_start + chunk_size - 1).min(end_slot);
-        let pool = pool.clone(); // docs said this is cloneable
-        let mut inserter = inserter.clone();
-        let progress = progress.clone();
-        set.spawn(async move {
-            println!("Fetching events for slots {} to {}", chunk_start, chunk_end);
-            let (swaps, transfers, txs) = get_events(pool.clone(), chunk_start, chunk_end).await;
-            let mut swaps_start = 0;
-            let mut transfers_start = 0;
-            let mut txs_start = 0;
-            for slot in (chunk_start..=chunk_end).step_by(LEADER_GROUP_SIZE as usize) {
-                let swaps_end = swaps.iter().skip(swaps_start).position(|s| *s.slot() >= slot + LEADER_GROUP_SIZE).map(|n| n + swaps_start).unwrap_or(swaps.len());
-                let transfers_end = transfers.iter().skip(transfers_start).position(|t| *t.slot() >= slot + LEADER_GROUP_SIZE).map(|n| n + transfers_start).unwrap_or(transfers.len());
-                let txs_end = txs.iter().skip(txs_start).position(|t| *t.slot() >= slot + LEADER_GROUP_SIZE).map(|n| n + txs_start).unwrap_or(txs.len());
-
-                let slot_swaps = &swaps[swaps_start..swaps_end];
-                let slot_transfers = &transfers[transfers_start..transfers_end];
-                let slot_txs = &txs[txs_start..txs_end];
-                println!("Processing slots {} to {}", slot, slot + LEADER_GROUP_SIZE - 1);
-                // println!("Swaps: {:#?}", slot_swaps.len());
-                // println!("Transfers: {:#?}", slot_transfers.len());
-                // println!("Txs: {:#?}", slot_txs.len());
-                let sandwiches = detect(slot_swaps, slot_transfers, slot_txs);
-                // for sandwich in sandwiches.iter() {
-                //     println!("Detected sandwich: {:#?}", sandwich);
-                // }
-                inserter.insert_sandwiches(slot, sandwiches).await;
-
-                swaps_start = swaps_end;
-                transfers_start = transfers_end;
-                txs_start = txs_end;
-                let completed = progress.fetch_add(1, Ordering::AcqRel);
-                // if completed % 100 == 0 {
-                    println!("{}/{}", completed, (end_slot - start_slot + 1) / LEADER_GROUP_SIZE);
-                // }
-            }
-        });
-        if set.len() >= 16 {
-            set.join_next().await;
-        }
-    }
-    set.join_all().await;
+    detect::run(Context::init(), mode).await;
 }
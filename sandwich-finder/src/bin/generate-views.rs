@@ -0,0 +1,22 @@
+use sandwich_finder::{utils::create_db_pool, views::generate_views};
+
+/// Creates or updates the `grafana_*` views to match the current schema, rerun after applying migrations
+fn main() {
+    dotenv::dotenv().ok();
+    let pool = create_db_pool();
+    let mut conn = pool.get_conn().unwrap();
+    match generate_views(&mut conn) {
+        Ok(plan) => {
+            for (name, _) in plan.create.iter() {
+                println!("created {}", name);
+            }
+            for (name, missing) in plan.drop.iter() {
+                println!("dropped {}, table {} doesn't exist", name, missing);
+            }
+        },
+        Err(e) => {
+            eprintln!("Failed to generate views: {}", e);
+            std::process::exit(1);
+        },
+    }
+}
@@ -0,0 +1,50 @@
+use std::{env, fs::File, io::{self, BufRead, BufReader}};
+
+use sandwich_finder::{bundles::{insert_bundles, link_bundles, BundleReceipt}, utils::create_db_pool};
+
+/// Receipts inserted per batch
+const BATCH_SIZE: usize = 1000;
+
+/// Imports Jito bundle receipts, one json object per line of the form `{"bundleId": ..., "slot": ..., "transactions": [sig, ...]}`,
+/// from the file given as the first argument or stdin, then links them to the sandwiches already detected in the covered slots.
+fn main() {
+    dotenv::dotenv().ok();
+    let pool = create_db_pool();
+    let mut conn = pool.get_conn().unwrap();
+    let input: Box<dyn BufRead> = match env::args().nth(1) {
+        Some(path) => Box::new(BufReader::new(File::open(path).unwrap())),
+        None => Box::new(io::stdin().lock()),
+    };
+    let mut batch = vec![];
+    let mut imported = 0;
+    let mut slots = (u64::MAX, 0);
+    for (i, line) in input.lines().enumerate() {
+        let line = line.unwrap();
+        if line.trim().is_empty() {
+            continue;
+        }
+        let bundle: BundleReceipt = match serde_json::from_str(&line) {
+            Ok(bundle) => bundle,
+            Err(e) => {
+                eprintln!("Skipping line {}: {}", i + 1, e);
+                continue;
+            }
+        };
+        slots = (slots.0.min(bundle.slot), slots.1.max(bundle.slot));
+        batch.push(bundle);
+        if batch.len() == BATCH_SIZE {
+            insert_bundles(&mut conn, &batch).unwrap();
+            imported += batch.len();
+            batch.clear();
+            println!("imported {} bundles", imported);
+        }
+    }
+    insert_bundles(&mut conn, &batch).unwrap();
+    imported += batch.len();
+    if imported == 0 {
+        println!("no bundles imported");
+        return;
+    }
+    let linked = link_bundles(&mut conn, slots.0, slots.1).unwrap();
+    println!("imported {} bundles in slots {}..={}, linked {} sandwich events", imported, slots.0, slots.1, linked);
+}
@@ -1,12 +1,13 @@
-use std::{collections::{HashMap, HashSet}, env};
+use std::collections::{HashMap, HashSet};
 
-use mysql::{prelude::Queryable, Pool};
+use mysql::prelude::Queryable;
+use sandwich_finder::{config, utils::create_db_pool};
 use solana_rpc_client::nonblocking::rpc_client::RpcClient;
 
 #[tokio::main]
 async fn main() {
     dotenv::dotenv().ok();
-    let rpc_client = RpcClient::new(env::var("RPC_URL").unwrap());
+    let rpc_client = RpcClient::new(config::get().rpc_url().clone().expect("RPC_URL is not set"));
     let args = std::env::args().collect::<Vec<_>>();
     let epoch = if args.len() >= 2 {
         args[1].parse::<u64>().expect("Invalid epoch")
@@ -15,8 +16,7 @@ async fn main() {
     };
     let leader_schedule = rpc_client.get_leader_schedule(Some(epoch * 432000)).await.unwrap();
     let leader_schedule = leader_schedule.unwrap();
-    let mysql_url = env::var("MYSQL").unwrap();
-    let pool = Pool::new(mysql_url.as_str()).unwrap();
+    let pool = create_db_pool();
     let mut conn = pool.get_conn().unwrap();
     let leader_set: HashSet<_> = leader_schedule.keys().collect();
     let stmt = format!("insert ignore into address_lookup_table (address) values {}", leader_set.iter().map(|_| "(?)").collect::<Vec<_>>().join(","));
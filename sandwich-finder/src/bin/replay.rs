@@ -0,0 +1,36 @@
+use std::env;
+
+use sandwich_finder::{config, metrics, events::{common::Inserter, event::start_event_processor}, shutdown::Shutdown, source::file::FileSource, utils::create_db_pool};
+
+const CHUNK_SIZE: usize = 1000;
+
+#[tokio::main]
+async fn main() {
+    dotenv::dotenv().ok();
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 {
+        eprintln!("Usage: {} <dump file>", args[0]);
+        std::process::exit(1);
+    }
+    let rpc_url = config::get().rpc_url().clone().expect("RPC_URL is not set");
+    metrics::start_reporter(std::time::Duration::from_secs(60));
+    let pool = create_db_pool();
+    let source = match FileSource::open(&args[1]) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("Unable to read dump {}: {}", args[1], e);
+            std::process::exit(1);
+        }
+    };
+    // missing LUTs are fetched over rpc, the ones updated in the dump are applied as they're read
+    let mut receiver = start_event_processor(source, rpc_url, Shutdown::install(), None, None);
+    let inserter = Inserter::new(pool.clone());
+    while let Some((_slot, event)) = receiver.recv().await {
+        println!("Received batch: {:?}", event.len());
+        let mut inserter = inserter.clone();
+        for chunk in event.chunks(CHUNK_SIZE) {
+            inserter.insert_events(chunk).await;
+        }
+    }
+    println!("Replay finished");
+}
@@ -1,6 +1,7 @@
 use std::{collections::HashMap, env, time};
 
-use mysql::{prelude::Queryable, Pool};
+use mysql::prelude::Queryable;
+use sandwich_finder::utils::create_db_pool;
 use serde::Deserialize;
 use tokio::task::JoinHandle;
 use std::fs::File;
@@ -52,8 +53,7 @@ async fn main() {
         serde_json::from_str(&text).unwrap()
     });
     let now = time::Instant::now();
-    let mysql_url = env::var("MYSQL").unwrap();
-    let pool = Pool::new(mysql_url.as_str()).unwrap();
+    let pool = create_db_pool();
     let mut conn = pool.get_conn().unwrap();
     let mut report = File::create("report.csv").unwrap();
     let mut filtered_report = File::create("filtered_report.csv").unwrap();
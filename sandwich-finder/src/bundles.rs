@@ -0,0 +1,83 @@
+use std::{collections::HashMap, sync::Arc};
+
+use mysql::{prelude::Queryable as _, PooledConn};
+use serde::Deserialize;
+
+/// A landed bundle as exported by the Jito block engine
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleReceipt {
+    pub bundle_id: Arc<str>,
+    pub slot: u64,
+    /// Signatures of the txs in the bundle, in bundle order
+    pub transactions: Vec<Arc<str>>,
+}
+
+/// Stores the receipts, receipts already imported are ignored
+pub fn insert_bundles(conn: &mut PooledConn, bundles: &[BundleReceipt]) -> mysql::Result<()> {
+    let args = bundles.iter().flat_map(|b| b.transactions.iter().map(move |sig| (b.slot, sig.as_ref(), b.bundle_id.as_ref())));
+    conn.exec_batch("insert ignore into jito_bundles (slot, sig, bundle_id) values (?, ?, ?)", args)
+}
+
+/// Sandwich events still without a bundle id, looked up by the slot and signature of their tx
+#[derive(Clone, Debug, Default)]
+pub struct UnlinkedEvents(HashMap<u64, HashMap<String, Vec<u64>>>);
+
+impl UnlinkedEvents {
+    /// From the (event id, slot, sig) of each event
+    pub fn new(events: impl IntoIterator<Item = (u64, u64, String)>) -> Self {
+        let mut by_tx: HashMap<u64, HashMap<String, Vec<u64>>> = HashMap::new();
+        for (event_id, slot, sig) in events {
+            by_tx.entry(slot).or_default().entry(sig).or_default().push(event_id);
+        }
+        Self(by_tx)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Ids of the events whose tx is `sig` in `slot`
+    pub fn events(&self, slot: u64, sig: &str) -> &[u64] {
+        self.0.get(&slot).and_then(|sigs| sigs.get(sig)).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Sets the bundle id of the sandwich events in `[from_slot, to_slot]` whose tx has a receipt, returning the events linked
+pub fn link_bundles(conn: &mut PooledConn, from_slot: u64, to_slot: u64) -> mysql::Result<u64> {
+    let unlinked = UnlinkedEvents::new(conn.exec::<(u64, u64, String), _, _>(
+        "select s.event_id, e.slot, t.sig from sandwiches s join events_with_id e on e.id=s.event_id join transactions t on t.slot=e.slot and t.inclusion_order=e.inclusion_order where e.slot between ? and ? and s.bundle_id is null",
+        (from_slot, to_slot),
+    )?);
+    if unlinked.is_empty() {
+        return Ok(0);
+    }
+    let mut links = vec![];
+    for row in conn.exec_iter("select slot, sig, bundle_id from jito_bundles where slot between ? and ?", (from_slot, to_slot))? {
+        let (slot, sig, bundle_id): (u64, String, String) = mysql::from_row(row?);
+        links.extend(unlinked.events(slot, &sig).iter().map(|&event_id| (bundle_id.clone(), event_id)));
+    }
+    conn.exec_batch("update sandwiches set bundle_id=? where event_id=? and bundle_id is null", &links)?;
+    Ok(links.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_bundles() {
+        // as exported by the block engine, the second bundle has nothing to do with the sandwich
+        let fixture = r#"{"bundleId":"b1","slot":10,"transactions":["front","victim","back"]}
+{"bundleId":"b2","slot":10,"transactions":["other"]}
+{"bundleId":"b3","slot":11,"transactions":[]}"#;
+        let bundles: Vec<BundleReceipt> = fixture.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(bundles.iter().map(|b| (b.bundle_id.as_ref(), b.slot, b.transactions.len())).collect::<Vec<_>>(), vec![("b1", 10, 3), ("b2", 10, 1), ("b3", 11, 0)]);
+        assert!(serde_json::from_str::<BundleReceipt>(r#"{"bundle_id":"b4","slot":12,"transactions":[]}"#).is_err());
+        // the sandwich's frontrun and backrun events, plus a victim that landed outside the bundle in the next slot
+        let unlinked = UnlinkedEvents::new([(1, 10, "front".to_string()), (2, 10, "back".to_string()), (3, 10, "back".to_string()), (4, 11, "victim".to_string())]);
+        let links: Vec<_> = bundles.iter().flat_map(|b| b.transactions.iter().flat_map(|sig| unlinked.events(b.slot, sig).iter().map(|&event_id| (b.bundle_id.as_ref(), event_id)))).collect();
+        assert_eq!(links, vec![("b1", 1), ("b1", 2), ("b1", 3)]);
+        assert!(UnlinkedEvents::default().is_empty());
+    }
+}
@@ -0,0 +1,131 @@
+use std::{collections::{HashMap, HashSet}, env, str::FromStr as _};
+
+use solana_sdk::pubkey::Pubkey;
+use yellowstone_grpc_proto::geyser::SubscribeUpdateBlock;
+
+use crate::{detector::SLOTS_PER_HOUR, events::{addresses::program_name, event::{Event, FINDERS}}, metrics};
+
+/// (successful txs invoking the program, swaps found on it) over the current window
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Activity {
+    txs: u64,
+    swaps: u64,
+}
+
+/// Watches for finders that stop finding swaps while their program keeps being invoked, which is what a protocol
+/// changing its instruction layout looks like from here. Counts are kept per window of `FINDER_CANARY_WINDOW_SLOTS`
+/// (an hour by default) and exported as `finder_txs_<program>` and `finder_swaps_<program>` when it closes.
+pub struct FinderCanary {
+    window_slots: u64,
+    /// Txs a program must show up in over a window before its finder finding nothing raises an alarm
+    min_txs: u64,
+    window_start: Option<u64>,
+    activity: HashMap<Pubkey, Activity>,
+    /// (txs, swaps) metric names per program, leaked once at startup
+    metric_names: HashMap<Pubkey, (&'static str, &'static str)>,
+}
+
+impl FinderCanary {
+    pub fn new(window_slots: u64, min_txs: u64) -> Self {
+        let metric_names = FINDERS.programs().into_iter().map(|program| {
+            let (txs, swaps): (&'static str, &'static str) = (format!("finder_txs_{}", program).leak(), format!("finder_swaps_{}", program).leak());
+            (program, (txs, swaps))
+        }).collect();
+        Self {
+            window_slots: window_slots.max(1),
+            min_txs,
+            window_start: None,
+            activity: HashMap::new(),
+            metric_names,
+        }
+    }
+
+    /// Reads `FINDER_CANARY_WINDOW_SLOTS` and `FINDER_CANARY_MIN_TXS`, 50 by default
+    pub fn from_env() -> Self {
+        let var = |name: &str, default: u64| env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self::new(var("FINDER_CANARY_WINDOW_SLOTS", SLOTS_PER_HOUR), var("FINDER_CANARY_MIN_TXS", 50))
+    }
+
+    /// Counts the finders' programs invoked by the block's successful txs and the swaps found on them
+    pub fn observe(&mut self, block: &SubscribeUpdateBlock, events: &[Event]) {
+        let mut invoked = vec![];
+        for tx in block.transactions.iter().filter(|tx| !tx.is_vote) {
+            let (Some(meta), Some(message)) = (&tx.meta, tx.transaction.as_ref().and_then(|t| t.message.as_ref())) else {
+                continue;
+            };
+            if meta.err.is_some() {
+                continue;
+            }
+            let keys: Vec<&[u8]> = message.account_keys.iter().chain(meta.loaded_writable_addresses.iter()).chain(meta.loaded_readonly_addresses.iter()).map(|k| k.as_slice()).collect();
+            let program_indexes = message.instructions.iter().map(|ix| ix.program_id_index)
+                .chain(meta.inner_instructions.iter().flat_map(|inner| inner.instructions.iter().map(|ix| ix.program_id_index)));
+            let programs: HashSet<Pubkey> = program_indexes
+                .filter_map(|i| keys.get(i as usize).and_then(|k| Pubkey::try_from(*k).ok()))
+                .filter(|program| self.metric_names.contains_key(program))
+                .collect();
+            invoked.extend(programs);
+        }
+        let swaps = events.iter().filter_map(|e| match e {
+            Event::Swap(swap) => Pubkey::from_str(swap.program()).ok(),
+            _ => None,
+        });
+        self.record(block.slot, invoked, swaps);
+    }
+
+    /// Adds a block's programs invoked (once per tx) and swaps found (once per swap), closing the window first if the
+    /// block is past it. Returns the programs found silent in the window closed, if any.
+    fn record(&mut self, slot: u64, invoked: impl IntoIterator<Item = Pubkey>, swaps: impl IntoIterator<Item = Pubkey>) -> Vec<Pubkey> {
+        let mut silent = vec![];
+        match self.window_start {
+            Some(start) if slot >= start + self.window_slots => {
+                silent = self.close_window(start, slot);
+                self.window_start = Some(slot);
+            },
+            None => self.window_start = Some(slot),
+            _ => {},
+        }
+        for program in invoked {
+            self.activity.entry(program).or_default().txs += 1;
+        }
+        for program in swaps {
+            self.activity.entry(program).or_default().swaps += 1;
+        }
+        silent
+    }
+
+    fn close_window(&mut self, start: u64, end: u64) -> Vec<Pubkey> {
+        let mut silent = vec![];
+        for (program, activity) in self.activity.drain() {
+            if let Some((txs_metric, swaps_metric)) = self.metric_names.get(&program) {
+                metrics::set(txs_metric, activity.txs);
+                metrics::set(swaps_metric, activity.swaps);
+            }
+            if activity.swaps == 0 && activity.txs >= self.min_txs {
+                println!("finder canary: {} ({}) was invoked by {} txs in slots {} - {} without a single swap found, has its layout changed?", program_name(&program).unwrap_or("unknown"), program, activity.txs, start, end - 1);
+                silent.push(program);
+            }
+        }
+        metrics::set("finder_canary_silent", silent.len() as u64);
+        metrics::add("finder_canary_alarms", silent.len() as u64);
+        silent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let mut canary = FinderCanary::new(10, 2);
+        let programs = FINDERS.programs();
+        let (working, broken, quiet) = (programs[0], programs[1], programs[2]);
+        assert!(canary.record(100, [working, broken], [working]).is_empty());
+        assert!(canary.record(105, [working, broken, quiet], [working, working]).is_empty());
+        // the first block past the window closes it
+        assert_eq!(canary.record(110, [broken], []), vec![broken]);
+        assert_eq!(canary.activity[&broken], Activity { txs: 1, swaps: 0 });
+        // too few txs to tell
+        assert!(canary.record(125, [], []).is_empty());
+    }
+}
@@ -0,0 +1,41 @@
+use crate::{commands::{detect::{self, DetectMode}, Context}, config, events::{common::Inserter, event::start_event_processor}, sinks::db::EVENT_CHUNK_SIZE, source::rpc::RpcSource};
+
+/// Indexes `[start_slot, end_slot]` from `getBlock` over `RPC_URL`, through the same finders as the live stream, then
+/// detects over it. For slots older than any geyser archive, which takes an rpc node keeping that much history.
+/// Detecting by leader needs the range's leader schedule to be populated first.
+pub async fn run(ctx: Context, start_slot: u64, end_slot: u64) {
+    let pool = ctx.pool.clone().expect("backfill needs MYSQL");
+    let rpc_url = config::get().rpc_url().clone().expect("RPC_URL is not set");
+    println!("Backfilling slots {} to {}", start_slot, end_slot);
+    let source = RpcSource::new(rpc_url.clone(), Some(start_slot), Some(end_slot));
+    let mut receiver = start_event_processor(source, rpc_url, ctx.shutdown.clone(), None, None);
+    let mut inserter = Inserter::new(pool);
+    let (mut blocks, mut failed) = (0u64, 0u64);
+    let mut last_slot = None;
+    while let Some((slot, events)) = receiver.recv().await {
+        for chunk in events.chunks(EVENT_CHUNK_SIZE) {
+            if !inserter.insert_events(chunk).await {
+                failed += 1;
+            }
+        }
+        blocks += 1;
+        last_slot = last_slot.max(Some(slot));
+        if blocks % 100 == 0 {
+            println!("Backfilled {} blocks, up to slot {}", blocks, slot);
+        }
+    }
+    println!("Backfilled {} blocks, {} event chunks failed to write", blocks, failed);
+    if ctx.shutdown.is_triggered() {
+        println!("Stopped before the end of the range, skipping detection");
+        return;
+    }
+    // the source also stops on rpc errors, only the slots that came through are worth detecting over
+    let Some(last_slot) = last_slot else {
+        eprintln!("No blocks backfilled, skipping detection");
+        return;
+    };
+    if last_slot < end_slot {
+        eprintln!("Backfill stopped at slot {} short of {}, detecting up to there", last_slot, end_slot);
+    }
+    detect::run(ctx, DetectMode::Range(start_slot, last_slot)).await;
+}
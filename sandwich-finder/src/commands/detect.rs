@@ -0,0 +1,161 @@
+use std::{env, sync::{atomic::{AtomicU64, Ordering}, Arc}, time::Duration};
+
+use crate::{commands::Context, detector::{detect_group, DetectorConfig, EventLoader, GroupConfig, LeaderSchedule}, event_cache::EventCache, events::common::Inserter, shutdown::Shutdown};
+use tokio::task::JoinSet;
+
+const MAX_CHUNK_SIZE: u64 = 1000; // max slots to fetch at a time
+const MAX_FOLLOW_SLOTS: u64 = 16 * MAX_CHUNK_SIZE; // max slots to take on at a time when following
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// What detection over a slot range needs, shared by one-off runs and `--follow`
+struct Detector {
+    loader: EventLoader,
+    inserter: Inserter,
+    group_config: GroupConfig,
+    detector_config: DetectorConfig,
+    cache: Option<EventCache>,
+}
+
+impl Detector {
+    /// Detects over `[start_slot, end_slot]`, which must be aligned to whole groups
+    async fn run(&self, start_slot: u64, end_slot: u64) {
+        // fetch events for up to 1k slots at a time and process in leader groups
+        let leaders = if self.group_config.by_leader {
+            self.loader.load_leaders(start_slot, end_slot).await
+        } else {
+            LeaderSchedule::default()
+        };
+        let bounds = self.group_config.bounds(start_slot, end_slot, &leaders);
+        let group_count = bounds.len();
+        let chunk_size = ((end_slot - start_slot + 1) / 16).clamp(1, MAX_CHUNK_SIZE);
+        println!("Processing slots {} to {} ({} leader groups)", start_slot, end_slot, group_count);
+        // split the groups into chunks of roughly chunk_size slots
+        let mut chunks: Vec<Vec<(u64, u64)>> = vec![];
+        for bound in bounds {
+            match chunks.last_mut() {
+                Some(chunk) if bound.1 - chunk[0].0 < chunk_size => chunk.push(bound),
+                _ => chunks.push(vec![bound]),
+            }
+        }
+        let progress = Arc::from(AtomicU64::new(0));
+        let mut set = JoinSet::new();
+        for chunk in chunks {
+            let chunk_start = chunk[0].0;
+            let chunk_end = chunk[chunk.len() - 1].1;
+            let loader = self.loader.clone();
+            let mut inserter = self.inserter.clone();
+            let progress = progress.clone();
+            let detector_config = self.detector_config.clone();
+            let cache = self.cache.clone();
+            set.spawn(async move {
+                let events = match cache.as_ref().and_then(|cache| cache.load(&chunk)) {
+                    Some(events) => {
+                        println!("Loaded cached events for slots {} to {}", chunk_start, chunk_end);
+                        events
+                    },
+                    None => {
+                        println!("Fetching events for slots {} to {}", chunk_start, chunk_end);
+                        let events = loader.load(chunk_start, chunk_end).await;
+                        if let Some(cache) = &cache {
+                            cache.store(&events, &chunk);
+                        }
+                        events
+                    },
+                };
+                for group in events.groups(chunk) {
+                    println!("Processing slots {} to {}", group.start_slot(), group.end_slot());
+                    let detections = detect_group(&group, &detector_config);
+                    if detections.rejections().total() > 0 {
+                        println!("Rejected candidates in slots {} to {}: {}", group.start_slot(), group.end_slot(), detections.rejections());
+                    }
+                    // for sandwich in detections.sandwiches().iter() {
+                    //     println!("Detected sandwich: {:#?}", sandwich);
+                    // }
+                    inserter.insert_sandwiches(*group.start_slot(), detections.sandwiches().clone()).await;
+                    inserter.insert_backruns(detections.backruns().clone()).await;
+                    inserter.insert_washes(detections.washes().clone()).await;
+                    inserter.insert_anomalies(detections.anomalies().clone()).await;
+                    inserter.insert_block_volumes(detections.block_volumes().clone()).await;
+
+                    let completed = progress.fetch_add(1, Ordering::AcqRel);
+                    // if completed % 100 == 0 {
+                        println!("{}/{}", completed, group_count);
+                    // }
+                }
+            });
+            if set.len() >= 16 {
+                set.join_next().await;
+            }
+        }
+        set.join_all().await;
+    }
+
+    /// Pulls `end_slot` back by whole groups so a leader's consecutive slots aren't split across two runs, unless
+    /// nothing would be left
+    async fn trim_to_leader(&self, start_slot: u64, end_slot: u64) -> u64 {
+        if !self.group_config.by_leader {
+            return end_slot;
+        }
+        let leaders = self.loader.load_leaders(start_slot, end_slot + 1).await;
+        let mut end_slot = end_slot;
+        while end_slot >= start_slot + self.group_config.size && leaders.leader(end_slot).is_some() && leaders.leader(end_slot) == leaders.leader(end_slot + 1) {
+            end_slot -= self.group_config.size;
+        }
+        end_slot
+    }
+
+    /// Keeps detecting over the slots the indexer has filled, `DETECTOR_FOLLOW_LAG_SLOTS` (150 by default) behind
+    /// its latest so blocks still being written are left alone. Progress is kept in `detector_cursor` under
+    /// `DETECTOR_CURSOR` ("detector" by default), where a restart resumes from unless `start_slot` is given.
+    /// Returns once shutdown is triggered, after the range being processed is done.
+    async fn follow(&self, start_slot: Option<u64>, shutdown: Shutdown) {
+        let name = env::var("DETECTOR_CURSOR").ok().filter(|n| !n.is_empty()).unwrap_or_else(|| "detector".to_string());
+        let lag: u64 = env::var("DETECTOR_FOLLOW_LAG_SLOTS").ok().and_then(|v| v.parse().ok()).unwrap_or(150);
+        let filled_slot = || self.loader.filled_slot().unwrap_or(0).saturating_sub(lag);
+        let next_slot = start_slot.or_else(|| self.loader.load_cursor(&name).map(|slot| slot + 1)).unwrap_or_else(filled_slot);
+        let mut next_slot = self.group_config.align(next_slot, next_slot).0;
+        println!("Following the indexer from slot {} as {}", next_slot, name);
+        while !shutdown.is_triggered() {
+            let Some((start_slot, end_slot)) = self.group_config.follow_range(next_slot, filled_slot(), MAX_FOLLOW_SLOTS) else {
+                if shutdown.unless_triggered(tokio::time::sleep(FOLLOW_POLL_INTERVAL)).await.is_none() {
+                    break;
+                }
+                continue;
+            };
+            let end_slot = self.trim_to_leader(start_slot, end_slot).await;
+            self.run(start_slot, end_slot).await;
+            self.loader.save_cursor(&name, end_slot);
+            next_slot = end_slot + 1;
+        }
+        println!("Stopped following at slot {}", next_slot);
+    }
+}
+
+/// What `detect` runs over
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DetectMode {
+    /// `[start_slot, end_slot]`, widened to whole groups
+    Range(u64, u64),
+    /// Whatever the indexer fills from then on, from the given slot or where the cursor left off
+    Follow(Option<u64>),
+}
+
+/// Offline detection over indexed events, for the live one see [`super::realtime`]
+pub async fn run(ctx: Context, mode: DetectMode) {
+    let pool = ctx.pool.expect("the detector needs MYSQL");
+    let detector = Detector {
+        loader: EventLoader::new(pool.clone()),
+        inserter: Inserter::new(pool.clone()),
+        group_config: GroupConfig::from_env(),
+        detector_config: DetectorConfig::from_env(),
+        cache: EventCache::from_env(),
+    };
+    match mode {
+        DetectMode::Range(start_slot, end_slot) => {
+            // alignment
+            let (start_slot, end_slot) = detector.group_config.align(start_slot, end_slot);
+            detector.run(start_slot, end_slot).await;
+        },
+        DetectMode::Follow(start_slot) => detector.follow(start_slot, ctx.shutdown).await,
+    }
+}
@@ -0,0 +1,120 @@
+use std::{env, net::SocketAddr, sync::{Arc, Mutex}};
+
+use crate::{api::feed::{self, SlotEvents}, canary::FinderCanary, commands::Context, config, decode_failures::DecodeFailureLog, events::{common::Inserter, event::{start_event_processor, FINDERS}}, partition::{start_partition_maintenance, PartitionConfig}, shutdown::{load_checkpoint, save_checkpoint, Shutdown, SlotTracker}, sinks::{broadcast::BroadcastSink, db::{DbSink, EVENT_CHUNK_SIZE}, Sinks}, source::{grpc::{GrpcSource, Subscription}, rpc::RpcSource}, wal::{open_from_env, replay_events, EventBatch}};
+use tokio::sync::broadcast;
+
+const CHECKPOINT_STREAM: &str = "indexer";
+
+/// Returns once shutdown is triggered
+async fn indexer_loop(sinks: &Sinks, shutdown: Shutdown, tracker: Arc<Mutex<SlotTracker>>, canary: Arc<Mutex<FinderCanary>>, failure_log: Option<Arc<DecodeFailureLog>>, mut from_slot: Option<u64>) {
+    loop {
+        let received = indexer(sinks, &shutdown, &canary, &failure_log, from_slot).await;
+        if shutdown.is_triggered() {
+            return;
+        }
+        // picks up where the stream dropped, unless nothing came through, e.g. the slot is past the provider's retention
+        from_slot = if received {
+            tracker.lock().unwrap().checkpoint().map(|slot| slot + 1)
+        } else {
+            None
+        };
+        // reconnect in 5secs
+        if shutdown.unless_triggered(tokio::time::sleep(std::time::Duration::from_secs(5))).await.is_none() {
+            return;
+        }
+    }
+}
+
+/// Whether any block was received before the stream ended
+async fn indexer(sinks: &Sinks, shutdown: &Shutdown, canary: &Arc<Mutex<FinderCanary>>, failure_log: &Option<Arc<DecodeFailureLog>>, from_slot: Option<u64>) -> bool {
+    let config = config::get();
+    let rpc_url = config.rpc_url().clone().expect("RPC_URL is not set");
+    let mut receiver = match config.grpc_url() {
+        Some(grpc_url) => {
+            let mut subscription = Subscription::default().blocks().lookup_tables().from_slot(from_slot);
+            // standalone transfers don't touch the finders' programs but link multi-wallet sandwiches, so it's opt-in
+            if env::var("GRPC_FILTER_BLOCKS").is_ok_and(|v| v == "1" || v == "true") {
+                println!("subscribing to the transactions touching finder programs only");
+                subscription = subscription.account_include(&FINDERS.programs());
+            }
+            match GrpcSource::connect(grpc_url, &subscription).await {
+                Ok(source) => start_event_processor(source, rpc_url, shutdown.clone(), Some(canary.clone()), failure_log.clone()),
+                Err(e) => {
+                    eprintln!("Failed to subscribe: {}", e);
+                    return false;
+                }
+            }
+        },
+        None => {
+            println!("GRPC_URL is not set, polling blocks over rpc");
+            start_event_processor(RpcSource::new(rpc_url.clone(), from_slot, None), rpc_url, shutdown.clone(), Some(canary.clone()), failure_log.clone())
+        },
+    };
+    println!("Started event processor");
+    let mut received = false;
+    // the channel also closes on shutdown, after the blocks already taken
+    while let Some((slot, event)) = receiver.recv().await {
+        received = true;
+        println!("Received batch: {:?}", event.len());
+        sinks.send_events(slot, event);
+    }
+    println!("Event processor disconnected");
+    received
+}
+
+/// Exposes the swap feed over websocket when `FEED_PORT` is set
+async fn start_feed_server(feed_sender: broadcast::Sender<SlotEvents>) {
+    let Ok(feed_port) = env::var("FEED_PORT") else {
+        return;
+    };
+    let listener = tokio::net::TcpListener::bind(format!("127.0.0.1:{feed_port}"))
+        .await
+        .unwrap();
+    axum::serve(
+        listener,
+        feed::router(feed_sender).into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
+}
+
+/// Indexes blocks from `GRPC_URL`, or polls them from `RPC_URL` without one, into the sinks, resuming from the last
+/// checkpoint. Returns once shutdown is triggered and everything taken off the stream is written.
+pub async fn run(ctx: Context) {
+    let Context { pool, shutdown } = ctx;
+    let (feed_sender, _) = broadcast::channel::<SlotEvents>(16);
+    let tracker = Arc::new(Mutex::new(SlotTracker::default()));
+    let mut sinks = Sinks::from_env();
+    if sinks.enabled("broadcast") {
+        sinks.add("broadcast", BroadcastSink::new(feed_sender.clone()));
+    }
+    match &pool {
+        Some(pool) => {
+            start_partition_maintenance(pool.clone(), PartitionConfig::from_env(), std::time::Duration::from_secs(3600));
+            if sinks.enabled("db") {
+                // whatever didn't make it into the db before the last shutdown goes in before anything new
+                let wal = match open_from_env::<EventBatch>("events") {
+                    Some((wal, pending)) => {
+                        replay_events(&mut Inserter::new(pool.clone()), &wal, pending, EVENT_CHUNK_SIZE).await;
+                        Some(wal)
+                    },
+                    None => None,
+                };
+                sinks.add("db", DbSink::new(Inserter::new(pool.clone())).with_events_wal(wal).with_tracker(tracker.clone()));
+            }
+        },
+        None => println!("No db configured, streaming only"),
+    }
+    tokio::spawn(start_feed_server(feed_sender));
+    let from_slot = pool.as_ref().and_then(|pool| load_checkpoint(pool, CHECKPOINT_STREAM)).map(|slot| slot + 1);
+    // kept across reconnects so a window isn't cut short by one
+    let canary = Arc::new(Mutex::new(FinderCanary::from_env()));
+    let failure_log = pool.as_ref().and_then(|pool| DecodeFailureLog::from_env(pool.clone())).map(Arc::new);
+    indexer_loop(&sinks, shutdown, tracker.clone(), canary, failure_log, from_slot).await;
+    // everything taken off the stream is written before the checkpoint
+    sinks.close().await;
+    let checkpoint = tracker.lock().unwrap().checkpoint();
+    if let (Some(pool), Some(slot)) = (pool, checkpoint) {
+        save_checkpoint(&pool, CHECKPOINT_STREAM, slot);
+    }
+}
@@ -0,0 +1,49 @@
+//! The services behind the `sandwich-finder` binary's subcommands, each also kept as a binary of its own.
+//!
+//! Every command takes a [`Context`], set up once per process, so `all-in-one` runs several of them on the same db
+//! pool and shutdown signal.
+
+use std::time::Duration;
+
+use mysql::Pool;
+
+use crate::{metrics, shutdown::Shutdown, utils::try_create_db_pool};
+
+pub mod backfill;
+pub mod detect;
+pub mod ingest;
+pub mod profits;
+pub mod realtime;
+pub mod serve;
+
+/// What every command shares
+#[derive(Clone)]
+pub struct Context {
+    /// `None` in stream-only mode, see [`try_create_db_pool`]
+    pub pool: Option<Pool>,
+    pub shutdown: Shutdown,
+}
+
+impl Context {
+    /// Loads `.env`, starts the metrics reporter and installs the shutdown handler, so it must only be called once
+    /// and from within a tokio runtime
+    pub fn init() -> Self {
+        dotenv::dotenv().ok();
+        metrics::start_reporter(Duration::from_secs(60));
+        Self {
+            pool: try_create_db_pool(),
+            shutdown: Shutdown::install(),
+        }
+    }
+}
+
+/// `ingest`, `detect --realtime` and `serve` in one process. Returns once ingestion and serving have drained after
+/// shutdown, the realtime detector has nothing in flight worth waiting for.
+pub async fn all_in_one(ctx: Context) {
+    if ctx.pool.is_some() {
+        tokio::spawn(realtime::run(ctx.clone()));
+    } else {
+        println!("No db configured, running without the detector");
+    }
+    tokio::join!(ingest::run(ctx.clone()), serve::run(ctx));
+}
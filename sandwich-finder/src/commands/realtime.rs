@@ -0,0 +1,212 @@
+use std::{collections::HashSet, sync::{Arc, Mutex}};
+
+use crate::{commands::Context, config, detector::{detect_group, DetectorConfig, EventLoader, GroupConfig, GroupDetections, LeaderSchedule, SLOTS_PER_HOUR}, drift::{start_drift_monitor, DriftConfig}, events::{common::Inserter, snipe::{detect_snipes, first_swaps, SnipeConfig}}, finality::{write_at_finalized, FinalityBuffer}, fingerprint::{start_fingerprinting, FingerprintConfig}, metrics, rpc::BoundedRpc, shadow::{ShadowConfig, ShadowDiff}, sinks::{db::DbSink, dedup::load_recent, Sinks}, source::{grpc::{GrpcSource, Subscription}, BlockSource as _, BlockUpdate}, wal::{open_from_env, replay_sandwiches, SandwichBatch}};
+use solana_sdk::commitment_config::CommitmentConfig;
+use yellowstone_grpc_proto::geyser::CommitmentLevel;
+
+/// Detections waiting for finalization as (first slot of the group, detections), keyed by the group's last slot
+type PendingDetections = Arc<Mutex<FinalityBuffer<(u64, GroupDetections)>>>;
+
+/// What's run over each group
+#[derive(Clone)]
+struct Detectors {
+    detector: DetectorConfig,
+    /// Run alongside `detector` without touching its output
+    shadow: Option<ShadowConfig>,
+    snipe: SnipeConfig,
+}
+
+/// Detects over each group as soon as the indexer is done with it, following the chain tip over `GRPC_URL`
+pub async fn run(ctx: Context) {
+    let pool = ctx.pool.expect("the detector needs MYSQL");
+    start_fingerprinting(pool.clone(), FingerprintConfig::from_env());
+    let group_config = GroupConfig::from_env();
+    start_drift_monitor(pool.clone(), group_config, DetectorConfig::from_env(), DriftConfig::from_env());
+    let loader = EventLoader::new(pool.clone());
+    let mut inserter = Inserter::new(pool.clone());
+    // sandwiches detected before the last shutdown but never written
+    let wal = match open_from_env::<SandwichBatch>("sandwiches") {
+        Some((wal, pending)) => {
+            replay_sandwiches(&mut inserter, &wal, pending).await;
+            Some(wal)
+        },
+        None => None,
+    };
+    // sandwiches go through the sinks, the rest of the detections straight to the db
+    let mut sinks = Sinks::from_env();
+    if sinks.enabled("db") {
+        sinks.add("db", DbSink::new(inserter.clone()).with_sandwiches_wal(wal));
+    }
+    // groups replayed after a restart would announce their sandwiches again
+    if let Some(slots) = sinks.dedup_slots() {
+        sinks.seed_dedup(load_recent(&mut pool.get_conn().unwrap(), slots));
+    }
+    let sinks = Arc::new(sinks);
+    let detectors = Detectors {
+        detector: DetectorConfig::from_env(),
+        shadow: ShadowConfig::from_env(),
+        snipe: SnipeConfig::from_env(),
+    };
+    if let Some(shadow) = &detectors.shadow {
+        println!("running shadow detector {}", shadow.label);
+    }
+    // kept across reconnects so nothing detected before a disconnect is lost
+    let pending = write_at_finalized().then(PendingDetections::default);
+    if pending.is_some() {
+        println!("writing detections once finalized");
+    }
+    // backfills the finalizations missed while disconnected
+    let rpc = pending.as_ref().and(config::get().rpc_url().clone()).map(|url| BoundedRpc::from_env(&url, CommitmentConfig::finalized()));
+
+    loop {
+        detect_realtime(&loader, &inserter, group_config, &detectors, pending.as_ref(), rpc.as_ref(), &sinks).await;
+        // reconnect in 5secs
+        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+    }
+}
+
+/// Marks the slots finalized between the last finalization seen and `slot`, which were missed while disconnected.
+/// Only the slots pending detections can still be in are looked up, with `getBlocks` over `rpc`. Without an rpc, or if
+/// the lookup fails, the whole gap is taken as finalized, keeping the odd sandwich on an abandoned fork over dropping
+/// every one found in the gap.
+async fn backfill_finalized(pending: &PendingDetections, rpc: Option<&BoundedRpc>, slot: u64) {
+    let (last_finalized, oldest_pending) = {
+        let pending = pending.lock().unwrap();
+        (pending.last_finalized(), pending.oldest_pending())
+    };
+    let (Some(last_finalized), Some(oldest_pending)) = (last_finalized, oldest_pending) else {
+        return;
+    };
+    let start_slot = (last_finalized + 1).max(oldest_pending.saturating_sub(SLOTS_PER_HOUR));
+    if start_slot >= slot {
+        return;
+    }
+    let end_slot = slot - 1;
+    let finalized = match rpc.map(|rpc| rpc.get_blocks(start_slot, end_slot)) {
+        Some(blocks) => blocks.await.map_err(|e| e.to_string()),
+        None => Err("no rpc".to_string()),
+    };
+    let finalized = finalized.unwrap_or_else(|e| {
+        eprintln!("Failed to look up the slots finalized in {} - {}, taking them all as finalized: {}", start_slot, end_slot, e);
+        (start_slot..=end_slot).collect()
+    });
+    println!("backfilled {} finalized slots in {} - {}", finalized.len(), start_slot, end_slot);
+    pending.lock().unwrap().mark_finalized(finalized);
+}
+
+async fn detect_realtime(loader: &EventLoader, inserter: &Inserter, group_config: GroupConfig, detectors: &Detectors, pending: Option<&PendingDetections>, rpc: Option<&BoundedRpc>, sinks: &Arc<Sinks>) {
+    let grpc_url = config::get().grpc_url().as_ref().expect("GRPC_URL is not set");
+    let mut subscription = Subscription::default().blocks_meta();
+    if pending.is_some() {
+        // every status update, finalizations are picked out below
+        subscription = subscription.slot_status();
+    }
+    let mut source = match GrpcSource::connect(grpc_url, &subscription).await {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("Failed to subscribe: {}", e);
+            return;
+        }
+    };
+
+    let mut leaders = LeaderSchedule::default();
+    // the first finalization since connecting may follow a gap
+    let mut reconnected = true;
+    while let Some(update) = source.next_update().await {
+        match update {
+            BlockUpdate::BlockMeta(meta) => {
+                // println!("{:?}", meta);
+                let slot = meta.slot;
+                // Intentionally lag behind slightly to ensure all events are inserted
+                let lagged_slot = slot - group_config.size;
+                if group_config.by_leader && leaders.leader(lagged_slot + 1).is_none() && group_config.is_group_end(slot) {
+                    // an hour either way, reloaded once we run past it or the schedule gets populated
+                    leaders = loader.load_leaders(lagged_slot.saturating_sub(SLOTS_PER_HOUR), lagged_slot + SLOTS_PER_HOUR).await;
+                }
+                if let Some((start_slot, end_slot)) = group_config.group_ending_at(lagged_slot, &leaders) {
+                    let loader = loader.clone();
+                    let mut inserter = inserter.clone();
+                    let detectors = detectors.clone();
+                    let pending = pending.cloned();
+                    let sinks = sinks.clone();
+                    tokio::spawn(async move {
+                        println!("Processing slots {} - {}", start_slot, end_slot);
+                        let events = loader.load(start_slot, end_slot).await;
+                        let Some(group) = events.groups(vec![(start_slot, end_slot)]).next() else {
+                            return;
+                        };
+                        let detections = detect_group(&group, &detectors.detector);
+                        println!("Found {} sandwiches in slots {} - {}", detections.sandwiches().len(), start_slot, end_slot);
+                        if detections.rejections().total() > 0 {
+                            println!("Rejected candidates in slots {} - {}: {}", start_slot, end_slot, detections.rejections());
+                        }
+                        detections.rejections().export_metrics();
+                        metrics::add("swaps_incomplete_skipped", *detections.incomplete() as u64);
+                        metrics::add("swaps_below_min_notional", *detections.below_min_notional() as u64);
+                        // written as soon as it's found, it's only there to be compared against
+                        if let Some(shadow) = &detectors.shadow {
+                            let shadow_detections = detect_group(&group, &shadow.detector);
+                            let diff = ShadowDiff::new(detections.sandwiches(), shadow_detections.sandwiches());
+                            diff.export_metrics();
+                            if !diff.is_empty() {
+                                println!("Shadow {} differs in slots {} - {}: {}", shadow.label, start_slot, end_slot, diff);
+                            }
+                            inserter.insert_shadow_sandwiches(&shadow.label, shadow_detections.sandwiches()).await;
+                        }
+                        if let Some(pending) = pending {
+                            pending.lock().unwrap().insert(end_slot, (start_slot, detections));
+                        } else {
+                            sinks.send_sandwiches(start_slot, detections.sandwiches().clone());
+                            inserter.insert_backruns(detections.backruns().clone()).await;
+                            inserter.insert_washes(detections.washes().clone()).await;
+                            inserter.insert_anomalies(detections.anomalies().clone()).await;
+                            inserter.insert_block_volumes(detections.block_volumes().clone()).await;
+                        }
+                        let creations = inserter.pool_creations(start_slot, end_slot).await;
+                        let created_amms: HashSet<_> = creations.iter().map(|(amm, _)| amm.clone()).collect();
+                        for (amm, created) in inserter.register_pools(&first_swaps(group.swaps(), &creations), &created_amms, detectors.snipe.warmup_slots).await {
+                            let window = loader.load(*created.slot(), created.slot() + detectors.snipe.window_slots - 1).await;
+                            let snipes = detect_snipes(&amm, &created, window.swaps(), &detectors.snipe);
+                            if !snipes.is_empty() {
+                                println!("Found {} snipes on new pool {}", snipes.len(), amm);
+                            }
+                            inserter.insert_snipes(&snipes).await;
+                        }
+                    });
+                }
+            },
+            BlockUpdate::SlotStatus(update) if update.status == CommitmentLevel::Finalized as i32 => {
+                let Some(pending) = pending else {
+                    continue;
+                };
+                if std::mem::take(&mut reconnected) {
+                    backfill_finalized(pending, rpc, update.slot).await;
+                }
+                let released: Vec<_> = {
+                    let mut pending = pending.lock().unwrap();
+                    let released = pending.finalize(update.slot);
+                    released.into_iter().map(|(_, (start_slot, detections))| {
+                        let finalized = detections.retain_slots(|slot| pending.is_finalized(slot));
+                        metrics::add("orphaned_sandwiches", (detections.sandwiches().len() - finalized.sandwiches().len()) as u64);
+                        (start_slot, finalized)
+                    }).collect()
+                };
+                if released.is_empty() {
+                    continue;
+                }
+                let mut inserter = inserter.clone();
+                let sinks = sinks.clone();
+                tokio::spawn(async move {
+                    for (start_slot, detections) in released {
+                        sinks.send_sandwiches(start_slot, detections.sandwiches().clone());
+                        inserter.insert_backruns(detections.backruns().clone()).await;
+                        inserter.insert_washes(detections.washes().clone()).await;
+                        inserter.insert_anomalies(detections.anomalies().clone()).await;
+                        inserter.insert_block_volumes(detections.block_volumes().clone()).await;
+                    }
+                });
+            },
+            _ => {},
+        }
+    }
+}
\ No newline at end of file
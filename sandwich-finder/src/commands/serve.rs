@@ -0,0 +1,594 @@
+use crate::{api, commands::Context, config, detector::SLOTS_PER_HOUR, ui, events::legacy::{SandwichFormat, SandwichMessage}, lut_cache::LutCache, metrics, redact::{Redact as _, Redaction}, replica::ReadPool, rpc::BoundedRpc, shutdown::{load_checkpoint, save_checkpoint, Shutdown}, source::{grpc::{GrpcSource, Subscription}, BlockSource as _, BlockUpdate}, utils::{block_stats, decompile, find_sandwiches, DbMessage, DecompiledTransaction, Sandwich, Swap, SwapType}};
+use std::{collections::{BTreeMap, HashMap, HashSet, VecDeque}, net::SocketAddr, sync::{Arc, RwLock}, time::Duration, vec};
+use axum::{extract::{ws::{Message, WebSocket}, Path, Query, State, WebSocketUpgrade}, response::IntoResponse, routing::get, Json, Router};
+use mysql::{prelude::Queryable, Pool, PooledConn, TxOpts, Value};
+use serde::Deserialize;
+
+use tokio::sync::{broadcast, mpsc};
+
+const MAX_HISTORY_LIMIT: usize = 1000;
+/// Window of `dont_front_sandwiches_24h`
+const DONT_FRONT_WINDOW_SLOTS: u64 = 24 * SLOTS_PER_HOUR;
+/// How often `dont_front_sandwiches_24h` is brought up to the chain tip when no sandwiches come in
+const DONT_FRONT_REFRESH: Duration = Duration::from_secs(60);
+const CHECKPOINT_STREAM: &str = "sandwich-finder";
+/// Sandwiches waiting to be broadcast, the finder drops them rather than wait once it's full
+const BROADCAST_QUEUE_SIZE: usize = 1024;
+
+/// The latest sandwiches, oldest first. Readers take a snapshot that later sandwiches don't touch,
+/// so serving `/history` never holds up the broadcast and vice versa.
+struct History {
+    sandwiches: RwLock<Arc<VecDeque<Sandwich>>>,
+    size: usize,
+}
+
+impl History {
+    fn new(size: usize) -> Self {
+        Self {
+            sandwiches: RwLock::default(),
+            size,
+        }
+    }
+
+    fn snapshot(&self) -> Arc<VecDeque<Sandwich>> {
+        self.sandwiches.read().unwrap().clone()
+    }
+
+    /// Copies the buffer if a snapshot of it is still around, appends in place otherwise
+    fn push(&self, sandwich: Sandwich) {
+        let mut sandwiches = self.sandwiches.write().unwrap();
+        let sandwiches = Arc::make_mut(&mut sandwiches);
+        if sandwiches.len() >= self.size {
+            sandwiches.pop_front();
+        }
+        sandwiches.push_back(sandwich);
+    }
+}
+
+#[derive(Clone)]
+struct AppState {
+    message_history: Arc<History>,
+    sender: broadcast::Sender<Sandwich>,
+    /// `None` in stream-only mode
+    pool: Option<ReadPool>,
+    redaction: Redaction,
+}
+
+/// Returns the last slot processed once shutdown is triggered
+async fn sandwich_finder(sender: mpsc::Sender<Sandwich>, db_sender: mpsc::Sender<DbMessage>, shutdown: Shutdown, mut from_slot: Option<u64>) -> Option<u64> {
+    let mut last_slot = None;
+    loop {
+        let processed = sandwich_finder_loop(sender.clone(), db_sender.clone(), &shutdown, from_slot).await;
+        last_slot = processed.or(last_slot);
+        if shutdown.is_triggered() {
+            return last_slot;
+        }
+        // picks up where the stream dropped, unless nothing came through, e.g. the slot is past the provider's retention
+        from_slot = processed.map(|slot| slot + 1);
+        // reconnect in 5secs
+        if shutdown.unless_triggered(tokio::time::sleep(std::time::Duration::from_secs(5))).await.is_none() {
+            return last_slot;
+        }
+    }
+}
+
+/// The last slot processed before the stream ended, blocks are processed one at a time so every slot before it was too
+async fn sandwich_finder_loop(sender: mpsc::Sender<Sandwich>, db_sender: mpsc::Sender<DbMessage>, shutdown: &Shutdown, from_slot: Option<u64>) -> Option<u64> {
+    let config = config::get();
+    let rpc_url = config.rpc_url().as_ref().expect("RPC_URL is not set");
+    let grpc_url = config.grpc_url().as_ref().expect("GRPC_URL is not set");
+    let rpc_client = BoundedRpc::from_env(rpc_url, config.commitment_config());
+    let lut_cache = LutCache::default();
+    let subscription = Subscription::default().blocks().lookup_tables().from_slot(from_slot);
+    let mut source = match GrpcSource::connect(grpc_url, &subscription).await {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("Failed to subscribe: {}", e);
+            return None;
+        }
+    };
+    println!("subscription request sent!");
+    let mut last_slot = None;
+    while let Some(update) = shutdown.unless_triggered(source.next_update()).await.flatten() {
+        match update {
+            BlockUpdate::Block(block) => {
+                // println!("new block {}, {} txs", block.slot, block.transactions.len());
+                let now = std::time::Instant::now();
+                let ts = block.block_time.unwrap().timestamp;
+                let slot = block.slot;
+                metrics::set("chain_tip_slot", slot);
+                lut_cache.evict_deactivated(slot);
+                let mut bundle_count = 0;
+                db_sender.send(block_stats(&block)).await.unwrap();
+                let futs = block.transactions.iter().filter_map(|tx| {
+                    if tx.is_vote {
+                        None
+                    } else {
+                        Some(decompile(tx, &rpc_client, &lut_cache))
+                    }
+                }).collect::<Vec<_>>();
+                let joined_futs = futures::future::join_all(futs).await;
+                let mut block_txs = joined_futs.iter().filter_map(|tx| {
+                    if let Some(tx) = tx {
+                        Some(tx)
+                    } else {
+                        None
+                    }
+                }).collect::<Vec<&DecompiledTransaction>>();
+                let swap_count = block_txs.iter().map(|tx| tx.swaps().len()).sum::<usize>();
+                block_txs.sort_by_key(|x| x.order());
+                // criteria for sandwiches:
+                // 1. has 3 txs of strictly increasing inclusion order (frontrun-victim-backrun)
+                // 2. the 1st and 2nd are in the same direction, the 3rd is in reverse
+                // 3. output of 3rd tx >= input of 1st tx && output of 1st tx >= input of 3rd tx (profitability constraint)
+                // 4. all 3 txs use the same amm
+                // 5. 2nd tx's swapper is different from the 1st and 3rd
+                // 6. a wrapper program is present in the 1st and 3rd txs and are the same
+
+                // group swaps by amm
+                let mut amm_swaps: HashMap<&String, Vec<&Swap>> = HashMap::new();
+                block_txs.iter().for_each(|tx| {
+                    tx.swaps().iter().for_each(|swap| {
+                        let swaps = amm_swaps.entry(swap.amm()).or_default();
+                        swaps.push(swap);
+                    });
+                });
+
+                // check #4
+                amm_swaps.iter().for_each(|(_amm, swaps)| {
+                    if swaps.len() < 3 {
+                        return;
+                    }
+                    // within the group, further group by direction (input token)
+                    let mut input_swaps: HashMap<&String, Vec<&Swap>> = HashMap::new();
+                    swaps.iter().for_each(|swap| {
+                        let input_swaps = input_swaps.entry(swap.input_mint()).or_default();
+                        input_swaps.push(swap);
+                    });
+                    // bail out if there's not exactly 2 directions
+                    if input_swaps.len() != 2 {
+                        return;
+                    }
+                    let mut iter = input_swaps.iter();
+                    let dir0 = iter.next().unwrap();
+                    let dir1 = iter.next().unwrap();
+                    // look for 0-0-1 sandwiches (check #2)
+                    find_sandwiches(dir0.1, dir1.1, slot, ts).into_iter().for_each(|sandwich| {
+                        publish(&sender, &db_sender, sandwich);
+                        bundle_count += 1;
+                    });
+                    // look for 1-1-0 sandwiches (check #2)
+                    find_sandwiches(dir1.1, dir0.1, slot, ts).into_iter().for_each(|sandwich| {
+                        publish(&sender, &db_sender, sandwich);
+                        bundle_count += 1;
+                    });
+                });
+                if bundle_count >= 1 {
+                    println!("block {} processed in {}us, {} swaps found, {} bundles found", block.slot, now.elapsed().as_micros(), swap_count, bundle_count);
+                }
+                last_slot = Some(slot);
+            }
+            BlockUpdate::LookupTable(account) => {
+                lut_cache.apply_update(account);
+            }
+            _ => {}
+        }
+    }
+    last_slot
+}
+
+/// Hands a sandwich to the broadcaster and the db writer without waiting on either
+fn publish(sender: &mpsc::Sender<Sandwich>, db_sender: &mpsc::Sender<DbMessage>, sandwich: Sandwich) {
+    if sender.try_send(sandwich.clone()).is_err() {
+        metrics::incr("broadcast_dropped");
+    }
+    let db_sender = db_sender.clone();
+    tokio::spawn(async move {
+        db_sender.send(DbMessage::Sandwich(sandwich)).await.unwrap();
+    });
+}
+
+/// Keeps the history and fans the sandwiches out to the websocket clients, until the finder stops.
+/// Also keeps `dont_front_sandwiches_24h` up to date with the chain tip, whether new ones come in or not.
+async fn broadcast_sandwiches(mut receiver: mpsc::Receiver<Sandwich>, message_history: Arc<History>, sender: broadcast::Sender<Sandwich>, mut dont_front: DontFrontWindow) {
+    let mut interval = tokio::time::interval(DONT_FRONT_REFRESH);
+    loop {
+        tokio::select! {
+            message = receiver.recv() => {
+                let Some(message) = message else {
+                    break;
+                };
+                if message.has_dont_front_victim() {
+                    dont_front.push(*message.slot());
+                }
+                metrics::set("dont_front_sandwiches_24h", dont_front.count(metrics::get("chain_tip_slot").max(*message.slot())));
+                message_history.push(message.clone());
+                let _ = sender.send(message);
+            }
+            _ = interval.tick() => {
+                metrics::set("dont_front_sandwiches_24h", dont_front.count(metrics::get("chain_tip_slot")));
+            }
+        }
+    }
+}
+
+/// Recent sandwiches with a dont_front victim by slot, for a count over the last `DONT_FRONT_WINDOW_SLOTS`
+#[derive(Default)]
+struct DontFrontWindow {
+    slots: BTreeMap<u64, u64>,
+}
+
+impl DontFrontWindow {
+    /// Seeded with the ones already in the db, so a restart doesn't start the count over
+    fn load(conn: &mut PooledConn) -> mysql::Result<Self> {
+        let mut window = Self::default();
+        let Some(tip) = conn.query_first::<Option<u64>, _>("SELECT max(slot) FROM `sandwich_view`")?.flatten() else {
+            return Ok(window);
+        };
+        let slots: Vec<u64> = conn.exec("SELECT min(slot) FROM `sandwich_view` where slot > ? and swap_type = 'VICTIM' and dont_front = 1 group by sandwich_id", (tip.saturating_sub(DONT_FRONT_WINDOW_SLOTS),))?;
+        slots.into_iter().for_each(|slot| window.push(slot));
+        Ok(window)
+    }
+
+    fn push(&mut self, slot: u64) {
+        *self.slots.entry(slot).or_default() += 1;
+    }
+
+    /// Drops the sandwiches that fell out of the window ending at `tip` and returns how many are left
+    fn count(&mut self, tip: u64) -> u64 {
+        self.slots = self.slots.split_off(&(tip.saturating_sub(DONT_FRONT_WINDOW_SLOTS) + 1));
+        self.slots.values().sum()
+    }
+}
+
+const INSERT_BLOCK: &str = "insert into block (slot, timestamp, tx_count, vote_count, reward_lamports, successful_cu, total_cu) values (?, ?, ?, ?, ?, ?, ?)";
+const INSERT_TX: &str = "insert into transaction (tx_hash, signer, slot, order_in_block, dont_front) values (?, ?, ?, ?, ?)";
+const INSERT_SWAP: &str = "insert into swap (sandwich_id, outer_program, inner_program, amm, subject, input_mint, output_mint, input_amount, output_amount, tx_id, swap_type) values (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)";
+
+async fn store_to_db(pool: Option<Pool>, mut receiver: mpsc::Receiver<DbMessage>) {
+    let Some(pool) = pool else {
+        // stream-only, nothing to store but the finder still needs its messages taken
+        while receiver.recv().await.is_some() {}
+        return;
+    };
+    let mut tx_db_id_cache: HashMap<String, u64> = HashMap::new();
+    while let Some(msg) = receiver.recv().await {
+        // checked out per message so a connection that died in between is replaced, the statements come from its cache
+        let mut conn = match pool.get_conn() {
+            Ok(conn) => conn,
+            Err(e) => {
+                eprintln!("Failed to connect to the db, dropping a message: {}", e);
+                continue;
+            }
+        };
+        match msg {
+            DbMessage::Block(block) => {
+                conn.exec_drop(INSERT_BLOCK, (block.slot(), block.ts(), block.tx_count(), block.vote_count(), block.reward_lamports(), block.successful_cu(), block.total_cu())).unwrap();
+            }
+            DbMessage::Sandwich(sandwich) => {
+                let mut dbtx = conn.start_transaction(TxOpts::default()).unwrap();
+                // obtain an id for this sandwich
+                dbtx.query_drop("insert into sandwich values ()").unwrap();
+                let sandwich_id = dbtx.last_insert_id();
+                let mut swaps = Vec::new();
+                swaps.push((sandwich.frontrun(), SwapType::Frontrun));
+                swaps.extend(sandwich.victim().iter().map(|x| (x, SwapType::Victim)));
+                swaps.push((sandwich.backrun(), SwapType::Backrun));
+                // figure out which txs are new to the db
+                let args: Vec<_> = swaps.iter().filter_map(|swap| {
+                    if tx_db_id_cache.contains_key(swap.0.sig()) {
+                        None
+                    } else {
+                        Some((swap.0.sig(), swap.0.signer(), sandwich.slot(), swap.0.order(), swap.0.dont_front()))
+                    }
+                }).collect();
+                if !args.is_empty() {
+                    dbtx.exec_batch(INSERT_TX, &args).unwrap();
+                    // populate the cache with a select
+                    let tx_hashes = args.iter().map(|(tx_hash, _, _, _, _)| tx_hash).collect::<Vec<_>>();
+                    let q_marks = tx_hashes.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+                    let _ = dbtx.exec_map(format!("select id, tx_hash from transaction where tx_hash in ({q_marks})"), tx_hashes, |(id, tx_hash)| {
+                        tx_db_id_cache.insert(tx_hash, id);
+                    }).unwrap();
+                }
+                // insert the swaps in this sandwich into the db
+                dbtx.exec_batch(INSERT_SWAP, swaps.iter().map(|swap| {
+                    let tx_id = tx_db_id_cache.get(swap.0.sig()).unwrap();
+                    (sandwich_id, swap.0.outer_program().as_deref(), swap.0.program().as_str(), swap.0.amm().as_str(), swap.0.subject().as_str(), swap.0.input_mint().as_str(), swap.0.output_mint().as_str(), swap.0.input_amount(), swap.0.output_amount(), tx_id, swap.1.clone())
+                })).unwrap();
+                dbtx.commit().unwrap();
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct FormatQuery {
+    #[serde(default)]
+    format: SandwichFormat,
+    /// Only the sandwiches with a victim that opted out of being frontrun
+    #[serde(default)]
+    dont_front: bool,
+}
+
+async fn handle_websocket(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Query(query): Query<FormatQuery>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| handle_socket(socket, state, query.format, query.dont_front))
+}
+
+async fn handle_socket(
+    mut socket: WebSocket,
+    state: AppState,
+    format: SandwichFormat,
+    dont_front: bool,
+) {
+    let mut receiver = state.sender.subscribe();
+    while let Ok(mut msg) = receiver.recv().await {
+        if dont_front && !msg.has_dont_front_victim() {
+            continue;
+        }
+        msg.redact(&state.redaction, false);
+        let msg = SandwichMessage::new(msg, format);
+        if socket.send(Message::Text(serde_json::to_string(&msg).unwrap().into())).await.is_err() {
+            break; // Client disconnected
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct HistoryQuery {
+    limit: Option<usize>,
+    before_slot: Option<u64>,
+    amm: Option<String>,
+    #[serde(default)]
+    format: SandwichFormat,
+    /// Only the sandwiches with a victim that opted out of being frontrun
+    #[serde(default)]
+    dont_front: bool,
+}
+
+/// Latest sandwiches, oldest first. Served from memory, topped up from the db when the buffer has fewer than asked for,
+/// e.g. after a restart or for slots older than the buffer reaches
+async fn handle_history(State(state): State<AppState>, Query(query): Query<HistoryQuery>) -> Json<Vec<SandwichMessage>> {
+    let limit = query.limit.unwrap_or(state.message_history.size).min(MAX_HISTORY_LIMIT);
+    let matches = |s: &Sandwich| query.before_slot.is_none_or(|before| *s.slot() < before) && query.amm.as_ref().is_none_or(|amm| s.frontrun().amm() == amm) && (!query.dont_front || s.has_dont_front_victim());
+    let history = state.message_history.snapshot();
+    let mut snapshot: Vec<_> = history.iter().rev().filter(|s| matches(s)).take(limit).cloned().collect();
+    snapshot.reverse();
+    let snapshot = match state.pool.as_ref().filter(|_| snapshot.len() < limit).map(|pool| pool.get_conn()) {
+        Some(Ok(mut conn)) => {
+            let before_slot = query.before_slot.unwrap_or(u64::MAX);
+            let mut filter = "slot < ?".to_string();
+            let mut params: Vec<Value> = vec![before_slot.into()];
+            if let Some(amm) = &query.amm {
+                filter += " and amm = ?";
+                params.push(amm.as_str().into());
+            }
+            if query.dont_front {
+                filter += " and sandwich_id in (SELECT sandwich_id FROM `sandwich_view` where swap_type = 'VICTIM' and dont_front = 1)";
+            }
+            params.push(limit.into());
+            let sandwich_ids: Vec<u64> = conn.exec(format!("SELECT sandwich_id FROM `sandwich_view` where {filter} and swap_type = 'FRONTRUN' order by sandwich_id desc limit ?"), params).unwrap();
+            let mut sandwiches = load_sandwiches(&mut conn, &sandwich_ids);
+            sandwiches.reverse();
+            merge_history(sandwiches, snapshot, limit)
+        }
+        Some(Err(e)) => {
+            eprintln!("Failed to connect to the db, serving history from memory: {}", e);
+            snapshot
+        }
+        None => snapshot,
+    };
+    Json(snapshot.into_iter().map(|mut s| {
+        s.redact(&state.redaction, false);
+        SandwichMessage::new(s, query.format)
+    }).collect())
+}
+
+/// The latest `limit` of both, oldest first. The newest sandwiches in memory may not be written yet and the db ones may
+/// be in memory too, so the two are merged by frontrun tx.
+fn merge_history(stored: Vec<Sandwich>, in_memory: Vec<Sandwich>, limit: usize) -> Vec<Sandwich> {
+    let sigs: HashSet<String> = in_memory.iter().map(|s| s.frontrun().sig().clone()).collect();
+    let mut sandwiches: Vec<_> = stored.into_iter().filter(|s| !sigs.contains(s.frontrun().sig())).chain(in_memory).collect();
+    sandwiches.sort_by_key(|s| *s.slot());
+    let skip = sandwiches.len().saturating_sub(limit);
+    sandwiches.split_off(skip)
+}
+
+/// The rows of one sandwich in `sandwich_view`, gathered before it's rebuilt
+#[derive(Default)]
+struct SandwichParts {
+    slot: u64,
+    ts: i64,
+    frontrun: Option<Swap>,
+    victims: Vec<Swap>,
+    backrun: Option<Swap>,
+}
+
+/// Rebuilds sandwiches from `sandwich_view`, in the order of `sandwich_ids`
+fn load_sandwiches(conn: &mut PooledConn, sandwich_ids: &[u64]) -> Vec<Sandwich> {
+    if sandwich_ids.is_empty() {
+        return vec![];
+    }
+    let q_marks = sandwich_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+    let stmt = conn.prep(format!("SELECT sandwich_id, tx_hash, signer, slot, timestamp, order_in_block, outer_program, inner_program, amm, subject, input_amount, input_mint, output_amount, output_mint, swap_type, dont_front FROM `sandwich_view` where sandwich_id in ({q_marks})")).unwrap();
+    let mut parts: HashMap<u64, SandwichParts> = HashMap::new();
+    let res = conn.exec_iter(&stmt, sandwich_ids.to_vec()).unwrap();
+    for row in res {
+        let row = row.unwrap();
+        let sandwich_id: u64 = row.get(0).unwrap();
+        let tx_hash: String = row.get(1).unwrap();
+        let signer: String = row.get(2).unwrap();
+        let slot: u64 = row.get(3).unwrap();
+        let ts: i64 = row.get(4).unwrap();
+        let order_in_block: u64 = row.get(5).unwrap();
+        let outer_program: Option<String> = row.get(6).unwrap();
+        let inner_program: String = row.get(7).unwrap();
+        let amm: String = row.get(8).unwrap();
+        let subject: String = row.get(9).unwrap();
+        let input_amount: u64 = row.get(10).unwrap();
+        let input_mint: String = row.get(11).unwrap();
+        let output_amount: u64 = row.get(12).unwrap();
+        let output_mint: String = row.get(13).unwrap();
+        let swap_type: String = row.get(14).unwrap();
+        let dont_front: bool = match row.get(15).unwrap() {
+            Value::Bytes(bytes) if bytes.len() == 1 => bytes[0] != 0,
+            _ => false,
+        };
+        let swap = Swap::new(
+            outer_program,
+            inner_program,
+            amm,
+            signer,
+            subject,
+            input_mint,
+            output_mint,
+            input_amount,
+            output_amount,
+            order_in_block,
+            tx_hash.clone(),
+            dont_front,
+        );
+        let entry = parts.entry(sandwich_id).or_insert_with(|| SandwichParts { slot, ts, ..Default::default() });
+        match swap_type.into() {
+            SwapType::Frontrun => entry.frontrun = Some(swap),
+            SwapType::Victim => entry.victims.push(swap),
+            SwapType::Backrun => entry.backrun = Some(swap),
+        };
+    }
+    sandwich_ids.iter().filter_map(|id| {
+        let SandwichParts { slot, ts, frontrun, victims, backrun } = parts.remove(id)?;
+        match (frontrun, backrun) {
+            (Some(frontrun), Some(backrun)) if !victims.is_empty() => Some(Sandwich::new(
+                slot,
+                frontrun,
+                victims,
+                backrun,
+                ts,
+            )),
+            _ => None,
+        }
+    }).collect()
+}
+
+async fn handle_search_tx(State(state): State<AppState>, Path(txid): Path<String>, Query(query): Query<FormatQuery>) -> Json<Option<SandwichMessage>> {
+    let Some(pool) = &state.pool else {
+        return Json(None);
+    };
+    let mut conn = pool.get_conn().unwrap();
+    // look for a valid sandwich
+    let stmt = conn.prep("SELECT sandwich_id, (max(order_in_block)-min(order_in_block))/count(*) as ratio FROM `sandwich_view` v where sandwich_id in (select sandwich_id from sandwich_view where tx_hash=?) GROUP by sandwich_id order by ratio asc limit 1;").unwrap();
+    let sandwich_id = conn.exec_first(&stmt, (txid,)).unwrap().map(|(sandwich_id, _): (u64, f64)| {
+        sandwich_id
+    });
+    if sandwich_id.is_none() {
+        return Json(None);
+    }
+    let sandwich = load_sandwiches(&mut conn, &[sandwich_id.unwrap()]).pop().map(|mut sandwich| {
+        sandwich.redact(&state.redaction, false);
+        SandwichMessage::new(sandwich, query.format)
+    });
+    Json(sandwich)
+}
+
+/// Without a db only the live feed and the in-memory history are served
+async fn start_web_server(sender: broadcast::Sender<Sandwich>, message_history: Arc<History>, pool: Option<Pool>) {
+    let pool = pool.map(ReadPool::from_env);
+    let mut app = Router::new()
+        .route("/", get(handle_websocket))
+        .route("/history", get(handle_history));
+    if pool.is_some() {
+        app = app.route("/search/{txid}", get(handle_search_tx));
+    }
+    let mut app = app.with_state(AppState {
+        message_history,
+        sender,
+        pool: pool.clone(),
+        redaction: Redaction::from_env(),
+    }).merge(ui::router());
+    if let Some(pool) = pool {
+        #[cfg(feature = "telegram")]
+        if let Some(config) = crate::telegram::TelegramConfig::from_env() {
+            crate::telegram::start_bot(pool.clone(), Redaction::from_env(), config);
+        }
+        app = app.merge(api::router(pool));
+    }
+    let listener = tokio::net::TcpListener::bind(config::get().bind_address())
+        .await
+        .unwrap();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
+}
+
+/// Runs the legacy finder over `GRPC_URL` and serves its feed along with the API, without a db only the live feed
+/// and the in-memory history. Returns once shutdown is triggered and the finder's last sandwiches are written.
+pub async fn run(ctx: Context) {
+    let Context { pool: db_pool, shutdown } = ctx;
+    if db_pool.is_none() {
+        println!("No db configured, streaming only");
+    }
+    let (sender, receiver) = mpsc::channel::<Sandwich>(BROADCAST_QUEUE_SIZE);
+    let (db_sender, db_receiver) = mpsc::channel::<DbMessage>(100);
+    let from_slot = db_pool.as_ref().and_then(|pool| load_checkpoint(pool, CHECKPOINT_STREAM)).map(|slot| slot + 1);
+    let finder = tokio::spawn(sandwich_finder(sender, db_sender, shutdown, from_slot));
+    let message_history = Arc::new(History::new(*config::get().history_size()));
+    let (sender, _) = broadcast::channel::<Sandwich>(100);
+    tokio::spawn(start_web_server(sender.clone(), message_history.clone(), db_pool.clone()));
+    let writer = tokio::spawn(store_to_db(db_pool.clone(), db_receiver));
+    let dont_front = match db_pool.as_ref().map(|pool| pool.get_conn().and_then(|mut conn| DontFrontWindow::load(&mut conn))) {
+        Some(Ok(window)) => window,
+        Some(Err(e)) => {
+            eprintln!("Failed to load the recent dont_front sandwiches: {}", e);
+            DontFrontWindow::default()
+        }
+        None => DontFrontWindow::default(),
+    };
+    let broadcaster = tokio::spawn(broadcast_sandwiches(receiver, message_history, sender, dont_front));
+    // once the finder has stopped, the channels close after the last of its messages are broadcast and written
+    let last_slot = finder.await.unwrap();
+    broadcaster.await.unwrap();
+    writer.await.unwrap();
+    if let (Some(pool), Some(slot)) = (db_pool, last_slot) {
+        save_checkpoint(&pool, CHECKPOINT_STREAM, slot);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sandwich(slot: u64, sig: &str) -> Sandwich {
+        let swap = |sig: &str| Swap::new(None, "program".into(), "amm".into(), "signer".into(), "subject".into(), "in".into(), "out".into(), 1, 1, 0, sig.into(), false);
+        Sandwich::new(slot, swap(sig), vec![swap("victim")], swap("backrun"), 0)
+    }
+
+    #[test]
+    fn test_merge_history() {
+        // 3 is in both, 4 isn't written yet
+        let merged = merge_history(vec![sandwich(1, "a"), sandwich(2, "b"), sandwich(3, "c")], vec![sandwich(3, "c"), sandwich(4, "d")], 3);
+        assert_eq!(merged.iter().map(|s| s.frontrun().sig().as_str()).collect::<Vec<_>>(), vec!["b", "c", "d"]);
+        // nothing in memory, e.g. right after a restart
+        assert_eq!(merge_history(vec![sandwich(1, "a")], vec![], 3).len(), 1);
+    }
+
+    #[test]
+    fn test_dont_front_window() {
+        let mut window = DontFrontWindow::default();
+        window.push(100);
+        window.push(100 + DONT_FRONT_WINDOW_SLOTS - 1);
+        assert_eq!(window.count(100 + DONT_FRONT_WINDOW_SLOTS - 1), 2);
+        // a late arrival behind the latest one is still dropped once it's out of the window
+        window.push(150);
+        assert_eq!(window.count(100 + DONT_FRONT_WINDOW_SLOTS), 2);
+        // and the count decays without anything new coming in
+        assert_eq!(window.count(150 + DONT_FRONT_WINDOW_SLOTS), 1);
+        assert_eq!(window.count(200 + 2 * DONT_FRONT_WINDOW_SLOTS), 0);
+    }
+}
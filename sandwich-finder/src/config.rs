@@ -0,0 +1,159 @@
+use std::{collections::HashMap, env, fs, io, path::Path, str::FromStr as _, sync::OnceLock};
+
+use derive_getters::Getters;
+use serde::Deserialize;
+use solana_sdk::{commitment_config::{CommitmentConfig, CommitmentLevel}};
+use thiserror::Error;
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("unable to read {0}: {1}")]
+    Read(String, io::Error),
+    #[error("unable to parse {0}: {1}")]
+    Parse(String, toml::de::Error),
+    #[error("invalid {0}: {1}")]
+    Invalid(&'static str, String),
+}
+
+/// Settings shared by every binary, read from the TOML file at `CONFIG_FILE` if there's one, with the environment
+/// variables of the same settings taking precedence, so instances can share a file and differ in a variable or two.
+/// The more specific settings are still read from the environment by the modules they belong to.
+#[derive(Clone, Debug, Deserialize, Getters)]
+#[serde(default)]
+pub struct Config {
+    /// `GRPC_URL`
+    grpc_url: Option<String>,
+    /// `RPC_URL`
+    rpc_url: Option<String>,
+    /// `MYSQL`
+    mysql: Option<String>,
+    /// `BIND_ADDRESS`, or `127.0.0.1:$API_PORT`, for the API server
+    bind_address: String,
+    /// `HISTORY_SIZE`, sandwiches the legacy finder keeps for `/history`
+    history_size: usize,
+    /// `COMMITMENT`, for the rpc lookups made while decoding blocks
+    commitment: CommitmentLevel,
+    /// Whether the finder for each program id runs, all of them unless turned off here or listed in `DISABLED_FINDERS`
+    finders: HashMap<String, bool>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            grpc_url: None,
+            rpc_url: None,
+            mysql: None,
+            bind_address: "127.0.0.1:11000".to_string(),
+            history_size: 100,
+            commitment: CommitmentLevel::Processed,
+            finders: HashMap::new(),
+        }
+    }
+}
+
+impl Config {
+    pub fn from_toml(path: &str, toml: &str) -> Result<Self, ConfigError> {
+        toml::from_str(toml).map_err(|e| ConfigError::Parse(path.to_string(), e))
+    }
+
+    /// The file at `path` if given, then the environment on top
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+        let mut config = match path {
+            Some(path) => {
+                let display = path.display().to_string();
+                let toml = fs::read_to_string(path).map_err(|e| ConfigError::Read(display.clone(), e))?;
+                Self::from_toml(&display, &toml)?
+            },
+            None => Self::default(),
+        };
+        config.apply_overrides(|name| env::var(name).ok())?;
+        Ok(config)
+    }
+
+    /// Replaces the settings `var` has a value for, an empty one unsets the optional ones
+    pub fn apply_overrides(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), ConfigError> {
+        let url = |name: &str, current: &mut Option<String>| {
+            if let Some(url) = var(name) {
+                *current = Some(url).filter(|url| !url.is_empty());
+            }
+        };
+        url("GRPC_URL", &mut self.grpc_url);
+        url("RPC_URL", &mut self.rpc_url);
+        url("MYSQL", &mut self.mysql);
+        if let Some(bind_address) = var("BIND_ADDRESS") {
+            self.bind_address = bind_address;
+        } else if let Some(port) = var("API_PORT") {
+            self.bind_address = format!("127.0.0.1:{port}");
+        }
+        if let Some(history_size) = var("HISTORY_SIZE") {
+            self.history_size = history_size.parse().map_err(|_| ConfigError::Invalid("HISTORY_SIZE", history_size))?;
+        }
+        if let Some(commitment) = var("COMMITMENT") {
+            self.commitment = CommitmentLevel::from_str(&commitment).map_err(|_| ConfigError::Invalid("COMMITMENT", commitment))?;
+        }
+        if let Some(disabled) = var("DISABLED_FINDERS") {
+            for program in disabled.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+                self.finders.insert(program.to_string(), false);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn commitment_config(&self) -> CommitmentConfig {
+        CommitmentConfig { commitment: self.commitment }
+    }
+
+    pub fn finder_enabled(&self, program: &str) -> bool {
+        self.finders.get(program).copied().unwrap_or(true)
+    }
+}
+
+/// Loads the config from `path`, or `CONFIG_FILE` without one, unless it's already loaded. Exits if it can't be read.
+pub fn init(path: Option<&Path>) -> &'static Config {
+    CONFIG.get_or_init(|| {
+        let file = path.map(Path::to_path_buf).or_else(|| env::var("CONFIG_FILE").ok().filter(|f| !f.is_empty()).map(Into::into));
+        match Config::load(file.as_deref()) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("Failed to load config: {}", e);
+                std::process::exit(1);
+            },
+        }
+    })
+}
+
+/// The process' config, loaded from `CONFIG_FILE` on first use
+pub fn get() -> &'static Config {
+    init(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overrides() {
+        let mut config = Config::from_toml("test.toml", r#"
+            rpc_url = "http://file"
+            mysql = "mysql://file"
+            commitment = "confirmed"
+
+            [finders]
+            "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8" = false
+        "#).unwrap();
+        assert_eq!(config.history_size, 100);
+        assert_eq!(config.commitment, CommitmentLevel::Confirmed);
+        let env: HashMap<&str, &str> = [("RPC_URL", "http://env"), ("MYSQL", ""), ("API_PORT", "12000"), ("DISABLED_FINDERS", "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc")].into();
+        config.apply_overrides(|name| env.get(name).map(|v| v.to_string())).unwrap();
+        assert_eq!(config.rpc_url.as_deref(), Some("http://env"));
+        assert_eq!(config.mysql, None);
+        assert_eq!(config.bind_address, "127.0.0.1:12000");
+        assert!(!config.finder_enabled("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8"));
+        assert!(!config.finder_enabled("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc"));
+        assert!(config.finder_enabled("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK"));
+        assert!(config.apply_overrides(|name| (name == "HISTORY_SIZE").then(|| "lots".to_string())).is_err());
+        assert!(Config::from_toml("config.example.toml", include_str!("../../config.example.toml")).is_ok());
+    }
+}
@@ -0,0 +1,137 @@
+use std::{collections::HashMap, env, sync::Arc};
+
+use mysql::{prelude::Queryable as _, Pool};
+use solana_sdk::{bs58, instruction::Instruction, pubkey::Pubkey};
+use yellowstone_grpc_proto::geyser::SubscribeUpdateTransactionInfo;
+
+use crate::{events::swap::SwapV2, metrics};
+
+/// The raw ix behind a swap whose legs couldn't be paired with transfers, which is what a finder matching the
+/// discriminant of a changed layout yields
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodeFailure {
+    pub slot: u64,
+    pub signature: String,
+    pub program: Arc<str>,
+    pub ix_index: u32,
+    pub inner_ix_index: Option<u32>,
+    pub data: Vec<u8>,
+    pub accounts: Vec<Pubkey>,
+}
+
+/// The failures among the swaps found in a tx, `ixs` and `account_keys` being the decompiled tx's
+pub fn decode_failures(raw_tx: &SubscribeUpdateTransactionInfo, ixs: &[Instruction], account_keys: &[Pubkey], swaps: &[SwapV2]) -> Vec<DecodeFailure> {
+    swaps.iter().filter(|swap| !swap.is_complete()).filter_map(|swap| {
+        let ix_index = *swap.ix_index();
+        let (data, accounts) = match swap.inner_ix_index() {
+            None => {
+                let ix = ixs.get(ix_index as usize)?;
+                (ix.data.clone(), ix.accounts.iter().map(|a| a.pubkey).collect())
+            },
+            Some(inner_ix_index) => {
+                let inner_ixs = raw_tx.meta.as_ref()?.inner_instructions.iter().find(|inner| inner.index == ix_index)?;
+                let ix = inner_ixs.instructions.get(*inner_ix_index as usize)?;
+                (ix.data.clone(), ix.accounts.iter().filter_map(|&i| account_keys.get(i as usize).copied()).collect())
+            },
+        };
+        Some(DecodeFailure {
+            slot: *swap.slot(),
+            signature: raw_tx.transaction.as_ref().and_then(|tx| tx.signatures.first()).map(|sig| bs58::encode(sig).into_string()).unwrap_or_default(),
+            program: swap.program().clone(),
+            ix_index,
+            inner_ix_index: *swap.inner_ix_index(),
+            data,
+            accounts,
+        })
+    }).collect()
+}
+
+/// Keeps the most recent failures of each program in `decode_failures`, so a layout change can be looked into
+/// without capturing the blocks it broke on again
+pub struct DecodeFailureLog {
+    pool: Pool,
+    /// Rows kept per program
+    max_rows: usize,
+}
+
+impl DecodeFailureLog {
+    /// Reads `DECODE_FAILURES_MAX_ROWS`, 100 by default, 0 disables the log
+    pub fn from_env(pool: Pool) -> Option<Self> {
+        let max_rows = env::var("DECODE_FAILURES_MAX_ROWS").ok().and_then(|v| v.parse().ok()).unwrap_or(100);
+        (max_rows > 0).then_some(Self { pool, max_rows })
+    }
+
+    /// Writes a block's failures in the background, then drops the rows of their programs past the limit
+    pub fn record(&self, failures: Vec<DecodeFailure>) {
+        if failures.is_empty() {
+            return;
+        }
+        metrics::add("decode_failures", failures.len() as u64);
+        let failures = cap_per_program(failures, self.max_rows);
+        let pool = self.pool.clone();
+        let max_rows = self.max_rows;
+        tokio::task::spawn_blocking(move || {
+            let mut conn = match pool.get_conn() {
+                Ok(conn) => conn,
+                Err(e) => {
+                    eprintln!("Failed to get a connection for decode failures: {}", e);
+                    return;
+                }
+            };
+            let mut programs: Vec<Arc<str>> = failures.iter().map(|f| f.program.clone()).collect();
+            programs.sort();
+            programs.dedup();
+            let rows = failures.into_iter().map(|f| {
+                let accounts: Vec<String> = f.accounts.iter().map(Pubkey::to_string).collect();
+                (f.slot, f.signature, f.program.to_string(), f.ix_index, f.inner_ix_index, f.data, accounts.join(","))
+            });
+            if let Err(e) = conn.exec_batch("insert into decode_failures (slot, sig, program, ix_index, inner_ix_index, data, accounts) values (?, ?, ?, ?, ?, ?, ?)", rows) {
+                eprintln!("Failed to insert decode failures: {}", e);
+                return;
+            }
+            for program in programs {
+                // the derived table works around mysql not taking a limit in an `in` subquery
+                if let Err(e) = conn.exec_drop(
+                    "delete from decode_failures where program=? and id <= (select id from (select id from decode_failures where program=? order by id desc limit 1 offset ?) oldest)",
+                    (program.as_ref(), program.as_ref(), max_rows),
+                ) {
+                    eprintln!("Failed to prune decode failures: {}", e);
+                }
+            }
+        });
+    }
+}
+
+/// The first `max_rows` failures of each program, a finder broken outright can fail on every swap of a block
+fn cap_per_program(failures: Vec<DecodeFailure>, max_rows: usize) -> Vec<DecodeFailure> {
+    let mut counts: HashMap<Arc<str>, usize> = HashMap::new();
+    failures.into_iter().filter(|f| {
+        let count = counts.entry(f.program.clone()).or_default();
+        *count += 1;
+        *count <= max_rows
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure(program: &str, ix_index: u32) -> DecodeFailure {
+        DecodeFailure {
+            slot: 1,
+            signature: "sig".to_string(),
+            program: program.into(),
+            ix_index,
+            inner_ix_index: None,
+            data: vec![],
+            accounts: vec![],
+        }
+    }
+
+    #[test]
+    fn test_cap_per_program() {
+        let failures = vec![failure("a", 0), failure("a", 1), failure("b", 2), failure("a", 3)];
+        let capped: Vec<u32> = cap_per_program(failures, 2).iter().map(|f| f.ix_index).collect();
+        assert_eq!(capped, vec![0, 1, 2]);
+    }
+}
@@ -1,81 +1,648 @@
-use std::{collections::{HashMap, HashSet}, sync::Arc};
+//! Group-wise sandwich detection over indexed events.
+//!
+//! [`EventLoader`] reads a slot range out of the database, [`GroupIterator`] splits the loaded events
+//! into groups of consecutive slots, either fixed size or following the [`LeaderSchedule`], and [`detect_group`] runs the detectors over a single group.
+//! Sandwiches can't span more than a leader's consecutive slots, so groups are what detection works on.
 
+use std::{borrow::Cow, collections::{BTreeMap, HashMap, HashSet}, env, sync::Arc};
+
+use derive_getters::Getters;
 use mysql::{prelude::Queryable, Pool, Row};
-use crate::events::{common::Timestamp, swap::SwapV2, transaction::TransactionV2, transfer::TransferV2};
-
-pub const LEADER_GROUP_SIZE: u64 = 4; // slots per leader group
-
-pub async fn get_events(conn: Pool, start_slot: u64, end_slot: u64) -> (Vec<SwapV2>, Vec<TransferV2>, Vec<TransactionV2>) {
-    let conn = &mut conn.get_conn().unwrap();
-    let res: Vec<Row> = conn.exec("select id, event_type, slot, inclusion_order, ix_index, inner_ix_index, authority, outer_program, program, amm, input_mint, output_mint, input_amount, output_amount, input_ata, output_ata, input_inner_ix_index, output_inner_ix_index from event_view where slot between ? and ?", vec![start_slot, end_slot]).unwrap();
-    let mut swaps = vec![];
-    let mut transfers = vec![];
-    let mut txs = vec![];
-    for row in res {
-        let id: u64 = row.get("id").unwrap();
-        let event_type: Arc<str> = row.get("event_type").unwrap();
-        let slot: u64 = row.get("slot").unwrap();
-        let inclusion_order: u32 = row.get("inclusion_order").unwrap();
-        let ix_index: u32 = row.get("ix_index").unwrap();
-        let inner_ix_index: Option<i32> = row.get("inner_ix_index").unwrap();
-        let authority: Arc<str> = row.get("authority").unwrap();
-        let outer_program: Option<Arc<str>> = row.get("outer_program").unwrap();
-        let program: Arc<str> = row.get("program").unwrap();
-        let amm: Option<Arc<str>> = row.get("amm").unwrap();
-        let input_mint: Arc<str> = row.get("input_mint").unwrap();
-        let output_mint: Arc<str> = row.get("output_mint").unwrap();
-        let input_amount: u64 = row.get("input_amount").unwrap();
-        let output_amount: u64 = row.get("output_amount").unwrap();
-        let input_ata: Arc<str> = row.get("input_ata").unwrap();
-        let output_ata: Arc<str> = row.get("output_ata").unwrap();
-        let input_inner_ix_index: Option<i32> = row.get("input_inner_ix_index").unwrap();
-        let output_inner_ix_index: Option<i32> = row.get("output_inner_ix_index").unwrap();
-        let inner_ix_index = inner_ix_index.filter(|&x| x >= 0).map(|x| x as u32);
-        let input_inner_ix_index = input_inner_ix_index.filter(|&x| x >= 0).map(|x| x as u32);
-        let output_inner_ix_index = output_inner_ix_index.filter(|&x| x >= 0).map(|x| x as u32);
-        match event_type.as_ref() {
-            "SWAP" => {
-                swaps.push(SwapV2::new(outer_program, program, authority, amm.unwrap(), input_mint, output_mint, input_amount, output_amount, input_ata, output_ata, input_inner_ix_index, output_inner_ix_index, slot, inclusion_order, ix_index, inner_ix_index, id));
-            },
-            "TRANSFER" => {
-                transfers.push(TransferV2::new(outer_program, program, authority, input_mint, input_amount, input_ata, output_ata, slot, inclusion_order, ix_index, inner_ix_index, id));
-            },
-            _ => {},
+use crate::{events::{addresses::WSOL_MINT, anomaly::{detect_anomalies, AnomalyConfig, PriceAnomaly}, backrun::{detect_backruns, BackrunCandidate, BackrunConfig}, common::Timestamp, event::Event, sandwich::{detect, RejectionStats, SandwichCandidate, SandwichConfig}, sol_mints::SOL_EQUIVALENTS, swap::{PoolReserves, QuoteLimits, SwapV2}, transaction::TransactionV2, transfer::TransferV2, wash::{detect_washes, WashCandidate, WashConfig}}, utils::prefixed_env_var};
+
+pub const LEADER_GROUP_SIZE: u64 = 4; // default slots per leader group
+pub const ROLLUP_BUCKET_SLOTS: u64 = 750; // ~5 minutes
+pub const SLOTS_PER_HOUR: u64 = 9000;
+
+/// Parses a row with the columns of `event_view`
+pub fn event_from_row(row: &Row) -> Option<Event> {
+    let id: u64 = row.get("id").unwrap();
+    let event_type: Arc<str> = row.get("event_type").unwrap();
+    let slot: u64 = row.get("slot").unwrap();
+    let inclusion_order: u32 = row.get("inclusion_order").unwrap();
+    let ix_index: u32 = row.get("ix_index").unwrap();
+    let inner_ix_index: Option<i32> = row.get("inner_ix_index").unwrap();
+    let authority: Arc<str> = row.get("authority").unwrap();
+    let outer_program: Option<Arc<str>> = row.get("outer_program").unwrap();
+    let program: Arc<str> = row.get("program").unwrap();
+    let amm: Option<Arc<str>> = row.get("amm").unwrap();
+    let input_mint: Arc<str> = row.get("input_mint").unwrap();
+    let output_mint: Arc<str> = row.get("output_mint").unwrap();
+    let input_amount: u64 = row.get("input_amount").unwrap();
+    let output_amount: u64 = row.get("output_amount").unwrap();
+    let input_ata: Arc<str> = row.get("input_ata").unwrap();
+    let output_ata: Arc<str> = row.get("output_ata").unwrap();
+    let input_inner_ix_index: Option<i32> = row.get("input_inner_ix_index").unwrap();
+    let output_inner_ix_index: Option<i32> = row.get("output_inner_ix_index").unwrap();
+    // only there if the query joined transactions
+    let block_time: Option<i64> = row.get::<Option<i64>, _>("block_time").flatten();
+    let inner_ix_index = inner_ix_index.filter(|&x| x >= 0).map(|x| x as u32);
+    let input_inner_ix_index = input_inner_ix_index.filter(|&x| x >= 0).map(|x| x as u32);
+    let output_inner_ix_index = output_inner_ix_index.filter(|&x| x >= 0).map(|x| x as u32);
+    let mut event = match event_type.as_ref() {
+        "SWAP" => {
+            let mut swap = SwapV2::new(outer_program, program, authority, amm.unwrap(), input_mint, output_mint, input_amount, output_amount, input_ata, output_ata, input_inner_ix_index, output_inner_ix_index, slot, inclusion_order, ix_index, inner_ix_index, id);
+            // only there if the query selected them from events_with_id
+            swap.set_quote_limits(QuoteLimits::new(row.get::<Option<u64>, _>("min_out").flatten(), row.get::<Option<u64>, _>("max_in").flatten()));
+            swap.set_pool_reserves(PoolReserves::new(row.get::<Option<u64>, _>("input_reserve").flatten(), row.get::<Option<u64>, _>("output_reserve").flatten()));
+            // same for the caller, which also needs joining against the address dictionary
+            swap.set_caller_program(row.get::<Option<Arc<str>>, _>("caller_program").flatten());
+            Some(Event::Swap(swap))
+        },
+        "TRANSFER" => {
+            Some(Event::Transfer(TransferV2::new(outer_program, program, authority, input_mint, input_amount, input_ata, output_ata, slot, inclusion_order, ix_index, inner_ix_index, id)))
+        },
+        _ => None,
+    }?;
+    event.set_block_time(block_time);
+    Some(event)
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GroupConfig {
+    /// Slots per group, groups start at multiples of this
+    pub size: u64,
+    /// Group consecutive slots of the same leader instead, slots missing from the schedule fall back to `size`
+    pub by_leader: bool,
+}
+
+impl Default for GroupConfig {
+    fn default() -> Self {
+        Self {
+            size: LEADER_GROUP_SIZE,
+            by_leader: false,
+        }
+    }
+}
+
+impl GroupConfig {
+    /// Reads `LEADER_GROUP_SIZE` and `GROUP_BY_LEADER`, falling back to the defaults
+    pub fn from_env() -> Self {
+        let size = env::var("LEADER_GROUP_SIZE").ok().and_then(|v| v.parse().ok()).filter(|&s| s > 0).unwrap_or(LEADER_GROUP_SIZE);
+        let by_leader = env::var("GROUP_BY_LEADER").is_ok_and(|v| v == "1" || v == "true");
+        Self { size, by_leader }
+    }
+
+    /// Widens `[start_slot, end_slot]` to whole groups
+    pub fn align(&self, start_slot: u64, end_slot: u64) -> (u64, u64) {
+        (start_slot / self.size * self.size, end_slot / self.size * self.size + self.size - 1)
+    }
+
+    /// Whether `slot` is the last one of its fixed size group
+    pub fn is_group_end(&self, slot: u64) -> bool {
+        slot % self.size == self.size - 1
+    }
+
+    /// Splits `[start_slot, end_slot]` into groups, as (first slot, last slot)
+    pub fn bounds(&self, start_slot: u64, end_slot: u64, leaders: &LeaderSchedule) -> Vec<(u64, u64)> {
+        let mut bounds = vec![];
+        let mut slot = start_slot;
+        while slot <= end_slot {
+            let end = match leaders.leader(slot).filter(|_| self.by_leader) {
+                Some(leader) => (slot..end_slot).find(|&s| leaders.leader(s + 1) != Some(leader)).unwrap_or(end_slot),
+                None => {
+                    let end = (slot / self.size * self.size + self.size - 1).min(end_slot);
+                    // stop short of slots the schedule does cover
+                    (slot..end).find(|&s| self.by_leader && leaders.leader(s + 1).is_some()).unwrap_or(end)
+                }
+            };
+            bounds.push((slot, end));
+            slot = end + 1;
+        }
+        bounds
+    }
+
+    /// The whole fixed size groups from `next_slot`, itself the start of one, through `filled_slot`, spanning at most
+    /// `max_slots` but never less than a group. `None` until the group starting at `next_slot` is filled.
+    pub fn follow_range(&self, next_slot: u64, filled_slot: u64, max_slots: u64) -> Option<(u64, u64)> {
+        let end = ((filled_slot + 1) / self.size * self.size).min(next_slot + max_slots.max(self.size) / self.size * self.size);
+        (end > next_slot).then(|| (next_slot, end - 1))
+    }
+
+    /// The group that ends at `slot`, if `slot` is the last slot of one
+    pub fn group_ending_at(&self, slot: u64, leaders: &LeaderSchedule) -> Option<(u64, u64)> {
+        match leaders.leader(slot).filter(|_| self.by_leader) {
+            Some(leader) => {
+                if leaders.leader(slot + 1) == Some(leader) {
+                    return None;
+                }
+                let start = (leaders.first_slot()..slot).rev().find(|&s| leaders.leader(s) != Some(leader)).map(|s| s + 1).unwrap_or(slot.min(leaders.first_slot()));
+                Some((start, slot))
+            }
+            None => self.is_group_end(slot).then(|| (slot + 1 - self.size, slot)),
+        }
+    }
+}
+
+/// Leader of each slot as address ids, see populate-leader-schedule
+#[derive(Clone, Debug, Default)]
+pub struct LeaderSchedule {
+    leaders: BTreeMap<u64, u64>,
+}
+
+impl LeaderSchedule {
+    pub fn new(leaders: impl IntoIterator<Item = (u64, u64)>) -> Self {
+        Self { leaders: leaders.into_iter().collect() }
+    }
+
+    pub fn leader(&self, slot: u64) -> Option<u64> {
+        self.leaders.get(&slot).copied()
+    }
+
+    /// Earliest slot in the schedule, 0 if empty
+    pub fn first_slot(&self) -> u64 {
+        self.leaders.keys().next().copied().unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaders.is_empty()
+    }
+}
+
+/// Events of a slot range, in chronological order and with the transfers detection doesn't care about taken out
+#[derive(Clone, Debug, Default, Getters)]
+pub struct LoadedEvents {
+    swaps: Vec<SwapV2>,
+    transfers: Vec<TransferV2>,
+    txs: Vec<TransactionV2>,
+}
+
+impl LoadedEvents {
+    pub fn new(mut swaps: Vec<SwapV2>, mut transfers: Vec<TransferV2>, mut txs: Vec<TransactionV2>) -> Self {
+        // SOL under any of its mints pairs up with WSOL
+        swaps.iter_mut().for_each(|s| SOL_EQUIVALENTS.normalize_swap(s));
+        transfers.iter_mut().for_each(|t| SOL_EQUIVALENTS.normalize_transfer(t));
+        // Filter out swap leg transfers
+        let mut transfer_map: HashMap<Timestamp, TransferV2> = transfers.into_iter()
+            .map(|t| (*t.timestamp(), t))
+            .collect();
+        // Legacy swaps were stored without their legs' inner ix indexes, their legs are matched by ix and ATA instead
+        let mut legacy_legs: HashSet<(u64, u32, u32, &str, &str)> = HashSet::new();
+        for ele in swaps.iter() {
+            if let Some(input_inner_ix) = ele.input_inner_ix_index() {
+                transfer_map.remove(&Timestamp::new(*ele.slot(), *ele.inclusion_order(), *ele.ix_index(), Some(*input_inner_ix)));
+            } else {
+                legacy_legs.insert((*ele.slot(), *ele.inclusion_order(), *ele.ix_index(), ele.input_ata(), ""));
+            }
+            if let Some(output_inner_ix) = ele.output_inner_ix_index() {
+                transfer_map.remove(&Timestamp::new(*ele.slot(), *ele.inclusion_order(), *ele.ix_index(), Some(*output_inner_ix)));
+            } else {
+                legacy_legs.insert((*ele.slot(), *ele.inclusion_order(), *ele.ix_index(), "", ele.output_ata()));
+            }
+        }
+        let transfers: Vec<_> = transfer_map.into_values().filter(|t| {
+            let key = (*t.slot(), *t.inclusion_order(), *t.ix_index());
+            !legacy_legs.contains(&(key.0, key.1, key.2, t.input_ata().as_ref(), "")) && !legacy_legs.contains(&(key.0, key.1, key.2, "", t.output_ata().as_ref()))
+        }).collect();
+
+        // Filter out transfers from AMMs (gets rid of some noise from fees)
+        let amms = swaps.iter().map(|s| s.amm()).collect::<HashSet<_>>();
+        let mut transfers: Vec<TransferV2> = transfers.into_iter().filter(|t| !amms.contains(t.input_ata()) && !amms.contains(t.output_ata()) && !amms.contains(t.authority())).collect();
+
+        // Sort events in chronological order. Legacy swaps within the same ix have no inner ix index to tell them
+        // apart, they were inserted in execution order so their ids break the tie.
+        swaps.sort_by_cached_key(|s| (*s.timestamp(), *s.id()));
+        transfers.sort_by_cached_key(|t| (*t.timestamp(), *t.id()));
+        txs.sort_by_cached_key(|t| Timestamp::new(*t.slot(), *t.inclusion_order(), 0, None));
+
+        Self { swaps, transfers, txs }
+    }
+
+    /// Splits the events into groups, see [`GroupConfig::bounds`]
+    pub fn groups(&self, bounds: Vec<(u64, u64)>) -> GroupIterator<'_> {
+        GroupIterator::new(self, bounds)
+    }
+}
+
+/// Every column [`event_from_row`] reads, including the ones only `events_with_id` has
+const LOAD_EVENTS: &str = "select v.id, v.event_type, v.slot, v.inclusion_order, v.ix_index, v.inner_ix_index, v.authority, v.outer_program, v.program, v.amm, v.input_mint, v.output_mint, v.input_amount, v.output_amount, v.input_ata, v.output_ata, v.input_inner_ix_index, v.output_inner_ix_index, e.min_out, e.max_in, e.input_reserve, e.output_reserve, c.address as caller_program from event_view v join events_with_id e on e.id=v.id left join address_lookup_table c on c.id=e.caller_program_id where v.slot between ? and ?";
+
+#[derive(Clone)]
+pub struct EventLoader {
+    pool: Pool,
+}
+
+impl EventLoader {
+    pub fn new(pool: Pool) -> Self {
+        Self { pool }
+    }
+
+    /// Events of `[start_slot, end_slot]`, both ends inclusive
+    pub async fn load(&self, start_slot: u64, end_slot: u64) -> LoadedEvents {
+        let conn = &mut self.pool.get_conn().unwrap();
+        let res: Vec<Row> = conn.exec(LOAD_EVENTS, vec![start_slot, end_slot]).unwrap();
+        let mut swaps = vec![];
+        let mut transfers = vec![];
+        let mut txs = vec![];
+        for row in res {
+            match event_from_row(&row) {
+                Some(Event::Swap(swap)) => swaps.push(swap),
+                Some(Event::Transfer(transfer)) => transfers.push(transfer),
+                _ => {},
+            }
+        }
+        let res: Vec<Row> = conn.exec("select slot, inclusion_order, sig, fee, cu_actual, ifnull(dont_front, 0) as dont_front, block_time, block_tx_count, cu_limit, block_cu from transactions where slot between ? and ?", vec![start_slot, end_slot]).unwrap();
+        for row in res {
+            let slot: u64 = row.get("slot").unwrap();
+            let inclusion_order: u32 = row.get("inclusion_order").unwrap();
+            let sig: String = row.get("sig").unwrap();
+            let fee: u64 = row.get("fee").unwrap();
+            let cu_actual: u64 = row.get("cu_actual").unwrap();
+            let dont_front: bool = row.get("dont_front").unwrap();
+            let block_time: Option<i64> = row.get("block_time").unwrap();
+            let block_tx_count: Option<u32> = row.get("block_tx_count").unwrap();
+            let cu_limit: Option<u32> = row.get("cu_limit").unwrap();
+            let block_cu: Option<u64> = row.get("block_cu").unwrap();
+            let mut tx = TransactionV2::new(slot, inclusion_order, sig.into(), fee, cu_actual, dont_front);
+            tx.set_block_time(block_time);
+            tx.set_block_tx_count(block_tx_count);
+            tx.set_cu_limit(cu_limit);
+            tx.set_block_cu(block_cu);
+            txs.push(tx);
+        }
+        LoadedEvents::new(swaps, transfers, txs)
+    }
+
+    /// Latest slot the indexer has written transactions for
+    pub fn filled_slot(&self) -> Option<u64> {
+        let conn = &mut self.pool.get_conn().unwrap();
+        conn.query_first("select max(slot) from transactions").unwrap().flatten()
+    }
+
+    /// Last slot the detector following the indexer as `name` is done with, see `detector --follow`
+    pub fn load_cursor(&self, name: &str) -> Option<u64> {
+        let conn = &mut self.pool.get_conn().unwrap();
+        conn.exec_first("select slot from detector_cursor where name=?", (name,)).unwrap()
+    }
+
+    pub fn save_cursor(&self, name: &str, slot: u64) {
+        let conn = &mut self.pool.get_conn().unwrap();
+        if let Err(e) = conn.exec_drop("insert into detector_cursor (name, slot) values (?, ?) on duplicate key update slot=values(slot)", (name, slot)) {
+            eprintln!("Failed to save the {} detector cursor: {}", name, e);
+        }
+    }
+
+    /// Leader schedule of `[start_slot, end_slot]`, empty if it hasn't been populated
+    pub async fn load_leaders(&self, start_slot: u64, end_slot: u64) -> LeaderSchedule {
+        let conn = &mut self.pool.get_conn().unwrap();
+        LeaderSchedule::new(conn.exec::<(u64, u64), _, _>("select slot, leader_id from leader_schedule where slot between ? and ?", (start_slot, end_slot)).unwrap())
+    }
+}
+
+/// Events of one group of slots, borrowed from [`LoadedEvents`]
+#[derive(Clone, Copy, Debug, Getters)]
+pub struct EventGroup<'a> {
+    start_slot: u64,
+    end_slot: u64,
+    swaps: &'a [SwapV2],
+    transfers: &'a [TransferV2],
+    txs: &'a [TransactionV2],
+}
+
+pub struct GroupIterator<'a> {
+    events: &'a LoadedEvents,
+    bounds: std::vec::IntoIter<(u64, u64)>,
+}
+
+impl<'a> GroupIterator<'a> {
+    /// Yields a group for each of `bounds` (first slot, last slot), events outside of them are skipped
+    pub fn new(events: &'a LoadedEvents, bounds: Vec<(u64, u64)>) -> Self {
+        Self {
+            events,
+            bounds: bounds.into_iter(),
         }
     }
-    let res: Vec<Row> = conn.exec("select slot, inclusion_order, sig, fee, cu_actual, ifnull(dont_front, 0) as dont_front from transactions where slot between ? and ?", vec![start_slot, end_slot]).unwrap();
-    for row in res {
-        let slot: u64 = row.get("slot").unwrap();
-        let inclusion_order: u32 = row.get("inclusion_order").unwrap();
-        let sig: String = row.get("sig").unwrap();
-        let fee: u64 = row.get("fee").unwrap();
-        let cu_actual: u64 = row.get("cu_actual").unwrap();
-        let dont_front: bool = row.get("dont_front").unwrap();
-        txs.push(TransactionV2::new(slot, inclusion_order, sig.into(), fee, cu_actual, dont_front));
-    }
-
-    // Filter out swap leg transfers
-    let mut transfer_map: HashMap<Timestamp, TransferV2> = transfers.into_iter()
-        .map(|t| (*t.timestamp(), t))
-        .collect();
-    for ele in swaps.iter() {
-        if let Some(input_inner_ix) = ele.input_inner_ix_index() {
-            transfer_map.remove(&Timestamp::new(*ele.slot(), *ele.inclusion_order(), *ele.ix_index(), Some(*input_inner_ix)));
+}
+
+impl<'a> Iterator for GroupIterator<'a> {
+    type Item = EventGroup<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (start_slot, end_slot) = self.bounds.next()?;
+        let swaps = &self.events.swaps;
+        let transfers = &self.events.transfers;
+        let txs = &self.events.txs;
+        Some(EventGroup {
+            start_slot,
+            end_slot,
+            swaps: &swaps[swaps.partition_point(|s| *s.slot() < start_slot)..swaps.partition_point(|s| *s.slot() <= end_slot)],
+            transfers: &transfers[transfers.partition_point(|t| *t.slot() < start_slot)..transfers.partition_point(|t| *t.slot() <= end_slot)],
+            txs: &txs[txs.partition_point(|t| *t.slot() < start_slot)..txs.partition_point(|t| *t.slot() <= end_slot)],
+        })
+    }
+}
+
+/// SOL-side swap volume of a block, and how much of it was victims getting sandwiched
+#[derive(Clone, Debug, Default, PartialEq, Eq, Getters)]
+pub struct BlockVolume {
+    slot: u64,
+    swap_volume_lamports: u64,
+    sandwiched_volume_lamports: u64,
+}
+
+impl BlockVolume {
+    pub fn sandwiched_bps(&self) -> u64 {
+        self.sandwiched_volume_lamports * 10000 / self.swap_volume_lamports.max(1)
+    }
+}
+
+/// The swap's WSOL leg, `None` if neither side is WSOL
+fn wsol_lamports(swap: &SwapV2, wsol: &str) -> Option<u64> {
+    if swap.input_mint().as_ref() == wsol {
+        Some(*swap.input_amount())
+    } else if swap.output_mint().as_ref() == wsol {
+        Some(*swap.output_amount())
+    } else {
+        None
+    }
+}
+
+/// Sums the WSOL legs of the swaps per slot, swaps without one don't count towards either volume
+pub fn block_volumes(swaps: &[SwapV2], sandwiches: &[SandwichCandidate]) -> Arc<[BlockVolume]> {
+    let wsol = WSOL_MINT.to_string();
+    let victims: HashSet<u64> = sandwiches.iter().flat_map(|s| s.victim().iter().map(|v| *v.id())).collect();
+    let mut volumes: BTreeMap<u64, BlockVolume> = BTreeMap::new();
+    for swap in swaps.iter() {
+        let Some(lamports) = wsol_lamports(swap, &wsol) else {
+            continue;
+        };
+        let volume = volumes.entry(*swap.slot()).or_insert_with(|| BlockVolume { slot: *swap.slot(), ..Default::default() });
+        volume.swap_volume_lamports += lamports;
+        if victims.contains(swap.id()) {
+            volume.sandwiched_volume_lamports += lamports;
+        }
+    }
+    volumes.into_values().collect()
+}
+
+#[derive(Clone, Debug, Getters)]
+pub struct GroupDetections {
+    sandwiches: Arc<[SandwichCandidate]>,
+    backruns: Arc<[BackrunCandidate]>,
+    washes: Arc<[WashCandidate]>,
+    anomalies: Arc<[PriceAnomaly]>,
+    block_volumes: Arc<[BlockVolume]>,
+    rejections: RejectionStats,
+    /// Swaps left out for missing a leg, see [`SwapCompleteness`](crate::events::swap::SwapCompleteness)
+    incomplete: usize,
+    /// Swaps left out for being under [`MinNotional`]
+    below_min_notional: usize,
+}
+
+impl GroupDetections {
+    /// Drops the detections with an event in a slot `keep` rejects
+    pub fn retain_slots(&self, keep: impl Fn(u64) -> bool) -> Self {
+        Self {
+            sandwiches: self.sandwiches.iter().filter(|s| {
+                s.frontrun().iter().chain(s.victim().iter()).chain(s.backrun().iter()).chain(s.suspected_wash().iter()).chain(s.conversions().iter()).all(|sw| keep(*sw.slot()))
+                    && s.transfers().iter().all(|t| keep(*t.slot()))
+            }).cloned().collect(),
+            backruns: self.backruns.iter().filter(|b| keep(*b.victim().slot()) && keep(*b.backrun().slot())).cloned().collect(),
+            washes: self.washes.iter().filter(|w| w.swaps().iter().all(|sw| keep(*sw.slot()))).cloned().collect(),
+            anomalies: self.anomalies.iter().filter(|a| keep(a.slot())).cloned().collect(),
+            block_volumes: self.block_volumes.iter().filter(|v| keep(v.slot)).cloned().collect(),
+            rejections: self.rejections.clone(),
+            incomplete: self.incomplete,
+            below_min_notional: self.below_min_notional,
+        }
+    }
+}
+
+/// Dust swaps, mostly on bonding curves, that are indexed like any other but left out of detection.
+/// Swaps are valued on their WSOL leg, those without one are never left out.
+#[derive(Clone, Debug, Default)]
+pub struct MinNotional {
+    /// Lamports, applies to every program without an override
+    pub default_lamports: u64,
+    pub per_program_lamports: HashMap<Arc<str>, u64>,
+}
+
+impl MinNotional {
+    /// Reads `MIN_NOTIONAL_LAMPORTS` and `MIN_NOTIONAL_PROGRAMS` (`program:lamports,program:lamports`), both default to no minimum
+    pub fn from_env() -> Self {
+        Self::from_env_with_prefix("")
+    }
+
+    /// Like [`MinNotional::from_env`], preferring the variables with `prefix` prepended
+    pub fn from_env_with_prefix(prefix: &str) -> Self {
+        let default_lamports = prefixed_env_var(prefix, "MIN_NOTIONAL_LAMPORTS").and_then(|v| v.parse().ok()).unwrap_or(0);
+        let per_program_lamports = prefixed_env_var(prefix, "MIN_NOTIONAL_PROGRAMS").unwrap_or_default()
+            .split(',')
+            .filter_map(|entry| {
+                let (program, lamports) = entry.trim().split_once(':')?;
+                Some((program.into(), lamports.parse().ok()?))
+            })
+            .collect();
+        Self { default_lamports, per_program_lamports }
+    }
+
+    pub fn lamports(&self, program: &str) -> u64 {
+        self.per_program_lamports.get(program).copied().unwrap_or(self.default_lamports)
+    }
+
+    /// `swaps` without the dust
+    pub fn retain<'a>(&self, swaps: &'a [SwapV2]) -> Cow<'a, [SwapV2]> {
+        if self.default_lamports == 0 && self.per_program_lamports.is_empty() {
+            return Cow::Borrowed(swaps);
         }
-        if let Some(output_inner_ix) = ele.output_inner_ix_index() {
-            transfer_map.remove(&Timestamp::new(*ele.slot(), *ele.inclusion_order(), *ele.ix_index(), Some(*output_inner_ix)));
+        let wsol = WSOL_MINT.to_string();
+        swaps.iter().filter(|swap| wsol_lamports(swap, &wsol).is_none_or(|lamports| lamports >= self.lamports(swap.program()))).cloned().collect()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DetectorConfig {
+    pub sandwich: SandwichConfig,
+    pub backrun: BackrunConfig,
+    pub wash: WashConfig,
+    pub anomaly: AnomalyConfig,
+    pub min_notional: MinNotional,
+}
+
+impl DetectorConfig {
+    pub fn from_env() -> Self {
+        Self::from_env_with_prefix("")
+    }
+
+    /// Each detector's config with the variables prefixed by `prefix` taking precedence
+    pub fn from_env_with_prefix(prefix: &str) -> Self {
+        Self {
+            sandwich: SandwichConfig::from_env_with_prefix(prefix),
+            backrun: BackrunConfig::from_env_with_prefix(prefix),
+            wash: WashConfig::from_env_with_prefix(prefix),
+            anomaly: AnomalyConfig::from_env_with_prefix(prefix),
+            min_notional: MinNotional::from_env_with_prefix(prefix),
         }
     }
-    let transfers: Vec<_> = transfer_map.into_iter().map(|(_k, v)| v).collect();
+}
+
+/// Runs the sandwich, backrun, wash trading and price anomaly detectors over a single group
+/// Incomplete and dust swaps are left out of detection but still count towards the block volumes
+pub fn detect_group(group: &EventGroup, config: &DetectorConfig) -> GroupDetections {
+    let complete: Cow<[SwapV2]> = if group.swaps.iter().all(SwapV2::is_complete) {
+        Cow::Borrowed(group.swaps)
+    } else {
+        group.swaps.iter().filter(|swap| swap.is_complete()).cloned().collect()
+    };
+    let swaps = config.min_notional.retain(&complete);
+    let (sandwiches, rejections) = detect(&swaps, group.transfers, group.txs, &config.sandwich);
+    let anomalies = detect_anomalies(&swaps, &sandwiches, &config.anomaly);
+    GroupDetections {
+        block_volumes: block_volumes(group.swaps, &sandwiches),
+        sandwiches,
+        backruns: detect_backruns(&swaps, &config.backrun),
+        washes: detect_washes(&swaps, &config.wash),
+        anomalies,
+        rejections,
+        incomplete: group.swaps.len() - complete.len(),
+        below_min_notional: complete.len() - swaps.len(),
+    }
+}
 
-    // Filter out transfers from AMMs (gets rid of some noise from fees)
-    let amms = swaps.iter().map(|s| s.amm()).collect::<HashSet<_>>();
-    let mut transfers: Vec<TransferV2> = transfers.into_iter().filter(|t| !amms.contains(t.input_ata()) && !amms.contains(t.output_ata()) && !amms.contains(t.authority())).collect();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::SwapBuilder;
 
-    // Sort events in chronological order
-    swaps.sort_by_cached_key(|s| *s.timestamp());
-    transfers.sort_by_cached_key(|t| *t.timestamp());
-    txs.sort_by_cached_key(|t| Timestamp::new(*t.slot(), *t.inclusion_order(), 0, None));
+    fn swap(slot: u64, inclusion_order: u32) -> SwapV2 {
+        SwapBuilder::new().authority("authority").at(slot, inclusion_order).build()
+    }
 
-    (swaps, transfers, txs)
+    fn tx(slot: u64, inclusion_order: u32) -> TransactionV2 {
+        TransactionV2::new(slot, inclusion_order, "sig".into(), 5000, 0, false)
+    }
+
+    #[test]
+    fn test_load_events_columns() {
+        // a row with just the columns LOAD_EVENTS selects, named the way the server names them
+        let select = LOAD_EVENTS.trim_start_matches("select ").split(" from ").next().unwrap();
+        let names: Vec<&str> = select.split(", ").map(|c| c.rsplit([' ', '.']).next().unwrap()).collect();
+        let columns: Arc<[mysql::Column]> = names.iter().map(|name| mysql::Column::new(mysql::consts::ColumnType::MYSQL_TYPE_NULL).with_name(name.as_bytes())).collect();
+        let values = names.iter().map(|name| match *name {
+            "event_type" => mysql::Value::from("SWAP"),
+            "inner_ix_index" | "input_inner_ix_index" | "output_inner_ix_index" => mysql::Value::from(-1),
+            "outer_program" | "caller_program" => mysql::Value::NULL,
+            "min_out" => mysql::Value::from(90u64),
+            "max_in" => mysql::Value::from(110u64),
+            "input_reserve" => mysql::Value::from(1000u64),
+            "output_reserve" => mysql::Value::from(2000u64),
+            "id" | "slot" | "inclusion_order" | "ix_index" | "input_amount" | "output_amount" => mysql::Value::from(1u64),
+            name => mysql::Value::from(name),
+        }).collect();
+        let row = mysql_common::row::new_row(values, columns);
+        let Some(Event::Swap(swap)) = event_from_row(&row) else {
+            panic!("not a swap");
+        };
+        assert_eq!(*swap.quote_limits(), QuoteLimits::new(Some(90), Some(110)));
+        assert_eq!(*swap.pool_reserves(), PoolReserves::new(Some(1000), Some(2000)));
+    }
+
+    #[test]
+    fn test_legacy_swaps() {
+        // two hops in one ix, stored without inner ix indexes and loaded out of order
+        let hop = |input_ata: &str, output_ata: &str, id: u64| SwapBuilder::new().authority("authority").atas(input_ata, output_ata).id(id).build();
+        let transfer = |input_ata: &str, output_ata: &str, inner_ix_index: u32| TransferV2::new(None, "program".into(), "authority".into(), "in".into(), 1, input_ata.into(), output_ata.into(), 1, 0, 0, Some(inner_ix_index), inner_ix_index as u64 + 10);
+        let events = LoadedEvents::new(
+            vec![hop("mid_ata", "out_ata", 2), hop("in_ata", "mid_ata", 1)],
+            vec![transfer("in_ata", "vault_a", 1), transfer("vault_b", "mid_ata", 2), transfer("tip_ata", "tip_vault", 5)],
+            vec![],
+        );
+        assert_eq!(events.swaps().iter().map(|s| *s.id()).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(events.transfers().iter().map(|t| *t.inner_ix_index()).collect::<Vec<_>>(), vec![Some(5)]);
+    }
+
+    #[test]
+    fn test_group_iterator() {
+        let events = LoadedEvents::new(
+            vec![swap(13, 0), swap(8, 1), swap(8, 0), swap(11, 2), swap(16, 0)],
+            vec![],
+            vec![tx(8, 0), tx(8, 1), tx(11, 2), tx(13, 0), tx(16, 0)],
+        );
+        let config = GroupConfig { size: 4, by_leader: false };
+        let leaders = LeaderSchedule::default();
+        assert_eq!(config.align(9, 13), (8, 15));
+        let groups: Vec<_> = events.groups(config.bounds(8, 15, &leaders)).map(|g| (*g.start_slot(), *g.end_slot(), g.swaps().iter().map(|s| (*s.slot(), *s.inclusion_order())).collect::<Vec<_>>(), g.txs().len())).collect();
+        assert_eq!(groups, vec![
+            (8, 11, vec![(8, 0), (8, 1), (11, 2)], 3),
+            (12, 15, vec![(13, 0)], 1),
+        ]);
+        let groups: Vec<_> = events.groups(config.bounds(9, 13, &leaders)).map(|g| (*g.start_slot(), *g.end_slot(), g.swaps().len())).collect();
+        assert_eq!(groups, vec![(9, 11, 1), (12, 13, 1)]);
+    }
+
+    #[test]
+    fn test_leader_bounds() {
+        // leader 1 has two consecutive windows, 16..19 isn't in the schedule
+        let leaders = LeaderSchedule::new((0..8).map(|s| (s, 1)).chain((8..12).map(|s| (s, 2))).chain((12..16).map(|s| (s, 3))).chain((20..24).map(|s| (s, 1))));
+        let config = GroupConfig { size: 4, by_leader: true };
+        assert_eq!(config.bounds(2, 22, &leaders), vec![(2, 7), (8, 11), (12, 15), (16, 19), (20, 22)]);
+        assert_eq!(config.bounds(2, 22, &LeaderSchedule::default()), vec![(2, 3), (4, 7), (8, 11), (12, 15), (16, 19), (20, 22)]);
+        assert_eq!(config.group_ending_at(3, &leaders), None);
+        assert_eq!(config.group_ending_at(7, &leaders), Some((0, 7)));
+        assert_eq!(config.group_ending_at(15, &leaders), Some((12, 15)));
+        assert_eq!(config.group_ending_at(19, &leaders), Some((16, 19)));
+        assert_eq!(GroupConfig { by_leader: false, ..config }.group_ending_at(7, &leaders), Some((4, 7)));
+    }
+
+    #[test]
+    fn test_follow_range() {
+        let config = GroupConfig { size: 4, by_leader: false };
+        // 8..11 isn't whole yet
+        assert_eq!(config.follow_range(8, 10, 100), None);
+        assert_eq!(config.follow_range(8, 11, 100), Some((8, 11)));
+        assert_eq!(config.follow_range(8, 30, 100), Some((8, 27)));
+        assert_eq!(config.follow_range(8, 30, 10), Some((8, 15)));
+        // a group at the least
+        assert_eq!(config.follow_range(8, 30, 1), Some((8, 11)));
+    }
+
+    #[test]
+    fn test_block_volumes() {
+        let wsol = WSOL_MINT.to_string();
+        let sol_swap = |slot: u64, inclusion_order: u32, outer_program: Option<&str>, buy: bool, input_amount: u64, output_amount: u64| {
+            let swap = SwapBuilder::new().outer_program(outer_program).authority(&format!("wallet{slot}{inclusion_order}")).at(slot, inclusion_order).amounts(input_amount, output_amount);
+            if buy { swap.buy(&wsol) } else { swap.sell(&wsol) }.build()
+        };
+        let bot = Some("11111111111111111111111111111111");
+        let swaps = vec![
+            sol_swap(1, 0, bot, true, 100, 100),
+            sol_swap(1, 1, None, true, 200, 180),
+            sol_swap(1, 2, bot, false, 100, 110),
+            sol_swap(2, 0, None, false, 50, 40),
+            // no SOL leg
+            swap(2, 1),
+        ];
+        let (sandwiches, _) = detect(&swaps, &[], &[], &SandwichConfig::default());
+        assert_eq!(sandwiches.len(), 1);
+        let volumes = block_volumes(&swaps, &sandwiches);
+        assert_eq!(volumes.iter().map(|v| (v.slot, v.swap_volume_lamports, v.sandwiched_volume_lamports, v.sandwiched_bps())).collect::<Vec<_>>(), vec![(1, 410, 200, 4878), (2, 40, 0, 0)]);
+    }
+
+    #[test]
+    fn test_min_notional() {
+        let wsol = WSOL_MINT.to_string();
+        let sol_swap = |program: &str, input_amount: u64, id: u64| {
+            SwapBuilder::new().program(program).buy(&wsol).amounts(input_amount, 1).at(1, id as u32).id(id).build()
+        };
+        let swaps = vec![sol_swap("curve", 999, 0), sol_swap("curve", 1000, 1), sol_swap("amm", 10, 2), swap(1, 3)];
+        assert_eq!(MinNotional::default().retain(&swaps).len(), 4);
+        let min_notional = MinNotional {
+            default_lamports: 5,
+            per_program_lamports: [("curve".into(), 1000)].into(),
+        };
+        // the swap without a SOL leg is kept
+        assert_eq!(min_notional.retain(&swaps).iter().map(|s| *s.id()).collect::<Vec<_>>(), vec![1, 2, 1003]);
+    }
+
+    #[test]
+    fn test_incomplete_swaps_skipped() {
+        let missing_output = SwapBuilder::new().authority("authority").mints("in", "").amounts(1, 0).at(8, 2).build();
+        assert!(!missing_output.is_complete());
+        let events = LoadedEvents::new(vec![swap(8, 0), swap(8, 1), missing_output], vec![], vec![tx(8, 0), tx(8, 1), tx(8, 2)]);
+        let config = GroupConfig { size: 4, by_leader: false };
+        let group = events.groups(config.bounds(8, 11, &LeaderSchedule::default())).next().unwrap();
+        let detections = detect_group(&group, &DetectorConfig::default());
+        assert_eq!(*detections.incomplete(), 1);
+        assert_eq!(*detections.below_min_notional(), 0);
+    }
 }
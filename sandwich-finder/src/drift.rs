@@ -0,0 +1,126 @@
+use std::{env, time::Duration};
+
+use mysql::{prelude::Queryable as _, Pool};
+
+use crate::{detector::{detect_group, DetectorConfig, EventLoader, GroupConfig, LeaderSchedule}, events::common::Inserter, metrics};
+
+#[derive(Clone, Debug)]
+pub struct DriftConfig {
+    /// Time between checks
+    pub period: Duration,
+    /// Drift in slots past which an alarm is raised
+    pub alarm_slots: u64,
+    /// Whether to run the group detector over the slots the sandwiches are missing for once the alarm is raised
+    pub catchup: bool,
+    /// Most slots caught up on per check, the oldest first
+    pub max_catchup_slots: u64,
+}
+
+impl Default for DriftConfig {
+    fn default() -> Self {
+        Self {
+            period: Duration::from_secs(60),
+            alarm_slots: 150,
+            catchup: false,
+            max_catchup_slots: 1000,
+        }
+    }
+}
+
+impl DriftConfig {
+    /// Reads `DRIFT_PERIOD_SECS`, `DRIFT_ALARM_SLOTS`, `DRIFT_CATCHUP` and `DRIFT_MAX_CATCHUP_SLOTS`, falling back to the defaults
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let var = |name: &str, default: u64| env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self {
+            period: Duration::from_secs(var("DRIFT_PERIOD_SECS", default.period.as_secs()).max(1)),
+            alarm_slots: var("DRIFT_ALARM_SLOTS", default.alarm_slots),
+            catchup: env::var("DRIFT_CATCHUP").is_ok_and(|v| v == "1" || v == "true"),
+            max_catchup_slots: var("DRIFT_MAX_CATCHUP_SLOTS", default.max_catchup_slots).max(1),
+        }
+    }
+}
+
+/// Whole groups to run the detector over, given the latest slot with events, the latest slot with sandwiches and
+/// the last slot already caught up on. The group still being filled in behind the events is left to the live detector.
+fn catchup_range(events_slot: u64, sandwiches_slot: u64, caught_up_to: u64, group_size: u64, max_slots: u64) -> Option<(u64, u64)> {
+    let group_end = |slot: u64| ((slot + 1) / group_size * group_size).checked_sub(1);
+    let start = (sandwiches_slot.max(caught_up_to) + 1) / group_size * group_size;
+    let end = group_end(events_slot.checked_sub(group_size)?)?;
+    let end = group_end(end.min(start + max_slots.max(group_size) - 1))?;
+    (start <= end).then_some((start, end))
+}
+
+/// Periodically compares the latest slot in the events table with the latest one with a sandwich in `sandwich_spans`,
+/// exporting the difference as `detector_slot_drift`. A sandwich table falling behind means the live detector
+/// can't keep up with the indexer or has stopped, in which case the missing range can be detected from here.
+pub fn start_drift_monitor(pool: Pool, group_config: GroupConfig, detector_config: DetectorConfig, config: DriftConfig) {
+    tokio::spawn(async move {
+        let loader = EventLoader::new(pool.clone());
+        let mut inserter = Inserter::new(pool.clone());
+        let mut interval = tokio::time::interval(config.period);
+        let mut caught_up_to = 0;
+        loop {
+            interval.tick().await;
+            let mut conn = match pool.get_conn() {
+                Ok(conn) => conn,
+                Err(e) => {
+                    eprintln!("Drift monitor unable to connect: {}", e);
+                    continue;
+                }
+            };
+            let events_slot: Option<u64> = conn.query_first("select max(slot) from events_with_id").ok().flatten().flatten();
+            let sandwiches_slot: Option<u64> = conn.query_first("select max(slot) from sandwich_spans").ok().flatten().flatten();
+            let (Some(events_slot), Some(sandwiches_slot)) = (events_slot, sandwiches_slot) else {
+                continue;
+            };
+            let drift = events_slot.saturating_sub(sandwiches_slot);
+            metrics::set("detector_slot_drift", drift);
+            if drift <= config.alarm_slots {
+                continue;
+            }
+            println!("slot drift alarm: events at slot {}, sandwiches at slot {} ({} slots behind)", events_slot, sandwiches_slot, drift);
+            metrics::incr("detector_drift_alarms");
+            if !config.catchup {
+                continue;
+            }
+            let Some((start_slot, end_slot)) = catchup_range(events_slot, sandwiches_slot, caught_up_to, group_config.size, config.max_catchup_slots) else {
+                continue;
+            };
+            println!("Catching up on slots {} - {}", start_slot, end_slot);
+            let leaders = if group_config.by_leader {
+                loader.load_leaders(start_slot, end_slot).await
+            } else {
+                LeaderSchedule::default()
+            };
+            let events = loader.load(start_slot, end_slot).await;
+            for group in events.groups(group_config.bounds(start_slot, end_slot, &leaders)) {
+                let detections = detect_group(&group, &detector_config);
+                inserter.insert_sandwiches(*group.start_slot(), detections.sandwiches().clone()).await;
+                inserter.insert_backruns(detections.backruns().clone()).await;
+                inserter.insert_washes(detections.washes().clone()).await;
+                inserter.insert_anomalies(detections.anomalies().clone()).await;
+                inserter.insert_block_volumes(detections.block_volumes().clone()).await;
+            }
+            // a range without any sandwiches wouldn't move the sandwiches table along
+            caught_up_to = end_slot;
+            metrics::add("detector_catchup_slots", end_slot - start_slot + 1);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catchup_range() {
+        assert_eq!(catchup_range(1000, 801, 0, 4, 1000), Some((800, 995)));
+        assert_eq!(catchup_range(1000, 801, 0, 4, 102), Some((800, 899)));
+        assert_eq!(catchup_range(1000, 801, 0, 4, 1), Some((800, 803)));
+        // already caught up past the last sandwich
+        assert_eq!(catchup_range(1000, 801, 899, 4, 1000), Some((900, 995)));
+        assert_eq!(catchup_range(1000, 801, 995, 4, 1000), None);
+        assert_eq!(catchup_range(6, 0, 0, 4, 1000), None);
+    }
+}
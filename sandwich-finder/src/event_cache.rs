@@ -0,0 +1,327 @@
+//! Optional on-disk cache of loaded events for the offline detector.
+//!
+//! Each group's events are stored as a zstd-compressed json file named after the group's slot bounds, so repeat runs
+//! over overlapping slot ranges read them from disk instead of the database.
+//! Entries are never invalidated, clear the directory after re-indexing a range.
+
+use std::{env, fs, io, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{detector::{EventGroup, LoadedEvents}, events::{pool::PoolCreated, swap::{PoolReserves, QuoteLimits, SwapCompleteness, SwapV2}, transaction::TransactionV2, transfer::TransferV2}};
+
+// bump when the cached structs change so stale files are ignored
+const FORMAT_VERSION: u32 = 5;
+const COMPRESSION_LEVEL: i32 = 3;
+
+#[derive(Serialize, Deserialize)]
+pub struct CachedSwap {
+    id: u64,
+    outer_program: Option<String>,
+    caller_program: Option<String>,
+    program: String,
+    authority: String,
+    amm: String,
+    input_mint: String,
+    output_mint: String,
+    input_amount: u64,
+    output_amount: u64,
+    input_ata: String,
+    output_ata: String,
+    input_inner_ix_index: Option<u32>,
+    output_inner_ix_index: Option<u32>,
+    min_out: Option<u64>,
+    max_in: Option<u64>,
+    // defaulted for the WAL entries written before these were kept
+    #[serde(default)]
+    input_reserve: Option<u64>,
+    #[serde(default)]
+    output_reserve: Option<u64>,
+    #[serde(default)]
+    completeness: Option<SwapCompleteness>,
+    slot: u64,
+    inclusion_order: u32,
+    ix_index: u32,
+    inner_ix_index: Option<u32>,
+    block_time: Option<i64>,
+}
+
+impl From<&SwapV2> for CachedSwap {
+    fn from(swap: &SwapV2) -> Self {
+        Self {
+            id: *swap.id(),
+            outer_program: swap.outer_program().as_ref().map(|p| p.to_string()),
+            caller_program: swap.caller_program().as_ref().map(|p| p.to_string()),
+            program: swap.program().to_string(),
+            authority: swap.authority().to_string(),
+            amm: swap.amm().to_string(),
+            input_mint: swap.input_mint().to_string(),
+            output_mint: swap.output_mint().to_string(),
+            input_amount: *swap.input_amount(),
+            output_amount: *swap.output_amount(),
+            input_ata: swap.input_ata().to_string(),
+            output_ata: swap.output_ata().to_string(),
+            input_inner_ix_index: *swap.input_inner_ix_index(),
+            output_inner_ix_index: *swap.output_inner_ix_index(),
+            min_out: *swap.quote_limits().min_out(),
+            max_in: *swap.quote_limits().max_in(),
+            input_reserve: *swap.pool_reserves().input_reserve(),
+            output_reserve: *swap.pool_reserves().output_reserve(),
+            completeness: Some(*swap.completeness()),
+            slot: *swap.slot(),
+            inclusion_order: *swap.inclusion_order(),
+            ix_index: *swap.ix_index(),
+            inner_ix_index: *swap.inner_ix_index(),
+            block_time: swap.block_time().0,
+        }
+    }
+}
+
+impl From<CachedSwap> for SwapV2 {
+    fn from(s: CachedSwap) -> Self {
+        let mut swap = SwapV2::new(s.outer_program.map(Into::into), s.program.into(), s.authority.into(), s.amm.into(), s.input_mint.into(), s.output_mint.into(), s.input_amount, s.output_amount, s.input_ata.into(), s.output_ata.into(), s.input_inner_ix_index, s.output_inner_ix_index, s.slot, s.inclusion_order, s.ix_index, s.inner_ix_index, s.id);
+        swap.set_quote_limits(QuoteLimits::new(s.min_out, s.max_in));
+        swap.set_pool_reserves(PoolReserves::new(s.input_reserve, s.output_reserve));
+        if let Some(completeness) = s.completeness {
+            swap.set_completeness(completeness);
+        }
+        swap.set_caller_program(s.caller_program.map(Into::into));
+        swap.set_block_time(s.block_time);
+        swap
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CachedTransfer {
+    id: u64,
+    outer_program: Option<String>,
+    program: String,
+    authority: String,
+    mint: String,
+    amount: u64,
+    input_ata: String,
+    output_ata: String,
+    slot: u64,
+    inclusion_order: u32,
+    ix_index: u32,
+    inner_ix_index: Option<u32>,
+    block_time: Option<i64>,
+}
+
+impl From<&TransferV2> for CachedTransfer {
+    fn from(transfer: &TransferV2) -> Self {
+        Self {
+            id: *transfer.id(),
+            outer_program: transfer.outer_program().as_ref().map(|p| p.to_string()),
+            program: transfer.program().to_string(),
+            authority: transfer.authority().to_string(),
+            mint: transfer.mint().to_string(),
+            amount: *transfer.amount(),
+            input_ata: transfer.input_ata().to_string(),
+            output_ata: transfer.output_ata().to_string(),
+            slot: *transfer.slot(),
+            inclusion_order: *transfer.inclusion_order(),
+            ix_index: *transfer.ix_index(),
+            inner_ix_index: *transfer.inner_ix_index(),
+            block_time: transfer.block_time().0,
+        }
+    }
+}
+
+impl From<CachedTransfer> for TransferV2 {
+    fn from(t: CachedTransfer) -> Self {
+        let mut transfer = TransferV2::new(t.outer_program.map(Into::into), t.program.into(), t.authority.into(), t.mint.into(), t.amount, t.input_ata.into(), t.output_ata.into(), t.slot, t.inclusion_order, t.ix_index, t.inner_ix_index, t.id);
+        transfer.set_block_time(t.block_time);
+        transfer
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CachedTransaction {
+    slot: u64,
+    inclusion_order: u32,
+    sig: String,
+    fee: u64,
+    cu_actual: u64,
+    dont_front: bool,
+    block_time: Option<i64>,
+    block_tx_count: Option<u32>,
+    cu_limit: Option<u32>,
+    block_cu: Option<u64>,
+}
+
+impl From<&TransactionV2> for CachedTransaction {
+    fn from(tx: &TransactionV2) -> Self {
+        Self {
+            slot: *tx.slot(),
+            inclusion_order: *tx.inclusion_order(),
+            sig: tx.sig().to_string(),
+            fee: *tx.fee(),
+            cu_actual: *tx.cu_actual(),
+            dont_front: *tx.dont_front(),
+            block_time: tx.block_time().0,
+            block_tx_count: *tx.block_tx_count(),
+            cu_limit: *tx.cu_limit(),
+            block_cu: *tx.block_cu(),
+        }
+    }
+}
+
+impl From<CachedTransaction> for TransactionV2 {
+    fn from(t: CachedTransaction) -> Self {
+        let mut tx = TransactionV2::new(t.slot, t.inclusion_order, t.sig.into(), t.fee, t.cu_actual, t.dont_front);
+        tx.set_block_time(t.block_time);
+        tx.set_block_tx_count(t.block_tx_count);
+        tx.set_cu_limit(t.cu_limit);
+        tx.set_block_cu(t.block_cu);
+        tx
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CachedPoolCreated {
+    program: String,
+    amm: String,
+    mint: String,
+    slot: u64,
+    inclusion_order: u32,
+    ix_index: u32,
+    inner_ix_index: Option<u32>,
+    block_time: Option<i64>,
+}
+
+impl From<&PoolCreated> for CachedPoolCreated {
+    fn from(pool: &PoolCreated) -> Self {
+        Self {
+            program: pool.program().to_string(),
+            amm: pool.amm().to_string(),
+            mint: pool.mint().to_string(),
+            slot: *pool.slot(),
+            inclusion_order: *pool.inclusion_order(),
+            ix_index: *pool.ix_index(),
+            inner_ix_index: *pool.inner_ix_index(),
+            block_time: pool.block_time().0,
+        }
+    }
+}
+
+impl From<CachedPoolCreated> for PoolCreated {
+    fn from(p: CachedPoolCreated) -> Self {
+        let mut pool = PoolCreated::new(p.program.into(), p.amm.into(), p.mint.into(), p.slot, p.inclusion_order, p.ix_index, p.inner_ix_index);
+        pool.set_block_time(p.block_time);
+        pool
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedGroup {
+    swaps: Vec<CachedSwap>,
+    transfers: Vec<CachedTransfer>,
+    txs: Vec<CachedTransaction>,
+}
+
+#[derive(Clone, Debug)]
+pub struct EventCache {
+    dir: PathBuf,
+}
+
+impl EventCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Enabled by setting `EVENT_CACHE_DIR`
+    pub fn from_env() -> Option<Self> {
+        env::var("EVENT_CACHE_DIR").ok().filter(|d| !d.is_empty()).map(Self::new)
+    }
+
+    fn path(&self, (start_slot, end_slot): (u64, u64)) -> PathBuf {
+        self.dir.join(format!("{start_slot}-{end_slot}.v{FORMAT_VERSION}.json.zst"))
+    }
+
+    fn read_group(&self, bound: (u64, u64)) -> io::Result<CachedGroup> {
+        let compressed = fs::read(self.path(bound))?;
+        let json = zstd::decode_all(compressed.as_slice())?;
+        Ok(serde_json::from_slice(&json)?)
+    }
+
+    fn write_group(&self, group: &EventGroup) -> io::Result<()> {
+        let cached = CachedGroup {
+            swaps: group.swaps().iter().map(CachedSwap::from).collect(),
+            transfers: group.transfers().iter().map(CachedTransfer::from).collect(),
+            txs: group.txs().iter().map(CachedTransaction::from).collect(),
+        };
+        let compressed = zstd::encode_all(serde_json::to_vec(&cached)?.as_slice(), COMPRESSION_LEVEL)?;
+        // write then rename so a concurrent or interrupted run never sees half a file
+        let path = self.path((*group.start_slot(), *group.end_slot()));
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, compressed)?;
+        fs::rename(tmp, path)
+    }
+
+    /// Events of the groups in `bounds`, None unless every group is cached
+    pub fn load(&self, bounds: &[(u64, u64)]) -> Option<LoadedEvents> {
+        let mut swaps = vec![];
+        let mut transfers = vec![];
+        let mut txs = vec![];
+        for &bound in bounds {
+            let group = match self.read_group(bound) {
+                Ok(group) => group,
+                Err(e) => {
+                    if e.kind() != io::ErrorKind::NotFound {
+                        eprintln!("Failed to read cached events for slots {} to {}: {}", bound.0, bound.1, e);
+                    }
+                    return None;
+                },
+            };
+            swaps.extend(group.swaps.into_iter().map(SwapV2::from));
+            transfers.extend(group.transfers.into_iter().map(TransferV2::from));
+            txs.extend(group.txs.into_iter().map(TransactionV2::from));
+        }
+        Some(LoadedEvents::new(swaps, transfers, txs))
+    }
+
+    /// Stores each group in `bounds` in a file of its own
+    pub fn store(&self, events: &LoadedEvents, bounds: &[(u64, u64)]) {
+        if let Err(e) = fs::create_dir_all(&self.dir) {
+            eprintln!("Failed to create event cache dir {:?}: {}", self.dir, e);
+            return;
+        }
+        for group in events.groups(bounds.to_vec()) {
+            if let Err(e) = self.write_group(&group) {
+                eprintln!("Failed to cache events for slots {} to {}: {}", group.start_slot(), group.end_slot(), e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::SwapBuilder;
+
+    #[test]
+    fn test_round_trip() {
+        let dir = env::temp_dir().join(format!("event-cache-test-{}", std::process::id()));
+        let cache = EventCache::new(&dir);
+        let mut swap = SwapBuilder::new().authority("authority").amounts(1, 2).atas("in ata", "out ata").inner_ix_indexes(Some(0), Some(1)).at(10, 3).ix_index(1).id(7).build();
+        swap.set_quote_limits(QuoteLimits::exact_in(Some(2)));
+        swap.set_pool_reserves(PoolReserves::new(Some(1000), Some(2000)));
+        swap.set_block_time(Some(1_700_000_000));
+        let tx = TransactionV2::new(10, 3, "sig".into(), 5000, 100, true);
+        let events = LoadedEvents::new(vec![swap], vec![], vec![tx]);
+        assert!(cache.load(&[(8, 11)]).is_none());
+        cache.store(&events, &[(8, 11), (12, 15)]);
+        let loaded = cache.load(&[(8, 11), (12, 15)]).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(loaded.swaps().len(), 1);
+        let swap = &loaded.swaps()[0];
+        assert_eq!((*swap.id(), *swap.slot(), swap.amm().as_ref()), (7, 10, "amm"));
+        assert_eq!(*swap.quote_limits(), QuoteLimits::exact_in(Some(2)));
+        assert_eq!(*swap.pool_reserves(), PoolReserves::new(Some(1000), Some(2000)));
+        assert_eq!(*swap.completeness(), SwapCompleteness::Complete);
+        assert_eq!(swap.block_time().0, Some(1_700_000_000));
+        assert_eq!(loaded.txs().len(), 1);
+        assert!(*loaded.txs()[0].dont_front());
+    }
+}
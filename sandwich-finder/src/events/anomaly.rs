@@ -0,0 +1,173 @@
+use std::{collections::{BTreeMap, HashMap}, sync::Arc};
+
+use derive_getters::Getters;
+use uuid::Uuid;
+
+use crate::{events::{sandwich::SandwichCandidate, swap::SwapV2}, utils::prefixed_env_var};
+
+#[derive(Clone, Debug)]
+pub struct AnomalyConfig {
+    /// How far a swap's price has to get from the block's first swap on the pool
+    pub min_move_bps: u64,
+    /// How close a later swap's price has to come back for the move to count as reverted, wide enough to cover the spread
+    pub revert_bps: u64,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            min_move_bps: 100,
+            revert_bps: 50,
+        }
+    }
+}
+
+impl AnomalyConfig {
+    /// Reads `ANOMALY_MIN_MOVE_BPS` and `ANOMALY_REVERT_BPS`, falling back to the defaults
+    pub fn from_env() -> Self {
+        Self::from_env_with_prefix("")
+    }
+
+    /// Like [`AnomalyConfig::from_env`], preferring the variables with `prefix` prepended
+    pub fn from_env_with_prefix(prefix: &str) -> Self {
+        let default = Self::default();
+        let var = |name: &str, default: u64| prefixed_env_var(prefix, name).and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self {
+            min_move_bps: var("ANOMALY_MIN_MOVE_BPS", default.min_move_bps),
+            revert_bps: var("ANOMALY_REVERT_BPS", default.revert_bps),
+        }
+    }
+}
+
+/// A pool's price moving away and coming back within a block, which every sandwich does along with anything else
+/// pushing a pool around for a few txs. Prices are the swaps' execution prices in one canonical direction, from the
+/// first swap of the run to the one that brought the price back.
+#[derive(Clone, Debug, Getters)]
+pub struct PriceAnomaly {
+    swaps: Arc<[SwapV2]>,
+    /// Index of the swap furthest from the first one
+    peak: usize,
+    move_bps: u64,
+    /// The sandwich sharing a swap with the run, None for the runs the sandwich detector didn't explain
+    sandwich: Option<Uuid>,
+}
+
+impl PriceAnomaly {
+    pub fn uuid(&self) -> Uuid {
+        let name: Vec<u8> = self.swaps.iter().flat_map(|sw| sw.id().to_le_bytes()).collect();
+        Uuid::new_v5(&Uuid::NAMESPACE_DNS, &name)
+    }
+
+    pub fn amm(&self) -> &Arc<str> {
+        self.swaps[0].amm()
+    }
+
+    pub fn slot(&self) -> u64 {
+        *self.swaps[0].slot()
+    }
+}
+
+/// (mint B per mint A) as a fraction, A being the lesser mint so both directions are comparable
+fn price(swap: &SwapV2) -> (u128, u128) {
+    let (input, output) = (*swap.input_amount() as u128, *swap.output_amount() as u128);
+    if swap.input_mint() < swap.output_mint() {
+        (output, input)
+    } else {
+        (input, output)
+    }
+}
+
+/// Distance between two prices in bps of the first
+fn distance_bps((num0, den0): (u128, u128), (num1, den1): (u128, u128)) -> u64 {
+    let (a, b) = (num1.saturating_mul(den0), num0.saturating_mul(den1));
+    (a.abs_diff(b).saturating_mul(10000) / b.max(1)).min(u64::MAX as u128) as u64
+}
+
+/// Moves and reverts in a block's swaps on one pool, taking each run from where the last one ended
+fn find_runs(swaps: &[SwapV2], config: &AnomalyConfig) -> Vec<(usize, usize, usize, u64)> {
+    let mut runs = vec![];
+    let mut start = 0;
+    while start + 2 < swaps.len() {
+        let reference = price(&swaps[start]);
+        let mut peak = None;
+        let mut end = None;
+        for (i, swap) in swaps.iter().enumerate().skip(start + 1) {
+            let distance = distance_bps(reference, price(swap));
+            match peak {
+                Some((_, peak_bps)) if distance <= config.revert_bps => {
+                    end = Some((i, peak_bps));
+                    break;
+                },
+                Some((_, peak_bps)) if distance <= peak_bps => {},
+                _ if distance >= config.min_move_bps => peak = Some((i, distance)),
+                _ => {},
+            }
+        }
+        match (peak, end) {
+            (Some((peak, _)), Some((end, move_bps))) => {
+                runs.push((start, peak, end, move_bps));
+                start = end + 1;
+            },
+            _ => start += 1,
+        }
+    }
+    runs
+}
+
+/// This function expects the swaps to be sorted in chronological order
+pub fn detect_anomalies(swaps: &[SwapV2], sandwiches: &[SandwichCandidate], config: &AnomalyConfig) -> Arc<[PriceAnomaly]> {
+    let sandwiched: HashMap<u64, Uuid> = sandwiches.iter().flat_map(|s| {
+        let uuid = s.uuid();
+        s.frontrun().iter().chain(s.victim().iter()).chain(s.backrun().iter()).map(move |sw| (*sw.id(), uuid)).collect::<Vec<_>>()
+    }).collect();
+    let mut pool_swaps: BTreeMap<(u64, &str), Vec<SwapV2>> = BTreeMap::new();
+    for swap in swaps.iter().filter(|sw| *sw.input_amount() > 0 && *sw.output_amount() > 0) {
+        pool_swaps.entry((*swap.slot(), swap.amm())).or_default().push(swap.clone());
+    }
+    let mut anomalies = vec![];
+    for swaps in pool_swaps.values() {
+        for (start, peak, end, move_bps) in find_runs(swaps, config) {
+            let swaps = &swaps[start..=end];
+            anomalies.push(PriceAnomaly {
+                swaps: swaps.into(),
+                peak: peak - start,
+                move_bps,
+                sandwich: swaps.iter().find_map(|sw| sandwiched.get(sw.id()).copied()),
+            });
+        }
+    }
+    anomalies.sort_by_key(|a| *a.swaps[0].timestamp());
+    anomalies.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::SwapBuilder;
+
+    fn swap(inclusion_order: u32, buy: bool, input_amount: u64, output_amount: u64) -> SwapV2 {
+        let swap = SwapBuilder::new().amounts(input_amount, output_amount).at(1, inclusion_order);
+        if buy { swap.buy("sol") } else { swap.sell("sol") }.build()
+    }
+
+    #[test]
+    fn test_detect_anomalies() {
+        let config = AnomalyConfig::default();
+        let swaps = vec![
+            swap(0, true, 1000, 1000),
+            // 2% then 5% worse
+            swap(1, true, 1000, 980),
+            swap(2, true, 1000, 950),
+            // back to within 0.5%
+            swap(3, false, 1000, 996),
+            // moves without coming back
+            swap(4, true, 1000, 900),
+        ];
+        let anomalies = detect_anomalies(&swaps, &[], &config);
+        assert_eq!(anomalies.len(), 1);
+        let anomaly = &anomalies[0];
+        assert_eq!((anomaly.swaps().len(), *anomaly.peak(), *anomaly.move_bps(), *anomaly.sandwich()), (4, 2, 500, None));
+        // flat
+        assert!(detect_anomalies(&[swap(0, true, 1000, 1000), swap(1, true, 1000, 999), swap(2, false, 1000, 1000)], &[], &config).is_empty());
+    }
+}
@@ -0,0 +1,195 @@
+use std::{collections::HashMap, sync::Arc};
+
+use derive_getters::Getters;
+use uuid::Uuid;
+
+use crate::{events::{addresses::WSOL_MINT, common::Timestamp, swap::SwapV2}, utils::prefixed_env_var};
+
+#[derive(Clone, Debug)]
+pub struct BackrunConfig {
+    /// Max number of txs between the victim and the backrun
+    pub max_distance: u32,
+    /// Victim swaps smaller than this aren't worth backrunning
+    pub min_victim_lamports: u64,
+    /// Net SOL the backrun tx has to make to count
+    pub min_profit_lamports: u64,
+}
+
+impl Default for BackrunConfig {
+    fn default() -> Self {
+        Self {
+            max_distance: 3,
+            min_victim_lamports: 10_000_000_000,
+            min_profit_lamports: 1_000_000,
+        }
+    }
+}
+
+impl BackrunConfig {
+    /// Reads `BACKRUN_MAX_DISTANCE`, `BACKRUN_MIN_VICTIM_LAMPORTS` and `BACKRUN_MIN_PROFIT_LAMPORTS`, falling back to the defaults
+    pub fn from_env() -> Self {
+        Self::from_env_with_prefix("")
+    }
+
+    /// Like [`BackrunConfig::from_env`], preferring the variables with `prefix` prepended
+    pub fn from_env_with_prefix(prefix: &str) -> Self {
+        let default = Self::default();
+        let var = |name: &str, default: u64| prefixed_env_var(prefix, name).and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self {
+            max_distance: var("BACKRUN_MAX_DISTANCE", default.max_distance as u64) as u32,
+            min_victim_lamports: var("BACKRUN_MIN_VICTIM_LAMPORTS", default.min_victim_lamports),
+            min_profit_lamports: var("BACKRUN_MIN_PROFIT_LAMPORTS", default.min_profit_lamports),
+        }
+    }
+}
+
+/// A swap that trades against a large swap right after it without frontrunning it,
+/// i.e. the second half of a sandwich on its own. Only SOL pairs are considered so profits are comparable.
+#[derive(Clone, Debug, Getters)]
+pub struct BackrunCandidate {
+    victim: SwapV2,
+    backrun: SwapV2,
+    // Net SOL received by the backrunner across all its swaps in the backrun tx, less what it paid for the tokens the tx
+    // sold out of its inventory
+    profit_lamports: u64,
+}
+
+impl BackrunCandidate {
+    pub fn uuid(&self) -> Uuid {
+        let name = [self.victim.id().to_le_bytes(), self.backrun.id().to_le_bytes()].concat();
+        Uuid::new_v5(&Uuid::NAMESPACE_DNS, &name)
+    }
+}
+
+/// What `authority` paid for `amount` of `token`, at the price of its latest buy of it with SOL in `earlier`
+fn cost_of(earlier: &[SwapV2], authority: &str, token: &str, wsol: &str, amount: u128) -> Option<u128> {
+    let buy = earlier.iter().rev().find(|s| s.authority().as_ref() == authority && s.input_mint().as_ref() == wsol && s.output_mint().as_ref() == token && *s.output_amount() > 0)?;
+    Some(*buy.input_amount() as u128 * amount / *buy.output_amount() as u128)
+}
+
+/// This function expects the swaps to be sorted in chronological order
+pub fn detect_backruns(swaps: &[SwapV2], config: &BackrunConfig) -> Arc<[BackrunCandidate]> {
+    let wsol: Arc<str> = WSOL_MINT.to_string().into();
+    // swaps of each tx
+    let mut tx_swaps: HashMap<(u64, u32), Vec<&SwapV2>> = HashMap::new();
+    // earliest swap of each wallet on each AMM+direction, to rule out frontruns
+    let mut first_swap: HashMap<(u64, &str, &str, &str), Timestamp> = HashMap::new();
+    for swap in swaps.iter() {
+        tx_swaps.entry((*swap.slot(), *swap.inclusion_order())).or_default().push(swap);
+        first_swap.entry((*swap.slot(), swap.authority(), swap.amm(), swap.input_mint())).or_insert(*swap.timestamp());
+    }
+    let mut backruns = vec![];
+    for (i, victim) in swaps.iter().enumerate() {
+        let size = if victim.input_mint() == &wsol {
+            *victim.input_amount()
+        } else if victim.output_mint() == &wsol {
+            *victim.output_amount()
+        } else {
+            continue;
+        };
+        if size < config.min_victim_lamports {
+            continue;
+        }
+        let following = swaps[i + 1..].iter().take_while(|s| s.slot() == victim.slot() && *s.inclusion_order() <= victim.inclusion_order() + config.max_distance);
+        for backrun in following {
+            if backrun.inclusion_order() == victim.inclusion_order()
+                || backrun.authority() == victim.authority()
+                || backrun.amm() != victim.amm()
+                || backrun.input_mint() != victim.output_mint()
+                || backrun.output_mint() != victim.input_mint() {
+                continue;
+            }
+            // frontrunning the victim makes it a sandwich, which is covered by the sandwich detector
+            let frontran = first_swap.get(&(*victim.slot(), backrun.authority(), victim.amm(), victim.input_mint())).is_some_and(|t| t < victim.timestamp());
+            if frontran {
+                continue;
+            }
+            let backrun_swaps: Vec<_> = tx_swaps[&(*backrun.slot(), *backrun.inclusion_order())].iter().filter(|s| s.authority() == backrun.authority()).collect();
+            let proceeds = backrun_swaps.iter().map(|s| {
+                let received = if s.output_mint() == &wsol { *s.output_amount() as i128 } else { 0 };
+                let spent = if s.input_mint() == &wsol { *s.input_amount() as i128 } else { 0 };
+                received - spent
+            }).sum::<i128>();
+            // an atomic backrun buys back what it sells within the tx, otherwise the tokens came from an earlier buy
+            let token = if victim.input_mint() == &wsol { victim.output_mint() } else { victim.input_mint() };
+            let sold = backrun_swaps.iter().map(|s| {
+                let spent = if s.input_mint() == token { *s.input_amount() as i128 } else { 0 };
+                let received = if s.output_mint() == token { *s.output_amount() as i128 } else { 0 };
+                spent - received
+            }).sum::<i128>();
+            let cost = if sold > 0 {
+                match cost_of(&swaps[..i], backrun.authority(), token, &wsol, sold as u128) {
+                    Some(cost) => cost as i128,
+                    // no telling whether selling them made anything
+                    None => continue,
+                }
+            } else {
+                0
+            };
+            let profit = proceeds - cost;
+            if profit > config.min_profit_lamports as i128 {
+                backruns.push(BackrunCandidate {
+                    victim: victim.clone(),
+                    backrun: backrun.clone(),
+                    profit_lamports: profit as u64,
+                });
+                break;
+            }
+        }
+    }
+    backruns.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::SwapBuilder;
+
+    const TOKEN: &str = "token";
+
+    fn swap(authority: &str, slot: u64, inclusion_order: u32, input_mint: &str, output_mint: &str, input_amount: u64, output_amount: u64) -> SwapV2 {
+        SwapBuilder::new().authority(authority).mints(input_mint, output_mint).amounts(input_amount, output_amount).at(slot, inclusion_order).build()
+    }
+
+    fn backruns(swaps: &[SwapV2]) -> Vec<u64> {
+        detect_backruns(swaps, &BackrunConfig::default()).iter().map(|b| b.profit_lamports).collect()
+    }
+
+    #[test]
+    fn test_atomic_backrun() {
+        let wsol = WSOL_MINT.to_string();
+        let swaps = [
+            swap("victim", 10, 0, &wsol, TOKEN, 20_000_000_000, 1000),
+            // buys the tokens elsewhere and sells them into the pool the victim moved, in one tx
+            SwapBuilder::new().authority("bot").amm("other_amm").mints(&wsol, TOKEN).amounts(5_000_000_000, 100).at(10, 1).id(10_002).build(),
+            swap("bot", 10, 1, TOKEN, &wsol, 100, 6_000_000_000),
+        ];
+        assert_eq!(backruns(&swaps), vec![1_000_000_000]);
+    }
+
+    #[test]
+    fn test_non_atomic_backrun() {
+        let wsol = WSOL_MINT.to_string();
+        let swaps = [
+            // bought a slot earlier, so it isn't a frontrun
+            swap("bot", 9, 0, &wsol, TOKEN, 5_000_000_000, 100),
+            swap("victim", 10, 0, &wsol, TOKEN, 20_000_000_000, 1000),
+            swap("bot", 10, 1, TOKEN, &wsol, 100, 6_000_000_000),
+        ];
+        assert_eq!(backruns(&swaps), vec![1_000_000_000]);
+    }
+
+    #[test]
+    fn test_unprofitable_backrun() {
+        let wsol = WSOL_MINT.to_string();
+        // the proceeds barely cover what the tokens cost
+        let swaps = [
+            swap("bot", 9, 0, &wsol, TOKEN, 6_000_000_000, 100),
+            swap("victim", 10, 0, &wsol, TOKEN, 20_000_000_000, 1000),
+            swap("bot", 10, 1, TOKEN, &wsol, 100, 6_000_500_000),
+        ];
+        assert!(backruns(&swaps).is_empty());
+        // nor without knowing what they cost
+        assert!(backruns(&swaps[1..]).is_empty());
+    }
+}
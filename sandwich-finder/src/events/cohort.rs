@@ -0,0 +1,80 @@
+use std::env;
+
+use crate::detector::SLOTS_PER_HOUR;
+
+#[derive(Clone, Debug)]
+pub struct CohortConfig {
+    /// Wallets first seen this recently before the victim swap are new
+    pub new_wallet_slots: u64,
+    /// Swaps over `bot_window_slots` before the victim swap that make a wallet a bot
+    pub bot_swaps: u64,
+    pub bot_window_slots: u64,
+}
+
+impl Default for CohortConfig {
+    fn default() -> Self {
+        Self {
+            new_wallet_slots: 24 * SLOTS_PER_HOUR,
+            bot_swaps: 30,
+            bot_window_slots: SLOTS_PER_HOUR,
+        }
+    }
+}
+
+impl CohortConfig {
+    /// Reads `COHORT_NEW_WALLET_SLOTS`, `COHORT_BOT_SWAPS` and `COHORT_BOT_WINDOW_SLOTS`, falling back to the defaults
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let var = |name: &str, default: u64| env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self {
+            new_wallet_slots: var("COHORT_NEW_WALLET_SLOTS", default.new_wallet_slots),
+            bot_swaps: var("COHORT_BOT_SWAPS", default.bot_swaps),
+            bot_window_slots: var("COHORT_BOT_WINDOW_SLOTS", default.bot_window_slots),
+        }
+    }
+}
+
+/// What kind of wallet a victim is, judging by the swaps indexed for it before it got sandwiched.
+/// Only as far back as the events are kept, a wallet older than the retention looks new when it's first seen after it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VictimCohort {
+    New,
+    Retail,
+    Bot,
+}
+
+impl VictimCohort {
+    /// `first_seen` is the wallet's earliest indexed swap and `recent_swaps` its swaps over the bot window, both before `slot`.
+    /// Trading like a bot takes precedence over being new.
+    pub fn classify(slot: u64, first_seen: Option<u64>, recent_swaps: u64, config: &CohortConfig) -> Self {
+        if recent_swaps >= config.bot_swaps {
+            Self::Bot
+        } else if first_seen.is_none_or(|first_seen| slot.saturating_sub(first_seen) < config.new_wallet_slots) {
+            Self::New
+        } else {
+            Self::Retail
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::New => "NEW",
+            Self::Retail => "RETAIL",
+            Self::Bot => "BOT",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        let config = CohortConfig { new_wallet_slots: 100, bot_swaps: 10, bot_window_slots: 50 };
+        assert_eq!(VictimCohort::classify(1000, None, 0, &config), VictimCohort::New);
+        assert_eq!(VictimCohort::classify(1000, Some(950), 2, &config), VictimCohort::New);
+        assert_eq!(VictimCohort::classify(1000, Some(900), 2, &config), VictimCohort::Retail);
+        assert_eq!(VictimCohort::classify(1000, Some(990), 10, &config), VictimCohort::Bot);
+    }
+}
@@ -1,72 +1,119 @@
-use std::{collections::HashSet, sync::Arc};
+use std::{collections::{HashMap, HashSet}, sync::Arc};
 
 use dashmap::DashMap;
-use derive_getters::Getters;
-use mysql::{prelude::Queryable as _, Pool, Row, TxOpts, Value};
-use serde::Serialize;
-use uuid::Uuid;
-
-use crate::{detector::LEADER_GROUP_SIZE, events::{event::Event, sandwich::SandwichCandidate}};
-
-#[derive(Debug, Clone, Copy, Getters, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
-pub struct Timestamp {
-    slot: u64,
-    inclusion_order: u32,
-    ix_index: u32,
-    inner_ix_index: Option<u32>,
+use mysql::{prelude::Queryable as _, Pool, PooledConn, Row, Value};
+use tokio::{join, task::JoinHandle};
+
+use crate::{bundles, detector::{BlockVolume, ROLLUP_BUCKET_SLOTS}, events::{addresses::WSOL_MINT, anomaly::PriceAnomaly, backrun::BackrunCandidate, cohort::{CohortConfig, VictimCohort}, event::Event, sandwich::SandwichCandidate, snipe::Snipe, stable::StableMints, wash::WashCandidate}, metrics, prices::PriceOracle};
+
+pub use sandwich_finder_core::common::{BlockTime, Timestamp};
+
+/// A row of `sandwich_rollup`, `sandwich_attacker_rollup` or `sandwich_pool_rollup`
+#[derive(Default)]
+struct RollupCounts {
+    sandwiches: u64,
+    victims: u64,
+    victim_loss_lamports: u64,
+    dont_front_victims: u64,
 }
 
-impl Timestamp {
-    pub fn new(slot: u64, inclusion_order: u32, ix_index: u32, inner_ix_index: Option<u32>) -> Self {
-        Self {
-            slot,
-            inclusion_order,
-            ix_index,
-            inner_ix_index,
-        }
-    }
+/// A row of `sandwich_mint_rollup`
+#[derive(Default)]
+struct MintRollupCounts {
+    sandwiches: u64,
+    victims: u64,
+    victim_volume: u64,
+    victim_volume_lamports: u64,
+    victim_loss_lamports: u64,
+}
+
+/// A row of `sandwich_program_rollup`
+#[derive(Default)]
+struct ProgramRollupCounts {
+    sandwiches: u64,
+    victims: u64,
+    victim_volume_lamports: u64,
+    victim_loss_lamports: u64,
+}
+
+/// A row of `sandwich_stable_rollup`
+#[derive(Default)]
+struct StableRollupCounts {
+    sandwiches: u64,
+    victims: u64,
 }
 
 #[derive(Clone)]
 pub struct Inserter {
     pool: Pool,
     address_lookup_table: Arc<DashMap<Arc<str>, u32>>,
+    // Whether a multi-row insert is guaranteed to get consecutive ids, so they can be derived from last_insert_id
+    consecutive_ids: bool,
+    // For the SOL equivalents of swap amounts
+    prices: Arc<PriceOracle>,
+    cohorts: CohortConfig,
+    stable_mints: StableMints,
 }
 
 impl Inserter {
     pub fn new(pool: Pool) -> Self {
         let address_lookup_table = Arc::from(DashMap::new());
         address_lookup_table.insert(Arc::from(""), 0);
+        // Interleaved mode (2) may hand out gaps when other writers insert concurrently
+        let consecutive_ids = pool.get_conn().ok()
+            .and_then(|mut conn| conn.query_first::<(u8, u32), _>("select @@innodb_autoinc_lock_mode, @@auto_increment_increment").ok().flatten())
+            .is_some_and(|(mode, increment)| mode < 2 && increment == 1);
         Self {
             pool: pool.clone(),
             address_lookup_table,
+            consecutive_ids,
+            prices: Arc::new(PriceOracle::from_env()),
+            cohorts: CohortConfig::from_env(),
+            stable_mints: StableMints::from_env(),
         }
     }
 
     /// Also caches the corresponding ids in the address_lookup_table
+    /// Addresses that are already cached are skipped, and when a batch is inserted in full its ids are derived from
+    /// last_insert_id instead of selecting them back.
     fn insert_addresses(&mut self, addresses: Arc<[&str]>) {
-        if addresses.is_empty() {
+        let missing: Vec<&str> = addresses.iter().copied()
+            .filter(|&addr| !self.address_lookup_table.contains_key(addr))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        if missing.is_empty() {
             return;
         }
         let mut conn = self.pool.get_conn().unwrap();
-        for batch in addresses.chunks(1000) {
-            let args: Vec<_> = batch.iter().map(|&addr| vec![Value::from(addr)]).flatten().collect();
+        let mut unresolved = vec![];
+        for batch in missing.chunks(1000) {
+            let args: Vec<_> = batch.iter().map(|&addr| Value::from(addr)).collect();
             let stmt = format!("insert ignore into address_lookup_table (address) values {}", "(?),".repeat(batch.len()));
             let stmt = stmt.trim_end_matches(",").to_string();
             conn.exec_drop(stmt, args).unwrap();
+            if self.consecutive_ids && conn.affected_rows() == batch.len() as u64 {
+                let first_id = conn.last_insert_id() as u32;
+                for (i, &addr) in batch.iter().enumerate() {
+                    self.address_lookup_table.insert(addr.into(), first_id + i as u32);
+                }
+            } else {
+                unresolved.extend_from_slice(batch);
+            }
         }
-        self.retrieve_addresses(addresses);
+        self.retrieve_addresses(&mut conn, &unresolved);
     }
 
-    fn retrieve_addresses(&mut self, addresses: Arc<[&str]>) {
-        let mut conn = self.pool.get_conn().unwrap();
-        let args: Vec<_> = addresses.iter().map(|&addr| Value::from(addr)).collect();
-        let stmt = format!("select id, address from address_lookup_table where address in ({})", "?,".repeat(addresses.len()).trim_end_matches(","));
-        let res: Vec<Row> = conn.exec(stmt, args).unwrap();
-        for row in res {
-            let id: u32 = row.get("id").unwrap();
-            let address: Arc<str> = row.get("address").unwrap();
-            self.address_lookup_table.insert(address, id);
+    fn retrieve_addresses(&mut self, conn: &mut PooledConn, addresses: &[&str]) {
+        for batch in addresses.chunks(1000) {
+            let args: Vec<_> = batch.iter().map(|&addr| Value::from(addr)).collect();
+            let stmt = format!("select id, address from address_lookup_table where address in ({})", "?,".repeat(batch.len()).trim_end_matches(","));
+            let res: Vec<Row> = conn.exec(stmt, args).unwrap();
+            for row in res {
+                let id: u32 = row.get("id").unwrap();
+                let address: Arc<str> = row.get("address").unwrap();
+                self.address_lookup_table.insert(address, id);
+            }
         }
     }
 
@@ -83,25 +130,35 @@ impl Inserter {
 
     fn to_event_vec(&self, event: &Event) -> Vec<Value> {
         match event {
-            Event::Swap(swap) => vec![
-                Value::from("SWAP"),
-                Value::from(swap.slot()),
-                Value::from(swap.inclusion_order()),
-                Value::from(swap.ix_index()),
-                Value::from(swap.inner_ix_index()),
-                Value::from(self.get(swap.authority().clone(), 1)),
-                Value::from(self.get_by_option(swap.outer_program(), 2)),
-                Value::from(self.get(swap.program().clone(), 3)),
-                Value::from(self.get(swap.amm().clone(), 4)),
-                Value::from(self.get(swap.input_mint().clone(), 5)),
-                Value::from(self.get(swap.output_mint().clone(), 6)),
-                Value::from(swap.input_amount()),
-                Value::from(swap.output_amount()),
-                Value::from(self.get(swap.input_ata().clone(), 7)),
-                Value::from(self.get(swap.output_ata().clone(), 8)),
-                Value::from(swap.input_inner_ix_index()),
-                Value::from(swap.output_inner_ix_index()),
-            ],
+            Event::Swap(swap) => {
+                let (input_amount_sol, output_amount_sol) = self.prices.sol_amounts(swap);
+                vec![
+                    Value::from("SWAP"),
+                    Value::from(swap.slot()),
+                    Value::from(swap.inclusion_order()),
+                    Value::from(swap.ix_index()),
+                    Value::from(swap.inner_ix_index()),
+                    Value::from(self.get(swap.authority().clone(), 1)),
+                    Value::from(self.get_by_option(swap.outer_program(), 2)),
+                    Value::from(self.get(swap.program().clone(), 3)),
+                    Value::from(self.get(swap.amm().clone(), 4)),
+                    Value::from(self.get(swap.input_mint().clone(), 5)),
+                    Value::from(self.get(swap.output_mint().clone(), 6)),
+                    Value::from(swap.input_amount()),
+                    Value::from(swap.output_amount()),
+                    Value::from(self.get(swap.input_ata().clone(), 7)),
+                    Value::from(self.get(swap.output_ata().clone(), 8)),
+                    Value::from(swap.input_inner_ix_index()),
+                    Value::from(swap.output_inner_ix_index()),
+                    Value::from(swap.quote_limits().min_out()),
+                    Value::from(swap.quote_limits().max_in()),
+                    Value::from(self.get_by_option(swap.caller_program(), 15)),
+                    Value::from(swap.pool_reserves().input_reserve()),
+                    Value::from(swap.pool_reserves().output_reserve()),
+                    Value::from(input_amount_sol),
+                    Value::from(output_amount_sol),
+                ]
+            },
             Event::Transfer(transfer) => vec![
                 Value::from("TRANSFER"),
                 Value::from(transfer.slot()),
@@ -120,8 +177,15 @@ impl Inserter {
                 Value::from(self.get(transfer.output_ata().clone(), 14)),
                 Value::from(transfer.inner_ix_index()),
                 Value::from(transfer.inner_ix_index()),
+                Value::from(None::<u64>),
+                Value::from(None::<u64>),
+                Value::from(None::<u32>),
+                Value::from(None::<u64>),
+                Value::from(None::<u64>),
+                Value::from(None::<u64>),
+                Value::from(None::<u64>),
             ],
-            Event::Transaction(_) => vec![], // They belong to another table
+            Event::Transaction(_) | Event::PoolCreated(_) => vec![], // They belong to another table
         }
     }
 
@@ -134,43 +198,465 @@ impl Inserter {
                 Value::from(tx.fee()),
                 Value::from(tx.cu_actual()),
                 Value::from(tx.dont_front()),
+                Value::from(tx.block_time().0),
+                Value::from(tx.block_tx_count()),
+                Value::from(tx.cu_limit()),
+                Value::from(tx.block_cu()),
             ],
             _ => vec![], // They belong to another table
         }
     }
 
-    pub async fn insert_sandwiches(&mut self, slot: u64, sandwiches: Arc<[SandwichCandidate]>) {
+    fn to_pool_vec(&self, event: &Event) -> Vec<Value> {
+        match event {
+            Event::PoolCreated(pool) => vec![
+                Value::from(self.get(pool.amm().clone(), 30)),
+                Value::from(self.get(pool.program().clone(), 31)),
+                Value::from(self.get(pool.mint().clone(), 32)),
+                Value::from(pool.slot()),
+                Value::from(pool.inclusion_order()),
+                Value::from(pool.ix_index()),
+            ],
+            _ => vec![], // They belong to another table
+        }
+    }
+
+    /// Safe to call again for the same slots, sandwiches already stored are left alone and aren't counted in the rollups twice
+    /// False if the sandwiches couldn't be written
+    pub async fn insert_sandwiches(&mut self, slot: u64, sandwiches: Arc<[SandwichCandidate]>) -> bool {
         let mut conn = self.pool.get_conn().unwrap();
+        let uuids: Vec<_> = sandwiches.iter().map(|s| s.uuid().to_string()).collect();
+        let existing: HashSet<String> = if uuids.is_empty() {
+            HashSet::new()
+        } else {
+            conn.exec(format!("select distinct id from sandwiches where id in ({})", "?,".repeat(uuids.len()).trim_end_matches(",")), uuids).unwrap().into_iter().collect()
+        };
+        let new_sandwiches: Vec<_> = sandwiches.iter().filter(|s| !existing.contains(&s.uuid().to_string())).cloned().collect();
+        let cohorts = self.victim_cohorts(&mut conn, &sandwiches);
         let args: Vec<_> = sandwiches.iter().flat_map(|s| {
-            // deterministic id for each sandwich
-            let name: Vec<u8> = [
-                s.frontrun().iter().flat_map(|sw| sw.id().to_le_bytes()).collect::<Vec<_>>(),
-                s.backrun().iter().flat_map(|sw| sw.id().to_le_bytes()).collect::<Vec<_>>(),
-                s.victim().iter().flat_map(|sw| sw.id().to_le_bytes()).collect::<Vec<_>>(),
-                s.transfers().iter().flat_map(|sw| sw.id().to_le_bytes()).collect::<Vec<_>>(),
-            ].concat();
-            // println!("name {}", hex::encode(&name));
-            let uuid = &*Uuid::new_v5(&Uuid::NAMESPACE_DNS, &name).to_string();
+            let uuid = &*s.uuid().to_string();
+            let losses = s.estimate_victim_losses();
+            let losses_lamports = s.estimate_victim_losses_lamports();
+            let stable = self.stable_mints.is_stable_sandwich(s);
+            // only the attacker legs get their position in the block, and only the victims their price impact, loss, routing and cohort
             [
-                s.frontrun().iter().flat_map(|sw| vec![Value::from(uuid), Value::from(sw.id()), Value::from("FRONTRUN")]).collect::<Vec<_>>(),
-                s.backrun().iter().flat_map(|sw| vec![Value::from(uuid), Value::from(sw.id()), Value::from("BACKRUN")]).collect::<Vec<_>>(),
-                s.victim().iter().flat_map(|sw| vec![Value::from(uuid), Value::from(sw.id()), Value::from("VICTIM")]).collect::<Vec<_>>(),
-                s.transfers().iter().flat_map(|sw| vec![Value::from(uuid), Value::from(sw.id()), Value::from("TRANSFER")]).collect::<Vec<_>>(),
+                s.frontrun().iter().flat_map(|sw| vec![Value::from(uuid), Value::from(sw.id()), Value::from("FRONTRUN"), Value::from(s.position_bps(sw)), Value::from(None::<u64>), Value::from(None::<u64>), Value::from(None::<bool>), Value::from(None::<String>), Value::from(stable)]).collect::<Vec<_>>(),
+                s.backrun().iter().flat_map(|sw| vec![Value::from(uuid), Value::from(sw.id()), Value::from("BACKRUN"), Value::from(s.position_bps(sw)), Value::from(None::<u64>), Value::from(None::<u64>), Value::from(None::<bool>), Value::from(None::<String>), Value::from(stable)]).collect::<Vec<_>>(),
+                s.victim().iter().zip(losses.iter().zip(losses_lamports.iter())).flat_map(|(sw, (loss, loss_lamports))| vec![Value::from(uuid), Value::from(sw.id()), Value::from("VICTIM"), Value::from(None::<u64>), Value::from(loss.price_impact_bps()), Value::from(loss_lamports), Value::from(sw.is_aggregator_routed()), Value::from(cohorts.get(sw.id()).map(VictimCohort::as_str)), Value::from(stable)]).collect::<Vec<_>>(),
+                s.transfers().iter().flat_map(|sw| vec![Value::from(uuid), Value::from(sw.id()), Value::from("TRANSFER"), Value::from(None::<u64>), Value::from(None::<u64>), Value::from(None::<u64>), Value::from(None::<bool>), Value::from(None::<String>), Value::from(stable)]).collect::<Vec<_>>(),
+                s.suspected_wash().iter().flat_map(|sw| vec![Value::from(uuid), Value::from(sw.id()), Value::from("SUSPECTED_WASH"), Value::from(None::<u64>), Value::from(None::<u64>), Value::from(None::<u64>), Value::from(None::<bool>), Value::from(None::<String>), Value::from(stable)]).collect::<Vec<_>>(),
+                s.conversions().iter().flat_map(|sw| vec![Value::from(uuid), Value::from(sw.id()), Value::from("CONVERSION"), Value::from(None::<u64>), Value::from(None::<u64>), Value::from(None::<u64>), Value::from(None::<bool>), Value::from(None::<String>), Value::from(stable)]).collect::<Vec<_>>(),
             ].concat()
         }).collect();
         if !args.is_empty() {
-            let stmt = format!("insert into sandwiches (id, event_id, role) values {}", "(?, ?, ?),".repeat(args.len() / 3));
-            let stmt = stmt.trim_end_matches(",").to_string();
+            let stmt = format!("insert into sandwiches (id, event_id, role, position_bps, price_impact_bps, est_victim_loss_lamports, aggregator_routed, victim_cohort, stable_pair) values {}", "(?, ?, ?, ?, ?, ?, ?, ?, ?),".repeat(args.len() / 9));
+            let stmt = stmt.trim_end_matches(",").to_string() + " on duplicate key update role=values(role), position_bps=values(position_bps), price_impact_bps=values(price_impact_bps), est_victim_loss_lamports=values(est_victim_loss_lamports), aggregator_routed=values(aggregator_routed), victim_cohort=values(victim_cohort), stable_pair=values(stable_pair)";
             if let Err(r) = conn.exec_drop(stmt, args) {
-                eprintln!("Failed to insert sandwiches for slots {} to {}: {}", slot, slot + LEADER_GROUP_SIZE - 1, r);
+                eprintln!("Failed to insert sandwiches for the group starting at slot {}: {}", slot, r);
                 eprintln!("{:?}", sandwiches);
+                return false;
+            }
+        }
+        self.insert_rollups(&new_sandwiches);
+        self.register_attackers(&new_sandwiches);
+        self.insert_dont_front_violations(&sandwiches);
+        self.insert_spans(&sandwiches);
+        self.insert_pool_tvl(&sandwiches);
+        self.link_bundles(&sandwiches);
+        true
+    }
+
+    /// Tracks when each attacker was first and last seen. Attackers seen for the first time that aren't part of any cluster
+    /// are left for the notifier to announce, see [`crate::api::notify::start_notifier`]
+    fn register_attackers(&mut self, sandwiches: &[SandwichCandidate]) {
+        if sandwiches.is_empty() {
+            return;
+        }
+        // (first slot, first sandwich, last slot, sandwiches) per attacker
+        let mut attackers: HashMap<u32, (u64, String, u64, u64)> = HashMap::new();
+        for s in sandwiches.iter() {
+            let entry = attackers.entry(self.get(s.attacker().clone(), 24)).or_insert_with(|| (s.slot(), s.uuid().to_string(), s.slot(), 0));
+            if s.slot() < entry.0 {
+                (entry.0, entry.1) = (s.slot(), s.uuid().to_string());
+            }
+            entry.2 = entry.2.max(s.slot());
+            entry.3 += 1;
+        }
+        let mut conn = self.pool.get_conn().unwrap();
+        let placeholders = "?,".repeat(attackers.len());
+        let placeholders = placeholders.trim_end_matches(",");
+        let ids: Vec<Value> = attackers.keys().map(|&id| Value::from(id)).collect();
+        let known: HashSet<u32> = match conn.exec(format!("select attacker_id from attackers where attacker_id in ({placeholders}) union select attacker_id from attacker_clusters where attacker_id in ({placeholders})"), [ids.clone(), ids].concat()) {
+            Ok(known) => known.into_iter().collect(),
+            Err(e) => {
+                eprintln!("Failed to look up attackers: {}", e);
+                return;
+            }
+        };
+        let res = conn.exec_batch(
+            "insert into attackers (attacker_id, first_slot, first_sandwich_id, last_slot, sandwiches, notified) values (?, ?, ?, ?, ?, ?) on duplicate key update first_sandwich_id=if(values(first_slot)<first_slot, values(first_sandwich_id), first_sandwich_id), first_slot=least(first_slot, values(first_slot)), last_slot=greatest(last_slot, values(last_slot)), sandwiches=sandwiches+values(sandwiches)",
+            attackers.iter().map(|(id, (first_slot, first_sandwich_id, last_slot, sandwiches))| (id, first_slot, first_sandwich_id, last_slot, sandwiches, known.contains(id))),
+        );
+        if let Err(e) = res {
+            eprintln!("Failed to register attackers: {}", e);
+            return;
+        }
+        metrics::add("new_attackers", attackers.keys().filter(|id| !known.contains(id)).count() as u64);
+    }
+
+    /// Picks up receipts imported before the sandwiches were detected
+    fn link_bundles(&mut self, sandwiches: &[SandwichCandidate]) {
+        let Some(from_slot) = sandwiches.iter().map(|s| s.slot()).min() else {
+            return;
+        };
+        let to_slot = sandwiches.iter().map(|s| s.slot() + s.span().slots()).max().unwrap();
+        let mut conn = self.pool.get_conn().unwrap();
+        if let Err(e) = bundles::link_bundles(&mut conn, from_slot, to_slot) {
+            eprintln!("Failed to link bundles: {}", e);
+        }
+    }
+
+    fn insert_spans(&mut self, sandwiches: &[SandwichCandidate]) {
+        if sandwiches.is_empty() {
+            return;
+        }
+        let mut conn = self.pool.get_conn().unwrap();
+        let args = sandwiches.iter().map(|s| {
+            let (span, timing) = (s.span(), s.timing());
+            (s.uuid().to_string(), s.slot(), *span.slots(), *span.inclusion_orders(), timing.map(|t| *t.frontrun_offset()), timing.map(|t| *t.victim_offset()), timing.map(|t| *t.backrun_offset()), timing.map(|t| *t.frontrun_victim_slots()), timing.map(|t| *t.victim_backrun_slots()))
+        });
+        if let Err(e) = conn.exec_batch("insert ignore into sandwich_spans (sandwich_id, slot, slot_span, inclusion_order_span, frontrun_slot_offset, victim_slot_offset, backrun_slot_offset, frontrun_victim_slots, victim_backrun_slots) values (?, ?, ?, ?, ?, ?, ?, ?, ?)", args) {
+            eprintln!("Failed to insert sandwich spans: {}", e);
+        }
+    }
+
+    /// Cohort of each victim by event id, from the victim's swaps indexed before the earliest victim and up to the latest one
+    fn victim_cohorts(&mut self, conn: &mut PooledConn, sandwiches: &[SandwichCandidate]) -> HashMap<u64, VictimCohort> {
+        let victims: Vec<_> = sandwiches.iter().flat_map(|s| s.victim().iter()).collect();
+        let (Some(first_slot), Some(last_slot)) = (victims.iter().map(|v| *v.slot()).min(), victims.iter().map(|v| *v.slot()).max()) else {
+            return HashMap::new();
+        };
+        self.insert_addresses(victims.iter().map(|v| v.authority().as_ref()).collect::<HashSet<_>>().into_iter().collect());
+        let authority_ids: Vec<u32> = victims.iter().map(|v| self.get(v.authority().clone(), 29)).collect::<HashSet<_>>().into_iter().collect();
+        let stmt = format!("select authority_id, min(slot), cast(sum(slot >= ?) as unsigned) from events_with_id where event_type='SWAP' and slot < ? and authority_id in ({}) group by authority_id", "?,".repeat(authority_ids.len()).trim_end_matches(","));
+        let params: Vec<Value> = [Value::from(first_slot.saturating_sub(self.cohorts.bot_window_slots)), Value::from(last_slot)].into_iter().chain(authority_ids.into_iter().map(Value::from)).collect();
+        let activity: HashMap<u32, (u64, u64)> = match conn.exec_map(stmt, params, |(id, first_seen, recent_swaps): (u32, u64, u64)| (id, (first_seen, recent_swaps))) {
+            Ok(rows) => rows.into_iter().collect(),
+            Err(e) => {
+                eprintln!("Failed to load victim activity: {}", e);
+                return HashMap::new();
+            },
+        };
+        victims.iter().map(|v| {
+            let (first_seen, recent_swaps) = match activity.get(&self.get(v.authority().clone(), 29)) {
+                Some(&(first_seen, recent_swaps)) => (Some(first_seen), recent_swaps),
+                None => (None, 0),
+            };
+            (*v.id(), VictimCohort::classify(*v.slot(), first_seen, recent_swaps, &self.cohorts))
+        }).collect()
+    }
+
+    fn insert_pool_tvl(&mut self, sandwiches: &[SandwichCandidate]) {
+        // the rollups only resolve the mints other than SOL, and only for new sandwiches
+        self.insert_addresses(sandwiches.iter().flat_map(|s| [s.amm().as_ref(), s.frontrun()[0].input_mint().as_ref(), s.frontrun()[0].output_mint().as_ref()]).collect::<HashSet<_>>().into_iter().collect());
+        let args: Vec<_> = sandwiches.iter().filter_map(|s| {
+            let tvl = s.pool_tvl()?;
+            let frontrun = &s.frontrun()[0];
+            Some((s.uuid().to_string(), s.slot(), self.get(s.amm().clone(), 26), self.get(frontrun.input_mint().clone(), 27), *tvl.reserve_a(), self.get(frontrun.output_mint().clone(), 27), *tvl.reserve_b(), *tvl.tvl_lamports()))
+        }).collect();
+        if args.is_empty() {
+            return;
+        }
+        let mut conn = self.pool.get_conn().unwrap();
+        if let Err(e) = conn.exec_batch("insert ignore into sandwich_pool_tvl (sandwich_id, slot, amm_id, mint_a_id, reserve_a, mint_b_id, reserve_b, tvl_lamports) values (?, ?, ?, ?, ?, ?, ?, ?)", args) {
+            eprintln!("Failed to insert pool tvl: {}", e);
+        }
+    }
+
+    fn insert_dont_front_violations(&mut self, sandwiches: &[SandwichCandidate]) {
+        let args: Vec<_> = sandwiches.iter().flat_map(|s| {
+            let uuid = s.uuid().to_string();
+            let attacker_id = self.get(s.attacker().clone(), 17);
+            s.dont_front_victims().into_iter().map(move |v| (uuid.clone(), v.id(), v.slot(), v.inclusion_order(), attacker_id)).collect::<Vec<_>>()
+        }).collect();
+        if args.is_empty() {
+            return;
+        }
+        let mut conn = self.pool.get_conn().unwrap();
+        if let Err(e) = conn.exec_batch("insert ignore into dont_front_violations (sandwich_id, victim_event_id, slot, inclusion_order, attacker_id) values (?, ?, ?, ?, ?)", args) {
+            eprintln!("Failed to insert dont front violations: {}", e);
+        }
+    }
+
+    /// Adds the sandwiches to the per-bucket, per-attacker, per-pool, per-mint and per-program rollups.
+    /// Sandwiches on stablecoin pairs only go to the per-pool stable rollup.
+    fn insert_rollups(&mut self, sandwiches: &[SandwichCandidate]) {
+        if sandwiches.is_empty() {
+            return;
+        }
+        let addresses: HashSet<&str> = sandwiches.iter().flat_map(|s| [s.attacker().as_ref(), s.amm().as_ref(), s.program().as_ref()].into_iter().chain(s.token_mints().into_iter().map(|m| m.as_ref()))).collect();
+        self.insert_addresses(addresses.into_iter().collect());
+        let (stable, sandwiches): (Vec<_>, Vec<_>) = sandwiches.iter().partition(|s| self.stable_mints.is_stable_sandwich(s));
+        // per bucket and pool
+        let mut stable_pools: HashMap<(u64, u32), StableRollupCounts> = HashMap::new();
+        for s in stable.iter() {
+            let entry = stable_pools.entry((s.slot() - s.slot() % ROLLUP_BUCKET_SLOTS, self.get(s.amm().clone(), 16))).or_default();
+            entry.sandwiches += 1;
+            entry.victims += s.victim().len() as u64;
+        }
+        let wsol = WSOL_MINT.to_string();
+        // per bucket, mint and pool
+        let mut mints: HashMap<(u64, u32, u32), MintRollupCounts> = HashMap::new();
+        // per bucket and program
+        let mut programs: HashMap<(u64, u32), ProgramRollupCounts> = HashMap::new();
+        let mut totals: HashMap<u64, RollupCounts> = HashMap::new();
+        let mut attackers: HashMap<(u64, u32), RollupCounts> = HashMap::new();
+        let mut pools: HashMap<(u64, u32), RollupCounts> = HashMap::new();
+        for s in sandwiches.iter() {
+            let bucket = s.slot() - s.slot() % ROLLUP_BUCKET_SLOTS;
+            let victims = s.victim().len() as u64;
+            let loss = s.estimate_victim_loss_lamports();
+            let dont_front_victims = s.dont_front_victims().len() as u64;
+            for entry in [
+                totals.entry(bucket).or_default(),
+                attackers.entry((bucket, self.get(s.attacker().clone(), 15))).or_default(),
+                pools.entry((bucket, self.get(s.amm().clone(), 16))).or_default(),
+            ] {
+                entry.sandwiches += 1;
+                entry.victims += victims;
+                entry.victim_loss_lamports += loss;
+                entry.dont_front_victims += dont_front_victims;
             }
+            let volume_lamports = s.victim_volume(&wsol);
+            let entry = programs.entry((bucket, self.get(s.program().clone(), 25))).or_default();
+            entry.sandwiches += 1;
+            entry.victims += victims;
+            entry.victim_volume_lamports += volume_lamports;
+            entry.victim_loss_lamports += loss;
+            for mint in s.token_mints() {
+                let entry = mints.entry((bucket, self.get(mint.clone(), 23), self.get(s.amm().clone(), 16))).or_default();
+                entry.sandwiches += 1;
+                entry.victims += victims;
+                entry.victim_volume += s.victim_volume(mint);
+                entry.victim_volume_lamports += volume_lamports;
+                entry.victim_loss_lamports += loss;
+            }
+        }
+        let mut conn = self.pool.get_conn().unwrap();
+        let update = "sandwiches=sandwiches+values(sandwiches), victims=victims+values(victims), victim_loss_lamports=victim_loss_lamports+values(victim_loss_lamports), dont_front_victims=dont_front_victims+values(dont_front_victims)";
+        let res = conn.exec_batch(
+            format!("insert into sandwich_rollup (bucket_slot, sandwiches, victims, victim_loss_lamports, dont_front_victims) values (?, ?, ?, ?, ?) on duplicate key update {update}"),
+            totals.iter().map(|(bucket, v)| (bucket, v.sandwiches, v.victims, v.victim_loss_lamports, v.dont_front_victims)),
+        ).and_then(|_| conn.exec_batch(
+            format!("insert into sandwich_attacker_rollup (bucket_slot, attacker_id, sandwiches, victims, victim_loss_lamports, dont_front_victims) values (?, ?, ?, ?, ?, ?) on duplicate key update {update}"),
+            attackers.iter().map(|((bucket, id), v)| (bucket, id, v.sandwiches, v.victims, v.victim_loss_lamports, v.dont_front_victims)),
+        )).and_then(|_| conn.exec_batch(
+            format!("insert into sandwich_pool_rollup (bucket_slot, amm_id, sandwiches, victims, victim_loss_lamports, dont_front_victims) values (?, ?, ?, ?, ?, ?) on duplicate key update {update}"),
+            pools.iter().map(|((bucket, id), v)| (bucket, id, v.sandwiches, v.victims, v.victim_loss_lamports, v.dont_front_victims)),
+        )).and_then(|_| conn.exec_batch(
+            "insert into sandwich_mint_rollup (bucket_slot, mint_id, amm_id, sandwiches, victims, victim_volume, victim_volume_lamports, victim_loss_lamports) values (?, ?, ?, ?, ?, ?, ?, ?) on duplicate key update sandwiches=sandwiches+values(sandwiches), victims=victims+values(victims), victim_volume=victim_volume+values(victim_volume), victim_volume_lamports=victim_volume_lamports+values(victim_volume_lamports), victim_loss_lamports=victim_loss_lamports+values(victim_loss_lamports)",
+            mints.iter().map(|((bucket, mint, amm), v)| (bucket, mint, amm, v.sandwiches, v.victims, v.victim_volume, v.victim_volume_lamports, v.victim_loss_lamports)),
+        )).and_then(|_| conn.exec_batch(
+            "insert into sandwich_program_rollup (bucket_slot, program_id, sandwiches, victims, victim_volume_lamports, victim_loss_lamports) values (?, ?, ?, ?, ?, ?) on duplicate key update sandwiches=sandwiches+values(sandwiches), victims=victims+values(victims), victim_volume_lamports=victim_volume_lamports+values(victim_volume_lamports), victim_loss_lamports=victim_loss_lamports+values(victim_loss_lamports)",
+            programs.iter().map(|((bucket, program), v)| (bucket, program, v.sandwiches, v.victims, v.victim_volume_lamports, v.victim_loss_lamports)),
+        )).and_then(|_| conn.exec_batch(
+            "insert into sandwich_stable_rollup (bucket_slot, amm_id, sandwiches, victims) values (?, ?, ?, ?) on duplicate key update sandwiches=sandwiches+values(sandwiches), victims=victims+values(victims)",
+            stable_pools.iter().map(|((bucket, amm), v)| (bucket, amm, v.sandwiches, v.victims)),
+        ));
+        if let Err(e) = res {
+            eprintln!("Failed to update rollups: {}", e);
+        }
+    }
+
+    /// Sandwiches from a shadow detector, written apart from the primary output and without any of its derived tables
+    pub async fn insert_shadow_sandwiches(&mut self, label: &str, sandwiches: &[SandwichCandidate]) {
+        if sandwiches.is_empty() {
+            return;
+        }
+        let mut conn = self.pool.get_conn().unwrap();
+        let args: Vec<_> = sandwiches.iter().flat_map(|s| {
+            let uuid = s.uuid().to_string();
+            let slot = s.slot();
+            [
+                s.frontrun().iter().map(|sw| (sw.id(), "FRONTRUN")).collect::<Vec<_>>(),
+                s.backrun().iter().map(|sw| (sw.id(), "BACKRUN")).collect(),
+                s.victim().iter().map(|sw| (sw.id(), "VICTIM")).collect(),
+                s.transfers().iter().map(|t| (t.id(), "TRANSFER")).collect(),
+                s.suspected_wash().iter().map(|sw| (sw.id(), "SUSPECTED_WASH")).collect(),
+                s.conversions().iter().map(|sw| (sw.id(), "CONVERSION")).collect(),
+            ].concat().into_iter().map(move |(event_id, role)| (label, uuid.clone(), *event_id, role, slot))
+        }).collect();
+        if let Err(e) = conn.exec_batch("insert ignore into shadow_sandwiches (label, id, event_id, role, slot) values (?, ?, ?, ?, ?)", args) {
+            eprintln!("Failed to insert shadow sandwiches: {}", e);
+        }
+    }
+
+    pub async fn insert_backruns(&mut self, backruns: Arc<[BackrunCandidate]>) {
+        if backruns.is_empty() {
+            return;
+        }
+        let mut conn = self.pool.get_conn().unwrap();
+        let args = backruns.iter().map(|b| (b.uuid().to_string(), b.victim().id(), b.backrun().id(), b.backrun().slot(), b.profit_lamports()));
+        if let Err(e) = conn.exec_batch("insert ignore into backruns (id, victim_event_id, backrun_event_id, slot, profit_lamports) values (?, ?, ?, ?, ?)", args) {
+            eprintln!("Failed to insert backruns: {}", e);
+        }
+    }
+
+    pub async fn insert_washes(&mut self, washes: Arc<[WashCandidate]>) {
+        if washes.is_empty() {
+            return;
+        }
+        self.insert_addresses(washes.iter().flat_map(|w| [w.authority().as_ref(), w.amm().as_ref(), w.mint_a().as_ref(), w.mint_b().as_ref()]).collect::<HashSet<_>>().into_iter().collect());
+        let args: Vec<_> = washes.iter().map(|w| vec![
+            Value::from(w.uuid().to_string()),
+            Value::from(self.get(w.authority().clone(), 19)),
+            Value::from(self.get(w.amm().clone(), 20)),
+            Value::from(w.slot()),
+            Value::from(w.last_slot()),
+            Value::from(w.swaps().len()),
+            Value::from(w.round_trips()),
+            Value::from(self.get(w.mint_a().clone(), 21)),
+            Value::from(self.get(w.mint_b().clone(), 22)),
+            Value::from(w.volume_a()),
+            Value::from(w.volume_b()),
+            Value::from(*w.net_a() as i64),
+            Value::from(*w.net_b() as i64),
+            Value::from(w.volume_lamports()),
+        ]).collect();
+        let mut conn = self.pool.get_conn().unwrap();
+        if let Err(e) = conn.exec_batch("insert ignore into wash_events (id, authority_id, amm_id, slot, last_slot, swaps, round_trips, mint_a_id, mint_b_id, volume_a, volume_b, net_a, net_b, volume_lamports) values (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)", args) {
+            eprintln!("Failed to insert wash events: {}", e);
+        }
+    }
+
+    /// The swaps of each anomaly go to anomaly_swaps, peak marking the one furthest from the first
+    pub async fn insert_anomalies(&mut self, anomalies: Arc<[PriceAnomaly]>) {
+        if anomalies.is_empty() {
+            return;
+        }
+        metrics::add("price_anomalies", anomalies.len() as u64);
+        metrics::add("price_anomalies_unexplained", anomalies.iter().filter(|a| a.sandwich().is_none()).count() as u64);
+        self.insert_addresses(anomalies.iter().map(|a| a.amm().as_ref()).collect::<HashSet<_>>().into_iter().collect());
+        let args: Vec<_> = anomalies.iter().map(|a| (
+            a.uuid().to_string(),
+            self.get(a.amm().clone(), 28),
+            a.slot(),
+            a.swaps().len(),
+            *a.move_bps(),
+            a.sandwich().map(|id| id.to_string()),
+        )).collect();
+        let swap_args: Vec<_> = anomalies.iter().flat_map(|a| {
+            let uuid = a.uuid().to_string();
+            a.swaps().iter().enumerate().map(move |(i, sw)| (uuid.clone(), *sw.id(), i, i == *a.peak())).collect::<Vec<_>>()
+        }).collect();
+        let mut conn = self.pool.get_conn().unwrap();
+        if let Err(e) = conn.exec_batch("insert ignore into anomalies (id, amm_id, slot, swaps, move_bps, sandwich_id) values (?, ?, ?, ?, ?, ?)", args) {
+            eprintln!("Failed to insert anomalies: {}", e);
+            return;
+        }
+        if let Err(e) = conn.exec_batch("insert ignore into anomaly_swaps (anomaly_id, event_id, position, peak) values (?, ?, ?, ?)", swap_args) {
+            eprintln!("Failed to insert anomaly swaps: {}", e);
+        }
+    }
+
+    /// Overwrites the volumes of the slots, they're always computed over whole blocks
+    pub async fn insert_block_volumes(&mut self, volumes: Arc<[BlockVolume]>) {
+        if volumes.is_empty() {
+            return;
+        }
+        let mut conn = self.pool.get_conn().unwrap();
+        let args = volumes.iter().map(|v| (v.slot(), v.swap_volume_lamports(), v.sandwiched_volume_lamports(), v.sandwiched_bps()));
+        if let Err(e) = conn.exec_batch("insert into block_volume (slot, swap_volume_lamports, sandwiched_volume_lamports, sandwiched_bps) values (?, ?, ?, ?) on duplicate key update swap_volume_lamports=values(swap_volume_lamports), sandwiched_volume_lamports=values(sandwiched_volume_lamports), sandwiched_bps=values(sandwiched_bps)", args) {
+            eprintln!("Failed to insert block volumes: {}", e);
         }
     }
 
-    pub async fn insert_events(&mut self, events: &[Event]) {
-        let conn = &mut self.pool.get_conn().unwrap();
-        let mut tx = conn.start_transaction(TxOpts::default()).unwrap();
+    /// Records the first swap of each AMM in the pool registry, returning the pools that weren't known before.
+    /// Nothing is returned until the registry has been running for `warmup_slots`, except for the pools in `created`
+    /// which were seen being created.
+    pub async fn register_pools(&mut self, firsts: &[(Arc<str>, Timestamp)], created: &HashSet<Arc<str>>, warmup_slots: u64) -> Vec<(Arc<str>, Timestamp)> {
+        if firsts.is_empty() {
+            return vec![];
+        }
+        self.insert_addresses(firsts.iter().map(|(amm, _)| amm.as_ref()).collect());
+        let mut conn = self.pool.get_conn().unwrap();
+        let registry_start: Option<u64> = conn.query_first("select min(first_slot) from pools").unwrap().flatten();
+        let ids: Vec<_> = firsts.iter().map(|(amm, _)| Value::from(self.get(amm.clone(), 18))).collect();
+        let known: HashSet<u32> = conn.exec(format!("select amm_id from pools where amm_id in ({})", "?,".repeat(ids.len()).trim_end_matches(",")), ids).unwrap().into_iter().collect();
+        let res = conn.exec_batch(
+            "insert into pools (amm_id, first_slot, first_inclusion_order) values (?, ?, ?) on duplicate key update first_inclusion_order=if(values(first_slot)<first_slot, values(first_inclusion_order), first_inclusion_order), first_slot=least(first_slot, values(first_slot))",
+            firsts.iter().map(|(amm, ts)| (self.get(amm.clone(), 18), ts.slot(), ts.inclusion_order())),
+        );
+        if let Err(e) = res {
+            eprintln!("Failed to register pools: {}", e);
+            return vec![];
+        }
+        let warmed_up = |ts: &Timestamp| registry_start.is_some_and(|start| *ts.slot() >= start + warmup_slots);
+        firsts.iter().filter(|(amm, ts)| (created.contains(amm) || warmed_up(ts)) && !known.contains(&self.get(amm.clone(), 18))).cloned().collect()
+    }
+
+    /// Pools seen being created between the slots, inclusive, with their creation timestamps
+    pub async fn pool_creations(&self, start_slot: u64, end_slot: u64) -> Vec<(Arc<str>, Timestamp)> {
+        let mut conn = self.pool.get_conn().unwrap();
+        let res = conn.exec_map(
+            "select amm.address, pc.slot, pc.inclusion_order, pc.ix_index from pool_creations pc join address_lookup_table amm on amm.id=pc.amm_id where pc.slot between ? and ?",
+            (start_slot, end_slot),
+            |(amm, slot, inclusion_order, ix_index): (String, u64, u32, u32)| (Arc::from(amm), Timestamp::new(slot, inclusion_order, ix_index, None)),
+        );
+        match res {
+            Ok(creations) => creations,
+            Err(e) => {
+                eprintln!("Failed to load pool creations: {}", e);
+                vec![]
+            },
+        }
+    }
+
+    pub async fn insert_snipes(&mut self, snipes: &[Snipe]) {
+        if snipes.is_empty() {
+            return;
+        }
+        self.insert_addresses(snipes.iter().flat_map(|s| [s.swap().amm().as_ref(), s.swap().authority().as_ref()]).collect::<HashSet<_>>().into_iter().collect());
+        let mut conn = self.pool.get_conn().unwrap();
+        let args = snipes.iter().map(|s| (
+            s.swap().id(),
+            self.get(s.swap().amm().clone(), 19),
+            self.get(s.swap().authority().clone(), 20),
+            s.swap().slot(),
+            s.swap().inclusion_order(),
+            s.swap().input_amount(),
+            s.slots_after_creation(),
+        ));
+        if let Err(e) = conn.exec_batch("insert ignore into snipes (event_id, amm_id, authority_id, slot, inclusion_order, lamports, slots_after_creation) values (?, ?, ?, ?, ?, ?, ?)", args) {
+            eprintln!("Failed to insert snipes: {}", e);
+        }
+    }
+
+    /// Writes one table's rows on a connection of its own
+    /// Resolves to whether the rows were written
+    fn spawn_writer(&self, table: &'static str, stmt: String, params: Vec<Value>) -> JoinHandle<bool> {
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(move || {
+            if params.is_empty() {
+                return true;
+            }
+            let mut conn = pool.get_conn().unwrap();
+            if let Err(e) = conn.exec_drop(stmt, params) {
+                eprintln!("Failed to insert {}: {}", table, e);
+                return false;
+            }
+            true
+        })
+    }
+
+    /// Safe to call again for the same slots, rows are matched on their slot/order/ix indexes
+    /// Transactions don't reference the address dictionary so they're written while the addresses are being resolved,
+    /// events are written once the addresses are in. False if any of them couldn't be written.
+    pub async fn insert_events(&mut self, events: &[Event]) -> bool {
+        let tx_params: Vec<_> = events.iter().flat_map(|e| self.to_tx_vec(e)).collect();
+        let tx_stmt = format!("insert into transactions (slot, inclusion_order, sig, fee, cu_actual, dont_front, block_time, block_tx_count, cu_limit, block_cu) values {}", "(?, ?, ?, ?, ?, ?, ?, ?, ?, ?),".repeat(tx_params.len() / 10));
+        let tx_stmt = tx_stmt.trim_end_matches(",").to_string() + " on duplicate key update sig=values(sig), fee=values(fee), cu_actual=values(cu_actual), dont_front=values(dont_front), block_time=values(block_time), block_tx_count=values(block_tx_count), cu_limit=values(cu_limit), block_cu=values(block_cu)";
+        let tx_writer = self.spawn_writer("transactions", tx_stmt, tx_params);
         // 5, 6, 7, 8, 9, 10, 13, 14
         let addresses = events.iter().map(|e| {
             match e {
@@ -183,6 +669,7 @@ impl Inserter {
                     s.output_mint().as_ref(),
                     s.input_ata().as_ref(),
                     s.output_ata().as_ref(),
+                    s.caller_program().as_ref().map(|s| s.as_ref()).unwrap_or(""),
                 ],
                 Event::Transfer(t) => vec![
                     t.authority().as_ref(),
@@ -192,22 +679,41 @@ impl Inserter {
                     t.input_ata().as_ref(),
                     t.output_ata().as_ref(),
                 ],
+                Event::PoolCreated(p) => vec![
+                    p.amm().as_ref(),
+                    p.program().as_ref(),
+                    p.mint().as_ref(),
+                ],
                 _ => vec![],
             }
         }).flatten().filter(|&s| !s.is_empty()).collect::<HashSet<_>>();
-        self.insert_addresses(addresses.into_iter().collect());
-        let event_vecs = events.iter().map(|e| self.to_event_vec(e)).collect::<Vec<_>>();
-        let event_params: Vec<_> = event_vecs.iter().flat_map(|e| e).collect();
-        let event_stmt = format!("insert into events_with_id (event_type, slot, inclusion_order, ix_index, inner_ix_index, authority_id, outer_program_id, program_id, amm_id, input_mint_id, output_mint_id, input_amount, output_amount, input_ata_id, output_ata_id, input_inner_ix_index, output_inner_ix_index) values {}", "(?, ?, ?, ?, ifnull(?, -1), ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ifnull(?, -1), ifnull(?, -1)),".repeat(event_params.len() / 17));
-        let tx_params: Vec<_> = events.iter().flat_map(|e| self.to_tx_vec(e)).collect();
-        let tx_stmt = format!("insert into transactions (slot, inclusion_order, sig, fee, cu_actual, dont_front) values {}", "(?, ?, ?, ?, ?, ?),".repeat(tx_params.len() / 6));
-        if !event_params.is_empty() {
-            tx.exec_drop(event_stmt.trim_end_matches(","), event_params).unwrap();
-        }
-        if !tx_params.is_empty() {
-            tx.exec_drop(tx_stmt.trim_end_matches(","), tx_params).unwrap();
+        let mut address_writer = self.clone();
+        let addresses: Vec<Arc<str>> = addresses.into_iter().map(Arc::from).collect();
+        let address_writer = tokio::task::spawn_blocking(move || address_writer.insert_addresses(addresses.iter().map(|a| a.as_ref()).collect()));
+        let addresses_written = match address_writer.await {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!("Failed to insert addresses: {}", e);
+                false
+            },
+        };
+        let event_params: Vec<_> = events.iter().flat_map(|e| self.to_event_vec(e)).collect();
+        // upserts on the natural keys so re-ingesting a slot keeps the existing ids
+        let event_stmt = format!("insert into events_with_id (event_type, slot, inclusion_order, ix_index, inner_ix_index, authority_id, outer_program_id, program_id, amm_id, input_mint_id, output_mint_id, input_amount, output_amount, input_ata_id, output_ata_id, input_inner_ix_index, output_inner_ix_index, min_out, max_in, caller_program_id, input_reserve, output_reserve, input_amount_sol, output_amount_sol) values {}", "(?, ?, ?, ?, ifnull(?, -1), ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ifnull(?, -1), ifnull(?, -1), ?, ?, ?, ?, ?, ?, ?),".repeat(event_params.len() / 24));
+        let event_stmt = event_stmt.trim_end_matches(",").to_string() + " on duplicate key update authority_id=values(authority_id), outer_program_id=values(outer_program_id), program_id=values(program_id), amm_id=values(amm_id), input_mint_id=values(input_mint_id), output_mint_id=values(output_mint_id), input_amount=values(input_amount), output_amount=values(output_amount), input_ata_id=values(input_ata_id), output_ata_id=values(output_ata_id), min_out=values(min_out), max_in=values(max_in), caller_program_id=values(caller_program_id), input_reserve=values(input_reserve), output_reserve=values(output_reserve), input_amount_sol=values(input_amount_sol), output_amount_sol=values(output_amount_sol)";
+        let event_writer = self.spawn_writer("events", event_stmt, event_params);
+        let pool_params: Vec<_> = events.iter().flat_map(|e| self.to_pool_vec(e)).collect();
+        let pool_stmt = format!("insert into pool_creations (amm_id, program_id, mint_id, slot, inclusion_order, ix_index) values {}", "(?, ?, ?, ?, ?, ?),".repeat(pool_params.len() / 6));
+        let pool_stmt = pool_stmt.trim_end_matches(",").to_string() + " on duplicate key update slot=values(slot), inclusion_order=values(inclusion_order), ix_index=values(ix_index)";
+        let pool_writer = self.spawn_writer("pool creations", pool_stmt, pool_params);
+        let (tx_res, event_res, pool_res) = join!(tx_writer, event_writer, pool_writer);
+        match tx_res.and_then(|tx_written| Ok(tx_written & event_res? & pool_res?)) {
+            Ok(written) => written && addresses_written,
+            Err(e) => {
+                eprintln!("Insert writer failed: {}", e);
+                false
+            },
         }
-        tx.commit().unwrap();
     }
 }
 
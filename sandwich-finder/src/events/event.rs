@@ -1,214 +1,250 @@
-use std::{collections::HashMap, sync::Arc};
+use std::sync::{Arc, LazyLock, Mutex};
 
-use dashmap::DashMap;
 use debug_print::debug_println;
-use futures::{SinkExt as _, StreamExt as _};
 use serde::Serialize;
-use solana_rpc_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::{address_lookup_table::{state::AddressLookupTable, AddressLookupTableAccount}, bs58, commitment_config::CommitmentConfig};
+use solana_sdk::bs58;
 use tokio::sync::mpsc;
-use yellowstone_grpc_client::GeyserGrpcBuilder;
-use yellowstone_grpc_proto::{geyser::{subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest, SubscribeRequestFilterAccounts, SubscribeRequestFilterBlocks, SubscribeRequestPing}, tonic::transport::Endpoint};
+use yellowstone_grpc_proto::geyser::SubscribeUpdateBlock;
 
-use crate::{events::{addresses::{DONT_FRONT_END, DONT_FRONT_START}, swap::SwapV2, swaps::{alpha::AlphaSwapFinder, apesu::ApesuSwapFinder, aqua::AquaSwapFinder, clearpool::ClearpoolSwapFinder, discoverer::Discoverer, dooar::DooarSwapFinder, fluxbeam::FluxbeamSwapFinder, fusionamm::FusionAmmSwapFinder, goonfi::GoonFiSwapFinder, humidifi::HumidiFiSwapFinder, jup_order_engine::JupOrderEngineSwapFinder, jup_perps::JupPerpsSwapFinder, lifinity_v2::LifinityV2SwapFinder, limo::LimoSwapFinder, meteora::MeteoraSwapFinder, meteora_damm_v2::MeteoraDammV2Finder, meteora_dbc::MeteoraDBCSwapFinder, meteora_dlmm::MeteoraDLMMSwapFinder, onedex::OneDexSwapFinder, openbook_v2::OpenbookV2SwapFinder, pancake_swap::PancakeSwapSwapFinder, pumpamm::PumpAmmSwapFinder, pumpfun::PumpFunSwapFinder, pumpup::PumpupSwapFinder, raydium_cl::RaydiumCLSwapFinder, raydium_lp::RaydiumLPSwapFinder, raydium_v4::RaydiumV4SwapFinder, raydium_v5::RaydiumV5SwapFinder, saros_dlmm::SarosDLMMSwapFinder, solfi::SolFiSwapFinder, stabble_weighted::StabbleWeightedSwapFinder, sugar::SugarSwapFinder, sv2e::Sv2eSwapFinder, swap_finder_ext::SwapFinderExt as _, tessv::TessVSwapFinder, whirlpool::{WhirlpoolSwapFinder, WhirlpoolTwoHopSwapFinder1, WhirlpoolTwoHopSwapFinder2, WhirlpoolTwoHopSwapV2Finder1, WhirlpoolTwoHopSwapV2Finder2}, zerofi::ZeroFiSwapFinder}, transaction::TransactionV2, transfer::TransferV2, transfers::{stake::StakeProgramTransferfinder, system::SystemProgramTransferfinder, token::TokenProgramTransferFinder, transfer_finder_ext::TransferFinderExt as _}}, utils::{decompile_tx, pubkey_from_slice}};
+use crate::{canary::FinderCanary, config, decode_failures::{decode_failures, DecodeFailure, DecodeFailureLog}, events::{dont_front::DontFrontMatcher, pool::PoolCreated, swap::SwapV2, swaps::{discoverer::Discoverer, jupiter_v6::apply_swap_events_in_tx, pumpfun::PumpFunSwapFinder, registry::FinderRegistry, swap_finder_ext::SwapFinderExt as _}, transaction::{cu_limit_from_ixs, TransactionV2}, transfer::TransferV2, transfers::{stake::StakeProgramTransferfinder, system::SystemProgramTransferfinder, token::TokenProgramTransferFinder, transfer_finder_ext::TransferFinderExt as _}}, lut_cache::LutCache, metrics, redact::{Redact, Redaction}, rpc::BoundedRpc, shutdown::Shutdown, source::{BlockSource, BlockUpdate}, utils::decompile_tx};
 
 
+/// Marker accounts a tx includes to opt out of being frontrun, from `DONT_FRONT_MARKERS`
+pub static DONT_FRONT: LazyLock<DontFrontMatcher> = LazyLock::new(DontFrontMatcher::from_env);
+
 #[derive(Clone, Debug, Serialize)]
 pub enum Event {
     Swap(SwapV2),
     Transfer(TransferV2),
     Transaction(TransactionV2),
+    PoolCreated(PoolCreated),
 }
 
-pub fn start_event_processor(grpc_url: String, rpc_url: String) -> mpsc::Receiver<(u64, Arc<[Event]>)> {
+impl Event {
+    pub fn set_block_time(&mut self, block_time: Option<i64>) {
+        match self {
+            Event::Swap(swap) => swap.set_block_time(block_time),
+            Event::Transfer(transfer) => transfer.set_block_time(block_time),
+            Event::Transaction(tx) => tx.set_block_time(block_time),
+            Event::PoolCreated(pool) => pool.set_block_time(block_time),
+        }
+    }
+}
+
+impl Redact for Event {
+    fn redact(&mut self, redaction: &Redaction, victim: bool) {
+        match self {
+            Event::Swap(swap) => swap.redact(redaction, victim),
+            Event::Transfer(transfer) => transfer.redact(redaction, victim),
+            Event::Transaction(_) | Event::PoolCreated(_) => {},
+        }
+    }
+}
+
+/// Streams the events of each block `source` hands out, in order.
+/// Stops taking blocks once `shutdown` is triggered, closing the channel after the blocks already taken are sent.
+/// Live sources pass a `canary` to be told about finders gone silent, see [`FinderCanary`], and a `failure_log` to
+/// keep the ixs of the swaps that couldn't be decoded.
+pub fn start_event_processor<S: BlockSource>(mut source: S, rpc_url: String, shutdown: Shutdown, canary: Option<Arc<Mutex<FinderCanary>>>, failure_log: Option<Arc<DecodeFailureLog>>) -> mpsc::Receiver<(u64, Arc<[Event]>)> {
     // Initialize event processing system
-    let rpc_client = RpcClient::new_with_commitment(rpc_url.to_string(), CommitmentConfig::processed());
-    let lut_cache = DashMap::new();
+    let rpc_client = BoundedRpc::from_env(&rpc_url, config::get().commitment_config());
+    let lut_cache = LutCache::default();
     let (sender, receiver) = mpsc::channel::<_>(100);
     tokio::spawn(async move {
-        println!("connecting to grpc server: {}", grpc_url);
-        let mut grpc_client = GeyserGrpcBuilder{
-            endpoint: Endpoint::from_shared(grpc_url.to_string()).unwrap(),
-            x_token: None,
-            x_request_snapshot: false,
-            send_compressed: None,
-            accept_compressed: None,
-            max_decoding_message_size: Some(128 * 1024 * 1024),
-            max_encoding_message_size: None,
-        }.connect().await.expect("cannon connect to grpc server");
-        println!("connected to grpc server!");
-        let mut blocks = HashMap::new();
-        blocks.insert("client".to_string(), SubscribeRequestFilterBlocks {
-            account_include: vec![],
-            include_transactions: Some(true),
-            include_accounts: Some(true),
-            include_entries: Some(false),
-        });
-        let mut accounts = HashMap::new();
-        accounts.insert("client".to_string(), SubscribeRequestFilterAccounts {
-            account: vec![],
-            owner: vec!["AddressLookupTab1e1111111111111111111111111".to_string()],
-            filters: vec![],
-            nonempty_txn_signature: Some(true),
-        });
-        let (mut sink, mut stream) = grpc_client.subscribe_with_request(Some(SubscribeRequest {
-            accounts,
-            blocks,
-            commitment: Some(CommitmentLevel::Confirmed as i32),
-            ..Default::default()
-        })).await.expect("unable to subscribe");
-
-        while let Some(msg) = stream.next().await {
-            if msg.is_err() {
-                println!("grpc error: {:?}", msg.err());
-                break;
-            }
-            let msg = msg.unwrap();
-            match msg.update_oneof {
-                Some(UpdateOneof::Block(block)) => {
-                    // println!("new block {}, {} txs", block.slot, block.transactions.len());
-                    // let now = std::time::Instant::now();
-                    // let ts = block.block_time.unwrap().timestamp;
+        while let Some(update) = shutdown.unless_triggered(source.next_update()).await.flatten() {
+            match update {
+                BlockUpdate::Block(mut block) => {
                     let slot = block.slot;
-                    let futs = block.transactions.iter().filter_map(|tx| {
-                        if tx.is_vote {
-                            None
-                        } else {
-                            Some(decompile_tx(tx, &rpc_client, &lut_cache))
-                        }
-                    }).collect::<Vec<_>>();
-                    let joined_futs = futures::future::join_all(futs).await;
-                    let block_txs = joined_futs.iter().filter_map(|tx| {
-                        if let Some(tx) = tx {
-                            Some(tx)
-                        } else {
-                            None
-                        }
-                    }).collect::<Vec<_>>();
-                    // let swap_count = block_txs.iter().map(|tx| tx.swaps().len()).sum::<usize>();
-                    // block_txs.sort_by_key(|x| x.order());
-                    let mut events = vec![];
-                    block_txs.iter().for_each(|tx| {
-                        // println!("processing tx {} in slot {}", bs58::encode(&tx.0.signature).into_string(), slot);
-                        let swaps: Vec<Event> = [
-                            RaydiumV4SwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            RaydiumV5SwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            RaydiumLPSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            RaydiumCLSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            PumpFunSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            PumpAmmSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            WhirlpoolSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            WhirlpoolTwoHopSwapFinder1::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            WhirlpoolTwoHopSwapFinder2::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            WhirlpoolTwoHopSwapV2Finder1::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            WhirlpoolTwoHopSwapV2Finder2::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            MeteoraDLMMSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            MeteoraSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            MeteoraDBCSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            MeteoraDammV2Finder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            OpenbookV2SwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            ZeroFiSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            JupOrderEngineSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            PancakeSwapSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            FluxbeamSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            HumidiFiSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            SarosDLMMSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            SolFiSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            GoonFiSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            SugarSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            TessVSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            Sv2eSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            LifinityV2SwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            ApesuSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            OneDexSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            AquaSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            StabbleWeightedSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            JupPerpsSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            DooarSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            PumpupSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            ClearpoolSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            FusionAmmSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            AlphaSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            LimoSwapFinder::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2),
-                        ].concat().into_iter().map(|s| Event::Swap(s)).collect();
-                        let transfers: Vec<Event> = [
-                            SystemProgramTransferfinder::find_transfers_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            TokenProgramTransferFinder::find_transfers_in_tx(slot, tx.0, &tx.1, &tx.2),
-                            StakeProgramTransferfinder::find_transfers_in_tx(slot, tx.0, &tx.1, &tx.2),
-                        ].concat().into_iter().map(|t| Event::Transfer(t)).collect();
-                        if swaps.is_empty() {
-                            let swaps = Discoverer::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2);
-                            if !swaps.is_empty() {
-                                println!("[Discoverer] tx {} ix #{} in slot {} triggered program {}", bs58::encode(&tx.0.signature).into_string(), swaps[0].ix_index(), slot, swaps[0].program());
-                                debug_println!("{:?}", &tx);
-                            }
-                        }
-                        let mut tx_events = swaps;
-                        tx_events.extend(transfers);
-                        // println!("found {} swaps in slot {} tx {}", swaps.len(), slot, bs58::encode(&tx.0.signature).into_string());
-                        // println!("found {} transfers in slot {} tx {}", transfers.len(), slot, bs58::encode(&tx.0.signature).into_string());
-                        // println!("{:?}", swaps);
-                        if tx_events.len() > 0 {
-                            let dont_front = tx.2.iter().any(|k| k.to_bytes() >= DONT_FRONT_START && k.to_bytes() < DONT_FRONT_END);
-                            if let Some(meta) = &tx.0.meta {
-                                tx_events.push(Event::Transaction(TransactionV2::new(
-                                    slot,
-                                    tx.0.index as u32,
-                                    bs58::encode(&tx.0.signature).into_string().into(),
-                                    meta.fee,
-                                    meta.compute_units_consumed.unwrap_or(0),
-                                    dont_front,
-                                )));
-                            } else {
-                                tx_events.push(Event::Transaction(TransactionV2::new(
-                                    slot,
-                                    tx.0.index as u32,
-                                    bs58::encode(&tx.0.signature).into_string().into(),
-                                    0,
-                                    0,
-                                    dont_front,
-                                )));
-                            }
-                        }
-                        events.extend(tx_events);
-                    });
+                    metrics::set("chain_tip_slot", slot);
+                    lut_cache.evict_deactivated(slot);
+                    let (events, failures) = events_from_block(&mut block, &FINDERS, &rpc_client, &lut_cache).await;
+                    if let Some(failure_log) = &failure_log {
+                        failure_log.record(failures);
+                    }
+                    if let Some(canary) = &canary {
+                        canary.lock().unwrap().observe(&block, &events);
+                    }
                     let event_len = events.len();
-                    tokio::spawn({
-                        let sender = sender.clone();
-                        async move {
-                            let _ = sender.send((slot, events.into())).await;
-                            println!("sent {} events from slot {}", event_len, slot);
-                        }
-                    });
-                }
-                Some(UpdateOneof::Account(account)) => {
-                    if let Some(account_info) = account.account {
-                        let lut = AddressLookupTable::deserialize(&account_info.data).expect("unable to deserialize account");
-                        let key = pubkey_from_slice(&account_info.pubkey[0..32]);
-                        // println!("lut updated: {:?}", key);
-                        // refuse to shorten luts
-                        if let Some(existing_entry) = lut_cache.get(&key) {
-                            let existing_len = existing_entry.addresses.len();
-                            if existing_len > lut.addresses.len() {
-                                continue;
-                            }
-                        }
-                        lut_cache.insert(key, AddressLookupTableAccount {
-                            key,
-                            addresses: lut.addresses.to_vec(),
-                        });
+                    if sender.send((slot, events.into())).await.is_err() {
+                        break;
                     }
+                    println!("sent {} events from slot {}", event_len, slot);
                 }
-                Some(UpdateOneof::Ping(_)) => {
-                    let _ = sink.send(SubscribeRequest {
-                        ping: Some(SubscribeRequestPing {id: 1}),
-                        ..Default::default()
-                    }).await;
+                BlockUpdate::LookupTable(account) => {
+                    lut_cache.apply_update(account);
                 }
                 _ => {}
             }
         }
-        println!("event processor grpc stream ended");
+        println!("event processor source ended");
     });
-    return receiver;
-}
\ No newline at end of file
+    receiver
+}
+
+/// Checks that tx indexes strictly increase along the block's transaction list. Some providers
+/// emit them out of order after filtering votes, in which case the ordering is re-derived from
+/// the list position. Returns the number of corrected indexes.
+/// A block filtered down to some programs has gaps in its indexes, so the list position isn't a tx's place in the
+/// block and its transactions are sorted by their original indexes instead.
+pub fn fix_tx_indexes(block: &mut SubscribeUpdateBlock) -> usize {
+    let consistent = block.transactions.windows(2).all(|w| w[0].index < w[1].index);
+    if consistent {
+        return 0;
+    }
+    if (block.transactions.len() as u64) < block.executed_transaction_count {
+        block.transactions.sort_by_key(|tx| tx.index);
+        println!("slot {} had out of order tx indexes in a filtered block, sorted them", block.slot);
+        metrics::incr("tx_index_sorted_blocks");
+        return 0;
+    }
+    let mut corrected = 0;
+    block.transactions.iter_mut().enumerate().for_each(|(i, tx)| {
+        if tx.index != i as u64 {
+            tx.index = i as u64;
+            corrected += 1;
+        }
+    });
+    println!("slot {} had inconsistent tx indexes, corrected {}", block.slot, corrected);
+    metrics::incr("tx_index_corrected_blocks");
+    metrics::add("tx_index_corrected_txs", corrected as u64);
+    corrected
+}
+
+/// The swap finders of the programs enabled in the [`config`]
+pub static FINDERS: LazyLock<FinderRegistry> = LazyLock::new(|| {
+    let mut finders = FinderRegistry::default();
+    finders.retain(|program| config::get().finder_enabled(&program.to_string()));
+    finders
+});
+
+/// Runs every finder in `finders` over the non-vote transactions of a block and returns the events found,
+/// in block order, along with the swaps whose amounts couldn't be extracted.
+pub async fn events_from_block(block: &mut SubscribeUpdateBlock, finders: &FinderRegistry, rpc_client: &BoundedRpc, lut_cache: &LutCache) -> (Vec<Event>, Vec<DecodeFailure>) {
+    fix_tx_indexes(block);
+    // println!("new block {}, {} txs", block.slot, block.transactions.len());
+    // let now = std::time::Instant::now();
+    let block_time = block.block_time.as_ref().map(|t| t.timestamp);
+    // blocks filtered down to the finders' programs only have the count of what was left out
+    let filtered = (block.transactions.len() as u64) < block.executed_transaction_count;
+    let block_tx_count = block.transactions.len().max(block.executed_transaction_count as usize) as u32;
+    let block_cu: Option<u64> = (!filtered).then(|| block.transactions.iter().filter_map(|tx| tx.meta.as_ref()?.compute_units_consumed).sum());
+    let slot = block.slot;
+    let futs = block.transactions.iter().filter_map(|tx| {
+        if tx.is_vote {
+            None
+        } else {
+            Some(decompile_tx(tx, rpc_client, lut_cache))
+        }
+    }).collect::<Vec<_>>();
+    let joined_futs = futures::future::join_all(futs).await;
+    let block_txs = joined_futs.iter().filter_map(|tx| {
+        if let Some(tx) = tx {
+            Some(tx)
+        } else {
+            None
+        }
+    }).collect::<Vec<_>>();
+    // let swap_count = block_txs.iter().map(|tx| tx.swaps().len()).sum::<usize>();
+    // block_txs.sort_by_key(|x| x.order());
+    let mut events = vec![];
+    let mut failures = vec![];
+    block_txs.iter().for_each(|tx| {
+        // println!("processing tx {} in slot {}", bs58::encode(&tx.0.signature).into_string(), slot);
+        let mut swaps = finders.find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2, &tx.3);
+        apply_swap_events_in_tx(&mut swaps, tx.0, &tx.2);
+        failures.extend(decode_failures(tx.0, &tx.1, &tx.2, &swaps));
+        let pools = PumpFunSwapFinder::find_pool_creations_in_tx(slot, tx.0, &tx.1, &tx.2, &swaps);
+        let swaps: Vec<Event> = swaps.into_iter().map(|s| Event::Swap(s)).collect();
+        let transfers: Vec<Event> = [
+            SystemProgramTransferfinder::find_transfers_in_tx(slot, tx.0, &tx.1, &tx.2, &tx.3),
+            TokenProgramTransferFinder::find_transfers_in_tx(slot, tx.0, &tx.1, &tx.2, &tx.3),
+            StakeProgramTransferfinder::find_transfers_in_tx(slot, tx.0, &tx.1, &tx.2, &tx.3),
+        ].concat().into_iter().map(|t| Event::Transfer(t)).collect();
+        if swaps.is_empty() {
+            let swaps = Discoverer::find_swaps_in_tx(slot, tx.0, &tx.1, &tx.2, &tx.3);
+            if !swaps.is_empty() {
+                println!("[Discoverer] tx {} ix #{} in slot {} triggered program {}", bs58::encode(&tx.0.signature).into_string(), swaps[0].ix_index(), slot, swaps[0].program());
+                debug_println!("{:?}", &tx);
+            }
+        }
+        let mut tx_events = swaps;
+        tx_events.extend(transfers);
+        tx_events.extend(pools.into_iter().map(Event::PoolCreated));
+        // println!("found {} swaps in slot {} tx {}", swaps.len(), slot, bs58::encode(&tx.0.signature).into_string());
+        // println!("found {} transfers in slot {} tx {}", transfers.len(), slot, bs58::encode(&tx.0.signature).into_string());
+        // println!("{:?}", swaps);
+        if tx_events.len() > 0 {
+            let dont_front = DONT_FRONT.any(&tx.2);
+            let mut transaction = if let Some(meta) = &tx.0.meta {
+                TransactionV2::new(
+                    slot,
+                    tx.0.index as u32,
+                    bs58::encode(&tx.0.signature).into_string().into(),
+                    meta.fee,
+                    meta.compute_units_consumed.unwrap_or(0),
+                    dont_front,
+                )
+            } else {
+                TransactionV2::new(
+                    slot,
+                    tx.0.index as u32,
+                    bs58::encode(&tx.0.signature).into_string().into(),
+                    0,
+                    0,
+                    dont_front,
+                )
+            };
+            transaction.set_block_tx_count(Some(block_tx_count));
+            transaction.set_cu_limit(Some(cu_limit_from_ixs(&tx.1)));
+            transaction.set_block_cu(block_cu);
+            tx_events.push(Event::Transaction(transaction));
+        }
+        events.extend(tx_events);
+    });
+    events.iter_mut().for_each(|e| e.set_block_time(block_time));
+    (events, failures)
+}
+
+#[cfg(test)]
+mod tests {
+    use yellowstone_grpc_proto::geyser::SubscribeUpdateTransactionInfo;
+
+    use super::*;
+
+    fn block(indexes: &[u64], executed_transaction_count: u64) -> SubscribeUpdateBlock {
+        SubscribeUpdateBlock {
+            slot: 1,
+            executed_transaction_count,
+            transactions: indexes.iter().map(|&index| SubscribeUpdateTransactionInfo { index, ..Default::default() }).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_fix_tx_indexes() {
+        let indexes = |block: &SubscribeUpdateBlock| block.transactions.iter().map(|tx| tx.index).collect::<Vec<_>>();
+        let mut in_order = block(&[0, 1, 2], 3);
+        assert_eq!(fix_tx_indexes(&mut in_order), 0);
+        assert_eq!(indexes(&in_order), vec![0, 1, 2]);
+        // out of order, renumbered from the list position
+        let mut out_of_order = block(&[1, 0, 2, 4, 3], 5);
+        assert_eq!(fix_tx_indexes(&mut out_of_order), 4);
+        assert_eq!(indexes(&out_of_order), vec![0, 1, 2, 3, 4]);
+        // missing indexes come through as 0
+        let mut missing = block(&[0, 0, 0, 3], 4);
+        assert_eq!(fix_tx_indexes(&mut missing), 2);
+        assert_eq!(indexes(&missing), vec![0, 1, 2, 3]);
+        // gaps alone are left as they are while the indexes still increase
+        let mut gaps = block(&[0, 2, 5], 3);
+        assert_eq!(fix_tx_indexes(&mut gaps), 0);
+        assert_eq!(indexes(&gaps), vec![0, 2, 5]);
+    }
+
+    #[test]
+    fn test_fix_tx_indexes_filtered() {
+        // 3 of 10 txs, out of order, keep their indexes
+        let mut filtered = block(&[7, 2, 5], 10);
+        assert_eq!(fix_tx_indexes(&mut filtered), 0);
+        assert_eq!(filtered.transactions.iter().map(|tx| tx.index).collect::<Vec<_>>(), vec![2, 5, 7]);
+    }
+}
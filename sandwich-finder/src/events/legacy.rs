@@ -0,0 +1,122 @@
+//! Conversion from the legacy finder's [`Sandwich`] to [`SandwichCandidate`], so endpoints can serve either schema
+//! from the same detection result while consumers move over to the V2 one.
+
+use std::{collections::HashSet, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{events::{sandwich::SandwichCandidate, swap::SwapV2, transaction::TransactionV2}, utils::{Sandwich, Swap}};
+
+/// Schema of the sandwiches an endpoint returns, picked with `?format=v1|v2`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SandwichFormat {
+    #[default]
+    V1,
+    V2,
+}
+
+/// A sandwich in the format the client asked for
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum SandwichMessage {
+    V1(Box<Sandwich>),
+    V2(SandwichCandidate),
+}
+
+impl SandwichMessage {
+    pub fn new(sandwich: Sandwich, format: SandwichFormat) -> Self {
+        match format {
+            SandwichFormat::V1 => Self::V1(Box::new(sandwich)),
+            SandwichFormat::V2 => Self::V2((&sandwich).into()),
+        }
+    }
+}
+
+/// Legacy swaps aren't stored as events, so their ids are made up from their position in the chain instead.
+/// This keeps the uuids of converted sandwiches stable and distinct, but they never match the ids of indexed events.
+fn legacy_event_id(slot: u64, order: u64) -> u64 {
+    (slot << 20) | (order & 0xfffff)
+}
+
+fn swap_v2(slot: u64, ts: i64, swap: &Swap) -> SwapV2 {
+    // the legacy finder doesn't keep token accounts or instruction positions, those are left blank
+    let mut swap_v2 = SwapV2::new(
+        swap.outer_program().as_deref().map(Into::into),
+        swap.program().as_str().into(),
+        swap.signer().as_str().into(),
+        swap.amm().as_str().into(),
+        swap.input_mint().as_str().into(),
+        swap.output_mint().as_str().into(),
+        *swap.input_amount(),
+        *swap.output_amount(),
+        "".into(),
+        "".into(),
+        None,
+        None,
+        slot,
+        *swap.order() as u32,
+        0,
+        None,
+        legacy_event_id(slot, *swap.order()),
+    );
+    swap_v2.set_block_time(Some(ts));
+    swap_v2
+}
+
+impl From<&Sandwich> for SandwichCandidate {
+    fn from(sandwich: &Sandwich) -> Self {
+        let (slot, ts) = (*sandwich.slot(), *sandwich.ts());
+        let swaps = [sandwich.frontrun()].into_iter().chain(sandwich.victim().iter()).chain([sandwich.backrun()]);
+        let mut seen = HashSet::new();
+        // fees and compute aren't known to the legacy finder
+        let txs = swaps.filter(|s| seen.insert(s.sig().as_str())).map(|s| {
+            let mut tx = TransactionV2::new(slot, *s.order() as u32, Arc::from(s.sig().as_str()), 0, 0, *s.dont_front());
+            tx.set_block_time(Some(ts));
+            tx
+        }).collect();
+        SandwichCandidate::from_parts(
+            vec![swap_v2(slot, ts, sandwich.frontrun())],
+            sandwich.victim().iter().map(|v| swap_v2(slot, ts, v)).collect(),
+            vec![swap_v2(slot, ts, sandwich.backrun())],
+            vec![],
+            txs,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn swap(order: u64, signer: &str, buy: bool, input_amount: u64, output_amount: u64) -> Swap {
+        let (input_mint, output_mint) = if buy { ("sol", "token") } else { ("token", "sol") };
+        Swap::new(Some("router".to_string()), "program".to_string(), "amm".to_string(), signer.to_string(), signer.to_string(), input_mint.to_string(), output_mint.to_string(), input_amount, output_amount, order, format!("sig{order}"), order == 2)
+    }
+
+    #[test]
+    fn test_convert_legacy_sandwich() {
+        let sandwich = Sandwich::new(100, swap(1, "attacker", true, 1000, 900), vec![swap(2, "victim", true, 1000, 800)], swap(3, "attacker", false, 900, 1100), 1_700_000_000);
+        let candidate = SandwichCandidate::from(&sandwich);
+        assert_eq!(candidate.slot(), 100);
+        assert_eq!(candidate.attacker().as_ref(), "attacker");
+        assert_eq!((*candidate.victim()[0].inclusion_order(), candidate.victim()[0].authority().as_ref()), (2, "victim"));
+        assert_eq!(candidate.txs().len(), 3);
+        assert_eq!(candidate.dont_front_victims().len(), 1);
+        assert_eq!(candidate.estimate_victim_losses(), sandwich.estimate_victim_losses());
+        assert_eq!(candidate.uuid(), SandwichCandidate::from(&sandwich).uuid());
+
+        let v1 = serde_json::to_value(SandwichMessage::new(sandwich.clone(), SandwichFormat::V1)).unwrap();
+        assert_eq!(v1["frontrun"]["signer"], "attacker");
+        assert!(v1.get("id").is_none());
+        let v2 = serde_json::to_value(SandwichMessage::new(sandwich, SandwichFormat::V2)).unwrap();
+        assert_eq!(v2["id"], candidate.uuid().to_string());
+        assert_eq!(v2["victim"][0]["authority"], "victim");
+        assert_eq!(v2["txs"][1]["dontFront"], true);
+        // the victim got 800 where it'd have got 800 + output_amount without the frontrun
+        let loss = candidate.estimate_victim_losses()[0];
+        assert!(*loss.output_amount() > 0);
+        assert_eq!(*loss.price_impact_bps(), loss.output_amount() * 10000 / (loss.output_amount() + 800));
+        assert_eq!(v2["victimLosses"][0]["priceImpactBps"], *loss.price_impact_bps());
+    }
+}
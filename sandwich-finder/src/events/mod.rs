@@ -1,9 +1,15 @@
-pub mod addresses;
+pub use sandwich_finder_core::{addresses, dont_front, pool, swap, swaps, token_accounts, transfer, transfers};
+
+pub mod anomaly;
+pub mod backrun;
+pub mod cohort;
 pub mod common;
 pub mod event;
+pub mod legacy;
+pub mod replay;
 pub mod sandwich;
-pub mod swap;
-pub mod swaps;
+pub mod snipe;
+pub mod sol_mints;
+pub mod stable;
 pub mod transaction;
-pub mod transfer;
-pub mod transfers;
\ No newline at end of file
+pub mod wash;
\ No newline at end of file
@@ -0,0 +1,219 @@
+use std::io::{ErrorKind, Read};
+
+use thiserror::Error;
+use yellowstone_grpc_proto::{geyser::{subscribe_update::UpdateOneof, SubscribeUpdate, SubscribeUpdateBlock}, prost::{self, Message as _}};
+
+/// Magic bytes at the start of a versioned block dump.
+/// Dumps without the magic are treated as [`DumpVersion::Legacy`].
+pub const DUMP_MAGIC: [u8; 4] = *b"SFBD";
+
+/// Upper bound on a single encoded message, same as the grpc client's decoding limit.
+const MAX_MESSAGE_SIZE: usize = 128 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("unsupported dump version {0}")]
+    UnsupportedVersion(u8),
+    #[error("malformed length prefix")]
+    InvalidLength,
+    #[error("message of {0} bytes exceeds the size limit")]
+    MessageTooLarge(usize),
+    #[error("protobuf decode error: {0}")]
+    Decode(#[from] prost::DecodeError),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DumpVersion {
+    /// Raw stream of varint length-delimited `SubscribeUpdate` messages, as recorded by
+    /// older builds straight off the grpc stream. Blocks may predate fields such as
+    /// `SubscribeUpdateTransactionInfo.index` and `executed_transaction_count`.
+    Legacy,
+    /// `DUMP_MAGIC`, a version byte, then varint length-delimited `SubscribeUpdate` messages
+    /// carrying the current block schema.
+    V1,
+}
+
+impl DumpVersion {
+    pub const LATEST: DumpVersion = DumpVersion::V1;
+
+    fn from_byte(version: u8) -> Result<Self, ReplayError> {
+        match version {
+            1 => Ok(DumpVersion::V1),
+            _ => Err(ReplayError::UnsupportedVersion(version)),
+        }
+    }
+}
+
+/// Reads `SubscribeUpdate` messages out of a block dump, upgrading blocks written with older
+/// encodings so they look like what the grpc stream produces today.
+pub struct DumpReader<R: Read> {
+    reader: R,
+    version: DumpVersion,
+    /// Bytes consumed while sniffing for the header that belong to the first message.
+    pending: Vec<u8>,
+}
+
+impl<R: Read> DumpReader<R> {
+    pub fn new(mut reader: R) -> Result<Self, ReplayError> {
+        let mut head = Vec::with_capacity(DUMP_MAGIC.len());
+        (&mut reader).take(DUMP_MAGIC.len() as u64).read_to_end(&mut head)?;
+        if head == DUMP_MAGIC {
+            let mut version = [0u8; 1];
+            reader.read_exact(&mut version)?;
+            Ok(Self { reader, version: DumpVersion::from_byte(version[0])?, pending: vec![] })
+        } else {
+            Ok(Self { reader, version: DumpVersion::Legacy, pending: head })
+        }
+    }
+
+    pub fn version(&self) -> DumpVersion {
+        self.version
+    }
+
+    fn read_byte(&mut self) -> Result<Option<u8>, ReplayError> {
+        if !self.pending.is_empty() {
+            return Ok(Some(self.pending.remove(0)));
+        }
+        let mut byte = [0u8; 1];
+        match self.reader.read_exact(&mut byte) {
+            Ok(()) => Ok(Some(byte[0])),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Reads the varint length prefix of the next message, `None` on a clean end of file.
+    fn read_len(&mut self) -> Result<Option<usize>, ReplayError> {
+        let mut len = 0u64;
+        for i in 0..10 {
+            let byte = match self.read_byte()? {
+                Some(byte) => byte,
+                None if i == 0 => return Ok(None),
+                None => return Err(ReplayError::InvalidLength),
+            };
+            len |= ((byte & 0x7f) as u64) << (7 * i);
+            if byte & 0x80 == 0 {
+                return Ok(Some(len as usize));
+            }
+        }
+        Err(ReplayError::InvalidLength)
+    }
+
+    pub fn next_update(&mut self) -> Result<Option<SubscribeUpdate>, ReplayError> {
+        let len = match self.read_len()? {
+            Some(len) => len,
+            None => return Ok(None),
+        };
+        if len > MAX_MESSAGE_SIZE {
+            return Err(ReplayError::MessageTooLarge(len));
+        }
+        let mut buf = Vec::with_capacity(len);
+        let from_pending = len.min(self.pending.len());
+        buf.extend(self.pending.drain(..from_pending));
+        buf.resize(len, 0);
+        self.reader.read_exact(&mut buf[from_pending..])?;
+        let mut update = SubscribeUpdate::decode(buf.as_slice())?;
+        if let Some(UpdateOneof::Block(block)) = &mut update.update_oneof {
+            upgrade_block(block, self.version);
+        }
+        Ok(Some(update))
+    }
+}
+
+impl<R: Read> Iterator for DumpReader<R> {
+    type Item = Result<SubscribeUpdate, ReplayError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_update().transpose()
+    }
+}
+
+/// Fills in fields that older `SubscribeUpdateBlock` encodings didn't carry. Missing fields
+/// decode to their defaults, so they're recomputed from what the block does contain.
+pub fn upgrade_block(block: &mut SubscribeUpdateBlock, version: DumpVersion) {
+    if version == DumpVersion::Legacy {
+        // tx index was added after the first dumps were taken, blocks from back then
+        // contain every tx in execution order so the position is the index
+        if block.transactions.len() > 1 && block.transactions.iter().all(|tx| tx.index == 0) {
+            block.transactions.iter_mut().enumerate().for_each(|(i, tx)| tx.index = i as u64);
+        }
+    }
+    if block.executed_transaction_count == 0 {
+        block.executed_transaction_count = block.transactions.len() as u64;
+    }
+    if block.updated_account_count == 0 {
+        block.updated_account_count = block.accounts.len() as u64;
+    }
+    if block.entries_count == 0 {
+        block.entries_count = block.entries.len() as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use yellowstone_grpc_proto::geyser::SubscribeUpdateTransactionInfo;
+
+    use super::*;
+
+    fn block(txs: usize) -> SubscribeUpdate {
+        SubscribeUpdate {
+            filters: vec![],
+            created_at: None,
+            update_oneof: Some(UpdateOneof::Block(SubscribeUpdateBlock {
+                slot: 42,
+                transactions: (0..txs).map(|i| SubscribeUpdateTransactionInfo {
+                    signature: vec![i as u8; 64],
+                    ..Default::default()
+                }).collect(),
+                ..Default::default()
+            })),
+        }
+    }
+
+    fn unwrap_block(update: SubscribeUpdate) -> SubscribeUpdateBlock {
+        match update.update_oneof {
+            Some(UpdateOneof::Block(block)) => block,
+            _ => panic!("not a block"),
+        }
+    }
+
+    #[test]
+    fn test_legacy_dump_upgrade() {
+        let mut buf = vec![];
+        block(3).encode_length_delimited(&mut buf).unwrap();
+        block(1).encode_length_delimited(&mut buf).unwrap();
+        let mut reader = DumpReader::new(buf.as_slice()).unwrap();
+        assert_eq!(reader.version(), DumpVersion::Legacy);
+        let first = unwrap_block(reader.next_update().unwrap().unwrap());
+        assert_eq!(first.transactions.iter().map(|tx| tx.index).collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(first.executed_transaction_count, 3);
+        let second = unwrap_block(reader.next_update().unwrap().unwrap());
+        assert_eq!(second.transactions.len(), 1);
+        assert!(reader.next_update().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_v1_dump_keeps_indexes() {
+        let mut buf = DUMP_MAGIC.to_vec();
+        buf.push(1);
+        let mut update = block(2);
+        if let Some(UpdateOneof::Block(block)) = &mut update.update_oneof {
+            block.transactions[0].index = 7;
+            block.transactions[1].index = 9;
+        }
+        update.encode_length_delimited(&mut buf).unwrap();
+        let mut reader = DumpReader::new(buf.as_slice()).unwrap();
+        assert_eq!(reader.version(), DumpVersion::V1);
+        let block = unwrap_block(reader.next_update().unwrap().unwrap());
+        assert_eq!(block.transactions.iter().map(|tx| tx.index).collect::<Vec<_>>(), vec![7, 9]);
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let mut buf = DUMP_MAGIC.to_vec();
+        buf.push(99);
+        assert!(matches!(DumpReader::new(buf.as_slice()), Err(ReplayError::UnsupportedVersion(99))));
+    }
+}
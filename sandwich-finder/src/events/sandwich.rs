@@ -1,10 +1,12 @@
-use std::{cmp::Reverse, collections::{HashMap, HashSet}, sync::Arc};
+use std::{cmp::Reverse, collections::{BTreeMap, HashMap, HashSet}, fmt, ops::Range, sync::Arc};
 
 use derive_getters::Getters;
-use solana_sdk::pubkey::Pubkey;
+use serde::{ser::SerializeStruct as _, Serialize, Serializer};
+use solana_sdk::{clock::NUM_CONSECUTIVE_LEADER_SLOTS, pubkey::Pubkey};
 use thiserror::Error;
+use uuid::Uuid;
 
-use crate::events::{addresses::is_known_aggregator, swap::SwapV2, transaction::TransactionV2, transfer::TransferV2};
+use crate::{events::{addresses::{is_known_aggregator, WSOL_MINT}, swap::SwapV2, transaction::TransactionV2, transfer::TransferV2}, metrics, utils::{estimate_victim_losses, prefixed_env_var, VictimLoss}};
 
 #[derive(Debug, Error)]
 pub enum SandwichError {
@@ -22,10 +24,215 @@ pub enum SandwichError {
     InvalidVictim,
     #[error("Transfers don't connect frontrun output ATAs to backrun input ATAs entirely")]
     InvalidTransfers,
-    #[error("The sandwich is not strictly profitable")]
+    #[error("All victims are signed by a frontrun/backrun wallet")]
+    SelfOverlap,
+    /// Profits in token A/B net of the tolerance
+    #[error("The sandwich is not profitable within the tolerance")]
     NonProfitable(i128, i128),
 }
 
+impl SandwichError {
+    /// Counter the rejection is exported as
+    pub fn metric_name(&self) -> &'static str {
+        match self {
+            Self::InvalidFrontrun => "sandwich_rejected_invalid_frontrun",
+            Self::InvalidBackrun => "sandwich_rejected_invalid_backrun",
+            Self::MissingWrapperProgram => "sandwich_rejected_missing_wrapper_program",
+            Self::FrontrunBackrunPairMismatch => "sandwich_rejected_pair_mismatch",
+            Self::FrontrunBackrunWrapperMismatch => "sandwich_rejected_wrapper_mismatch",
+            Self::InvalidVictim => "sandwich_rejected_invalid_victim",
+            Self::InvalidTransfers => "sandwich_rejected_invalid_transfers",
+            Self::SelfOverlap => "sandwich_rejected_self_overlap",
+            Self::NonProfitable(..) => "sandwich_rejected_non_profitable",
+        }
+    }
+}
+
+/// Candidates `detect()` turned down, per [`SandwichError`] variant
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RejectionStats(BTreeMap<&'static str, u64>);
+
+impl RejectionStats {
+    pub fn record(&mut self, error: &SandwichError) {
+        *self.0.entry(error.metric_name()).or_default() += 1;
+    }
+
+    pub fn get(&self, metric_name: &str) -> u64 {
+        self.0.get(metric_name).copied().unwrap_or(0)
+    }
+
+    pub fn total(&self) -> u64 {
+        self.0.values().sum()
+    }
+
+    /// Adds the counts to the process-wide metrics
+    pub fn export_metrics(&self) {
+        for (name, count) in self.0.iter() {
+            metrics::add(name, *count);
+        }
+    }
+}
+
+impl fmt::Display for RejectionStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let counts: Vec<_> = self.0.iter().map(|(name, count)| format!("{}={}", name.trim_start_matches("sandwich_rejected_"), count)).collect();
+        f.write_str(&counts.join(" "))
+    }
+}
+
+/// How `detect()` picks among the candidates found around the same victim
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SelectionPolicy {
+    /// Most victims, then most swaps, ties going to same-slot candidates and then the tightest span
+    #[default]
+    MostVictims,
+    /// Same-slot candidates first, then most victims, then the tightest span, then most swaps.
+    /// Keeps a 3-tx bundle from losing out to a sprawling cross-slot candidate that happens to pick up more victims.
+    Hybrid,
+}
+
+impl SelectionPolicy {
+    /// Reads `SANDWICH_SELECTION` (`most-victims` or `hybrid`), falling back to the default
+    pub fn from_env() -> Self {
+        Self::from_env_with_prefix("")
+    }
+
+    /// Like [`SelectionPolicy::from_env`], preferring the variable with `prefix` prepended
+    pub fn from_env_with_prefix(prefix: &str) -> Self {
+        match prefixed_env_var(prefix, "SANDWICH_SELECTION").as_deref() {
+            Some("most-victims") => Self::MostVictims,
+            Some("hybrid") => Self::Hybrid,
+            _ => Self::default(),
+        }
+    }
+}
+
+/// How far below break-even a leg may end up and still count as profitable, for fee-on-transfer tokens
+/// where the backrun gets back slightly less than the frontrun spent in raw units.
+#[derive(Clone, Debug, Default)]
+pub struct ProfitTolerance {
+    /// Applies to every mint without an override
+    pub default_bps: u64,
+    pub per_mint_bps: HashMap<Arc<str>, u64>,
+}
+
+impl ProfitTolerance {
+    /// Reads `PROFIT_TOLERANCE_BPS` and `PROFIT_TOLERANCE_MINTS` (`mint:bps,mint:bps`), both default to no tolerance
+    pub fn from_env() -> Self {
+        Self::from_env_with_prefix("")
+    }
+
+    /// Like [`ProfitTolerance::from_env`], preferring the variables with `prefix` prepended
+    pub fn from_env_with_prefix(prefix: &str) -> Self {
+        let default_bps = prefixed_env_var(prefix, "PROFIT_TOLERANCE_BPS").and_then(|v| v.parse().ok()).unwrap_or(0);
+        let per_mint_bps = prefixed_env_var(prefix, "PROFIT_TOLERANCE_MINTS").unwrap_or_default()
+            .split(',')
+            .filter_map(|entry| {
+                let (mint, bps) = entry.trim().split_once(':')?;
+                Some((mint.into(), bps.parse().ok()?))
+            })
+            .collect();
+        Self { default_bps, per_mint_bps }
+    }
+
+    pub fn bps(&self, mint: &str) -> u64 {
+        self.per_mint_bps.get(mint).copied().unwrap_or(self.default_bps)
+    }
+
+    /// How much of `mint` may be lost relative to `amount`
+    pub fn allowance(&self, mint: &str, amount: i128) -> i128 {
+        amount * self.bps(mint) as i128 / 10000
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SandwichConfig {
+    pub selection: SelectionPolicy,
+    pub tolerance: ProfitTolerance,
+    /// Keep victims signed by an attacker wallet as suspected wash trades instead of dropping them
+    pub flag_wash: bool,
+    /// Let swaps by an attacker wallet in a frontrun/backrun tx link the legs, for attackers holding another mint in between,
+    /// e.g. converting the frontrun's SOL to USDC and back before the backrun. Unrelated trades of theirs can link them too.
+    pub link_conversions: bool,
+}
+
+impl SandwichConfig {
+    /// See [`SelectionPolicy::from_env`] and [`ProfitTolerance::from_env`], `FLAG_SUSPECTED_WASH=1` turns on `flag_wash`
+    /// and `LINK_CONVERSIONS=1` `link_conversions`
+    pub fn from_env() -> Self {
+        Self::from_env_with_prefix("")
+    }
+
+    /// Like [`SandwichConfig::from_env`], preferring the variables with `prefix` prepended
+    pub fn from_env_with_prefix(prefix: &str) -> Self {
+        Self {
+            selection: SelectionPolicy::from_env_with_prefix(prefix),
+            tolerance: ProfitTolerance::from_env_with_prefix(prefix),
+            flag_wash: prefixed_env_var(prefix, "FLAG_SUSPECTED_WASH").is_some_and(|v| v == "1" || v == "true"),
+            link_conversions: prefixed_env_var(prefix, "LINK_CONVERSIONS").is_some_and(|v| v == "1" || v == "true"),
+        }
+    }
+}
+
+/// Distance between the first frontrun and the last backrun
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Getters)]
+pub struct SandwichSpan {
+    slots: u64,
+    /// Inclusion order distance, only comparable between spans with the same number of slots
+    inclusion_orders: u32,
+}
+
+/// Where the legs landed in the leader's window of consecutive slots. Legs a slot or more apart mean the attacker saw the
+/// victim's tx before it landed, which a leader consistently hosting them is likely leaking.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Getters)]
+pub struct SandwichTiming {
+    /// Which of the window's slots the first frontrun, first victim and last backrun landed in, from 0
+    frontrun_offset: u8,
+    victim_offset: u8,
+    backrun_offset: u8,
+    /// Slots from the first frontrun to the first victim, and from the last victim to the last backrun
+    frontrun_victim_slots: u64,
+    victim_backrun_slots: u64,
+}
+
+impl SandwichTiming {
+    /// Leader windows are aligned to the epoch, whose lengths are multiples of the window
+    fn offset(slot: u64) -> u8 {
+        (slot % NUM_CONSECUTIVE_LEADER_SLOTS) as u8
+    }
+}
+
+/// Liquidity of the attacked pool just before the first frontrun, from the reserves the frontrun's tx left behind
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Getters)]
+pub struct PoolTvl {
+    /// In the mint the frontrun paid in
+    reserve_a: u64,
+    /// In the mint the frontrun received
+    reserve_b: u64,
+    /// Both reserves in lamports at the frontrun's price, `None` unless the pair is priced in SOL
+    tvl_lamports: Option<u64>,
+}
+
+impl PoolTvl {
+    fn before(frontrun: &SwapV2) -> Option<Self> {
+        let (input_amount, output_amount) = (*frontrun.input_amount(), *frontrun.output_amount());
+        let (reserve_a, reserve_b) = frontrun.pool_reserves().before_swap(input_amount, output_amount)?;
+        let wsol = WSOL_MINT.to_string();
+        let tvl_lamports = if frontrun.input_mint().as_ref() == wsol && output_amount > 0 {
+            Some(reserve_a as u128 + reserve_b as u128 * input_amount as u128 / output_amount as u128)
+        } else if frontrun.output_mint().as_ref() == wsol && input_amount > 0 {
+            Some(reserve_b as u128 + reserve_a as u128 * output_amount as u128 / input_amount as u128)
+        } else {
+            None
+        };
+        Some(Self {
+            reserve_a,
+            reserve_b,
+            tvl_lamports: tvl_lamports.map(|tvl| tvl.min(u64::MAX as u128) as u64),
+        })
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Getters)]
 pub struct TradePair {
     amm: Arc<str>,
@@ -60,17 +267,27 @@ impl TradePair {
 /// Additionally, the profitability constraint is that
 /// - # of tokens spent in step 1 <= # of tokens received in step 5
 /// - # of tokens received in step 1 >= # of tokens spent in step 5
+///
+/// Either side may fall short by the [`ProfitTolerance`] of its mint.
 /// 
 /// And obviously, the swapping steps must use the same AMM.
-/// To reduce false positives, steps 1 and 5 must use the same non null non well-known aggregator outer program,
+/// To reduce false positives, steps 1 and 5 must use the same non null non well-known aggregator wrapper program,
+/// the wrapper being the program that directly invoked the AMM, see [`SwapV2::wrapper_program`],
 /// the justification being well-known aggregators aren't designed for sandwichers to keep track of their tokens across txs.
 /// Victim swaps also can't use the same wrapper program as the frontrun/backrun swaps.
+/// Victims signed by a frontrun/backrun wallet are the bot trading with itself, they're dropped,
+/// or kept apart as suspected wash trades if [`SandwichConfig::flag_wash`] is set.
+/// With [`SandwichConfig::link_conversions`], the attacker's swaps in the frontrun/backrun txs can stand in for transfers
+/// in steps 2 and 4, when the attacker holds another mint in between.
 #[derive(Clone, Debug, Getters)]
 pub struct SandwichCandidate {
     frontrun: Arc<[SwapV2]>,
     victim: Arc<[SwapV2]>,
     backrun: Arc<[SwapV2]>,
+    suspected_wash: Arc<[SwapV2]>,
     transfers: Arc<[TransferV2]>,
+    /// Swaps linking the frontrun's output to the backrun's input along with `transfers`
+    conversions: Arc<[SwapV2]>,
     txs: Arc<[TransactionV2]>,
 }
 
@@ -84,22 +301,62 @@ fn pair_from_swaps(swaps: &[SwapV2], check_wrapper: bool) -> Option<(Option<Arc<
         input_mint: first.input_mint().clone(),
         output_mint: first.output_mint().clone(),
     };
-    let outer_program = if check_wrapper { first.outer_program().clone() } else { None };
+    let wrapper = if check_wrapper { first.wrapper_program().cloned() } else { None };
     for swap in swaps.iter() {
         let swap_pair = TradePair {
             amm: swap.amm().clone(),
             input_mint: swap.input_mint().clone(),
             output_mint: swap.output_mint().clone(),
         };
-        if swap_pair != pair || (swap.outer_program() != &outer_program && check_wrapper) {
+        if swap_pair != pair || (swap.wrapper_program() != wrapper.as_ref() && check_wrapper) {
             return None;
         }
     }
-    Some((outer_program, pair))
+    Some((wrapper, pair))
+}
+
+/// Every ATA reachable from `start` along the (input, output) `edges`, following them backwards unless `forward`
+fn reachable<'a>(edges: &[(&'a Arc<str>, &'a Arc<str>)], start: HashSet<&'a Arc<str>>, forward: bool) -> HashSet<&'a Arc<str>> {
+    let mut reached = start;
+    loop {
+        let before = reached.len();
+        for (input, output) in edges.iter() {
+            let (a, b) = if forward { (input, output) } else { (output, input) };
+            if reached.contains(*a) {
+                reached.insert(*b);
+            }
+        }
+        if reached.len() == before {
+            return reached;
+        }
+    }
+}
+
+/// The conversions, and the transfers between them, chaining every ATA in `from` to one in `to` and every ATA in `to`
+/// to one in `from`. `None` if some can't be, or if no conversion is needed to, plain transfers are checked on their own.
+fn conversion_links(from: &HashSet<&Arc<str>>, to: &HashSet<&Arc<str>>, conversions: &[SwapV2], transfers: &[TransferV2]) -> Option<(Vec<SwapV2>, Vec<TransferV2>)> {
+    let nodes = conversions.iter().flat_map(|c| [c.input_ata(), c.output_ata()]).chain(from.iter().copied()).chain(to.iter().copied()).collect::<HashSet<_>>();
+    let transfers = transfers.iter().filter(|t| nodes.contains(t.input_ata()) && nodes.contains(t.output_ata())).collect::<Vec<_>>();
+    let edges = conversions.iter().map(|c| (c.input_ata(), c.output_ata()))
+        .chain(transfers.iter().map(|t| (t.input_ata(), t.output_ata())))
+        .collect::<Vec<_>>();
+    let linked = |atas: &HashSet<&Arc<str>>, others: &HashSet<&Arc<str>>, forward: bool| {
+        atas.iter().all(|ata| reachable(&edges, HashSet::from([*ata]), forward).iter().any(|reached| others.contains(reached)))
+    };
+    if !linked(from, to, true) || !linked(to, from, false) {
+        return None;
+    }
+    // only what's on a path from `from` to `to`
+    let (downstream, upstream) = (reachable(&edges, from.clone(), true), reachable(&edges, to.clone(), false));
+    let on_path = |input: &Arc<str>, output: &Arc<str>| downstream.contains(input) && upstream.contains(output);
+    let conversions = conversions.iter().filter(|c| on_path(c.input_ata(), c.output_ata())).cloned().collect::<Vec<_>>();
+    let transfers = transfers.into_iter().filter(|t| on_path(t.input_ata(), t.output_ata())).cloned().collect();
+    (!conversions.is_empty()).then_some((conversions, transfers))
 }
 
 impl SandwichCandidate {
-    pub fn new(frontrun: &[SwapV2], victim: &[SwapV2], backrun: &[SwapV2], transfers: &[TransferV2], txs: &[TransactionV2]) -> Result<Self, SandwichError> {
+    /// `swaps` are where conversions are looked for, see [`SandwichConfig::link_conversions`]
+    pub fn new(frontrun: &[SwapV2], victim: &[SwapV2], backrun: &[SwapV2], swaps: &[SwapV2], transfers: &[TransferV2], txs: &[TransactionV2], config: &SandwichConfig) -> Result<Self, SandwichError> {
         // Sanity checks
         // {Front/back}run directions check - all frontrun swaps has the same pair and the reverse pair for the backrun swaps
         let (frontrun_wrapper, frontrun_pair) = pair_from_swaps(frontrun, true).ok_or(SandwichError::InvalidFrontrun)?;
@@ -114,41 +371,287 @@ impl SandwichCandidate {
         let (_, victim_pair) = pair_from_swaps(victim, false).ok_or(SandwichError::InvalidVictim)?;
         (victim_pair == frontrun_pair).then_some(()).ok_or(SandwichError::InvalidVictim)?;
         // Victim wrapper check - must not share the same wrapper program as the frontrun/backrun unless it's None
-        victim.iter().all(|s| s.outer_program().is_none() || s.outer_program() != &frontrun_wrapper).then_some(()).ok_or(SandwichError::InvalidVictim)?;
+        victim.iter().all(|s| s.wrapper_program().is_none() || s.wrapper_program() != frontrun_wrapper.as_ref()).then_some(()).ok_or(SandwichError::InvalidVictim)?;
         // Profitability check
         let frontrun_spent = frontrun.iter().map(|s| *s.input_amount() as i128).sum::<i128>();
         let frontrun_received = frontrun.iter().map(|s| *s.output_amount() as i128).sum::<i128>();
         let backrun_spent = backrun.iter().map(|s| *s.input_amount() as i128).sum::<i128>();
         let backrun_received = backrun.iter().map(|s| *s.output_amount() as i128).sum::<i128>();
-        let profit_a = backrun_received.saturating_sub(frontrun_spent);
-        let profit_b = frontrun_received.saturating_sub(backrun_spent);
+        let profit_a = backrun_received.saturating_sub(frontrun_spent) + config.tolerance.allowance(frontrun_pair.input_mint(), frontrun_spent);
+        let profit_b = frontrun_received.saturating_sub(backrun_spent) + config.tolerance.allowance(frontrun_pair.output_mint(), frontrun_received);
         (profit_a >= 0 && profit_b >= 0).then_some(()).ok_or(SandwichError::NonProfitable(profit_a, profit_b))?;
         // Transfers check - frontrun output ATAs must match backrun input ATAs either directly or with transfers
         let mut frontrun_set = frontrun.iter().map(|s| s.output_ata()).collect::<HashSet<_>>();
         let mut backrun_set = backrun.iter().map(|s| s.input_ata()).collect::<HashSet<_>>();
-        let transfers = transfers.iter().filter(|t| frontrun_set.contains(t.input_ata()) && backrun_set.contains(t.output_ata())).cloned().collect::<Vec<_>>();
-        for t in transfers.iter() {
+        let mut linking_transfers = transfers.iter().filter(|t| frontrun_set.contains(t.input_ata()) && backrun_set.contains(t.output_ata())).cloned().collect::<Vec<_>>();
+        for t in linking_transfers.iter() {
             frontrun_set.remove(t.input_ata());
             backrun_set.remove(t.output_ata());
         }
+        let attackers = frontrun.iter().chain(backrun.iter()).map(|s| s.authority()).collect::<HashSet<_>>();
+        let mut conversions = vec![];
+        if frontrun_set != backrun_set && config.link_conversions {
+            // Conversions check - the rest may be linked by the attacker's swaps in their own txs
+            let attacker_txs = frontrun.iter().chain(backrun.iter()).map(|s| (*s.slot(), *s.inclusion_order())).collect::<HashSet<_>>();
+            let legs = frontrun.iter().chain(backrun.iter()).map(|s| s.id()).collect::<HashSet<_>>();
+            let candidates = swaps.iter().filter(|s| {
+                attacker_txs.contains(&(*s.slot(), *s.inclusion_order())) && attackers.contains(s.authority()) && !legs.contains(s.id())
+            }).cloned().collect::<Vec<_>>();
+            let from = frontrun_set.difference(&backrun_set).copied().collect();
+            let to = backrun_set.difference(&frontrun_set).copied().collect();
+            if let Some((linking_conversions, conversion_transfers)) = conversion_links(&from, &to, &candidates, transfers) {
+                conversions = linking_conversions;
+                linking_transfers.extend(conversion_transfers);
+                frontrun_set.retain(|ata| !from.contains(ata));
+                backrun_set.retain(|ata| !to.contains(ata));
+            }
+        }
         (frontrun_set == backrun_set).then_some(()).ok_or(SandwichError::InvalidTransfers)?;
+        // Same signer check - victims signed by an attacker wallet aren't victims
+        let (suspected_wash, victim): (Vec<_>, Vec<_>) = victim.iter().cloned().partition(|s| attackers.contains(s.authority()));
+        let suspected_wash = if config.flag_wash { suspected_wash } else { vec![] };
+        (!victim.is_empty() || !suspected_wash.is_empty()).then_some(()).ok_or(SandwichError::SelfOverlap)?;
         let tx_orders = [
             frontrun.iter().map(|f| (f.slot(), f.inclusion_order())).collect::<Vec<_>>(),
-            victim.iter().map(|v| (v.slot(), v.inclusion_order())).collect::<Vec<_>>(),
+            victim.iter().chain(suspected_wash.iter()).map(|v| (v.slot(), v.inclusion_order())).collect::<Vec<_>>(),
             backrun.iter().map(|b| (b.slot(), b.inclusion_order())).collect::<Vec<_>>(),
         ].concat();
+        let txs = txs.iter().filter(|tx| tx_orders.contains(&(tx.slot(), tx.inclusion_order())) ).cloned().collect();
         Ok(Self {
             frontrun: Arc::from(frontrun),
-            victim: Arc::from(victim),
+            victim: victim.into(),
             backrun: Arc::from(backrun),
+            suspected_wash: suspected_wash.into(),
+            transfers: linking_transfers.into(),
+            conversions: conversions.into(),
+            txs,
+        })
+    }
+
+    /// Assembles a candidate without any of the checks in [`SandwichCandidate::new`], for sandwiches found by other means
+    pub(crate) fn from_parts(frontrun: Vec<SwapV2>, victim: Vec<SwapV2>, backrun: Vec<SwapV2>, transfers: Vec<TransferV2>, txs: Vec<TransactionV2>) -> Self {
+        Self {
+            frontrun: frontrun.into(),
+            victim: victim.into(),
+            backrun: backrun.into(),
+            suspected_wash: Arc::from([]),
             transfers: transfers.into(),
-            txs: txs.iter().filter(|tx| tx_orders.contains(&(tx.slot(), tx.inclusion_order())) ).cloned().collect(),
+            conversions: Arc::from([]),
+            txs: txs.into(),
+        }
+    }
+
+    /// Restores the wash legs of a candidate assembled with [`SandwichCandidate::from_parts`]
+    pub(crate) fn with_suspected_wash(mut self, suspected_wash: Vec<SwapV2>) -> Self {
+        self.suspected_wash = suspected_wash.into();
+        self
+    }
+
+    /// Restores the conversions of a candidate assembled with [`SandwichCandidate::from_parts`]
+    pub(crate) fn with_conversions(mut self, conversions: Vec<SwapV2>) -> Self {
+        self.conversions = conversions.into();
+        self
+    }
+
+    /// The wallet behind the frontrun
+    pub fn attacker(&self) -> &Arc<str> {
+        self.frontrun[0].authority()
+    }
+
+    pub fn amm(&self) -> &Arc<str> {
+        self.frontrun[0].amm()
+    }
+
+    /// The AMM program the pool belongs to
+    pub fn program(&self) -> &Arc<str> {
+        self.frontrun[0].program()
+    }
+
+    pub fn slot(&self) -> u64 {
+        *self.frontrun[0].slot()
+    }
+
+    pub fn span(&self) -> SandwichSpan {
+        let first = &self.frontrun[0];
+        let last = &self.backrun[self.backrun.len() - 1];
+        SandwichSpan {
+            slots: last.slot() - first.slot(),
+            inclusion_orders: last.inclusion_order().abs_diff(*first.inclusion_order()),
+        }
+    }
+
+    /// `None` for a candidate whose only victims are suspected wash
+    pub fn timing(&self) -> Option<SandwichTiming> {
+        let (frontrun, backrun) = (&self.frontrun[0], &self.backrun[self.backrun.len() - 1]);
+        let (first_victim, last_victim) = (self.victim.first()?, self.victim.last()?);
+        Some(SandwichTiming {
+            frontrun_offset: SandwichTiming::offset(*frontrun.slot()),
+            victim_offset: SandwichTiming::offset(*first_victim.slot()),
+            backrun_offset: SandwichTiming::offset(*backrun.slot()),
+            frontrun_victim_slots: first_victim.slot().saturating_sub(*frontrun.slot()),
+            victim_backrun_slots: backrun.slot().saturating_sub(*last_victim.slot()),
         })
     }
+
+    /// `None` if the first frontrun's reserves weren't captured
+    pub fn pool_tvl(&self) -> Option<PoolTvl> {
+        PoolTvl::before(&self.frontrun[0])
+    }
+
+    /// Deterministic id derived from the ids of the events involved
+    pub fn uuid(&self) -> Uuid {
+        let name: Vec<u8> = [
+            self.frontrun.iter().flat_map(|sw| sw.id().to_le_bytes()).collect::<Vec<_>>(),
+            self.backrun.iter().flat_map(|sw| sw.id().to_le_bytes()).collect::<Vec<_>>(),
+            self.victim.iter().flat_map(|sw| sw.id().to_le_bytes()).collect::<Vec<_>>(),
+            self.transfers.iter().flat_map(|sw| sw.id().to_le_bytes()).collect::<Vec<_>>(),
+            self.suspected_wash.iter().flat_map(|sw| sw.id().to_le_bytes()).collect::<Vec<_>>(),
+            self.conversions.iter().flat_map(|sw| sw.id().to_le_bytes()).collect::<Vec<_>>(),
+        ].concat();
+        Uuid::new_v5(&Uuid::NAMESPACE_DNS, &name)
+    }
+
+    /// Victim swaps whose tx carried a jitodontfront account
+    pub fn dont_front_victims(&self) -> Vec<&SwapV2> {
+        self.victim.iter().filter(|v| self.txs.iter().any(|tx| tx.slot() == v.slot() && tx.inclusion_order() == v.inclusion_order() && *tx.dont_front())).collect()
+    }
+
+    /// Loss of each victim in lamports, in the same order as `victim`, `None` if the pair isn't priced in SOL
+    pub fn estimate_victim_losses_lamports(&self) -> Vec<Option<u64>> {
+        let wsol = WSOL_MINT.to_string();
+        let losses = self.estimate_victim_losses();
+        if self.frontrun[0].input_mint().as_ref() == wsol {
+            losses.iter().map(|l| Some(*l.input_amount())).collect()
+        } else if self.frontrun[0].output_mint().as_ref() == wsol {
+            losses.iter().map(|l| Some(*l.output_amount())).collect()
+        } else {
+            vec![None; losses.len()]
+        }
+    }
+
+    /// Total victim loss if the pair is priced in SOL, 0 otherwise
+    pub fn estimate_victim_loss_lamports(&self) -> u64 {
+        self.estimate_victim_losses_lamports().iter().flatten().sum()
+    }
+
+    /// Position of the swap's tx in its block, see [`TransactionV2::position_bps`]
+    pub fn position_bps(&self, swap: &SwapV2) -> Option<u64> {
+        self.txs.iter().find(|tx| tx.slot() == swap.slot() && tx.inclusion_order() == swap.inclusion_order())?.position_bps()
+    }
+
+    /// Victim amounts of `mint`, whichever side of the swaps it's on
+    pub fn victim_volume(&self, mint: &str) -> u64 {
+        self.victim.iter().map(|v| {
+            if v.input_mint().as_ref() == mint {
+                *v.input_amount()
+            } else if v.output_mint().as_ref() == mint {
+                *v.output_amount()
+            } else {
+                0
+            }
+        }).sum()
+    }
+
+    /// Mints traded other than SOL, the ones a sandwich on this pair is a sandwich of
+    pub fn token_mints(&self) -> Vec<&Arc<str>> {
+        let wsol = WSOL_MINT.to_string();
+        [self.frontrun[0].input_mint(), self.frontrun[0].output_mint()].into_iter().filter(|m| m.as_ref() != wsol).collect()
+    }
+
+    /// Per-victim loss in victim order, treating the AMM as constant product with the frontrun legs aggregated
+    pub fn estimate_victim_losses(&self) -> Vec<VictimLoss> {
+        let frontrun = self.frontrun.iter().fold((0, 0), |(i, o), s| (i + s.input_amount(), o + s.output_amount()));
+        let victims = self.victim.iter().map(|v| (*v.input_amount(), *v.output_amount())).collect::<Vec<_>>();
+        estimate_victim_losses(frontrun, &victims)
+    }
+}
+
+impl Serialize for SandwichCandidate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("SandwichCandidate", 11)?;
+        state.serialize_field("id", &self.uuid().to_string())?;
+        state.serialize_field("slot", &self.slot())?;
+        state.serialize_field("frontrun", &self.frontrun)?;
+        state.serialize_field("victim", &self.victim)?;
+        state.serialize_field("backrun", &self.backrun)?;
+        state.serialize_field("suspectedWash", &self.suspected_wash)?;
+        state.serialize_field("transfers", &self.transfers)?;
+        state.serialize_field("conversions", &self.conversions)?;
+        state.serialize_field("txs", &self.txs)?;
+        state.serialize_field("victimLosses", &self.estimate_victim_losses())?;
+        state.serialize_field("estVictimLossLamports", &self.estimate_victim_loss_lamports())?;
+        state.end()
+    }
+}
+
+/// How a pair of frontrun and backrun segments fared, see [`search_segments`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SegmentVerdict {
+    Accept,
+    /// Turned down for anything but its profits, which says nothing about the other segments
+    Reject,
+    /// Profits in token A/B, negative for at least one of them
+    NonProfitable(i128, i128),
+}
+
+/// Tries every pair of contiguous segments `before[i..j]` and `after[m..n]` against `verdict` and returns the accepted ones,
+/// without trying the ones the profits of those already tried rule out.
+///
+/// The pruning relies on the profits being monotonic in the segments:
+/// 1. for a given (i, j, m), the token A spent and token B received by the frontruns are fixed, as n increases the backruns only
+///    spend more token B and receive more token A, so once the profit in token B goes negative it only gets more negative
+/// 2. at the end of the n loop, incrementing m only receives less token A in the backruns, so if the profit in token A is negative
+///    there, no other m can make it up and we move on to the next (i, j)
+/// 3. at (m, n) = (0, after_len), removing any backrun decreases the profit in token A and so does adding another frontrun,
+///    so if it's negative there we move on to the next i
+pub fn search_segments(before_len: usize, after_len: usize, mut verdict: impl FnMut(Range<usize>, Range<usize>) -> SegmentVerdict) -> Vec<(Range<usize>, Range<usize>)> {
+    let mut accepted = vec![];
+    for i in 0..before_len {
+        'j: for j in i+1..=before_len {
+            'm: for m in 0..after_len {
+                'n: for n in m+1..=after_len {
+                    match verdict(i..j, m..n) {
+                        SegmentVerdict::Accept => accepted.push((i..j, m..n)),
+                        SegmentVerdict::Reject => {},
+                        SegmentVerdict::NonProfitable(profit_a, profit_b) => {
+                            if profit_b < 0 {
+                                break 'n; // pruning condition #1
+                            }
+                            if n == after_len && profit_a < 0 {
+                                if m == 0 {
+                                    break 'j; // pruning condition #3
+                                }
+                                break 'm; // pruning condition #2
+                            }
+                        },
+                    }
+                }
+            }
+        }
+    }
+    accepted
+}
+
+/// Profits in token A/B of frontruns swapping A for B and backruns swapping B back for A, as (amount in, amount out) pairs
+pub fn segment_profits(frontrun: &[(u64, u64)], backrun: &[(u64, u64)]) -> (i128, i128) {
+    let sum = |swaps: &[(u64, u64)]| swaps.iter().fold((0i128, 0i128), |(i, o), &(input, output)| (i + input as i128, o + output as i128));
+    let ((frontrun_spent, frontrun_received), (backrun_spent, backrun_received)) = (sum(frontrun), sum(backrun));
+    (backrun_received - frontrun_spent, frontrun_received - backrun_spent)
+}
+
+/// The segments of `before` and `after` that make for a profitable sandwich on their amounts alone, see [`search_segments`]
+pub fn profitable_segments(before: &[(u64, u64)], after: &[(u64, u64)]) -> Vec<(Range<usize>, Range<usize>)> {
+    search_segments(before.len(), after.len(), |frontrun, backrun| {
+        match segment_profits(&before[frontrun], &after[backrun]) {
+            (profit_a, profit_b) if profit_a >= 0 && profit_b >= 0 => SegmentVerdict::Accept,
+            (profit_a, profit_b) => SegmentVerdict::NonProfitable(profit_a, profit_b),
+        }
+    })
 }
 
 /// This function expects the events to be sorted in chronological order
-pub fn detect(swaps: &[SwapV2], transfers: &[TransferV2], txs: &[TransactionV2]) -> Arc<[SandwichCandidate]> {
+/// Also returns why the candidates that were tried and turned down failed, pruned ones aren't counted
+pub fn detect(swaps: &[SwapV2], transfers: &[TransferV2], txs: &[TransactionV2], config: &SandwichConfig) -> (Arc<[SandwichCandidate]>, RejectionStats) {
     // Group swaps by AMM then direction also by outer program
     let mut amm_swaps: HashMap<Arc<str>, HashMap<TradePair, Vec<SwapV2>>> = HashMap::new();
     for swap in swaps.iter() {
@@ -163,6 +666,7 @@ pub fn detect(swaps: &[SwapV2], transfers: &[TransferV2], txs: &[TransactionV2])
     // for each swap, we want to match it with a series of swaps before it in the same direction and a series of swaps after it in the opposite direction
     let mut matched_timestamps = HashSet::new(); // to avoid double counting
     let mut sandwiches = vec![];
+    let mut rejections = RejectionStats::default();
     for swap in swaps.iter() {
         if matched_timestamps.contains(swap.timestamp()) {
             continue;
@@ -179,18 +683,18 @@ pub fn detect(swaps: &[SwapV2], transfers: &[TransferV2], txs: &[TransactionV2])
             continue;
         }
         // println!("Analyzing swap at {:?} for sandwiches {:?} {:?}", swap.timestamp(), before_swaps, after_swaps);
-        // we then group the swaps before and after by outer program and see if some outer program may be sandwiching this swap
+        // we then group the swaps before and after by wrapper program and see if some wrapper program may be sandwiching this swap
         let before_outer = {
             let mut map: HashMap<Option<Arc<str>>, Vec<SwapV2>> = HashMap::new();
             for s in before_swaps.iter() {
-                map.entry(s.outer_program().clone()).or_default().push(s.clone());
+                map.entry(s.wrapper_program().cloned()).or_default().push(s.clone());
             }
             map
         };
         let after_outer = {
             let mut map: HashMap<Option<Arc<str>>, Vec<SwapV2>> = HashMap::new();
             for s in after_swaps.iter() {
-                map.entry(s.outer_program().clone()).or_default().push(s.clone());
+                map.entry(s.wrapper_program().cloned()).or_default().push(s.clone());
             }
             map
         };
@@ -221,65 +725,49 @@ pub fn detect(swaps: &[SwapV2], transfers: &[TransferV2], txs: &[TransactionV2])
                     v.sort_by_cached_key(|s| *s.timestamp());
                     v
                 } else { after_swaps };
-                // pruning condition #1
-                // notice that in the n loop, the amounts of token A spent and token B received from the frontruns are fixed since we already chose the set of frontruns
-                // as n increases, we'll only be spending more token B and receiving more token A in the backruns
-                // which means as soon as the profit for token B becomes negative, we can break out of the n loop as it's guaranteed to become even more negative
-                // in some cases we'll break out of the n loop much earlier than reaching the end
-                // pruning condition #2
-                // also notice that, when we've reached the end of the n loop, incrementing m further will result in receiving less token A in the backruns
-                // which means, if we're at the end of the n loop, and the profit for token A is negative, we can break out of the m loop as well and try the next (i, j)
-                // pruning condition #3
-                // further notice that, when we've reached (m, n) = (0, br.len()), removing any backrun will decrease the profit in token A
-                // adding another frontrun will further decrease the profit in token A by spending more, so we can break out of the j loop if the profit of token A is negative
-                // println!("Looking at outer program {:?} {} {}", k, before_swaps.len(), after_swaps.len());
-                for i in 0..before_swaps.len() {
-                    'j: for j in i+1..=before_swaps.len() {
-                        'm: for m in 0..after_swaps.len() {
-                            'n: for n in m+1..=after_swaps.len() {
-                                let frontrun = &before_swaps[i..j];
-                                let frontrun_last = before_swaps[j - 1].clone();
-                                let backrun = &after_swaps[m..n];
-                                let backrun_first = after_swaps[m].clone();
-                                let victim = &swaps.iter().filter(|s| s.timestamp() > frontrun_last.timestamp() && s.timestamp() < backrun_first.timestamp() && s.amm() == swap.amm() && s.input_mint() == swap.input_mint() && s.output_mint() == swap.output_mint()).cloned().collect::<Vec<_>>()[..];
-                                match SandwichCandidate::new(frontrun, victim, backrun, &transfers, &txs) {
-                                    Ok(sandwich) => {
-                                        candidates.push(sandwich);
-                                        victim.iter().for_each(|s| { matched_timestamps.insert(*s.timestamp()); });
-                                    }
-                                    Err(SandwichError::NonProfitable(profit_a, profit_b)) => {
-                                        // println!("Failed to create sandwich candidate: {},{},{},{} {},{}", i,j,m,n,profit_a,profit_b);
-                                        if profit_b < 0 {
-                                            // println!("prune #1");
-                                            break 'n; // break out of n loop - pruning condition #1
-                                        }
-                                        if n == after_swaps.len() && profit_a < 0 {
-                                            // println!("prune #2");
-                                            break 'm; // break out of m loop - pruning condition #2
-                                        }
-                                        if n == after_swaps.len() && m == 0 && profit_a < 0 {
-                                            // println!("prune #3");
-                                            break 'j; // break out of j loop - pruning condition #3
-                                        }
-                                    },
-                                    // Err(e) => println!("Failed to create sandwich candidate: {},{},{},{} {:?}", i,j,m,n,e),
-                                    Err(_) => {},
-                                }
-                            }
+                // pruning conditions #1-#3, see search_segments
+                search_segments(before_swaps.len(), after_swaps.len(), |frontrun, backrun| {
+                    let frontrun = &before_swaps[frontrun];
+                    let backrun = &after_swaps[backrun];
+                    let (frontrun_last, backrun_first) = (&frontrun[frontrun.len() - 1], &backrun[0]);
+                    let victim = &swaps.iter().filter(|s| s.timestamp() > frontrun_last.timestamp() && s.timestamp() < backrun_first.timestamp() && s.amm() == swap.amm() && s.input_mint() == swap.input_mint() && s.output_mint() == swap.output_mint()).cloned().collect::<Vec<_>>()[..];
+                    match SandwichCandidate::new(frontrun, victim, backrun, swaps, transfers, txs, config) {
+                        Ok(sandwich) => {
+                            candidates.push(sandwich);
+                            victim.iter().for_each(|s| { matched_timestamps.insert(*s.timestamp()); });
+                            SegmentVerdict::Accept
                         }
+                        // the profits are net of the tolerance, which grows slower than the amounts it's relative to, so pruning still holds
+                        Err(e @ SandwichError::NonProfitable(profit_a, profit_b)) => {
+                            rejections.record(&e);
+                            SegmentVerdict::NonProfitable(profit_a, profit_b)
+                        },
+                        Err(e) => {
+                            rejections.record(&e);
+                            SegmentVerdict::Reject
+                        },
                     }
-                }
+                });
             }
         }
-        // if there are multiple candidates, we pick the one with the most victims, then the one with the most swaps
-        if !candidates.is_empty() {
-            candidates.sort_by_cached_key(|c| (c.victim().len(), c.frontrun().len() + c.backrun().len()));
-            sandwiches.push(candidates.last().unwrap().clone());
+        // if there are multiple candidates, we pick the best one according to the policy
+        let best = match config.selection {
+            SelectionPolicy::MostVictims => candidates.iter().max_by_key(|c| {
+                let span = c.span();
+                (c.victim().len(), c.frontrun().len() + c.backrun().len(), span.slots == 0, Reverse(span))
+            }),
+            SelectionPolicy::Hybrid => candidates.iter().max_by_key(|c| {
+                let span = c.span();
+                (span.slots == 0, c.victim().len(), Reverse(span), c.frontrun().len() + c.backrun().len())
+            }),
+        };
+        if let Some(best) = best {
+            sandwiches.push(best.clone());
         }
     }
     // println!("Sandwiches {:#?}", sandwiches);
 
-    sandwiches.into()
+    (sandwiches.into(), rejections)
 }
 /*
 SandwichCandidate {
@@ -318,4 +806,180 @@ SandwichCandidate {
     TransactionV2 { slot: 372367926, inclusion_order: 1326, sig: "LP29LGbuePvufokqmMW2yVvQGX48PA6D32XDX4MPKhdGrpvDuhDZBXVSkmM4XX8xMPEKFNGqWaukfk1g6GQgevq", fee: 6200, cu_actual: 69165 }
   ]
 }
- */
\ No newline at end of file
+ */
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use crate::{events::swap::PoolReserves, test_utils::SwapBuilder};
+
+    use super::*;
+
+    const BOT: &str = "11111111111111111111111111111111";
+
+    fn swap(slot: u64, inclusion_order: u32, outer_program: Option<&str>, buy: bool, input_amount: u64, output_amount: u64) -> SwapV2 {
+        let swap = SwapBuilder::new().outer_program(outer_program).authority(&format!("wallet{slot}{inclusion_order}")).at(slot, inclusion_order).amounts(input_amount, output_amount);
+        if buy { swap.buy("sol") } else { swap.sell("sol") }.build()
+    }
+
+    #[test]
+    fn test_selection_policy() {
+        // a same-slot bundle, and a second backrun in the next slot which would also cover the victim there
+        let swaps = vec![
+            swap(1, 0, Some(BOT), true, 100, 100),
+            swap(1, 1, None, true, 100, 90),
+            swap(1, 2, Some(BOT), false, 100, 101),
+            swap(2, 0, None, true, 100, 80),
+            swap(2, 1, Some(BOT), false, 100, 102),
+        ];
+        let victims = |sandwiches: Arc<[SandwichCandidate]>| sandwiches.iter().map(|s| (s.victim().len(), s.span())).collect::<Vec<_>>();
+        assert_eq!(victims(detect(&swaps, &[], &[], &SandwichConfig::default()).0), vec![(2, SandwichSpan { slots: 1, inclusion_orders: 1 })]);
+        assert_eq!(victims(detect(&swaps, &[], &[], &SandwichConfig { selection: SelectionPolicy::Hybrid, ..Default::default() }).0), vec![(1, SandwichSpan { slots: 0, inclusion_orders: 2 })]);
+        // same victims and swaps either way, the same-slot backrun wins the tie
+        let swaps = vec![
+            swap(1, 0, Some(BOT), true, 100, 100),
+            swap(1, 1, None, true, 100, 90),
+            swap(1, 2, Some(BOT), false, 100, 101),
+            swap(2, 0, Some(BOT), false, 100, 102),
+        ];
+        assert_eq!(victims(detect(&swaps, &[], &[], &SandwichConfig::default()).0), vec![(1, SandwichSpan { slots: 0, inclusion_orders: 2 })]);
+    }
+
+    #[test]
+    fn test_rejection_stats() {
+        // the backrun gets back less than the frontrun spent
+        let swaps = vec![
+            swap(1, 0, Some(BOT), true, 100, 100),
+            swap(1, 1, None, true, 100, 90),
+            swap(1, 2, Some(BOT), false, 100, 99),
+        ];
+        let (sandwiches, rejections) = detect(&swaps, &[], &[], &SandwichConfig::default());
+        assert!(sandwiches.is_empty());
+        assert_eq!(rejections.get("sandwich_rejected_non_profitable"), 1);
+        assert_eq!(rejections.total(), 1);
+        assert_eq!(rejections.to_string(), "non_profitable=1");
+    }
+
+    #[test]
+    fn test_profit_tolerance() {
+        // the backrun sells 1% more tokens than the frontrun bought, like a 1% transfer tax would leave it
+        let swaps = vec![
+            swap(1, 0, Some(BOT), true, 1000, 1000),
+            swap(1, 1, None, true, 100, 90),
+            swap(1, 2, Some(BOT), false, 1010, 1010),
+        ];
+        assert!(detect(&swaps, &[], &[], &SandwichConfig::default()).0.is_empty());
+        let tolerance = ProfitTolerance { default_bps: 0, per_mint_bps: HashMap::from([("token".into(), 100)]) };
+        assert_eq!(tolerance.bps("token"), 100);
+        assert_eq!(tolerance.bps("sol"), 0);
+        assert_eq!(detect(&swaps, &[], &[], &SandwichConfig { tolerance, ..Default::default() }).0.len(), 1);
+    }
+
+    #[test]
+    fn test_suspected_wash() {
+        // signed by the frontrun wallet
+        let wash = SwapBuilder::new().authority("wallet10").buy("sol").amounts(100, 90).at(1, 1).build();
+        let swaps = vec![
+            swap(1, 0, Some(BOT), true, 100, 100),
+            wash,
+            swap(1, 2, None, true, 100, 90),
+            swap(1, 3, Some(BOT), false, 100, 101),
+        ];
+        let sandwiches = detect(&swaps, &[], &[], &SandwichConfig::default()).0;
+        assert_eq!(sandwiches.iter().map(|s| (s.victim().len(), s.suspected_wash().len())).collect::<Vec<_>>(), vec![(1, 0)]);
+        let sandwiches = detect(&swaps, &[], &[], &SandwichConfig { flag_wash: true, ..Default::default() }).0;
+        assert_eq!(sandwiches.iter().map(|s| (s.victim().len(), s.suspected_wash().len())).collect::<Vec<_>>(), vec![(1, 1)]);
+        // only the bot's own swap in between
+        let (sandwiches, rejections) = detect(&[swaps[0].clone(), swaps[1].clone(), swaps[3].clone()], &[], &[], &SandwichConfig::default());
+        assert!(sandwiches.is_empty());
+        assert_eq!(rejections.get("sandwich_rejected_self_overlap"), 1);
+    }
+
+    #[test]
+    fn test_link_conversions() {
+        // the bot sells for SOL, holds USDC until the backrun and buys back with SOL from another account
+        let leg = |inclusion_order: u32, ix_index: u32, amm: &str, (input_mint, output_mint): (&str, &str), (input_ata, output_ata): (&str, &str), input_amount: u64, output_amount: u64| {
+            let authority = if amm == "amm" && inclusion_order == 1 { "victim" } else { "bot" };
+            let outer_program = (amm == "amm" && authority == "bot").then_some(BOT);
+            SwapBuilder::new().outer_program(outer_program).authority(authority).amm(amm).mints(input_mint, output_mint).atas(input_ata, output_ata).amounts(input_amount, output_amount)
+                .at(1, inclusion_order).ix_index(ix_index).id(1000 + inclusion_order as u64 * 10 + ix_index as u64).build()
+        };
+        let swaps = vec![
+            leg(0, 0, "amm", ("token", "sol"), ("token_ata", "sol_ata"), 100, 100),
+            leg(0, 1, "usdc_amm", ("sol", "usdc"), ("sol_ata", "usdc_ata"), 100, 200),
+            leg(1, 0, "amm", ("token", "sol"), ("victim_token_ata", "victim_sol_ata"), 100, 90),
+            leg(2, 0, "usdc_amm", ("usdc", "sol"), ("usdc_ata", "other_sol_ata"), 200, 99),
+            leg(2, 1, "amm", ("sol", "token"), ("other_sol_ata", "token_ata"), 99, 101),
+        ];
+        let (sandwiches, rejections) = detect(&swaps, &[], &[], &SandwichConfig::default());
+        assert!(sandwiches.is_empty());
+        assert_eq!(rejections.get("sandwich_rejected_invalid_transfers"), 1);
+        let sandwiches = detect(&swaps, &[], &[], &SandwichConfig { link_conversions: true, ..Default::default() }).0;
+        assert_eq!(sandwiches.len(), 1);
+        assert_eq!(sandwiches[0].conversions().iter().map(|c| *c.id()).collect::<Vec<_>>(), vec![1001, 1020]);
+        // a conversion into something else doesn't link them
+        let mut unlinked = swaps.clone();
+        unlinked[3] = leg(2, 0, "usdc_amm", ("usdc", "sol"), ("usdc_ata", "elsewhere"), 200, 99);
+        assert!(detect(&unlinked, &[], &[], &SandwichConfig { link_conversions: true, ..Default::default() }).0.is_empty());
+    }
+
+    fn brute_force_segments(before: &[(u64, u64)], after: &[(u64, u64)]) -> Vec<(Range<usize>, Range<usize>)> {
+        let mut segments = vec![];
+        for i in 0..before.len() {
+            for j in i+1..=before.len() {
+                for m in 0..after.len() {
+                    for n in m+1..=after.len() {
+                        let (profit_a, profit_b) = segment_profits(&before[i..j], &after[m..n]);
+                        if profit_a >= 0 && profit_b >= 0 {
+                            segments.push((i..j, m..n));
+                        }
+                    }
+                }
+            }
+        }
+        segments
+    }
+
+    #[test]
+    fn test_pool_tvl() {
+        let wsol = WSOL_MINT.to_string();
+        let mut frontrun = SwapBuilder::new().buy(&wsol).amounts(100, 50).inner_ix_indexes(Some(0), Some(1)).id(1).build();
+        assert_eq!(PoolTvl::before(&frontrun), None);
+        // 1000 SOL and 550 tokens before the frontrun, which paid 2 lamports per token
+        frontrun.set_pool_reserves(PoolReserves::new(Some(1100), Some(500)));
+        assert_eq!(PoolTvl::before(&frontrun), Some(PoolTvl { reserve_a: 1000, reserve_b: 550, tvl_lamports: Some(2100) }));
+        let mut backrun = SwapBuilder::new().sell(&wsol).amounts(50, 100).inner_ix_indexes(Some(0), Some(1)).at(1, 2).id(2).build();
+        backrun.set_pool_reserves(PoolReserves::new(Some(600), Some(900)));
+        assert_eq!(PoolTvl::before(&backrun).and_then(|tvl| tvl.tvl_lamports), Some(1000 + 550 * 2));
+    }
+
+    #[test]
+    fn test_timing() {
+        let sandwich = SandwichCandidate::from_parts(vec![swap(401, 5, Some(BOT), true, 100, 100)], vec![swap(402, 0, None, true, 100, 90)], vec![swap(403, 1, Some(BOT), false, 100, 101)], vec![], vec![]);
+        let timing = sandwich.timing().unwrap();
+        assert_eq!((timing.frontrun_offset, timing.victim_offset, timing.backrun_offset), (1, 2, 3));
+        assert_eq!((timing.frontrun_victim_slots, timing.victim_backrun_slots), (1, 1));
+        // wash only, no victim to time
+        let wash = SandwichCandidate::from_parts(vec![swap(401, 5, Some(BOT), true, 100, 100)], vec![], vec![swap(403, 1, Some(BOT), false, 100, 101)], vec![], vec![])
+            .with_suspected_wash(vec![swap(402, 0, Some(BOT), true, 100, 90)]);
+        assert_eq!(wash.timing(), None);
+    }
+
+    #[test]
+    fn test_profitable_segments() {
+        // buys 100 tokens for 100 and sells them for 101
+        assert_eq!(profitable_segments(&[(100, 100)], &[(100, 101)]), vec![(0..1, 0..1)]);
+        assert!(profitable_segments(&[(100, 100)], &[(101, 101)]).is_empty());
+        assert!(profitable_segments(&[], &[(100, 101)]).is_empty());
+    }
+
+    proptest! {
+        #[test]
+        fn test_search_segments_matches_brute_force(
+            before in prop::collection::vec((0..1000u64, 0..1000u64), 0..8),
+            after in prop::collection::vec((0..1000u64, 0..1000u64), 0..8),
+        ) {
+            prop_assert_eq!(profitable_segments(&before, &after), brute_force_segments(&before, &after));
+        }
+    }
+}
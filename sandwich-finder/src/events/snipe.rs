@@ -0,0 +1,122 @@
+use std::{collections::{HashMap, HashSet}, env, sync::Arc};
+
+use derive_getters::Getters;
+
+use crate::events::{addresses::WSOL_MINT, common::Timestamp, swap::SwapV2};
+
+#[derive(Clone, Debug)]
+pub struct SnipeConfig {
+    /// Buys within this many slots of the pool's creation are considered. The realtime detector only lags
+    /// one leader group behind so larger windows may miss events that aren't indexed yet
+    pub window_slots: u64,
+    /// Txs right after the creation tx in the same slot that are treated as part of its bundle
+    pub bundle_window: u32,
+    /// Pools first seen within this many slots of the registry's start aren't treated as new,
+    /// since they're more likely existing pools that just haven't traded yet
+    pub warmup_slots: u64,
+}
+
+impl Default for SnipeConfig {
+    fn default() -> Self {
+        Self {
+            window_slots: 4,
+            bundle_window: 4,
+            warmup_slots: 216000,
+        }
+    }
+}
+
+impl SnipeConfig {
+    /// Reads `SNIPE_WINDOW_SLOTS`, `SNIPE_BUNDLE_WINDOW` and `SNIPE_WARMUP_SLOTS`, falling back to the defaults
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let var = |name: &str, default: u64| env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self {
+            window_slots: var("SNIPE_WINDOW_SLOTS", default.window_slots),
+            bundle_window: var("SNIPE_BUNDLE_WINDOW", default.bundle_window as u64) as u32,
+            warmup_slots: var("SNIPE_WARMUP_SLOTS", default.warmup_slots),
+        }
+    }
+}
+
+/// A SOL buy on a new pool shortly after its creation by a wallet other than the creator that also bought in the creation bundle
+#[derive(Clone, Debug, Getters)]
+pub struct Snipe {
+    swap: SwapV2,
+    slots_after_creation: u64,
+}
+
+/// Earliest swap on each AMM, which is taken as the pool's creation tx unless the creation itself is in `creations`
+pub fn first_swaps(swaps: &[SwapV2], creations: &[(Arc<str>, Timestamp)]) -> Vec<(Arc<str>, Timestamp)> {
+    let mut firsts: HashMap<Arc<str>, Timestamp> = HashMap::new();
+    for (amm, timestamp) in swaps.iter().map(|swap| (swap.amm(), swap.timestamp())).chain(creations.iter().map(|(amm, ts)| (amm, ts))) {
+        let ts = firsts.entry(amm.clone()).or_insert(*timestamp);
+        if timestamp < ts {
+            *ts = *timestamp;
+        }
+    }
+    firsts.into_iter().collect()
+}
+
+/// `swaps` should cover at least `window_slots` slots from the creation
+pub fn detect_snipes(amm: &str, created: &Timestamp, swaps: &[SwapV2], config: &SnipeConfig) -> Vec<Snipe> {
+    let wsol = WSOL_MINT.to_string();
+    let buys: Vec<_> = swaps.iter().filter(|s| {
+        s.amm().as_ref() == amm
+            && s.input_mint().as_ref() == wsol
+            && s.slot() >= created.slot()
+            && *s.slot() < created.slot() + config.window_slots
+    }).collect();
+    // the creator buying in its own creation tx, or the first swap taken for the creation, is the launch itself
+    let creators: HashSet<_> = swaps.iter().filter(|s| {
+        s.amm().as_ref() == amm && s.slot() == created.slot() && s.inclusion_order() == created.inclusion_order()
+    }).map(|s| s.authority().clone()).collect();
+    let insiders: HashSet<_> = buys.iter().filter(|s| {
+        s.slot() == created.slot()
+            && s.inclusion_order() >= created.inclusion_order()
+            && *s.inclusion_order() <= created.inclusion_order() + config.bundle_window
+            && !creators.contains(s.authority())
+    }).map(|s| s.authority().clone()).collect();
+    buys.into_iter().filter(|s| insiders.contains(s.authority())).map(|s| Snipe {
+        swap: s.clone(),
+        slots_after_creation: s.slot() - created.slot(),
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::SwapBuilder;
+
+    fn swap(amm: &str, slot: u64, inclusion_order: u32) -> SwapV2 {
+        SwapBuilder::new().amm(amm).buy("sol").at(slot, inclusion_order).ix_index(1).build()
+    }
+
+    fn buy(authority: &str, slot: u64, inclusion_order: u32) -> SwapV2 {
+        SwapBuilder::new().authority(authority).buy(&WSOL_MINT.to_string()).at(slot, inclusion_order).ix_index(1).build()
+    }
+
+    #[test]
+    fn test_detect_snipes() {
+        let created = Timestamp::new(10, 0, 1, None);
+        let swaps = [buy("creator", 10, 0), buy("bundler", 10, 2), buy("creator", 11, 0), buy("bundler", 11, 1), buy("late", 10, 9), buy("late", 11, 2)];
+        let snipes = detect_snipes("amm", &created, &swaps, &SnipeConfig::default());
+        // the creator's own buys don't count, however soon after the creation
+        assert_eq!(snipes.iter().map(|s| (s.swap.authority().as_ref(), *s.swap.slot())).collect::<Vec<_>>(), vec![("bundler", 10), ("bundler", 11)]);
+        // nor does a launch with no one else in its bundle
+        assert!(detect_snipes("amm", &created, &swaps[..1], &SnipeConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn test_first_swaps() {
+        let swaps = [swap("a", 10, 5), swap("a", 10, 2), swap("b", 11, 0)];
+        let creations = [("b".into(), Timestamp::new(11, 0, 0, None)), ("c".into(), Timestamp::new(9, 1, 0, None))];
+        let mut firsts = first_swaps(&swaps, &creations);
+        firsts.sort();
+        assert_eq!(firsts, vec![
+            ("a".into(), Timestamp::new(10, 2, 1, None)),
+            ("b".into(), Timestamp::new(11, 0, 0, None)),
+            ("c".into(), Timestamp::new(9, 1, 0, None)),
+        ]);
+    }
+}
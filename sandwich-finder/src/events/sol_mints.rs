@@ -0,0 +1,72 @@
+use std::{collections::HashSet, env, sync::{Arc, LazyLock}};
+
+use crate::events::{addresses::WSOL_MINT, swap::SwapV2, transfer::TransferV2};
+
+pub static SOL_EQUIVALENTS: LazyLock<SolEquivalents> = LazyLock::new(SolEquivalents::from_env);
+
+/// Mints the detector treats as WSOL, so a sandwich that spends SOL under one of them and gets it back under another
+/// still pairs up. Native lamport transfers are already given the WSOL mint by the transfer finders, this covers the
+/// mints that aren't, like the native SOL placeholder some programs and routers use, or whatever liquid staking
+/// tokens a deployment would rather count as SOL.
+#[derive(Clone, Debug)]
+pub struct SolEquivalents {
+    wsol: Arc<str>,
+    mints: HashSet<Arc<str>>,
+}
+
+impl SolEquivalents {
+    pub fn new<'a>(mints: impl IntoIterator<Item = &'a str>) -> Self {
+        let wsol: Arc<str> = WSOL_MINT.to_string().into();
+        Self {
+            mints: mints.into_iter().filter(|m| !m.is_empty() && *m != wsol.as_ref()).map(Arc::from).collect(),
+            wsol,
+        }
+    }
+
+    /// Reads the comma separated `SOL_EQUIVALENT_MINTS`, only the native SOL placeholder if unset
+    pub fn from_env() -> Self {
+        match env::var("SOL_EQUIVALENT_MINTS") {
+            Ok(mints) => Self::new(mints.split(',').map(str::trim)),
+            Err(_) => Self::new(["So11111111111111111111111111111111111111111"]),
+        }
+    }
+
+    fn canonical<'a>(&'a self, mint: &'a Arc<str>) -> &'a Arc<str> {
+        if self.mints.contains(mint) {
+            &self.wsol
+        } else {
+            mint
+        }
+    }
+
+    pub fn normalize_swap(&self, swap: &mut SwapV2) {
+        if self.mints.contains(swap.input_mint()) || self.mints.contains(swap.output_mint()) {
+            let (input_mint, output_mint) = (self.canonical(swap.input_mint()).clone(), self.canonical(swap.output_mint()).clone());
+            swap.set_mints(input_mint, output_mint);
+        }
+    }
+
+    pub fn normalize_transfer(&self, transfer: &mut TransferV2) {
+        if self.mints.contains(transfer.mint()) {
+            transfer.set_mint(self.wsol.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::SwapBuilder;
+
+    #[test]
+    fn test_normalize() {
+        let equivalents = SolEquivalents::new(["native", "bsol"]);
+        let mut swap = SwapBuilder::new().buy("bsol").build();
+        equivalents.normalize_swap(&mut swap);
+        assert_eq!((swap.input_mint().as_ref(), swap.output_mint().as_ref()), (WSOL_MINT.to_string().as_str(), "token"));
+        assert!(swap.is_complete());
+        let mut transfer = TransferV2::new(None, "program".into(), "wallet".into(), "native".into(), 1, "in".into(), "out".into(), 1, 0, 0, None, 0);
+        equivalents.normalize_transfer(&mut transfer);
+        assert_eq!(transfer.mint().to_string(), WSOL_MINT.to_string());
+    }
+}
@@ -0,0 +1,61 @@
+use std::{collections::HashSet, env, sync::Arc};
+
+use crate::events::sandwich::SandwichCandidate;
+
+const DEFAULT_STABLE_MINTS: [&str; 3] = [
+    "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", // USDC
+    "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYb", // USDT
+    "2b1kV6DkPAnxd5ixfnxCpjxmKwqjjaYmCZfHsFu24GXo", // PYUSD
+];
+
+/// Mints treated as stablecoins. Sandwiches on pools between two of them are mostly arbing a depeg rather than
+/// taking from retail, so they're tagged and rolled up apart from the rest.
+#[derive(Clone, Debug)]
+pub struct StableMints {
+    mints: HashSet<Arc<str>>,
+}
+
+impl Default for StableMints {
+    fn default() -> Self {
+        Self {
+            mints: DEFAULT_STABLE_MINTS.into_iter().map(Arc::from).collect(),
+        }
+    }
+}
+
+impl StableMints {
+    /// Reads the comma separated `STABLE_MINTS`, USDC, USDT and PYUSD if unset
+    pub fn from_env() -> Self {
+        match env::var("STABLE_MINTS") {
+            Ok(mints) => Self {
+                mints: mints.split(',').map(str::trim).filter(|m| !m.is_empty()).map(Arc::from).collect(),
+            },
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn is_stable_pair(&self, mint_a: &str, mint_b: &str) -> bool {
+        mint_a != mint_b && self.mints.contains(mint_a) && self.mints.contains(mint_b)
+    }
+
+    /// Whether the sandwiched pool is between two stablecoins
+    pub fn is_stable_sandwich(&self, sandwich: &SandwichCandidate) -> bool {
+        let frontrun = &sandwich.frontrun()[0];
+        self.is_stable_pair(frontrun.input_mint(), frontrun.output_mint())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_stable_pair() {
+        let stables = StableMints::default();
+        let (usdc, usdt) = (DEFAULT_STABLE_MINTS[0], DEFAULT_STABLE_MINTS[1]);
+        assert!(stables.is_stable_pair(usdc, usdt));
+        assert!(stables.is_stable_pair(usdt, usdc));
+        assert!(!stables.is_stable_pair(usdc, "So11111111111111111111111111111111111111112"));
+        assert!(!stables.is_stable_pair(usdc, usdc));
+    }
+}
@@ -2,6 +2,29 @@ use std::sync::Arc;
 
 use derive_getters::Getters;
 use serde::Serialize;
+use solana_sdk::instruction::Instruction;
+
+use crate::events::{addresses::COMPUTE_BUDGET_PROGRAM_ID, common::BlockTime};
+
+// ComputeBudget SetComputeUnitLimit, followed by the limit as a u32
+const SET_COMPUTE_UNIT_LIMIT: u8 = 2;
+const DEFAULT_CU_PER_IX: u32 = 200_000;
+const MAX_CU_LIMIT: u32 = 1_400_000;
+
+/// The CU limit a tx runs with, either set through the ComputeBudget program or the runtime default
+pub fn cu_limit_from_ixs(ixs: &[Instruction]) -> u32 {
+    let requested = ixs.iter().rev().find_map(|ix| {
+        if ix.program_id != COMPUTE_BUDGET_PROGRAM_ID || ix.data.first() != Some(&SET_COMPUTE_UNIT_LIMIT) {
+            return None;
+        }
+        Some(u32::from_le_bytes(ix.data.get(1..5)?.try_into().ok()?))
+    });
+    let limit = requested.unwrap_or_else(|| {
+        let ix_count = ixs.iter().filter(|ix| ix.program_id != COMPUTE_BUDGET_PROGRAM_ID).count() as u32;
+        ix_count.saturating_mul(DEFAULT_CU_PER_IX)
+    });
+    limit.min(MAX_CU_LIMIT)
+}
 
 #[derive(Clone, Debug, Serialize, Getters)]
 #[serde(rename_all = "camelCase")]
@@ -11,7 +34,14 @@ pub struct TransactionV2 {
     sig: Arc<str>,
     fee: u64,
     cu_actual: u64,
-    dont_front: bool
+    dont_front: bool,
+    #[serde(flatten)]
+    block_time: BlockTime,
+    // Txs in the block including votes, what inclusion_order is out of
+    block_tx_count: Option<u32>,
+    cu_limit: Option<u32>,
+    // CU consumed by the whole block, votes included
+    block_cu: Option<u64>,
 }
 
 impl TransactionV2 {
@@ -23,6 +53,74 @@ impl TransactionV2 {
             fee,
             cu_actual,
             dont_front,
+            block_time: BlockTime::default(),
+            block_tx_count: None,
+            cu_limit: None,
+            block_cu: None,
         }
     }
-}
\ No newline at end of file
+
+    pub fn set_block_time(&mut self, block_time: Option<i64>) {
+        self.block_time = BlockTime(block_time);
+    }
+
+    pub fn set_block_tx_count(&mut self, block_tx_count: Option<u32>) {
+        self.block_tx_count = block_tx_count;
+    }
+
+    pub fn set_cu_limit(&mut self, cu_limit: Option<u32>) {
+        self.cu_limit = cu_limit;
+    }
+
+    pub fn set_block_cu(&mut self, block_cu: Option<u64>) {
+        self.block_cu = block_cu;
+    }
+
+    /// Share of the CU limit left unused, None if the limit isn't known
+    pub fn unused_cu_bps(&self) -> Option<u64> {
+        let cu_limit = self.cu_limit.filter(|&l| l > 0)? as u64;
+        Some(cu_limit.saturating_sub(self.cu_actual) * 10000 / cu_limit)
+    }
+
+    /// How far into its block the tx landed, 0 for the first tx and 10000 for the last
+    pub fn position_bps(&self) -> Option<u64> {
+        let count = self.block_tx_count.filter(|&c| c > 1)?;
+        Some((self.inclusion_order as u64 * 10000 / (count - 1) as u64).min(10000))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_bps() {
+        let mut tx = TransactionV2::new(1, 0, "sig".into(), 5000, 100, false);
+        assert_eq!(tx.position_bps(), None);
+        tx.set_block_tx_count(Some(1));
+        assert_eq!(tx.position_bps(), None);
+        tx.set_block_tx_count(Some(5));
+        assert_eq!(tx.position_bps(), Some(0));
+        let mut tx = TransactionV2::new(1, 4, "sig".into(), 5000, 100, false);
+        tx.set_block_tx_count(Some(5));
+        assert_eq!(tx.position_bps(), Some(10000));
+        let mut tx = TransactionV2::new(1, 1, "sig".into(), 5000, 100, false);
+        tx.set_block_tx_count(Some(5));
+        assert_eq!(tx.position_bps(), Some(2500));
+    }
+
+    #[test]
+    fn test_cu_limit_from_ixs() {
+        let ix = |program_id, data: Vec<u8>| Instruction { program_id, accounts: vec![], data };
+        let swap = ix(solana_sdk::pubkey::Pubkey::new_unique(), vec![1, 2, 3]);
+        let set_limit = ix(COMPUTE_BUDGET_PROGRAM_ID, [vec![SET_COMPUTE_UNIT_LIMIT], 150_000u32.to_le_bytes().to_vec()].concat());
+        let set_price = ix(COMPUTE_BUDGET_PROGRAM_ID, vec![3, 1, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(cu_limit_from_ixs(&[set_limit.clone(), set_price.clone(), swap.clone()]), 150_000);
+        assert_eq!(cu_limit_from_ixs(&[set_price, swap.clone(), swap.clone()]), 400_000);
+        assert_eq!(cu_limit_from_ixs(&vec![swap; 10]), MAX_CU_LIMIT);
+        let mut tx = TransactionV2::new(1, 0, "sig".into(), 5000, 30_000, false);
+        assert_eq!(tx.unused_cu_bps(), None);
+        tx.set_cu_limit(Some(120_000));
+        assert_eq!(tx.unused_cu_bps(), Some(7500));
+    }
+}
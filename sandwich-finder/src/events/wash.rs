@@ -0,0 +1,194 @@
+use std::{collections::HashMap, sync::Arc};
+
+use derive_getters::Getters;
+use uuid::Uuid;
+
+use crate::{events::{addresses::WSOL_MINT, swap::SwapV2}, utils::prefixed_env_var};
+
+#[derive(Clone, Debug)]
+pub struct WashConfig {
+    /// Max slots between consecutive swaps of the same run, runs don't extend past the group being detected on
+    pub window_slots: u64,
+    /// Buy+sell pairs needed to count as washing
+    pub min_round_trips: u64,
+    /// Max net position change on either side, relative to the volume on that side
+    pub max_net_bps: u64,
+}
+
+impl Default for WashConfig {
+    fn default() -> Self {
+        Self {
+            window_slots: 4,
+            min_round_trips: 2,
+            max_net_bps: 50,
+        }
+    }
+}
+
+impl WashConfig {
+    /// Reads `WASH_WINDOW_SLOTS`, `WASH_MIN_ROUND_TRIPS` and `WASH_MAX_NET_BPS`, falling back to the defaults
+    pub fn from_env() -> Self {
+        Self::from_env_with_prefix("")
+    }
+
+    /// Like [`WashConfig::from_env`], preferring the variables with `prefix` prepended
+    pub fn from_env_with_prefix(prefix: &str) -> Self {
+        let default = Self::default();
+        let var = |name: &str, default: u64| prefixed_env_var(prefix, name).and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self {
+            window_slots: var("WASH_WINDOW_SLOTS", default.window_slots),
+            min_round_trips: var("WASH_MIN_ROUND_TRIPS", default.min_round_trips),
+            max_net_bps: var("WASH_MAX_NET_BPS", default.max_net_bps),
+        }
+    }
+}
+
+/// A wallet swapping back and forth on one pool while ending up about where it started, i.e. volume for the sake of volume.
+/// Mint A is the input mint of the first swap.
+#[derive(Clone, Debug, Getters)]
+pub struct WashCandidate {
+    swaps: Arc<[SwapV2]>,
+    mint_a: Arc<str>,
+    mint_b: Arc<str>,
+    // Amounts spent plus received on each side
+    volume_a: u64,
+    volume_b: u64,
+    // Amounts received minus spent on each side
+    net_a: i128,
+    net_b: i128,
+    round_trips: u64,
+}
+
+impl WashCandidate {
+    pub fn uuid(&self) -> Uuid {
+        let name: Vec<u8> = self.swaps.iter().flat_map(|sw| sw.id().to_le_bytes()).collect();
+        Uuid::new_v5(&Uuid::NAMESPACE_DNS, &name)
+    }
+
+    pub fn authority(&self) -> &Arc<str> {
+        self.swaps[0].authority()
+    }
+
+    pub fn amm(&self) -> &Arc<str> {
+        self.swaps[0].amm()
+    }
+
+    pub fn slot(&self) -> u64 {
+        *self.swaps[0].slot()
+    }
+
+    pub fn last_slot(&self) -> u64 {
+        *self.swaps[self.swaps.len() - 1].slot()
+    }
+
+    /// SOL side of the volume, None unless the pair is priced in SOL
+    pub fn volume_lamports(&self) -> Option<u64> {
+        let wsol = WSOL_MINT.to_string();
+        if self.mint_a.as_ref() == wsol {
+            Some(self.volume_a)
+        } else if self.mint_b.as_ref() == wsol {
+            Some(self.volume_b)
+        } else {
+            None
+        }
+    }
+
+    fn new(swaps: &[SwapV2]) -> Self {
+        let mint_a = swaps[0].input_mint().clone();
+        let mint_b = swaps[0].output_mint().clone();
+        let (mut volume_a, mut volume_b, mut net_a, mut net_b) = (0u64, 0u64, 0i128, 0i128);
+        let (mut sells, mut buys) = (0u64, 0u64);
+        for swap in swaps.iter() {
+            let (input, output) = (*swap.input_amount(), *swap.output_amount());
+            if swap.input_mint() == &mint_a {
+                sells += 1;
+                volume_a = volume_a.saturating_add(input);
+                volume_b = volume_b.saturating_add(output);
+                net_a -= input as i128;
+                net_b += output as i128;
+            } else {
+                buys += 1;
+                volume_b = volume_b.saturating_add(input);
+                volume_a = volume_a.saturating_add(output);
+                net_b -= input as i128;
+                net_a += output as i128;
+            }
+        }
+        Self {
+            swaps: swaps.into(),
+            mint_a,
+            mint_b,
+            volume_a,
+            volume_b,
+            net_a,
+            net_b,
+            round_trips: sells.min(buys),
+        }
+    }
+
+    fn is_wash(&self, config: &WashConfig) -> bool {
+        let flat = |net: i128, volume: u64| volume > 0 && net.unsigned_abs() * 10000 <= volume as u128 * config.max_net_bps as u128;
+        self.round_trips >= config.min_round_trips && flat(self.net_a, self.volume_a) && flat(self.net_b, self.volume_b)
+    }
+}
+
+/// This function expects the swaps to be sorted in chronological order
+pub fn detect_washes(swaps: &[SwapV2], config: &WashConfig) -> Arc<[WashCandidate]> {
+    // swaps of each wallet on each AMM, both directions
+    let mut wallet_swaps: HashMap<(&str, &str), Vec<SwapV2>> = HashMap::new();
+    for swap in swaps.iter() {
+        wallet_swaps.entry((swap.authority(), swap.amm())).or_default().push(swap.clone());
+    }
+    let mut washes = vec![];
+    for swaps in wallet_swaps.values() {
+        // split into runs of swaps no more than window_slots apart
+        let mut start = 0;
+        for end in 1..=swaps.len() {
+            if end < swaps.len() && swaps[end].slot() - swaps[end - 1].slot() <= config.window_slots {
+                continue;
+            }
+            let candidate = WashCandidate::new(&swaps[start..end]);
+            if candidate.is_wash(config) {
+                washes.push(candidate);
+            }
+            start = end;
+        }
+    }
+    washes.sort_by_key(|w| *w.swaps[0].timestamp());
+    washes.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::SwapBuilder;
+
+    fn swap(slot: u64, authority: &str, buy: bool, input_amount: u64, output_amount: u64) -> SwapV2 {
+        let swap = SwapBuilder::new().authority(authority).amounts(input_amount, output_amount).at(slot, 0);
+        if buy { swap.buy("sol") } else { swap.sell("sol") }.build()
+    }
+
+    #[test]
+    fn test_detect_washes() {
+        let config = WashConfig::default();
+        let swaps = vec![
+            swap(1, "washer", true, 1000, 500),
+            swap(1, "trader", true, 1000, 500),
+            swap(2, "washer", false, 500, 999),
+            swap(3, "washer", true, 999, 500),
+            swap(4, "washer", false, 500, 998),
+            // too far from the rest
+            swap(20, "washer", true, 1000, 500),
+        ];
+        let washes = detect_washes(&swaps, &config);
+        assert_eq!(washes.len(), 1);
+        let wash = &washes[0];
+        assert_eq!((wash.swaps().len(), *wash.round_trips(), *wash.net_a(), *wash.net_b()), (4, 2, -2, 0));
+        assert_eq!((*wash.volume_a(), *wash.volume_b(), wash.last_slot()), (3996, 2000, 4));
+        // a single round trip isn't enough
+        assert!(detect_washes(&swaps[..3], &config).is_empty());
+        // neither is keeping most of what was bought
+        let accumulating = vec![swap(1, "buyer", true, 1000, 500), swap(2, "buyer", false, 100, 200), swap(3, "buyer", true, 1000, 500), swap(4, "buyer", false, 100, 200)];
+        assert!(detect_washes(&accumulating, &config).is_empty());
+    }
+}
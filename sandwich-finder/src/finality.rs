@@ -0,0 +1,118 @@
+use std::{collections::{BTreeMap, BTreeSet}, env};
+
+/// Finalized slots kept around for `is_finalized` lookups, well past how far back a released entry can reach
+const RETAINED_SLOTS: u64 = 1024;
+
+/// Whether `WRITE_COMMITMENT=finalized` is set, in which case results found at confirmed are only written once finalized
+pub fn write_at_finalized() -> bool {
+    env::var("WRITE_COMMITMENT").is_ok_and(|v| v == "finalized")
+}
+
+/// Holds results produced at confirmed commitment until their slots are finalized.
+/// Entries are keyed by the last slot they cover and released once that slot is finalized, or a later one
+/// is, in which case the slot was on an abandoned fork and `is_finalized` tells which parts to drop.
+/// Finalizations only arrive while the stream is connected, the ones missed in between have to be backfilled with
+/// [`FinalityBuffer::mark_finalized`] or the entries in those slots are taken for orphans.
+pub struct FinalityBuffer<T> {
+    pending: BTreeMap<u64, T>,
+    finalized: BTreeSet<u64>,
+    last_finalized: Option<u64>,
+}
+
+impl<T> Default for FinalityBuffer<T> {
+    fn default() -> Self {
+        Self {
+            pending: BTreeMap::new(),
+            finalized: BTreeSet::new(),
+            last_finalized: None,
+        }
+    }
+}
+
+impl<T> FinalityBuffer<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, slot: u64, value: T) {
+        self.pending.insert(slot, value);
+    }
+
+    /// Records `slot` as finalized and returns the entries up to it, oldest first
+    pub fn finalize(&mut self, slot: u64) -> Vec<(u64, T)> {
+        self.finalized.insert(slot);
+        self.last_finalized = self.last_finalized.max(Some(slot));
+        let newer = self.pending.split_off(&(slot + 1));
+        let released = std::mem::replace(&mut self.pending, newer);
+        // the released entries still need their slots looked up, however long they waited
+        let oldest = released.keys().next().copied().unwrap_or(slot);
+        self.finalized = self.finalized.split_off(&oldest.min(slot).saturating_sub(RETAINED_SLOTS));
+        released.into_iter().collect()
+    }
+
+    /// Records slots found finalized some other way, without releasing anything
+    pub fn mark_finalized(&mut self, slots: impl IntoIterator<Item = u64>) {
+        self.finalized.extend(slots);
+    }
+
+    /// The latest slot passed to [`FinalityBuffer::finalize`]
+    pub fn last_finalized(&self) -> Option<u64> {
+        self.last_finalized
+    }
+
+    /// Key of the oldest entry still waiting
+    pub fn oldest_pending(&self) -> Option<u64> {
+        self.pending.keys().next().copied()
+    }
+
+    pub fn is_finalized(&self, slot: u64) -> bool {
+        self.finalized.contains(&slot)
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finality_buffer() {
+        let mut buffer = FinalityBuffer::new();
+        buffer.insert(3, "a");
+        buffer.insert(7, "b");
+        buffer.insert(11, "c");
+        assert!(buffer.finalize(2).is_empty());
+        assert_eq!(buffer.finalize(3), vec![(3, "a")]);
+        // 4..=7 never finalized, they were skipped or on another fork
+        assert_eq!(buffer.finalize(8), vec![(7, "b")]);
+        assert!(buffer.is_finalized(3) && buffer.is_finalized(8) && !buffer.is_finalized(7));
+        assert_eq!(buffer.len(), 1);
+        assert_eq!(buffer.finalize(2000), vec![(11, "c")]);
+        assert!(buffer.is_empty());
+        // pruned once nothing released still needs it
+        buffer.finalize(2001);
+        assert!(!buffer.is_finalized(3));
+    }
+
+    #[test]
+    fn test_finality_gap() {
+        let mut buffer = FinalityBuffer::new();
+        buffer.finalize(10);
+        buffer.insert(12, "a");
+        buffer.insert(5000, "b");
+        // disconnected from 11 until 6000, the gap's finalized slots are backfilled before the next finalization
+        assert_eq!(buffer.last_finalized(), Some(10));
+        assert_eq!(buffer.oldest_pending(), Some(12));
+        buffer.mark_finalized([11, 12, 5000]);
+        assert_eq!(buffer.finalize(6000), vec![(12, "a"), (5000, "b")]);
+        // kept for the entries just released even though they're older than RETAINED_SLOTS
+        assert!(buffer.is_finalized(12) && buffer.is_finalized(5000));
+    }
+}
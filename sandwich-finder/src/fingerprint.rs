@@ -0,0 +1,281 @@
+use std::{collections::{HashMap, HashSet}, env, sync::Arc, time::Duration};
+
+use mysql::{prelude::Queryable as _, Pool, PooledConn, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::{detector::SLOTS_PER_HOUR, events::{addresses::JITO_TIP_ACCOUNTS, swap::QuoteLimits}, metrics, partition::chain_tip};
+
+/// Base fee of a single signature tx, the rest of the fee is priority fee
+const BASE_FEE_LAMPORTS: u64 = 5000;
+/// Entries kept in each ranked list of a fingerprint
+const TOP_N: usize = 10;
+
+#[derive(Clone, Debug)]
+pub struct FingerprintConfig {
+    /// Slots of history each fingerprint covers, ending at the chain tip
+    pub window_slots: u64,
+    /// Time between fingerprint runs
+    pub period: Duration,
+}
+
+impl Default for FingerprintConfig {
+    fn default() -> Self {
+        Self {
+            window_slots: 24 * SLOTS_PER_HOUR,
+            period: Duration::from_secs(3600),
+        }
+    }
+}
+
+impl FingerprintConfig {
+    /// Reads `FINGERPRINT_WINDOW_SLOTS` and `FINGERPRINT_PERIOD_SECS`, falling back to the defaults
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let var = |name: &str, default: u64| env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self {
+            window_slots: var("FINGERPRINT_WINDOW_SLOTS", default.window_slots),
+            period: Duration::from_secs(var("FINGERPRINT_PERIOD_SECS", default.period.as_secs()).max(1)),
+        }
+    }
+}
+
+/// A frontrun/backrun swap, or a transfer between the attacker's accounts within a sandwich
+#[derive(Clone, Debug)]
+pub struct AttackerLeg {
+    pub sandwich_id: Arc<str>,
+    pub attacker_id: u32,
+    pub attacker: Arc<str>,
+    pub slot: u64,
+    pub inclusion_order: u32,
+    pub outer_program: Option<Arc<str>>,
+    /// `None` for transfers
+    pub amm: Option<Arc<str>>,
+    pub input_amount: u64,
+    pub output_amount: u64,
+    pub quote_limits: QuoteLimits,
+    pub fee: Option<u64>,
+    pub cu_actual: Option<u64>,
+    pub block_time: Option<i64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Ranked {
+    pub key: String,
+    pub count: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Percentiles {
+    pub p10: u64,
+    pub p50: u64,
+    pub p90: u64,
+}
+
+/// Behavioural traits of an attacker cluster over a window, for telling bots apart
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Fingerprint {
+    pub members: Vec<String>,
+    pub sandwiches: u64,
+    pub wrapper_programs: Vec<Ranked>,
+    /// Priority fee per CU consumed in micro-lamports. The CU limit isn't indexed, so this is an upper bound of the CU price.
+    pub cu_price: Option<Percentiles>,
+    pub tip_accounts: Vec<Ranked>,
+    /// Slippage the attacker legs were submitted with, see [`QuoteLimits::slippage_bps`]
+    pub slippage_bps: Option<Percentiles>,
+    pub pools: Vec<Ranked>,
+    /// Attacker txs per UTC hour of day
+    pub hourly_activity: [u64; 24],
+}
+
+fn rank<'a>(keys: impl Iterator<Item = &'a str>) -> Vec<Ranked> {
+    let mut counts: HashMap<&str, u64> = HashMap::new();
+    keys.for_each(|key| *counts.entry(key).or_default() += 1);
+    let mut ranked: Vec<_> = counts.into_iter().map(|(key, count)| Ranked { key: key.to_string(), count }).collect();
+    ranked.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+    ranked.truncate(TOP_N);
+    ranked
+}
+
+pub fn percentiles(mut values: Vec<u64>) -> Option<Percentiles> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+    let at = |p: usize| values[(values.len() - 1) * p / 100];
+    Some(Percentiles { p10: at(10), p50: at(50), p90: at(90) })
+}
+
+impl Fingerprint {
+    /// Builds the fingerprint of the legs of one cluster, `tips` maps (slot, inclusion order) to the tip account paid by that tx
+    pub fn build(legs: &[&AttackerLeg], tips: &HashMap<(u64, u32), Arc<str>>) -> Self {
+        let mut members: Vec<_> = legs.iter().map(|l| l.attacker.to_string()).collect::<HashSet<_>>().into_iter().collect();
+        members.sort();
+        let swaps: Vec<_> = legs.iter().filter(|l| l.amm.is_some()).collect();
+        let mut seen = HashSet::new();
+        let txs: Vec<_> = legs.iter().filter(|l| seen.insert((l.slot, l.inclusion_order))).collect();
+        let mut hourly_activity = [0; 24];
+        txs.iter().filter_map(|l| l.block_time).for_each(|t| hourly_activity[(t.rem_euclid(86400) / 3600) as usize] += 1);
+        Self {
+            members,
+            sandwiches: legs.iter().map(|l| &l.sandwich_id).collect::<HashSet<_>>().len() as u64,
+            wrapper_programs: rank(swaps.iter().filter_map(|l| l.outer_program.as_deref())),
+            cu_price: percentiles(txs.iter().filter_map(|l| {
+                let cu_actual = l.cu_actual.filter(|&cu| cu > 0)?;
+                Some(l.fee?.saturating_sub(BASE_FEE_LAMPORTS) * 1_000_000 / cu_actual)
+            }).collect()),
+            tip_accounts: rank(txs.iter().filter_map(|l| tips.get(&(l.slot, l.inclusion_order)).map(|t| t.as_ref()))),
+            slippage_bps: percentiles(swaps.iter().filter_map(|l| l.quote_limits.slippage_bps(l.input_amount, l.output_amount)).collect()),
+            pools: rank(swaps.iter().filter_map(|l| l.amm.as_deref())),
+            hourly_activity,
+        }
+    }
+}
+
+fn find(parent: &mut HashMap<u32, u32>, x: u32) -> u32 {
+    let p = *parent.entry(x).or_insert(x);
+    if p == x {
+        return x;
+    }
+    let root = find(parent, p);
+    parent.insert(x, root);
+    root
+}
+
+/// Groups the attacker wallets that took part in the same sandwich, mapping each wallet's address id to its cluster.
+/// A cluster is identified by its lowest address id, so a cluster keeps its id as it picks up new wallets.
+pub fn cluster_attackers(legs: &[AttackerLeg]) -> HashMap<u32, u32> {
+    let mut parent = HashMap::new();
+    let mut first_of_sandwich: HashMap<&str, u32> = HashMap::new();
+    for leg in legs {
+        let first = *first_of_sandwich.entry(leg.sandwich_id.as_ref()).or_insert(leg.attacker_id);
+        let (a, b) = (find(&mut parent, first), find(&mut parent, leg.attacker_id));
+        parent.insert(a.max(b), a.min(b));
+    }
+    let attackers: Vec<_> = parent.keys().copied().collect();
+    attackers.into_iter().map(|a| (a, find(&mut parent, a))).collect()
+}
+
+fn load_legs(conn: &mut PooledConn, from_slot: u64, to_slot: u64) -> mysql::Result<Vec<AttackerLeg>> {
+    conn.exec_map(
+        "select s.id, e.authority_id, v.authority, v.slot, v.inclusion_order, v.outer_program, v.amm, v.input_amount, v.output_amount, e.min_out, e.max_in, t.fee, t.cu_actual, t.block_time from sandwiches s join event_view v on v.id=s.event_id join events_with_id e on e.id=s.event_id left join transactions t on t.slot=v.slot and t.inclusion_order=v.inclusion_order where s.role in ('FRONTRUN', 'BACKRUN', 'TRANSFER') and v.slot between ? and ?",
+        (from_slot, to_slot),
+        |row: Row| AttackerLeg {
+            sandwich_id: row.get("id").unwrap(),
+            attacker_id: row.get("authority_id").unwrap(),
+            attacker: row.get("authority").unwrap(),
+            slot: row.get("slot").unwrap(),
+            inclusion_order: row.get("inclusion_order").unwrap(),
+            outer_program: row.get("outer_program").unwrap(),
+            amm: row.get("amm").unwrap(),
+            input_amount: row.get("input_amount").unwrap(),
+            output_amount: row.get("output_amount").unwrap(),
+            quote_limits: QuoteLimits::new(row.get("min_out").unwrap(), row.get("max_in").unwrap()),
+            fee: row.get("fee").unwrap(),
+            cu_actual: row.get("cu_actual").unwrap(),
+            block_time: row.get("block_time").unwrap(),
+        },
+    )
+}
+
+/// Tip account paid by each tx in the range, by (slot, inclusion order)
+fn load_tips(conn: &mut PooledConn, from_slot: u64, to_slot: u64) -> mysql::Result<HashMap<(u64, u32), Arc<str>>> {
+    let tip_accounts: Vec<String> = JITO_TIP_ACCOUNTS.iter().map(|a| a.to_string()).collect();
+    let mut params: Vec<mysql::Value> = tip_accounts.into_iter().map(|a| a.into()).collect();
+    params.extend([from_slot.into(), to_slot.into()]);
+    conn.exec_map(
+        format!("select v.slot, v.inclusion_order, v.output_ata from event_view v where v.event_type='TRANSFER' and v.output_ata in ({}) and v.slot between ? and ?", "?,".repeat(JITO_TIP_ACCOUNTS.len()).trim_end_matches(",")),
+        params,
+        |(slot, inclusion_order, tip_account): (u64, u32, String)| ((slot, inclusion_order), tip_account.into()),
+    ).map(HashMap::from_iter)
+}
+
+/// Clusters the attackers active in the window ending at `to_slot` and stores a fingerprint per cluster, returning the number of clusters
+pub fn compute_fingerprints(conn: &mut PooledConn, to_slot: u64, config: &FingerprintConfig) -> mysql::Result<usize> {
+    let from_slot = to_slot.saturating_sub(config.window_slots);
+    let legs = load_legs(conn, from_slot, to_slot)?;
+    let tips = load_tips(conn, from_slot, to_slot)?;
+    let clusters = cluster_attackers(&legs);
+    let mut cluster_legs: HashMap<u32, Vec<&AttackerLeg>> = HashMap::new();
+    legs.iter().for_each(|l| cluster_legs.entry(clusters[&l.attacker_id]).or_default().push(l));
+    conn.exec_batch(
+        "insert into attacker_clusters (attacker_id, cluster_id, updated_slot) values (?, ?, ?) on duplicate key update cluster_id=values(cluster_id), updated_slot=values(updated_slot)",
+        clusters.iter().map(|(attacker_id, cluster_id)| (attacker_id, cluster_id, to_slot)),
+    )?;
+    conn.exec_batch(
+        "insert into attacker_fingerprints (cluster_id, from_slot, to_slot, fingerprint) values (?, ?, ?, ?) on duplicate key update from_slot=values(from_slot), fingerprint=values(fingerprint)",
+        cluster_legs.iter().map(|(cluster_id, legs)| (cluster_id, from_slot, to_slot, serde_json::to_string(&Fingerprint::build(legs, &tips)).unwrap())),
+    )?;
+    metrics::set("attacker_clusters", cluster_legs.len() as u64);
+    Ok(cluster_legs.len())
+}
+
+/// Runs `compute_fingerprints` every `config.period` against the chain tip, or the latest indexed slot before the tip is known
+pub fn start_fingerprinting(pool: Pool, config: FingerprintConfig) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(config.period);
+        loop {
+            interval.tick().await;
+            let mut conn = match pool.get_conn() {
+                Ok(conn) => conn,
+                Err(e) => {
+                    eprintln!("Fingerprinting unable to connect: {}", e);
+                    continue;
+                }
+            };
+            let tip = chain_tip(&mut conn);
+            match compute_fingerprints(&mut conn, tip, &config) {
+                Ok(clusters) => println!("fingerprinted {} attacker clusters up to slot {}", clusters, tip),
+                Err(e) => eprintln!("Failed to fingerprint attackers: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leg(sandwich_id: &str, attacker_id: u32, inclusion_order: u32, amm: Option<&str>) -> AttackerLeg {
+        AttackerLeg {
+            sandwich_id: sandwich_id.into(),
+            attacker_id,
+            attacker: format!("wallet{attacker_id}").into(),
+            slot: 100,
+            inclusion_order,
+            outer_program: Some("bot".into()),
+            amm: amm.map(|a| a.into()),
+            input_amount: 1000,
+            output_amount: 1000,
+            quote_limits: QuoteLimits::exact_in(Some(990)),
+            fee: Some(5000 + 200_000),
+            cu_actual: Some(100_000),
+            block_time: Some(3600 * 25 + 1),
+        }
+    }
+
+    #[test]
+    fn test_cluster_attackers() {
+        let legs = vec![
+            leg("a", 5, 1, Some("pool1")),
+            leg("a", 3, 3, Some("pool1")),
+            leg("b", 3, 4, Some("pool2")),
+            leg("b", 9, 6, None),
+            leg("c", 7, 7, Some("pool1")),
+        ];
+        let clusters = cluster_attackers(&legs);
+        assert_eq!(clusters, HashMap::from([(3, 3), (5, 3), (9, 3), (7, 7)]));
+        let tips = HashMap::from([((100, 1), "tip".into())]);
+        let members: Vec<_> = legs.iter().filter(|l| clusters[&l.attacker_id] == 3).collect();
+        let fingerprint = Fingerprint::build(&members, &tips);
+        assert_eq!(fingerprint.members, vec!["wallet3", "wallet5", "wallet9"]);
+        assert_eq!(fingerprint.sandwiches, 2);
+        assert_eq!(fingerprint.pools, vec![Ranked { key: "pool1".into(), count: 2 }, Ranked { key: "pool2".into(), count: 1 }]);
+        assert_eq!(fingerprint.tip_accounts, vec![Ranked { key: "tip".into(), count: 1 }]);
+        assert_eq!(fingerprint.slippage_bps, Some(Percentiles { p10: 100, p50: 100, p90: 100 }));
+        assert_eq!(fingerprint.cu_price, Some(Percentiles { p10: 2_000_000, p50: 2_000_000, p90: 2_000_000 }));
+        assert_eq!(fingerprint.hourly_activity[1], 4);
+    }
+}
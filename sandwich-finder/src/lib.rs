@@ -1,3 +1,32 @@
+pub mod api;
+pub mod bundles;
+pub mod canary;
+pub mod commands;
+pub mod config;
+pub mod db;
+pub mod decode_failures;
 pub mod detector;
+pub mod drift;
 pub mod utils;
-pub mod events;
\ No newline at end of file
+pub mod events;
+pub mod event_cache;
+pub mod finality;
+pub mod fingerprint;
+pub mod lut_cache;
+pub mod metrics;
+pub mod partition;
+pub mod prices;
+pub mod redact;
+pub mod replica;
+pub mod rpc;
+pub mod shadow;
+pub mod shutdown;
+pub mod sinks;
+pub mod source;
+#[cfg(feature = "telegram")]
+pub mod telegram;
+#[cfg(test)]
+mod test_utils;
+pub mod ui;
+pub mod views;
+pub mod wal;
\ No newline at end of file
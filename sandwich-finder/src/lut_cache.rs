@@ -0,0 +1,138 @@
+use dashmap::{mapref::one::Ref, DashMap};
+use solana_sdk::{address_lookup_table::{state::AddressLookupTable, AddressLookupTableAccount}, pubkey::Pubkey};
+use yellowstone_grpc_proto::geyser::SubscribeUpdateAccount;
+
+use crate::{events::addresses::LUT_PROGRAM_ID, metrics};
+
+/// A deactivated table can still be looked up until its deactivation slot drops out of the slot hashes sysvar
+const DEACTIVATION_COOLDOWN_SLOTS: u64 = 513;
+
+/// Lookup tables seen so far, fetched over rpc on first use and kept up to date off account updates
+#[derive(Debug, Default)]
+pub struct LutCache {
+    tables: DashMap<Pubkey, AddressLookupTableAccount>,
+    /// Deactivation slot of the cached tables being deactivated
+    deactivations: DashMap<Pubkey, u64>,
+}
+
+impl LutCache {
+    pub fn get(&self, key: &Pubkey) -> Option<Ref<'_, Pubkey, AddressLookupTableAccount>> {
+        self.tables.get(key)
+    }
+
+    pub fn contains_key(&self, key: &Pubkey) -> bool {
+        self.tables.contains_key(key)
+    }
+
+    /// Caches the table in `data` if `owner` is the lookup table program. Updates owned by anything else are ignored,
+    /// except for a cached table being reassigned to the system program by closing it, which evicts it.
+    pub fn update(&self, key: Pubkey, owner: &Pubkey, data: &[u8]) {
+        if *owner != LUT_PROGRAM_ID {
+            if self.remove(&key) {
+                metrics::incr("luts_closed");
+            } else {
+                metrics::incr("lut_updates_ignored");
+            }
+            return;
+        }
+        let lut = match AddressLookupTable::deserialize(data) {
+            Ok(lut) => lut,
+            Err(e) => {
+                eprintln!("Failed to deserialize lut {}: {}", key, e);
+                metrics::incr("lut_decode_failures");
+                return;
+            }
+        };
+        if lut.meta.deactivation_slot == u64::MAX {
+            self.deactivations.remove(&key);
+        } else {
+            self.deactivations.insert(key, lut.meta.deactivation_slot);
+        }
+        // refuse to shorten luts
+        if self.tables.get(&key).is_some_and(|existing| existing.addresses.len() > lut.addresses.len()) {
+            return;
+        }
+        self.tables.insert(key, AddressLookupTableAccount {
+            key,
+            addresses: lut.addresses.to_vec(),
+        });
+    }
+
+    /// Like [`LutCache::update`], but takes an update off the grpc stream
+    pub fn apply_update(&self, account: SubscribeUpdateAccount) {
+        let Some(account_info) = account.account else {
+            return;
+        };
+        let (Ok(key), Ok(owner)) = (Pubkey::try_from(&account_info.pubkey[..]), Pubkey::try_from(&account_info.owner[..])) else {
+            metrics::incr("lut_updates_ignored");
+            return;
+        };
+        self.update(key, &owner, &account_info.data);
+    }
+
+    /// Drops the tables whose cooldown is over by `slot`, they can't be used by any later transaction.
+    /// Returns the number of tables dropped.
+    pub fn evict_deactivated(&self, slot: u64) -> usize {
+        let expired: Vec<Pubkey> = self.deactivations.iter()
+            .filter(|entry| slot > entry.value().saturating_add(DEACTIVATION_COOLDOWN_SLOTS))
+            .map(|entry| *entry.key())
+            .collect();
+        expired.iter().for_each(|key| {
+            self.remove(key);
+        });
+        if !expired.is_empty() {
+            metrics::add("luts_evicted", expired.len() as u64);
+        }
+        expired.len()
+    }
+
+    fn remove(&self, key: &Pubkey) -> bool {
+        self.deactivations.remove(key);
+        self.tables.remove(key).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use solana_sdk::address_lookup_table::state::LookupTableMeta;
+
+    use super::*;
+
+    fn lut_data(deactivation_slot: u64, addresses: &[Pubkey]) -> Vec<u8> {
+        AddressLookupTable {
+            meta: LookupTableMeta { deactivation_slot, ..LookupTableMeta::default() },
+            addresses: Cow::Borrowed(addresses),
+        }.serialize_for_tests().unwrap()
+    }
+
+    #[test]
+    fn test_update() {
+        let cache = LutCache::default();
+        let (key, addresses) = (Pubkey::new_unique(), [Pubkey::new_unique(), Pubkey::new_unique()]);
+        cache.update(key, &Pubkey::new_unique(), &lut_data(u64::MAX, &addresses));
+        assert!(!cache.contains_key(&key));
+        cache.update(key, &LUT_PROGRAM_ID, &[1, 2, 3]);
+        assert!(!cache.contains_key(&key));
+        cache.update(key, &LUT_PROGRAM_ID, &lut_data(u64::MAX, &addresses));
+        assert_eq!(cache.get(&key).unwrap().addresses, addresses);
+        cache.update(key, &LUT_PROGRAM_ID, &lut_data(u64::MAX, &addresses[..1]));
+        assert_eq!(cache.get(&key).unwrap().addresses.len(), 2);
+        // closed
+        cache.update(key, &Pubkey::default(), &[]);
+        assert!(!cache.contains_key(&key));
+    }
+
+    #[test]
+    fn test_evict_deactivated() {
+        let cache = LutCache::default();
+        let (active, deactivated) = (Pubkey::new_unique(), Pubkey::new_unique());
+        cache.update(active, &LUT_PROGRAM_ID, &lut_data(u64::MAX, &[Pubkey::new_unique()]));
+        cache.update(deactivated, &LUT_PROGRAM_ID, &lut_data(100, &[Pubkey::new_unique()]));
+        assert_eq!(cache.evict_deactivated(100 + DEACTIVATION_COOLDOWN_SLOTS), 0);
+        assert_eq!(cache.evict_deactivated(101 + DEACTIVATION_COOLDOWN_SLOTS), 1);
+        assert!(cache.contains_key(&active));
+        assert!(!cache.contains_key(&deactivated));
+    }
+}
@@ -0,0 +1,24 @@
+use std::time::Duration;
+
+/// Kept by the core crate so its finders can count what they skip too
+pub use sandwich_finder_core::metrics::{add, get, incr, set, snapshot};
+
+/// Prometheus text exposition of `snapshot()`.
+pub fn render() -> String {
+    snapshot().iter().map(|(name, value)| format!("{} {}\n", name, value)).collect()
+}
+
+/// Periodically prints every non-zero metric, for binaries that don't serve `/metrics`.
+pub fn start_reporter(period: Duration) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let metrics = snapshot().iter().filter(|(_, v)| *v > 0).map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>();
+            if !metrics.is_empty() {
+                println!("metrics: {}", metrics.join(" "));
+            }
+        }
+    });
+}
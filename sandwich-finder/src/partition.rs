@@ -0,0 +1,148 @@
+use std::{env, time::Duration};
+
+use mysql::{prelude::Queryable as _, Pool, PooledConn};
+
+use crate::metrics;
+
+/// Tables range partitioned on `slot`, see migrations/0006_slot_partitions.sql
+pub const PARTITIONED_TABLES: [&str; 2] = ["events_with_id", "transactions"];
+
+#[derive(Clone, Debug)]
+pub struct PartitionConfig {
+    /// Slots covered by each partition, boundaries are multiples of this
+    pub partition_slots: u64,
+    /// Partitions to keep ready past the one holding the chain tip
+    pub ahead: u64,
+    /// Partitions entirely older than this many slots are dropped, `None` keeps everything
+    pub retention_slots: Option<u64>,
+}
+
+impl Default for PartitionConfig {
+    fn default() -> Self {
+        Self {
+            partition_slots: 432000,
+            ahead: 2,
+            retention_slots: None,
+        }
+    }
+}
+
+impl PartitionConfig {
+    /// Reads `PARTITION_SLOTS`, `PARTITION_AHEAD` and `PARTITION_RETENTION_SLOTS` (0 to keep everything), falling back to the defaults
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let var = |name: &str, default: u64| env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self {
+            partition_slots: var("PARTITION_SLOTS", default.partition_slots).max(1),
+            ahead: var("PARTITION_AHEAD", default.ahead),
+            retention_slots: Some(var("PARTITION_RETENTION_SLOTS", default.retention_slots.unwrap_or(0))).filter(|&r| r > 0),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PartitionPlan {
+    /// (first slot, end slot exclusive) of the partitions to split off `pmax`
+    pub create: Vec<(u64, u64)>,
+    /// Names of expired partitions
+    pub drop: Vec<String>,
+}
+
+/// Works out the partitions to create and drop given the existing ones as (name, upper bound), excluding `pmax`
+pub fn plan_partitions(existing: &[(String, u64)], tip: u64, config: &PartitionConfig) -> PartitionPlan {
+    let mut plan = PartitionPlan::default();
+    let Some(mut last) = existing.iter().map(|(_, upper)| *upper).max() else {
+        // not partitioned yet
+        return plan;
+    };
+    let target = tip + config.ahead * config.partition_slots;
+    while last <= target {
+        let end = (last / config.partition_slots + 1) * config.partition_slots;
+        plan.create.push((last, end));
+        last = end;
+    }
+    if let Some(retention) = config.retention_slots {
+        let cutoff = tip.saturating_sub(retention);
+        plan.drop = existing.iter().filter(|(_, upper)| *upper <= cutoff).map(|(name, _)| name.clone()).collect();
+    }
+    plan
+}
+
+/// Brings the partitions of every table in `PARTITIONED_TABLES` in line with `plan_partitions`
+pub fn maintain_partitions(conn: &mut PooledConn, tip: u64, config: &PartitionConfig) -> mysql::Result<()> {
+    for table in PARTITIONED_TABLES {
+        let existing: Vec<(String, String)> = conn.exec(
+            "select partition_name, partition_description from information_schema.partitions where table_schema=database() and table_name=? and partition_name is not null",
+            (table,),
+        )?;
+        let existing: Vec<_> = existing.into_iter().filter_map(|(name, upper)| Some((name, upper.parse().ok()?))).collect();
+        let plan = plan_partitions(&existing, tip, config);
+        if !plan.create.is_empty() {
+            let partitions = plan.create.iter().map(|(start, end)| format!("partition p{start} values less than ({end}), ")).collect::<String>();
+            conn.query_drop(format!("alter table {table} reorganize partition pmax into ({partitions}partition pmax values less than maxvalue)"))?;
+            println!("created {} partitions on {}, up to slot {}", plan.create.len(), table, plan.create.last().unwrap().1);
+        }
+        if !plan.drop.is_empty() {
+            conn.query_drop(format!("alter table {table} drop partition {}", plan.drop.join(", ")))?;
+            println!("dropped partitions {:?} from {}", plan.drop, table);
+            metrics::add("partitions_dropped", plan.drop.len() as u64);
+        }
+    }
+    Ok(())
+}
+
+/// The chain tip seen by the indexer, or the latest indexed slot before the tip is known
+pub fn chain_tip(conn: &mut PooledConn) -> u64 {
+    match Some(metrics::get("chain_tip_slot")).filter(|&s| s > 0) {
+        Some(tip) => tip,
+        None => conn.query_first("select max(slot) from transactions").ok().flatten().flatten().unwrap_or(0),
+    }
+}
+
+/// Runs `maintain_partitions` every `period` against the chain tip, or the latest indexed slot before the tip is known
+pub fn start_partition_maintenance(pool: Pool, config: PartitionConfig, period: Duration) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            let mut conn = match pool.get_conn() {
+                Ok(conn) => conn,
+                Err(e) => {
+                    eprintln!("Partition maintenance unable to connect: {}", e);
+                    continue;
+                }
+            };
+            let tip = chain_tip(&mut conn);
+            if let Err(e) = maintain_partitions(&mut conn, tip, &config) {
+                eprintln!("Partition maintenance failed: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(retention_slots: Option<u64>) -> PartitionConfig {
+        PartitionConfig { partition_slots: 100, ahead: 2, retention_slots }
+    }
+
+    #[test]
+    fn test_plan_creates_ahead_of_tip() {
+        let existing = vec![("p0".to_string(), 250)];
+        let plan = plan_partitions(&existing, 420, &config(None));
+        assert_eq!(plan.create, vec![(250, 300), (300, 400), (400, 500), (500, 600), (600, 700)]);
+        assert!(plan.drop.is_empty());
+        assert_eq!(plan_partitions(&existing, 0, &config(None)).create, vec![]);
+        assert_eq!(plan_partitions(&[], 420, &config(None)), PartitionPlan::default());
+    }
+
+    #[test]
+    fn test_plan_drops_expired() {
+        let existing = vec![("p0".to_string(), 300), ("p300".to_string(), 400), ("p400".to_string(), 500), ("p500".to_string(), 600)];
+        let plan = plan_partitions(&existing, 450, &config(Some(50)));
+        assert_eq!(plan.drop, vec!["p0".to_string(), "p300".to_string()]);
+        assert_eq!(plan.create, vec![(600, 700)]);
+    }
+}
@@ -0,0 +1,100 @@
+use std::{env, sync::Arc};
+
+use dashmap::DashMap;
+
+use crate::{detector::SLOTS_PER_HOUR, events::{addresses::WSOL_MINT, swap::SwapV2}};
+
+/// Lamports paid for `tokens` of a mint in the last swap against SOL
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Price {
+    lamports: u64,
+    tokens: u64,
+    slot: u64,
+}
+
+/// SOL price of each mint, taken from the last swap between it and WSOL the indexer saw. Prices are only known
+/// for mints traded against SOL since startup, and go stale past `PRICE_MAX_AGE_SLOTS`.
+pub struct PriceOracle {
+    prices: DashMap<Arc<str>, Price>,
+    /// Swaps with a smaller WSOL leg don't move the price, dust trades round too much to be one
+    min_lamports: u64,
+    max_age_slots: u64,
+}
+
+impl PriceOracle {
+    pub fn new(min_lamports: u64, max_age_slots: u64) -> Self {
+        Self {
+            prices: DashMap::new(),
+            min_lamports,
+            max_age_slots,
+        }
+    }
+
+    /// Reads `PRICE_MIN_LAMPORTS`, 0.01 SOL by default, and `PRICE_MAX_AGE_SLOTS`, an hour by default
+    pub fn from_env() -> Self {
+        let var = |name: &str, default: u64| env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self::new(var("PRICE_MIN_LAMPORTS", 10_000_000), var("PRICE_MAX_AGE_SLOTS", SLOTS_PER_HOUR))
+    }
+
+    /// Takes the price of the swap's token if it's a complete swap against SOL
+    pub fn observe(&self, swap: &SwapV2) {
+        if !swap.is_complete() {
+            return;
+        }
+        let wsol = WSOL_MINT.to_string();
+        let (mint, lamports, tokens) = if swap.input_mint().as_ref() == wsol && swap.output_mint().as_ref() != wsol {
+            (swap.output_mint(), *swap.input_amount(), *swap.output_amount())
+        } else if swap.output_mint().as_ref() == wsol && swap.input_mint().as_ref() != wsol {
+            (swap.input_mint(), *swap.output_amount(), *swap.input_amount())
+        } else {
+            return;
+        };
+        if lamports < self.min_lamports || tokens == 0 {
+            return;
+        }
+        self.prices.insert(mint.clone(), Price { lamports, tokens, slot: *swap.slot() });
+    }
+
+    /// `amount` of `mint` in lamports as of `slot`, `None` without a recent enough price
+    pub fn lamports(&self, mint: &str, amount: u64, slot: u64) -> Option<u64> {
+        if mint == WSOL_MINT.to_string() {
+            return Some(amount);
+        }
+        let price = *self.prices.get(mint)?;
+        if slot.saturating_sub(price.slot) > self.max_age_slots {
+            return None;
+        }
+        Some((amount as u128 * price.lamports as u128 / price.tokens as u128).min(u64::MAX as u128) as u64)
+    }
+
+    /// (input, output) amounts of the swap in lamports, priced after the swap itself is observed
+    pub fn sol_amounts(&self, swap: &SwapV2) -> (Option<u64>, Option<u64>) {
+        self.observe(swap);
+        (self.lamports(swap.input_mint(), *swap.input_amount(), *swap.slot()), self.lamports(swap.output_mint(), *swap.output_amount(), *swap.slot()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::SwapBuilder;
+
+    fn swap(slot: u64, input_mint: &str, output_mint: &str, input_amount: u64, output_amount: u64) -> SwapV2 {
+        SwapBuilder::new().mints(input_mint, output_mint).amounts(input_amount, output_amount).at(slot, 0).build()
+    }
+
+    #[test]
+    fn test_sol_amounts() {
+        let wsol = WSOL_MINT.to_string();
+        let prices = PriceOracle::new(100, 10);
+        // no price for either token yet
+        assert_eq!(prices.sol_amounts(&swap(1, "token", "usdc", 10, 20)), (None, None));
+        // 4 lamports per token
+        assert_eq!(prices.sol_amounts(&swap(1, &wsol, "token", 400, 100)), (Some(400), Some(400)));
+        assert_eq!(prices.sol_amounts(&swap(2, "token", "usdc", 10, 20)), (Some(40), None));
+        // too small to move the price
+        assert_eq!(prices.sol_amounts(&swap(3, "token", &wsol, 10, 10)), (Some(40), Some(10)));
+        // stale
+        assert_eq!(prices.lamports("token", 10, 12), None);
+    }
+}
@@ -0,0 +1,85 @@
+use std::{env, sync::Arc};
+
+use sha2::{Digest as _, Sha256};
+
+use crate::events::{swap::SwapV2, transfer::TransferV2};
+
+pub const REDACTED: &str = "redacted";
+
+/// What to strip from API responses when running a public instance.
+/// Configured with `REDACT`, a comma separated list of `victims` (hash victim wallets) and `atas` (hide token accounts),
+/// victim hashes are salted with `REDACT_SALT`.
+#[derive(Clone, Debug, Default)]
+pub struct Redaction {
+    hash_victims: bool,
+    hide_atas: bool,
+    salt: Arc<str>,
+}
+
+impl Redaction {
+    pub fn from_env() -> Self {
+        let opts = env::var("REDACT").unwrap_or_default();
+        let opts: Vec<_> = opts.split(',').map(|s| s.trim()).collect();
+        Self {
+            hash_victims: opts.contains(&"victims"),
+            hide_atas: opts.contains(&"atas"),
+            salt: env::var("REDACT_SALT").unwrap_or_default().into(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.hash_victims || self.hide_atas
+    }
+
+    /// Replaces a victim's address with a stable pseudonym so victims can still be told apart
+    pub fn victim(&self, address: &str) -> Option<String> {
+        self.hash_victims.then(|| {
+            let mut hasher = Sha256::new();
+            hasher.update(self.salt.as_bytes());
+            hasher.update(address.as_bytes());
+            hex::encode(&hasher.finalize()[..16])
+        })
+    }
+
+    /// Token accounts are hidden entirely, or hashed like the wallet if they belong to a victim
+    pub fn ata(&self, address: &str, victim: bool) -> Option<String> {
+        if self.hide_atas {
+            Some(REDACTED.to_string())
+        } else if victim {
+            self.victim(address)
+        } else {
+            None
+        }
+    }
+}
+
+pub trait Redact {
+    /// `victim` tells whether the value belongs to a victim, in which case its wallet gets hashed
+    fn redact(&mut self, redaction: &Redaction, victim: bool);
+}
+
+impl Redact for SwapV2 {
+    fn redact(&mut self, redaction: &Redaction, victim: bool) {
+        if victim {
+            if let Some(authority) = redaction.victim(self.authority()) {
+                self.set_authority(authority.into());
+            }
+        }
+        let input_ata = redaction.ata(self.input_ata(), victim).map_or_else(|| self.input_ata().clone(), Arc::from);
+        let output_ata = redaction.ata(self.output_ata(), victim).map_or_else(|| self.output_ata().clone(), Arc::from);
+        self.set_atas(input_ata, output_ata);
+    }
+}
+
+impl Redact for TransferV2 {
+    fn redact(&mut self, redaction: &Redaction, victim: bool) {
+        if victim {
+            if let Some(authority) = redaction.victim(self.authority()) {
+                self.set_authority(authority.into());
+            }
+        }
+        let input_ata = redaction.ata(self.input_ata(), victim).map_or_else(|| self.input_ata().clone(), Arc::from);
+        let output_ata = redaction.ata(self.output_ata(), victim).map_or_else(|| self.output_ata().clone(), Arc::from);
+        self.set_atas(input_ata, output_ata);
+    }
+}
@@ -0,0 +1,130 @@
+use std::{env, sync::{atomic::{AtomicBool, Ordering}, Arc}, time::Duration};
+
+use mysql::{prelude::Queryable as _, Pool, PooledConn, Row};
+
+use crate::{db::{create_pool, PoolConfig}, metrics};
+
+#[derive(Clone, Debug)]
+pub struct ReplicaConfig {
+    /// The primary is used instead while the replica is further behind than this
+    pub max_lag_secs: u64,
+    /// How often the replica's lag is checked
+    pub check_secs: u64,
+}
+
+impl Default for ReplicaConfig {
+    fn default() -> Self {
+        Self {
+            max_lag_secs: 30,
+            check_secs: 5,
+        }
+    }
+}
+
+impl ReplicaConfig {
+    /// Reads `REPLICA_MAX_LAG_SECS` and `REPLICA_CHECK_SECS`, falling back to the defaults
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let var = |name: &str, default: u64| env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self {
+            max_lag_secs: var("REPLICA_MAX_LAG_SECS", default.max_lag_secs),
+            check_secs: var("REPLICA_CHECK_SECS", default.check_secs).max(1),
+        }
+    }
+}
+
+/// Connections for read-only queries, kept off the primary so heavy API queries don't stall the inserts.
+/// Reads go to the replica unless it's lagging or unreachable, in which case they fail over to the primary.
+#[derive(Clone)]
+pub struct ReadPool {
+    primary: Pool,
+    replica: Option<Pool>,
+    lagging: Arc<AtomicBool>,
+}
+
+impl ReadPool {
+    /// Reads from the primary only
+    pub fn primary_only(primary: Pool) -> Self {
+        Self {
+            primary,
+            replica: None,
+            lagging: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Uses the replica at `MYSQL_READ` if set, watching its lag as configured by [`ReplicaConfig::from_env`].
+    /// Must be called from within a tokio runtime.
+    pub fn from_env(primary: Pool) -> Self {
+        let Some(url) = env::var("MYSQL_READ").ok().filter(|url| !url.is_empty()) else {
+            return Self::primary_only(primary);
+        };
+        let pool = Self {
+            primary,
+            replica: Some(create_pool(&url, &PoolConfig::from_env())),
+            // until the first check says otherwise
+            lagging: Arc::new(AtomicBool::new(true)),
+        };
+        pool.start_lag_monitor(ReplicaConfig::from_env());
+        pool
+    }
+
+    /// A connection to the replica, or the primary if there's no usable replica
+    pub fn get_conn(&self) -> mysql::Result<PooledConn> {
+        match &self.replica {
+            Some(replica) if !self.lagging.load(Ordering::Relaxed) => replica.get_conn().or_else(|e| {
+                eprintln!("Failed to connect to the replica, reading from the primary: {}", e);
+                self.primary.get_conn()
+            }),
+            _ => self.primary.get_conn(),
+        }
+    }
+
+    /// For the few API queries that write
+    pub fn primary(&self) -> &Pool {
+        &self.primary
+    }
+
+    fn start_lag_monitor(&self, config: ReplicaConfig) {
+        let Some(replica) = self.replica.clone() else {
+            return;
+        };
+        let lagging = self.lagging.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(config.check_secs));
+            loop {
+                interval.tick().await;
+                let lag = match replica.get_conn().and_then(|mut conn| replica_lag(&mut conn)) {
+                    Ok(lag) => lag,
+                    Err(e) => {
+                        eprintln!("Failed to check the replica's lag: {}", e);
+                        None
+                    }
+                };
+                metrics::set("replica_lag_secs", lag.unwrap_or(0));
+                let over = lag.is_none_or(|lag| lag > config.max_lag_secs);
+                if over != lagging.swap(over, Ordering::Relaxed) {
+                    match lag {
+                        Some(lag) if over => println!("replica is {}s behind, reading from the primary", lag),
+                        None => println!("replica isn't replicating, reading from the primary"),
+                        _ => println!("replica caught up, reading from it again"),
+                    }
+                }
+                metrics::set("replica_fallback", over as u64);
+            }
+        });
+    }
+}
+
+/// Seconds the replica is behind its source, `None` if replication is stopped.
+/// A server that isn't set up as a replica, like a managed read endpoint, counts as up to date.
+fn replica_lag(conn: &mut PooledConn) -> mysql::Result<Option<u64>> {
+    // mysql 8.0.22+ renamed the statement and its columns, mariadb and older mysql only have the old ones
+    let (row, column) = match conn.query_first::<Row, _>("show replica status") {
+        Ok(row) => (row, "Seconds_Behind_Source"),
+        Err(_) => (conn.query_first::<Row, _>("show slave status")?, "Seconds_Behind_Master"),
+    };
+    let Some(row) = row else {
+        return Ok(Some(0));
+    };
+    Ok(row.get::<Option<u64>, _>(column).or_else(|| row.get::<Option<u64>, _>("Seconds_Behind_Master")).flatten())
+}
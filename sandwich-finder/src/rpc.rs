@@ -0,0 +1,288 @@
+use std::{cmp::Reverse, env, future::Future, sync::Mutex, time::{Duration, Instant}};
+
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_rpc_client_api::client_error::{Error as ClientError, ErrorKind};
+use solana_sdk::{account::Account, commitment_config::CommitmentConfig, pubkey::Pubkey};
+use tokio::sync::Semaphore;
+
+use crate::metrics;
+
+/// How hard [`BoundedRpc`] may hit the providers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RpcLimits {
+    /// Calls in flight at once, the rest wait for a permit
+    pub max_concurrency: usize,
+    /// Retries of a call failing on the transport or a rate limit, errors returned by the node aren't retried
+    pub max_retries: u32,
+    /// Wait before the first retry, doubling with each one after
+    pub backoff: Duration,
+}
+
+impl Default for RpcLimits {
+    fn default() -> Self {
+        Self {
+            max_concurrency: 8,
+            max_retries: 3,
+            backoff: Duration::from_millis(250),
+        }
+    }
+}
+
+impl RpcLimits {
+    /// Reads `RPC_MAX_CONCURRENCY`, `RPC_MAX_RETRIES` and `RPC_BACKOFF_MS`, falling back to the defaults
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let var = |name: &str| env::var(name).ok().and_then(|v| v.parse::<u64>().ok());
+        Self {
+            max_concurrency: var("RPC_MAX_CONCURRENCY").filter(|&c| c > 0).map_or(default.max_concurrency, |c| c as usize),
+            max_retries: var("RPC_MAX_RETRIES").map_or(default.max_retries, |r| r as u32),
+            backoff: var("RPC_BACKOFF_MS").map_or(default.backoff, Duration::from_millis),
+        }
+    }
+
+    /// Wait before retry number `retry`, counting from 0
+    fn backoff(&self, retry: u32) -> Duration {
+        self.backoff.saturating_mul(2u32.saturating_pow(retry))
+    }
+}
+
+/// Budgets are counted over windows of this long
+const BUDGET_WINDOW: Duration = Duration::from_secs(60);
+
+/// One of the urls in `RPC_URLS`
+struct Provider {
+    url: String,
+    client: RpcClient,
+    weight: i64,
+    /// Calls per [`BUDGET_WINDOW`], unlimited if `None`
+    budget: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct ProviderState {
+    /// Smooth weighted round-robin, the provider furthest ahead goes next
+    current_weight: i64,
+    window_start: Option<Instant>,
+    window_calls: u64,
+    consecutive_failures: u32,
+    blacklisted_until: Option<Instant>,
+}
+
+/// Rotates calls across rpc providers by weight, skipping those out of budget for the minute and those blacklisted
+/// after `RPC_BLACKLIST_FAILURES` failures in a row, for `RPC_BLACKLIST_SECS`
+pub struct RpcPool {
+    providers: Vec<Provider>,
+    states: Mutex<Vec<ProviderState>>,
+    failures_to_blacklist: u32,
+    blacklist_for: Duration,
+}
+
+impl RpcPool {
+    /// Each of `(url, weight, calls per minute)`, 0 being unlimited
+    pub fn new(providers: impl IntoIterator<Item = (String, u32, u64)>, commitment: CommitmentConfig, failures_to_blacklist: u32, blacklist_for: Duration) -> Self {
+        let providers: Vec<Provider> = providers.into_iter().map(|(url, weight, budget)| Provider {
+            client: RpcClient::new_with_commitment(url.clone(), commitment),
+            url,
+            weight: weight.max(1) as i64,
+            budget: (budget > 0).then_some(budget),
+        }).collect();
+        assert!(!providers.is_empty(), "no rpc url configured");
+        Self {
+            states: Mutex::new(vec![ProviderState::default(); providers.len()]),
+            providers,
+            failures_to_blacklist: failures_to_blacklist.max(1),
+            blacklist_for,
+        }
+    }
+
+    /// The comma separated `RPC_URLS`, `RPC_WEIGHTS` (1 by default) and `RPC_BUDGETS` (calls per minute, 0 or
+    /// missing for unlimited), lined up by position. `fallback_url` alone if `RPC_URLS` isn't set.
+    pub fn from_env(fallback_url: &str, commitment: CommitmentConfig) -> Self {
+        let list = |name: &str| env::var(name).ok().filter(|v| !v.is_empty()).map(|v| v.split(',').map(|s| s.trim().to_string()).collect::<Vec<_>>()).unwrap_or_default();
+        let mut urls = list("RPC_URLS");
+        if urls.is_empty() {
+            urls.push(fallback_url.to_string());
+        }
+        let (weights, budgets) = (list("RPC_WEIGHTS"), list("RPC_BUDGETS"));
+        let providers = urls.into_iter().enumerate().map(|(i, url)| {
+            let weight = weights.get(i).and_then(|w| w.parse().ok()).unwrap_or(1);
+            let budget = budgets.get(i).and_then(|b| b.parse().ok()).unwrap_or(0);
+            (url, weight, budget)
+        });
+        let failures_to_blacklist = env::var("RPC_BLACKLIST_FAILURES").ok().and_then(|v| v.parse().ok()).unwrap_or(5);
+        let blacklist_for = Duration::from_secs(env::var("RPC_BLACKLIST_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(60));
+        Self::new(providers, commitment, failures_to_blacklist, blacklist_for)
+    }
+
+    /// Index of the provider to call next, counted against its budget. `None` if every one of them is out of
+    /// budget or blacklisted.
+    fn select(&self, now: Instant) -> Option<usize> {
+        let mut states = self.states.lock().unwrap();
+        let eligible: Vec<usize> = self.providers.iter().zip(states.iter_mut()).enumerate().filter_map(|(i, (provider, state))| {
+            if state.blacklisted_until.is_some_and(|until| now < until) {
+                return None;
+            }
+            if state.window_start.is_none_or(|start| now.duration_since(start) >= BUDGET_WINDOW) {
+                state.window_start = Some(now);
+                state.window_calls = 0;
+            }
+            provider.budget.is_none_or(|budget| state.window_calls < budget).then_some(i)
+        }).collect();
+        let mut total_weight = 0;
+        for &i in &eligible {
+            states[i].current_weight += self.providers[i].weight;
+            total_weight += self.providers[i].weight;
+        }
+        // the first listed wins a tie
+        let best = eligible.into_iter().max_by_key(|&i| (states[i].current_weight, Reverse(i)))?;
+        states[best].current_weight -= total_weight;
+        states[best].window_calls += 1;
+        Some(best)
+    }
+
+    /// Blacklists provider `index` once it has failed `failures_to_blacklist` times in a row
+    fn report(&self, index: usize, ok: bool, now: Instant) {
+        let mut states = self.states.lock().unwrap();
+        let state = &mut states[index];
+        if ok {
+            state.consecutive_failures = 0;
+            return;
+        }
+        state.consecutive_failures += 1;
+        if state.consecutive_failures >= self.failures_to_blacklist {
+            println!("blacklisting rpc {} for {}s after {} failures in a row", self.providers[index].url, self.blacklist_for.as_secs(), state.consecutive_failures);
+            metrics::incr("rpc_blacklistings");
+            state.consecutive_failures = 0;
+            state.blacklisted_until = Some(now + self.blacklist_for);
+        }
+    }
+}
+
+/// An [`RpcPool`] behind a semaphore, so a block full of uncached lookup tables queues its fetches instead of
+/// tripping the providers' rate limits. Counts calls in `rpc_calls`, rate limited ones in `rpc_rate_limited`.
+pub struct BoundedRpc {
+    pool: RpcPool,
+    permits: Semaphore,
+    limits: RpcLimits,
+}
+
+impl BoundedRpc {
+    pub fn new(pool: RpcPool, limits: RpcLimits) -> Self {
+        metrics::set("rpc_max_concurrency", limits.max_concurrency as u64);
+        Self {
+            pool,
+            permits: Semaphore::new(limits.max_concurrency),
+            limits,
+        }
+    }
+
+    /// [`RpcPool::from_env`] limited by [`RpcLimits::from_env`]
+    pub fn from_env(fallback_url: &str, commitment: CommitmentConfig) -> Self {
+        Self::new(RpcPool::from_env(fallback_url, commitment), RpcLimits::from_env())
+    }
+
+    pub async fn get_multiple_accounts(&self, keys: &[Pubkey]) -> Result<Vec<Option<Account>>, ClientError> {
+        self.call(|client| client.get_multiple_accounts(keys)).await
+    }
+
+    /// Slots with a block in `[start_slot, end_slot]`, at the pool's commitment
+    pub async fn get_blocks(&self, start_slot: u64, end_slot: u64) -> Result<Vec<u64>, ClientError> {
+        self.call(|client| client.get_blocks(start_slot, Some(end_slot))).await
+    }
+
+    /// Runs `request` on the pool's next provider once a permit is free, retrying it with backoff, on the provider
+    /// after, while it fails in a way worth retrying
+    pub async fn call<'a, T, F, Fut>(&'a self, request: F) -> Result<T, ClientError>
+    where
+        F: Fn(&'a RpcClient) -> Fut,
+        Fut: Future<Output = Result<T, ClientError>>,
+    {
+        let mut retry = 0;
+        loop {
+            let Some(provider) = self.pool.select(Instant::now()) else {
+                metrics::incr("rpc_pool_exhausted");
+                if retry >= self.limits.max_retries {
+                    return Err(ErrorKind::Custom("every rpc provider is blacklisted or out of budget".to_string()).into());
+                }
+                tokio::time::sleep(self.limits.backoff(retry)).await;
+                retry += 1;
+                continue;
+            };
+            let result = {
+                let _permit = self.permits.acquire().await.expect("the semaphore is never closed");
+                metrics::set("rpc_in_flight", (self.limits.max_concurrency - self.permits.available_permits()) as u64);
+                metrics::incr("rpc_calls");
+                request(&self.pool.providers[provider].client).await
+            };
+            let e = match result {
+                Ok(value) => {
+                    self.pool.report(provider, true, Instant::now());
+                    return Ok(value);
+                },
+                Err(e) => e,
+            };
+            // errors from the node itself say nothing about the provider
+            let retryable = is_retryable(e.kind());
+            self.pool.report(provider, !retryable, Instant::now());
+            if is_rate_limited(e.kind()) {
+                metrics::incr("rpc_rate_limited");
+            }
+            if !retryable || retry >= self.limits.max_retries {
+                metrics::incr("rpc_errors");
+                return Err(e);
+            }
+            metrics::incr("rpc_retries");
+            tokio::time::sleep(self.limits.backoff(retry)).await;
+            retry += 1;
+        }
+    }
+}
+
+/// Whether the provider answered 429, after the client's own retries of it
+fn is_rate_limited(kind: &ErrorKind) -> bool {
+    matches!(kind, ErrorKind::Reqwest(e) if e.status().is_some_and(|s| s.as_u16() == 429))
+}
+
+/// Transport errors, rate limits and server errors, which may well go through on another try
+fn is_retryable(kind: &ErrorKind) -> bool {
+    match kind {
+        ErrorKind::Io(_) => true,
+        ErrorKind::Reqwest(e) => e.status().is_none_or(|s| s.as_u16() == 429 || s.is_server_error()),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let limits = RpcLimits { backoff: Duration::from_millis(100), ..RpcLimits::default() };
+        assert_eq!(limits.backoff(0), Duration::from_millis(100));
+        assert_eq!(limits.backoff(3), Duration::from_millis(800));
+        assert!(!is_retryable(&ErrorKind::Custom("bad request".to_string())));
+        assert!(is_retryable(&ErrorKind::Io(std::io::ErrorKind::ConnectionReset.into())));
+    }
+
+    #[test]
+    fn test_pool_rotation() {
+        let providers = [("http://a".to_string(), 2, 0), ("http://b".to_string(), 1, 2)];
+        let pool = RpcPool::new(providers, CommitmentConfig::processed(), 2, Duration::from_secs(10));
+        let now = Instant::now();
+        let picks: Vec<usize> = (0..6).map(|_| pool.select(now).unwrap()).collect();
+        assert_eq!(picks, vec![0, 1, 0, 0, 1, 0]);
+        // b is out of budget for the minute
+        assert_eq!(pool.select(now), Some(0));
+        assert!((0..3).any(|_| pool.select(now + BUDGET_WINDOW) == Some(1)));
+        // blacklisted on the 2nd failure in a row
+        let pool = RpcPool::new([("http://a".to_string(), 1, 0)], CommitmentConfig::processed(), 2, Duration::from_secs(10));
+        pool.report(0, false, now);
+        pool.report(0, true, now);
+        pool.report(0, false, now);
+        assert_eq!(pool.select(now), Some(0));
+        pool.report(0, false, now);
+        assert_eq!(pool.select(now), None);
+        assert_eq!(pool.select(now + Duration::from_secs(10)), Some(0));
+    }
+}
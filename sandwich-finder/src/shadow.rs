@@ -0,0 +1,95 @@
+//! A second detector configuration run over the same groups as the live one, for trying out rule changes.
+//!
+//! Its sandwiches go to `shadow_sandwiches` under its label instead of the primary tables, and each group logs how
+//! they differ from the primary's so a change can be judged on live traffic before it's promoted.
+
+use std::{collections::HashSet, env, fmt};
+
+use uuid::Uuid;
+
+use crate::{detector::DetectorConfig, events::sandwich::SandwichCandidate, metrics};
+
+pub const SHADOW_PREFIX: &str = "SHADOW_";
+const MAX_LABEL_LENGTH: usize = 32;
+
+#[derive(Clone, Debug)]
+pub struct ShadowConfig {
+    /// Tags the shadow's rows, e.g. the rule version under test
+    pub label: String,
+    pub detector: DetectorConfig,
+}
+
+impl ShadowConfig {
+    /// Enabled by setting `SHADOW_LABEL`. The shadow reads the primary's variables with `SHADOW_` prepended,
+    /// e.g. `SHADOW_SANDWICH_SELECTION`, and falls back to the primary's own for those that aren't set.
+    pub fn from_env() -> Option<Self> {
+        let label = env::var("SHADOW_LABEL").ok().filter(|l| !l.is_empty())?;
+        if label.len() > MAX_LABEL_LENGTH {
+            panic!("SHADOW_LABEL must be at most {} characters", MAX_LABEL_LENGTH);
+        }
+        Some(Self {
+            label,
+            detector: DetectorConfig::from_env_with_prefix(SHADOW_PREFIX),
+        })
+    }
+}
+
+/// How the shadow's sandwiches compare to the primary's, matched on their ids
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ShadowDiff {
+    pub matched: usize,
+    /// Found by the shadow only
+    pub added: usize,
+    /// Found by the primary only
+    pub removed: usize,
+}
+
+impl ShadowDiff {
+    pub fn new(primary: &[SandwichCandidate], shadow: &[SandwichCandidate]) -> Self {
+        let primary: HashSet<Uuid> = primary.iter().map(SandwichCandidate::uuid).collect();
+        let shadow: HashSet<Uuid> = shadow.iter().map(SandwichCandidate::uuid).collect();
+        let matched = primary.intersection(&shadow).count();
+        Self {
+            matched,
+            added: shadow.len() - matched,
+            removed: primary.len() - matched,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added == 0 && self.removed == 0
+    }
+
+    pub fn export_metrics(&self) {
+        metrics::add("shadow_sandwiches_matched", self.matched as u64);
+        metrics::add("shadow_sandwiches_added", self.added as u64);
+        metrics::add("shadow_sandwiches_removed", self.removed as u64);
+    }
+}
+
+impl fmt::Display for ShadowDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "matched={} added={} removed={}", self.matched, self.added, self.removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::SwapBuilder;
+
+    fn sandwich(first_id: u64) -> SandwichCandidate {
+        let swap = |id: u64| SwapBuilder::new().at(1, id as u32).id(id).build();
+        SandwichCandidate::from_parts(vec![swap(first_id)], vec![swap(first_id + 1)], vec![swap(first_id + 2)], vec![], vec![])
+    }
+
+    #[test]
+    fn test_diff() {
+        let primary = [sandwich(1), sandwich(10)];
+        let shadow = [sandwich(10), sandwich(20), sandwich(30)];
+        let diff = ShadowDiff::new(&primary, &shadow);
+        assert_eq!(diff, ShadowDiff { matched: 1, added: 2, removed: 1 });
+        assert!(!diff.is_empty());
+        assert!(ShadowDiff::new(&primary, &primary).is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+use std::{collections::BTreeSet, future::Future};
+
+use mysql::{prelude::Queryable as _, Pool};
+use tokio::sync::watch;
+
+/// Set once ctrl-c or SIGTERM is received. Streams stop taking new blocks when it's triggered,
+/// work already taken is drained before the process exits. A second signal exits right away.
+#[derive(Clone)]
+pub struct Shutdown {
+    receiver: watch::Receiver<bool>,
+}
+
+impl Shutdown {
+    /// Must be called from within a tokio runtime
+    pub fn install() -> Self {
+        let (sender, receiver) = watch::channel(false);
+        tokio::spawn(async move {
+            wait_for_signal().await;
+            println!("shutting down, draining in-flight work, signal again to exit right away");
+            let _ = sender.send(true);
+            wait_for_signal().await;
+            std::process::exit(130);
+        });
+        Self { receiver }
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.receiver.borrow()
+    }
+
+    /// Resolves once shutdown is triggered
+    pub async fn triggered(&self) {
+        let _ = self.receiver.clone().wait_for(|&triggered| triggered).await;
+    }
+
+    /// `fut`'s output, or None if shutdown is triggered first
+    pub async fn unless_triggered<F: Future>(&self, fut: F) -> Option<F::Output> {
+        tokio::select! {
+            output = fut => Some(output),
+            _ = self.triggered() => None,
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() {
+    let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()).unwrap();
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {},
+        _ = terminate.recv() => {},
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Slots handed off for processing, which may finish out of order
+#[derive(Debug, Default)]
+pub struct SlotTracker {
+    in_flight: BTreeSet<u64>,
+    last_done: Option<u64>,
+}
+
+impl SlotTracker {
+    pub fn start(&mut self, slot: u64) {
+        self.in_flight.insert(slot);
+    }
+
+    pub fn finish(&mut self, slot: u64) {
+        self.in_flight.remove(&slot);
+        self.last_done = self.last_done.max(Some(slot));
+    }
+
+    /// The slot to resume after, the one before the oldest still in flight
+    pub fn checkpoint(&self) -> Option<u64> {
+        match self.in_flight.first() {
+            Some(&first) => first.checked_sub(1),
+            None => self.last_done,
+        }
+    }
+}
+
+/// The slot `stream` last checkpointed
+pub fn load_checkpoint(pool: &Pool, stream: &str) -> Option<u64> {
+    let mut conn = pool.get_conn().unwrap();
+    match conn.exec_first("select slot from stream_checkpoints where stream=?", (stream,)) {
+        Ok(slot) => slot,
+        Err(e) => {
+            eprintln!("Failed to load the {} checkpoint: {}", stream, e);
+            None
+        }
+    }
+}
+
+pub fn save_checkpoint(pool: &Pool, stream: &str, slot: u64) {
+    let mut conn = pool.get_conn().unwrap();
+    match conn.exec_drop("insert into stream_checkpoints (stream, slot) values (?, ?) on duplicate key update slot=values(slot)", (stream, slot)) {
+        Ok(()) => println!("checkpointed {} at slot {}", stream, slot),
+        Err(e) => eprintln!("Failed to save the {} checkpoint: {}", stream, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint() {
+        let mut tracker = SlotTracker::default();
+        assert_eq!(tracker.checkpoint(), None);
+        (10..=13).for_each(|slot| tracker.start(slot));
+        tracker.finish(12);
+        assert_eq!(tracker.checkpoint(), Some(9));
+        tracker.finish(10);
+        assert_eq!(tracker.checkpoint(), Some(10));
+        tracker.finish(11);
+        assert_eq!(tracker.checkpoint(), Some(12));
+        tracker.finish(13);
+        assert_eq!(tracker.checkpoint(), Some(13));
+    }
+}
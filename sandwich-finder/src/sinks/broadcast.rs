@@ -0,0 +1,24 @@
+use std::sync::Arc;
+
+use tokio::sync::broadcast;
+
+use crate::{api::feed::SlotEvents, events::event::Event, sinks::{Sink, SinkError}};
+
+/// Feeds the channel the websocket feed in [`crate::api::feed`] subscribes to
+pub struct BroadcastSink {
+    sender: broadcast::Sender<SlotEvents>,
+}
+
+impl BroadcastSink {
+    pub fn new(sender: broadcast::Sender<SlotEvents>) -> Self {
+        Self { sender }
+    }
+}
+
+impl Sink for BroadcastSink {
+    async fn handle_events(&mut self, slot: u64, events: Arc<[Event]>) -> Result<(), SinkError> {
+        // no subscribers isn't an error, there's just nobody listening
+        let _ = self.sender.send((slot, events));
+        Ok(())
+    }
+}
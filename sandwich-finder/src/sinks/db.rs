@@ -0,0 +1,59 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{events::{common::Inserter, event::Event, sandwich::SandwichCandidate}, shutdown::SlotTracker, sinks::{Sink, SinkError}, wal::{insert_events_logged, insert_sandwiches_logged, EventBatch, SandwichBatch, Wal}};
+
+/// Events are inserted this many at a time
+pub const EVENT_CHUNK_SIZE: usize = 1000;
+
+/// Writes through the [`Inserter`], logging each batch to the matching write-ahead log first if there's one
+pub struct DbSink {
+    inserter: Inserter,
+    events_wal: Option<Wal<EventBatch>>,
+    sandwiches_wal: Option<Wal<SandwichBatch>>,
+    tracker: Option<Arc<Mutex<SlotTracker>>>,
+}
+
+impl DbSink {
+    pub fn new(inserter: Inserter) -> Self {
+        Self {
+            inserter,
+            events_wal: None,
+            sandwiches_wal: None,
+            tracker: None,
+        }
+    }
+
+    pub fn with_events_wal(mut self, wal: Option<Wal<EventBatch>>) -> Self {
+        self.events_wal = wal;
+        self
+    }
+
+    pub fn with_sandwiches_wal(mut self, wal: Option<Wal<SandwichBatch>>) -> Self {
+        self.sandwiches_wal = wal;
+        self
+    }
+
+    /// Tracks the slots of the events written, for checkpointing
+    pub fn with_tracker(mut self, tracker: Arc<Mutex<SlotTracker>>) -> Self {
+        self.tracker = Some(tracker);
+        self
+    }
+}
+
+impl Sink for DbSink {
+    async fn handle_events(&mut self, slot: u64, events: Arc<[Event]>) -> Result<(), SinkError> {
+        if let Some(tracker) = &self.tracker {
+            tracker.lock().unwrap().start(slot);
+        }
+        let written = insert_events_logged(&mut self.inserter, self.events_wal.as_ref(), &events, EVENT_CHUNK_SIZE).await;
+        if let Some(tracker) = &self.tracker {
+            tracker.lock().unwrap().finish(slot);
+        }
+        written.then_some(()).ok_or(SinkError::Db)
+    }
+
+    async fn handle_sandwiches(&mut self, slot: u64, sandwiches: Arc<[SandwichCandidate]>) -> Result<(), SinkError> {
+        let written = insert_sandwiches_logged(&mut self.inserter, self.sandwiches_wal.as_ref(), slot, sandwiches).await;
+        written.then_some(()).ok_or(SinkError::Db)
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::{BTreeMap, HashSet};
+
+use mysql::{prelude::Queryable as _, PooledConn};
+use uuid::Uuid;
+
+/// Ids of the sandwiches sent to the sinks over the last `slots` slots, so a slot replayed after a restart or
+/// reconnect doesn't announce the same sandwich twice. Ids are deterministic, see [`crate::events::sandwich::SandwichCandidate::uuid`].
+pub struct DedupWindow {
+    slots: u64,
+    seen: HashSet<Uuid>,
+    by_slot: BTreeMap<u64, Vec<Uuid>>,
+    newest: u64,
+}
+
+impl DedupWindow {
+    pub fn new(slots: u64) -> Self {
+        Self {
+            slots,
+            seen: HashSet::new(),
+            by_slot: BTreeMap::new(),
+            newest: 0,
+        }
+    }
+
+    /// Records `id` as sent in `slot`, false if it already was within the window
+    pub fn insert(&mut self, slot: u64, id: Uuid) -> bool {
+        if !self.seen.insert(id) {
+            return false;
+        }
+        self.by_slot.entry(slot).or_default().push(id);
+        self.newest = self.newest.max(slot);
+        self.evict();
+        true
+    }
+
+    fn evict(&mut self) {
+        while let Some(entry) = self.by_slot.first_entry() {
+            if entry.key() + self.slots >= self.newest {
+                break;
+            }
+            for id in entry.remove() {
+                self.seen.remove(&id);
+            }
+        }
+    }
+
+    pub fn slots(&self) -> u64 {
+        self.slots
+    }
+
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+}
+
+/// (slot, id) of the sandwiches written within `slots` of the latest one, to seed the window with on startup
+pub fn load_recent(conn: &mut PooledConn, slots: u64) -> Vec<(u64, Uuid)> {
+    let rows: Vec<(String, u64)> = match conn.exec(
+        "select sandwich_id, slot from sandwich_spans where slot >= (select ifnull(max(slot), 0) from sandwich_spans) - ?",
+        (slots,),
+    ) {
+        Ok(rows) => rows,
+        Err(e) => {
+            eprintln!("Failed to load recent sandwiches: {}", e);
+            return vec![];
+        }
+    };
+    rows.into_iter().filter_map(|(id, slot)| Some((slot, id.parse().ok()?))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dedup_window() {
+        let mut window = DedupWindow::new(10);
+        let (a, b, c) = (Uuid::from_u128(1), Uuid::from_u128(2), Uuid::from_u128(3));
+        assert!(window.insert(100, a));
+        assert!(!window.insert(100, a));
+        // out of order within the window
+        assert!(window.insert(95, b));
+        assert!(!window.insert(105, b));
+        assert!(window.insert(110, c));
+        // 95 fell out of the window at 110
+        assert_eq!(window.len(), 2);
+        assert!(!window.insert(110, a));
+        assert!(window.insert(111, b));
+        assert_eq!(window.len(), 2);
+        assert!(window.insert(125, Uuid::from_u128(4)));
+        assert_eq!(window.len(), 1);
+    }
+}
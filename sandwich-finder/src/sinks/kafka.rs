@@ -0,0 +1,60 @@
+use std::{env, sync::Arc, time::Duration};
+
+use serde::Serialize;
+
+use crate::{events::{event::Event, sandwich::SandwichCandidate}, sinks::{Sink, SinkError}};
+
+const CONTENT_TYPE: &str = "application/vnd.kafka.json.v2+json";
+
+#[derive(Serialize)]
+struct Record<'a, T: Serialize> {
+    key: String,
+    value: &'a T,
+}
+
+#[derive(Serialize)]
+struct Records<'a, T: Serialize> {
+    records: Vec<Record<'a, T>>,
+}
+
+/// Produces to Kafka through a REST proxy at `SINK_KAFKA_REST_URL`, one record per event keyed by its slot to
+/// `SINK_KAFKA_EVENTS_TOPIC` and one per sandwich keyed by its id to `SINK_KAFKA_SANDWICHES_TOPIC`
+pub struct KafkaSink {
+    client: reqwest::Client,
+    events_url: String,
+    sandwiches_url: String,
+}
+
+impl KafkaSink {
+    pub fn from_env() -> Option<Self> {
+        let url = env::var("SINK_KAFKA_REST_URL").ok().filter(|url| !url.is_empty())?;
+        let url = url.trim_end_matches('/');
+        let topic = |name: &str, default: &str| env::var(name).ok().filter(|t| !t.is_empty()).unwrap_or_else(|| default.to_string());
+        Some(Self {
+            client: reqwest::Client::new(),
+            events_url: format!("{}/topics/{}", url, topic("SINK_KAFKA_EVENTS_TOPIC", "events")),
+            sandwiches_url: format!("{}/topics/{}", url, topic("SINK_KAFKA_SANDWICHES_TOPIC", "sandwiches")),
+        })
+    }
+
+    async fn produce<T: Serialize>(&self, url: &str, records: Vec<Record<'_, T>>) -> Result<(), SinkError> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let body = serde_json::to_vec(&Records { records })?;
+        self.client.post(url).header(reqwest::header::CONTENT_TYPE, CONTENT_TYPE).body(body).timeout(Duration::from_secs(10)).send().await?.error_for_status()?;
+        Ok(())
+    }
+}
+
+impl Sink for KafkaSink {
+    async fn handle_events(&mut self, slot: u64, events: Arc<[Event]>) -> Result<(), SinkError> {
+        let records = events.iter().map(|event| Record { key: slot.to_string(), value: event }).collect();
+        self.produce(&self.events_url, records).await
+    }
+
+    async fn handle_sandwiches(&mut self, _slot: u64, sandwiches: Arc<[SandwichCandidate]>) -> Result<(), SinkError> {
+        let records = sandwiches.iter().map(|sandwich| Record { key: sandwich.uuid().to_string(), value: sandwich }).collect();
+        self.produce(&self.sandwiches_url, records).await
+    }
+}
@@ -0,0 +1,242 @@
+//! Destinations for indexed events and detected sandwiches.
+//!
+//! Every sink registered with [`Sinks`] gets its own bounded queue and a task draining it, so a slow or failing
+//! sink only ever loses its own messages: when its queue is full new messages are dropped for it alone.
+
+use std::{collections::HashSet, env, future::Future, sync::{Arc, Mutex}};
+
+use serde::Serialize;
+use thiserror::Error;
+use tokio::{sync::mpsc::{self, error::TrySendError}, task::JoinHandle};
+use uuid::Uuid;
+
+use crate::{events::{event::Event, sandwich::SandwichCandidate}, metrics, sinks::dedup::DedupWindow};
+
+pub mod broadcast;
+pub mod db;
+pub mod dedup;
+pub mod kafka;
+pub mod stdout;
+pub mod webhook;
+
+#[derive(Debug, Error)]
+pub enum SinkError {
+    #[error("the db write failed")]
+    Db,
+    #[error("request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("serialization failed: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("write failed: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Both handlers default to ignoring the batch, implement the ones the sink cares about
+pub trait Sink: Send + 'static {
+    fn handle_events(&mut self, _slot: u64, _events: Arc<[Event]>) -> impl Future<Output = Result<(), SinkError>> + Send {
+        async { Ok(()) }
+    }
+
+    /// `slot` is the first slot of the group the sandwiches were found in
+    fn handle_sandwiches(&mut self, _slot: u64, _sandwiches: Arc<[SandwichCandidate]>) -> impl Future<Output = Result<(), SinkError>> + Send {
+        async { Ok(()) }
+    }
+}
+
+/// How the sinks that serialize lay out a batch
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub(crate) enum Batch<'a> {
+    Events { slot: u64, events: &'a [Event] },
+    Sandwiches { slot: u64, sandwiches: &'a [SandwichCandidate] },
+}
+
+enum SinkMessage {
+    Events(u64, Arc<[Event]>),
+    Sandwiches(u64, Arc<[SandwichCandidate]>),
+}
+
+struct SinkHandle {
+    name: &'static str,
+    sender: mpsc::Sender<SinkMessage>,
+    dropped_metric: &'static str,
+}
+
+/// The sinks named in `SINKS` (comma separated, `db,broadcast` by default), each with a queue of `SINK_QUEUE_SIZE`
+/// batches, 1024 by default. `webhook`, `kafka` and `stdout` are configured from the env by [`Sinks::from_env`],
+/// the rest need something only the binary has and are added by it when [`Sinks::enabled`].
+/// Sandwiches already sent within `SINK_DEDUP_SLOTS` slots, 1500 by default and 0 to disable, are dropped for every sink.
+pub struct Sinks {
+    names: HashSet<String>,
+    queue_size: usize,
+    dedup: Option<Mutex<DedupWindow>>,
+    handles: Vec<SinkHandle>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl Sinks {
+    /// Must be called from within a tokio runtime
+    pub fn from_env() -> Self {
+        let names = env::var("SINKS").unwrap_or_else(|_| "db,broadcast".to_string())
+            .split(',')
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect();
+        let mut sinks = Self::new(names, env::var("SINK_QUEUE_SIZE").ok().and_then(|v| v.parse().ok()).unwrap_or(1024));
+        let dedup_slots = env::var("SINK_DEDUP_SLOTS").ok().and_then(|v| v.parse().ok()).unwrap_or(1500);
+        if dedup_slots > 0 {
+            sinks.dedup = Some(Mutex::new(DedupWindow::new(dedup_slots)));
+        }
+        if sinks.enabled("webhook") {
+            match webhook::WebhookSink::from_env() {
+                Some(sink) => sinks.add("webhook", sink),
+                None => println!("webhook sink needs SINK_WEBHOOK_URL, skipping it"),
+            }
+        }
+        if sinks.enabled("kafka") {
+            match kafka::KafkaSink::from_env() {
+                Some(sink) => sinks.add("kafka", sink),
+                None => println!("kafka sink needs SINK_KAFKA_REST_URL, skipping it"),
+            }
+        }
+        if sinks.enabled("stdout") {
+            sinks.add("stdout", stdout::StdoutSink);
+        }
+        sinks
+    }
+
+    fn new(names: HashSet<String>, queue_size: usize) -> Self {
+        Self {
+            names,
+            queue_size: queue_size.max(1),
+            dedup: None,
+            handles: vec![],
+            workers: vec![],
+        }
+    }
+
+    pub fn enabled(&self, name: &str) -> bool {
+        self.names.contains(name)
+    }
+
+    /// Starts the task feeding `sink` from its own queue
+    pub fn add<S: Sink>(&mut self, name: &'static str, mut sink: S) {
+        let (sender, mut receiver) = mpsc::channel(self.queue_size);
+        // one of each per sink, leaked once at startup
+        let failed_metric: &'static str = format!("sink_{}_failed", name).leak();
+        self.workers.push(tokio::spawn(async move {
+            while let Some(msg) = receiver.recv().await {
+                let (kind, slot, res) = match msg {
+                    SinkMessage::Events(slot, events) => ("events", slot, sink.handle_events(slot, events).await),
+                    SinkMessage::Sandwiches(slot, sandwiches) => ("sandwiches", slot, sink.handle_sandwiches(slot, sandwiches).await),
+                };
+                if let Err(e) = res {
+                    eprintln!("Sink {} failed on the {} of slot {}: {}", name, kind, slot, e);
+                    metrics::incr(failed_metric);
+                }
+            }
+        }));
+        self.handles.push(SinkHandle { name, sender, dropped_metric: format!("sink_{}_dropped", name).leak() });
+        println!("added sink {}", name);
+    }
+
+    fn send(&self, msg: impl Fn() -> SinkMessage) {
+        for handle in &self.handles {
+            match handle.sender.try_send(msg()) {
+                Ok(()) => {},
+                Err(TrySendError::Full(_)) => {
+                    eprintln!("Sink {} is falling behind, dropping a batch", handle.name);
+                    metrics::incr(handle.dropped_metric);
+                },
+                Err(TrySendError::Closed(_)) => metrics::incr(handle.dropped_metric),
+            }
+        }
+    }
+
+    pub fn send_events(&self, slot: u64, events: Arc<[Event]>) {
+        self.send(|| SinkMessage::Events(slot, events.clone()));
+    }
+
+    pub fn send_sandwiches(&self, slot: u64, sandwiches: Arc<[SandwichCandidate]>) {
+        let sandwiches = match &self.dedup {
+            Some(dedup) => {
+                let mut dedup = dedup.lock().unwrap();
+                let fresh: Arc<[SandwichCandidate]> = sandwiches.iter().filter(|s| dedup.insert(slot, s.uuid())).cloned().collect();
+                metrics::add("sink_duplicate_sandwiches", (sandwiches.len() - fresh.len()) as u64);
+                metrics::set("sink_dedup_window", dedup.len() as u64);
+                if fresh.is_empty() && !sandwiches.is_empty() {
+                    return;
+                }
+                fresh
+            },
+            None => sandwiches,
+        };
+        self.send(|| SinkMessage::Sandwiches(slot, sandwiches.clone()));
+    }
+
+    /// Marks sandwiches sent before a restart as already sent, see [`dedup::load_recent`]
+    pub fn seed_dedup(&self, sent: impl IntoIterator<Item = (u64, Uuid)>) {
+        if let Some(dedup) = &self.dedup {
+            let mut dedup = dedup.lock().unwrap();
+            for (slot, id) in sent {
+                dedup.insert(slot, id);
+            }
+            println!("seeded the sink dedup window with {} sandwiches", dedup.len());
+        }
+    }
+
+    pub fn dedup_slots(&self) -> Option<u64> {
+        self.dedup.as_ref().map(|dedup| dedup.lock().unwrap().slots())
+    }
+
+    /// Returns once every sink has handled what's already queued for it
+    pub async fn close(self) {
+        drop(self.handles);
+        for worker in self.workers {
+            if let Err(e) = worker.await {
+                eprintln!("Sink worker failed: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Mutex, time::Duration};
+
+    use super::*;
+
+    struct Recorder(Arc<Mutex<Vec<u64>>>);
+
+    impl Sink for Recorder {
+        async fn handle_events(&mut self, slot: u64, _events: Arc<[Event]>) -> Result<(), SinkError> {
+            self.0.lock().unwrap().push(slot);
+            Ok(())
+        }
+    }
+
+    struct Stuck;
+
+    impl Sink for Stuck {
+        async fn handle_events(&mut self, _slot: u64, _events: Arc<[Event]>) -> Result<(), SinkError> {
+            tokio::time::sleep(Duration::from_secs(3600)).await;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stuck_sink_is_isolated() {
+        let mut sinks = Sinks::new(HashSet::new(), 2);
+        let seen = Arc::new(Mutex::new(vec![]));
+        sinks.add("stuck", Stuck);
+        sinks.add("recorder", Recorder(seen.clone()));
+        for slot in 0..10 {
+            sinks.send_events(slot, Arc::from([]));
+            // the recorder keeps up, the stuck sink's queue fills up and starts dropping
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        assert_eq!(*seen.lock().unwrap(), (0..10).collect::<Vec<_>>());
+        assert!(metrics::get("sink_stuck_dropped") > 0);
+        assert_eq!(metrics::get("sink_recorder_dropped"), 0);
+    }
+}
@@ -0,0 +1,25 @@
+use std::{io::Write as _, sync::Arc};
+
+use crate::{events::{event::Event, sandwich::SandwichCandidate}, sinks::{Batch, Sink, SinkError}};
+
+/// Prints each batch as a line of json
+pub struct StdoutSink;
+
+impl StdoutSink {
+    fn print(batch: &Batch) -> Result<(), SinkError> {
+        let mut line = serde_json::to_vec(batch)?;
+        line.push(b'\n');
+        std::io::stdout().lock().write_all(&line)?;
+        Ok(())
+    }
+}
+
+impl Sink for StdoutSink {
+    async fn handle_events(&mut self, slot: u64, events: Arc<[Event]>) -> Result<(), SinkError> {
+        Self::print(&Batch::Events { slot, events: &events })
+    }
+
+    async fn handle_sandwiches(&mut self, slot: u64, sandwiches: Arc<[SandwichCandidate]>) -> Result<(), SinkError> {
+        Self::print(&Batch::Sandwiches { slot, sandwiches: &sandwiches })
+    }
+}
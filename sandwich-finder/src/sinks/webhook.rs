@@ -0,0 +1,31 @@
+use std::{env, sync::Arc, time::Duration};
+
+use crate::{events::{event::Event, sandwich::SandwichCandidate}, sinks::{Batch, Sink, SinkError}};
+
+/// Posts each batch as json to `SINK_WEBHOOK_URL`, a batch the endpoint doesn't accept is dropped
+pub struct WebhookSink {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl WebhookSink {
+    pub fn from_env() -> Option<Self> {
+        let url = env::var("SINK_WEBHOOK_URL").ok().filter(|url| !url.is_empty())?;
+        Some(Self { client: reqwest::Client::new(), url })
+    }
+
+    async fn post(&self, batch: &Batch<'_>) -> Result<(), SinkError> {
+        self.client.post(&self.url).json(batch).timeout(Duration::from_secs(10)).send().await?.error_for_status()?;
+        Ok(())
+    }
+}
+
+impl Sink for WebhookSink {
+    async fn handle_events(&mut self, slot: u64, events: Arc<[Event]>) -> Result<(), SinkError> {
+        self.post(&Batch::Events { slot, events: &events }).await
+    }
+
+    async fn handle_sandwiches(&mut self, slot: u64, sandwiches: Arc<[SandwichCandidate]>) -> Result<(), SinkError> {
+        self.post(&Batch::Sandwiches { slot, sandwiches: &sandwiches }).await
+    }
+}
@@ -0,0 +1,50 @@
+use std::{fs::File, io::BufReader};
+
+use tokio::sync::mpsc;
+use yellowstone_grpc_proto::geyser::SubscribeUpdate;
+
+use crate::{events::replay::{DumpReader, ReplayError}, source::{BlockSource, BlockUpdate}};
+
+/// Dumps are read ahead of what's been taken by up to this many updates
+const READ_AHEAD: usize = 16;
+
+/// The updates in a block dump, in the order they were recorded. Ends at the end of the file or the first
+/// message that can't be decoded.
+pub struct FileSource {
+    receiver: mpsc::Receiver<SubscribeUpdate>,
+}
+
+impl FileSource {
+    /// Must be called from within a tokio runtime
+    pub fn open(path: &str) -> Result<Self, ReplayError> {
+        let reader = DumpReader::new(BufReader::new(File::open(path)?))?;
+        println!("replaying {} ({:?} format)", path, reader.version());
+        let (sender, receiver) = mpsc::channel(READ_AHEAD);
+        tokio::task::spawn_blocking(move || {
+            for update in reader {
+                match update {
+                    Ok(update) => {
+                        if sender.blocking_send(update).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        println!("dump decode error: {:?}", e);
+                        break;
+                    }
+                }
+            }
+        });
+        Ok(Self { receiver })
+    }
+}
+
+impl BlockSource for FileSource {
+    async fn next_update(&mut self) -> Option<BlockUpdate> {
+        loop {
+            if let Some(update) = BlockUpdate::from_update(self.receiver.recv().await?) {
+                return Some(update);
+            }
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use std::{collections::HashMap, env, time::Duration};
+
+use futures::{channel::mpsc, Stream, StreamExt as _};
+use solana_sdk::pubkey::Pubkey;
+use thiserror::Error;
+use yellowstone_grpc_client::{GeyserGrpcBuilder, GeyserGrpcBuilderError};
+use yellowstone_grpc_proto::{geyser::{subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest, SubscribeRequestFilterAccounts, SubscribeRequestFilterBlocks, SubscribeRequestFilterBlocksMeta, SubscribeRequestFilterSlots, SubscribeRequestPing, SubscribeUpdate}, tonic::{transport::Endpoint, Status, Streaming}};
+
+use crate::{events::addresses::LUT_PROGRAM_ID, metrics, source::{BlockSource, BlockUpdate}};
+
+#[derive(Debug, Error)]
+pub enum GrpcSourceError {
+    #[error("invalid grpc url: {0}")]
+    Url(#[from] yellowstone_grpc_proto::tonic::transport::Error),
+    #[error("unable to connect: {0}")]
+    Connect(#[from] GeyserGrpcBuilderError),
+    #[error("unable to subscribe: {0}")]
+    Subscribe(#[from] Status),
+}
+
+/// Time without any message (blocks, pings, ...) after which a stream is considered stalled.
+/// Read from `GRPC_STALL_TIMEOUT_SECS`, 30s by default.
+pub fn stall_timeout() -> Duration {
+    let secs = env::var("GRPC_STALL_TIMEOUT_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(30);
+    Duration::from_secs(secs)
+}
+
+/// Like `stream.next()`, but also returns `None` once the stream has been silent for `timeout`
+/// so callers drop the subscription and reconnect instead of waiting forever.
+pub async fn next_or_stall<S: Stream + Unpin>(stream: &mut S, timeout: Duration) -> Option<S::Item> {
+    match tokio::time::timeout(timeout, stream.next()).await {
+        Ok(msg) => msg,
+        Err(_) => {
+            println!("grpc stream stalled for {:?}, resubscribing", timeout);
+            metrics::incr("grpc_stalls");
+            None
+        }
+    }
+}
+
+/// What to subscribe to, at confirmed commitment
+#[derive(Clone, Debug, Default)]
+pub struct Subscription {
+    /// `Some(true)` for blocks with their transactions and accounts, `Some(false)` for bare blocks
+    blocks: Option<bool>,
+    /// Base58, blocks carry every transaction when empty
+    account_include: Vec<String>,
+    lookup_tables: bool,
+    blocks_meta: bool,
+    slot_status: bool,
+    from_slot: Option<u64>,
+}
+
+impl Subscription {
+    pub fn blocks(mut self) -> Self {
+        self.blocks = Some(true);
+        self
+    }
+
+    /// Blocks without their transactions and accounts, e.g. for the rewards
+    pub fn bare_blocks(mut self) -> Self {
+        self.blocks = Some(false);
+        self
+    }
+
+    /// Only the transactions of a block touching one of `accounts`, e.g. the programs there are finders for.
+    /// Not every provider supports filtering blocks, leave it out to get full blocks from those.
+    pub fn account_include(mut self, accounts: &[Pubkey]) -> Self {
+        self.account_include = accounts.iter().map(|account| account.to_string()).collect();
+        self
+    }
+
+    /// Updates to lookup tables, so transactions using them can be resolved without an rpc roundtrip
+    pub fn lookup_tables(mut self) -> Self {
+        self.lookup_tables = true;
+        self
+    }
+
+    pub fn blocks_meta(mut self) -> Self {
+        self.blocks_meta = true;
+        self
+    }
+
+    /// Every commitment level each slot reaches
+    pub fn slot_status(mut self) -> Self {
+        self.slot_status = true;
+        self
+    }
+
+    /// Replays from `slot` if the provider still has it, the tip if `None`
+    pub fn from_slot(mut self, slot: Option<u64>) -> Self {
+        self.from_slot = slot;
+        self
+    }
+
+    fn request(&self) -> SubscribeRequest {
+        let mut blocks = HashMap::new();
+        if let Some(full) = self.blocks {
+            blocks.insert("client".to_string(), SubscribeRequestFilterBlocks {
+                account_include: self.account_include.clone(),
+                include_transactions: Some(full),
+                include_accounts: Some(full),
+                include_entries: Some(false),
+            });
+        }
+        let mut accounts = HashMap::new();
+        if self.lookup_tables {
+            accounts.insert("client".to_string(), SubscribeRequestFilterAccounts {
+                account: vec![],
+                owner: vec![LUT_PROGRAM_ID.to_string()],
+                filters: vec![],
+                nonempty_txn_signature: Some(true),
+            });
+        }
+        let mut blocks_meta = HashMap::new();
+        if self.blocks_meta {
+            blocks_meta.insert("client".to_string(), SubscribeRequestFilterBlocksMeta {});
+        }
+        let mut slots = HashMap::new();
+        if self.slot_status {
+            slots.insert("client".to_string(), SubscribeRequestFilterSlots {
+                filter_by_commitment: Some(false),
+                interslot_updates: Some(false),
+            });
+        }
+        SubscribeRequest {
+            accounts,
+            blocks,
+            blocks_meta,
+            slots,
+            commitment: Some(CommitmentLevel::Confirmed as i32),
+            from_slot: self.from_slot,
+            ..Default::default()
+        }
+    }
+}
+
+/// One subscription to a Yellowstone grpc server, answering its pings. Ends on the first error or stall,
+/// reconnecting is up to the caller.
+pub struct GrpcSource {
+    requests: mpsc::UnboundedSender<SubscribeRequest>,
+    stream: Streaming<SubscribeUpdate>,
+    stall_timeout: Duration,
+    /// Held for as long as the subscription
+    _client: Box<dyn Send>,
+}
+
+impl GrpcSource {
+    pub async fn connect(grpc_url: &str, subscription: &Subscription) -> Result<Self, GrpcSourceError> {
+        println!("connecting to grpc server: {}", grpc_url);
+        let mut client = GeyserGrpcBuilder{
+            endpoint: Endpoint::from_shared(grpc_url.to_string())?,
+            x_token: None,
+            x_request_snapshot: false,
+            send_compressed: None,
+            accept_compressed: None,
+            max_decoding_message_size: Some(128 * 1024 * 1024),
+            max_encoding_message_size: None,
+        }.connect().await?;
+        println!("connected to grpc server!");
+        if let Some(from_slot) = subscription.from_slot {
+            println!("resuming from slot {}", from_slot);
+        }
+        let (requests, receiver) = mpsc::unbounded();
+        // can't fail, the receiver is right here
+        let _ = requests.unbounded_send(subscription.request());
+        let stream = client.geyser.subscribe(receiver).await?.into_inner();
+        Ok(Self {
+            requests,
+            stream,
+            stall_timeout: stall_timeout(),
+            _client: Box::new(client),
+        })
+    }
+}
+
+impl BlockSource for GrpcSource {
+    async fn next_update(&mut self) -> Option<BlockUpdate> {
+        loop {
+            let msg = match next_or_stall(&mut self.stream, self.stall_timeout).await? {
+                Ok(msg) => msg,
+                Err(e) => {
+                    println!("grpc error: {:?}", e);
+                    return None;
+                }
+            };
+            if let Some(UpdateOneof::Ping(_)) = msg.update_oneof {
+                let _ = self.requests.unbounded_send(SubscribeRequest {
+                    ping: Some(SubscribeRequestPing {id: 1}),
+                    ..Default::default()
+                });
+                continue;
+            }
+            if let Some(update) = BlockUpdate::from_update(msg) {
+                return Some(update);
+            }
+        }
+    }
+}
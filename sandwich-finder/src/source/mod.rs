@@ -0,0 +1,50 @@
+//! Where blocks come from: a live Yellowstone grpc subscription, a block dump recorded off one, or rpc polling.
+//!
+//! Every source yields the same [`BlockUpdate`]s, blocks are shaped like the grpc stream's whatever produced them,
+//! so the code consuming them doesn't care which one it's reading.
+
+use std::future::Future;
+
+use yellowstone_grpc_proto::geyser::{subscribe_update::UpdateOneof, SubscribeUpdate, SubscribeUpdateAccount, SubscribeUpdateBlock, SubscribeUpdateBlockMeta, SubscribeUpdateSlot};
+
+pub mod file;
+pub mod grpc;
+pub mod rpc;
+
+#[derive(Clone, Debug)]
+pub enum BlockUpdate {
+    /// With its transactions and accounts unless the source was asked for bare blocks
+    Block(Box<SubscribeUpdateBlock>),
+    BlockMeta(SubscribeUpdateBlockMeta),
+    /// A lookup table changed
+    LookupTable(SubscribeUpdateAccount),
+    /// A slot reached a new commitment level
+    SlotStatus(SubscribeUpdateSlot),
+}
+
+impl BlockUpdate {
+    /// `None` for the updates no source hands out, e.g. pings
+    pub fn from_update(update: SubscribeUpdate) -> Option<Self> {
+        match update.update_oneof? {
+            UpdateOneof::Block(block) => Some(Self::Block(Box::new(block))),
+            UpdateOneof::BlockMeta(meta) => Some(Self::BlockMeta(meta)),
+            UpdateOneof::Account(account) => Some(Self::LookupTable(account)),
+            UpdateOneof::Slot(slot) => Some(Self::SlotStatus(slot)),
+            _ => None,
+        }
+    }
+
+    pub fn slot(&self) -> u64 {
+        match self {
+            Self::Block(block) => block.slot,
+            Self::BlockMeta(meta) => meta.slot,
+            Self::LookupTable(account) => account.slot,
+            Self::SlotStatus(slot) => slot.slot,
+        }
+    }
+}
+
+pub trait BlockSource: Send + 'static {
+    /// The next update, `None` once the source is exhausted or its connection is lost
+    fn next_update(&mut self) -> impl Future<Output = Option<BlockUpdate>> + Send;
+}
@@ -0,0 +1,355 @@
+use std::{str::FromStr as _, time::Duration};
+
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_rpc_client_api::{client_error::{Error as ClientError, ErrorKind}, config::RpcBlockConfig, custom_error::{JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE, JSON_RPC_SERVER_ERROR_LONG_TERM_STORAGE_SLOT_SKIPPED, JSON_RPC_SERVER_ERROR_SLOT_SKIPPED}, request::RpcError};
+use solana_sdk::{bs58, commitment_config::CommitmentConfig, message::VersionedMessage, pubkey::Pubkey, reward_type::RewardType, transaction::VersionedTransaction};
+use solana_transaction_status_client_types::{EncodedTransactionWithStatusMeta, Reward as UiReward, TransactionDetails, UiConfirmedBlock, UiInnerInstructions, UiInstruction, UiLoadedAddresses, UiTransactionEncoding, UiTransactionStatusMeta, UiTransactionTokenBalance};
+use yellowstone_grpc_proto::{geyser::{SubscribeUpdateBlock, SubscribeUpdateTransactionInfo}, solana::storage::confirmed_block::{self as proto, BlockHeight, NumPartitions, Rewards, UnixTimestamp}};
+
+use crate::{metrics, source::{BlockSource, BlockUpdate}};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(400);
+/// Polls of a slot at or below the tip that isn't available yet before it's taken as skipped
+const MAX_UNAVAILABLE_POLLS: u32 = 25;
+
+/// Confirmed blocks fetched one at a time with `getBlock`, from `from_slot` up to `to_slot` inclusive. Without a
+/// `to_slot` it follows the tip and never ends unless the rpc fails.
+pub struct RpcSource {
+    rpc_client: RpcClient,
+    /// `None` until the tip's been looked up when no start was given
+    next_slot: Option<u64>,
+    to_slot: Option<u64>,
+    tip: u64,
+    unavailable_polls: u32,
+}
+
+impl RpcSource {
+    pub fn new(rpc_url: String, from_slot: Option<u64>, to_slot: Option<u64>) -> Self {
+        Self {
+            rpc_client: RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed()),
+            next_slot: from_slot,
+            to_slot,
+            tip: 0,
+            unavailable_polls: 0,
+        }
+    }
+
+    async fn get_block(&self, slot: u64) -> Result<UiConfirmedBlock, ClientError> {
+        self.rpc_client.get_block_with_config(slot, RpcBlockConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            transaction_details: Some(TransactionDetails::Full),
+            rewards: Some(true),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        }).await
+    }
+
+    /// Waits for the tip to reach `slot`, false if the rpc failed
+    async fn wait_for_tip(&mut self, slot: u64) -> bool {
+        while self.tip < slot {
+            match self.rpc_client.get_slot().await {
+                Ok(tip) if tip >= slot => self.tip = tip,
+                Ok(_) => tokio::time::sleep(POLL_INTERVAL).await,
+                Err(e) => {
+                    eprintln!("Failed to get the tip: {}", e);
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+fn rpc_error_code(e: &ClientError) -> Option<i64> {
+    match e.kind() {
+        ErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => Some(*code),
+        _ => None,
+    }
+}
+
+impl BlockSource for RpcSource {
+    async fn next_update(&mut self) -> Option<BlockUpdate> {
+        loop {
+            let slot = match self.next_slot {
+                Some(slot) => slot,
+                None => match self.rpc_client.get_slot().await {
+                    Ok(tip) => tip,
+                    Err(e) => {
+                        eprintln!("Failed to get the tip: {}", e);
+                        return None;
+                    }
+                },
+            };
+            self.next_slot = Some(slot);
+            if self.to_slot.is_some_and(|to_slot| slot > to_slot) {
+                return None;
+            }
+            if !self.wait_for_tip(slot).await {
+                return None;
+            }
+            match self.get_block(slot).await {
+                Ok(block) => {
+                    self.next_slot = Some(slot + 1);
+                    self.unavailable_polls = 0;
+                    metrics::incr("rpc_source_blocks");
+                    return Some(BlockUpdate::Block(Box::new(block_from_rpc(slot, block))));
+                }
+                Err(e) => match rpc_error_code(&e) {
+                    Some(JSON_RPC_SERVER_ERROR_SLOT_SKIPPED | JSON_RPC_SERVER_ERROR_LONG_TERM_STORAGE_SLOT_SKIPPED) => {
+                        self.next_slot = Some(slot + 1);
+                    }
+                    // not confirmed yet, or never will be if the fork it was on was abandoned
+                    Some(JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE) if self.unavailable_polls < MAX_UNAVAILABLE_POLLS => {
+                        self.unavailable_polls += 1;
+                        tokio::time::sleep(POLL_INTERVAL).await;
+                    }
+                    Some(JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE) => {
+                        println!("slot {} never became available, skipping it", slot);
+                        self.next_slot = Some(slot + 1);
+                        self.unavailable_polls = 0;
+                    }
+                    _ => {
+                        eprintln!("Failed to get block {}: {}", slot, e);
+                        return None;
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// Reshapes a `getBlock` response fetched with base64 encoding into what the grpc stream sends for the same block.
+/// Transactions that can't be decoded are left out, the rest keep their position in the block as their index.
+/// Return data isn't carried over.
+pub fn block_from_rpc(slot: u64, block: UiConfirmedBlock) -> SubscribeUpdateBlock {
+    let transactions: Vec<_> = block.transactions.unwrap_or_default().into_iter().enumerate().filter_map(|(index, tx)| {
+        transaction_from_rpc(index as u64, tx)
+    }).collect();
+    SubscribeUpdateBlock {
+        slot,
+        blockhash: block.blockhash,
+        rewards: block.rewards.map(|rewards| Rewards {
+            rewards: rewards.into_iter().map(reward_from_rpc).collect(),
+            num_partitions: block.num_reward_partitions.map(|num_partitions| NumPartitions { num_partitions }),
+        }),
+        block_time: block.block_time.map(|timestamp| UnixTimestamp { timestamp }),
+        block_height: block.block_height.map(|block_height| BlockHeight { block_height }),
+        parent_slot: block.parent_slot,
+        parent_blockhash: block.previous_blockhash,
+        executed_transaction_count: transactions.len() as u64,
+        transactions,
+        ..Default::default()
+    }
+}
+
+fn transaction_from_rpc(index: u64, tx: EncodedTransactionWithStatusMeta) -> Option<SubscribeUpdateTransactionInfo> {
+    let decoded = tx.transaction.decode()?;
+    Some(SubscribeUpdateTransactionInfo {
+        signature: decoded.signatures.first()?.as_ref().to_vec(),
+        is_vote: is_simple_vote(&decoded),
+        transaction: Some(proto::Transaction {
+            signatures: decoded.signatures.iter().map(|sig| sig.as_ref().to_vec()).collect(),
+            message: Some(message_from_rpc(&decoded.message)),
+        }),
+        meta: tx.meta.map(meta_from_rpc),
+        index,
+    })
+}
+
+/// Same rule as the validator's: a legacy tx with at most 2 signatures and a single vote instruction
+fn is_simple_vote(tx: &VersionedTransaction) -> bool {
+    let keys = tx.message.static_account_keys();
+    matches!(tx.message, VersionedMessage::Legacy(_))
+        && tx.signatures.len() < 3
+        && tx.message.instructions().len() == 1
+        && keys.get(tx.message.instructions()[0].program_id_index as usize) == Some(&solana_sdk::vote::program::id())
+}
+
+fn message_from_rpc(message: &VersionedMessage) -> proto::Message {
+    let header = message.header();
+    proto::Message {
+        header: Some(proto::MessageHeader {
+            num_required_signatures: header.num_required_signatures as u32,
+            num_readonly_signed_accounts: header.num_readonly_signed_accounts as u32,
+            num_readonly_unsigned_accounts: header.num_readonly_unsigned_accounts as u32,
+        }),
+        account_keys: message.static_account_keys().iter().map(|key| key.to_bytes().to_vec()).collect(),
+        recent_blockhash: message.recent_blockhash().to_bytes().to_vec(),
+        instructions: message.instructions().iter().map(|ix| proto::CompiledInstruction {
+            program_id_index: ix.program_id_index as u32,
+            accounts: ix.accounts.clone(),
+            data: ix.data.clone(),
+        }).collect(),
+        versioned: !matches!(message, VersionedMessage::Legacy(_)),
+        address_table_lookups: message.address_table_lookups().unwrap_or_default().iter().map(|lookup| proto::MessageAddressTableLookup {
+            account_key: lookup.account_key.to_bytes().to_vec(),
+            writable_indexes: lookup.writable_indexes.clone(),
+            readonly_indexes: lookup.readonly_indexes.clone(),
+        }).collect(),
+    }
+}
+
+fn meta_from_rpc(meta: UiTransactionStatusMeta) -> proto::TransactionStatusMeta {
+    let inner_instructions: Option<Vec<UiInnerInstructions>> = meta.inner_instructions.into();
+    let log_messages: Option<Vec<String>> = meta.log_messages.into();
+    let pre_token_balances: Option<Vec<UiTransactionTokenBalance>> = meta.pre_token_balances.into();
+    let post_token_balances: Option<Vec<UiTransactionTokenBalance>> = meta.post_token_balances.into();
+    let rewards: Option<Vec<UiReward>> = meta.rewards.into();
+    let loaded_addresses: Option<UiLoadedAddresses> = meta.loaded_addresses.into();
+    let loaded_addresses = loaded_addresses.unwrap_or_default();
+    proto::TransactionStatusMeta {
+        err: meta.err.map(|err| proto::TransactionError { err: bincode::serialize(&err).unwrap() }),
+        fee: meta.fee,
+        pre_balances: meta.pre_balances,
+        post_balances: meta.post_balances,
+        inner_instructions_none: inner_instructions.is_none(),
+        inner_instructions: inner_instructions.unwrap_or_default().into_iter().map(inner_instructions_from_rpc).collect(),
+        log_messages_none: log_messages.is_none(),
+        log_messages: log_messages.unwrap_or_default(),
+        pre_token_balances: pre_token_balances.unwrap_or_default().into_iter().map(token_balance_from_rpc).collect(),
+        post_token_balances: post_token_balances.unwrap_or_default().into_iter().map(token_balance_from_rpc).collect(),
+        rewards: rewards.unwrap_or_default().into_iter().map(reward_from_rpc).collect(),
+        loaded_writable_addresses: pubkeys_from_rpc(&loaded_addresses.writable),
+        loaded_readonly_addresses: pubkeys_from_rpc(&loaded_addresses.readonly),
+        return_data: None,
+        return_data_none: true,
+        compute_units_consumed: meta.compute_units_consumed.into(),
+    }
+}
+
+fn inner_instructions_from_rpc(inner: UiInnerInstructions) -> proto::InnerInstructions {
+    proto::InnerInstructions {
+        index: inner.index as u32,
+        // binary encodings only ever give compiled instructions
+        instructions: inner.instructions.into_iter().filter_map(|ix| match ix {
+            UiInstruction::Compiled(ix) => Some(proto::InnerInstruction {
+                program_id_index: ix.program_id_index as u32,
+                accounts: ix.accounts,
+                data: bs58::decode(&ix.data).into_vec().ok()?,
+                stack_height: ix.stack_height,
+            }),
+            UiInstruction::Parsed(_) => None,
+        }).collect(),
+    }
+}
+
+fn token_balance_from_rpc(balance: UiTransactionTokenBalance) -> proto::TokenBalance {
+    proto::TokenBalance {
+        account_index: balance.account_index as u32,
+        mint: balance.mint,
+        ui_token_amount: Some(proto::UiTokenAmount {
+            ui_amount: balance.ui_token_amount.ui_amount.unwrap_or_default(),
+            decimals: balance.ui_token_amount.decimals as u32,
+            amount: balance.ui_token_amount.amount,
+            ui_amount_string: balance.ui_token_amount.ui_amount_string,
+        }),
+        owner: Option::from(balance.owner).unwrap_or_default(),
+        program_id: Option::from(balance.program_id).unwrap_or_default(),
+    }
+}
+
+fn reward_from_rpc(reward: UiReward) -> proto::Reward {
+    let reward_type = match reward.reward_type {
+        None => proto::RewardType::Unspecified,
+        Some(RewardType::Fee) => proto::RewardType::Fee,
+        Some(RewardType::Rent) => proto::RewardType::Rent,
+        Some(RewardType::Staking) => proto::RewardType::Staking,
+        Some(RewardType::Voting) => proto::RewardType::Voting,
+    };
+    proto::Reward {
+        pubkey: reward.pubkey,
+        lamports: reward.lamports,
+        post_balance: reward.post_balance,
+        reward_type: reward_type as i32,
+        commission: reward.commission.map(|commission| commission.to_string()).unwrap_or_default(),
+    }
+}
+
+fn pubkeys_from_rpc(keys: &[String]) -> Vec<Vec<u8>> {
+    keys.iter().filter_map(|key| Pubkey::from_str(key).ok()).map(|key| key.to_bytes().to_vec()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{hash::Hash, instruction::CompiledInstruction, message::{v0, MessageHeader}, signature::Signature};
+    use solana_transaction_status_client_types::{option_serializer::OptionSerializer, EncodedTransaction, UiCompiledInstruction};
+
+    #[test]
+    fn test_block_from_rpc() {
+        let keys = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let lut_key = Pubkey::new_unique();
+        let tx = VersionedTransaction {
+            signatures: vec![Signature::from([7; 64])],
+            message: VersionedMessage::V0(v0::Message {
+                header: MessageHeader { num_required_signatures: 1, num_readonly_signed_accounts: 0, num_readonly_unsigned_accounts: 1 },
+                account_keys: keys.to_vec(),
+                recent_blockhash: Hash::new_unique(),
+                instructions: vec![CompiledInstruction { program_id_index: 1, accounts: vec![0, 2], data: vec![1, 2, 3] }],
+                address_table_lookups: vec![v0::MessageAddressTableLookup { account_key: lut_key, writable_indexes: vec![4], readonly_indexes: vec![] }],
+            }),
+        };
+        let meta: UiTransactionStatusMeta = serde_json::from_value(serde_json::json!({
+            "err": null,
+            "status": {"Ok": null},
+            "fee": 5000,
+            "preBalances": [10, 0, 1],
+            "postBalances": [5, 0, 1],
+            "innerInstructions": [],
+            "preTokenBalances": [],
+            "postTokenBalances": [],
+            "loadedAddresses": {"writable": [Pubkey::new_unique().to_string()], "readonly": []},
+            "computeUnitsConsumed": 1234,
+        })).unwrap();
+        let meta = UiTransactionStatusMeta {
+            inner_instructions: OptionSerializer::Some(vec![UiInnerInstructions {
+                index: 0,
+                instructions: vec![UiInstruction::Compiled(UiCompiledInstruction {
+                    program_id_index: 1,
+                    accounts: vec![2],
+                    data: bs58::encode([9, 9]).into_string(),
+                    stack_height: Some(2),
+                })],
+            }]),
+            ..meta
+        };
+        let encoded = EncodedTransactionWithStatusMeta {
+            transaction: EncodedTransaction::LegacyBinary(bs58::encode(bincode::serialize(&tx).unwrap()).into_string()),
+            meta: Some(meta),
+            version: None,
+        };
+        let block = block_from_rpc(42, UiConfirmedBlock {
+            previous_blockhash: "parent".to_string(),
+            blockhash: "hash".to_string(),
+            parent_slot: 41,
+            // an undecodable tx first so the one that decodes keeps its position
+            transactions: Some(vec![EncodedTransactionWithStatusMeta {
+                transaction: EncodedTransaction::LegacyBinary("not a tx".to_string()),
+                meta: None,
+                version: None,
+            }, encoded]),
+            signatures: None,
+            rewards: None,
+            num_reward_partitions: None,
+            block_time: Some(1_700_000_000),
+            block_height: None,
+        });
+        assert_eq!(block.slot, 42);
+        assert_eq!(block.block_time.unwrap().timestamp, 1_700_000_000);
+        assert_eq!(block.transactions.len(), 1);
+        let tx_info = &block.transactions[0];
+        assert_eq!(tx_info.index, 1);
+        assert_eq!(tx_info.signature, vec![7; 64]);
+        assert!(!tx_info.is_vote);
+        let message = tx_info.transaction.as_ref().unwrap().message.as_ref().unwrap();
+        assert!(message.versioned);
+        assert_eq!(message.instructions[0].data, vec![1, 2, 3]);
+        assert_eq!(message.address_table_lookups[0].account_key, lut_key.to_bytes().to_vec());
+        let meta = tx_info.meta.as_ref().unwrap();
+        assert!(meta.err.is_none());
+        assert_eq!(meta.compute_units_consumed, Some(1234));
+        assert_eq!(meta.loaded_writable_addresses.len(), 1);
+        assert_eq!(meta.inner_instructions[0].instructions[0].data, vec![9, 9]);
+        assert_eq!(meta.inner_instructions[0].instructions[0].stack_height, Some(2));
+    }
+}
@@ -0,0 +1,153 @@
+use std::{env, str::FromStr as _, sync::Arc};
+
+use solana_sdk::{native_token::lamports_to_sol, pubkey::Pubkey, signature::Signature};
+use teloxide::{dispatching::UpdateFilterExt as _, dptree, prelude::*, utils::command::BotCommands};
+
+use crate::{api::{action::check_transaction, summary::{load_summary, RankedAddress, Summary}, wallet::{load_wallet_summary, WalletSummary}}, metrics, redact::Redaction, replica::ReadPool};
+
+pub struct TelegramConfig {
+    token: String,
+}
+
+impl TelegramConfig {
+    /// Reads `TELEGRAM_BOT_TOKEN`, the bot is disabled without one
+    pub fn from_env() -> Option<Self> {
+        let token = env::var("TELEGRAM_BOT_TOKEN").ok().filter(|t| !t.is_empty())?;
+        Some(Self { token })
+    }
+}
+
+#[derive(BotCommands, Clone, Debug, PartialEq)]
+#[command(rename_rule = "lowercase", description = "Sandwich detection data, commands:")]
+enum Command {
+    #[command(description = "show this message")]
+    Help,
+    #[command(description = "whether a transaction was sandwiched, /check <signature>")]
+    Check(String),
+    #[command(description = "how often a wallet got sandwiched, /wallet <pubkey>")]
+    Wallet(String),
+    #[command(description = "sandwiches of the last 24h and who's behind them")]
+    Top24h,
+}
+
+struct BotState {
+    pool: ReadPool,
+    redaction: Redaction,
+}
+
+/// Answers the [`Command`]s sent to the bot, in private or group chats, with the same data the API serves
+pub fn start_bot(pool: ReadPool, redaction: Redaction, config: TelegramConfig) {
+    let bot = Bot::new(config.token);
+    let state = Arc::new(BotState { pool, redaction });
+    tokio::spawn(async move {
+        let handler = Update::filter_message().filter_command::<Command>().endpoint(answer);
+        println!("starting the telegram bot");
+        Dispatcher::builder(bot, handler)
+            .dependencies(dptree::deps![state])
+            .build()
+            .dispatch()
+            .await;
+    });
+}
+
+async fn answer(bot: Bot, msg: Message, command: Command, state: Arc<BotState>) -> ResponseResult<()> {
+    let text = match command {
+        Command::Help => Command::descriptions().to_string(),
+        Command::Check(signature) => match Signature::from_str(signature.trim()) {
+            Ok(_) => match state.pool.get_conn() {
+                Ok(mut conn) => {
+                    let (title, description) = check_transaction(&mut conn, signature.trim());
+                    format!("{}\n{}", title, description)
+                },
+                Err(e) => unavailable(e),
+            },
+            Err(_) => "Usage: /check <transaction signature>".to_string(),
+        },
+        Command::Wallet(wallet) => {
+            let wallet = wallet.trim();
+            if Pubkey::from_str(wallet).is_err() {
+                "Usage: /wallet <pubkey>".to_string()
+            } else if state.redaction.victim(wallet).is_some() {
+                "Victims are redacted on this instance.".to_string()
+            } else {
+                match state.pool.get_conn() {
+                    Ok(mut conn) => format_wallet(&load_wallet_summary(&mut conn, wallet)),
+                    Err(e) => unavailable(e),
+                }
+            }
+        },
+        Command::Top24h => match state.pool.get_conn() {
+            Ok(mut conn) => format_summary(&load_summary(&mut conn)),
+            Err(e) => unavailable(e),
+        },
+    };
+    metrics::incr("telegram_commands");
+    bot.send_message(msg.chat.id, text).await?;
+    Ok(())
+}
+
+fn unavailable(e: mysql::Error) -> String {
+    eprintln!("Failed to get a db connection for the telegram bot: {}", e);
+    "The database is unavailable, try again later.".to_string()
+}
+
+fn sol(lamports: u64) -> String {
+    format!("{:.3} SOL", lamports_to_sol(lamports))
+}
+
+fn percent(bps: u64) -> String {
+    format!("{:.2}%", bps as f64 / 100.0)
+}
+
+fn format_wallet(summary: &WalletSummary) -> String {
+    if *summary.times_sandwiched() == 0 {
+        return format!("{} hasn't been sandwiched.", summary.wallet());
+    }
+    let mut lines = vec![format!("{} was sandwiched {} times, losing an estimated {}.", summary.wallet(), summary.times_sandwiched(), sol(*summary.est_loss_lamports()))];
+    if let (Some(avg), Some(worst)) = (summary.avg_price_impact_bps(), summary.worst_price_impact_bps()) {
+        lines.push(format!("Price impact: {} on average, {} at worst", percent(*avg), percent(*worst)));
+    }
+    if let Some(pool) = summary.most_targeted_pool() {
+        lines.push(format!("Most targeted pool: {} ({} times)", pool.amm(), pool.times_sandwiched()));
+    }
+    if let Some(last) = summary.last_incident() {
+        lines.push(format!("Last incident at slot {}", last.slot()));
+    }
+    if !summary.attacker_clusters().is_empty() {
+        let clusters: Vec<String> = summary.attacker_clusters().iter().map(|c| format!("#{} ({})", c.cluster_id(), c.sandwiches())).collect();
+        lines.push(format!("Attacker clusters: {}", clusters.join(", ")));
+    }
+    lines.join("\n")
+}
+
+fn format_summary(summary: &Summary) -> String {
+    let mut lines = vec![format!("Last 24h: {} sandwiches, victims lost {}", summary.sandwiches_24h(), sol(*summary.victim_loss_lamports_24h()))];
+    if let Some(bps) = summary.sandwiched_volume_bps_24h() {
+        lines.push(format!("{} of the SOL volume was sandwiched", percent(*bps)));
+    }
+    let ranked = |title: &str, addresses: &[RankedAddress]| {
+        let mut lines = vec![format!("\n{}:", title)];
+        lines.extend(addresses.iter().enumerate().map(|(i, a)| format!("{}. {} - {} sandwiches, {}", i + 1, a.address(), a.sandwiches(), sol(*a.victim_loss_lamports()))));
+        lines.join("\n")
+    };
+    if !summary.top_attackers().is_empty() {
+        lines.push(ranked("Top attackers", summary.top_attackers()));
+    }
+    if !summary.top_pools().is_empty() {
+        lines.push(ranked("Top pools", summary.top_pools()));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commands() {
+        assert_eq!(Command::parse("/top24h", "bot").unwrap(), Command::Top24h);
+        assert_eq!(Command::parse("/wallet abc", "bot").unwrap(), Command::Wallet("abc".to_string()));
+        assert_eq!(Command::parse("/check@bot sig", "bot").unwrap(), Command::Check("sig".to_string()));
+        assert!(Command::parse("/stats", "bot").is_err());
+    }
+}
@@ -0,0 +1,144 @@
+use crate::events::swap::SwapV2;
+
+/// Builds `SwapV2` fixtures for tests, anything not set gets a placeholder and the id defaults to `slot * 1000 + inclusion_order`
+#[derive(Clone, Debug)]
+pub struct SwapBuilder {
+    outer_program: Option<String>,
+    program: String,
+    authority: String,
+    amm: String,
+    input_mint: String,
+    output_mint: String,
+    input_amount: u64,
+    output_amount: u64,
+    input_ata: String,
+    output_ata: String,
+    input_inner_ix_index: Option<u32>,
+    output_inner_ix_index: Option<u32>,
+    slot: u64,
+    inclusion_order: u32,
+    ix_index: u32,
+    id: Option<u64>,
+}
+
+impl Default for SwapBuilder {
+    fn default() -> Self {
+        Self {
+            outer_program: None,
+            program: "program".into(),
+            authority: "wallet".into(),
+            amm: "amm".into(),
+            input_mint: "in".into(),
+            output_mint: "out".into(),
+            input_amount: 1,
+            output_amount: 1,
+            input_ata: "in_ata".into(),
+            output_ata: "out_ata".into(),
+            input_inner_ix_index: None,
+            output_inner_ix_index: None,
+            slot: 1,
+            inclusion_order: 0,
+            ix_index: 0,
+            id: None,
+        }
+    }
+}
+
+impl SwapBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn at(mut self, slot: u64, inclusion_order: u32) -> Self {
+        self.slot = slot;
+        self.inclusion_order = inclusion_order;
+        self
+    }
+
+    pub fn ix_index(mut self, ix_index: u32) -> Self {
+        self.ix_index = ix_index;
+        self
+    }
+
+    pub fn id(mut self, id: u64) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    pub fn outer_program(mut self, outer_program: Option<&str>) -> Self {
+        self.outer_program = outer_program.map(Into::into);
+        self
+    }
+
+    pub fn program(mut self, program: &str) -> Self {
+        self.program = program.into();
+        self
+    }
+
+    pub fn authority(mut self, authority: &str) -> Self {
+        self.authority = authority.into();
+        self
+    }
+
+    pub fn amm(mut self, amm: &str) -> Self {
+        self.amm = amm.into();
+        self
+    }
+
+    pub fn mints(mut self, input_mint: &str, output_mint: &str) -> Self {
+        self.input_mint = input_mint.into();
+        self.output_mint = output_mint.into();
+        self
+    }
+
+    pub fn amounts(mut self, input_amount: u64, output_amount: u64) -> Self {
+        self.input_amount = input_amount;
+        self.output_amount = output_amount;
+        self
+    }
+
+    pub fn atas(mut self, input_ata: &str, output_ata: &str) -> Self {
+        self.input_ata = input_ata.into();
+        self.output_ata = output_ata.into();
+        self
+    }
+
+    pub fn inner_ix_indexes(mut self, input_inner_ix_index: Option<u32>, output_inner_ix_index: Option<u32>) -> Self {
+        self.input_inner_ix_index = input_inner_ix_index;
+        self.output_inner_ix_index = output_inner_ix_index;
+        self
+    }
+
+    /// Buys "token" with `quote`, between the "sol_ata" and "token_ata" accounts
+    pub fn buy(self, quote: &str) -> Self {
+        self.mints(quote, "token").atas("sol_ata", "token_ata")
+    }
+
+    /// Sells "token" for `quote`, the reverse of [`SwapBuilder::buy`]
+    pub fn sell(self, quote: &str) -> Self {
+        self.mints("token", quote).atas("token_ata", "sol_ata")
+    }
+
+    pub fn build(self) -> SwapV2 {
+        let id = self.id.unwrap_or(self.slot * 1000 + self.inclusion_order as u64);
+        SwapV2::new(
+            self.outer_program.map(Into::into),
+            self.program.into(),
+            self.authority.into(),
+            self.amm.into(),
+            self.input_mint.into(),
+            self.output_mint.into(),
+            self.input_amount,
+            self.output_amount,
+            self.input_ata.into(),
+            self.output_ata.into(),
+            self.input_inner_ix_index,
+            self.output_inner_ix_index,
+            self.slot,
+            self.inclusion_order,
+            self.ix_index,
+            None,
+            id,
+        )
+    }
+}
@@ -0,0 +1,36 @@
+use axum::{extract::Path, http::{header, StatusCode}, response::{IntoResponse, Redirect, Response}, routing::get, Router};
+use rust_embed::RustEmbed;
+
+/// The dashboard under `ui/`, built into the binary
+#[derive(RustEmbed)]
+#[folder = "ui/"]
+struct Assets;
+
+/// Serves the dashboard at `/ui`: recent sandwiches from `/history`, the live feed at `/` and `/search/{txid}`
+pub fn router<S: Clone + Send + Sync + 'static>() -> Router<S> {
+    Router::new()
+        .route("/ui", get(|| async { Redirect::permanent("/ui/") }))
+        .route("/ui/", get(|| async { asset("index.html") }))
+        .route("/ui/{*path}", get(|Path(path): Path<String>| async move { asset(&path) }))
+}
+
+fn asset(path: &str) -> Response {
+    match Assets::get(path) {
+        Some(file) => ([(header::CONTENT_TYPE, file.metadata.mimetype().to_string())], file.data).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assets() {
+        let index = asset("index.html");
+        assert_eq!(index.status(), StatusCode::OK);
+        assert_eq!(index.headers()[header::CONTENT_TYPE], "text/html");
+        assert_eq!(asset("app.js").headers()[header::CONTENT_TYPE], "text/javascript");
+        assert_eq!(asset("missing.html").status(), StatusCode::NOT_FOUND);
+    }
+}
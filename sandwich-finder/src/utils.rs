@@ -1,15 +1,14 @@
 use std::{collections::HashMap, env, fmt::Debug, str::FromStr};
 
-use dashmap::DashMap;
 use derive_getters::Getters;
 use mysql::{Pool, Value};
 use serde::{ser::SerializeStruct, Serialize};
-use solana_rpc_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::{account::ReadableAccount, address_lookup_table::{state::AddressLookupTable, AddressLookupTableAccount}, bs58, instruction::{AccountMeta, Instruction}, pubkey::Pubkey};
-use yellowstone_grpc_proto::{geyser::{SubscribeUpdateBlock, SubscribeUpdateTransactionInfo}, prelude::{InnerInstruction, InnerInstructions, RewardType, TransactionStatusMeta}};
+use solana_sdk::{account::ReadableAccount, bs58, instruction::{AccountMeta, Instruction}, pubkey::Pubkey};
+use yellowstone_grpc_proto::{geyser::{SubscribeUpdateBlock, SubscribeUpdateTransactionInfo}, prelude::{InnerInstruction, InnerInstructions, RewardType}};
 
-const DONT_FRONT_START: [u8; 32] = [10,241,195,67,33,136,202,58,99,81,53,161,58,24,149,26,206,189,41,230,172,45,174,103,255,219,6,215,64,0,0,0];
-const DONT_FRONT_END: [u8; 32]   = [10,241,195,67,33,136,202,58,99,82,11,83,236,186,243,27,60,23,98,46,152,130,58,175,28,197,174,53,128,0,0,0];
+use crate::{config, db::{create_pool, PoolConfig}, events::{addresses::is_known_aggregator, event::DONT_FRONT, token_accounts::TokenAccounts}, lut_cache::LutCache, metrics, redact::{Redact, Redaction}, rpc::BoundedRpc};
+
+pub use sandwich_finder_core::utils::pubkey_from_slice;
 
 const RAYDIUM_V4_PUBKEY: Pubkey = Pubkey::from_str_const("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
 const RAYDIUM_V5_PUBKEY: Pubkey = Pubkey::from_str_const("CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C");
@@ -71,6 +70,19 @@ impl Swap {
     }
 }
 
+impl Redact for Swap {
+    fn redact(&mut self, redaction: &Redaction, victim: bool) {
+        if victim {
+            if let Some(signer) = redaction.victim(&self.signer) {
+                self.signer = signer;
+            }
+        }
+        if let Some(subject) = redaction.ata(&self.subject, victim) {
+            self.subject = subject;
+        }
+    }
+}
+
 impl Debug for Swap {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str("{\n")?;
@@ -138,22 +150,82 @@ impl Sandwich {
         }
     }
 
+    /// Per-victim loss, in the same order as `victim`
+    pub fn estimate_victim_losses(&self) -> Vec<VictimLoss> {
+        let victims = self.victim.iter().map(|v| (v.input_amount, v.output_amount)).collect::<Vec<_>>();
+        estimate_victim_losses((self.frontrun.input_amount, self.frontrun.output_amount), &victims)
+    }
+
     pub fn estimate_victim_loss(&self) -> (u64, u64) {
-        let (a1, a2) = (self.frontrun.input_amount as i128, self.victim[0].input_amount as i128);
-        let (b1, b2) = (self.frontrun.output_amount as i128, self.victim[0].output_amount as i128);
-        let (a3, b3) = (a1 + a2, b1 + b2);
-        let (c1, c2) = (-a1 * b1, -a3 * b3);
-        // | b1   -a1 | | a | = | c1 |
-        // | b3   -a3 | | b |   | c2 |
-        let det = a1 * b3 - b1 * a3;
-        let det_a = a1 * c2 - c1 * a3;
-        let det_b = b1 * c2 - b3 * c1;
-        let a = det_a / det;
-        let b = det_b / det;
-        let k = a * b;
-        let b2_ = b - k / (a + a2);
-        let a2_ = a - k / (b - b2);
-        ((a2 - a2_) as u64, (b2_ - b2) as u64)
+        self.estimate_victim_losses().iter().fold((0, 0), |(a, b), loss| (a + loss.input_amount, b + loss.output_amount))
+    }
+
+    /// Whether any victim opted out of being frontrun
+    pub fn has_dont_front_victim(&self) -> bool {
+        self.victim.iter().any(|v| v.dont_front)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Getters)]
+#[serde(rename_all = "camelCase")]
+pub struct VictimLoss {
+    /// Extra input paid for the output actually received
+    input_amount: u64,
+    /// Output missed out on for the input actually paid
+    output_amount: u64,
+    /// How much worse the victim's price was than the pool would have given it without the frontrun, in bps
+    price_impact_bps: u64,
+}
+
+/// Estimates the loss of each victim of a sandwich on a constant product pool.
+/// The pool reserves are solved from the frontrun and the first victim, then each victim is
+/// replayed in order against the pool without the frontrun so later victims are priced
+/// against the state the earlier victims left behind.
+pub fn estimate_victim_losses(frontrun: (u64, u64), victims: &[(u64, u64)]) -> Vec<VictimLoss> {
+    let no_loss = vec![VictimLoss::default(); victims.len()];
+    let Some(&(a2, b2)) = victims.first() else {
+        return no_loss;
+    };
+    let (a1, a2) = (frontrun.0 as i128, a2 as i128);
+    let (b1, b2) = (frontrun.1 as i128, b2 as i128);
+    let (a3, b3) = (a1 + a2, b1 + b2);
+    let (c1, c2) = (-a1 * b1, -a3 * b3);
+    // | b1   -a1 | | a | = | c1 |
+    // | b3   -a3 | | b |   | c2 |
+    let det = a1 * b3 - b1 * a3;
+    if det == 0 {
+        return no_loss;
+    }
+    let det_a = a1 * c2 - c1 * a3;
+    let det_b = b1 * c2 - b3 * c1;
+    let a = det_a / det;
+    let b = det_b / det;
+    if a <= 0 || b <= 0 {
+        return no_loss;
+    }
+    let k = a * b;
+    // reserves had there been no frontrun
+    let (mut x, mut y) = (a, b);
+    victims.iter().map(|&(vi, vo)| {
+        let (vi, vo) = (vi as i128, vo as i128);
+        let fair_output = y - k / (x + vi);
+        let fair_input = if y > vo { k / (y - vo) - x } else { vi };
+        x += vi;
+        y = k / x;
+        let output_amount = (fair_output - vo).max(0);
+        VictimLoss {
+            input_amount: (vi - fair_input).max(0) as u64,
+            output_amount: output_amount as u64,
+            price_impact_bps: if fair_output > 0 { (output_amount * 10000 / fair_output) as u64 } else { 0 },
+        }
+    }).collect()
+}
+
+impl Redact for Sandwich {
+    fn redact(&mut self, redaction: &Redaction, _victim: bool) {
+        self.frontrun.redact(redaction, false);
+        self.victim.iter_mut().for_each(|v| v.redact(redaction, true));
+        self.backrun.redact(redaction, false);
     }
 }
 
@@ -167,6 +239,7 @@ impl Serialize for Sandwich {
         state.serialize_field("victim", &self.victim)?;
         state.serialize_field("backrun", &self.backrun)?;
         state.serialize_field("ts", &self.ts)?;
+        state.serialize_field("victimLosses", &self.estimate_victim_losses())?;
         state.end()
     }
 }
@@ -218,10 +291,24 @@ pub enum DbMessage {
     Sandwich(Sandwich),
 }
 
+/// Pool for `MYSQL`, configured by [`PoolConfig::from_env`]
+/// `{prefix}{name}` if it's set, `name` otherwise, so a second copy of a config only lists the variables it changes
+pub fn prefixed_env_var(prefix: &str, name: &str) -> Option<String> {
+    env::var(format!("{prefix}{name}")).or_else(|_| env::var(name)).ok()
+}
+
 pub fn create_db_pool() -> Pool {
-    let url = env::var("MYSQL").unwrap();
-    let pool = Pool::new(url.as_str()).unwrap();
-    pool
+    let url = config::get().mysql().as_ref().expect("MYSQL is not set");
+    create_pool(url, &PoolConfig::from_env())
+}
+
+/// Pool for `MYSQL`, or `None` in stream-only mode, which is when `STREAM_ONLY=1` or `MYSQL` isn't set
+pub fn try_create_db_pool() -> Option<Pool> {
+    if env::var("STREAM_ONLY").is_ok_and(|v| v == "1") {
+        return None;
+    }
+    let url = config::get().mysql().as_ref()?;
+    Some(create_pool(url, &PoolConfig::from_env()))
 }
 
 pub fn block_stats(block: &SubscribeUpdateBlock) -> DbMessage {
@@ -264,7 +351,7 @@ pub fn block_stats(block: &SubscribeUpdateBlock) -> DbMessage {
     })
 }
 
-pub async fn decompile(raw_tx: &SubscribeUpdateTransactionInfo, rpc_client: &RpcClient, lut_cache: &DashMap<Pubkey, AddressLookupTableAccount>) -> Option<DecompiledTransaction> {
+pub async fn decompile(raw_tx: &SubscribeUpdateTransactionInfo, rpc_client: &BoundedRpc, lut_cache: &LutCache) -> Option<DecompiledTransaction> {
     if let Some(tx) = &raw_tx.transaction {
         if let Some(meta) = &raw_tx.meta {
             // no swaps in failed txs
@@ -274,27 +361,7 @@ pub async fn decompile(raw_tx: &SubscribeUpdateTransactionInfo, rpc_client: &Rpc
             if let Some(msg) = &tx.message {
                 if let Some(header) = &msg.header {
                     let sig = bs58::encode(&raw_tx.signature).into_string();
-                    let lut_keys = msg.address_table_lookups.iter().map(|lut| {
-                        pubkey_from_slice(&lut.account_key[0..32])
-                    }).collect::<Vec<Pubkey>>();
-        
-                    // get the uncached lut accounts, deserialize them and cache them
-                    let uncached_luts = lut_keys.iter().filter(|lut_key| !lut_cache.contains_key(lut_key)).map(|x| *x).collect::<Vec<Pubkey>>();
-                    if !uncached_luts.is_empty() {
-                        let accounts = rpc_client.get_multiple_accounts(uncached_luts.as_slice()).await.expect("unable to get accounts");
-                        accounts.iter().enumerate().for_each(|(i, account)| {
-                            if let Some(account) = account {
-                                let lut = AddressLookupTable::deserialize(&account.data()).expect("unable to deserialize account");
-                                lut_cache.insert(uncached_luts[i], AddressLookupTableAccount {
-                                    key: uncached_luts[i],
-                                    addresses: lut.addresses.to_vec(),
-                                });
-                            }
-                        });
-                    }
-        
-                    // resolve lookups
-                    let (writable, readonly) = resolve_lut_lookups(&lut_cache, &msg);
+                    let (writable, readonly) = load_lut_addresses(raw_tx, msg, rpc_client, lut_cache).await?;
                     let num_signed_accts = header.num_required_signatures as usize;
                     let num_static_keys = msg.account_keys.len();
                     let num_writable_lut_keys = writable.len();
@@ -328,14 +395,15 @@ pub async fn decompile(raw_tx: &SubscribeUpdateTransactionInfo, rpc_client: &Rpc
                         }
                     }).collect::<Vec<Instruction>>();
 
-                    // don't front flag - if the tx contains a pubkey that starts with jitodontfront, which is pubkeys within [DONT_FRONT_START, DONT_FRONT_END)
-                    let dont_front = account_keys.iter().any(|k| k.to_bytes() >= DONT_FRONT_START && k.to_bytes() < DONT_FRONT_END);
+                    // don't front flag - if the tx contains a marker account, such as a pubkey that starts with jitodontfront
+                    let dont_front = DONT_FRONT.any(&account_keys);
                     
                     // find swaps from the ixs
                     // we're looking for raydium swaps, those swaps can occur in 2 forms:
                     // 1. as a direct call to the raydium program, in that case we should see 2 inner ixs corresponding to the send/receive
                     // 2. as a cpi, in that case we should see 3 inner ixs, the raydium call and the transfers
                     // raydium swap txs has this call data: 09/amountIn u64/minOut u64, and the 2nd account is the amm id
+                    let token_accounts = TokenAccounts::from_meta(meta);
                     let mut inner_ix_map: HashMap<usize, &InnerInstructions> = HashMap::new();
                     meta.inner_instructions.iter().for_each(|inner_ix| {
                         inner_ix_map.insert(inner_ix.index as usize, inner_ix);
@@ -354,26 +422,26 @@ pub async fn decompile(raw_tx: &SubscribeUpdateTransactionInfo, rpc_client: &Rpc
                         let inner_ix = inner_ix_map.get(&i);
                         if let Some(inner_ix) = inner_ix {
                             // ray v4 swap
-                            swaps.extend(find_swaps(ix, inner_ix, &RAYDIUM_V4_PUBKEY, &[0x09], 1, 1, 2, 17, meta, &account_keys, sig.clone(), raw_tx.index, dont_front));
+                            swaps.extend(find_swaps(ix, inner_ix, &RAYDIUM_V4_PUBKEY, &[0x09], 1, 1, 2, 17, &token_accounts, &account_keys, sig.clone(), raw_tx.index, dont_front));
                             // ray v5 swap_base_input/swap_base_output
-                            swaps.extend(find_swaps(ix, inner_ix, &RAYDIUM_V5_PUBKEY, &[0x8f, 0xbe, 0x5a, 0xda, 0xc4, 0x1e, 0x33, 0xde], 3, 1, 2, 24, meta, &account_keys, sig.clone(), raw_tx.index, dont_front));
-                            swaps.extend(find_swaps(ix, inner_ix, &RAYDIUM_V5_PUBKEY, &[0x37, 0xd9, 0x62, 0x56, 0xa3, 0x4a, 0xb4, 0xad], 3, 1, 2, 24, meta, &account_keys, sig.clone(), raw_tx.index, dont_front));
+                            swaps.extend(find_swaps(ix, inner_ix, &RAYDIUM_V5_PUBKEY, &[0x8f, 0xbe, 0x5a, 0xda, 0xc4, 0x1e, 0x33, 0xde], 3, 1, 2, 24, &token_accounts, &account_keys, sig.clone(), raw_tx.index, dont_front));
+                            swaps.extend(find_swaps(ix, inner_ix, &RAYDIUM_V5_PUBKEY, &[0x37, 0xd9, 0x62, 0x56, 0xa3, 0x4a, 0xb4, 0xad], 3, 1, 2, 24, &token_accounts, &account_keys, sig.clone(), raw_tx.index, dont_front));
                             // ray launchpad buy_exact_in/sell_exact_in
-                            swaps.extend(find_swaps(ix, inner_ix, &RAYDIUM_LP_PUBKEY, &[0xfa, 0xea, 0x0d, 0x7b, 0xd5, 0x9c, 0x13, 0xec], 4, 2, 3, 32, meta, &account_keys, sig.clone(), raw_tx.index, dont_front));
-                            swaps.extend(find_swaps(ix, inner_ix, &RAYDIUM_LP_PUBKEY, &[0x95, 0x27, 0xde, 0x9b, 0xd3, 0x7c, 0x98, 0x1a], 4, 2, 3, 32, meta, &account_keys, sig.clone(), raw_tx.index, dont_front));
+                            swaps.extend(find_swaps(ix, inner_ix, &RAYDIUM_LP_PUBKEY, &[0xfa, 0xea, 0x0d, 0x7b, 0xd5, 0x9c, 0x13, 0xec], 4, 2, 3, 32, &token_accounts, &account_keys, sig.clone(), raw_tx.index, dont_front));
+                            swaps.extend(find_swaps(ix, inner_ix, &RAYDIUM_LP_PUBKEY, &[0x95, 0x27, 0xde, 0x9b, 0xd3, 0x7c, 0x98, 0x1a], 4, 2, 3, 32, &token_accounts, &account_keys, sig.clone(), raw_tx.index, dont_front));
                             // pdf buy/sell
-                            swaps.extend(find_swaps(ix, inner_ix, &PDF_PUBKEY, &[0x66, 0x06, 0x3d, 0x12, 0x01, 0xda, 0xeb, 0xea], 3, 2, 1, 24, meta, &account_keys, sig.clone(), raw_tx.index, dont_front));
-                            swaps.extend(find_swaps(ix, inner_ix, &PDF_PUBKEY, &[0x33, 0xe6, 0x85, 0xa4, 0x01, 0x7f, 0x83, 0xad], 3, 1, 2, 24, meta, &account_keys, sig.clone(), raw_tx.index, dont_front));
+                            swaps.extend(find_swaps(ix, inner_ix, &PDF_PUBKEY, &[0x66, 0x06, 0x3d, 0x12, 0x01, 0xda, 0xeb, 0xea], 3, 2, 1, 24, &token_accounts, &account_keys, sig.clone(), raw_tx.index, dont_front));
+                            swaps.extend(find_swaps(ix, inner_ix, &PDF_PUBKEY, &[0x33, 0xe6, 0x85, 0xa4, 0x01, 0x7f, 0x83, 0xad], 3, 1, 2, 24, &token_accounts, &account_keys, sig.clone(), raw_tx.index, dont_front));
                             // pdf2 buy/sell
-                            swaps.extend(find_swaps(ix, inner_ix, &PDF2_PUBKEY, &[0x66, 0x06, 0x3d, 0x12, 0x01, 0xda, 0xeb, 0xea], 0, 2, 1, 24, meta, &account_keys, sig.clone(), raw_tx.index, dont_front));
-                            swaps.extend(find_swaps(ix, inner_ix, &PDF2_PUBKEY, &[0x33, 0xe6, 0x85, 0xa4, 0x01, 0x7f, 0x83, 0xad], 0, 1, 2, 24, meta, &account_keys, sig.clone(), raw_tx.index, dont_front));
+                            swaps.extend(find_swaps(ix, inner_ix, &PDF2_PUBKEY, &[0x66, 0x06, 0x3d, 0x12, 0x01, 0xda, 0xeb, 0xea], 0, 2, 1, 24, &token_accounts, &account_keys, sig.clone(), raw_tx.index, dont_front));
+                            swaps.extend(find_swaps(ix, inner_ix, &PDF2_PUBKEY, &[0x33, 0xe6, 0x85, 0xa4, 0x01, 0x7f, 0x83, 0xad], 0, 1, 2, 24, &token_accounts, &account_keys, sig.clone(), raw_tx.index, dont_front));
                             // whirlpool swap
-                            swaps.extend(find_swaps(ix, inner_ix, &WHIRLPOOL_PUBKEY, &[0xf8, 0xc6, 0x9e, 0x91, 0xe1, 0x75, 0x87, 0xc8], 2, 1, 2, 42, meta, &account_keys, sig.clone(), raw_tx.index, dont_front));
+                            swaps.extend(find_swaps(ix, inner_ix, &WHIRLPOOL_PUBKEY, &[0xf8, 0xc6, 0x9e, 0x91, 0xe1, 0x75, 0x87, 0xc8], 2, 1, 2, 42, &token_accounts, &account_keys, sig.clone(), raw_tx.index, dont_front));
                             // dlmm swap
-                            swaps.extend(find_swaps(ix, inner_ix, &DLMM_PUBKEY, &[0xf8, 0xc6, 0x9e, 0x91, 0xe1, 0x75, 0x87, 0xc8], 0, 1, 2, 24, meta, &account_keys, sig.clone(), raw_tx.index, dont_front));
+                            swaps.extend(find_swaps(ix, inner_ix, &DLMM_PUBKEY, &[0xf8, 0xc6, 0x9e, 0x91, 0xe1, 0x75, 0x87, 0xc8], 0, 1, 2, 24, &token_accounts, &account_keys, sig.clone(), raw_tx.index, dont_front));
                             // meteora swap (swap, (charge_fee),  deposit, send, mint_lp, withdraw, recv, burn_lp)
-                            swaps.extend(find_swaps(ix, inner_ix, &METEORA_PUBKEY, &[0xf8, 0xc6, 0x9e, 0x91, 0xe1, 0x75, 0x87, 0xc8], 0, 2, 5, 24, meta, &account_keys, sig.clone(), raw_tx.index, dont_front));
-                            swaps.extend(find_swaps(ix, inner_ix, &METEORA_PUBKEY, &[0xf8, 0xc6, 0x9e, 0x91, 0xe1, 0x75, 0x87, 0xc8], 0, 3, 6, 24, meta, &account_keys, sig.clone(), raw_tx.index, dont_front));
+                            swaps.extend(find_swaps(ix, inner_ix, &METEORA_PUBKEY, &[0xf8, 0xc6, 0x9e, 0x91, 0xe1, 0x75, 0x87, 0xc8], 0, 2, 5, 24, &token_accounts, &account_keys, sig.clone(), raw_tx.index, dont_front));
+                            swaps.extend(find_swaps(ix, inner_ix, &METEORA_PUBKEY, &[0xf8, 0xc6, 0x9e, 0x91, 0xe1, 0x75, 0x87, 0xc8], 0, 3, 6, 24, &token_accounts, &account_keys, sig.clone(), raw_tx.index, dont_front));
                         }                        
                     });
                     return Some(DecompiledTransaction::new(
@@ -391,7 +459,8 @@ pub async fn decompile(raw_tx: &SubscribeUpdateTransactionInfo, rpc_client: &Rpc
     None    
 }
 
-pub async fn decompile_tx<'a>(raw_tx: &'a SubscribeUpdateTransactionInfo, rpc_client: &RpcClient, lut_cache: &DashMap<Pubkey, AddressLookupTableAccount>) -> Option<(&'a SubscribeUpdateTransactionInfo, Vec<Instruction>, Vec<Pubkey>)> {
+/// The tx's ixs with their accounts resolved, its account keys and the token accounts among them
+pub async fn decompile_tx<'a>(raw_tx: &'a SubscribeUpdateTransactionInfo, rpc_client: &BoundedRpc, lut_cache: &LutCache) -> Option<(&'a SubscribeUpdateTransactionInfo, Vec<Instruction>, Vec<Pubkey>, TokenAccounts)> {
     if let Some(tx) = &raw_tx.transaction {
         if let Some(meta) = &raw_tx.meta {
             if meta.err.is_some() {
@@ -400,27 +469,7 @@ pub async fn decompile_tx<'a>(raw_tx: &'a SubscribeUpdateTransactionInfo, rpc_cl
             }
             if let Some(msg) = &tx.message {
                 if let Some(header) = &msg.header {
-                    let lut_keys = msg.address_table_lookups.iter().map(|lut| {
-                        pubkey_from_slice(&lut.account_key[0..32])
-                    }).collect::<Vec<Pubkey>>();
-
-                    // get the uncached lut accounts, deserialize them and cache them
-                    let uncached_luts = lut_keys.iter().filter(|lut_key| !lut_cache.contains_key(lut_key)).map(|x| *x).collect::<Vec<Pubkey>>();
-                    if !uncached_luts.is_empty() {
-                        let accounts = rpc_client.get_multiple_accounts(uncached_luts.as_slice()).await.expect("unable to get accounts");
-                        accounts.iter().enumerate().for_each(|(i, account)| {
-                            if let Some(account) = account {
-                                let lut = AddressLookupTable::deserialize(&account.data()).expect("unable to deserialize account");
-                                lut_cache.insert(uncached_luts[i], AddressLookupTableAccount {
-                                    key: uncached_luts[i],
-                                    addresses: lut.addresses.to_vec(),
-                                });
-                            }
-                        });
-                    }
-
-                    // resolve lookups
-                    let (writable, readonly) = resolve_lut_lookups(&lut_cache, &msg);
+                    let (writable, readonly) = load_lut_addresses(raw_tx, msg, rpc_client, lut_cache).await?;
                     let num_signed_accts = header.num_required_signatures as usize;
                     let num_static_keys = msg.account_keys.len();
                     let num_writable_lut_keys = writable.len();
@@ -453,7 +502,7 @@ pub async fn decompile_tx<'a>(raw_tx: &'a SubscribeUpdateTransactionInfo, rpc_cl
                             data: ix.data.clone(),
                         }
                     }).collect::<Vec<Instruction>>();
-                    return Some((raw_tx, ixs, account_keys));
+                    return Some((raw_tx, ixs, account_keys, TokenAccounts::from_meta(meta)));
                 }
             }
         }
@@ -493,7 +542,7 @@ pub fn find_sandwiches(in_trades: &Vec<&Swap>, out_trades: &Vec<&Swap>, slot: u6
             if in_trade.outer_program() != out_trade.outer_program() || in_trade.outer_program().is_none() || out_trade.outer_program().is_none() {
                 continue;
             }
-            if in_trade.outer_program() == &Some("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4".to_string()) {
+            if in_trade.outer_program().as_ref().and_then(|p| Pubkey::from_str(p).ok()).is_some_and(|p| is_known_aggregator(&p)) {
                 continue;
             }
             if nonmatching_out_trade.is_none() {
@@ -541,14 +590,14 @@ pub fn find_sandwiches(in_trades: &Vec<&Swap>, out_trades: &Vec<&Swap>, slot: u6
     sandwiches
 }
 
-fn find_swaps(ix: &Instruction, inner_ix: &InnerInstructions, swap_program: &Pubkey, discriminant: &[u8], amm_index: usize, send_ix_index: usize, recv_ix_index: usize, data_len: usize, meta: &TransactionStatusMeta, account_keys: &Vec<Pubkey>, sig: String, tx_index: u64, dont_front: bool) -> Vec<Swap> {
+fn find_swaps(ix: &Instruction, inner_ix: &InnerInstructions, swap_program: &Pubkey, discriminant: &[u8], amm_index: usize, send_ix_index: usize, recv_ix_index: usize, data_len: usize, token_accounts: &TokenAccounts, account_keys: &Vec<Pubkey>, sig: String, tx_index: u64, dont_front: bool) -> Vec<Swap> {
     let mut swaps: Vec<Swap> = Vec::new();
     // case 1
     if ix.program_id == *swap_program && ix.data.len() == data_len && ix.data[0..discriminant.len()] == *discriminant {
         let send_inner_ix = &inner_ix.instructions[send_ix_index - 1];
         let recv_inner_ix = &inner_ix.instructions[recv_ix_index - 1];
-        let input = find_transferred_token(send_inner_ix, meta);
-        let output = find_transferred_token(recv_inner_ix, meta);
+        let input = find_transferred_token(send_inner_ix, token_accounts);
+        let output = find_transferred_token(recv_inner_ix, token_accounts);
         if let Some(input) = input {
             if let Some(output) = output {
                 swaps.push(Swap::new(
@@ -581,8 +630,8 @@ fn find_swaps(ix: &Instruction, inner_ix: &InnerInstructions, swap_program: &Pub
             }
             let send_inner_ix = &inner_ix.instructions[j + send_ix_index];
             let recv_inner_ix = &inner_ix.instructions[j + recv_ix_index];
-            let input = find_transferred_token(send_inner_ix, meta);
-            let output = find_transferred_token(recv_inner_ix, meta);
+            let input = find_transferred_token(send_inner_ix, token_accounts);
+            let output = find_transferred_token(recv_inner_ix, token_accounts);
             if let Some(input) = input {
                 if let Some(output) = output {
                     swaps.push(Swap::new(
@@ -606,7 +655,7 @@ fn find_swaps(ix: &Instruction, inner_ix: &InnerInstructions, swap_program: &Pub
     swaps
 }
 
-fn find_transferred_token(ix: &InnerInstruction, meta: &TransactionStatusMeta) -> Option<(Pubkey, u8, u64)> {
+fn find_transferred_token(ix: &InnerInstruction, token_accounts: &TokenAccounts) -> Option<(Pubkey, u8, u64)> {
     // transfer: 1/0; transferChecked: 2/0
     let (i1, i0, subject_idx, range) = match ix.data[0] {
         2 => (99, 99, ix.accounts[0], 4..12), // system program transfer
@@ -619,31 +668,135 @@ fn find_transferred_token(ix: &InnerInstruction, meta: &TransactionStatusMeta) -
     if (i1, i0) == (99, 99) {
         return Some((WSOL_PUBKEY, subject_idx, amount));
     }
-    return meta.post_token_balances.iter().filter(|x| x.account_index == i1 as u32 || x.account_index == i0 as u32).map(|x| {
-        (Pubkey::from_str(&x.mint).expect("invalid pubkey"), subject_idx, amount)
-    }).next();
+    let mint = token_accounts.mint_at(i1 as usize).or_else(|| token_accounts.mint_at(i0 as usize))?;
+    Some((mint, subject_idx, amount))
+}
+
+/// Caches the tables in `lut_keys` over rpc, all of them if `refetch`, otherwise only those not cached yet
+async fn fetch_luts(lut_keys: &[Pubkey], rpc_client: &BoundedRpc, lut_cache: &LutCache, refetch: bool) {
+    let keys = lut_keys.iter().filter(|lut_key| refetch || !lut_cache.contains_key(lut_key)).copied().collect::<Vec<Pubkey>>();
+    if keys.is_empty() {
+        return;
+    }
+    // txs using the tables that couldn't be fetched are quarantined
+    let accounts = match rpc_client.get_multiple_accounts(keys.as_slice()).await {
+        Ok(accounts) => accounts,
+        Err(e) => {
+            eprintln!("Failed to fetch {} luts: {}", keys.len(), e);
+            return;
+        }
+    };
+    accounts.iter().enumerate().for_each(|(i, account)| {
+        if let Some(account) = account {
+            lut_cache.update(keys[i], account.owner(), account.data());
+        }
+    });
+}
+
+/// The (writable, readonly) addresses `msg` loads from lookup tables. A cached table missing some of them is refetched once,
+/// if it's still inconsistent the tx is quarantined (logged and skipped) rather than taking the pipeline down.
+async fn load_lut_addresses(raw_tx: &SubscribeUpdateTransactionInfo, msg: &yellowstone_grpc_proto::prelude::Message, rpc_client: &BoundedRpc, lut_cache: &LutCache) -> Option<(Vec<Pubkey>, Vec<Pubkey>)> {
+    let lut_keys = msg.address_table_lookups.iter().filter_map(|lut| {
+        Pubkey::try_from(&lut.account_key[..]).ok()
+    }).collect::<Vec<Pubkey>>();
+    fetch_luts(&lut_keys, rpc_client, lut_cache, false).await;
+    if let Some(addresses) = resolve_lut_lookups(lut_cache, msg) {
+        return Some(addresses);
+    }
+    metrics::incr("lut_refetches");
+    fetch_luts(&lut_keys, rpc_client, lut_cache, true).await;
+    let addresses = resolve_lut_lookups(lut_cache, msg);
+    if addresses.is_none() {
+        println!("quarantined tx {}: its lookups don't match the tables", bs58::encode(&raw_tx.signature).into_string());
+        metrics::incr("txs_quarantined");
+    }
+    addresses
 }
 
-fn resolve_lut_lookups(lut_cache: &DashMap<Pubkey, AddressLookupTableAccount>, msg: &yellowstone_grpc_proto::prelude::Message) -> (Vec<Pubkey>, Vec<Pubkey>) {
+/// `None` if a table isn't cached or is shorter than an index into it
+fn resolve_lut_lookups(lut_cache: &LutCache, msg: &yellowstone_grpc_proto::prelude::Message) -> Option<(Vec<Pubkey>, Vec<Pubkey>)> {
     let mut writable: Vec<Pubkey> = Vec::new();
     let mut readonly: Vec<Pubkey> = Vec::new();
-    msg.address_table_lookups.iter().for_each(|table_lookup| {
-        let lut_key = pubkey_from_slice(&table_lookup.account_key[0..32]);
+    for table_lookup in &msg.address_table_lookups {
+        let lut_key = Pubkey::try_from(&table_lookup.account_key[..]).ok()?;
         // find the correct lut account
-        let lut = lut_cache.get(&lut_key).expect("lut not found");
+        let lut = lut_cache.get(&lut_key)?;
 
-        table_lookup.writable_indexes.iter().for_each(|index| {
-            writable.push(lut.addresses[*index as usize]);
-        });
+        for index in &table_lookup.writable_indexes {
+            writable.push(*lut.addresses.get(*index as usize)?);
+        }
 
-        table_lookup.readonly_indexes.iter().for_each(|index| {
-            readonly.push(lut.addresses[*index as usize]);
-        });
-    });
+        for index in &table_lookup.readonly_indexes {
+            readonly.push(*lut.addresses.get(*index as usize)?);
+        }
+    }
 
-    (writable, readonly)
+    Some((writable, readonly))
 }
 
-pub fn pubkey_from_slice(slice: &[u8]) -> Pubkey {
-    Pubkey::new_from_array(slice.try_into().expect("slice with incorrect length"))
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use solana_sdk::address_lookup_table::state::{AddressLookupTable, LookupTableMeta};
+    use yellowstone_grpc_proto::prelude::{Message, MessageAddressTableLookup};
+
+    use crate::events::addresses::{LUT_PROGRAM_ID, OKX_DEX_ROUTER_PROGRAM_ID};
+
+    use super::*;
+
+    #[test]
+    fn test_find_sandwiches_skips_aggregators() {
+        let swap = |outer_program: &str, signer: &str, buy: bool, input_amount: u64, output_amount: u64, order: u64| {
+            let (input_mint, output_mint) = if buy { ("sol", "token") } else { ("token", "sol") };
+            Swap::new(Some(outer_program.to_string()), "program".to_string(), "amm".to_string(), signer.to_string(), signer.to_string(), input_mint.to_string(), output_mint.to_string(), input_amount, output_amount, order, format!("sig{order}"), false)
+        };
+        let sandwiches = |outer_program: &str| {
+            let buys = [swap(outer_program, "bot", true, 100, 100, 1), swap("router", "victim", true, 100, 90, 2)];
+            let sells = [swap(outer_program, "bot", false, 100, 101, 3)];
+            find_sandwiches(&buys.iter().collect(), &sells.iter().collect(), 1, 0).len()
+        };
+        assert_eq!(sandwiches("bot_program"), 1);
+        // two users routing through OKX share its program id, that's no sign of the same bot
+        assert_eq!(sandwiches(&OKX_DEX_ROUTER_PROGRAM_ID.to_string()), 0);
+    }
+
+    #[test]
+    fn test_estimate_victim_losses() {
+        // a 1000/1000 pool, the frontrun buys 90 with 100 and the victim then gets 75 for 100 where it would have got 90
+        let loss = |input_amount, output_amount, price_impact_bps| VictimLoss { input_amount, output_amount, price_impact_bps };
+        assert_eq!(estimate_victim_losses((100, 90), &[(100, 75)]), vec![loss(19, 15, 1666)]);
+        // the second victim is priced after the first, not against the untouched pool
+        assert_eq!(estimate_victim_losses((100, 90), &[(100, 75), (100, 64)]), vec![loss(19, 15, 1666), loss(16, 11, 1466)]);
+        assert_eq!(estimate_victim_losses((100, 90), &[]), vec![]);
+    }
+
+    #[test]
+    fn test_estimate_zero_output_victim() {
+        // the pool can't be solved from a first victim that got nothing
+        assert_eq!(estimate_victim_losses((100, 90), &[(100, 0)]), vec![VictimLoss::default()]);
+        // a later one lost its whole output
+        let losses = estimate_victim_losses((100, 90), &[(100, 75), (100, 0)]);
+        assert_eq!((losses[1].input_amount, losses[1].output_amount, losses[1].price_impact_bps), (100, 75, 10000));
+    }
+
+    #[test]
+    fn test_resolve_lut_lookups() {
+        let (lut_key, addresses) = (Pubkey::new_unique(), [Pubkey::new_unique(), Pubkey::new_unique()]);
+        let lut_cache = LutCache::default();
+        let msg = |writable_indexes: Vec<u8>| Message {
+            address_table_lookups: vec![MessageAddressTableLookup {
+                account_key: lut_key.to_bytes().to_vec(),
+                writable_indexes,
+                readonly_indexes: vec![0],
+            }],
+            ..Default::default()
+        };
+        assert_eq!(resolve_lut_lookups(&lut_cache, &msg(vec![1])), None);
+        let data = AddressLookupTable { meta: LookupTableMeta::default(), addresses: Cow::Borrowed(&addresses) }.serialize_for_tests().unwrap();
+        lut_cache.update(lut_key, &LUT_PROGRAM_ID, &data);
+        assert_eq!(resolve_lut_lookups(&lut_cache, &msg(vec![1])), Some((vec![addresses[1]], vec![addresses[0]])));
+        // a stale copy of a table that's since been extended
+        assert_eq!(resolve_lut_lookups(&lut_cache, &msg(vec![2])), None);
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::HashSet;
+
+use mysql::{prelude::Queryable as _, PooledConn};
+
+use crate::detector::SLOTS_PER_HOUR;
+
+/// Views cover the day leading up to the latest indexed slot
+const VIEW_WINDOW_SLOTS: u64 = 24 * SLOTS_PER_HOUR;
+
+/// Columns of the current database, as (table, column)
+#[derive(Clone, Debug, Default)]
+pub struct Schema(HashSet<(String, String)>);
+
+impl Schema {
+    pub fn new<'a>(columns: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        Self(columns.into_iter().map(|(t, c)| (t.to_string(), c.to_string())).collect())
+    }
+
+    pub fn load(conn: &mut PooledConn) -> mysql::Result<Self> {
+        let columns: Vec<(String, String)> = conn.query("select table_name, column_name from information_schema.columns where table_schema=database()")?;
+        Ok(Self(columns.into_iter().collect()))
+    }
+
+    pub fn has(&self, table: &str, column: &str) -> bool {
+        self.0.contains(&(table.to_string(), column.to_string()))
+    }
+
+    pub fn has_table(&self, table: &str) -> bool {
+        self.0.iter().any(|(t, _)| t == table)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ViewPlan {
+    /// (view name, select statement)
+    pub create: Vec<(&'static str, String)>,
+    /// Views the schema lacks a table for, with the first missing table
+    pub drop: Vec<(&'static str, &'static str)>,
+}
+
+// (view name, tables it needs, builder)
+type ViewDef = (&'static str, &'static [&'static str], fn(&Schema) -> String);
+
+fn since() -> String {
+    format!("(select max(slot) from transactions) - {VIEW_WINDOW_SLOTS}")
+}
+
+fn recent_sandwiches(schema: &Schema) -> String {
+    let mut columns = vec![
+        "sp.sandwich_id as id".to_string(),
+        "sp.slot".to_string(),
+        "sp.slot_span".to_string(),
+        "sp.inclusion_order_span".to_string(),
+        "sum(s.role='FRONTRUN') as frontruns".to_string(),
+        "sum(s.role='VICTIM') as victims".to_string(),
+        "sum(s.role='BACKRUN') as backruns".to_string(),
+    ];
+    if schema.has("transactions", "block_time") {
+        columns.insert(0, "(select from_unixtime(min(t.block_time)) from transactions t where t.slot=sp.slot) as time".to_string());
+    }
+    if schema.has("sandwiches", "position_bps") {
+        columns.push("min(case when s.role='FRONTRUN' then s.position_bps end) as frontrun_position_bps".to_string());
+        columns.push("max(case when s.role='BACKRUN' then s.position_bps end) as backrun_position_bps".to_string());
+    }
+    if schema.has("sandwiches", "price_impact_bps") {
+        columns.push("max(case when s.role='VICTIM' then s.price_impact_bps end) as worst_victim_price_impact_bps".to_string());
+    }
+    format!(
+        "select {} from sandwich_spans sp join sandwiches s on s.id=sp.sandwich_id where sp.slot >= {} group by sp.sandwich_id, sp.slot, sp.slot_span, sp.inclusion_order_span",
+        columns.join(", "),
+        since(),
+    )
+}
+
+fn top_attackers(schema: &Schema) -> String {
+    let mut columns = vec![
+        "a.address as attacker",
+        "sum(r.sandwiches) as sandwiches",
+        "sum(r.victims) as victims",
+        "sum(r.victim_loss_lamports) as victim_loss_lamports",
+    ];
+    if schema.has("sandwich_attacker_rollup", "dont_front_victims") {
+        columns.push("sum(r.dont_front_victims) as dont_front_victims");
+    }
+    format!(
+        "select {} from sandwich_attacker_rollup r join address_lookup_table a on a.id=r.attacker_id where r.bucket_slot >= {} group by r.attacker_id, a.address",
+        columns.join(", "),
+        since(),
+    )
+}
+
+fn validator_stats(schema: &Schema) -> String {
+    let since = since();
+    let mut columns = vec![
+        "a.address as leader".to_string(),
+        format!("(select count(*) from leader_schedule l2 where l2.leader_id=l.leader_id and l2.slot >= {since}) as leader_slots"),
+        "count(distinct sp.slot) as sandwich_blocks".to_string(),
+        "count(*) as sandwiches".to_string(),
+    ];
+    if schema.has_table("dont_front_violations") {
+        columns.push(format!("(select count(*) from dont_front_violations d join leader_schedule l2 on l2.slot=d.slot where l2.leader_id=l.leader_id and d.slot >= {since}) as dont_front_violations"));
+    }
+    if schema.has("sandwich_spans", "frontrun_victim_slots") {
+        columns.push("sum(sp.frontrun_victim_slots + sp.victim_backrun_slots > 0) as multi_slot_sandwiches".to_string());
+    }
+    if schema.has_table("block_volume") {
+        columns.push(format!("(select sum(v.sandwiched_volume_lamports) * 10000 / nullif(sum(v.swap_volume_lamports), 0) from block_volume v join leader_schedule l2 on l2.slot=v.slot where l2.leader_id=l.leader_id and v.slot >= {since}) as sandwiched_volume_bps"));
+    }
+    format!(
+        "select {} from sandwich_spans sp join leader_schedule l on l.slot=sp.slot join address_lookup_table a on a.id=l.leader_id where sp.slot >= {since} group by l.leader_id, a.address",
+        columns.join(", "),
+    )
+}
+
+/// Works out the view definitions for the given schema. Views are prefixed `grafana_` and only use columns the schema
+/// has, so rerunning after a migration picks up new columns without breaking dashboards that don't use them.
+pub fn plan_views(schema: &Schema) -> ViewPlan {
+    let views: [ViewDef; 3] = [
+        ("grafana_recent_sandwiches", &["sandwich_spans", "sandwiches", "transactions"], recent_sandwiches),
+        ("grafana_top_attackers", &["sandwich_attacker_rollup", "address_lookup_table", "transactions"], top_attackers),
+        ("grafana_validator_stats", &["sandwich_spans", "leader_schedule", "address_lookup_table", "transactions"], validator_stats),
+    ];
+    let mut plan = ViewPlan::default();
+    for (name, tables, build) in views {
+        match tables.iter().find(|t| !schema.has_table(t)) {
+            Some(missing) => plan.drop.push((name, missing)),
+            None => plan.create.push((name, build(schema))),
+        }
+    }
+    plan
+}
+
+/// Creates or replaces the views in `plan_views`, dropping the ones the schema can't support anymore
+pub fn generate_views(conn: &mut PooledConn) -> mysql::Result<ViewPlan> {
+    let plan = plan_views(&Schema::load(conn)?);
+    for (name, select) in plan.create.iter() {
+        conn.query_drop(format!("create or replace view {name} as {select}"))?;
+    }
+    for (name, _) in plan.drop.iter() {
+        conn.query_drop(format!("drop view if exists {name}"))?;
+    }
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_views() {
+        let base = [
+            ("transactions", "slot"),
+            ("sandwiches", "id"),
+            ("sandwich_spans", "slot"),
+            ("sandwich_attacker_rollup", "attacker_id"),
+            ("address_lookup_table", "address"),
+        ];
+        let plan = plan_views(&Schema::new(base));
+        assert_eq!(plan.create.iter().map(|(name, _)| *name).collect::<Vec<_>>(), vec!["grafana_recent_sandwiches", "grafana_top_attackers"]);
+        assert_eq!(plan.drop, vec![("grafana_validator_stats", "leader_schedule")]);
+        assert!(!plan.create[0].1.contains("position_bps") && !plan.create[0].1.contains("from_unixtime") && !plan.create[0].1.contains("price_impact_bps"));
+        assert!(!plan.create[1].1.contains("dont_front_victims"));
+        let plan = plan_views(&Schema::new(base.into_iter().chain([
+            ("transactions", "block_time"),
+            ("sandwiches", "position_bps"),
+            ("sandwiches", "price_impact_bps"),
+            ("sandwich_attacker_rollup", "dont_front_victims"),
+            ("leader_schedule", "leader_id"),
+            ("block_volume", "slot"),
+        ])));
+        assert_eq!(plan.create.len(), 3);
+        assert!(plan.drop.is_empty());
+        assert!(plan.create[0].1.contains("from_unixtime") && plan.create[0].1.contains("frontrun_position_bps") && plan.create[0].1.contains("worst_victim_price_impact_bps"));
+        assert!(plan.create[1].1.contains("dont_front_victims"));
+        assert!(plan.create[2].1.contains("sandwiched_volume_bps") && !plan.create[2].1.contains("dont_front_violations"));
+    }
+}
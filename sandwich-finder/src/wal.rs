@@ -0,0 +1,356 @@
+//! Write-ahead log for rows on their way to the database.
+//!
+//! Each entry is appended as a json line and fsynced before its db write starts, then acknowledged once the write went
+//! through. Entries still unacknowledged when the process stops are handed back by [`Wal::open`] to be written again,
+//! which relies on the inserts being idempotent.
+//! The log is split into segments named after their first sequence number, a segment is deleted once every entry in
+//! it is acknowledged. Acks of an entry in an older segment live in the newer one, so deleting the newer segment first
+//! can replay an entry twice but never lose one.
+
+use std::{collections::{BTreeMap, HashMap, HashSet}, env, fs::{self, File, OpenOptions}, io::{self, BufRead as _, BufReader, Write as _}, marker::PhantomData, path::{Path, PathBuf}, sync::{Arc, Mutex}};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{event_cache::{CachedPoolCreated, CachedSwap, CachedTransaction, CachedTransfer}, events::{common::Inserter, event::Event, sandwich::SandwichCandidate, swap::SwapV2, transaction::TransactionV2, transfer::TransferV2}, metrics};
+
+const SEGMENT_EXTENSION: &str = "wal";
+
+#[derive(Clone, Debug)]
+pub struct WalConfig {
+    pub dir: PathBuf,
+    /// A new segment is started once the current one grows past this
+    pub segment_bytes: u64,
+}
+
+impl WalConfig {
+    /// Enabled by setting `WAL_DIR`, each log gets a subdirectory `name` in it. `WAL_SEGMENT_BYTES` defaults to 64MiB.
+    pub fn from_env(name: &str) -> Option<Self> {
+        let dir = env::var("WAL_DIR").ok().filter(|d| !d.is_empty())?;
+        Some(Self {
+            dir: Path::new(&dir).join(name),
+            segment_bytes: env::var("WAL_SEGMENT_BYTES").ok().and_then(|v| v.parse().ok()).unwrap_or(64 << 20),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Line<T> {
+    Entry { seq: u64, entry: T },
+    Ack { seq: u64 },
+}
+
+struct Segment {
+    first_seq: u64,
+    file: File,
+    bytes: u64,
+}
+
+struct WalState {
+    config: WalConfig,
+    current: Segment,
+    next_seq: u64,
+    /// Unacknowledged seqs by the first seq of their segment
+    unacked: BTreeMap<u64, HashSet<u64>>,
+}
+
+/// An opened log and its unacknowledged entries as (seq, entry), oldest first
+pub type OpenedWal<T> = (Wal<T>, Vec<(u64, T)>);
+
+/// A log of `T`s, cheap to clone and shared between writers
+pub struct Wal<T> {
+    state: Arc<Mutex<WalState>>,
+    _entry: PhantomData<fn(T) -> T>,
+}
+
+impl<T> Clone for Wal<T> {
+    fn clone(&self) -> Self {
+        Self { state: self.state.clone(), _entry: PhantomData }
+    }
+}
+
+fn segment_path(dir: &Path, first_seq: u64) -> PathBuf {
+    dir.join(format!("{first_seq:020}.{SEGMENT_EXTENSION}"))
+}
+
+fn create_segment(dir: &Path, first_seq: u64) -> io::Result<Segment> {
+    let file = OpenOptions::new().create(true).append(true).open(segment_path(dir, first_seq))?;
+    // makes the new file's directory entry durable too
+    File::open(dir)?.sync_all()?;
+    Ok(Segment { first_seq, file, bytes: 0 })
+}
+
+impl<T: Serialize + DeserializeOwned> Wal<T> {
+    /// Opens the log in `config.dir`, returning it along with the unacknowledged entries as (seq, entry), oldest first.
+    /// Each of those should be written again and acknowledged like a new one.
+    pub fn open(config: WalConfig) -> io::Result<OpenedWal<T>> {
+        fs::create_dir_all(&config.dir)?;
+        let mut segments: Vec<u64> = fs::read_dir(&config.dir)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == SEGMENT_EXTENSION))
+            .filter_map(|path| path.file_stem()?.to_str()?.parse().ok())
+            .collect();
+        segments.sort_unstable();
+        let mut entries = BTreeMap::new();
+        let mut segment_of = HashMap::new();
+        let mut acked = HashSet::new();
+        let mut next_seq = 0;
+        for &first_seq in &segments {
+            for line in BufReader::new(File::open(segment_path(&config.dir, first_seq))?).lines() {
+                // a torn last line from a crash mid-append, its write never started
+                let Ok(line) = serde_json::from_str::<Line<T>>(&line?) else {
+                    continue;
+                };
+                match line {
+                    Line::Entry { seq, entry } => {
+                        next_seq = next_seq.max(seq + 1);
+                        segment_of.insert(seq, first_seq);
+                        entries.insert(seq, entry);
+                    },
+                    Line::Ack { seq } => {
+                        acked.insert(seq);
+                    },
+                }
+            }
+        }
+        entries.retain(|seq, _| !acked.contains(seq));
+        let mut unacked: BTreeMap<u64, HashSet<u64>> = BTreeMap::new();
+        for seq in entries.keys() {
+            unacked.entry(segment_of[seq]).or_default().insert(*seq);
+        }
+        for first_seq in segments.into_iter().filter(|s| !unacked.contains_key(s)) {
+            fs::remove_file(segment_path(&config.dir, first_seq))?;
+        }
+        let current = create_segment(&config.dir, next_seq)?;
+        let wal = Self {
+            state: Arc::new(Mutex::new(WalState { config, current, next_seq, unacked })),
+            _entry: PhantomData,
+        };
+        Ok((wal, entries.into_iter().collect()))
+    }
+
+    /// Durably appends `entry`, returning the seq to acknowledge it with
+    pub fn append(&self, entry: &T) -> io::Result<u64> {
+        let mut state = self.state.lock().unwrap();
+        if state.current.bytes >= state.config.segment_bytes {
+            let next_seq = state.next_seq;
+            let segment = create_segment(&state.config.dir, next_seq)?;
+            let done = std::mem::replace(&mut state.current, segment).first_seq;
+            if state.unacked.get(&done).is_none_or(HashSet::is_empty) {
+                state.unacked.remove(&done);
+                fs::remove_file(segment_path(&state.config.dir, done))?;
+            }
+        }
+        let seq = state.next_seq;
+        let mut line = serde_json::to_vec(&Line::Entry { seq, entry })?;
+        line.push(b'\n');
+        state.current.file.write_all(&line)?;
+        state.current.file.sync_data()?;
+        state.current.bytes += line.len() as u64;
+        state.next_seq += 1;
+        let first_seq = state.current.first_seq;
+        state.unacked.entry(first_seq).or_default().insert(seq);
+        metrics::set("wal_unacked", state.unacked.values().map(|s| s.len() as u64).sum());
+        Ok(seq)
+    }
+
+    /// Marks `seq` as written, deleting its segment if nothing in it is left.
+    /// Not synced, losing an ack only means the entry is written again.
+    pub fn ack(&self, seq: u64) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let mut line = serde_json::to_vec(&Line::<T>::Ack { seq })?;
+        line.push(b'\n');
+        state.current.file.write_all(&line)?;
+        state.current.bytes += line.len() as u64;
+        let current = state.current.first_seq;
+        let Some((&first_seq, seqs)) = state.unacked.range_mut(..=seq).next_back() else {
+            return Ok(());
+        };
+        seqs.remove(&seq);
+        if seqs.is_empty() && first_seq != current {
+            state.unacked.remove(&first_seq);
+            fs::remove_file(segment_path(&state.config.dir, first_seq))?;
+        }
+        metrics::set("wal_unacked", state.unacked.values().map(|s| s.len() as u64).sum());
+        Ok(())
+    }
+}
+
+/// Opens the log named `name` if `WAL_DIR` is set. Failing to open it is fatal, running without it would silently
+/// drop the durability it's configured for.
+pub fn open_from_env<T: Serialize + DeserializeOwned>(name: &str) -> Option<OpenedWal<T>> {
+    let config = WalConfig::from_env(name)?;
+    let (wal, pending) = Wal::open(config.clone()).unwrap_or_else(|e| panic!("Failed to open the write-ahead log in {:?}: {}", config.dir, e));
+    println!("write-ahead log in {:?}, {} entries to replay", config.dir, pending.len());
+    Some((wal, pending))
+}
+
+/// Appends `entry` if there's a log, None if there's none or appending failed
+pub fn append_logged<T: Serialize + DeserializeOwned>(wal: Option<&Wal<T>>, entry: impl FnOnce() -> T) -> Option<u64> {
+    match wal?.append(&entry()) {
+        Ok(seq) => Some(seq),
+        Err(e) => {
+            eprintln!("Failed to append to the write-ahead log: {}", e);
+            None
+        },
+    }
+}
+
+/// Acknowledges `seq` once its write succeeded, leaving it to be replayed otherwise
+pub fn ack_logged<T: Serialize + DeserializeOwned>(wal: Option<&Wal<T>>, seq: Option<u64>, written: bool) {
+    let (Some(wal), Some(seq), true) = (wal, seq, written) else {
+        return;
+    };
+    if let Err(e) = wal.ack(seq) {
+        eprintln!("Failed to acknowledge write-ahead log entry {}: {}", seq, e);
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum WalEvent {
+    Swap(CachedSwap),
+    Transfer(CachedTransfer),
+    Transaction(CachedTransaction),
+    PoolCreated(CachedPoolCreated),
+}
+
+impl From<&Event> for WalEvent {
+    fn from(event: &Event) -> Self {
+        match event {
+            Event::Swap(swap) => Self::Swap(swap.into()),
+            Event::Transfer(transfer) => Self::Transfer(transfer.into()),
+            Event::Transaction(tx) => Self::Transaction(tx.into()),
+            Event::PoolCreated(pool) => Self::PoolCreated(pool.into()),
+        }
+    }
+}
+
+impl From<WalEvent> for Event {
+    fn from(event: WalEvent) -> Self {
+        match event {
+            WalEvent::Swap(swap) => Self::Swap(swap.into()),
+            WalEvent::Transfer(transfer) => Self::Transfer(transfer.into()),
+            WalEvent::Transaction(tx) => Self::Transaction(tx.into()),
+            WalEvent::PoolCreated(pool) => Self::PoolCreated(pool.into()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct WalSandwich {
+    frontrun: Vec<CachedSwap>,
+    victim: Vec<CachedSwap>,
+    backrun: Vec<CachedSwap>,
+    suspected_wash: Vec<CachedSwap>,
+    transfers: Vec<CachedTransfer>,
+    /// Absent from sandwiches logged before conversions could link them
+    #[serde(default)]
+    conversions: Vec<CachedSwap>,
+    txs: Vec<CachedTransaction>,
+}
+
+impl From<&SandwichCandidate> for WalSandwich {
+    fn from(s: &SandwichCandidate) -> Self {
+        Self {
+            frontrun: s.frontrun().iter().map(CachedSwap::from).collect(),
+            victim: s.victim().iter().map(CachedSwap::from).collect(),
+            backrun: s.backrun().iter().map(CachedSwap::from).collect(),
+            suspected_wash: s.suspected_wash().iter().map(CachedSwap::from).collect(),
+            transfers: s.transfers().iter().map(CachedTransfer::from).collect(),
+            conversions: s.conversions().iter().map(CachedSwap::from).collect(),
+            txs: s.txs().iter().map(CachedTransaction::from).collect(),
+        }
+    }
+}
+
+impl From<WalSandwich> for SandwichCandidate {
+    fn from(s: WalSandwich) -> Self {
+        let swaps = |swaps: Vec<CachedSwap>| swaps.into_iter().map(SwapV2::from).collect();
+        SandwichCandidate::from_parts(
+            swaps(s.frontrun),
+            swaps(s.victim),
+            swaps(s.backrun),
+            s.transfers.into_iter().map(TransferV2::from).collect(),
+            s.txs.into_iter().map(TransactionV2::from).collect(),
+        ).with_suspected_wash(swaps(s.suspected_wash)).with_conversions(swaps(s.conversions))
+    }
+}
+
+/// A slot's events as logged by the indexer
+pub type EventBatch = Vec<WalEvent>;
+/// A group's sandwiches as logged by the realtime detector, along with the group's first slot
+pub type SandwichBatch = (u64, Vec<WalSandwich>);
+
+/// Logs the events, inserts them `chunk_size` at a time, and acknowledges them if every chunk went in.
+/// False if any chunk failed.
+pub async fn insert_events_logged(inserter: &mut Inserter, wal: Option<&Wal<EventBatch>>, events: &[Event], chunk_size: usize) -> bool {
+    let seq = append_logged(wal, || events.iter().map(WalEvent::from).collect());
+    let mut written = true;
+    for chunk in events.chunks(chunk_size) {
+        written &= inserter.insert_events(chunk).await;
+    }
+    ack_logged(wal, seq, written);
+    written
+}
+
+/// Logs the sandwiches, inserts them, and acknowledges them once they're in. False if they couldn't be written.
+pub async fn insert_sandwiches_logged(inserter: &mut Inserter, wal: Option<&Wal<SandwichBatch>>, start_slot: u64, sandwiches: Arc<[SandwichCandidate]>) -> bool {
+    let seq = append_logged(wal, || (start_slot, sandwiches.iter().map(WalSandwich::from).collect()));
+    let written = inserter.insert_sandwiches(start_slot, sandwiches).await;
+    ack_logged(wal, seq, written);
+    written
+}
+
+/// Writes the entries [`Wal::open`] handed back, in order
+pub async fn replay_events(inserter: &mut Inserter, wal: &Wal<EventBatch>, pending: Vec<(u64, EventBatch)>, chunk_size: usize) {
+    for (seq, batch) in pending {
+        let events: Vec<Event> = batch.into_iter().map(Event::from).collect();
+        let mut written = true;
+        for chunk in events.chunks(chunk_size) {
+            written &= inserter.insert_events(chunk).await;
+        }
+        ack_logged(Some(wal), Some(seq), written);
+    }
+}
+
+/// Writes the entries [`Wal::open`] handed back, in order
+pub async fn replay_sandwiches(inserter: &mut Inserter, wal: &Wal<SandwichBatch>, pending: Vec<(u64, SandwichBatch)>) {
+    for (seq, (start_slot, sandwiches)) in pending {
+        let sandwiches: Arc<[SandwichCandidate]> = sandwiches.into_iter().map(SandwichCandidate::from).collect();
+        let written = inserter.insert_sandwiches(start_slot, sandwiches).await;
+        ack_logged(Some(wal), Some(seq), written);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_and_cleanup() {
+        let dir = env::temp_dir().join(format!("wal-test-{}", std::process::id()));
+        let config = WalConfig { dir: dir.clone(), segment_bytes: 1 };
+        let (wal, pending) = Wal::<String>::open(config.clone()).unwrap();
+        assert!(pending.is_empty());
+        // every append starts a new segment
+        let seqs: Vec<_> = ["a", "b", "c"].iter().map(|e| wal.append(&e.to_string()).unwrap()).collect();
+        assert_eq!(seqs, vec![0, 1, 2]);
+        wal.ack(0).unwrap();
+        wal.ack(2).unwrap();
+        // the first segment is gone, the last one is still being written to
+        assert!(!segment_path(&dir, 0).exists() && segment_path(&dir, 2).exists());
+        // a torn write
+        OpenOptions::new().append(true).open(segment_path(&dir, 2)).unwrap().write_all(b"{\"type\":\"entry\",\"seq\":3,").unwrap();
+        drop(wal);
+        let (wal, pending) = Wal::<String>::open(config.clone()).unwrap();
+        assert_eq!(pending, vec![(1, "b".to_string())]);
+        assert_eq!(wal.append(&"d".to_string()).unwrap(), 3);
+        wal.ack(1).unwrap();
+        wal.ack(3).unwrap();
+        drop(wal);
+        let (_, pending) = Wal::<String>::open(config).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(pending.is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+// Recent sandwiches from /history, kept up to date from the websocket feed at /, both in the v2 format
+const MAX_ROWS = 200;
+const LAMPORTS_PER_SOL = 1e9;
+
+const rows = document.getElementById("sandwiches");
+const status = document.getElementById("status");
+const result = document.getElementById("result");
+
+function short(address) {
+  return address && address.length > 12 ? `${address.slice(0, 4)}…${address.slice(-4)}` : address || "";
+}
+
+function link(kind, value, text) {
+  const a = document.createElement("a");
+  a.href = `https://solscan.io/${kind}/${value}`;
+  a.target = "_blank";
+  a.rel = "noopener";
+  a.textContent = text || short(value);
+  return a;
+}
+
+function cell(content, mono) {
+  const td = document.createElement("td");
+  if (mono) {
+    td.className = "mono";
+  }
+  if (content instanceof Node) {
+    td.appendChild(content);
+  } else {
+    td.textContent = content;
+  }
+  return td;
+}
+
+function loss(sandwich) {
+  const lamports = sandwich.estVictimLossLamports;
+  return lamports ? `${(lamports / LAMPORTS_PER_SOL).toFixed(4)} SOL` : "";
+}
+
+// the sig of the tx holding a swap
+function swapSig(sandwich, swap) {
+  const tx = swap && sandwich.txs.find(tx => tx.slot === swap.timestamp.slot && tx.inclusionOrder === swap.timestamp.inclusion_order);
+  return tx && tx.sig;
+}
+
+function row(sandwich) {
+  const tr = document.createElement("tr");
+  const frontrun = sandwich.frontrun[0];
+  const sig = swapSig(sandwich, frontrun);
+  tr.appendChild(cell(link("block", sandwich.slot, String(sandwich.slot))));
+  tr.appendChild(cell(frontrun ? link("account", frontrun.amm) : "", true));
+  tr.appendChild(cell(frontrun ? link("account", frontrun.authority) : "", true));
+  tr.appendChild(cell(String(sandwich.victim.length)));
+  tr.appendChild(cell(loss(sandwich)));
+  tr.appendChild(cell(sig ? link("tx", sig) : "", true));
+  return tr;
+}
+
+function prepend(sandwich, highlight) {
+  const tr = row(sandwich);
+  if (highlight) {
+    tr.className = "new";
+    setTimeout(() => tr.className = "", 3000);
+  }
+  rows.insertBefore(tr, rows.firstChild);
+  while (rows.children.length > MAX_ROWS) {
+    rows.removeChild(rows.lastChild);
+  }
+}
+
+async function loadHistory() {
+  const res = await fetch(`/history?format=v2&limit=${MAX_ROWS}`);
+  if (!res.ok) {
+    return;
+  }
+  // oldest first
+  for (const sandwich of await res.json()) {
+    prepend(sandwich, false);
+  }
+}
+
+function connect() {
+  const scheme = location.protocol === "https:" ? "wss" : "ws";
+  const socket = new WebSocket(`${scheme}://${location.host}/?format=v2`);
+  socket.onopen = () => {
+    status.textContent = "live";
+    status.className = "status live";
+  };
+  socket.onmessage = msg => prepend(JSON.parse(msg.data), true);
+  socket.onclose = () => {
+    status.textContent = "reconnecting";
+    status.className = "status";
+    setTimeout(connect, 5000);
+  };
+}
+
+document.getElementById("search").addEventListener("submit", async e => {
+  e.preventDefault();
+  const sig = document.getElementById("search-sig").value.trim();
+  if (!sig) {
+    return;
+  }
+  result.hidden = false;
+  result.textContent = "Searching…";
+  const res = await fetch(`/search/${encodeURIComponent(sig)}?format=v2`);
+  const sandwich = res.ok ? await res.json() : null;
+  result.textContent = "";
+  if (!sandwich) {
+    result.textContent = res.status === 404 ? "Search needs a db." : "No sandwich found around this transaction.";
+    return;
+  }
+  const role = ["frontrun", "victim", "backrun"].find(role => sandwich[role].some(swap => swapSig(sandwich, swap) === sig));
+  result.append(`Part of a sandwich${role ? ` as the ${role}` : ""} in slot `, link("block", sandwich.slot, String(sandwich.slot)));
+  const lamports = loss(sandwich);
+  if (lamports) {
+    result.append(`, victims lost an estimated ${lamports}`);
+  }
+  if (sandwich.frontrun[0]) {
+    result.append(" by ", link("account", sandwich.frontrun[0].authority));
+  }
+});
+
+loadHistory().finally(connect);